@@ -0,0 +1,35 @@
+use std::sync::atomic::AtomicBool;
+
+pub use gix::daemon::Options;
+
+/// Listen on `address` and serve all clients connecting to it according to `options`, until `should_interrupt` is set.
+pub fn daemon(
+    address: impl std::net::ToSocketAddrs,
+    options: Options,
+    mut err: impl std::io::Write,
+    should_interrupt: &AtomicBool,
+) -> anyhow::Result<()> {
+    let listener = std::net::TcpListener::bind(address)?;
+    writeln!(err, "Listening on git://{}", listener.local_addr()?)?;
+    gix::daemon::serve(&listener, &options, should_interrupt, &|peer, err| {
+        // Connections are served on their own threads, so we can't use `err` here.
+        eprintln!("{peer}: {:#}", anyhow::Error::from(err));
+    })?;
+    Ok(())
+}
+
+/// Listen on `address` and serve all clients connecting to it via the smart HTTP protocol according to `options`,
+/// until `should_interrupt` is set.
+pub fn http_backend(
+    address: impl std::net::ToSocketAddrs,
+    options: gix::http_backend::Options,
+    mut err: impl std::io::Write,
+    should_interrupt: &AtomicBool,
+) -> anyhow::Result<()> {
+    let listener = std::net::TcpListener::bind(address)?;
+    writeln!(err, "Listening on http://{}", listener.local_addr()?)?;
+    gix::http_backend::serve(&listener, &options, should_interrupt, &|peer, err| {
+        eprintln!("{peer}: {:#}", anyhow::Error::from(err));
+    })?;
+    Ok(())
+}
@@ -189,7 +189,7 @@ where
             };
             buf.clear();
             // …but write nothing
-            Ok(Some(gix::objs::Data { kind, data: buf }))
+            Ok(Some(gix::objs::Data::new(kind, buf, id.kind())))
         } else {
             self.db.try_find(id, buf)
         }
@@ -200,11 +200,8 @@ where
 struct Empty;
 
 impl gix::objs::Find for Empty {
-    fn try_find<'a>(&self, _id: &gix::oid, buffer: &'a mut Vec<u8>) -> Result<Option<gix::objs::Data<'a>>, Error> {
+    fn try_find<'a>(&self, id: &gix::oid, buffer: &'a mut Vec<u8>) -> Result<Option<gix::objs::Data<'a>>, Error> {
         buffer.clear();
-        Ok(Some(gix::objs::Data {
-            kind: gix::object::Kind::Blob,
-            data: buffer,
-        }))
+        Ok(Some(gix::objs::Data::new(gix::object::Kind::Blob, buffer, id.kind())))
     }
 }
@@ -66,6 +66,8 @@ impl FromStr for OutputFormat {
 pub mod commitgraph;
 #[cfg(feature = "corpus")]
 pub mod corpus;
+#[cfg(feature = "blocking-server")]
+pub mod daemon;
 pub mod net;
 
 #[cfg(feature = "estimate-hours")]
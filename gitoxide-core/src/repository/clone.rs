@@ -6,6 +6,7 @@ pub struct Options {
     pub handshake_info: bool,
     pub no_tags: bool,
     pub shallow: gix::remote::fetch::Shallow,
+    pub filter: Option<gix::remote::fetch::Filter>,
     pub ref_name: Option<gix::refs::PartialName>,
 }
 
@@ -34,6 +35,7 @@ pub(crate) mod function {
             no_tags,
             ref_name,
             shallow,
+            filter,
         }: Options,
     ) -> anyhow::Result<()>
     where
@@ -75,6 +77,9 @@ pub(crate) mod function {
         if no_tags {
             prepare = prepare.configure_remote(|r| Ok(r.with_fetch_tags(gix::remote::fetch::Tags::None)));
         }
+        if let Some(filter) = filter {
+            prepare = prepare.with_filter(filter);
+        }
         let (mut checkout, fetch_outcome) = prepare
             .with_shallow(shallow)
             .with_ref_name(ref_name.as_ref())?
@@ -114,6 +119,21 @@ pub(crate) mod function {
                     &mut err,
                 )?;
             }
+            Status::DumbTransfer { update_refs, .. } => {
+                let remote = repo
+                    .find_default_remote(gix::remote::Direction::Fetch)
+                    .expect("one origin remote")?;
+                let ref_specs = remote.refspecs(gix::remote::Direction::Fetch);
+                print_updates(
+                    &repo,
+                    &Default::default(),
+                    update_refs,
+                    ref_specs,
+                    fetch_outcome.ref_map,
+                    &mut out,
+                    &mut err,
+                )?;
+            }
         };
 
         if let Some(gix::worktree::state::checkout::Outcome { collisions, errors, .. }) = outcome {
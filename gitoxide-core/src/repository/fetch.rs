@@ -103,14 +103,17 @@ pub(crate) mod function {
             Status::Change {
                 update_refs,
                 write_pack_bundle,
+                packfile_uri_bundles,
                 negotiate,
             } => {
                 print_updates(&repo, &negotiate, update_refs, ref_specs, res.ref_map, &mut out, err)?;
-                if let Some(data_path) = write_pack_bundle.data_path {
-                    writeln!(out, "pack  file: \"{}\"", data_path.display()).ok();
-                }
-                if let Some(index_path) = write_pack_bundle.index_path {
-                    writeln!(out, "index file: \"{}\"", index_path.display()).ok();
+                for bundle in std::iter::once(write_pack_bundle).chain(packfile_uri_bundles) {
+                    if let Some(data_path) = bundle.data_path {
+                        writeln!(out, "pack  file: \"{}\"", data_path.display()).ok();
+                    }
+                    if let Some(index_path) = bundle.index_path {
+                        writeln!(out, "index file: \"{}\"", index_path.display()).ok();
+                    }
                 }
                 if negotiation_info {
                     print_negotiate_info(&mut out, Some(&negotiate))?;
@@ -120,6 +123,31 @@ pub(crate) mod function {
                 }
                 Ok(())
             }
+            Status::DumbTransfer {
+                update_refs,
+                packs,
+                num_loose_objects,
+            } => {
+                print_updates(
+                    &repo,
+                    &Default::default(),
+                    update_refs,
+                    ref_specs,
+                    res.ref_map,
+                    &mut out,
+                    err,
+                )?;
+                for bundle in packs {
+                    if let Some(data_path) = bundle.data_path {
+                        writeln!(out, "pack  file: \"{}\"", data_path.display()).ok();
+                    }
+                    if let Some(index_path) = bundle.index_path {
+                        writeln!(out, "index file: \"{}\"", index_path.display()).ok();
+                    }
+                }
+                writeln!(out, "loose objects: {num_loose_objects}").ok();
+                Ok(())
+            }
         }?;
         if dry_run {
             writeln!(out, "DRY-RUN: No ref was updated and no pack was received.").ok();
@@ -47,10 +47,18 @@ pub mod mailmap;
 mod merge_base;
 pub use merge_base::merge_base;
 pub mod odb;
+#[cfg(feature = "blocking-server")]
+pub mod receive_pack;
+#[cfg(feature = "blocking-server")]
+pub use receive_pack::function::receive_pack;
 pub mod remote;
 pub mod revision;
 pub mod status;
 pub mod submodule;
 pub mod tree;
+#[cfg(feature = "blocking-server")]
+pub mod upload_pack;
+#[cfg(feature = "blocking-server")]
+pub use upload_pack::function::upload_pack;
 pub mod verify;
 pub mod worktree;
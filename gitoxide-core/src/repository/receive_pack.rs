@@ -0,0 +1,32 @@
+use gix::protocol::transport::Protocol;
+
+pub struct Options {
+    /// The protocol version requested by the client.
+    pub protocol: Protocol,
+    pub stateless_rpc: bool,
+    pub advertise_refs: bool,
+}
+
+pub(crate) mod function {
+    use std::sync::atomic::AtomicBool;
+
+    use super::Options;
+
+    pub fn receive_pack(
+        repo: gix::Repository,
+        mut input: impl std::io::Read,
+        mut out: impl std::io::Write,
+        should_interrupt: &AtomicBool,
+        Options {
+            protocol,
+            stateless_rpc,
+            advertise_refs,
+        }: Options,
+    ) -> anyhow::Result<()> {
+        let mut options = repo.receive_pack_options()?;
+        options.stateless_rpc = stateless_rpc;
+        options.advertise_refs = advertise_refs;
+        repo.receive_pack(protocol, &mut input, &mut out, should_interrupt, &options)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use gix::protocol::transport::Protocol;
+
+pub struct Options {
+    /// The protocol version requested by the client.
+    pub protocol: Protocol,
+    pub stateless_rpc: bool,
+    pub advertise_refs: bool,
+}
+
+/// Parse the protocol version out of the value of the `GIT_PROTOCOL` environment variable, like `version=2:key=value`,
+/// defaulting to `V0` if it isn't set or invalid, just like `git` does.
+pub fn protocol_from_env(value: Option<&std::ffi::OsStr>) -> Protocol {
+    value
+        .and_then(|value| value.to_str())
+        .and_then(|value| {
+            value
+                .split(':')
+                .find_map(|param| match param.strip_prefix("version=")? {
+                    "1" => Some(Protocol::V1),
+                    "2" => Some(Protocol::V2),
+                    _ => None,
+                })
+        })
+        .unwrap_or(Protocol::V0)
+}
+
+pub(crate) mod function {
+    use std::sync::atomic::AtomicBool;
+
+    use super::Options;
+
+    pub fn upload_pack(
+        repo: gix::Repository,
+        mut input: impl std::io::Read,
+        mut out: impl std::io::Write,
+        should_interrupt: &AtomicBool,
+        Options {
+            protocol,
+            stateless_rpc,
+            advertise_refs,
+        }: Options,
+    ) -> anyhow::Result<()> {
+        let mut options = repo.upload_pack_options()?;
+        options.stateless_rpc = stateless_rpc;
+        options.advertise_refs = advertise_refs;
+        repo.upload_pack(protocol, &mut input, &mut out, should_interrupt, &options)?;
+        Ok(())
+    }
+}
@@ -50,7 +50,7 @@ pub fn decode(data: &[u8]) -> Result<(Vec, &[u8]), decode::Error> {
 }
 
 mod access {
-    use super::Vec;
+    use super::{decode, Vec};
 
     impl Vec {
         /// Call `f(index)` for each bit that is true, given the index of the bit that identifies it uniquely within the bit array.
@@ -86,10 +86,38 @@ mod access {
             Some(())
         }
 
+        /// The amount of compressed words we are holding, which determines the size of the on-disk representation.
+        pub fn num_words(&self) -> usize {
+            self.bits.len()
+        }
+
         /// The amount of bits we are currently holding.
         pub fn num_bits(&self) -> usize {
             self.num_bits.try_into().expect("we are not on 16 bit systems")
         }
+
+        /// Decompress all words into an uncompressed bitmap, which is suitable for fast lookups and set operations.
+        ///
+        /// Fail if the compressed words expand to more words than needed to hold [`num_bits()`][Vec::num_bits()].
+        pub fn to_plain(&self) -> Result<crate::plain::Bitmap, decode::Error> {
+            let max_words = (self.num_bits() + 63) / 64;
+            let mut out = std::vec::Vec::with_capacity(max_words);
+            let mut iter = self.bits.iter();
+            while let Some(word) = iter.next() {
+                let fill = if rlw_runbit_is_set(word) { u64::MAX } else { 0 };
+                let running_len = usize::try_from(rlw_running_len(word)).unwrap_or(usize::MAX);
+                // Literal counts that point past the end of the data are silently truncated.
+                let literal_words = usize::try_from(rlw_literal_words(word)).map_or(iter.len(), |n| n.min(iter.len()));
+                if out.len().saturating_add(running_len).saturating_add(literal_words) > max_words {
+                    return Err(decode::Error::Corrupt {
+                        message: "compressed words expand past the amount of bits",
+                    });
+                }
+                out.extend(std::iter::repeat(fill).take(running_len));
+                out.extend(iter.by_ref().take(literal_words));
+            }
+            Ok(crate::plain::Bitmap::from_words(out))
+        }
     }
 
     #[inline]
@@ -112,12 +140,86 @@ mod access {
         w & 1 == 1
     }
 
-    const RLW_RUNNING_BITS: u64 = 4 * 8;
-    const RLW_LARGEST_RUNNING_COUNT: u64 = (1 << RLW_RUNNING_BITS) - 1;
+    use super::{RLW_LARGEST_RUNNING_COUNT, RLW_RUNNING_BITS};
+}
+
+mod encode {
+    use super::{Vec, RLW_LARGEST_LITERAL_COUNT, RLW_LARGEST_RUNNING_COUNT, RLW_RUNNING_BITS};
+
+    impl Vec {
+        /// Compress all bits of `bitmap`, which can then be [written][Vec::write_to()] in its on-disk representation.
+        ///
+        /// Trailing words without any set bit are not stored.
+        pub fn from_plain(bitmap: &crate::plain::Bitmap) -> Self {
+            let words = bitmap.as_words();
+            let words = &words[..words.iter().rposition(|w| *w != 0).map_or(0, |pos| pos + 1)];
+
+            let mut bits = std::vec::Vec::with_capacity(words.len() + 1);
+            let mut rlw;
+            let mut idx = 0;
+            loop {
+                rlw = bits.len();
+                bits.push(0);
+
+                let mut running_len = 0;
+                let run_word = words.get(idx).copied().filter(|w| *w == 0 || *w == u64::MAX);
+                if let Some(run_word) = run_word {
+                    while running_len < RLW_LARGEST_RUNNING_COUNT && words.get(idx) == Some(&run_word) {
+                        running_len += 1;
+                        idx += 1;
+                    }
+                }
+
+                let literals_start = idx;
+                while (idx - literals_start) < RLW_LARGEST_LITERAL_COUNT as usize
+                    && words.get(idx).map_or(false, |w| *w != 0 && *w != u64::MAX)
+                {
+                    idx += 1;
+                }
+                let num_literals = (idx - literals_start) as u64;
+
+                bits[rlw] = u64::from(run_word == Some(u64::MAX))
+                    | (running_len << 1)
+                    | (num_literals << (1 + RLW_RUNNING_BITS));
+                bits.extend_from_slice(&words[literals_start..idx]);
+                if idx == words.len() {
+                    break;
+                }
+            }
+
+            Vec {
+                num_bits: u32::try_from(words.len() * 64).unwrap_or(u32::MAX),
+                bits,
+                rlw: rlw as u64,
+            }
+        }
+
+        /// Write this instance in its on-disk representation to `out`, the inverse of [`decode()`][super::decode()].
+        pub fn write_to(&self, out: &mut dyn std::io::Write) -> std::io::Result<()> {
+            out.write_all(&self.num_bits.to_be_bytes())?;
+            out.write_all(
+                &u32::try_from(self.bits.len())
+                    .expect("BUG: number of words fit into u32 as number of bits does")
+                    .to_be_bytes(),
+            )?;
+            for word in &self.bits {
+                out.write_all(&word.to_be_bytes())?;
+            }
+            out.write_all(
+                &u32::try_from(self.rlw)
+                    .expect("BUG: the RLW is an index into our words")
+                    .to_be_bytes(),
+            )
+        }
+    }
 }
 
+const RLW_RUNNING_BITS: u64 = 4 * 8;
+const RLW_LITERAL_BITS: u64 = 64 - 1 - RLW_RUNNING_BITS;
+const RLW_LARGEST_RUNNING_COUNT: u64 = (1 << RLW_RUNNING_BITS) - 1;
+const RLW_LARGEST_LITERAL_COUNT: u64 = (1 << RLW_LITERAL_BITS) - 1;
+
 /// A growable collection of u64 that are seen as stream of individual bits.
-#[allow(dead_code)]
 #[derive(Clone)]
 pub struct Vec {
     num_bits: u32,
@@ -125,3 +227,84 @@ pub struct Vec {
     /// RLW is an offset into the `bits` buffer, so `1` translates into &bits\[1] essentially.
     rlw: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::plain::Bitmap;
+
+    fn roundtrip(bitmap: &Bitmap) -> super::Vec {
+        let mut buf = Vec::new();
+        super::Vec::from_plain(bitmap).write_to(&mut buf).unwrap();
+        let (decoded, rest) = super::decode(&buf).unwrap();
+        assert!(rest.is_empty(), "all bytes are consumed");
+        assert_eq!(&decoded.to_plain().unwrap(), bitmap);
+
+        let mut ones = Vec::new();
+        decoded.for_each_set_bit(|idx| {
+            ones.push(idx);
+            Some(())
+        });
+        assert_eq!(ones, bitmap.iter_ones().collect::<Vec<_>>());
+        decoded
+    }
+
+    #[test]
+    fn encode_empty() {
+        let decoded = roundtrip(&Bitmap::default());
+        assert_eq!(decoded.num_bits(), 0);
+        assert_eq!(decoded.bits, [0], "a single marker word without runs or literals");
+    }
+
+    #[test]
+    fn encode_runs_and_literals() {
+        let words = vec![
+            0,
+            0,
+            0,
+            0b101,
+            u64::MAX,
+            u64::MAX,
+            0x8000_0000_0000_0001,
+            7,
+            0,
+            0,
+            1 << 63,
+            0,
+            0,
+        ];
+        let decoded = roundtrip(&Bitmap::from_words(words));
+        assert_eq!(decoded.num_bits(), 11 * 64, "trailing empty words are not stored");
+        assert_eq!(decoded.bits.len(), 3 + 4, "3 markers and 4 literal words");
+    }
+
+    #[test]
+    fn to_plain_rejects_runs_past_the_amount_of_bits() {
+        let run_of_ones = 1 | (3 << 1);
+        let bitmap = super::Vec {
+            num_bits: 2 * 64,
+            bits: vec![run_of_ones],
+            rlw: 0,
+        };
+        assert_eq!(
+            bitmap.to_plain().unwrap_err().to_string(),
+            "compressed words expand past the amount of bits"
+        );
+
+        let bitmap = super::Vec {
+            num_bits: 3 * 64 - 1,
+            ..bitmap
+        };
+        assert_eq!(bitmap.to_plain().unwrap().as_words(), [u64::MAX; 3]);
+    }
+
+    #[test]
+    fn to_plain_rejects_literals_past_the_amount_of_bits() {
+        let two_literals = 2 << (1 + super::RLW_RUNNING_BITS);
+        let bitmap = super::Vec {
+            num_bits: 64,
+            bits: vec![two_literals, 1, 2],
+            rlw: 0,
+        };
+        assert!(bitmap.to_plain().is_err());
+    }
+}
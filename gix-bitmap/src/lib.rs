@@ -6,6 +6,9 @@
 /// Bitmap utilities for the advanced word-aligned hybrid bitmap
 pub mod ewah;
 
+/// An uncompressed bitmap, suitable for fast set operations after decompressing EWAH bitmaps.
+pub mod plain;
+
 pub(crate) mod decode {
     #[inline]
     pub(crate) fn split_at_pos(data: &[u8], pos: usize) -> Option<(&[u8], &[u8])> {
@@ -0,0 +1,194 @@
+/// A growable bitmap that stores each bit without compression, with the least significant bit of each word
+/// being the first bit of that word.
+///
+/// Bits that were never set are `false`, and reading beyond the end is allowed and yields `false` as well.
+/// Thus, bitmaps compare equal if the same bits are set, independently of how many words they have allocated.
+#[derive(Default, Clone)]
+pub struct Bitmap {
+    words: Vec<u64>,
+}
+
+impl PartialEq for Bitmap {
+    fn eq(&self, other: &Self) -> bool {
+        self.trimmed_words() == other.trimmed_words()
+    }
+}
+
+impl Eq for Bitmap {}
+
+impl std::hash::Hash for Bitmap {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.trimmed_words().hash(state);
+    }
+}
+
+impl std::fmt::Debug for Bitmap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Bitmap")
+            .field("num_words", &self.words.len())
+            .field("count_ones", &self.count_ones())
+            .finish()
+    }
+}
+
+/// Construction
+impl Bitmap {
+    /// Create a bitmap from the given `words`, with the first bit being the least significant bit of the first word.
+    pub fn from_words(words: Vec<u64>) -> Self {
+        Bitmap { words }
+    }
+
+    /// Create an empty bitmap able to hold `num_bits` without re-allocating.
+    pub fn with_capacity(num_bits: usize) -> Self {
+        Bitmap {
+            words: Vec::with_capacity(words_for(num_bits)),
+        }
+    }
+}
+
+/// Access
+impl Bitmap {
+    /// Return `true` if the bit at `index` is set.
+    pub fn get(&self, index: usize) -> bool {
+        self.words
+            .get(index / 64)
+            .map_or(false, |word| word & (1 << (index % 64)) != 0)
+    }
+
+    /// Set the bit at `index` to `true`, growing the bitmap if needed, and return `true` if it was not set before.
+    pub fn set(&mut self, index: usize) -> bool {
+        let word_index = index / 64;
+        if word_index >= self.words.len() {
+            self.words.resize(word_index + 1, 0);
+        }
+        let mask = 1 << (index % 64);
+        let word = &mut self.words[word_index];
+        let was_unset = *word & mask == 0;
+        *word |= mask;
+        was_unset
+    }
+
+    /// Return the amount of bits that are set.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Return `true` if no bit is set.
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|w| *w == 0)
+    }
+
+    /// Return the words backing this bitmap, possibly with trailing words that have no bit set.
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Return an iterator over the indices of all set bits, in ascending order.
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(word_index, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                (word != 0).then(|| {
+                    let bit = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    word_index * 64 + bit
+                })
+            })
+        })
+    }
+}
+
+/// Set operations
+impl Bitmap {
+    /// Set all bits that are set in `other` as well.
+    pub fn union_with(&mut self, other: &Bitmap) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (lhs, rhs) in self.words.iter_mut().zip(other.words.iter()) {
+            *lhs |= rhs;
+        }
+    }
+
+    /// Unset all bits that are set in `other`.
+    pub fn difference_with(&mut self, other: &Bitmap) {
+        for (lhs, rhs) in self.words.iter_mut().zip(other.words.iter()) {
+            *lhs &= !rhs;
+        }
+    }
+
+    /// Only keep bits that are also set in `other`.
+    pub fn intersect_with(&mut self, other: &Bitmap) {
+        for (lhs, rhs) in self
+            .words
+            .iter_mut()
+            .zip(other.words.iter().chain(std::iter::repeat(&0)))
+        {
+            *lhs &= rhs;
+        }
+    }
+
+    /// Toggle all bits that are set in `other`.
+    pub fn xor_with(&mut self, other: &Bitmap) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (lhs, rhs) in self.words.iter_mut().zip(other.words.iter()) {
+            *lhs ^= rhs;
+        }
+    }
+}
+
+impl Bitmap {
+    fn trimmed_words(&self) -> &[u64] {
+        let len = self.words.iter().rposition(|w| *w != 0).map_or(0, |pos| pos + 1);
+        &self.words[..len]
+    }
+}
+
+fn words_for(num_bits: usize) -> usize {
+    (num_bits + 63) / 64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Bitmap;
+
+    #[test]
+    fn set_get_and_iterate() {
+        let mut bitmap = Bitmap::default();
+        assert!(bitmap.is_empty());
+        assert!(bitmap.set(3));
+        assert!(!bitmap.set(3), "the bit was already set");
+        assert!(bitmap.set(64));
+        assert!(bitmap.set(200));
+        assert!(bitmap.get(3) && bitmap.get(64) && bitmap.get(200));
+        assert!(
+            !bitmap.get(4) && !bitmap.get(10_000),
+            "reading out of bounds is allowed"
+        );
+        assert_eq!(bitmap.count_ones(), 3);
+        assert_eq!(bitmap.iter_ones().collect::<Vec<_>>(), [3, 64, 200]);
+    }
+
+    #[test]
+    fn set_operations() {
+        let mut a = Bitmap::from_words(vec![0b1100]);
+        let b = Bitmap::from_words(vec![0b1010, 1]);
+
+        let mut union = a.clone();
+        union.union_with(&b);
+        assert_eq!(union.iter_ones().collect::<Vec<_>>(), [1, 2, 3, 64]);
+
+        let mut xor = a.clone();
+        xor.xor_with(&b);
+        assert_eq!(xor.iter_ones().collect::<Vec<_>>(), [1, 2, 64]);
+
+        let mut intersection = union.clone();
+        intersection.intersect_with(&a);
+        assert_eq!(intersection, a);
+
+        a.difference_with(&b);
+        assert_eq!(a.iter_ones().collect::<Vec<_>>(), [2]);
+    }
+}
@@ -0,0 +1,187 @@
+use std::io::{BufRead, Write};
+
+use bstr::ByteSlice;
+use gix_hash::ObjectId;
+
+use crate::{Header, Prerequisite, Ref, Version};
+
+/// The first line of a [V2][Version::V2] bundle.
+pub const SIGNATURE_V2: &[u8] = b"# v2 git bundle\n";
+/// The first line of a [V3][Version::V3] bundle.
+pub const SIGNATURE_V3: &[u8] = b"# v3 git bundle\n";
+
+///
+pub mod decode {
+    use bstr::BString;
+
+    /// The error returned by [`Header::from_read()`][crate::Header::from_read()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not read bundle header")]
+        Io(#[from] std::io::Error),
+        #[error("Not a bundle, or a bundle of unsupported version, as it starts with {signature:?}")]
+        Signature { signature: BString },
+        #[error("The header ended unexpectedly before the empty line that separates it from the pack")]
+        UnexpectedEof,
+        #[error("Capability {line:?} can't be used in V2 bundles, or after prerequisites and references")]
+        UnexpectedCapability { line: BString },
+        #[error("Capability {name:?} is unknown")]
+        UnknownCapability { name: BString },
+        #[error("Object format {name:?} is unsupported")]
+        UnsupportedObjectFormat { name: BString },
+        #[error("Prerequisites must be listed before all references, but got {line:?}")]
+        UnexpectedPrerequisite { line: BString },
+        #[error("Could not parse header line {line:?}")]
+        InvalidLine { line: BString },
+    }
+}
+
+/// Lifecycle
+impl Header {
+    /// Read a header from `read`, which is positioned at the beginning of a bundle file.
+    ///
+    /// After a successful call, `read` is positioned at the first byte of the pack.
+    pub fn from_read(read: &mut dyn BufRead) -> Result<Self, decode::Error> {
+        let mut line = Vec::new();
+        read_line(read, &mut line)?;
+        let version = match line.as_slice() {
+            SIGNATURE_V2 => Version::V2,
+            SIGNATURE_V3 => Version::V3,
+            _ => return Err(decode::Error::Signature { signature: line.into() }),
+        };
+
+        let mut header = Header {
+            version,
+            object_hash: gix_hash::Kind::Sha1,
+            filter: None,
+            prerequisites: Vec::new(),
+            refs: Vec::new(),
+        };
+        let mut may_use_capabilities = version == Version::V3;
+        loop {
+            read_line(read, &mut line)?;
+            let content = line
+                .strip_suffix(b"\n")
+                .expect("read_line() assures there is a newline");
+            if content.is_empty() {
+                break;
+            }
+            if let Some(capability) = content.strip_prefix(b"@") {
+                if !may_use_capabilities {
+                    return Err(decode::Error::UnexpectedCapability { line: content.into() });
+                }
+                header.set_capability(capability)?;
+                continue;
+            }
+            may_use_capabilities = false;
+
+            if let Some(prerequisite) = content.strip_prefix(b"-") {
+                if !header.refs.is_empty() {
+                    return Err(decode::Error::UnexpectedPrerequisite { line: content.into() });
+                }
+                let (id, comment) = parse_id_and_rest(prerequisite, header.object_hash, true)
+                    .ok_or_else(|| decode::Error::InvalidLine { line: content.into() })?;
+                header.prerequisites.push(Prerequisite {
+                    id,
+                    comment: comment.into(),
+                });
+            } else {
+                let (target, name) = parse_id_and_rest(content, header.object_hash, false)
+                    .filter(|(_, name)| !name.is_empty())
+                    .ok_or_else(|| decode::Error::InvalidLine { line: content.into() })?;
+                header.refs.push(Ref {
+                    name: name.into(),
+                    target,
+                });
+            }
+        }
+        Ok(header)
+    }
+
+    fn set_capability(&mut self, capability: &[u8]) -> Result<(), decode::Error> {
+        let (name, value) = match capability.find_byte(b'=') {
+            Some(pos) => (&capability[..pos], &capability[pos + 1..]),
+            None => (capability, &b""[..]),
+        };
+        match name {
+            b"object-format" => {
+                self.object_hash = value
+                    .to_str()
+                    .ok()
+                    .and_then(|name| name.parse().ok())
+                    .filter(|kind| gix_features::hash::is_supported(*kind))
+                    .ok_or_else(|| decode::Error::UnsupportedObjectFormat { name: value.into() })?;
+            }
+            b"filter" => self.filter = Some(value.into()),
+            _ => return Err(decode::Error::UnknownCapability { name: name.into() }),
+        }
+        Ok(())
+    }
+}
+
+/// Serialization
+impl Header {
+    /// Write this header to `out`, followed by the empty line after which the pack is expected to be written.
+    ///
+    /// Note that capabilities, i.e. the [object hash][Header::object_hash] and the [filter][Header::filter], are only written
+    /// in [V3 bundles][Version::V3], so it's up to the caller to choose the version accordingly.
+    pub fn write_to(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        match self.version {
+            Version::V2 => out.write_all(SIGNATURE_V2)?,
+            Version::V3 => {
+                out.write_all(SIGNATURE_V3)?;
+                writeln!(out, "@object-format={}", self.object_hash.as_str())?;
+                if let Some(filter) = &self.filter {
+                    out.write_all(b"@filter=")?;
+                    out.write_all(filter)?;
+                    out.write_all(b"\n")?;
+                }
+            }
+        }
+        for prerequisite in &self.prerequisites {
+            write!(out, "-{}", prerequisite.id)?;
+            if !prerequisite.comment.is_empty() {
+                out.write_all(b" ")?;
+                out.write_all(&prerequisite.comment)?;
+            }
+            out.write_all(b"\n")?;
+        }
+        for r in &self.refs {
+            write!(out, "{} ", r.target)?;
+            out.write_all(&r.name)?;
+            out.write_all(b"\n")?;
+        }
+        out.write_all(b"\n")
+    }
+
+    /// Return the minimal version of the format that can represent this header.
+    pub fn minimal_version(&self) -> Version {
+        if self.object_hash == gix_hash::Kind::Sha1 && self.filter.is_none() {
+            Version::V2
+        } else {
+            Version::V3
+        }
+    }
+}
+
+fn read_line(read: &mut dyn BufRead, line: &mut Vec<u8>) -> Result<(), decode::Error> {
+    line.clear();
+    read.read_until(b'\n', line)?;
+    if line.last() != Some(&b'\n') {
+        return Err(decode::Error::UnexpectedEof);
+    }
+    Ok(())
+}
+
+/// Parse `<hex-id>[ <rest>]`, with the remainder being optional only if `rest_is_optional` is set.
+fn parse_id_and_rest(line: &[u8], object_hash: gix_hash::Kind, rest_is_optional: bool) -> Option<(ObjectId, &[u8])> {
+    let hex_len = object_hash.len_in_hex();
+    let id = ObjectId::from_hex(line.get(..hex_len)?).ok()?;
+    let rest = match line.get(hex_len..)? {
+        [] if rest_is_optional => &[][..],
+        [b' ', rest @ ..] => rest,
+        _ => return None,
+    };
+    Some((id, rest))
+}
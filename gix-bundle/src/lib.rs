@@ -0,0 +1,122 @@
+//! Read and write [git bundles](https://git-scm.com/docs/gitformat-bundle), files which carry a pack along with the references
+//! pointing into it, to transport objects between repositories without a network connection.
+//!
+//! A [Bundle] can be [opened][Bundle::at()] to inspect its [Header], to [verify][Bundle::verify_prerequisites()] that a repository
+//! has all objects it depends on, and to [index its pack][Bundle::unbundle()] into an object database.
+//! It can also be [served][Bundle::into_connection()] like a remote repository to allow fetching from it with the usual machinery.
+//!
+//! New bundles are created with [`write::to_write()`].
+//!
+//! Bundles may also be offered for download in [lists][List], which allow to bootstrap clones and fetches from static
+//! storage before fetching the remainder from a remote.
+#![deny(rust_2018_idioms, missing_docs)]
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+
+use bstr::BString;
+use gix_hash::ObjectId;
+
+///
+pub mod header;
+///
+pub mod list;
+///
+pub mod open;
+///
+pub mod remote;
+///
+pub mod unbundle;
+///
+pub mod verify;
+///
+pub mod write;
+
+/// The version of the bundle format.
+#[derive(Default, PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum Version {
+    /// The original format which can only hold `SHA1` objects.
+    #[default]
+    V2,
+    /// The format that supports capabilities, like the object hash or the filter used to create the pack.
+    V3,
+}
+
+/// An object the receiving repository must have as the pack of a bundle depends on it.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Prerequisite {
+    /// The id of the commit that must exist in the receiving repository.
+    pub id: ObjectId,
+    /// A free-form comment, typically the summary of the commit message. It's empty if there was none.
+    pub comment: BString,
+}
+
+/// A reference contained in a bundle.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Ref {
+    /// The full name of the reference, like `refs/heads/main` or `HEAD`.
+    pub name: BString,
+    /// The object the reference points to, which is contained in the pack or is one of the prerequisites.
+    pub target: ObjectId,
+}
+
+/// The header of a bundle file, which precedes the pack.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Header {
+    /// The version of the format.
+    pub version: Version,
+    /// The kind of hash used for all object ids, which is always `SHA1` in [V2 bundles][Version::V2].
+    pub object_hash: gix_hash::Kind,
+    /// The specification of the filter used to omit objects from the pack, like `blob:none`, which makes it a partial pack.
+    ///
+    /// It's only available in [V3 bundles][Version::V3].
+    pub filter: Option<BString>,
+    /// The objects the receiving repository must already have.
+    pub prerequisites: Vec<Prerequisite>,
+    /// The references to create or update in the receiving repository.
+    pub refs: Vec<Ref>,
+}
+
+/// A list of bundles to download, as served from a bundle URI in configuration format or advertised by a server
+/// through the protocol V2 `bundle-uri` command.
+///
+/// Lists are built by [updating][List::update()] them with one key-value pair at a time.
+#[derive(Default, PartialEq, Eq, Debug, Clone)]
+pub struct List {
+    /// How the bundles relate to each other.
+    pub mode: list::Mode,
+    /// The heuristic to select bundles with, if any.
+    pub heuristic: Option<list::Heuristic>,
+    /// The bundles in the order in which they were first mentioned.
+    pub bundles: Vec<list::Entry>,
+}
+
+/// A bundle file on disk, with its [header][Header] already parsed.
+#[derive(Debug, Clone)]
+pub struct Bundle {
+    /// The parsed header.
+    pub header: Header,
+    path: PathBuf,
+    pack_offset: u64,
+}
+
+/// Access
+impl Bundle {
+    /// The path at which the bundle was opened.
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// The offset in bytes at which the pack starts in the bundle file.
+    pub fn pack_offset(&self) -> u64 {
+        self.pack_offset
+    }
+
+    /// Open the bundle file for reading, positioned at the beginning of its pack.
+    pub fn pack_reader(&self) -> std::io::Result<std::io::BufReader<std::fs::File>> {
+        use std::io::{Seek, SeekFrom};
+        let mut file = std::fs::File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.pack_offset))?;
+        Ok(std::io::BufReader::with_capacity(64 * 1024, file))
+    }
+}
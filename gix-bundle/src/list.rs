@@ -0,0 +1,165 @@
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+
+use crate::List;
+
+/// How the bundles of a [`List`] relate to each other.
+#[derive(Default, PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum Mode {
+    /// All bundles are needed to obtain all objects the list offers.
+    #[default]
+    All,
+    /// Any single bundle suffices, as all of them contain the same objects, possibly hosted in different locations.
+    Any,
+}
+
+/// A hint on how to select the bundles of a [`List`] to download.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum Heuristic {
+    /// Each bundle has a [creation token][Entry::creation_token], and bundles with greater tokens build upon those with
+    /// lower ones. Thus, only bundles whose token is greater than the greatest one seen previously need to be downloaded
+    /// to get up to date.
+    CreationToken,
+}
+
+/// A bundle in a [`List`].
+#[derive(Default, PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Entry {
+    /// The identifier of the bundle, which is unique within its list.
+    pub id: BString,
+    /// The location of the bundle, which may be relative to the location of the list, or empty if it wasn't set.
+    pub uri: BString,
+    /// A number to order bundles by if the list uses the [creation token heuristic][Heuristic::CreationToken].
+    pub creation_token: Option<u64>,
+}
+
+///
+pub mod update {
+    use bstr::BString;
+
+    /// The error returned by [`List::update()`][crate::List::update()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Bundle list version {version:?} isn't supported, only version 1 is")]
+        UnsupportedVersion { version: BString },
+        #[error("Bundle list mode {mode:?} is unknown, expected 'all' or 'any'")]
+        UnknownMode { mode: BString },
+        #[error("The creation token of bundle {id:?} must be an unsigned integer, got {value:?}")]
+        InvalidCreationToken { id: BString, value: BString },
+    }
+}
+
+/// Mutation
+impl List {
+    /// Set `value` for `key`, a configuration key like `bundle.mode` or `bundle.<id>.uri` as used in bundle lists
+    /// that are served from a bundle URI in configuration format, or sent by a server as `key=value` pairs.
+    ///
+    /// Keys other than `bundle.version`, `bundle.mode`, `bundle.heuristic`, `bundle.<id>.uri` and `bundle.<id>.creationToken`
+    /// are ignored as they may be understood by later versions, just like unknown heuristics.
+    pub fn update(&mut self, key: &BStr, value: &BStr) -> Result<(), update::Error> {
+        use update::Error;
+
+        let Some(key) = key
+            .get(..b"bundle.".len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(b"bundle."))
+            .map(|prefix| key[prefix.len()..].as_bstr())
+        else {
+            return Ok(());
+        };
+        match key.rfind_byte(b'.') {
+            None => {
+                if key.eq_ignore_ascii_case(b"version") {
+                    if value != "1" {
+                        return Err(Error::UnsupportedVersion { version: value.into() });
+                    }
+                } else if key.eq_ignore_ascii_case(b"mode") {
+                    self.mode = match value.as_bytes() {
+                        b"all" => Mode::All,
+                        b"any" => Mode::Any,
+                        _ => return Err(Error::UnknownMode { mode: value.into() }),
+                    };
+                } else if key.eq_ignore_ascii_case(b"heuristic") && value == "creationToken" {
+                    self.heuristic = Some(Heuristic::CreationToken);
+                }
+            }
+            Some(pos) => {
+                let (id, subkey) = (key[..pos].as_bstr(), &key[pos + 1..]);
+                if subkey.eq_ignore_ascii_case(b"uri") {
+                    self.entry_mut(id).uri = value.into();
+                } else if subkey.eq_ignore_ascii_case(b"creationToken") {
+                    let token = value
+                        .to_str()
+                        .ok()
+                        .and_then(|token| token.parse().ok())
+                        .ok_or_else(|| Error::InvalidCreationToken {
+                            id: id.into(),
+                            value: value.into(),
+                        })?;
+                    self.entry_mut(id).creation_token = Some(token);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Turn the relative URIs of all bundles into absolute ones by resolving them against `base`, the URI or path
+    /// of the list itself.
+    pub fn resolve_relative_uris(&mut self, base: &BStr) {
+        for entry in &mut self.bundles {
+            entry.uri = resolve_relative_uri(base, entry.uri.as_ref());
+        }
+    }
+
+    fn entry_mut(&mut self, id: &BStr) -> &mut Entry {
+        match self.bundles.iter().position(|entry| entry.id == id) {
+            Some(pos) => &mut self.bundles[pos],
+            None => {
+                self.bundles.push(Entry {
+                    id: id.into(),
+                    ..Default::default()
+                });
+                self.bundles.last_mut().expect("just pushed")
+            }
+        }
+    }
+}
+
+/// Resolve `uri` relative to the directory `base` is located in, unless it's absolute already.
+fn resolve_relative_uri(base: &BStr, uri: &BStr) -> BString {
+    if uri.is_empty() || uri.starts_with(b"/") || uri.contains_str("://") {
+        return uri.into();
+    }
+    // Never step above the host of URLs.
+    let root_len = base.find("://").map_or(0, |pos| {
+        let host_start = pos + "://".len();
+        base[host_start..]
+            .find_byte(b'/')
+            .map_or(base.len() + 1, |pos| host_start + pos + 1)
+    });
+    let mut out: BString = match base.rfind_byte(b'/') {
+        Some(pos) if pos + 1 >= root_len => base[..pos + 1].into(),
+        _ if root_len == 0 => BString::default(),
+        _ => {
+            let mut host: BString = base.into();
+            host.push(b'/');
+            host
+        }
+    };
+    let mut uri = uri;
+    loop {
+        if let Some(rest) = uri.strip_prefix(b"./") {
+            uri = rest.as_bstr();
+        } else if let Some(rest) = uri.strip_prefix(b"../") {
+            uri = rest.as_bstr();
+            if out.len() > root_len {
+                out.pop();
+                let parent_len = out.rfind_byte(b'/').map_or(0, |pos| pos + 1).max(root_len);
+                out.truncate(parent_len);
+            }
+        } else {
+            break;
+        }
+    }
+    out.push_str(uri);
+    out
+}
@@ -0,0 +1,39 @@
+use std::{io::Seek, path::PathBuf};
+
+use crate::{header, Bundle, Header};
+
+/// The error returned by [`Bundle::at()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not open bundle at \"{}\"", path.display())]
+    Io { source: std::io::Error, path: PathBuf },
+    #[error("Could not decode header of bundle at \"{}\"", path.display())]
+    Header {
+        source: header::decode::Error,
+        path: PathBuf,
+    },
+}
+
+/// Initialization
+impl Bundle {
+    /// Open the bundle at `path` and parse its header.
+    pub fn at(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+        let io_err = |source| Error::Io {
+            source,
+            path: path.clone(),
+        };
+        let mut read = std::io::BufReader::new(std::fs::File::open(&path).map_err(io_err)?);
+        let header = Header::from_read(&mut read).map_err(|source| Error::Header {
+            source,
+            path: path.clone(),
+        })?;
+        let pack_offset = read.stream_position().map_err(io_err)?;
+        Ok(Bundle {
+            header,
+            path,
+            pack_offset,
+        })
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+use bstr::{BString, ByteSlice};
+use gix_packetline_blocking::{decode, encode, Channel, PacketLineRef};
+
+use crate::Bundle;
+
+/// The largest amount of pack data to send in a single side-band packet line.
+const MAX_BAND_DATA_LEN: usize = 65515;
+
+/// The half of a [connection][Bundle::into_connection()] to read responses from.
+pub struct Read {
+    state: Arc<Mutex<State>>,
+}
+
+/// The half of a [connection][Bundle::into_connection()] to write requests to.
+pub struct Write {
+    state: Arc<Mutex<State>>,
+}
+
+struct State {
+    bundle: Bundle,
+    request: Vec<u8>,
+    response: Box<dyn io::Read + Send>,
+}
+
+impl Bundle {
+    /// Turn this bundle into the reading and writing halves of a connection to a repository which serves this bundle
+    /// like `git upload-pack` would, using protocol V2.
+    ///
+    /// The halves can be used just like the standard output and standard input of a spawned `git upload-pack` process, with
+    /// the capability advertisement being readable right away. This makes it possible to fetch from bundles as if they were
+    /// remote repositories.
+    ///
+    /// All references of the bundle are advertised, with `HEAD` pointing to a branch that has the same target if
+    /// symbolic references are requested, preferring `main` and `master` over the first such branch like `git clone` would.
+    /// Fetches always receive the pack of the bundle, no matter what was asked for, so it's the responsibility of the caller
+    /// to [verify its prerequisites][Bundle::verify_prerequisites()].
+    pub fn into_connection(self) -> (Read, Write) {
+        let mut advertisement = Vec::new();
+        for line in [
+            "version 2\n".into(),
+            concat!("agent=gix-bundle/", env!("CARGO_PKG_VERSION"), "\n").into(),
+            "ls-refs\n".into(),
+            "fetch\n".into(),
+            format!("object-format={}\n", self.header.object_hash.as_str()),
+        ] {
+            encode::data_to_write(line.as_bytes(), &mut advertisement).expect("write to memory works");
+        }
+        encode::flush_to_write(&mut advertisement).expect("write to memory works");
+
+        let state = Arc::new(Mutex::new(State {
+            bundle: self,
+            request: Vec::new(),
+            response: Box::new(io::Cursor::new(advertisement)),
+        }));
+        (Read { state: state.clone() }, Write { state })
+    }
+}
+
+impl io::Read for Read {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().expect("no panics while holding the lock");
+        loop {
+            let num_read = state.response.read(buf)?;
+            if num_read != 0 || buf.is_empty() || !state.respond_to_next_request()? {
+                return Ok(num_read);
+            }
+        }
+    }
+}
+
+impl io::Write for Write {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.state
+            .lock()
+            .expect("no panics while holding the lock")
+            .request
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A request as sent by the client.
+#[derive(Default)]
+struct Request {
+    command: Option<BString>,
+    arguments: Vec<BString>,
+}
+
+impl State {
+    /// Parse the next complete request and prepare the response to it, or return `false` if there is no such request.
+    fn respond_to_next_request(&mut self) -> io::Result<bool> {
+        let Some((request, bytes_consumed)) = parse_request(&self.request)? else {
+            return Ok(false);
+        };
+        self.request.drain(..bytes_consumed);
+
+        let mut out = Vec::new();
+        match request.command.as_ref().map(|c| c.as_slice()) {
+            None => {}
+            Some(b"ls-refs") => {
+                self.list_refs(&request.arguments, &mut out)?;
+                encode::flush_to_write(&mut out)?;
+            }
+            Some(b"fetch") => {
+                if !request.arguments.iter().any(|arg| arg == "done") {
+                    // Let the client know that there is no need to negotiate as we only have one pack to send.
+                    encode::text_to_write(b"acknowledgments", &mut out)?;
+                    encode::text_to_write(b"ready", &mut out)?;
+                    encode::delim_to_write(&mut out)?;
+                }
+                encode::text_to_write(b"packfile", &mut out)?;
+                self.response = Box::new(io::Read::chain(
+                    io::Cursor::new(out),
+                    Sideband {
+                        inner: self.bundle.pack_reader()?,
+                        data: vec![0; MAX_BAND_DATA_LEN],
+                        frame: Vec::new(),
+                        pos: 0,
+                        is_done: false,
+                    },
+                ));
+                return Ok(true);
+            }
+            Some(command) => {
+                encode::error_to_write(format!("unknown command {:?}", command.as_bstr()).as_bytes(), &mut out)?;
+            }
+        }
+        self.response = Box::new(io::Cursor::new(out));
+        Ok(true)
+    }
+
+    fn list_refs(&self, arguments: &[BString], out: &mut Vec<u8>) -> io::Result<()> {
+        let prefixes: Vec<_> = arguments
+            .iter()
+            .filter_map(|arg| arg.strip_prefix(b"ref-prefix "))
+            .collect();
+        let with_symrefs = arguments.iter().any(|arg| arg == "symrefs");
+        let refs = &self.bundle.header.refs;
+        for r in refs
+            .iter()
+            .filter(|r| prefixes.is_empty() || prefixes.iter().any(|prefix| r.name.starts_with(prefix)))
+        {
+            let mut line: BString = format!("{} ", r.target).into();
+            line.extend_from_slice(&r.name);
+            if with_symrefs && r.name == "HEAD" {
+                let branches = || {
+                    refs.iter()
+                        .filter(|b| b.target == r.target && b.name.starts_with(b"refs/heads/"))
+                };
+                if let Some(branch) = branches()
+                    .find(|b| b.name == "refs/heads/main" || b.name == "refs/heads/master")
+                    .or_else(|| branches().next())
+                {
+                    line.extend_from_slice(b" symref-target:");
+                    line.extend_from_slice(&branch.name);
+                }
+            }
+            encode::text_to_write(&line, &mut *out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse the first request in `buf` that is terminated by a flush packet, and return it along with the amount of bytes it takes.
+fn parse_request(buf: &[u8]) -> io::Result<Option<(Request, usize)>> {
+    let mut request = Request::default();
+    let mut pos = 0;
+    let mut saw_delimiter = false;
+    loop {
+        let (line, bytes_consumed) = match decode::streaming(&buf[pos..]) {
+            Ok(decode::Stream::Complete { line, bytes_consumed }) => (line, bytes_consumed),
+            Ok(decode::Stream::Incomplete { .. }) => return Ok(None),
+            Err(err) => return Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+        };
+        pos += bytes_consumed;
+        match line {
+            PacketLineRef::Flush => return Ok(Some((request, pos))),
+            PacketLineRef::Delimiter => saw_delimiter = true,
+            PacketLineRef::ResponseEnd => {}
+            PacketLineRef::Data(data) => {
+                let data = data.strip_suffix(b"\n").unwrap_or(data);
+                if saw_delimiter {
+                    request.arguments.push(data.into());
+                } else if let Some(command) = data.strip_prefix(b"command=") {
+                    request.command = Some(command.into());
+                }
+            }
+        }
+    }
+}
+
+/// Turn the pack data of `inner` into side-band packet lines, followed by a flush packet.
+struct Sideband<R> {
+    inner: R,
+    data: Vec<u8>,
+    frame: Vec<u8>,
+    pos: usize,
+    is_done: bool,
+}
+
+impl<R: io::Read> io::Read for Sideband<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.frame.len() {
+            if self.is_done {
+                return Ok(0);
+            }
+            let num_read = self.inner.read(&mut self.data)?;
+            self.frame.clear();
+            self.pos = 0;
+            if num_read == 0 {
+                encode::flush_to_write(&mut self.frame)?;
+                self.is_done = true;
+            } else {
+                encode::band_to_write(Channel::Data, &self.data[..num_read], &mut self.frame)?;
+            }
+        }
+        let num_copied = buf.len().min(self.frame.len() - self.pos);
+        buf[..num_copied].copy_from_slice(&self.frame[self.pos..][..num_copied]);
+        self.pos += num_copied;
+        Ok(num_copied)
+    }
+}
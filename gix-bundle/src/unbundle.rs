@@ -0,0 +1,71 @@
+use std::{path::PathBuf, sync::atomic::AtomicBool};
+
+use gix_features::progress::DynNestedProgress;
+
+use crate::Bundle;
+
+/// The error returned by [`Bundle::unbundle()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    VerifyPrerequisites(#[from] crate::verify::Error),
+    #[error("Could not open the pack of bundle at \"{}\"", path.display())]
+    OpenPack { source: std::io::Error, path: PathBuf },
+    #[error(transparent)]
+    WritePack(#[from] gix_pack::bundle::write::Error),
+    #[error("Could not write promisor file to \"{}\"", path.display())]
+    WritePromisorFile { source: std::io::Error, path: PathBuf },
+}
+
+impl Bundle {
+    /// Assure all prerequisites are present in `objects` and write the pack of this bundle along with a generated index
+    /// into `directory`, or only validate the pack if `directory` is `None`.
+    ///
+    /// `objects` is also used to resolve thin packs, whose deltas may refer to prerequisites as their base.
+    /// The object hash in `options` is ignored in favor of the one [declared by the bundle][crate::Header::object_hash].
+    /// If the bundle was created [with a filter][crate::Header::filter], a `.promisor` file listing all refs of the bundle
+    /// is written next to the pack as the objects it omits must be obtained elsewhere.
+    ///
+    /// Note that the references of the bundle are not written, and that the `.keep` file that protects the pack
+    /// from garbage collection remains for the caller to remove once the references are in place.
+    pub fn unbundle(
+        &self,
+        directory: Option<&std::path::Path>,
+        objects: impl gix_object::Find + gix_object::Exists,
+        progress: &mut dyn DynNestedProgress,
+        should_interrupt: &AtomicBool,
+        options: gix_pack::bundle::write::Options,
+    ) -> Result<gix_pack::bundle::write::Outcome, Error> {
+        self.verify_prerequisites(&objects)?;
+        let mut pack = self.pack_reader().map_err(|source| Error::OpenPack {
+            source,
+            path: self.path.clone(),
+        })?;
+        let outcome = gix_pack::Bundle::write_to_directory(
+            &mut pack,
+            directory,
+            progress,
+            should_interrupt,
+            Some(&objects),
+            gix_pack::bundle::write::Options {
+                object_hash: self.header.object_hash,
+                ..options
+            },
+        )?;
+
+        if self.header.filter.is_some() {
+            if let Some(data_path) = outcome.data_path.as_deref() {
+                let path = data_path.with_extension("promisor");
+                let mut content = Vec::new();
+                for r in &self.header.refs {
+                    content.extend_from_slice(format!("{} ", r.target).as_bytes());
+                    content.extend_from_slice(&r.name);
+                    content.push(b'\n');
+                }
+                std::fs::write(&path, content).map_err(|source| Error::WritePromisorFile { source, path })?;
+            }
+        }
+        Ok(outcome)
+    }
+}
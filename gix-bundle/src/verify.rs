@@ -0,0 +1,31 @@
+use gix_hash::ObjectId;
+
+use crate::Bundle;
+
+/// The error returned by [`Bundle::verify_prerequisites()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Repository lacks {} prerequisite object(s) of the bundle: {}", missing.len(), missing.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    MissingPrerequisites { missing: Vec<ObjectId> },
+}
+
+impl Bundle {
+    /// Assure that all prerequisites of this bundle exist in `objects`, which is required to be able to use its pack.
+    ///
+    /// Note that we don't verify that the prerequisites are also connected to the history of the receiving repository.
+    pub fn verify_prerequisites(&self, objects: &dyn gix_object::Exists) -> Result<(), Error> {
+        let missing: Vec<_> = self
+            .header
+            .prerequisites
+            .iter()
+            .filter(|p| !objects.exists(&p.id))
+            .map(|p| p.id)
+            .collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::MissingPrerequisites { missing })
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    io::Write,
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use gix_features::{
+    parallel::InOrderIter,
+    progress::{Count, DynNestedProgress, Progress},
+};
+use gix_hash::ObjectId;
+use gix_hashtable::HashSet;
+use gix_object::{CommitRefIter, FindExt, Kind, TagRefIter};
+use gix_pack::data::output;
+
+use crate::{Header, Prerequisite, Ref, Version};
+
+/// A filter to omit blobs from the pack of a bundle, turning it into a partial pack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Filter {
+    /// Omit all blobs, equivalent to `blob:none`.
+    BlobNone,
+    /// Omit blobs that are larger than the given amount of `bytes`, equivalent to `blob:limit=<bytes>`.
+    BlobLimit {
+        /// The size of the largest blob to include.
+        bytes: u64,
+    },
+}
+
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Filter::BlobNone => f.write_str("blob:none"),
+            Filter::BlobLimit { bytes } => write!(f, "blob:limit={bytes}"),
+        }
+    }
+}
+
+/// Configuration for [`to_write()`].
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// The version of the bundle format to write, or `None` to use the lowest one that can represent the bundle.
+    pub version: Option<Version>,
+    /// The kind of hash used by the objects in the object database.
+    pub object_hash: gix_hash::Kind,
+    /// If set, omit blobs from the pack as described by the filter.
+    pub filter: Option<Filter>,
+    /// The amount of threads to use when creating pack entries, or `None` to use all logical cores.
+    pub thread_limit: Option<usize>,
+    /// The directory with the packs of the object database, like `.git/objects/pack`, to find reachability bitmaps in.
+    ///
+    /// If a bitmap of a multi-pack index or of a pack is found there, it's used to count the objects to write much faster.
+    /// Otherwise, or if `None`, objects are counted by traversing the commit graph.
+    pub pack_dir: Option<PathBuf>,
+}
+
+/// The outcome of [`to_write()`].
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    /// The header that was written.
+    pub header: Header,
+    /// The amount of objects written into the pack.
+    pub num_objects: usize,
+    /// The checksum of the pack, which is also its name when stored in an object database.
+    pub pack_checksum: ObjectId,
+}
+
+/// The error returned by [`to_write()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("V2 bundles can only contain SHA1 objects and can't be filtered, use V3 instead")]
+    UnsupportedByVersion,
+    #[error(transparent)]
+    FindExisting(#[from] gix_object::find::existing::Error),
+    #[error(transparent)]
+    FindExistingObject(#[from] gix_object::find::existing_object::Error),
+    #[error(transparent)]
+    FindExistingIter(#[from] gix_object::find::existing_iter::Error),
+    #[error(transparent)]
+    FindHeader(gix_object::find::Error),
+    #[error("Object {id} was expected to exist to determine its size")]
+    MissingHeader { id: ObjectId },
+    #[error(transparent)]
+    Decode(#[from] gix_object::decode::Error),
+    #[error(transparent)]
+    TraverseTree(#[from] gix_traverse::tree::breadthfirst::Error),
+    #[error(transparent)]
+    CountObjects(#[from] output::count::objects::Error),
+    #[error(transparent)]
+    CreateEntries(#[from] output::entry::iter_from_counts::Error),
+    #[error(transparent)]
+    WritePack(#[from] output::bytes::Error<output::entry::iter_from_counts::Error>),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Interrupted")]
+    Interrupted,
+}
+
+/// Write a bundle to `out` which contains `refs` and all objects reachable from them, except for the objects reachable from
+/// `prerequisites` which the receiving repository is expected to have already. Objects are obtained from `db`.
+///
+/// `prerequisites` are typically commits, like the ones the receiving repository was last updated with.
+/// All objects reachable from the prerequisite commits, along with all objects in their trees, are left out of the pack.
+/// The pack is never thin, and it isn't an error if `refs` point to objects that are excluded that way.
+///
+/// `progress` is used to communicate the progress of counting objects and writing the pack, and `should_interrupt` is checked
+/// to abort the operation early.
+pub fn to_write<Find>(
+    db: Find,
+    refs: impl IntoIterator<Item = Ref>,
+    prerequisites: impl IntoIterator<Item = ObjectId>,
+    out: &mut dyn Write,
+    progress: &mut dyn DynNestedProgress,
+    should_interrupt: &AtomicBool,
+    Options {
+        version,
+        object_hash,
+        filter,
+        thread_limit,
+        pack_dir,
+    }: Options,
+) -> Result<Outcome, Error>
+where
+    Find: gix_pack::Find + gix_object::Find + gix_object::FindHeader + Send + Clone + 'static,
+{
+    let mut buf = Vec::new();
+    let refs: Vec<_> = refs.into_iter().collect();
+    let mut header = Header {
+        version: Version::V2,
+        object_hash,
+        filter: filter.map(|f| f.to_string().into()),
+        prerequisites: Vec::new(),
+        refs,
+    };
+    header.version = match version {
+        Some(Version::V2) if header.minimal_version() != Version::V2 => return Err(Error::UnsupportedByVersion),
+        Some(version) => version,
+        None => header.minimal_version(),
+    };
+
+    let prerequisites: Vec<_> = prerequisites.into_iter().collect();
+    for id in &prerequisites {
+        let comment = db.find_commit(id, &mut buf)?.message_summary().into_owned();
+        header.prerequisites.push(Prerequisite { id: *id, comment });
+    }
+
+    let mut counts = {
+        let mut progress = progress.add_child("counting".into());
+        progress.init(None, gix_features::progress::count("objects"));
+        let bitmap = pack_dir.and_then(|pack_dir| {
+            gix_pack::bitmap::Indexed::at_pack_dir(pack_dir, object_hash)
+                .ok()
+                .flatten()
+        });
+        let mut counts = match bitmap.as_ref().and_then(|bitmap| bitmap.reachability().ok()) {
+            Some(reachability) => {
+                output::count::objects_with_bitmap(
+                    &db,
+                    &reachability,
+                    &mut header.refs.iter().map(|r| Ok(r.target)),
+                    &mut prerequisites.iter().copied().map(Ok),
+                    &progress,
+                    should_interrupt,
+                )?
+                .0
+            }
+            None => count_objects_by_traversal(&db, &header.refs, &prerequisites, &progress, should_interrupt)?,
+        };
+        if let Some(filter) = filter {
+            let mut filtered = Vec::with_capacity(counts.len());
+            for count in counts {
+                let header = db
+                    .try_header(&count.id)
+                    .map_err(Error::FindHeader)?
+                    .ok_or(Error::MissingHeader { id: count.id })?;
+                let keep = match (header.kind, filter) {
+                    (Kind::Blob, Filter::BlobNone) => false,
+                    (Kind::Blob, Filter::BlobLimit { bytes }) => header.size <= bytes,
+                    _ => true,
+                };
+                if keep {
+                    filtered.push(count);
+                }
+            }
+            counts = filtered;
+        }
+        counts
+    };
+    counts.shrink_to_fit();
+
+    header.write_to(out)?;
+    let num_objects = counts.len();
+    let mut entries = InOrderIter::from(output::entry::iter_from_counts(
+        counts,
+        db,
+        Box::new(progress.add_child("creating entries".into())),
+        output::entry::iter_from_counts::Options {
+            thread_limit,
+            allow_thin_pack: false,
+            ..Default::default()
+        },
+    ));
+    let mut write_progress = progress.add_child("writing".into());
+    write_progress.init(None, gix_features::progress::bytes());
+    let mut pack = output::bytes::FromEntriesIter::new(
+        entries.by_ref(),
+        out,
+        num_objects as u32,
+        gix_pack::data::Version::V2,
+        object_hash,
+    );
+    for written in pack.by_ref() {
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+        write_progress.inc_by(written? as usize);
+    }
+    let pack_checksum = pack.digest().expect("iteration is done");
+    Ok(Outcome {
+        header,
+        num_objects,
+        pack_checksum,
+    })
+}
+
+/// Count all objects reachable from `refs` by traversal, without the objects reachable from the `prerequisites` commits
+/// and the objects in their trees.
+fn count_objects_by_traversal<Find>(
+    db: &Find,
+    refs: &[Ref],
+    prerequisites: &[ObjectId],
+    progress: &dyn Count,
+    should_interrupt: &AtomicBool,
+) -> Result<Vec<output::Count>, Error>
+where
+    Find: gix_pack::Find + gix_object::Find + Send + Clone + 'static,
+{
+    let mut buf = Vec::new();
+    let mut hidden = HashSet::default();
+    {
+        let mut commits = Vec::new();
+        for id in prerequisites {
+            let tree_id = db.find_commit_iter(id, &mut buf)?.tree_id()?;
+            hidden.insert(tree_id);
+            let tree = db.find_tree_iter(&tree_id, &mut buf)?;
+            let mut recorder = gix_traverse::tree::Recorder::default().track_location(None);
+            gix_traverse::tree::breadthfirst(
+                tree,
+                gix_traverse::tree::breadthfirst::State::default(),
+                db,
+                &mut recorder,
+            )?;
+            hidden.extend(recorder.records.into_iter().map(|entry| entry.oid));
+            commits.push(*id);
+        }
+        while let Some(id) = commits.pop() {
+            if !hidden.insert(id) {
+                continue;
+            }
+            if should_interrupt.load(Ordering::Relaxed) {
+                return Err(Error::Interrupted);
+            }
+            commits.extend(db.find_commit_iter(&id, &mut buf)?.parent_ids());
+        }
+    }
+
+    let mut input = Vec::new();
+    {
+        let mut seen = HashSet::default();
+        let mut tips: Vec<_> = refs.iter().map(|r| r.target).collect();
+        while let Some(id) = tips.pop() {
+            if hidden.contains(&id) || !seen.insert(id) {
+                continue;
+            }
+            if should_interrupt.load(Ordering::Relaxed) {
+                return Err(Error::Interrupted);
+            }
+            let object = db.find(&id, &mut buf)?;
+            match object.kind {
+                Kind::Tag => tips.push(TagRefIter::from_bytes(object.data).target_id()?),
+                Kind::Commit => tips.extend(CommitRefIter::from_bytes(object.data).parent_ids()),
+                Kind::Tree | Kind::Blob => {}
+            }
+            input.push(id);
+        }
+    }
+
+    let (mut counts, _outcome) = output::count::objects_unthreaded(
+        db,
+        &mut input.into_iter().map(Ok),
+        progress,
+        should_interrupt,
+        output::count::objects::ObjectExpansion::TreeAdditionsComparedToAncestor,
+    )?;
+    counts.retain(|count| !hidden.contains(&count.id));
+    Ok(counts)
+}
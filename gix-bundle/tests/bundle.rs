@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+
+use gix_bundle::Bundle;
+
+mod header;
+mod list;
+mod unbundle;
+mod write;
+
+fn fixture() -> gix_testtools::Result<PathBuf> {
+    gix_testtools::scripted_fixture_read_only("make_bundles.sh")
+}
+
+fn bundle(name: &str) -> gix_testtools::Result<Bundle> {
+    Ok(Bundle::at(fixture()?.join(name))?)
+}
+
+fn target_of(bundle: &Bundle, name: &str) -> gix_hash::ObjectId {
+    bundle
+        .header
+        .refs
+        .iter()
+        .find(|r| r.name == name)
+        .expect("ref is present")
+        .target
+}
@@ -0,0 +1,23 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q repo
+(cd repo
+  git checkout -q -b main
+  echo one > file && git add file && git commit -q -m "first"
+  echo two > file && git commit -q -am "second"
+  git tag -a -m "annotated" v1
+  echo three > file && git commit -q -am "third"
+  git checkout -q -b dev main~1
+  echo dev > other && git add other && git commit -q -m "dev"
+  git checkout -q main
+
+  git bundle create -q ../all.bundle --all
+  git bundle create -q ../incremental.bundle main~1..main
+  git bundle create -q --version=3 ../v3.bundle main
+)
+
+cp -R repo repo-with-bitmap
+(cd repo-with-bitmap
+  git repack -adbq
+)
@@ -0,0 +1,96 @@
+use gix_bundle::{header::decode, Header, Version};
+
+use crate::{bundle, target_of};
+
+#[test]
+fn v2_with_all_refs() -> gix_testtools::Result {
+    let bundle = bundle("all.bundle")?;
+    let header = &bundle.header;
+    assert_eq!(header.version, Version::V2);
+    assert_eq!(header.object_hash, gix_hash::Kind::Sha1);
+    assert_eq!(header.filter, None);
+    assert!(header.prerequisites.is_empty());
+    assert_eq!(
+        header.refs.iter().map(|r| r.name.to_string()).collect::<Vec<_>>(),
+        ["refs/heads/dev", "refs/heads/main", "refs/tags/v1", "HEAD"]
+    );
+    assert_eq!(target_of(&bundle, "HEAD"), target_of(&bundle, "refs/heads/main"));
+
+    let mut pack = bundle.pack_reader()?;
+    let mut signature = [0; 4];
+    std::io::Read::read_exact(&mut pack, &mut signature)?;
+    assert_eq!(&signature, b"PACK", "the pack starts right after the header");
+    Ok(())
+}
+
+#[test]
+fn incremental_with_prerequisites() -> gix_testtools::Result {
+    let bundle = bundle("incremental.bundle")?;
+    let header = &bundle.header;
+    assert_eq!(header.prerequisites.len(), 1);
+    assert_eq!(header.prerequisites[0].comment, "second", "git adds the commit summary");
+    assert_eq!(header.refs.len(), 1);
+    assert_eq!(header.refs[0].name, "refs/heads/main");
+    Ok(())
+}
+
+#[test]
+fn v3_with_capabilities() -> gix_testtools::Result {
+    let bundle = bundle("v3.bundle")?;
+    assert_eq!(bundle.header.version, Version::V3);
+    assert_eq!(bundle.header.object_hash, gix_hash::Kind::Sha1);
+    assert_eq!(bundle.header.refs.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn round_trip() -> gix_testtools::Result {
+    for name in ["all.bundle", "incremental.bundle", "v3.bundle"] {
+        let expected = bundle(name)?;
+        let mut buf = Vec::new();
+        expected.header.write_to(&mut buf)?;
+        assert_eq!(
+            buf.len() as u64,
+            expected.pack_offset(),
+            "{name}: we write the header exactly like git"
+        );
+        let actual = Header::from_read(&mut buf.as_slice())?;
+        assert_eq!(actual, expected.header);
+    }
+    Ok(())
+}
+
+#[test]
+fn filter_and_sha256_need_v3() -> gix_testtools::Result {
+    let header = Header::from_read(&mut &b"# v3 git bundle\n@object-format=sha256\n@filter=blob:none\n\n"[..])?;
+    assert_eq!(header.object_hash, gix_hash::Kind::Sha256);
+    assert_eq!(
+        header.filter.as_ref().map(ToString::to_string),
+        Some("blob:none".into())
+    );
+    assert_eq!(header.minimal_version(), Version::V3);
+
+    let mut buf = Vec::new();
+    header.write_to(&mut buf)?;
+    assert_eq!(Header::from_read(&mut buf.as_slice())?, header);
+    Ok(())
+}
+
+#[test]
+fn invalid() {
+    for (input, expected) in [
+        (&b"# v4 git bundle\n\n"[..], "Not a bundle"),
+        (b"# v2 git bundle\n@object-format=sha1\n\n", "can't be used in V2 bundles"),
+        (b"# v3 git bundle\n@unknown\n\n", "Capability \"unknown\" is unknown"),
+        (b"# v3 git bundle\n@object-format=md5\n\n", "Object format \"md5\" is unsupported"),
+        (b"# v2 git bundle\n0000000000000000000000000000000000000000\n\n", "Could not parse header line"),
+        (b"# v2 git bundle\n0000000000000000000000000000000000000000 HEAD\n", "ended unexpectedly"),
+        (
+            b"# v2 git bundle\n0000000000000000000000000000000000000000 HEAD\n-0000000000000000000000000000000000000000\n\n",
+            "Prerequisites must be listed before",
+        ),
+    ] {
+        let err: decode::Error = Header::from_read(&mut &input[..]).unwrap_err();
+        assert!(err.to_string().contains(expected), "{err} should contain {expected:?}");
+    }
+}
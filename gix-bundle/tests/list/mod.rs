@@ -0,0 +1,121 @@
+use gix_bundle::{
+    list::{update, Entry, Heuristic, Mode},
+    List,
+};
+
+fn list_from(pairs: &[(&str, &str)]) -> Result<List, update::Error> {
+    let mut list = List::default();
+    for (key, value) in pairs {
+        list.update((*key).into(), (*value).into())?;
+    }
+    Ok(list)
+}
+
+#[test]
+fn update_with_all_known_keys() -> gix_testtools::Result {
+    let list = list_from(&[
+        ("bundle.version", "1"),
+        ("bundle.mode", "any"),
+        ("bundle.heuristic", "creationToken"),
+        ("bundle.base.uri", "base.bundle"),
+        ("bundle.base.creationtoken", "1"),
+        ("bundle.later.creationToken", "2"),
+        ("bundle.later.uri", "https://example.com/later.bundle"),
+        ("bundle.base.unknown", "ignored"),
+        ("bundle.unknown", "ignored"),
+        ("other.key", "ignored"),
+    ])?;
+    assert_eq!(
+        list,
+        List {
+            mode: Mode::Any,
+            heuristic: Some(Heuristic::CreationToken),
+            bundles: vec![
+                Entry {
+                    id: "base".into(),
+                    uri: "base.bundle".into(),
+                    creation_token: Some(1),
+                },
+                Entry {
+                    id: "later".into(),
+                    uri: "https://example.com/later.bundle".into(),
+                    creation_token: Some(2),
+                },
+            ],
+        },
+        "keys are case-insensitive, and entries are kept in order of their first appearance"
+    );
+
+    let list = list_from(&[("bundle.heuristic", "unknown"), ("bundle.with.dots.uri", "a")])?;
+    assert_eq!(list.mode, Mode::All, "the default");
+    assert_eq!(list.heuristic, None, "unknown heuristics are ignored");
+    assert_eq!(list.bundles[0].id, "with.dots", "ids may contain dots");
+    Ok(())
+}
+
+#[test]
+fn update_rejects_invalid_values() {
+    assert!(matches!(
+        list_from(&[("bundle.version", "2")]),
+        Err(update::Error::UnsupportedVersion { .. })
+    ));
+    assert!(matches!(
+        list_from(&[("bundle.mode", "some")]),
+        Err(update::Error::UnknownMode { .. })
+    ));
+    assert!(matches!(
+        list_from(&[("bundle.a.creationToken", "-1")]),
+        Err(update::Error::InvalidCreationToken { .. })
+    ));
+}
+
+#[test]
+fn resolve_relative_uris() -> gix_testtools::Result {
+    let mut list = list_from(&[
+        ("bundle.a.uri", "a.bundle"),
+        ("bundle.b.uri", "./sub/b.bundle"),
+        ("bundle.c.uri", "../../../c.bundle"),
+        ("bundle.d.uri", "/absolute/d.bundle"),
+        ("bundle.e.uri", "file:///absolute/e.bundle"),
+    ])?;
+    let uris = |base: &str| {
+        let mut list = list.clone();
+        list.resolve_relative_uris(base.into());
+        list.bundles.into_iter().map(|b| b.uri.to_string()).collect::<Vec<_>>()
+    };
+    assert_eq!(
+        uris("https://example.com/dir/list"),
+        [
+            "https://example.com/dir/a.bundle",
+            "https://example.com/dir/sub/b.bundle",
+            "https://example.com/c.bundle",
+            "/absolute/d.bundle",
+            "file:///absolute/e.bundle"
+        ],
+        "parent directories can't be left beyond the host"
+    );
+    assert_eq!(
+        uris("https://example.com"),
+        [
+            "https://example.com/a.bundle",
+            "https://example.com/sub/b.bundle",
+            "https://example.com/c.bundle",
+            "/absolute/d.bundle",
+            "file:///absolute/e.bundle"
+        ]
+    );
+    assert_eq!(
+        uris("/a/b/c/d/list"),
+        [
+            "/a/b/c/d/a.bundle",
+            "/a/b/c/d/sub/b.bundle",
+            "/a/c.bundle",
+            "/absolute/d.bundle",
+            "file:///absolute/e.bundle"
+        ]
+    );
+
+    list.resolve_relative_uris("list".into());
+    assert_eq!(list.bundles[0].uri, "a.bundle", "relative to the current directory");
+    Ok(())
+}
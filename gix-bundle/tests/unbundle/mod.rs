@@ -0,0 +1,59 @@
+use std::sync::atomic::AtomicBool;
+
+use gix_bundle::unbundle;
+use gix_features::progress::Discard;
+use gix_object::Exists;
+
+use crate::{bundle, target_of};
+
+fn unbundle_into(
+    bundle: &gix_bundle::Bundle,
+    objects_dir: &std::path::Path,
+) -> Result<gix_pack::bundle::write::Outcome, unbundle::Error> {
+    let pack_dir = objects_dir.join("pack");
+    std::fs::create_dir_all(&pack_dir).expect("can create directory");
+    bundle.unbundle(
+        Some(&pack_dir),
+        gix_odb::at(objects_dir).expect("valid odb"),
+        &mut Discard,
+        &AtomicBool::default(),
+        Default::default(),
+    )
+}
+
+#[test]
+fn into_empty_object_database() -> gix_testtools::Result {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    let bundle = bundle("all.bundle")?;
+    let outcome = unbundle_into(&bundle, tmp.path())?;
+    assert_eq!(outcome.index.num_objects, 13, "4 commits, 4 trees, 4 blobs and a tag");
+    assert!(
+        outcome.keep_path.is_some(),
+        "the caller is expected to remove the keep file"
+    );
+
+    let odb = gix_odb::at(tmp.path())?;
+    for r in &bundle.header.refs {
+        assert!(odb.exists(&r.target), "{} was received", r.name);
+    }
+    Ok(())
+}
+
+#[test]
+fn prerequisites_must_exist() -> gix_testtools::Result {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    let incremental = bundle("incremental.bundle")?;
+    let err = unbundle_into(&incremental, tmp.path()).unwrap_err();
+    assert!(matches!(
+        err,
+        unbundle::Error::VerifyPrerequisites(gix_bundle::verify::Error::MissingPrerequisites { ref missing })
+            if missing == &[incremental.header.prerequisites[0].id]
+    ));
+
+    let all = bundle("all.bundle")?;
+    unbundle_into(&all, tmp.path())?;
+    let outcome = unbundle_into(&incremental, tmp.path())?;
+    assert_eq!(outcome.index.num_objects, 3, "a commit, its tree and the changed blob");
+    assert!(gix_odb::at(tmp.path())?.exists(&target_of(&incremental, "refs/heads/main")));
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use std::sync::atomic::AtomicBool;
+
+use gix_bundle::{write, Bundle, Ref, Version};
+use gix_features::progress::Discard;
+use gix_object::FindExt;
+
+use crate::{bundle, fixture, target_of};
+
+fn odb() -> gix_testtools::Result<gix_odb::HandleArc> {
+    odb_at("repo")
+}
+
+fn odb_at(repo: &str) -> gix_testtools::Result<gix_odb::HandleArc> {
+    let mut odb = gix_odb::at(fixture()?.join(repo).join(".git/objects"))?.into_arc()?;
+    odb.prevent_pack_unload();
+    Ok(odb)
+}
+
+fn write_bundle(
+    refs: Vec<Ref>,
+    prerequisites: Vec<gix_hash::ObjectId>,
+    options: write::Options,
+) -> gix_testtools::Result<(write::Outcome, Bundle, gix_testtools::tempfile::TempDir)> {
+    write_bundle_from("repo", refs, prerequisites, options)
+}
+
+fn write_bundle_from(
+    repo: &str,
+    refs: Vec<Ref>,
+    prerequisites: Vec<gix_hash::ObjectId>,
+    options: write::Options,
+) -> gix_testtools::Result<(write::Outcome, Bundle, gix_testtools::tempfile::TempDir)> {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    let path = tmp.path().join("out.bundle");
+    let mut file = std::fs::File::create(&path)?;
+    let outcome = write::to_write(
+        odb_at(repo)?,
+        refs,
+        prerequisites,
+        &mut file,
+        &mut Discard,
+        &AtomicBool::default(),
+        options,
+    )?;
+    let bundle = Bundle::at(&path)?;
+    assert_eq!(
+        bundle.header, outcome.header,
+        "the header we return is the one we wrote"
+    );
+    Ok((outcome, bundle, tmp))
+}
+
+fn git_verifies(bundle: &Bundle) -> gix_testtools::Result {
+    let out = std::process::Command::new("git")
+        .args(["bundle", "verify", "-q"])
+        .arg(bundle.path())
+        .current_dir(fixture()?.join("repo"))
+        .output()?;
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    Ok(())
+}
+
+#[test]
+fn all_refs() -> gix_testtools::Result {
+    let expected = bundle("all.bundle")?;
+    let (outcome, actual, _tmp) = write_bundle(expected.header.refs.clone(), Vec::new(), Default::default())?;
+    assert_eq!(actual.header.version, Version::V2);
+    assert_eq!(actual.header.refs, expected.header.refs);
+    assert_eq!(outcome.num_objects, 13, "the same objects git would write");
+    git_verifies(&actual)
+}
+
+#[test]
+fn with_prerequisites() -> gix_testtools::Result {
+    let all = bundle("all.bundle")?;
+    let main = target_of(&all, "refs/heads/main");
+    let odb = odb()?;
+    let parent = odb
+        .find_commit_iter(&main, &mut Vec::new())?
+        .parent_ids()
+        .next()
+        .expect("main has a parent");
+
+    let refs = vec![Ref {
+        name: "refs/heads/main".into(),
+        target: main,
+    }];
+    let (outcome, actual, _tmp) = write_bundle(refs.clone(), vec![parent], Default::default())?;
+    assert_eq!(actual.header.refs, refs);
+    assert_eq!(
+        actual.header.prerequisites,
+        bundle("incremental.bundle")?.header.prerequisites
+    );
+    assert_eq!(outcome.num_objects, 3, "a commit, its tree and the changed blob");
+    git_verifies(&actual)?;
+
+    let (outcome, _actual, _tmp) = write_bundle(refs, vec![main], Default::default())?;
+    assert_eq!(outcome.num_objects, 0, "everything is known to the receiver");
+    Ok(())
+}
+
+#[test]
+fn with_filter() -> gix_testtools::Result {
+    let all = bundle("all.bundle")?;
+    let (outcome, actual, _tmp) = write_bundle(
+        all.header.refs.clone(),
+        Vec::new(),
+        write::Options {
+            filter: Some(write::Filter::BlobNone),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(actual.header.version, Version::V3, "filters need V3");
+    assert_eq!(
+        actual.header.filter.as_ref().map(ToString::to_string),
+        Some("blob:none".into())
+    );
+    assert_eq!(outcome.num_objects, 13 - 4, "all blobs are gone");
+
+    let (outcome, _actual, _tmp) = write_bundle(
+        all.header.refs.clone(),
+        Vec::new(),
+        write::Options {
+            filter: Some(write::Filter::BlobLimit { bytes: 4 }),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(outcome.num_objects, 13 - 1, "only 'three\\n' exceeds the limit");
+
+    let err = write_bundle(
+        all.header.refs,
+        Vec::new(),
+        write::Options {
+            filter: Some(write::Filter::BlobNone),
+            version: Some(Version::V2),
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("use V3 instead"));
+    Ok(())
+}
+
+#[test]
+fn with_bitmap() -> gix_testtools::Result {
+    let repo = "repo-with-bitmap";
+    let pack_dir = fixture()?.join(repo).join(".git/objects/pack");
+    assert!(
+        gix_pack::bitmap::Indexed::at_pack_dir(&pack_dir, gix_hash::Kind::Sha1)?.is_some(),
+        "the pack has a bitmap"
+    );
+    let options = || write::Options {
+        pack_dir: Some(pack_dir.clone()),
+        ..Default::default()
+    };
+
+    let all = bundle("all.bundle")?;
+    let (outcome, actual, _tmp) = write_bundle_from(repo, all.header.refs.clone(), Vec::new(), options())?;
+    assert_eq!(outcome.num_objects, 13, "the same objects as without bitmap");
+    git_verifies(&actual)?;
+
+    let main = target_of(&all, "refs/heads/main");
+    let parent = odb_at(repo)?
+        .find_commit_iter(&main, &mut Vec::new())?
+        .parent_ids()
+        .next()
+        .expect("main has a parent");
+    let refs = vec![Ref {
+        name: "refs/heads/main".into(),
+        target: main,
+    }];
+    let (outcome, actual, _tmp) = write_bundle_from(repo, refs, vec![parent], options())?;
+    assert_eq!(
+        actual.header.prerequisites,
+        bundle("incremental.bundle")?.header.prerequisites
+    );
+    assert_eq!(outcome.num_objects, 3, "a commit, its tree and the changed blob");
+    git_verifies(&actual)
+}
@@ -0,0 +1,185 @@
+//! Changed-path Bloom filters, which allow to quickly determine that a commit did *not* change a path compared to its first parent.
+//!
+//! Filters are stored for each commit in the `BIDX` and `BDAT` chunks of a commit-graph file and contain all paths that changed
+//! along with their leading directories. A filter can't tell for sure if a path was changed, but if it doesn't contain
+//! a path, the path definitely wasn't changed.
+use std::collections::BTreeSet;
+
+use bstr::{BStr, ByteSlice};
+
+/// The amount of changed paths after which a commit is considered to have changed every path, in which case its filter
+/// is truncated to a single byte with all bits set.
+pub const MAX_CHANGED_PATHS: usize = 512;
+
+/// The settings used to compute the Bloom filters of a commit-graph file, as stored in its `BDAT` chunk.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Settings {
+    /// The version of the hash function, `1` for the murmur3 implementation of git before v2.42 which sign-extends bytes
+    /// with their high bit set, or `2` for the correct implementation.
+    pub hash_version: u32,
+    /// The amount of hashes, and thus bits, to set for each path.
+    pub num_hashes: u32,
+    /// The amount of bits to allocate for each path in a filter.
+    pub bits_per_entry: u32,
+}
+
+impl Default for Settings {
+    /// The settings git uses by default.
+    fn default() -> Self {
+        Settings {
+            hash_version: 1,
+            num_hashes: 7,
+            bits_per_entry: 10,
+        }
+    }
+}
+
+/// A path hashed with particular [`Settings`], ready to be tested against [filters](Filter).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Key {
+    hash_version: u32,
+    hashes: Vec<u32>,
+}
+
+impl Key {
+    /// Hash `path`, a slash-separated path relative to the repository root without trailing slash, using `settings`.
+    pub fn new(path: &BStr, settings: &Settings) -> Self {
+        const SEED0: u32 = 0x293a_e76f;
+        const SEED1: u32 = 0x7e64_6e2c;
+        let hash0 = murmur3(SEED0, path, settings.hash_version);
+        let hash1 = murmur3(SEED1, path, settings.hash_version);
+        Key {
+            hash_version: settings.hash_version,
+            hashes: (0..settings.num_hashes)
+                .map(|idx| hash0.wrapping_add(idx.wrapping_mul(hash1)))
+                .collect(),
+        }
+    }
+
+    /// Return the keys for `path` and all of its leading directories, all of which must [possibly be contained](Filter::may_contain())
+    /// in a filter for `path` to possibly have changed.
+    pub fn for_path_and_leading_directories(path: &BStr, settings: &Settings) -> Vec<Key> {
+        leading_directories_and_path(path)
+            .map(|path| Key::new(path, settings))
+            .collect()
+    }
+}
+
+/// A Bloom filter of the paths a commit changed compared to its first parent.
+#[derive(Clone, Copy, Debug)]
+pub struct Filter<'a> {
+    data: &'a [u8],
+    settings: &'a Settings,
+}
+
+impl<'a> Filter<'a> {
+    /// Create a new instance from `data` which was computed using `settings`.
+    pub fn new(data: &'a [u8], settings: &'a Settings) -> Self {
+        Filter { data, settings }
+    }
+
+    /// Return the raw bytes of the filter.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Return the settings used to compute the filter, which are also needed to compute [keys](Key) to test against it.
+    pub fn settings(&self) -> &'a Settings {
+        self.settings
+    }
+
+    /// Return `false` if the path hashed into `key` definitely wasn't changed, or `true` if it might have been.
+    ///
+    /// Empty filters, and keys computed with settings that don't match the ones of this filter, can't tell which is why
+    /// `true` is returned in that case.
+    pub fn may_contain(&self, key: &Key) -> bool {
+        if self.data.is_empty()
+            || key.hash_version != self.settings.hash_version
+            || key.hashes.len() != self.settings.num_hashes as usize
+        {
+            return true;
+        }
+        let num_bits = self.data.len() as u64 * 8;
+        key.hashes.iter().all(|hash| {
+            let bit = u64::from(*hash) % num_bits;
+            self.data[(bit / 8) as usize] & (1 << (bit % 8)) != 0
+        })
+    }
+}
+
+/// Compute the data of a filter from the paths of all non-tree entries that changed in a commit compared to its first parent,
+/// using `settings`.
+///
+/// Leading directories of `changed_paths` are added as well, and if there are more than [`MAX_CHANGED_PATHS`], a filter
+/// that contains everything is returned instead.
+pub fn compute<'a>(changed_paths: impl IntoIterator<Item = &'a BStr>, settings: &Settings) -> Vec<u8> {
+    let mut num_changes = 0;
+    let mut paths = BTreeSet::new();
+    for path in changed_paths {
+        num_changes += 1;
+        if num_changes > MAX_CHANGED_PATHS {
+            break;
+        }
+        paths.extend(leading_directories_and_path(path));
+    }
+    if num_changes > MAX_CHANGED_PATHS || paths.len() > MAX_CHANGED_PATHS {
+        return vec![0xff];
+    }
+
+    let len = ((paths.len() * settings.bits_per_entry as usize + 7) / 8).max(1);
+    let mut data = vec![0; len];
+    let num_bits = len as u64 * 8;
+    for path in paths {
+        for hash in Key::new(path, settings).hashes {
+            let bit = u64::from(hash) % num_bits;
+            data[(bit / 8) as usize] |= 1 << (bit % 8);
+        }
+    }
+    data
+}
+
+fn leading_directories_and_path(path: &BStr) -> impl Iterator<Item = &BStr> {
+    path.iter()
+        .enumerate()
+        .filter(|(_, byte)| **byte == b'/')
+        .map(|(idx, _)| path[..idx].as_bstr())
+        .chain(Some(path))
+}
+
+/// The 32 bit murmur3 hash of `data` with `seed`, emulating git's sign extension of bytes in `hash_version` 1.
+fn murmur3(seed: u32, data: &[u8], hash_version: u32) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+    let byte = |byte: u8| -> u32 {
+        if hash_version == 1 {
+            byte as i8 as u32
+        } else {
+            u32::from(byte)
+        }
+    };
+    let mix = |k: u32| k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+
+    let mut hash = seed;
+    let mut blocks = data.chunks_exact(4);
+    for block in &mut blocks {
+        let k = byte(block[0]) | byte(block[1]) << 8 | byte(block[2]) << 16 | byte(block[3]) << 24;
+        hash ^= mix(k);
+        hash = hash.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+    let tail = blocks.remainder();
+    if !tail.is_empty() {
+        let k = tail
+            .iter()
+            .enumerate()
+            .fold(0, |k, (idx, tail_byte)| k ^ byte(*tail_byte) << (idx * 8));
+        hash ^= mix(k);
+    }
+
+    hash ^= data.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^ (hash >> 16)
+}
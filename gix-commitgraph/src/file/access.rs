@@ -4,7 +4,8 @@ use std::{
 };
 
 use crate::{
-    file::{self, commit::Commit, COMMIT_DATA_ENTRY_SIZE_SANS_HASH},
+    bloom,
+    file::{self, commit::Commit, COMMIT_DATA_ENTRY_SIZE_SANS_HASH, CORRECTED_COMMIT_DATE_OFFSET_OVERFLOW},
     File,
 };
 
@@ -15,6 +16,36 @@ impl File {
         self.base_graph_count
     }
 
+    /// Returns the changed-path Bloom filter of the commit at the given lexicographical position, or `None` if this file has no
+    /// such filters or if it is corrupt.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is out of bounds.
+    pub fn bloom_filter_at(&self, pos: file::Position) -> Option<bloom::Filter<'_>> {
+        assert!(
+            pos.0 < self.num_commits(),
+            "expected lexicographical position less than {}, got {}",
+            self.num_commits(),
+            pos.0
+        );
+        let chunks = self.bloom_filters.as_ref()?;
+        let end_offset_at = |pos: u32| -> usize {
+            let start = chunks.index_offset + pos as usize * 4;
+            u32::from_be_bytes(self.data[start..][..4].try_into().expect("4 bytes")) as usize
+        };
+        let start = if pos.0 == 0 { 0 } else { end_offset_at(pos.0 - 1) };
+        let end = end_offset_at(pos.0);
+        let filter_data = &self.data[chunks.data_range.clone()];
+        (start <= end && end <= filter_data.len())
+            .then(|| bloom::Filter::new(&filter_data[start..end], &chunks.settings))
+    }
+
+    /// Returns the settings used to compute the changed-path Bloom filters of this file, if it has any.
+    pub fn bloom_filter_settings(&self) -> Option<&bloom::Settings> {
+        self.bloom_filters.as_ref().map(|chunks| &chunks.settings)
+    }
+
     /// Returns the commit data for the commit located at the given lexicographical position.
     ///
     /// `pos` must range from 0 to `self.num_commits()`.
@@ -26,6 +57,13 @@ impl File {
         Commit::new(self, pos)
     }
 
+    /// Returns `true` if this file stores the corrected commit dates of its commits, also known as generation numbers v2.
+    ///
+    /// Note that in a [`Graph`](crate::Graph) this is only the case if all of its files have them.
+    pub fn has_corrected_commit_dates(&self) -> bool {
+        self.generation_data_offset.is_some()
+    }
+
     /// The kind of hash used in this File.
     ///
     /// Note that it is always conforming to the hash used in the owning repository.
@@ -127,6 +165,21 @@ impl File {
         &self.data[start..][..entry_size]
     }
 
+    /// Returns the offset of the corrected commit date from the committer timestamp of the commit at `pos`, or `None` if
+    /// this file has no generation data or if it is corrupt.
+    pub(crate) fn corrected_commit_date_offset_at(&self, pos: file::Position) -> Option<u64> {
+        let start = self.generation_data_offset? + pos.0 as usize * 4;
+        let offset = u32::from_be_bytes(self.data[start..][..4].try_into().expect("4 bytes"));
+        if offset & CORRECTED_COMMIT_DATE_OFFSET_OVERFLOW == 0 {
+            return Some(offset.into());
+        }
+        let overflow_data = &self.data[self.generation_data_overflow_range.clone()?];
+        let start = (offset & !CORRECTED_COMMIT_DATE_OFFSET_OVERFLOW) as usize * 8;
+        overflow_data
+            .get(start..start + 8)
+            .map(|bytes| u64::from_be_bytes(bytes.try_into().expect("8 bytes")))
+    }
+
     /// Returns the byte slice for this file's entire Extra Edge List (EDGE) chunk.
     pub(crate) fn extra_edges_data(&self) -> Option<&[u8]> {
         Some(&self.data[self.extra_edges_list_range.clone()?])
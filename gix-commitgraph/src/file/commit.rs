@@ -50,15 +50,17 @@ impl<'a> Commit<'a> {
             root_tree_id: gix_hash::oid::from_bytes_unchecked(&bytes[..file.hash_len]),
             parent1: ParentEdge::from_raw(read_u32(&bytes[file.hash_len..][..4])),
             parent2: ParentEdge::from_raw(read_u32(&bytes[file.hash_len + 4..][..4])),
-            // TODO: Add support for corrected commit date offset overflow.
-            //      See https://github.com/git/git/commit/e8b63005c48696a26f976f5f9b0ccaf1983e439d and
-            //          https://github.com/git/git/commit/f90fca638e99a031dce8e3aca72427b2f9b4bb38 for more details and hints at a test.
             generation: read_u32(&bytes[file.hash_len + 8..][..4]) >> 2,
             commit_timestamp: u64::from_be_bytes(bytes[file.hash_len + 8..][..8].try_into().unwrap())
                 & 0x0003_ffff_ffff,
         }
     }
 
+    /// Returns the changed-path Bloom filter of this commit, if its file has one.
+    pub fn bloom_filter(&self) -> Option<crate::bloom::Filter<'a>> {
+        self.file.bloom_filter_at(self.pos)
+    }
+
     /// Returns the committer timestamp of this commit.
     ///
     /// The value is the number of seconds since 1970-01-01 00:00:00 UTC.
@@ -66,6 +68,26 @@ impl<'a> Commit<'a> {
         self.commit_timestamp
     }
 
+    /// Returns the corrected commit date of this commit, also known as generation number v2, if the graph has them.
+    ///
+    /// It's the committer timestamp of this commit, or the max of its parents' corrected commit dates + 1 if that is larger,
+    /// which makes it resilient to clock skew.
+    /// Note that the value is also `None` if the generation data of the commit is corrupt.
+    pub fn corrected_commit_date(&self) -> Option<u64> {
+        self.file
+            .corrected_commit_date_offset_at(self.pos)
+            .map(|offset| self.commit_timestamp + offset)
+    }
+
+    /// Returns the generation number to use when ordering commits, which is the [corrected commit date](Self::corrected_commit_date())
+    /// if available, or the [topological generation number](Self::generation()) otherwise.
+    ///
+    /// As corrected commit dates are used either for all or for none of the commits of a [`Graph`](crate::Graph), the values
+    /// of all of its commits can be compared with each other.
+    pub fn effective_generation(&self) -> u64 {
+        self.corrected_commit_date().unwrap_or_else(|| self.generation.into())
+    }
+
     /// Returns the generation number of this commit.
     ///
     /// Commits without parents have generation number 1. Commits with parents have a generation
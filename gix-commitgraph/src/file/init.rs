@@ -3,8 +3,10 @@ use std::path::PathBuf;
 
 use crate::{
     file::{
-        ChunkId, BASE_GRAPHS_LIST_CHUNK_ID, COMMIT_DATA_CHUNK_ID, COMMIT_DATA_ENTRY_SIZE_SANS_HASH,
-        EXTENDED_EDGES_LIST_CHUNK_ID, FAN_LEN, HEADER_LEN, OID_FAN_CHUNK_ID, OID_LOOKUP_CHUNK_ID, SIGNATURE,
+        BloomFilterChunks, ChunkId, BASE_GRAPHS_LIST_CHUNK_ID, BLOOM_FILTER_DATA_CHUNK_ID,
+        BLOOM_FILTER_DATA_HEADER_LEN, BLOOM_FILTER_INDEX_CHUNK_ID, COMMIT_DATA_CHUNK_ID,
+        COMMIT_DATA_ENTRY_SIZE_SANS_HASH, EXTENDED_EDGES_LIST_CHUNK_ID, FAN_LEN, GENERATION_DATA_CHUNK_ID,
+        GENERATION_DATA_OVERFLOW_CHUNK_ID, HEADER_LEN, OID_FAN_CHUNK_ID, OID_LOOKUP_CHUNK_ID, SIGNATURE,
     },
     File,
 };
@@ -88,7 +90,10 @@ impl File {
         };
         ofs += 1;
 
-        let object_hash = gix_hash::Kind::try_from(data[ofs]).map_err(Error::UnsupportedHashVersion)?;
+        let object_hash = gix_hash::Kind::try_from(data[ofs])
+            .ok()
+            .filter(|kind| gix_features::hash::is_supported(*kind))
+            .ok_or(Error::UnsupportedHashVersion(data[ofs]))?;
         ofs += 1;
 
         let chunk_count = data[ofs];
@@ -183,6 +188,35 @@ impl File {
             })??;
 
         let extra_edges_list_range = chunks.usize_offset_by_id(EXTENDED_EDGES_LIST_CHUNK_ID).ok();
+        let generation_data_range = chunks.usize_offset_by_id(GENERATION_DATA_CHUNK_ID).ok();
+        let generation_data_overflow_range = chunks
+            .validated_usize_offset_by_id(GENERATION_DATA_OVERFLOW_CHUNK_ID, |chunk_range| {
+                if chunk_range.len() % 8 != 0 {
+                    return Err(Error::InvalidChunkSize {
+                        id: GENERATION_DATA_OVERFLOW_CHUNK_ID,
+                        msg: format!("chunk size {} is not a multiple of 8", chunk_range.len()),
+                    });
+                }
+                Ok(chunk_range)
+            })
+            .ok()
+            .transpose()?;
+        let bloom_filter_index_range = chunks.usize_offset_by_id(BLOOM_FILTER_INDEX_CHUNK_ID).ok();
+        let bloom_filter_data_range = chunks
+            .validated_usize_offset_by_id(BLOOM_FILTER_DATA_CHUNK_ID, |chunk_range| {
+                if chunk_range.len() < BLOOM_FILTER_DATA_HEADER_LEN {
+                    return Err(Error::InvalidChunkSize {
+                        id: BLOOM_FILTER_DATA_CHUNK_ID,
+                        msg: format!(
+                            "expected chunk length of at least {BLOOM_FILTER_DATA_HEADER_LEN}, got {}",
+                            chunk_range.len()
+                        ),
+                    });
+                }
+                Ok(chunk_range)
+            })
+            .ok()
+            .transpose()?;
 
         let trailer = &data[chunks.highest_offset() as usize..];
         if trailer.len() != object_hash.len_in_bytes() {
@@ -217,13 +251,54 @@ impl File {
                 chunk2_commits: commit_data_count,
             });
         }
+        let generation_data_offset = match generation_data_range {
+            Some(range) => {
+                let expected_size = fan[255] as usize * 4;
+                if range.len() != expected_size {
+                    return Err(Error::InvalidChunkSize {
+                        id: GENERATION_DATA_CHUNK_ID,
+                        msg: format!("expected chunk length {expected_size}, got {}", range.len()),
+                    });
+                }
+                Some(range.start)
+            }
+            None => None,
+        };
+        let bloom_filters = match (bloom_filter_index_range, bloom_filter_data_range) {
+            (Some(index_range), Some(data_range)) => {
+                let expected_size = fan[255] as usize * 4;
+                if index_range.len() != expected_size {
+                    return Err(Error::InvalidChunkSize {
+                        id: BLOOM_FILTER_INDEX_CHUNK_ID,
+                        msg: format!("expected chunk length {expected_size}, got {}", index_range.len()),
+                    });
+                }
+                let header = &data[data_range.start..][..BLOOM_FILTER_DATA_HEADER_LEN];
+                let read_u32 = |ofs: usize| u32::from_be_bytes(header[ofs..][..4].try_into().expect("4 bytes"));
+                let settings = crate::bloom::Settings {
+                    hash_version: read_u32(0),
+                    num_hashes: read_u32(4),
+                    bits_per_entry: read_u32(8),
+                };
+                // Like git, ignore filters we can't use instead of failing.
+                (matches!(settings.hash_version, 1 | 2) && settings.num_hashes > 0).then(|| BloomFilterChunks {
+                    index_offset: index_range.start,
+                    data_range: data_range.start + BLOOM_FILTER_DATA_HEADER_LEN..data_range.end,
+                    settings,
+                })
+            }
+            _ => None,
+        };
         Ok(File {
             base_graph_count,
             base_graphs_list_offset,
+            bloom_filters,
             commit_data_offset,
             data,
             extra_edges_list_range,
             fan,
+            generation_data_offset,
+            generation_data_overflow_range,
             oid_lookup_offset,
             path,
             hash_len: object_hash.len_in_bytes(),
@@ -8,17 +8,26 @@ mod access;
 pub mod commit;
 mod init;
 pub mod verify;
+pub mod write;
 
 const COMMIT_DATA_ENTRY_SIZE_SANS_HASH: usize = 16;
 pub(crate) const FAN_LEN: usize = 256;
 const HEADER_LEN: usize = 8;
 
 const SIGNATURE: &[u8] = b"CGPH";
+/// The size of the header of the `BDAT` chunk, containing the hash version, the number of hashes and the bits per entry.
+const BLOOM_FILTER_DATA_HEADER_LEN: usize = 12;
 
 type ChunkId = gix_chunk::Id;
 const BASE_GRAPHS_LIST_CHUNK_ID: ChunkId = *b"BASE";
+const BLOOM_FILTER_DATA_CHUNK_ID: ChunkId = *b"BDAT";
+const BLOOM_FILTER_INDEX_CHUNK_ID: ChunkId = *b"BIDX";
 const COMMIT_DATA_CHUNK_ID: ChunkId = *b"CDAT";
 const EXTENDED_EDGES_LIST_CHUNK_ID: ChunkId = *b"EDGE";
+// git v2.36 replaced the `GDAT` and `GDOV` chunks with these, and ignores the former as they might have been written
+// incorrectly by older versions. We do the same.
+const GENERATION_DATA_CHUNK_ID: ChunkId = *b"GDA2";
+const GENERATION_DATA_OVERFLOW_CHUNK_ID: ChunkId = *b"GDO2";
 const OID_FAN_CHUNK_ID: ChunkId = *b"OIDF";
 const OID_LOOKUP_CHUNK_ID: ChunkId = *b"OIDL";
 
@@ -27,6 +36,18 @@ const OID_LOOKUP_CHUNK_ID: ChunkId = *b"OIDL";
 const NO_PARENT: u32 = 0x7000_0000;
 const EXTENDED_EDGES_MASK: u32 = 0x8000_0000;
 const LAST_EXTENDED_EDGE_MASK: u32 = 0x8000_0000;
+/// If set in a corrected commit date offset, the remaining bits are an index into the generation data overflow chunk.
+const CORRECTED_COMMIT_DATE_OFFSET_OVERFLOW: u32 = 0x8000_0000;
+
+/// The location of changed-path Bloom filters within a commit-graph file.
+pub(crate) struct BloomFilterChunks {
+    /// The offset of the `BIDX` chunk, containing the end offsets of each commit's filter.
+    index_offset: usize,
+    /// The range of the filter data in the `BDAT` chunk, past its header.
+    data_range: std::ops::Range<usize>,
+    /// The settings that were used to compute all filters.
+    settings: crate::bloom::Settings,
+}
 
 /// The position of a given commit within a graph file, starting at 0.
 ///
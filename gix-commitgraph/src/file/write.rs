@@ -0,0 +1,339 @@
+//! Write individual commit-graph files.
+use std::{cmp::max, io::Write};
+
+use gix_hash::ObjectId;
+
+use crate::{
+    bloom,
+    file::{
+        BASE_GRAPHS_LIST_CHUNK_ID, BLOOM_FILTER_DATA_CHUNK_ID, BLOOM_FILTER_DATA_HEADER_LEN,
+        BLOOM_FILTER_INDEX_CHUNK_ID, COMMIT_DATA_CHUNK_ID, COMMIT_DATA_ENTRY_SIZE_SANS_HASH,
+        CORRECTED_COMMIT_DATE_OFFSET_OVERFLOW, EXTENDED_EDGES_LIST_CHUNK_ID, EXTENDED_EDGES_MASK, FAN_LEN,
+        GENERATION_DATA_CHUNK_ID, GENERATION_DATA_OVERFLOW_CHUNK_ID, HEADER_LEN, LAST_EXTENDED_EDGE_MASK, NO_PARENT,
+        OID_FAN_CHUNK_ID, OID_LOOKUP_CHUNK_ID, SIGNATURE,
+    },
+    File, Graph, GENERATION_NUMBER_MAX, MAX_COMMITS,
+};
+
+/// A commit to be written into a commit-graph file with [`File::write_from_commits()`].
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Commit {
+    /// The id of the commit.
+    pub id: ObjectId,
+    /// The id of the tree the commit points to.
+    pub root_tree_id: ObjectId,
+    /// The ids of all parents of the commit, in order.
+    pub parents: Vec<ObjectId>,
+    /// The committer timestamp in seconds since the unix epoch.
+    ///
+    /// Note that only the lower 34 bits are stored, just like git does.
+    pub committer_timestamp: u64,
+    /// The data of the changed-path Bloom filter of the commit as computed by [`bloom::compute()`], or `None` if it wasn't computed.
+    ///
+    /// It's only written if Bloom filter settings are passed to [`File::write_from_commits()`].
+    pub bloom_filter: Option<Vec<u8>>,
+}
+
+/// The error used in [`File::write_from_commits()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Commit {id} has parent {parent} which is neither among the commits to write nor in the base graph")]
+    MissingParent { id: ObjectId, parent: ObjectId },
+    #[error("Commit {id} is already contained in the base graph")]
+    CommitInBaseGraph { id: ObjectId },
+    #[error("Cannot write {0} commits as only {} commits are allowed", MAX_COMMITS)]
+    TooManyCommits(usize),
+    #[error("A commit-graph file can build on at most 255 other files, but {0} were given")]
+    TooManyBaseGraphs(usize),
+    #[error("The changed-path Bloom filters would need more than 4GB of space")]
+    TooManyBloomFilters,
+}
+
+/// The result of [`File::write_from_commits()`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Outcome {
+    /// The checksum of the written file, as stored in its trailer.
+    ///
+    /// It's also the hash in the name of the file if it's part of a chain of commit-graph files.
+    pub checksum: ObjectId,
+    /// The amount of commits written into the file.
+    pub num_commits: u32,
+}
+
+/// Writing
+impl File {
+    /// Write a commit-graph file containing `commits` into `out`, whose object ids are hashed with `object_hash`.
+    ///
+    /// If `base` is set, the file will be written as layer on top of all files in `base`, allowing `commits` to have parents
+    /// in it. Otherwise, all parents must be contained in `commits` as well. Duplicate commits are ignored, and generation
+    /// numbers are computed for all commits.
+    /// Corrected commit dates are written as well, unless a file in `base` lacks them, just like git does by default.
+    ///
+    /// If `bloom_settings` are set, the [Bloom filters](Commit::bloom_filter) of all commits are written as well, assuming
+    /// they were computed with these settings. Commits without filter get an empty one, which indicates that it wasn't computed.
+    pub fn write_from_commits(
+        commits: impl IntoIterator<Item = Commit>,
+        base: Option<&Graph>,
+        object_hash: gix_hash::Kind,
+        bloom_settings: Option<&bloom::Settings>,
+        out: &mut dyn std::io::Write,
+    ) -> Result<Outcome, Error> {
+        let mut commits: Vec<_> = commits.into_iter().collect();
+        commits.sort_by_key(|c| c.id);
+        commits.dedup_by(|a, b| a.id == b.id);
+        let num_commits = u32::try_from(commits.len())
+            .ok()
+            .filter(|num_commits| *num_commits <= MAX_COMMITS.saturating_sub(base.map_or(0, Graph::num_commits)))
+            .ok_or(Error::TooManyCommits(commits.len()))?;
+        let base_files = base.map_or(&[][..], |base| base.files.as_slice());
+        let num_base_graphs = u8::try_from(base_files.len()).map_err(|_| Error::TooManyBaseGraphs(base_files.len()))?;
+        if let Some(base) = base {
+            if let Some(commit) = commits.iter().find(|c| base.lookup(c.id).is_some()) {
+                return Err(Error::CommitInBaseGraph { id: commit.id });
+            }
+        }
+
+        let generations = generations(&commits, base)?;
+        let write_generation_data = base_files.iter().all(File::has_corrected_commit_dates);
+        let corrected_commit_date_offsets: Vec<u64> = commits
+            .iter()
+            .zip(&generations)
+            .map(|(commit, (_, corrected_commit_date))| corrected_commit_date - commit.committer_timestamp)
+            .collect();
+        let num_generation_data_overflows = corrected_commit_date_offsets
+            .iter()
+            .filter(|offset| **offset > GENERATION_NUMBER_V2_OFFSET_MAX)
+            .count();
+        let num_base_commits = base.map_or(0, Graph::num_commits);
+        let graph_position = |id: &ObjectId| -> u32 {
+            match commits.binary_search_by(|c| c.id.cmp(id)) {
+                Ok(pos) => num_base_commits + pos as u32,
+                Err(_) => {
+                    base.and_then(|base| base.lookup(id))
+                        .expect("BUG: presence of parents was checked when computing generations")
+                        .0
+                }
+            }
+        };
+
+        let hash_len = object_hash.len_in_bytes();
+        let num_extra_edges: usize = commits
+            .iter()
+            .filter(|c| c.parents.len() > 2)
+            .map(|c| c.parents.len() - 1)
+            .sum();
+        let mut cf = gix_chunk::file::Index::for_writing();
+        cf.plan_chunk(OID_FAN_CHUNK_ID, (FAN_LEN * 4) as u64);
+        cf.plan_chunk(OID_LOOKUP_CHUNK_ID, (commits.len() * hash_len) as u64);
+        cf.plan_chunk(
+            COMMIT_DATA_CHUNK_ID,
+            (commits.len() * (hash_len + COMMIT_DATA_ENTRY_SIZE_SANS_HASH)) as u64,
+        );
+        if write_generation_data {
+            cf.plan_chunk(GENERATION_DATA_CHUNK_ID, (commits.len() * 4) as u64);
+            if num_generation_data_overflows > 0 {
+                cf.plan_chunk(
+                    GENERATION_DATA_OVERFLOW_CHUNK_ID,
+                    (num_generation_data_overflows * 8) as u64,
+                );
+            }
+        }
+        if num_extra_edges > 0 {
+            cf.plan_chunk(EXTENDED_EDGES_LIST_CHUNK_ID, (num_extra_edges * 4) as u64);
+        }
+        if bloom_settings.is_some() {
+            let num_filter_bytes: usize = commits
+                .iter()
+                .map(|c| c.bloom_filter.as_ref().map_or(0, Vec::len))
+                .sum();
+            if u32::try_from(num_filter_bytes).is_err() {
+                return Err(Error::TooManyBloomFilters);
+            }
+            cf.plan_chunk(BLOOM_FILTER_INDEX_CHUNK_ID, (commits.len() * 4) as u64);
+            cf.plan_chunk(
+                BLOOM_FILTER_DATA_CHUNK_ID,
+                (BLOOM_FILTER_DATA_HEADER_LEN + num_filter_bytes) as u64,
+            );
+        }
+        if !base_files.is_empty() {
+            cf.plan_chunk(BASE_GRAPHS_LIST_CHUNK_ID, (base_files.len() * hash_len) as u64);
+        }
+
+        let mut out = gix_features::hash::Write::new(out, object_hash);
+        out.write_all(SIGNATURE)?;
+        out.write_all(&[1 /* version */, object_hash as u8])?;
+        out.write_all(&[
+            u8::try_from(cf.num_chunks()).expect("BUG: we don't write that many chunks"),
+            num_base_graphs,
+        ])?;
+
+        let mut chunk_write = cf.into_write(&mut out, HEADER_LEN)?;
+        while let Some(chunk_to_write) = chunk_write.next_chunk() {
+            match chunk_to_write {
+                OID_FAN_CHUNK_ID => {
+                    let mut fan = [0u32; FAN_LEN];
+                    for commit in &commits {
+                        fan[usize::from(commit.id.first_byte())] += 1;
+                    }
+                    let mut count = 0;
+                    for entry in fan {
+                        count += entry;
+                        chunk_write.write_all(&count.to_be_bytes())?;
+                    }
+                }
+                OID_LOOKUP_CHUNK_ID => {
+                    for commit in &commits {
+                        chunk_write.write_all(commit.id.as_slice())?;
+                    }
+                }
+                COMMIT_DATA_CHUNK_ID => {
+                    let mut extra_edges_count = 0u32;
+                    for (commit, (generation, _)) in commits.iter().zip(&generations) {
+                        chunk_write.write_all(commit.root_tree_id.as_slice())?;
+                        let mut parents = commit.parents.iter().map(&graph_position);
+                        let parent1 = parents.next().unwrap_or(NO_PARENT);
+                        let parent2 = match commit.parents.len() {
+                            0 | 1 => NO_PARENT,
+                            2 => parents.next().expect("second parent present"),
+                            num_parents => {
+                                let edge_index = extra_edges_count | EXTENDED_EDGES_MASK;
+                                extra_edges_count += (num_parents - 1) as u32;
+                                edge_index
+                            }
+                        };
+                        chunk_write.write_all(&parent1.to_be_bytes())?;
+                        chunk_write.write_all(&parent2.to_be_bytes())?;
+
+                        let timestamp = commit.committer_timestamp & 0x0003_ffff_ffff;
+                        let generation_and_timestamp_high = (generation << 2) | (timestamp >> 32) as u32;
+                        chunk_write.write_all(&generation_and_timestamp_high.to_be_bytes())?;
+                        chunk_write.write_all(&(timestamp as u32).to_be_bytes())?;
+                    }
+                }
+                GENERATION_DATA_CHUNK_ID => {
+                    let mut overflow_index = 0u32;
+                    for offset in &corrected_commit_date_offsets {
+                        let offset = if *offset > GENERATION_NUMBER_V2_OFFSET_MAX {
+                            overflow_index += 1;
+                            (overflow_index - 1) | CORRECTED_COMMIT_DATE_OFFSET_OVERFLOW
+                        } else {
+                            *offset as u32
+                        };
+                        chunk_write.write_all(&offset.to_be_bytes())?;
+                    }
+                }
+                GENERATION_DATA_OVERFLOW_CHUNK_ID => {
+                    for offset in corrected_commit_date_offsets
+                        .iter()
+                        .filter(|offset| **offset > GENERATION_NUMBER_V2_OFFSET_MAX)
+                    {
+                        chunk_write.write_all(&offset.to_be_bytes())?;
+                    }
+                }
+                EXTENDED_EDGES_LIST_CHUNK_ID => {
+                    for commit in commits.iter().filter(|c| c.parents.len() > 2) {
+                        let num_edges = commit.parents.len() - 1;
+                        for (edge_index, parent) in commit.parents[1..].iter().enumerate() {
+                            let mut edge = graph_position(parent);
+                            if edge_index + 1 == num_edges {
+                                edge |= LAST_EXTENDED_EDGE_MASK;
+                            }
+                            chunk_write.write_all(&edge.to_be_bytes())?;
+                        }
+                    }
+                }
+                BLOOM_FILTER_INDEX_CHUNK_ID => {
+                    let mut end_offset = 0u32;
+                    for commit in &commits {
+                        end_offset += commit.bloom_filter.as_ref().map_or(0, Vec::len) as u32;
+                        chunk_write.write_all(&end_offset.to_be_bytes())?;
+                    }
+                }
+                BLOOM_FILTER_DATA_CHUNK_ID => {
+                    let settings = bloom_settings.expect("only planned with settings");
+                    for value in [settings.hash_version, settings.num_hashes, settings.bits_per_entry] {
+                        chunk_write.write_all(&value.to_be_bytes())?;
+                    }
+                    for filter in commits.iter().filter_map(|c| c.bloom_filter.as_ref()) {
+                        chunk_write.write_all(filter)?;
+                    }
+                }
+                BASE_GRAPHS_LIST_CHUNK_ID => {
+                    for file in base_files {
+                        chunk_write.write_all(file.checksum().as_bytes())?;
+                    }
+                }
+                unknown => unreachable!("BUG: forgot to implement chunk {:?}", std::str::from_utf8(&unknown)),
+            }
+        }
+
+        let checksum = out.hash.digest();
+        out.inner.write_all(checksum.as_slice())?;
+        Ok(Outcome { checksum, num_commits })
+    }
+}
+
+/// The largest offset of a corrected commit date from the committer timestamp that can be stored without overflow.
+const GENERATION_NUMBER_V2_OFFSET_MAX: u64 = (1 << 31) - 1;
+
+/// Compute the generation number and the corrected commit date of all `commits` in order, possibly with the help of the
+/// ones in `base`.
+fn generations(commits: &[Commit], base: Option<&Graph>) -> Result<Vec<(u32, u64)>, Error> {
+    const UNKNOWN: (u32, u64) = (0, 0);
+    let mut generations = vec![UNKNOWN; commits.len()];
+    let mut stack = Vec::new();
+    for start in 0..commits.len() {
+        if generations[start] != UNKNOWN {
+            continue;
+        }
+        stack.push(start);
+        while let Some(&idx) = stack.last() {
+            let commit = &commits[idx];
+            let mut max_parent_generation = 0;
+            let mut max_parent_corrected_commit_date = 0;
+            let mut has_unknown_parents = false;
+            for parent in &commit.parents {
+                match commits.binary_search_by(|c| c.id.cmp(parent)) {
+                    Ok(parent_idx) => match generations[parent_idx] {
+                        UNKNOWN => {
+                            stack.push(parent_idx);
+                            has_unknown_parents = true;
+                        }
+                        (generation, corrected_commit_date) => {
+                            max_parent_generation = max(max_parent_generation, generation);
+                            max_parent_corrected_commit_date =
+                                max(max_parent_corrected_commit_date, corrected_commit_date);
+                        }
+                    },
+                    Err(_) => {
+                        let parent = base
+                            .and_then(|base| base.commit_by_id(parent))
+                            .ok_or(Error::MissingParent {
+                                id: commit.id,
+                                parent: *parent,
+                            })?;
+                        max_parent_generation = max(max_parent_generation, parent.generation());
+                        // Without corrected commit dates in `base` they won't be written either.
+                        max_parent_corrected_commit_date = max(
+                            max_parent_corrected_commit_date,
+                            parent.corrected_commit_date().unwrap_or_default(),
+                        );
+                    }
+                }
+            }
+            if !has_unknown_parents {
+                generations[idx] = (
+                    (max_parent_generation + 1).min(GENERATION_NUMBER_MAX),
+                    max(commit.committer_timestamp, max_parent_corrected_commit_date + 1),
+                );
+                stack.pop();
+            }
+        }
+    }
+    Ok(generations)
+}
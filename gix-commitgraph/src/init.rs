@@ -84,7 +84,9 @@ impl Graph {
     }
 
     /// Create a new commit graph from a list of `files`.
-    pub fn new(files: Vec<File>) -> Result<Self, Error> {
+    ///
+    /// Like git, corrected commit dates are only used if all `files` have them.
+    pub fn new(mut files: Vec<File>) -> Result<Self, Error> {
         let num_commits: u64 = files.iter().map(|f| u64::from(f.num_commits())).sum();
         if num_commits > u64::from(MAX_COMMITS) {
             return Err(Error::TooManyCommits(num_commits));
@@ -103,6 +105,11 @@ impl Graph {
             }
         }
 
+        if files.iter().any(|f| f.generation_data_offset.is_none()) {
+            for file in &mut files {
+                file.generation_data_offset = None;
+            }
+        }
         Ok(Self { files })
     }
 }
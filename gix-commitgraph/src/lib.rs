@@ -24,10 +24,13 @@ use std::path::Path;
 pub struct File {
     base_graph_count: u8,
     base_graphs_list_offset: Option<usize>,
+    bloom_filters: Option<file::BloomFilterChunks>,
     commit_data_offset: usize,
     data: memmap2::Mmap,
     extra_edges_list_range: Option<std::ops::Range<usize>>,
     fan: [u32; file::FAN_LEN],
+    generation_data_offset: Option<usize>,
+    generation_data_overflow_range: Option<std::ops::Range<usize>>,
     oid_lookup_offset: usize,
     path: std::path::PathBuf,
     hash_len: usize,
@@ -49,10 +52,12 @@ pub fn at(path: impl AsRef<Path>) -> Result<Graph, init::Error> {
 }
 
 mod access;
+pub mod bloom;
 pub mod file;
 ///
 pub mod init;
 pub mod verify;
+pub mod write;
 
 /// The number of generations that are considered 'infinite' commit history.
 pub const GENERATION_NUMBER_INFINITY: u32 = 0xffff_ffff;
@@ -63,6 +68,10 @@ pub const GENERATION_NUMBER_INFINITY: u32 = 0xffff_ffff;
 /// The largest distinct generation number is `GENERATION_NUMBER_MAX - 1`.
 pub const GENERATION_NUMBER_MAX: u32 = 0x3fff_ffff;
 
+/// The generation that is considered 'infinite' when comparing [effective generations](file::Commit::effective_generation()),
+/// which is useful for commits that aren't part of a commit graph.
+pub const GENERATION_NUMBER_V2_INFINITY: u64 = u64::MAX;
+
 /// The maximum number of commits that can be stored in a commit graph.
 pub const MAX_COMMITS: u32 = (1 << 30) + (1 << 29) + (1 << 28) - 1;
 
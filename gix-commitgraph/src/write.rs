@@ -0,0 +1,519 @@
+//! Write commit-graphs into an `objects/info` directory, either as single file or as chain of files.
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use bstr::BString;
+use gix_hash::ObjectId;
+use gix_object::FindExt;
+use gix_tempfile::{handle::Writable, AutoRemove, ContainingDirectory};
+
+use crate::{bloom, file, File, Graph};
+
+/// Determines how to write a chain of commit-graph files, similar to `git commit-graph write --split`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum Split {
+    /// Add a new file to the top of the chain, but merge it with the files below it for as long as they contain no more
+    /// than `size_multiple` times the commits of the new file, or while the new file contains more than `max_commits` commits.
+    ///
+    /// This is what `git commit-graph write --split --size-multiple=<size_multiple> --max-commits=<max_commits>` does.
+    Merge {
+        /// The factor by which a file in the chain has to be larger than the new file to not be merged into it.
+        size_multiple: u32,
+        /// If set, merge files into the new file as long as it contains more than the given amount of commits.
+        max_commits: Option<u32>,
+    },
+    /// Always add a new file to the top of the chain, without merging existing files, like `--split=no-merge` does.
+    NoMerge,
+    /// Replace all files of the chain with a single new one, like `--split=replace` does.
+    Replace,
+}
+
+impl Default for Split {
+    fn default() -> Self {
+        Split::Merge {
+            size_multiple: 2,
+            max_commits: None,
+        }
+    }
+}
+
+/// The options for use in [`Graph::write_to_info_dir()`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Options {
+    /// The kind of hash to use for the commit-graph, which must match the one of the objects in the repository.
+    pub object_hash: gix_hash::Kind,
+    /// If `None`, write a single `objects/info/commit-graph` file with all reachable commits.
+    /// Otherwise, add a file with all commits not yet in the chain below `objects/info/commit-graphs/` as described by
+    /// the strategy.
+    pub split: Option<Split>,
+    /// If set, compute changed-path Bloom filters with the given settings for all commits, like `--changed-paths` does.
+    ///
+    /// Filters of commits that are already in a commit-graph are reused if they were computed with the same settings.
+    pub changed_paths: Option<bloom::Settings>,
+}
+
+/// The result of [`Graph::write_to_info_dir()`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Outcome {
+    /// The path to the newly written commit-graph file, or `None` if all commits were already contained in the chain
+    /// and nothing was written.
+    pub path: Option<PathBuf>,
+    /// The amount of commits written into the new file.
+    pub num_commits: u32,
+    /// The amount of files making up the commit-graph after the operation.
+    pub num_files: usize,
+}
+
+/// The error returned by [`Graph::write_to_info_dir()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not access '{}'", .path.display())]
+    Io {
+        #[source]
+        source: std::io::Error,
+        path: PathBuf,
+    },
+    #[error("Could not open the existing commit-graph")]
+    Init(#[from] crate::init::Error),
+    #[error("Could not read commit information from the existing commit-graph")]
+    GraphCommit(#[from] file::commit::Error),
+    #[error(transparent)]
+    FindExisting(#[from] gix_object::find::existing::Error),
+    #[error(transparent)]
+    FindExistingIter(#[from] gix_object::find::existing_iter::Error),
+    #[error("Could not decode object {id}")]
+    Decode {
+        #[source]
+        source: gix_object::decode::Error,
+        id: ObjectId,
+    },
+    #[error(transparent)]
+    WriteFile(#[from] file::write::Error),
+    #[error("Could not create a temporary file for the new commit-graph file")]
+    CreateTempfile(#[source] std::io::Error),
+    #[error("Could not move the new commit-graph file into place")]
+    Persist(#[from] gix_tempfile::handle::persist::Error<Writable>),
+    #[error(transparent)]
+    LockAcquire(#[from] gix_lock::acquire::Error),
+    #[error(transparent)]
+    LockCommit(#[from] gix_lock::commit::Error<gix_lock::File>),
+}
+
+/// Writing
+impl Graph {
+    /// Write a commit-graph with all commits reachable from `tips` into the `info_dir` (like `.git/objects/info`),
+    /// reading commits from `objects` unless they are already present in an existing commit-graph.
+    ///
+    /// Tags among `tips` are peeled, whereas trees and blobs are ignored.
+    /// Depending on `options`, a single `commit-graph` file is written to replace any existing one, or a new file is added
+    /// to the chain of commit-graph files in `info_dir/commit-graphs`, possibly merging existing files of the chain into it.
+    /// Files that were merged, along with a single `commit-graph` file when writing a chain, are deleted afterwards.
+    /// Similarly, writing a single file deletes an existing chain along with all of its files.
+    pub fn write_to_info_dir(
+        info_dir: &Path,
+        tips: impl IntoIterator<Item = ObjectId>,
+        objects: impl gix_object::Find,
+        options: Options,
+    ) -> Result<Outcome, Error> {
+        let bloom_settings = options.changed_paths.as_ref();
+        let single_path = info_dir.join("commit-graph");
+        let chain_dir = info_dir.join("commit-graphs");
+        let chain_path = chain_dir.join("commit-graph-chain");
+        let single = single_path
+            .is_file()
+            .then(|| Graph::from_file(&single_path))
+            .transpose()?;
+        let chain = chain_path
+            .is_file()
+            .then(|| Graph::from_commit_graphs_dir(&chain_dir))
+            .transpose()?;
+
+        let Some(split) = options.split else {
+            let sources: Vec<_> = single.iter().chain(chain.iter()).collect();
+            let mut commits = HashMap::new();
+            collect_commits(tips, &objects, &sources, None, bloom_settings, &mut commits)?;
+            if let Some(settings) = bloom_settings {
+                compute_bloom_filters(&mut commits, &sources, &objects, settings)?;
+            }
+            let removed_paths: Vec<_> = chain
+                .iter()
+                .flat_map(|chain| chain.files.iter().map(|f| f.path().to_owned()))
+                .chain(chain.is_some().then(|| chain_path.clone()))
+                .collect();
+            drop((single, chain));
+
+            let mut lock = std::io::BufWriter::with_capacity(
+                64 * 1024,
+                gix_lock::File::acquire_to_update_resource(&single_path, gix_lock::acquire::Fail::Immediately, None)?,
+            );
+            let outcome = File::write_from_commits(
+                commits.into_values(),
+                None,
+                options.object_hash,
+                bloom_settings,
+                &mut lock,
+            )?;
+            match lock.into_inner() {
+                Ok(lock) => lock.commit()?,
+                Err(err) => {
+                    return Err(Error::Io {
+                        source: err.into_error(),
+                        path: single_path,
+                    })
+                }
+            };
+            remove_files(removed_paths)?;
+            return Ok(Outcome {
+                path: Some(single_path),
+                num_commits: outcome.num_commits,
+                num_files: 1,
+            });
+        };
+
+        let mut commits = HashMap::new();
+        if let Some(single) = &single {
+            for commit in single.iter_commits() {
+                if chain
+                    .as_ref()
+                    .map_or(false, |chain| chain.lookup(commit.id()).is_some())
+                {
+                    continue;
+                }
+                let commit = commit_from_graph(single, commit, bloom_settings)?;
+                commits.insert(commit.id, commit);
+            }
+        }
+        let sources: Vec<_> = single.iter().collect();
+        collect_commits(tips, &objects, &sources, chain.as_ref(), bloom_settings, &mut commits)?;
+
+        let layers = chain.as_ref().map_or(&[][..], |chain| chain.files.as_slice());
+        let mut num_kept_layers = layers.len();
+        let mut num_commits = commits.len() as u64;
+        match split {
+            Split::Replace => num_kept_layers = 0,
+            Split::NoMerge => {}
+            Split::Merge {
+                size_multiple,
+                max_commits,
+            } => {
+                while let Some(top) = num_kept_layers.checked_sub(1).map(|idx| &layers[idx]) {
+                    let top_commits = u64::from(top.num_commits());
+                    if top_commits <= u64::from(size_multiple) * num_commits
+                        || max_commits.map_or(false, |max_commits| num_commits > u64::from(max_commits))
+                    {
+                        num_commits += top_commits;
+                        num_kept_layers -= 1;
+                    } else {
+                        break;
+                    }
+                }
+            }
+        }
+        if commits.is_empty() && num_kept_layers == layers.len() {
+            return Ok(Outcome {
+                path: None,
+                num_commits: 0,
+                num_files: layers.len(),
+            });
+        }
+
+        if let Some(chain) = &chain {
+            for commit in chain.files[num_kept_layers..].iter().flat_map(File::iter_commits) {
+                let commit = chain
+                    .commit_by_id(commit.id())
+                    .expect("commit of a file in the chain is present in the chain");
+                let commit = commit_from_graph(chain, commit, bloom_settings)?;
+                commits.insert(commit.id, commit);
+            }
+        }
+        if let Some(settings) = bloom_settings {
+            let graphs: Vec<_> = single.iter().chain(chain.iter()).collect();
+            compute_bloom_filters(&mut commits, &graphs, &objects, settings)?;
+        }
+        let kept_paths: Vec<_> = layers[..num_kept_layers].iter().map(|f| f.path().to_owned()).collect();
+        let mut chain_checksums: Vec<_> = layers[..num_kept_layers]
+            .iter()
+            .map(File::checksum)
+            .map(ToOwned::to_owned)
+            .collect();
+        let removed_paths: Vec<_> = layers[num_kept_layers..]
+            .iter()
+            .map(|f| f.path().to_owned())
+            .chain(single.is_some().then(|| single_path.clone()))
+            .collect();
+        drop((single, chain));
+
+        let base = if kept_paths.is_empty() {
+            None
+        } else {
+            let files = kept_paths
+                .iter()
+                .map(|path| {
+                    File::at(path).map_err(|err| crate::init::Error::File {
+                        err,
+                        path: path.to_owned(),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Some(Graph::new(files)?)
+        };
+
+        std::fs::create_dir_all(&chain_dir).map_err(|source| Error::Io {
+            source,
+            path: chain_dir.clone(),
+        })?;
+        let mut graph_file = std::io::BufWriter::with_capacity(
+            64 * 1024,
+            gix_tempfile::new(&chain_dir, ContainingDirectory::Exists, AutoRemove::Tempfile)
+                .map_err(Error::CreateTempfile)?,
+        );
+        let outcome = File::write_from_commits(
+            commits.into_values(),
+            base.as_ref(),
+            options.object_hash,
+            bloom_settings,
+            &mut graph_file,
+        )?;
+        drop(base);
+        let graph_path = chain_dir.join(format!("graph-{}.graph", outcome.checksum));
+        match graph_file.into_inner() {
+            Ok(graph_file) => graph_file.persist(&graph_path)?,
+            Err(err) => {
+                return Err(Error::Io {
+                    source: err.into_error(),
+                    path: graph_path,
+                })
+            }
+        };
+        chain_checksums.push(outcome.checksum);
+
+        let mut lock =
+            gix_lock::File::acquire_to_update_resource(&chain_path, gix_lock::acquire::Fail::Immediately, None)?;
+        chain_checksums
+            .iter()
+            .try_for_each(|checksum| writeln!(lock, "{checksum}"))
+            .map_err(|source| Error::Io {
+                source,
+                path: chain_path.clone(),
+            })?;
+        lock.commit()?;
+
+        remove_files(removed_paths)?;
+        Ok(Outcome {
+            path: Some(graph_path),
+            num_commits: outcome.num_commits,
+            num_files: chain_checksums.len(),
+        })
+    }
+}
+
+/// Delete the files at `paths` that are superseded by a newly written commit-graph, ignoring those that are already gone.
+fn remove_files(paths: Vec<PathBuf>) -> Result<(), Error> {
+    for path in paths {
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(source) => return Err(Error::Io { source, path }),
+        }
+    }
+    Ok(())
+}
+
+/// Traverse all commits reachable from `tips` and place them into `out`, reading them from the graphs in `sources`
+/// or from `objects`. Commits in `stop_at` aren't traversed.
+fn collect_commits(
+    tips: impl IntoIterator<Item = ObjectId>,
+    objects: &impl gix_object::Find,
+    sources: &[&Graph],
+    stop_at: Option<&Graph>,
+    bloom_settings: Option<&bloom::Settings>,
+    out: &mut HashMap<ObjectId, file::write::Commit>,
+) -> Result<(), Error> {
+    let mut stack: Vec<_> = tips.into_iter().collect();
+    let mut buf = Vec::new();
+    while let Some(id) = stack.pop() {
+        if out.contains_key(&id) || stop_at.map_or(false, |graph| graph.lookup(id).is_some()) {
+            continue;
+        }
+        let commit = match sources
+            .iter()
+            .find_map(|graph| graph.commit_by_id(id).map(|commit| (graph, commit)))
+        {
+            Some((graph, commit)) => commit_from_graph(graph, commit, bloom_settings)?,
+            None => {
+                let data = objects.find(&id, &mut buf)?;
+                match data.kind {
+                    gix_object::Kind::Commit => {
+                        let commit = gix_object::CommitRef::from_bytes(data.data)
+                            .map_err(|source| Error::Decode { source, id })?;
+                        file::write::Commit {
+                            id,
+                            root_tree_id: commit.tree(),
+                            parents: commit.parents().collect(),
+                            committer_timestamp: u64::try_from(commit.committer().time.seconds).unwrap_or_default(),
+                            bloom_filter: None,
+                        }
+                    }
+                    gix_object::Kind::Tag => {
+                        let target = gix_object::TagRefIter::from_bytes(data.data)
+                            .target_id()
+                            .map_err(|source| Error::Decode { source, id })?;
+                        stack.push(target);
+                        continue;
+                    }
+                    gix_object::Kind::Tree | gix_object::Kind::Blob => continue,
+                }
+            }
+        };
+        stack.extend(commit.parents.iter().copied());
+        out.insert(id, commit);
+    }
+    Ok(())
+}
+
+/// Convert `commit` of `graph` so it can be written, keeping its Bloom filter if it was computed with `bloom_settings`.
+fn commit_from_graph(
+    graph: &Graph,
+    commit: file::Commit<'_>,
+    bloom_settings: Option<&bloom::Settings>,
+) -> Result<file::write::Commit, Error> {
+    Ok(file::write::Commit {
+        bloom_filter: bloom_settings.and_then(|settings| {
+            commit
+                .bloom_filter()
+                .filter(|filter| filter.settings() == settings && !filter.data().is_empty())
+                .map(|filter| filter.data().to_owned())
+        }),
+        id: commit.id().to_owned(),
+        root_tree_id: commit.root_tree_id().to_owned(),
+        committer_timestamp: commit.committer_timestamp(),
+        parents: commit
+            .iter_parents()
+            .map(|pos| pos.map(|pos| graph.id_at(pos).to_owned()))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Compute the Bloom filters of all `commits` that don't have one yet with `settings`, by diffing their tree with the tree
+/// of their first parent, which is looked up in `commits`, `graphs` or `objects`.
+fn compute_bloom_filters(
+    commits: &mut HashMap<ObjectId, file::write::Commit>,
+    graphs: &[&Graph],
+    objects: &impl gix_object::Find,
+    settings: &bloom::Settings,
+) -> Result<(), Error> {
+    let (mut lhs_buf, mut rhs_buf) = (Vec::new(), Vec::new());
+    let mut changed_paths = Vec::new();
+    let mut filters = Vec::new();
+    for commit in commits.values().filter(|commit| commit.bloom_filter.is_none()) {
+        let parent_tree_id = match commit.parents.first() {
+            Some(parent) => Some(match commits.get(parent) {
+                Some(parent) => parent.root_tree_id,
+                None => match graphs.iter().find_map(|graph| graph.commit_by_id(parent)) {
+                    Some(parent) => parent.root_tree_id().to_owned(),
+                    None => objects
+                        .find_commit_iter(parent, &mut lhs_buf)?
+                        .tree_id()
+                        .map_err(|source| Error::Decode { source, id: *parent })?,
+                },
+            }),
+            None => None,
+        };
+        changed_paths.clear();
+        diff_trees(
+            parent_tree_id,
+            commit.root_tree_id,
+            objects,
+            &mut lhs_buf,
+            &mut rhs_buf,
+            &mut changed_paths,
+        )?;
+        filters.push((
+            commit.id,
+            bloom::compute(changed_paths.iter().map(AsRef::as_ref), settings),
+        ));
+    }
+    for (id, filter) in filters {
+        commits
+            .get_mut(&id)
+            .expect("filter was computed for this commit")
+            .bloom_filter = Some(filter);
+    }
+    Ok(())
+}
+
+/// Place the paths of all non-tree entries that differ between the `lhs` and `rhs` trees into `out`, recursively,
+/// just like `git diff-tree -r` would list them.
+///
+/// Stop early once more than [`bloom::MAX_CHANGED_PATHS`] were found as these won't be used anyway.
+fn diff_trees(
+    lhs: Option<ObjectId>,
+    rhs: ObjectId,
+    objects: &impl gix_object::Find,
+    lhs_buf: &mut Vec<u8>,
+    rhs_buf: &mut Vec<u8>,
+    out: &mut Vec<BString>,
+) -> Result<(), Error> {
+    let mut queue = vec![(BString::default(), lhs, Some(rhs))];
+    while let Some((prefix, lhs, rhs)) = queue.pop() {
+        let lhs = tree_entries(lhs, objects, lhs_buf)?;
+        let rhs = tree_entries(rhs, objects, rhs_buf)?;
+        let (mut lhs, mut rhs) = (lhs.into_iter().peekable(), rhs.into_iter().peekable());
+        loop {
+            let (lhs_entry, rhs_entry) = match (lhs.peek(), rhs.peek()) {
+                (Some(l), Some(r)) => match l.cmp(r) {
+                    Ordering::Less => (lhs.next(), None),
+                    Ordering::Greater => (None, rhs.next()),
+                    Ordering::Equal => (lhs.next(), rhs.next()),
+                },
+                (Some(_), None) => (lhs.next(), None),
+                (None, Some(_)) => (None, rhs.next()),
+                (None, None) => break,
+            };
+            let entry = rhs_entry
+                .as_ref()
+                .or(lhs_entry.as_ref())
+                .expect("at least one side is set");
+            if let (Some(l), Some(r)) = (&lhs_entry, &rhs_entry) {
+                if l.oid == r.oid && l.mode == r.mode {
+                    continue;
+                }
+            }
+            let mut path = prefix.clone();
+            if !path.is_empty() {
+                path.push(b'/');
+            }
+            path.extend_from_slice(&entry.filename);
+            if entry.mode.is_tree() {
+                queue.push((path, lhs_entry.map(|e| e.oid), rhs_entry.map(|e| e.oid)));
+            } else {
+                out.push(path);
+                if out.len() > bloom::MAX_CHANGED_PATHS {
+                    return Ok(());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn tree_entries(
+    id: Option<ObjectId>,
+    objects: &impl gix_object::Find,
+    buf: &mut Vec<u8>,
+) -> Result<Vec<gix_object::tree::Entry>, Error> {
+    let Some(id) = id else { return Ok(Vec::new()) };
+    objects
+        .find_tree_iter(&id, buf)?
+        .map(|entry| entry.map(Into::into))
+        .collect::<Result<_, _>>()
+        .map_err(|source| Error::Decode { source, id })
+}
@@ -24,6 +24,11 @@ fn single_commit_huge_dates_generation_v2_also_do_not_allow_huge_dates() {
         "this is the value we would want to see, but it's not possible in V2 either, as that is just about generations"
     );
     assert_eq!(actual.generation(), 1, "generations are fine though");
+    assert_eq!(
+        actual.corrected_commit_date(),
+        Some(1),
+        "the corrected commit date is stored as offset to the truncated date"
+    );
 }
 
 #[test]
@@ -37,6 +42,8 @@ fn single_commit_huge_dates_overflow_v1() {
         "this is the value we would want to see, but it's not possible in V1"
     );
     assert_eq!(actual.generation(), 1, "generations are fine though");
+    assert_eq!(actual.corrected_commit_date(), None, "V1 has no corrected commit dates");
+    assert_eq!(actual.effective_generation(), 1);
 }
 
 #[test]
@@ -69,6 +76,24 @@ fn generation_numbers_overflow_is_handled_in_chained_graph() {
     }
 }
 
+#[test]
+fn corrected_commit_dates_with_overflow_in_chained_graph() {
+    let names = ["future-1", "old-1", "future-2", "old-2", "extra"];
+    let (cg, refs) = graph_and_expected("generation_number_overflow.sh", &names);
+    let future_date = 4147483646;
+    for (name, expected) in names.iter().zip(future_date..) {
+        let commit = cg.commit_by_id(refs[*name].id).expect("present");
+        assert_eq!(
+            commit.corrected_commit_date(),
+            Some(expected),
+            "{name}: commits in the past are corrected to be younger than their parents, which needs the overflow chunk"
+        );
+        assert_eq!(commit.effective_generation(), expected);
+    }
+    cg.verify_integrity(|_| Ok::<_, std::convert::Infallible>(()))
+        .expect("corrected commit dates are consistent");
+}
+
 #[test]
 fn octupus_merges() {
     let (cg, refs) = graph_and_expected(
@@ -111,3 +136,30 @@ fn two_parents() {
     assert_eq!(cg.commit_at(refs["parent2"].pos()).generation(), 1);
     assert_eq!(cg.commit_at(refs["child"].pos()).generation(), 2);
 }
+
+#[test]
+fn sha256_graphs_can_only_be_read_with_the_sha256_feature() {
+    let info_dir = gix_testtools::scripted_fixture_read_only("sha256.sh")
+        .expect("script succeeds all the time")
+        .join(".git")
+        .join("objects")
+        .join("info");
+    let res = gix_commitgraph::Graph::from_file(&info_dir.join("commit-graph"));
+    if gix_features::hash::is_supported(gix_hash::Kind::Sha256) {
+        let cg = res.expect("graph present and valid");
+        assert_eq!(cg.iter_ids().next().expect("one commit").kind(), gix_hash::Kind::Sha256);
+        cg.verify_integrity(|_| Ok::<_, std::convert::Infallible>(()))
+            .expect("the checksum can be computed");
+    } else {
+        let err = res
+            .err()
+            .expect("SHA256 can't be hashed, so the graph can't be verified either");
+        assert!(matches!(
+            err,
+            gix_commitgraph::init::Error::File {
+                err: gix_commitgraph::file::Error::UnsupportedHashVersion(2),
+                ..
+            }
+        ));
+    }
+}
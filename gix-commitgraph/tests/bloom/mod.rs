@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use gix_commitgraph::{
+    bloom::{self, Key, Settings},
+    write::Options,
+    Graph,
+};
+use gix_testtools::{scripted_fixture_read_only, scripted_fixture_writable};
+
+use crate::{ids_of, write::git_verifies};
+
+fn info_dir(repo_dir: &std::path::Path) -> std::path::PathBuf {
+    repo_dir.join(".git").join("objects").join("info")
+}
+
+fn may_contain_path(graph: &Graph, id: gix_hash::ObjectId, path: &str) -> bool {
+    let filter = graph
+        .commit_by_id(id)
+        .expect("commit present")
+        .bloom_filter()
+        .expect("filter present");
+    Key::for_path_and_leading_directories(path.into(), filter.settings())
+        .iter()
+        .all(|key| filter.may_contain(key))
+}
+
+#[test]
+fn filters_written_by_git_can_be_queried() -> gix_testtools::Result {
+    let repo_dir = scripted_fixture_read_only("changed_paths.sh")?;
+    let graph = Graph::from_info_dir(&info_dir(&repo_dir))?;
+    assert_eq!(
+        graph
+            .commit_at(gix_commitgraph::Position(0))
+            .bloom_filter()
+            .map(|f| *f.settings()),
+        Some(Settings::default()),
+        "git uses the default settings"
+    );
+
+    let ids = ids_of(
+        &repo_dir,
+        &[
+            ":/change in a subdirectory",
+            ":/non-ascii paths",
+            ":/mode change",
+            ":/type change",
+            ":/too many changes",
+            ":/empty",
+        ],
+    );
+    let (subdir, non_ascii, mode_change, type_change, too_many, empty) =
+        (ids[0], ids[1], ids[2], ids[3], ids[4], ids[5]);
+
+    assert!(may_contain_path(&graph, subdir, "dir/subdir/c"));
+    assert!(may_contain_path(&graph, subdir, "dir/subdir"));
+    assert!(!may_contain_path(&graph, subdir, "other/d"));
+    assert!(!may_contain_path(&graph, subdir, "a"));
+
+    for path in ["über", "aä", "dir/😀"] {
+        assert!(may_contain_path(&graph, non_ascii, path), "{path}");
+    }
+    assert!(!may_contain_path(&graph, non_ascii, "dir/b"));
+
+    assert!(may_contain_path(&graph, mode_change, "a"), "mode changes are changes");
+    assert!(!may_contain_path(&graph, mode_change, "dir"));
+
+    assert!(may_contain_path(&graph, type_change, "other"));
+    assert!(may_contain_path(&graph, type_change, "other/d"));
+
+    let filter = graph
+        .commit_by_id(too_many)
+        .expect("present")
+        .bloom_filter()
+        .expect("set");
+    assert_eq!(
+        filter.data(),
+        [0xff],
+        "too many changes yield a filter that contains everything"
+    );
+    assert!(may_contain_path(&graph, too_many, "a"));
+
+    let filter = graph.commit_by_id(empty).expect("present").bloom_filter().expect("set");
+    assert_eq!(filter.data(), [0], "empty commits get a filter that contains nothing");
+    assert!(!may_contain_path(&graph, empty, "a"));
+    Ok(())
+}
+
+#[test]
+fn computed_filters_match_the_ones_written_by_git() -> gix_testtools::Result {
+    let expected_graph = Graph::from_info_dir(&info_dir(&scripted_fixture_read_only("changed_paths.sh")?))?;
+    let expected: HashMap<_, _> = expected_graph
+        .iter_commits()
+        .map(|c| (c.id().to_owned(), c.bloom_filter().expect("set").data().to_owned()))
+        .collect();
+
+    let tmp = scripted_fixture_writable("changed_paths.sh")?;
+    let info_dir = info_dir(tmp.path());
+    std::fs::remove_file(info_dir.join("commit-graph"))?;
+    let odb = gix_odb::at(tmp.path().join(".git").join("objects"))?;
+    Graph::write_to_info_dir(
+        &info_dir,
+        ids_of(tmp.path(), &["HEAD"]),
+        &odb,
+        Options {
+            object_hash: gix_hash::Kind::Sha1,
+            split: None,
+            changed_paths: Some(Settings::default()),
+        },
+    )?;
+
+    let actual = Graph::from_info_dir(&info_dir)?;
+    assert_eq!(actual.num_commits() as usize, expected.len());
+    for commit in actual.iter_commits() {
+        let filter = commit.bloom_filter().expect("all filters are written");
+        assert_eq!(filter.settings(), &Settings::default());
+        assert_eq!(filter.data(), expected[commit.id()], "{}", commit.id());
+    }
+    assert!(git_verifies(tmp.path()));
+    Ok(())
+}
+
+#[test]
+fn filters_are_reused_and_extended_in_chains() -> gix_testtools::Result {
+    let tmp = scripted_fixture_writable("changed_paths.sh")?;
+    let info_dir = info_dir(tmp.path());
+    let odb = gix_odb::at(tmp.path().join(".git").join("objects"))?;
+    let options = Options {
+        object_hash: gix_hash::Kind::Sha1,
+        split: Some(gix_commitgraph::write::Split::NoMerge),
+        changed_paths: Some(Settings::default()),
+    };
+    std::fs::remove_file(info_dir.join("commit-graph"))?;
+    let outcome = Graph::write_to_info_dir(&info_dir, ids_of(tmp.path(), &["HEAD~3"]), &odb, options)?;
+    assert_eq!((outcome.num_commits, outcome.num_files), (5, 1));
+    let outcome = Graph::write_to_info_dir(&info_dir, ids_of(tmp.path(), &["HEAD"]), &odb, options)?;
+    assert_eq!((outcome.num_commits, outcome.num_files), (4, 2));
+
+    let graph = Graph::from_info_dir(&info_dir)?;
+    let head = ids_of(tmp.path(), &["HEAD~1"])[0];
+    assert!(
+        may_contain_path(&graph, head, "side"),
+        "filters are computed for merges"
+    );
+    assert!(!may_contain_path(&graph, head, "many"));
+    Ok(())
+}
+
+#[test]
+fn hash_versions_differ_only_for_paths_with_non_ascii_bytes() {
+    let v1 = Settings::default();
+    let v2 = Settings { hash_version: 2, ..v1 };
+    assert_eq!(
+        bloom::compute(Some("dir/file".into()), &v1),
+        bloom::compute(Some("dir/file".into()), &v2)
+    );
+    assert_ne!(
+        bloom::compute(Some("über".into()), &v1),
+        bloom::compute(Some("über".into()), &v2)
+    );
+
+    let v1_filter = bloom::compute(Some("über".into()), &v1);
+    let filter = bloom::Filter::new(&v1_filter, &v1);
+    assert!(filter.may_contain(&Key::new("über".into(), &v1)));
+    assert!(
+        filter.may_contain(&Key::new("anything".into(), &v2)),
+        "keys computed with different settings can't be tested"
+    );
+}
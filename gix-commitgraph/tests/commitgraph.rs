@@ -10,6 +10,8 @@ use gix_commitgraph::{Graph, Position as GraphPosition};
 use gix_testtools::scripted_fixture_read_only;
 
 mod access;
+mod bloom;
+mod write;
 
 pub fn check_common(cg: &Graph, expected: &HashMap<String, RefInfo, impl BuildHasher>) {
     cg.verify_integrity(|_| Ok::<_, std::convert::Infallible>(()))
@@ -84,6 +86,22 @@ pub fn graph_and_expected_named(
     (cg, expected)
 }
 
+pub fn ids_of(repo_dir: &Path, revs: &[&str]) -> Vec<gix_hash::ObjectId> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_dir)
+        .arg("rev-parse")
+        .args(revs)
+        .env_remove("GIT_DIR")
+        .output()
+        .expect("git rev-parse works");
+    std::str::from_utf8(&output.stdout)
+        .expect("valid UTF-8")
+        .lines()
+        .map(|hex| gix_hash::ObjectId::from_hex(hex.as_bytes()).expect("valid hex"))
+        .collect()
+}
+
 pub struct RefInfo {
     id: gix_hash::ObjectId,
     pub time: gix_date::Time,
@@ -0,0 +1,41 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+git config core.quotePath false
+
+mkdir -p dir/subdir other
+echo a > a
+echo b > dir/b
+echo c > dir/subdir/c
+echo d > other/d
+git add . && git commit -q -m initial
+
+echo c2 > dir/subdir/c
+git commit -q -am "change in a subdirectory"
+
+echo ü > über
+echo ä > aä
+echo x > "dir/😀"
+git add . && git commit -q -m "non-ascii paths"
+
+chmod +x a
+git commit -q -am "mode change"
+
+git rm -q -r other
+echo file > other
+git add other && git commit -q -m "type change from tree to file"
+
+mkdir many
+for i in $(seq 1 600); do echo $i > many/$i; done
+git add many && git commit -q -m "too many changes"
+
+git checkout -q -b side HEAD~2
+echo side > side
+git add side && git commit -q -m "on a side branch"
+git checkout -q -
+git merge -q --no-ff -m "merge" side
+
+git commit -q --allow-empty -m "empty"
+
+git commit-graph write --no-progress --reachable --changed-paths
@@ -0,0 +1,10 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q --object-format=sha256
+
+git checkout -q -b commit
+git commit -q --allow-empty -m commit
+
+git commit-graph write --no-progress --reachable
+git repack -adq
@@ -0,0 +1,247 @@
+use std::{path::Path, process::Command};
+
+use gix_commitgraph::{
+    write::{Options, Split},
+    Graph,
+};
+use gix_testtools::scripted_fixture_writable;
+
+use crate::{check_common, graph_and_expected, ids_of};
+
+pub fn git_verifies(repo_dir: &Path) -> bool {
+    Command::new("git")
+        .arg("-C")
+        .arg(repo_dir)
+        .args(["commit-graph", "verify", "--no-progress"])
+        .env_remove("GIT_DIR")
+        .status()
+        .expect("git can be launched")
+        .success()
+}
+
+fn chain_hashes(info_dir: &Path) -> Vec<String> {
+    std::fs::read_to_string(info_dir.join("commit-graphs").join("commit-graph-chain"))
+        .expect("chain exists")
+        .lines()
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+#[test]
+fn single_file_matches_the_one_written_by_git() -> gix_testtools::Result {
+    let refs = [
+        "root",
+        "parent1",
+        "parent2",
+        "parent3",
+        "parent4",
+        "three_parents",
+        "four_parents",
+    ];
+    let (expected_graph, expected) = graph_and_expected("octopus_merges.sh", &refs);
+    let tmp = scripted_fixture_writable("octopus_merges.sh")?;
+    let info_dir = tmp.path().join(".git").join("objects").join("info");
+    let expected_data = std::fs::read(info_dir.join("commit-graph"))?;
+    std::fs::remove_file(info_dir.join("commit-graph"))?;
+
+    let odb = gix_odb::at(tmp.path().join(".git").join("objects"))?;
+    let outcome = Graph::write_to_info_dir(
+        &info_dir,
+        ids_of(tmp.path(), &["three_parents", "four_parents"]),
+        &odb,
+        Options {
+            object_hash: gix_hash::Kind::Sha1,
+            split: None,
+            changed_paths: None,
+        },
+    )?;
+    assert_eq!(outcome.path.as_deref(), Some(info_dir.join("commit-graph").as_path()));
+    assert_eq!(outcome.num_commits, 7);
+    assert_eq!(outcome.num_files, 1);
+
+    let actual = Graph::from_info_dir(&info_dir)?;
+    check_common(&actual, &expected);
+    for info in expected.values() {
+        assert_eq!(
+            actual.commit_at(info.pos()).generation(),
+            expected_graph.commit_at(info.pos()).generation(),
+            "generations are computed just like git does"
+        );
+    }
+    assert_eq!(
+        std::fs::read(info_dir.join("commit-graph"))?,
+        expected_data,
+        "the file is the same as the one git writes, including its generation data"
+    );
+    assert!(git_verifies(tmp.path()));
+    Ok(())
+}
+
+#[test]
+fn split_chains_are_extended_merged_and_replaced() -> gix_testtools::Result {
+    let tmp = scripted_fixture_writable("split_chain.sh")?;
+    let info_dir = tmp.path().join(".git").join("objects").join("info");
+    std::fs::remove_dir_all(info_dir.join("commit-graphs"))?;
+    let odb = gix_odb::at(tmp.path().join(".git").join("objects"))?;
+    let ids = ids_of(tmp.path(), &["commit1", "commit2", "commit3"]);
+    let write = |tip: gix_hash::ObjectId, split: Split| {
+        Graph::write_to_info_dir(
+            &info_dir,
+            Some(tip),
+            &odb,
+            Options {
+                object_hash: gix_hash::Kind::Sha1,
+                split: Some(split),
+                changed_paths: None,
+            },
+        )
+    };
+
+    let first = write(ids[0], Split::NoMerge)?;
+    assert_eq!((first.num_commits, first.num_files), (1, 1));
+    let second = write(ids[1], Split::NoMerge)?;
+    assert_eq!((second.num_commits, second.num_files), (1, 2));
+    assert_eq!(chain_hashes(&info_dir).len(), 2);
+
+    let unchanged = write(ids[1], Split::default())?;
+    assert_eq!(
+        (unchanged.path, unchanged.num_commits, unchanged.num_files),
+        (None, 0, 2),
+        "nothing is written if all commits are already present"
+    );
+
+    let merged = write(ids[2], Split::default())?;
+    assert_eq!(
+        (merged.num_commits, merged.num_files),
+        (3, 1),
+        "the new commit is merged with the layers below as they are not larger than twice its size"
+    );
+    let hashes = chain_hashes(&info_dir);
+    assert_eq!(hashes.len(), 1);
+    let remaining_files = std::fs::read_dir(info_dir.join("commit-graphs"))?.count();
+    assert_eq!(
+        remaining_files, 2,
+        "merged layers were removed, only the chain and the new file remain"
+    );
+
+    let graph = Graph::from_info_dir(&info_dir)?;
+    assert_eq!(graph.num_commits(), 3);
+    graph.verify_integrity(|_| Ok::<_, std::convert::Infallible>(()))?;
+    assert!(git_verifies(tmp.path()));
+    Ok(())
+}
+
+#[test]
+fn single_file_replaces_split_chain() -> gix_testtools::Result {
+    let tmp = scripted_fixture_writable("split_chain.sh")?;
+    let info_dir = tmp.path().join(".git").join("objects").join("info");
+    let chain_dir = info_dir.join("commit-graphs");
+    assert_eq!(chain_hashes(&info_dir).len(), 3, "the fixture starts out with a chain");
+    let odb = gix_odb::at(tmp.path().join(".git").join("objects"))?;
+
+    let outcome = Graph::write_to_info_dir(
+        &info_dir,
+        ids_of(tmp.path(), &["commit3"]),
+        &odb,
+        Options {
+            object_hash: gix_hash::Kind::Sha1,
+            split: None,
+            changed_paths: None,
+        },
+    )?;
+    assert_eq!((outcome.num_commits, outcome.num_files), (3, 1));
+    assert_eq!(
+        std::fs::read_dir(&chain_dir)?.count(),
+        0,
+        "the chain file and all graph files it referenced were removed"
+    );
+
+    let graph = Graph::from_info_dir(&info_dir)?;
+    assert_eq!(graph.num_commits(), 3);
+    graph.verify_integrity(|_| Ok::<_, std::convert::Infallible>(()))?;
+    assert!(git_verifies(tmp.path()));
+    Ok(())
+}
+
+#[test]
+fn split_chains_build_on_base_layers() -> gix_testtools::Result {
+    let tmp = scripted_fixture_writable("split_chain.sh")?;
+    let info_dir = tmp.path().join(".git").join("objects").join("info");
+    let odb = gix_odb::at(tmp.path().join(".git").join("objects"))?;
+    let head = ids_of(tmp.path(), &["commit3"])[0];
+    let parent = ids_of(tmp.path(), &["commit2"])[0];
+    std::fs::remove_dir_all(info_dir.join("commit-graphs"))?;
+
+    let mut options = Options {
+        object_hash: gix_hash::Kind::Sha1,
+        split: Some(Split::Replace),
+        changed_paths: None,
+    };
+    Graph::write_to_info_dir(&info_dir, Some(parent), &odb, options)?;
+    options.split = Some(Split::Merge {
+        size_multiple: 1,
+        max_commits: None,
+    });
+    let outcome = Graph::write_to_info_dir(&info_dir, Some(head), &odb, options)?;
+    assert_eq!(
+        (outcome.num_commits, outcome.num_files),
+        (1, 2),
+        "the base layer is larger than the new one and is kept"
+    );
+
+    let graph = Graph::from_info_dir(&info_dir)?;
+    graph.verify_integrity(|_| Ok::<_, std::convert::Infallible>(()))?;
+    let commit = graph.commit_by_id(head).expect("present in top layer");
+    assert_eq!(commit.generation(), 3, "generations build on the base layers");
+    assert_eq!(
+        graph.id_at(commit.parent1()?.expect("has parent")),
+        parent.as_ref(),
+        "parents can point into base layers"
+    );
+    assert!(git_verifies(tmp.path()));
+
+    options.split = Some(Split::Replace);
+    let outcome = Graph::write_to_info_dir(&info_dir, Some(head), &odb, options)?;
+    assert_eq!((outcome.num_commits, outcome.num_files), (3, 1));
+    assert!(git_verifies(tmp.path()));
+    Ok(())
+}
+
+#[test]
+fn corrected_commit_dates_overflow_like_in_git() -> gix_testtools::Result {
+    let names = ["future-1", "old-1", "future-2", "old-2", "extra"];
+    let tmp = scripted_fixture_writable("generation_number_overflow.sh")?;
+    let info_dir = tmp.path().join(".git").join("objects").join("info");
+    let ids = ids_of(tmp.path(), &names);
+    let expected: Vec<_> = {
+        let graph = Graph::from_info_dir(&info_dir)?;
+        ids.iter()
+            .map(|id| graph.commit_by_id(id).expect("present").corrected_commit_date())
+            .collect()
+    };
+    std::fs::remove_dir_all(info_dir.join("commit-graphs"))?;
+
+    let odb = gix_odb::at(tmp.path().join(".git").join("objects"))?;
+    let options = Options {
+        object_hash: gix_hash::Kind::Sha1,
+        split: Some(Split::NoMerge),
+        changed_paths: None,
+    };
+    Graph::write_to_info_dir(&info_dir, Some(ids[1]), &odb, options)?;
+    let outcome = Graph::write_to_info_dir(&info_dir, Some(ids[4]), &odb, options)?;
+    assert_eq!(
+        (outcome.num_commits, outcome.num_files),
+        (3, 2),
+        "corrected commit dates of the new layer build on the ones in the base layer"
+    );
+
+    let graph = Graph::from_info_dir(&info_dir)?;
+    let actual: Vec<_> = ids
+        .iter()
+        .map(|id| graph.commit_by_id(id).expect("present").corrected_commit_date())
+        .collect();
+    assert_eq!(actual, expected);
+    graph.verify_integrity(|_| Ok::<_, std::convert::Infallible>(()))?;
+    assert!(git_verifies(tmp.path()));
+    Ok(())
+}
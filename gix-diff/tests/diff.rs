@@ -37,10 +37,11 @@ mod util {
                 Some(data) => {
                     buffer.clear();
                     buffer.extend_from_slice(data);
-                    Ok(Some(gix_object::Data {
-                        kind: gix_object::Kind::Blob,
-                        data: buffer.as_slice(),
-                    }))
+                    Ok(Some(gix_object::Data::new(
+                        gix_object::Kind::Blob,
+                        buffer.as_slice(),
+                        id.kind(),
+                    )))
                 }
                 None => Ok(None),
             }
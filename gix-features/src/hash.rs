@@ -1,24 +1,41 @@
 //! Hash functions and hash utilities
 //!
-//! With the `fast-sha1` feature, the `Sha1` hash type will use a more elaborate implementation utilizing hardware support
-//! in case it is available. Otherwise the `rustsha1` feature should be set. `fast-sha1` will take precedence.
-//! Otherwise, a minimal yet performant implementation is used instead for a decent trade-off between compile times and run-time performance.
-#[cfg(all(feature = "rustsha1", not(feature = "fast-sha1")))]
+//! The `Sha1` hash type is available with the `rustsha1` or `fast-sha1` feature, and detects collision attacks like
+//! [SHAttered](https://shattered.io) just like `git` does. Such attacks are reported by [`Hasher::try_digest()`].
+//!
+//! The `Sha256` hash type is available with the `sha256` feature.
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
 mod _impl {
-    use super::Sha1Digest;
+    use super::{Error, Sha1Digest};
 
     /// A implementation of the Sha1 hash, which can be used once.
-    #[derive(Default, Clone)]
-    pub struct Sha1(sha1_smol::Sha1);
+    #[derive(Clone)]
+    pub struct Sha1(sha1_checked::Sha1);
+
+    impl Default for Sha1 {
+        fn default() -> Self {
+            // Keep the original digest of colliding inputs instead of altering it, as we report collisions as error anyway.
+            Sha1(sha1_checked::Sha1::builder().safe_hash(false).build())
+        }
+    }
 
     impl Sha1 {
         /// Digest the given `bytes`.
         pub fn update(&mut self, bytes: &[u8]) {
-            self.0.update(bytes);
+            sha1_checked::Digest::update(&mut self.0, bytes);
         }
-        /// Finalize the hash and produce a digest.
+        /// Finalize the hash and produce a digest, without regard for collision attacks.
         pub fn digest(self) -> Sha1Digest {
-            self.0.digest().bytes()
+            (*self.0.try_finalize().hash()).into()
+        }
+        /// Finalize the hash and produce a digest, or fail if the hashed data was part of a collision attack.
+        pub fn try_digest(self) -> Result<Sha1Digest, Error> {
+            let res = self.0.try_finalize();
+            let digest: Sha1Digest = (*res.hash()).into();
+            if res.has_collision() {
+                return Err(Error::CollisionAttack { digest: digest.into() });
+            }
+            Ok(digest)
         }
     }
 }
@@ -27,30 +44,64 @@ mod _impl {
 #[cfg(any(feature = "fast-sha1", feature = "rustsha1"))]
 pub type Sha1Digest = [u8; 20];
 
-#[cfg(feature = "fast-sha1")]
-mod _impl {
-    use sha1::Digest;
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
+pub use _impl::Sha1;
 
-    use super::Sha1Digest;
+/// The error returned by [`Hasher::try_digest()`] and [`Sha1::try_digest()`].
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Detected SHA-1 collision attack with digest {digest}")]
+    CollisionAttack { digest: gix_hash::ObjectId },
+}
 
-    /// A implementation of the Sha1 hash, which can be used once.
-    #[derive(Default, Clone)]
-    pub struct Sha1(sha1::Sha1);
+#[cfg(feature = "sha256")]
+mod sha256;
+#[cfg(feature = "sha256")]
+pub use sha256::{Sha256, Sha256Digest};
 
-    impl Sha1 {
-        /// Digest the given `bytes`.
-        pub fn update(&mut self, bytes: &[u8]) {
-            self.0.update(bytes);
+/// A hash implementation for any of the supported [kinds of hashes](gix_hash::Kind), which can be used once.
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
+#[derive(Clone)]
+#[allow(clippy::large_enum_variant)] // the collision-detecting `Sha1` is large, but boxing it would allocate for each hash.
+pub enum Hasher {
+    /// The Sha1 hash.
+    Sha1(Sha1),
+    /// The Sha256 hash.
+    #[cfg(feature = "sha256")]
+    Sha256(Sha256),
+}
+
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
+impl Hasher {
+    /// Digest the given `bytes`.
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Sha1(hash) => hash.update(bytes),
+            #[cfg(feature = "sha256")]
+            Hasher::Sha256(hash) => hash.update(bytes),
         }
-        /// Finalize the hash and produce a digest.
-        pub fn digest(self) -> Sha1Digest {
-            self.0.finalize().into()
+    }
+
+    /// Finalize the hash and produce the object id it represents, without regard for collision attacks.
+    pub fn digest(self) -> gix_hash::ObjectId {
+        match self {
+            Hasher::Sha1(hash) => hash.digest().into(),
+            #[cfg(feature = "sha256")]
+            Hasher::Sha256(hash) => hash.digest().into(),
         }
     }
-}
 
-#[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
-pub use _impl::Sha1;
+    /// Finalize the hash and produce the object id it represents, or fail if the hashed data was part of a collision attack.
+    pub fn try_digest(self) -> Result<gix_hash::ObjectId, Error> {
+        Ok(match self {
+            Hasher::Sha1(hash) => hash.try_digest()?.into(),
+            #[cfg(feature = "sha256")]
+            Hasher::Sha256(hash) => hash.digest().into(),
+        })
+    }
+}
 
 /// Compute a CRC32 hash from the given `bytes`, returning the CRC32 hash.
 ///
@@ -74,11 +125,31 @@ pub fn crc32(bytes: &[u8]) -> u32 {
     h.finalize()
 }
 
+/// Return `true` if [`hasher()`] can produce a hasher for `kind`, which for [`Sha256`](gix_hash::Kind::Sha256)
+/// is only the case if the `sha256` feature is enabled.
+///
+/// Use it to reject hash kinds read from disk before they are used for hashing.
+pub const fn is_supported(kind: gix_hash::Kind) -> bool {
+    match kind {
+        gix_hash::Kind::Sha1 => true,
+        gix_hash::Kind::Sha256 => cfg!(feature = "sha256"),
+    }
+}
+
 /// Produce a hasher suitable for the given kind of hash.
+///
+/// # Panics
+///
+/// If `kind` is [`Sha256`](gix_hash::Kind::Sha256) but the `sha256` feature isn't enabled, which can be checked
+/// with [`is_supported()`].
 #[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
-pub fn hasher(kind: gix_hash::Kind) -> Sha1 {
+pub fn hasher(kind: gix_hash::Kind) -> Hasher {
     match kind {
-        gix_hash::Kind::Sha1 => Sha1::default(),
+        gix_hash::Kind::Sha1 => Hasher::Sha1(Sha1::default()),
+        #[cfg(feature = "sha256")]
+        gix_hash::Kind::Sha256 => Hasher::Sha256(Sha256::default()),
+        #[cfg(not(feature = "sha256"))]
+        gix_hash::Kind::Sha256 => panic!("Hashing with SHA256 needs the `sha256` feature of `gix-features`"),
     }
 }
 
@@ -127,7 +198,7 @@ pub fn bytes(
 pub fn bytes_with_hasher(
     read: &mut dyn std::io::Read,
     num_bytes_from_start: u64,
-    mut hasher: Sha1,
+    mut hasher: Hasher,
     progress: &mut dyn crate::progress::Progress,
     should_interrupt: &std::sync::atomic::AtomicBool,
 ) -> std::io::Result<gix_hash::ObjectId> {
@@ -153,19 +224,19 @@ pub fn bytes_with_hasher(
         }
     }
 
-    let id = gix_hash::ObjectId::from(hasher.digest());
+    let id = hasher.digest();
     progress.show_throughput(start);
     Ok(id)
 }
 
 #[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
 mod write {
-    use crate::hash::Sha1;
+    use crate::hash::Hasher;
 
     /// A utility to automatically generate a hash while writing into an inner writer.
     pub struct Write<T> {
         /// The hash implementation.
-        pub hash: Sha1,
+        pub hash: Hasher,
         /// The inner writer.
         pub inner: T,
     }
@@ -191,11 +262,9 @@ mod write {
     {
         /// Create a new hash writer which hashes all bytes written to `inner` with a hash of `kind`.
         pub fn new(inner: T, object_hash: gix_hash::Kind) -> Self {
-            match object_hash {
-                gix_hash::Kind::Sha1 => Write {
-                    inner,
-                    hash: Sha1::default(),
-                },
+            Write {
+                inner,
+                hash: super::hasher(object_hash),
             }
         }
     }
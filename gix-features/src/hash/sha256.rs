@@ -0,0 +1,19 @@
+use sha2::Digest;
+
+/// A 32 bytes digest produced by a [`Sha256`] hash implementation.
+pub type Sha256Digest = [u8; 32];
+
+/// A implementation of the Sha256 hash, which can be used once.
+#[derive(Default, Clone)]
+pub struct Sha256(sha2::Sha256);
+
+impl Sha256 {
+    /// Digest the given `bytes`.
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+    /// Finalize the hash and produce a digest.
+    pub fn digest(self) -> Sha256Digest {
+        self.0.finalize().into()
+    }
+}
@@ -1,16 +1,150 @@
-use gix_features::hash::Sha1;
+mod sha1 {
+    use gix_features::hash::{hasher, Sha1};
 
-#[cfg(not(feature = "fast-sha1"))]
-#[test]
-fn size_of_sha1() {
-    assert_eq!(std::mem::size_of::<Sha1>(), 96);
+    fn sha1(data: &[u8]) -> String {
+        let mut hash = Sha1::default();
+        hash.update(data);
+        gix_hash::ObjectId::from(hash.try_digest().expect("no collision")).to_string()
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn size_of_sha1() {
+        assert_eq!(
+            std::mem::size_of::<Sha1>(),
+            824,
+            "collision detection needs a lot of state"
+        );
+    }
+
+    #[test]
+    fn known_digests() {
+        assert_eq!(sha1(b""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(sha1(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(
+            sha1(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+            "the padding needs another block"
+        );
+        assert_eq!(sha1(&vec![b'a'; 1_000_000]), "34aa973cd4c4daa4f61eeb2bdbad27316534016f");
+    }
+
+    #[test]
+    fn incremental_updates_yield_the_same_digest() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(256 * 5).collect();
+        let expected = "e37a04cb2353309f5cff4ee036cfb91a5e31cefd";
+        assert_eq!(sha1(&data), expected);
+        for chunk_size in [1, 7, 63, 64, 65, 200] {
+            let mut hash = Sha1::default();
+            for chunk in data.chunks(chunk_size) {
+                hash.update(chunk);
+            }
+            assert_eq!(
+                gix_hash::ObjectId::from(hash.digest()).to_string(),
+                expected,
+                "chunk size {chunk_size}"
+            );
+        }
+    }
+
+    #[test]
+    fn shattered_collision_is_detected() {
+        // The colliding blocks of the SHAttered PDFs are within their first 320 bytes, after which the internal state
+        // of both is the same. Any common suffix, like the rest of the PDFs, would yield the same digest.
+        let first = &include_bytes!("fixtures/shattered-1.pdf.prefix")[..];
+        let second = &include_bytes!("fixtures/shattered-2.pdf.prefix")[..];
+        assert_ne!(first, second);
+
+        let mut digests = Vec::new();
+        for input in [first, second] {
+            let mut hash = hasher(gix_hash::Kind::Sha1);
+            hash.update(input);
+            match hash.try_digest() {
+                Err(gix_features::hash::Error::CollisionAttack { digest }) => digests.push(digest),
+                Ok(id) => panic!("collision in input hashing to {id} wasn't detected"),
+            }
+        }
+        assert_eq!(
+            digests[0], digests[1],
+            "the reported digests are unaltered, so both inputs have the same one"
+        );
+        assert_eq!(digests[0].to_string(), "f92d74e3874587aaf443d1db961d4e26dde13e9c");
+    }
+
+    #[test]
+    fn shattered_collision_is_not_reported_by_digest() {
+        let mut hash = Sha1::default();
+        hash.update(include_bytes!("fixtures/shattered-1.pdf.prefix"));
+        assert_eq!(
+            gix_hash::ObjectId::from(hash.digest()).to_string(),
+            "f92d74e3874587aaf443d1db961d4e26dde13e9c"
+        );
+    }
+
+    #[test]
+    fn hasher_for_sha1_produces_sha1_object_ids() {
+        let mut hash = hasher(gix_hash::Kind::Sha1);
+        hash.update(b"blob 0\0");
+        assert_eq!(
+            hash.try_digest().expect("no collision"),
+            gix_hash::ObjectId::empty_blob(gix_hash::Kind::Sha1)
+        );
+    }
 }
 
-#[cfg(feature = "fast-sha1")]
-#[test]
-fn size_of_sha1() {
-    assert_eq!(
-        std::mem::size_of::<Sha1>(),
-        if cfg!(target_arch = "x86") { 96 } else { 104 }
-    );
+#[cfg(feature = "sha256")]
+mod sha256 {
+    use gix_features::hash::{hasher, Sha256};
+
+    fn hex(digest: [u8; 32]) -> String {
+        gix_hash::ObjectId::from_bytes_or_panic(&digest).to_string()
+    }
+
+    fn sha256(data: &[u8]) -> String {
+        let mut hash = Sha256::default();
+        hash.update(data);
+        hex(hash.digest())
+    }
+
+    #[test]
+    fn known_digests() {
+        assert_eq!(
+            sha256(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+            "the padding needs another block"
+        );
+        assert_eq!(
+            sha256(&vec![b'a'; 1_000_000]),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    #[test]
+    fn incremental_updates_yield_the_same_digest() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(256 * 5).collect();
+        let expected = "d414b085826eb06778483ba35564dc849e643359f69ed9747878ba6e54985bed";
+        assert_eq!(sha256(&data), expected);
+        for chunk_size in [1, 7, 63, 64, 65, 200] {
+            let mut hash = Sha256::default();
+            for chunk in data.chunks(chunk_size) {
+                hash.update(chunk);
+            }
+            assert_eq!(hex(hash.digest()), expected, "chunk size {chunk_size}");
+        }
+    }
+
+    #[test]
+    fn hasher_for_sha256_produces_sha256_object_ids() {
+        let mut hash = hasher(gix_hash::Kind::Sha256);
+        hash.update(b"blob 0\0");
+        assert_eq!(hash.digest(), gix_hash::ObjectId::empty_blob(gix_hash::Kind::Sha256));
+    }
 }
@@ -9,8 +9,26 @@ mod from_hex_len {
         assert_eq!(Kind::from_hex_len(40), Some(Kind::Sha1));
     }
 
+    #[test]
+    fn some_sha256() {
+        assert_eq!(Kind::from_hex_len(41), Some(Kind::Sha256));
+        assert_eq!(Kind::from_hex_len(64), Some(Kind::Sha256));
+    }
+
     #[test]
     fn none_if_there_is_no_fit() {
         assert_eq!(Kind::from_hex_len(65), None);
     }
 }
+
+mod as_str {
+    use gix_hash::Kind;
+
+    #[test]
+    fn roundtrips_through_from_str() {
+        for kind in [Kind::Sha1, Kind::Sha256] {
+            assert_eq!(kind.as_str().parse::<Kind>(), Ok(kind));
+        }
+        assert_eq!(Kind::Sha1.as_str(), "sha1");
+    }
+}
@@ -16,6 +16,9 @@ mod to_hex_with_len {
 
 #[test]
 fn is_null() {
-    assert!(gix_hash::Kind::Sha1.null().is_null());
-    assert!(gix_hash::Kind::Sha1.null().as_ref().is_null());
+    for kind in [gix_hash::Kind::Sha1, gix_hash::Kind::Sha256] {
+        assert!(kind.null().is_null());
+        assert!(kind.null().as_ref().is_null());
+        assert_eq!(kind.null().kind(), kind);
+    }
 }
@@ -98,6 +98,14 @@ mod try_from {
         let actual = Prefix::try_from(input).expect("No errors");
         assert_eq!(actual.cmp_oid(&expected), Ordering::Equal);
     }
+    #[test]
+    fn sha256_prefix() {
+        let input = "abcdefabcdefabcdefabcdefabcdefabcdefabcd123123123123123123";
+        let actual = Prefix::try_from(input).expect("valid sha256 prefix");
+        assert_eq!(actual.as_oid().kind(), gix_hash::Kind::Sha256);
+        assert_eq!(actual.hex_len(), 58);
+    }
+
     #[test]
     fn id_to_short() {
         let input = "ab";
@@ -108,8 +116,8 @@ mod try_from {
 
     #[test]
     fn id_to_long() {
-        let input = "abcdefabcdefabcdefabcdefabcdefabcdefabcd123123123123123123";
-        let expected = Error::TooLong { hex_len: 58 };
+        let input = "abcdefabcdefabcdefabcdefabcdefabcdefabcd123123123123123123abcdefa";
+        let expected = Error::TooLong { hex_len: 65 };
         let actual = Prefix::try_from(input).unwrap_err();
         assert_eq!(actual, expected);
     }
@@ -473,6 +473,8 @@ impl State {
     ///
     /// Alternatively, make sure to call [`sort_entries()`][State::sort_entries()] before entry lookup by path to restore
     /// the invariant.
+    ///
+    /// The trees containing `path` are invalidated in the `TREE` extension.
     pub fn dangerously_push_entry(
         &mut self,
         stat: entry::Stat,
@@ -481,6 +483,7 @@ impl State {
         mode: entry::Mode,
         path: &BStr,
     ) {
+        self.invalidate_tree_extension(path);
         let path = {
             let path_start = self.path_backing.len();
             self.path_backing.push_str(path);
@@ -519,6 +522,7 @@ impl State {
     /// Physically remove all entries for which `should_remove(idx, path, entry)` returns `true`, traversing them from first to last.
     ///
     /// Note that the memory used for the removed entries paths is not freed, as it's append-only.
+    /// The trees containing removed entries are invalidated in the `TREE` extension.
     ///
     /// ### Performance
     ///
@@ -527,10 +531,16 @@ impl State {
     pub fn remove_entries(&mut self, mut should_remove: impl FnMut(usize, &BStr, &mut Entry) -> bool) {
         let mut index = 0;
         let paths = &self.path_backing;
+        let tree = &mut self.tree;
         self.entries.retain_mut(|e| {
             let path = e.path_in(paths);
             let res = !should_remove(index, path, e);
             index += 1;
+            if !res {
+                if let Some(tree) = tree.as_mut() {
+                    tree.invalidate(path);
+                }
+            }
             res
         });
     }
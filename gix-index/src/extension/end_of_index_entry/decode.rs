@@ -1,9 +1,4 @@
-use crate::{
-    decode::header,
-    extension,
-    extension::end_of_index_entry::{MIN_SIZE, MIN_SIZE_WITH_HEADER, SIGNATURE},
-    util::from_be_u32,
-};
+use crate::{decode::header, extension, extension::end_of_index_entry::SIGNATURE, util::from_be_u32};
 
 /// Decode the end of index entry extension, which is no more than a glorified offset to the first byte of all extensions to allow
 /// loading entries and extensions in parallel.
@@ -15,33 +10,35 @@ use crate::{
 /// If the checksum wasn't matched, we will ignore this extension entirely.
 pub fn decode(data: &[u8], object_hash: gix_hash::Kind) -> Option<usize> {
     let hash_len = object_hash.len_in_bytes();
-    if data.len() < MIN_SIZE_WITH_HEADER + hash_len {
+    let size = 4 /* offset to extensions */ + hash_len;
+    let size_with_header = extension::MIN_SIZE + size;
+    if data.len() < size_with_header + hash_len {
         return None;
     }
 
-    let start_of_eoie = data.len() - MIN_SIZE_WITH_HEADER - hash_len;
+    let start_of_eoie = data.len() - size_with_header - hash_len;
     let ext_data = &data[start_of_eoie..data.len() - hash_len];
 
     let (signature, ext_size, ext_data) = extension::decode::header(ext_data);
-    if signature != SIGNATURE || ext_size as usize != MIN_SIZE {
+    if signature != SIGNATURE || ext_size as usize != size {
         return None;
     }
 
     let (offset, checksum) = ext_data.split_at(4);
     let offset = from_be_u32(offset) as usize;
-    if offset < header::SIZE || offset > start_of_eoie || checksum.len() != gix_hash::Kind::Sha1.len_in_bytes() {
+    if offset < header::SIZE || offset > start_of_eoie || checksum.len() != hash_len {
         return None;
     }
 
-    let mut hasher = gix_features::hash::hasher(gix_hash::Kind::Sha1);
+    let mut hasher = gix_features::hash::hasher(object_hash);
     let mut last_chunk = None;
-    for (signature, chunk) in extension::Iter::new(&data[offset..data.len() - MIN_SIZE_WITH_HEADER - hash_len]) {
+    for (signature, chunk) in extension::Iter::new(&data[offset..start_of_eoie]) {
         hasher.update(&signature);
         hasher.update(&(chunk.len() as u32).to_be_bytes());
         last_chunk = Some(chunk);
     }
 
-    if hasher.digest() != checksum {
+    if hasher.digest().as_slice() != checksum {
         return None;
     }
     // The last-to-this chunk ends where ours starts
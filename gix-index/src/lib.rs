@@ -38,6 +38,9 @@ pub mod verify;
 ///
 pub mod write;
 
+///
+pub mod write_tree;
+
 pub mod fs;
 
 /// All known versions of a git index file.
@@ -240,7 +243,7 @@ pub(crate) mod util {
 
 #[test]
 fn size_of_entry() {
-    assert_eq!(std::mem::size_of::<crate::Entry>(), 80);
+    assert_eq!(std::mem::size_of::<crate::Entry>(), 96);
 
     // the reason we have our own time is half the size.
     assert_eq!(std::mem::size_of::<crate::entry::stat::Time>(), 8);
@@ -0,0 +1,190 @@
+use bstr::{BStr, BString, ByteSlice};
+use gix_hash::ObjectId;
+use gix_object::tree::EntryKind;
+
+use crate::{entry, extension, Entry, PathStorageRef, State};
+
+/// The error returned by [`State::write_tree()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error<E: std::error::Error + Send + Sync + 'static> {
+    #[error("Cannot write a tree from an index with unmerged entries, like the one at '{path}'")]
+    Unmerged { path: BString },
+    #[error("The mode {mode:o} of the entry at '{path}' can't be represented in a tree")]
+    InvalidMode { path: BString, mode: u32 },
+    #[error("Failed to write a tree")]
+    Write(#[source] E),
+}
+
+/// Writing trees
+impl State {
+    /// Create a tree from all entries in this index by passing it and all its sub-trees to `out`, which writes them
+    /// and returns their id, similar to `git write-tree`. The id of the root tree is returned.
+    ///
+    /// Entries that are marked for removal or are only intended to be added are ignored, and unmerged entries
+    /// are an error. Directories that end up without any entry aren't written.
+    ///
+    /// Trees in the `TREE` extension which are still valid are used instead of writing them again, and the extension
+    /// is refreshed to reflect the written trees, so it should be written along with the index to speed up future calls.
+    /// Note that this is only correct if all changes made through [`entries_mut()`](State::entries_mut()) and similar
+    /// were followed by a call to [`invalidate_tree_extension()`](State::invalidate_tree_extension()).
+    pub fn write_tree<E>(
+        &mut self,
+        mut out: impl FnMut(&gix_object::Tree) -> Result<ObjectId, E>,
+    ) -> Result<ObjectId, Error<E>>
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let _span = gix_features::trace::coarse!("gix_index::State::write_tree()", num_entries = self.entries.len());
+        if let Some(tree) = self
+            .tree
+            .as_ref()
+            .filter(|tree| tree.num_entries == Some(self.entries.len() as u32))
+        {
+            return Ok(tree.id);
+        }
+        let mut root = write_recursive(&self.entries, &self.path_backing, 0, self.tree.as_ref(), &mut out)?;
+        let id = match root.take() {
+            Some(root) => {
+                let id = root.id;
+                self.tree = Some(root);
+                id
+            }
+            None => {
+                let id = out(&gix_object::Tree::empty()).map_err(Error::Write)?;
+                self.tree = Some(extension::Tree {
+                    name: Default::default(),
+                    id,
+                    // Entries that are only intended to be added might be added for real later.
+                    num_entries: self.entries.is_empty().then_some(0),
+                    children: Vec::new(),
+                });
+                id
+            }
+        };
+        Ok(id)
+    }
+
+    /// Mark all trees in the `TREE` extension that contain `path` as invalid, so they are written again
+    /// by [`write_tree()`](State::write_tree()).
+    ///
+    /// This is done automatically when adding or removing entries, but must be done manually after changing the id or mode
+    /// of entries in place.
+    pub fn invalidate_tree_extension(&mut self, path: &BStr) {
+        if let Some(tree) = self.tree.as_mut() {
+            tree.invalidate(path);
+        }
+    }
+}
+
+impl extension::Tree {
+    /// Mark this tree and all of its children on the way to `path` as invalid.
+    pub(crate) fn invalidate(&mut self, path: &BStr) {
+        let mut tree = self;
+        let mut components = path.split_str("/").peekable();
+        loop {
+            tree.num_entries = None;
+            let Some(name) = components.next() else { break };
+            if components.peek().is_none() {
+                break;
+            }
+            match tree.children.binary_search_by(|child| child.name.as_slice().cmp(name)) {
+                Ok(idx) => tree = &mut tree.children[idx],
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+/// Write the tree of all `entries` which share a directory path of `prefix_len` bytes, using the still valid
+/// trees of `cache` for that directory, and return its `TREE` extension node, or `None` if the tree would be empty.
+fn write_recursive<E>(
+    entries: &[Entry],
+    path_backing: &PathStorageRef,
+    prefix_len: usize,
+    cache: Option<&extension::Tree>,
+    out: &mut impl FnMut(&gix_object::Tree) -> Result<ObjectId, E>,
+) -> Result<Option<extension::Tree>, Error<E>>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let mut tree = gix_object::Tree::empty();
+    let mut children = Vec::new();
+    let mut is_valid = true;
+    let mut idx = 0;
+    while let Some(entry) = entries.get(idx) {
+        let path = entry.path_in(path_backing);
+        let rela_path = &path[prefix_len..];
+        match rela_path.find_byte(b'/').filter(|pos| *pos + 1 != rela_path.len()) {
+            Some(pos) => {
+                let name = &rela_path[..pos];
+                let dir_len = prefix_len + pos + 1;
+                let dir_path = &path[..dir_len];
+                let num_entries =
+                    entries[idx..].partition_point(|entry| entry.path_in(path_backing).starts_with(dir_path));
+                let dir_entries = &entries[idx..][..num_entries];
+                idx += num_entries;
+
+                let cached = cache.and_then(|cache| {
+                    cache
+                        .children
+                        .binary_search_by(|child| child.name.as_slice().cmp(name))
+                        .ok()
+                        .map(|child_idx| &cache.children[child_idx])
+                });
+                let child = match cached.filter(|child| child.num_entries == Some(num_entries as u32)) {
+                    Some(child) => Some(child.clone()),
+                    None => write_recursive(dir_entries, path_backing, dir_len, cached, out)?,
+                };
+                if let Some(mut child) = child {
+                    is_valid &= child.num_entries.is_some();
+                    child.name = name.as_bytes().into();
+                    tree.entries.push(gix_object::tree::Entry {
+                        mode: EntryKind::Tree.into(),
+                        filename: name.into(),
+                        oid: child.id,
+                    });
+                    children.push(child);
+                }
+            }
+            None => {
+                idx += 1;
+                if entry.stage() != entry::Stage::Unconflicted {
+                    return Err(Error::Unmerged { path: path.to_owned() });
+                }
+                if entry
+                    .flags
+                    .intersects(entry::Flags::REMOVE | entry::Flags::INTENT_TO_ADD)
+                {
+                    // Like `git`, make sure the tree is written again to pick up intended additions once they are real.
+                    is_valid = false;
+                    continue;
+                }
+                let mode = entry.mode.to_tree_entry_mode().ok_or_else(|| Error::InvalidMode {
+                    path: path.to_owned(),
+                    mode: entry.mode.bits(),
+                })?;
+                let filename = rela_path.strip_suffix(b"/").unwrap_or(rela_path);
+                tree.entries.push(gix_object::tree::Entry {
+                    mode,
+                    filename: filename.into(),
+                    oid: entry.id,
+                });
+            }
+        }
+    }
+
+    if tree.entries.is_empty() {
+        return Ok(None);
+    }
+    // The index is sorted by path, which is the order of entries in trees as well, but children in the extension
+    // are sorted by name alone.
+    children.sort_by(|a: &extension::Tree, b| a.name.cmp(&b.name));
+    let id = out(&tree).map_err(Error::Write)?;
+    Ok(Some(extension::Tree {
+        name: Default::default(),
+        id,
+        num_entries: is_valid.then_some(entries.len() as u32),
+        children,
+    }))
+}
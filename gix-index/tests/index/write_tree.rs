@@ -0,0 +1,171 @@
+use std::{cell::Cell, path::Path};
+
+use bstr::ByteSlice;
+use gix_hash::ObjectId;
+use gix_index::{entry, State};
+use gix_object::{FindExt, WriteTo};
+use gix_testtools::scripted_fixture_read_only_standalone;
+
+/// Compute the id of each tree passed to it without writing it, and count how many trees it saw.
+#[derive(Default)]
+struct HashOnly {
+    num_writes: Cell<usize>,
+}
+
+impl HashOnly {
+    fn write(&self, tree: &gix_object::Tree) -> Result<ObjectId, std::io::Error> {
+        let mut buf = Vec::new();
+        tree.write_to(&mut buf)?;
+        self.num_writes.set(self.num_writes.get() + 1);
+        Ok(gix_object::compute_hash(
+            gix_hash::Kind::Sha1,
+            gix_object::Kind::Tree,
+            &buf,
+        ))
+    }
+
+    fn num_writes(&self) -> usize {
+        self.num_writes.take()
+    }
+}
+
+fn head_tree(worktree_dir: &Path) -> crate::Result<ObjectId> {
+    let git_dir = worktree_dir.join(".git");
+    let head = std::fs::read(git_dir.join("HEAD"))?;
+    let head_ref = head.trim().strip_prefix(b"ref: ").expect("symbolic HEAD");
+    let commit_id: ObjectId = std::fs::read_to_string(git_dir.join(head_ref.to_path()?))?
+        .trim()
+        .parse()?;
+    let odb = gix_odb::at(git_dir.join("objects"))?;
+    let mut buf = Vec::new();
+    let tree = odb.find_commit(&commit_id, &mut buf)?.tree();
+    Ok(tree)
+}
+
+/// Create a copy of `state` without any extension.
+fn without_extensions(state: &State) -> State {
+    let mut out = State::new(state.object_hash());
+    for entry in state.entries() {
+        out.dangerously_push_entry(entry.stat, entry.id, entry.flags, entry.mode, entry.path(state));
+    }
+    out
+}
+
+#[test]
+fn matches_the_tree_of_head_with_and_without_tree_extension() -> crate::Result {
+    for fixture in [
+        "v2",
+        "v2_more_files",
+        "v2_all_file_kinds",
+        "v2_deeper_tree",
+        "v4_more_files_IEOT",
+        "v3_sparse_index",
+    ] {
+        let worktree_dir =
+            scripted_fixture_read_only_standalone(Path::new("make_index").join(fixture).with_extension("sh"))?;
+        let expected = head_tree(&worktree_dir)?;
+        let mut state: State = gix_index::File::at(
+            worktree_dir.join(".git").join("index"),
+            gix_hash::Kind::Sha1,
+            false,
+            Default::default(),
+        )?
+        .into();
+
+        let out = HashOnly::default();
+        assert_eq!(state.write_tree(|tree| out.write(tree))?, expected, "{fixture}");
+
+        let mut state = without_extensions(&state);
+        assert_eq!(state.write_tree(|tree| out.write(tree))?, expected, "{fixture}");
+        assert!(
+            out.num_writes() > 0,
+            "{fixture}: without extension, trees have to be written"
+        );
+        state.verify_extensions(false, gix_object::find::Never)?;
+
+        assert_eq!(state.write_tree(|tree| out.write(tree))?, expected, "{fixture}");
+        assert_eq!(out.num_writes(), 0, "{fixture}: the refreshed extension is used");
+    }
+    Ok(())
+}
+
+#[test]
+fn only_invalidated_trees_are_written_again() -> crate::Result {
+    let worktree_dir = scripted_fixture_read_only_standalone("make_index/v3_sparse_index.sh")?;
+    let odb = gix_odb::at(worktree_dir.join(".git").join("objects"))?;
+    let mut state = State::from_tree(&head_tree(&worktree_dir)?, &odb, Default::default())?;
+    let out = HashOnly::default();
+    let tree = state.write_tree(|tree| out.write(tree))?;
+    assert_eq!(out.num_writes(), 6, "all trees are written initially");
+
+    let id = state.entries()[0].id;
+    state.dangerously_push_entry(
+        Default::default(),
+        id,
+        entry::Flags::empty(),
+        entry::Mode::FILE,
+        "d/c4/new".into(),
+    );
+    state.sort_entries();
+    let new_tree = state.write_tree(|tree| out.write(tree))?;
+    assert_ne!(new_tree, tree);
+    assert_eq!(out.num_writes(), 3, "d/c4, d and the root tree are written");
+    assert_eq!(
+        new_tree,
+        without_extensions(&state).write_tree(|tree| out.write(tree))?,
+        "the result is the same as if computed from scratch"
+    );
+
+    state.remove_entries(|_, path, _| path == "d/c4/new");
+    assert_eq!(state.write_tree(|tree| out.write(tree))?, tree);
+
+    let idx = state.entry_index_by_path("c1/c2/a".into()).expect("present");
+    state.entries_mut()[idx].mode = entry::Mode::FILE_EXECUTABLE;
+    state.invalidate_tree_extension("c1/c2/a".into());
+    out.num_writes();
+    assert_ne!(state.write_tree(|tree| out.write(tree))?, tree);
+    assert_eq!(out.num_writes(), 3, "c1/c2, c1 and the root tree are written");
+    Ok(())
+}
+
+#[test]
+fn intent_to_add_entries_are_ignored() -> crate::Result {
+    let worktree_dir = scripted_fixture_read_only_standalone("make_index/v3_added_files.sh")?;
+    let mut state: State = gix_index::File::at(
+        worktree_dir.join(".git").join("index"),
+        gix_hash::Kind::Sha1,
+        false,
+        Default::default(),
+    )?
+    .into();
+    assert!(state.entries()[0].flags.contains(entry::Flags::INTENT_TO_ADD));
+    let out = HashOnly::default();
+    assert_eq!(
+        state.write_tree(|tree| out.write(tree))?,
+        ObjectId::empty_tree(gix_hash::Kind::Sha1)
+    );
+    assert_eq!(
+        state.tree().expect("written").num_entries,
+        None,
+        "the tree is invalid to write it again once the entry is added for real"
+    );
+    Ok(())
+}
+
+#[test]
+fn unmerged_entries_are_an_error() {
+    let mut state = State::new(gix_hash::Kind::Sha1);
+    state.dangerously_push_entry(
+        Default::default(),
+        gix_hash::Kind::Sha1.null(),
+        entry::Flags::from_bits_retain(2 << 12),
+        entry::Mode::FILE,
+        "dir/file".into(),
+    );
+    let out = HashOnly::default();
+    let err = state.write_tree(|tree| out.write(tree)).unwrap_err();
+    assert!(matches!(
+        err,
+        gix_index::write_tree::Error::Unmerged { path } if path == "dir/file"
+    ));
+}
@@ -229,6 +229,8 @@ pub struct TreeRef<'a> {
 pub struct TreeRefIter<'a> {
     /// The directories and files contained in this tree.
     data: &'a [u8],
+    /// The kind of hash used by all entries, which determines their length.
+    hash_kind: gix_hash::Kind,
 }
 
 /// A mutable Tree, containing other trees, blobs or commits.
@@ -253,6 +255,8 @@ impl Tree {
 pub struct Data<'a> {
     /// kind of object
     pub kind: Kind,
+    /// The kind of hash used by all object ids referenced by the object, needed to decode trees.
+    pub hash_kind: gix_hash::Kind,
     /// decoded, decompressed data, owned by a backing store.
     pub data: &'a [u8],
 }
@@ -401,7 +405,22 @@ pub fn compute_hash(hash_kind: gix_hash::Kind, object_kind: Kind, data: &[u8]) -
     hasher.update(&header);
     hasher.update(data);
 
-    hasher.digest().into()
+    hasher.digest()
+}
+
+/// Like [`compute_hash()`], but fails if `data` is part of a hash collision attack.
+pub fn try_compute_hash(
+    hash_kind: gix_hash::Kind,
+    object_kind: Kind,
+    data: &[u8],
+) -> Result<gix_hash::ObjectId, gix_features::hash::Error> {
+    let header = encode::loose_header(object_kind, data.len() as u64);
+
+    let mut hasher = gix_features::hash::hasher(hash_kind);
+    hasher.update(&header);
+    hasher.update(data);
+
+    hasher.try_digest()
 }
 
 /// A function to compute a hash of kind `hash_kind` for an object of `object_kind` and its data read from `stream`
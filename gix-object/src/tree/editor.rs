@@ -0,0 +1,249 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use gix_hash::ObjectId;
+
+use crate::{
+    tree::{Entry, EntryKind},
+    FindExt, Tree,
+};
+
+/// The error returned by [`Editor::upsert()`] and [`Editor::remove()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Paths must not be empty and must not contain empty path components")]
+    EmptyPathComponent,
+    #[error("The path component {component:?} must not contain a slash")]
+    PathComponentWithSlash { component: BString },
+    #[error(transparent)]
+    FindExistingObject(#[from] crate::find::existing_object::Error),
+}
+
+/// A way to edit a [`Tree`] at arbitrary depths by inserting, replacing and removing entries at nested paths,
+/// and to write only the trees that were affected by these edits.
+///
+/// Sub-trees are loaded lazily from the object database once an edit has to pass through them, and are kept in memory
+/// until they are [written](Self::write()).
+pub struct Editor<'a> {
+    /// The object database to load the trees we have to pass through.
+    find: &'a dyn crate::Find,
+    /// The kind of hash used in all trees, needed to produce the null id of trees that aren't written yet.
+    object_hash: gix_hash::Kind,
+    /// All trees that are loaded for editing, keyed by their slash-separated path relative to the root,
+    /// with the root itself at the empty path.
+    trees: HashMap<BString, Tree>,
+    /// A buffer to hold the data of trees as they are loaded.
+    tree_buf: Vec<u8>,
+}
+
+/// Lifecycle
+impl<'a> Editor<'a> {
+    /// Create a new editor that starts with `root`, using `find` to lookup sub-trees as needed, whose ids are of
+    /// kind `object_hash`.
+    pub fn new(root: Tree, find: &'a dyn crate::Find, object_hash: gix_hash::Kind) -> Self {
+        Editor {
+            find,
+            object_hash,
+            trees: HashMap::from_iter(Some((BString::default(), root))),
+            tree_buf: Vec::new(),
+        }
+    }
+
+    /// Discard all edits and start over with `root` as the tree to edit.
+    pub fn set_root(&mut self, root: Tree) -> &mut Self {
+        self.trees.clear();
+        self.trees.insert(BString::default(), root);
+        self
+    }
+}
+
+/// Editing
+impl Editor<'_> {
+    /// Insert a new entry of `kind` with `id` at `rela_path`, a path given by its components relative to the root tree,
+    /// or replace the entry that is already there.
+    ///
+    /// Intermediate trees are created as needed, and existing entries in the way that aren't trees are replaced by trees.
+    /// If `kind` is [`EntryKind::Tree`], `id` is assumed to refer to an existing tree which replaces everything previously
+    /// known at `rela_path`.
+    pub fn upsert<I, C>(&mut self, rela_path: I, kind: EntryKind, id: ObjectId) -> Result<&mut Self, Error>
+    where
+        I: IntoIterator<Item = C>,
+        C: AsRef<BStr>,
+    {
+        self.upsert_or_remove_at_path(rela_path, Some((kind, id)))
+    }
+
+    /// Remove the entry at `rela_path`, a path given by its components relative to the root tree, along with all of its
+    /// children if it is a tree.
+    ///
+    /// Nothing happens if there is no such entry. Trees which become empty this way are removed when [writing](Self::write()).
+    pub fn remove<I, C>(&mut self, rela_path: I) -> Result<&mut Self, Error>
+    where
+        I: IntoIterator<Item = C>,
+        C: AsRef<BStr>,
+    {
+        self.upsert_or_remove_at_path(rela_path, None)
+    }
+
+    /// Write all trees that were affected by edits, deepest trees first, by passing them to `out` which returns their id,
+    /// and return the id of the root tree.
+    ///
+    /// Trees that became empty are removed from their parent instead of being written, except for the root tree which
+    /// is always written. Afterwards, the editor is positioned at the written root tree to allow more edits.
+    pub fn write<E>(&mut self, mut out: impl FnMut(&Tree) -> Result<ObjectId, E>) -> Result<ObjectId, E> {
+        let mut paths: Vec<_> = self.trees.keys().filter(|path| !path.is_empty()).cloned().collect();
+        paths.sort_by_key(|path| std::cmp::Reverse(path.find_iter("/").count()));
+
+        for path in paths {
+            let tree = self.trees.get(&path).expect("path was obtained from the map");
+            let id = if tree.entries.is_empty() {
+                None
+            } else {
+                Some(out(tree)?)
+            };
+            let (parent_path, name) = match path.rfind_byte(b'/') {
+                Some(pos) => (path[..pos].as_bstr(), path[pos + 1..].as_bstr()),
+                None => ("".into(), path.as_bstr()),
+            };
+            let Some(parent) = self.trees.get_mut(parent_path) else {
+                continue;
+            };
+            if let Some(idx) = find_entry(parent, name) {
+                match id {
+                    Some(id) => parent.entries[idx].oid = id,
+                    None => {
+                        parent.entries.remove(idx);
+                    }
+                }
+            }
+            self.trees.remove(&path);
+        }
+
+        let root = self.trees.get(BStr::new("")).expect("root is always present");
+        out(root)
+    }
+
+    fn upsert_or_remove_at_path<I, C>(
+        &mut self,
+        rela_path: I,
+        value: Option<(EntryKind, ObjectId)>,
+    ) -> Result<&mut Self, Error>
+    where
+        I: IntoIterator<Item = C>,
+        C: AsRef<BStr>,
+    {
+        let mut cursor = BString::default();
+        let mut rela_path = rela_path.into_iter().peekable();
+        if rela_path.peek().is_none() {
+            return Err(Error::EmptyPathComponent);
+        }
+        while let Some(name) = rela_path.next() {
+            let name = name.as_ref();
+            if name.is_empty() {
+                return Err(Error::EmptyPathComponent);
+            }
+            if name.contains(&b'/') {
+                return Err(Error::PathComponentWithSlash {
+                    component: name.to_owned(),
+                });
+            }
+            let is_last = rela_path.peek().is_none();
+            let tree = self.trees.get_mut(&cursor).expect("every tree on the path is loaded");
+            let idx = find_entry(tree, name);
+            let parent = std::mem::take(&mut cursor);
+            cursor = join(parent.as_bstr(), name);
+
+            if is_last {
+                if let Some(idx) = idx {
+                    if tree.entries.remove(idx).mode.is_tree() {
+                        drop_subtrees(&mut self.trees, cursor.as_bstr());
+                    }
+                }
+                if let Some((kind, id)) = value {
+                    let tree = self.trees.get_mut(&parent).expect("still present");
+                    insert_sorted(
+                        tree,
+                        Entry {
+                            mode: kind.into(),
+                            filename: name.to_owned(),
+                            oid: id,
+                        },
+                    );
+                }
+                break;
+            }
+
+            match idx.map(|idx| (idx, tree.entries[idx].mode.is_tree(), tree.entries[idx].oid)) {
+                Some((_, true, id)) => {
+                    if !self.trees.contains_key(&cursor) {
+                        let subtree = self.find.find_tree(&id, &mut self.tree_buf)?.into();
+                        self.trees.insert(cursor.clone(), subtree);
+                    }
+                }
+                Some(_) | None if value.is_none() => break,
+                Some((idx, false, _)) => {
+                    tree.entries.remove(idx);
+                    self.insert_empty_tree(parent.as_bstr(), name, &cursor);
+                }
+                None => self.insert_empty_tree(parent.as_bstr(), name, &cursor),
+            }
+        }
+        Ok(self)
+    }
+
+    fn insert_empty_tree(&mut self, parent: &BStr, name: &BStr, path: &BString) {
+        let tree = self.trees.get_mut(parent).expect("parent was loaded");
+        insert_sorted(
+            tree,
+            Entry {
+                mode: EntryKind::Tree.into(),
+                filename: name.to_owned(),
+                oid: self.object_hash.null(),
+            },
+        );
+        self.trees.insert(path.clone(), Tree::empty());
+    }
+}
+
+/// Return the index of the entry named `name` in `tree`, no matter what kind it is.
+fn find_entry(tree: &Tree, name: &BStr) -> Option<usize> {
+    [false, true].into_iter().find_map(|is_tree| {
+        tree.entries
+            .binary_search_by(|entry| cmp_entry_with_name(entry, name, is_tree))
+            .ok()
+    })
+}
+
+/// Compare `entry` with an entry named `name` which is a tree if `is_tree` is `true`, using the order of entries in trees.
+fn cmp_entry_with_name(entry: &Entry, name: &BStr, is_tree: bool) -> Ordering {
+    let common = entry.filename.len().min(name.len());
+    entry.filename[..common].cmp(&name[..common]).then_with(|| {
+        let a = entry
+            .filename
+            .get(common)
+            .or_else(|| entry.mode.is_tree().then_some(&b'/'));
+        let b = name.get(common).or_else(|| is_tree.then_some(&b'/'));
+        a.cmp(&b)
+    })
+}
+
+fn insert_sorted(tree: &mut Tree, entry: Entry) {
+    let idx = tree.entries.binary_search(&entry).unwrap_or_else(|idx| idx);
+    tree.entries.insert(idx, entry);
+}
+
+fn join(parent: &BStr, name: &BStr) -> BString {
+    let mut path = BString::from(Vec::with_capacity(parent.len() + 1 + name.len()));
+    if !parent.is_empty() {
+        path.push_str(parent);
+        path.push_byte(b'/');
+    }
+    path.push_str(name);
+    path
+}
+
+/// Remove the tree at `path` and all trees below it, as they were replaced or removed.
+fn drop_subtrees(trees: &mut HashMap<BString, Tree>, path: &BStr) {
+    trees.retain(|key, _| !(key.as_bstr() == path || (key.starts_with(path) && key.get(path.len()) == Some(&b'/'))));
+}
@@ -5,6 +5,10 @@ use crate::{
     tree,
 };
 
+///
+pub mod editor;
+pub use editor::Editor;
+
 mod ref_iter;
 ///
 pub mod write;
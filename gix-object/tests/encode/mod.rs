@@ -7,7 +7,7 @@ enum Error {
 }
 
 macro_rules! round_trip {
-    ($owned:ty, $borrowed:ty, $( $files:literal ), +) => {
+    ($owned:ty, $borrowed:ty, $kind:expr, $( $files:literal ), +) => {
         #[test]
         fn round_trip() -> Result<(), Box<dyn std::error::Error>> {
             use std::convert::TryFrom;
@@ -20,9 +20,10 @@ macro_rules! round_trip {
                 $( $files ),*
             ] {
                 let input = fixture_bytes(input);
-                // Test the parse->borrowed->owned->write chain for an object kind
                 let mut output = Vec::new();
-                let item = <$borrowed>::from_bytes(&input)?;
+                let object = ObjectRef::from_bytes($kind, &input, gix_hash::Kind::Sha1)?;
+                // Test the parse->borrowed->owned->write chain for an object kind
+                let item = <$borrowed>::try_from(object.clone()).or(Err(super::Error::TryFromError))?;
                 item.write_to(&mut output)?;
                 assert_eq!(output.as_bstr(), input.as_bstr());
 
@@ -32,7 +33,7 @@ macro_rules! round_trip {
                 assert_eq!(output.as_bstr(), input.as_bstr());
 
                 // Test the parse->borrowed->owned->write chain for the top-level objects
-                let item = ObjectRef::from(<$borrowed>::from_bytes(&input)?);
+                let item = object.clone();
                 output.clear();
                 item.write_to(&mut output)?;
                 assert_eq!(output.as_bstr(), input.as_bstr());
@@ -43,13 +44,13 @@ macro_rules! round_trip {
                 assert_eq!(output.as_bstr(), input.as_bstr());
 
                 // Test the loose serialisation -> parse chain for an object kind
-                let item = <$borrowed>::from_bytes(&input)?;
+                let item = <$borrowed>::try_from(object).or(Err(super::Error::TryFromError))?;
                 output.clear();
                 // serialise to a tagged loose object
                 let w = &mut output;
                 w.write_all(&item.loose_header())?;
                 item.write_to(w)?;
-                let parsed = ObjectRef::from_loose(&output)?;
+                let parsed = ObjectRef::from_loose(&output, gix_hash::Kind::Sha1)?;
                 let item2 = <$borrowed>::try_from(parsed).or(Err(super::Error::TryFromError))?;
                 assert_eq!(item2, item);
             }
@@ -62,6 +63,7 @@ mod tag {
     round_trip!(
         gix_object::Tag,
         gix_object::TagRef,
+        gix_object::Kind::Tag,
         "tag/empty.txt",
         "tag/no-tagger.txt",
         "tag/whitespace.txt",
@@ -74,6 +76,7 @@ mod commit {
     round_trip!(
         gix_object::Commit,
         gix_object::CommitRef,
+        gix_object::Kind::Commit,
         "commit/signed-whitespace.txt",
         "commit/two-multiline-headers.txt",
         "commit/mergetag.txt",
@@ -88,12 +91,22 @@ mod commit {
 }
 
 mod tree {
-    round_trip!(gix_object::Tree, gix_object::TreeRef, "tree/everything.tree");
+    round_trip!(
+        gix_object::Tree,
+        gix_object::TreeRef,
+        gix_object::Kind::Tree,
+        "tree/everything.tree"
+    );
 }
 
 mod blob {
     // It doesn't matter which data we use - it's not interpreted.
-    round_trip!(gix_object::Blob, gix_object::BlobRef, "tree/everything.tree");
+    round_trip!(
+        gix_object::Blob,
+        gix_object::BlobRef,
+        gix_object::Kind::Blob,
+        "tree/everything.tree"
+    );
 }
 
 mod loose_header {
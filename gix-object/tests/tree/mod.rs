@@ -5,13 +5,17 @@ mod iter {
 
     #[test]
     fn empty() {
-        assert_eq!(TreeRefIter::from_bytes(&[]).count(), 0, "empty trees are definitely ok");
+        assert_eq!(
+            TreeRefIter::from_bytes(&[], gix_hash::Kind::Sha1).count(),
+            0,
+            "empty trees are definitely ok"
+        );
     }
 
     #[test]
     fn error_handling() {
         let data = fixture_name("tree", "everything.tree");
-        let iter = TreeRefIter::from_bytes(&data[..data.len() / 2]);
+        let iter = TreeRefIter::from_bytes(&data[..data.len() / 2], gix_hash::Kind::Sha1);
         let entries = iter.collect::<Vec<_>>();
         assert!(
             entries.last().expect("at least one token").is_err(),
@@ -22,7 +26,8 @@ mod iter {
     #[test]
     fn everything() -> crate::Result {
         assert_eq!(
-            TreeRefIter::from_bytes(&fixture_name("tree", "everything.tree")).collect::<Result<Vec<_>, _>>()?,
+            TreeRefIter::from_bytes(&fixture_name("tree", "everything.tree"), gix_hash::Kind::Sha1)
+                .collect::<Result<Vec<_>, _>>()?,
             vec![
                 EntryRef {
                     mode: tree::EntryKind::BlobExecutable.into(),
@@ -63,7 +68,7 @@ mod from_bytes {
     #[test]
     fn empty() -> crate::Result {
         assert_eq!(
-            TreeRef::from_bytes(&[])?,
+            TreeRef::from_bytes(&[], gix_hash::Kind::Sha1)?,
             TreeRef { entries: vec![] },
             "empty trees are valid despite usually rare in the wild"
         );
@@ -73,7 +78,7 @@ mod from_bytes {
     #[test]
     fn everything() -> crate::Result {
         assert_eq!(
-            TreeRef::from_bytes(&fixture_name("tree", "everything.tree"))?,
+            TreeRef::from_bytes(&fixture_name("tree", "everything.tree"), gix_hash::Kind::Sha1)?,
             TreeRef {
                 entries: vec![
                     EntryRef {
@@ -111,14 +116,18 @@ mod from_bytes {
     fn invalid() {
         let fixture = fixture_name("tree", "definitely-special.tree");
         let partial_tree = &fixture[..fixture.len() / 2];
-        let err = TreeRef::from_bytes(partial_tree).unwrap_err().to_string();
+        let err = TreeRef::from_bytes(partial_tree, gix_hash::Kind::Sha1)
+            .unwrap_err()
+            .to_string();
         if cfg!(feature = "verbose-object-parsing-errors") {
             assert!(err.starts_with("object parsing failed at `100644"), "{err}");
         } else {
             assert_eq!(err, "object parsing failed");
         }
         assert_eq!(
-            TreeRefIter::from_bytes(partial_tree).take_while(Result::is_ok).count(),
+            TreeRefIter::from_bytes(partial_tree, gix_hash::Kind::Sha1)
+                .take_while(Result::is_ok)
+                .count(),
             9,
             "we can decode about half of it before failing"
         );
@@ -126,7 +135,10 @@ mod from_bytes {
 
     #[test]
     fn fuzzed() {
-        assert!(gix_object::TreeRef::from_bytes(b"2").is_err(), "fail, but don't crash");
+        assert!(
+            gix_object::TreeRef::from_bytes(b"2", gix_hash::Kind::Sha1).is_err(),
+            "fail, but don't crash"
+        );
     }
 
     #[test]
@@ -142,12 +154,14 @@ mod from_bytes {
         ] {
             let fixture = fixture_name("tree", &format!("{name}.tree"));
             assert_eq!(
-                TreeRef::from_bytes(&fixture)?.entries.len(),
+                TreeRef::from_bytes(&fixture, gix_hash::Kind::Sha1)?.entries.len(),
                 expected_entry_count,
                 "{name}"
             );
             assert_eq!(
-                TreeRefIter::from_bytes(&fixture).map(Result::unwrap).count(),
+                TreeRefIter::from_bytes(&fixture, gix_hash::Kind::Sha1)
+                    .map(Result::unwrap)
+                    .count(),
                 expected_entry_count,
                 "{name}"
             );
@@ -164,7 +178,7 @@ mod entries {
         let root = gix_testtools::scripted_fixture_read_only("make_trees.sh")?;
         let input = std::fs::read(root.join("tree.baseline"))?;
 
-        let mut tree = TreeRef::from_bytes(&input)?;
+        let mut tree = TreeRef::from_bytes(&input, gix_hash::Kind::Sha1)?;
         let expected = tree.entries.clone();
 
         tree.entries.sort();
@@ -274,3 +288,203 @@ mod entry_mode {
         }
     }
 }
+
+mod editor {
+    use std::{cell::RefCell, collections::HashMap};
+
+    use gix_hash::ObjectId;
+    use gix_object::{
+        tree::{editor, EntryKind},
+        Tree, WriteTo,
+    };
+
+    /// An in-memory object database which keeps track of the trees written to it.
+    #[derive(Default)]
+    struct Odb {
+        trees: RefCell<HashMap<ObjectId, Vec<u8>>>,
+        num_writes: RefCell<usize>,
+    }
+
+    impl gix_object::Find for Odb {
+        fn try_find<'a>(
+            &self,
+            id: &gix_hash::oid,
+            buffer: &'a mut Vec<u8>,
+        ) -> Result<Option<gix_object::Data<'a>>, gix_object::find::Error> {
+            let trees = self.trees.borrow();
+            Ok(trees.get(id).map(|data| {
+                buffer.clear();
+                buffer.extend_from_slice(data);
+                gix_object::Data::new(gix_object::Kind::Tree, buffer.as_slice(), gix_hash::Kind::Sha1)
+            }))
+        }
+    }
+
+    impl Odb {
+        fn write(&self, tree: &Tree) -> Result<ObjectId, std::io::Error> {
+            let mut buf = Vec::new();
+            tree.write_to(&mut buf)?;
+            let id = gix_object::compute_hash(gix_hash::Kind::Sha1, gix_object::Kind::Tree, &buf);
+            self.trees.borrow_mut().insert(id, buf);
+            *self.num_writes.borrow_mut() += 1;
+            Ok(id)
+        }
+
+        fn num_writes(&self) -> usize {
+            std::mem::take(&mut *self.num_writes.borrow_mut())
+        }
+
+        /// Return all entries of the tree at `id` recursively, as `<mode> <path>` in the order they are stored.
+        fn entries(&self, id: ObjectId) -> Vec<String> {
+            let mut out = Vec::new();
+            self.entries_inner(id, "", &mut out);
+            out
+        }
+
+        fn entries_inner(&self, id: ObjectId, prefix: &str, out: &mut Vec<String>) {
+            let data = self.trees.borrow().get(&id).cloned().expect("tree was written");
+            let tree = gix_object::TreeRef::from_bytes(&data, gix_hash::Kind::Sha1).expect("valid tree");
+            for entry in tree.entries {
+                let path = format!("{prefix}{}", entry.filename);
+                out.push(format!("{} {path}", entry.mode.kind().as_octal_str()));
+                if entry.mode.is_tree() {
+                    self.entries_inner(entry.oid.to_owned(), &format!("{path}/"), out);
+                }
+            }
+        }
+    }
+
+    fn blob(n: u8) -> ObjectId {
+        ObjectId::from_bytes_or_panic(&[n; 20])
+    }
+
+    #[test]
+    fn from_empty_tree_with_nested_paths_in_tree_order() -> crate::Result {
+        let odb = Odb::default();
+        let mut edit = gix_object::tree::Editor::new(Tree::empty(), &odb, gix_hash::Kind::Sha1);
+        edit.upsert(["a", "b", "c"], EntryKind::Blob, blob(1))?
+            .upsert(["a", "d"], EntryKind::BlobExecutable, blob(2))?
+            .upsert(["a.b"], EntryKind::Link, blob(3))?
+            .upsert(["a", "e"], EntryKind::Commit, blob(4))?;
+        let root = edit.write(|tree| odb.write(tree))?;
+        assert_eq!(
+            odb.entries(root),
+            [
+                "120000 a.b",
+                "40000 a",
+                "40000 a/b",
+                "100644 a/b/c",
+                "100755 a/d",
+                "160000 a/e"
+            ],
+            "trees sort as if they had a trailing slash"
+        );
+        assert_eq!(odb.num_writes(), 3);
+
+        edit.upsert(["a", "b", "c"], EntryKind::Blob, blob(5))?;
+        let new_root = edit.write(|tree| odb.write(tree))?;
+        assert_ne!(new_root, root);
+        assert_eq!(
+            odb.num_writes(),
+            3,
+            "all trees on the path to the changed entry are written"
+        );
+
+        edit.upsert(["a.b"], EntryKind::Blob, blob(6))?;
+        edit.write(|tree| odb.write(tree))?;
+        assert_eq!(odb.num_writes(), 1, "only the root tree is affected");
+        Ok(())
+    }
+
+    #[test]
+    fn replacing_entries_with_trees_and_back() -> crate::Result {
+        let odb = Odb::default();
+        let mut edit = gix_object::tree::Editor::new(Tree::empty(), &odb, gix_hash::Kind::Sha1);
+        edit.upsert(["file"], EntryKind::Blob, blob(1))?
+            .upsert(["dir", "a"], EntryKind::Blob, blob(2))?;
+        let root = edit.write(|tree| odb.write(tree))?;
+        let dir = odb.entries(root);
+        assert_eq!(dir, ["40000 dir", "100644 dir/a", "100644 file"]);
+
+        edit.upsert(["file", "nested"], EntryKind::Blob, blob(3))?
+            .upsert(["dir"], EntryKind::Blob, blob(4))?;
+        let root = edit.write(|tree| odb.write(tree))?;
+        assert_eq!(
+            odb.entries(root),
+            ["100644 dir", "40000 file", "100644 file/nested"],
+            "a blob in the way is replaced by a tree, and the other way around"
+        );
+
+        let mut edit = gix_object::tree::Editor::new(Tree::empty(), &odb, gix_hash::Kind::Sha1);
+        let subtree = edit
+            .upsert(["x"], EntryKind::Blob, blob(5))?
+            .write(|tree| odb.write(tree))?;
+        let mut edit = gix_object::tree::Editor::new(Tree::empty(), &odb, gix_hash::Kind::Sha1);
+        edit.upsert(["a", "b"], EntryKind::Tree, subtree)?
+            .upsert(["a", "b", "y"], EntryKind::Blob, blob(6))?;
+        let root = edit.write(|tree| odb.write(tree))?;
+        assert_eq!(
+            odb.entries(root),
+            ["40000 a", "40000 a/b", "100644 a/b/x", "100644 a/b/y"],
+            "existing trees are loaded to edit them"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn removals_also_remove_trees_that_become_empty() -> crate::Result {
+        let odb = Odb::default();
+        let mut edit = gix_object::tree::Editor::new(Tree::empty(), &odb, gix_hash::Kind::Sha1);
+        edit.upsert(["a", "b", "c"], EntryKind::Blob, blob(1))?
+            .upsert(["a", "d"], EntryKind::Blob, blob(2))?
+            .upsert(["e"], EntryKind::Blob, blob(3))?;
+        let root = edit.write(|tree| odb.write(tree))?;
+        odb.num_writes();
+
+        edit.remove(["a", "missing", "c"])?
+            .remove(["e", "not-a-tree"])?
+            .remove(["missing"])?;
+        assert_eq!(
+            edit.write(|tree| odb.write(tree))?,
+            root,
+            "removing what doesn't exist doesn't change anything"
+        );
+        odb.num_writes();
+
+        edit.remove(["a", "b", "c"])?;
+        let root = edit.write(|tree| odb.write(tree))?;
+        assert_eq!(odb.entries(root), ["40000 a", "100644 a/d", "100644 e"]);
+        assert_eq!(odb.num_writes(), 2, "the empty tree isn't written");
+
+        edit.remove(["a"])?.remove(["e"])?;
+        assert_eq!(
+            edit.write(|tree| odb.write(tree))?,
+            ObjectId::empty_tree(gix_hash::Kind::Sha1),
+            "the root is written even if empty"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_paths() {
+        let odb = Odb::default();
+        let mut edit = gix_object::tree::Editor::new(Tree::empty(), &odb, gix_hash::Kind::Sha1);
+        assert!(matches!(
+            edit.upsert(Vec::<&str>::new(), EntryKind::Blob, blob(1)),
+            Err(editor::Error::EmptyPathComponent)
+        ));
+        assert!(matches!(
+            edit.upsert(["a", ""], EntryKind::Blob, blob(1)),
+            Err(editor::Error::EmptyPathComponent)
+        ));
+        assert!(matches!(
+            edit.remove(["a/b"]),
+            Err(editor::Error::PathComponentWithSlash { .. })
+        ));
+        assert!(matches!(
+            edit.upsert(["missing"], EntryKind::Tree, blob(1))
+                .and_then(|edit| edit.upsert(["missing", "a"], EntryKind::Blob, blob(2))),
+            Err(editor::Error::FindExistingObject(_))
+        ));
+    }
+}
@@ -71,6 +71,9 @@ mod sink;
 ///
 pub mod find;
 
+pub mod promisor;
+pub use promisor::Promisor;
+
 /// An object database equivalent to `/dev/null`, dropping all objects stored into it.
 mod traits;
 
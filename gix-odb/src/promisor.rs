@@ -0,0 +1,15 @@
+//! Support for *partial clones*, whose object databases lack objects that a *promisor remote* promised to provide on demand.
+use gix_hash::ObjectId;
+
+/// The error returned by [`Promisor::fetch()`], which is opaque to the object database.
+pub type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// A way to obtain objects which are missing locally, but which a remote promised to provide, typically the remote
+/// a partial clone was created from.
+///
+/// It's set on a [handle](crate::store::Handle::promisor) to have it fetch objects it can't find locally.
+pub trait Promisor: Send + Sync {
+    /// Obtain all objects identified by `ids` in one go and make them available in the object database,
+    /// typically by writing a new pack into it.
+    fn fetch(&self, ids: &[ObjectId]) -> Result<(), Error>;
+}
@@ -44,6 +44,8 @@ pub(crate) mod error {
             /// The original object to lookup
             id: gix_hash::ObjectId,
         },
+        #[error("Could not fetch missing objects from the promisor")]
+        Promisor(#[source] crate::promisor::Error),
     }
 
     #[derive(Copy, Clone)]
@@ -111,6 +113,7 @@ where
             }
         }
 
+        let mut fetched_from_promisor = false;
         'outer: loop {
             {
                 let marker = snapshot.marker;
@@ -162,10 +165,7 @@ where
                         );
                         let res = match res {
                             Ok(r) => Ok((
-                                gix_object::Data {
-                                    kind: r.kind,
-                                    data: buffer.as_slice(),
-                                },
+                                gix_object::Data::new(r.kind, buffer.as_slice(), id.kind()),
                                 Some(gix_pack::data::entry::Location {
                                     pack_id: pack.id,
                                     pack_offset,
@@ -263,10 +263,7 @@ where
                                 )
                                 .map(move |r| {
                                     (
-                                        gix_object::Data {
-                                            kind: r.kind,
-                                            data: buffer.as_slice(),
-                                        },
+                                        gix_object::Data::new(r.kind, buffer.as_slice(), id.kind()),
                                         Some(gix_pack::data::entry::Location {
                                             pack_id: pack.id,
                                             pack_offset,
@@ -301,7 +298,15 @@ where
                     *snapshot = new_snapshot;
                     self.clear_cache();
                 }
-                None => return Ok(None),
+                None => {
+                    if recursion.is_none() && !fetched_from_promisor {
+                        fetched_from_promisor = true;
+                        if self.fetch_from_promisor(id, snapshot)? {
+                            continue;
+                        }
+                    }
+                    return Ok(None);
+                }
             }
         }
     }
@@ -1,5 +1,5 @@
 //! The standard object store which should fit all needs.
-use std::{cell::RefCell, ops::Deref};
+use std::{cell::RefCell, ops::Deref, sync::Arc};
 
 use gix_features::zlib;
 
@@ -24,6 +24,11 @@ where
     /// If true, replacements will not be performed even if these are available.
     pub ignore_replacements: bool,
 
+    /// If set, objects that can't be found locally will be fetched from it before looking them up again.
+    ///
+    /// This is what makes *partial clones* work.
+    pub promisor: Option<Arc<dyn crate::Promisor>>,
+
     pub(crate) token: Option<handle::Mode>,
     snapshot: RefCell<load_index::Snapshot>,
     inflate: RefCell<zlib::Inflate>,
@@ -80,6 +85,8 @@ pub mod verify;
 
 mod load_one;
 
+mod promisor;
+
 mod metrics;
 
 mod access;
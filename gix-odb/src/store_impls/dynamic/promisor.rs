@@ -0,0 +1,58 @@
+use std::ops::Deref;
+
+use gix_hash::ObjectId;
+
+use crate::store::{find::Error, load_index};
+
+impl<S> super::Handle<S>
+where
+    S: Deref<Target = super::Store> + Clone,
+{
+    /// Fetch all objects in `ids` that aren't present locally from our [`promisor`](Self::promisor) in a single batch,
+    /// which is much faster than fetching them one at a time as they are accessed.
+    ///
+    /// Return the number of objects that were missing and thus requested, which is always `0` if there is no promisor.
+    pub fn fetch_missing(&self, ids: impl IntoIterator<Item = ObjectId>) -> Result<usize, Error> {
+        let Some(promisor) = self.promisor.as_deref() else {
+            return Ok(0);
+        };
+        let mut missing: Vec<_> = ids
+            .into_iter()
+            .filter(|id| !gix_pack::Find::contains(self, id))
+            .collect();
+        missing.sort();
+        missing.dedup();
+        if missing.is_empty() {
+            return Ok(0);
+        }
+
+        promisor.fetch(&missing).map_err(Error::Promisor)?;
+        self.refresh_after_fetch(&mut self.snapshot.borrow_mut())?;
+        Ok(missing.len())
+    }
+
+    /// Fetch `id` from our promisor if we have one, after it couldn't be found locally, and return `true` if the lookup
+    /// should be retried as it might have been fetched.
+    pub(crate) fn fetch_from_promisor(
+        &self,
+        id: &gix_hash::oid,
+        snapshot: &mut load_index::Snapshot,
+    ) -> Result<bool, Error> {
+        let Some(promisor) = self.promisor.as_deref() else {
+            return Ok(false);
+        };
+        promisor.fetch(&[id.to_owned()]).map_err(Error::Promisor)?;
+        self.refresh_after_fetch(snapshot)?;
+        Ok(true)
+    }
+
+    /// Pick up the new pack even if our refresh mode wouldn't allow it.
+    fn refresh_after_fetch(&self, snapshot: &mut load_index::Snapshot) -> Result<(), Error> {
+        let needs_init = !self.store.index.load().is_initialized();
+        if let Some(new_snapshot) = self.store.consolidate_with_disk_state(needs_init, false)? {
+            *snapshot = new_snapshot;
+            self.clear_cache();
+        }
+        Ok(())
+    }
+}
@@ -776,6 +776,86 @@ fn missing_objects_triggers_everything_is_loaded() {
     );
 }
 
+#[test]
+fn missing_objects_are_fetched_from_the_promisor() -> crate::Result {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use gix_object::Find;
+
+    /// Copy the requested objects from our fixture into a loose object database.
+    struct CopyFromFixture {
+        destination: gix_odb::loose::Store,
+        calls: AtomicUsize,
+    }
+
+    impl gix_odb::Promisor for CopyFromFixture {
+        fn fetch(&self, ids: &[ObjectId]) -> Result<(), gix_odb::promisor::Error> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let source = gix_odb::at(fixture_path_standalone("objects"))?;
+            let mut buf = Vec::new();
+            for id in ids {
+                let obj = source.find(id, &mut buf)?;
+                self.destination.write_buf(obj.kind, obj.data)?;
+            }
+            Ok(())
+        }
+    }
+
+    let objects_dir = gix_testtools::tempfile::tempdir()?;
+    let mut handle = gix_odb::at(objects_dir.path())?;
+    let commit = hex_to_id("501b297447a8255d3533c6858bb692575cdefaa0");
+    let mut buf = Vec::new();
+    assert!(
+        handle.try_find(&commit, &mut buf)?.is_none(),
+        "the object isn't present locally"
+    );
+
+    let promisor = Arc::new(CopyFromFixture {
+        destination: gix_odb::loose::Store::at(objects_dir.path(), gix_hash::Kind::Sha1),
+        calls: AtomicUsize::default(),
+    });
+    handle.promisor = Some(promisor.clone());
+    assert!(!handle.exists(&commit), "checking for existence never fetches");
+
+    let obj = handle.find(&commit, &mut buf)?;
+    assert_eq!(obj.kind, gix_object::Kind::Commit);
+    assert_eq!(
+        promisor.calls.load(Ordering::SeqCst),
+        1,
+        "missing objects are fetched on demand"
+    );
+    assert!(handle.exists(&commit), "and are available from there on");
+    handle.find(&commit, &mut buf)?;
+    assert_eq!(
+        promisor.calls.load(Ordering::SeqCst),
+        1,
+        "the promisor is only consulted if an object can't be found locally"
+    );
+
+    let unknown = hex_to_id("0000000000000000000000000000000000000001");
+    assert!(
+        handle.try_find(&unknown, &mut buf).is_err(),
+        "the promisor fails to fetch objects it doesn't know"
+    );
+    assert_eq!(promisor.calls.load(Ordering::SeqCst), 2);
+
+    let tree = hex_to_id("4dac9989f96bc5b5b1263b582c08f0c5f0b58542");
+    let blob = hex_to_id("dd25c539efbb0ab018caa4cda2d133285634e9b5");
+    assert_eq!(
+        handle.fetch_missing([commit, tree, blob, tree])?,
+        2,
+        "only missing objects are requested, once"
+    );
+    assert_eq!(promisor.calls.load(Ordering::SeqCst), 3, "all of them in one batch");
+    assert!(handle.try_header(&blob)?.is_some());
+    assert_eq!(handle.fetch_missing([tree, blob])?, 0, "nothing to do anymore");
+    assert_eq!(promisor.calls.load(Ordering::SeqCst), 3);
+    Ok(())
+}
+
 #[test]
 fn iterate_over_a_bunch_of_loose_and_packed_objects() -> crate::Result {
     let (db, _tmp) = db_with_all_object_sources()?;
@@ -0,0 +1,117 @@
+use std::path::Path;
+
+use gix_bitmap::plain::Bitmap;
+
+use crate::bitmap::{flags, Entry, File, Version};
+
+/// Access methods
+impl File {
+    pub(crate) const SIGNATURE: &'static [u8] = b"BITM";
+
+    /// Returns the version of the bitmap file.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+    /// Returns the path from which the bitmap file was loaded.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Returns the kind of hash function used for object ids of the pack this file belongs to.
+    pub fn object_hash(&self) -> gix_hash::Kind {
+        self.object_hash
+    }
+    /// Returns the raw flags of the file, see the [`flags`][crate::bitmap::flags] module for their meaning.
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+    /// Returns `true` if the name-hash of each object is stored.
+    pub fn has_hash_cache(&self) -> bool {
+        self.hash_cache_ofs.is_some()
+    }
+    /// Returns `true` if a lookup table for bitmap entries is present.
+    pub fn has_lookup_table(&self) -> bool {
+        self.flags & flags::LOOKUP_TABLE != 0
+    }
+    /// Returns the amount of objects covered by this file, which must match the amount of objects in the pack or multi-pack index.
+    pub fn num_objects(&self) -> u32 {
+        self.num_objects
+    }
+    /// Returns the checksum of the pack or multi-pack index whose objects are described by this file.
+    pub fn index_checksum(&self) -> gix_hash::ObjectId {
+        gix_hash::ObjectId::from_bytes_or_panic(&self.data[Self::HEADER_LEN..][..self.hash_len])
+    }
+    /// Returns the checksum over the entire content of the file (excluding the checksum itself).
+    pub fn checksum(&self) -> gix_hash::ObjectId {
+        gix_hash::ObjectId::from_bytes_or_panic(&self.data[self.data.len() - self.hash_len..])
+    }
+    /// Returns all entries, one for each commit that has a bitmap, sorted by [commit index](Entry::commit_index).
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+    /// Returns the bitmap with all bits set that belong to objects of the given `kind`.
+    pub fn objects_of_kind(&self, kind: gix_object::Kind) -> &Bitmap {
+        match kind {
+            gix_object::Kind::Commit => &self.commits,
+            gix_object::Kind::Tree => &self.trees,
+            gix_object::Kind::Blob => &self.blobs,
+            gix_object::Kind::Tag => &self.tags,
+        }
+    }
+    /// Returns the kind of the object at the given bit position, or `None` if it is out of bounds.
+    pub fn kind_at_bit(&self, bit: usize) -> Option<gix_object::Kind> {
+        use gix_object::Kind::*;
+        [Commit, Tree, Blob, Tag]
+            .into_iter()
+            .find(|kind| self.objects_of_kind(*kind).get(bit))
+    }
+}
+
+impl File {
+    pub(crate) const HEADER_LEN: usize = 4 /*signature*/ + 2 /*version*/ + 2 /*flags*/ + 4 /*entry count*/;
+
+    /// Find the entry of the commit at `commit_index` in the index of the pack or multi-pack index, or `None` if the
+    /// commit doesn't have a bitmap.
+    pub fn lookup(&self, commit_index: u32) -> Option<usize> {
+        self.entries
+            .binary_search_by_key(&commit_index, |e| e.commit_index)
+            .ok()
+    }
+
+    /// Return the name-hash of the object at `index` in the pack or multi-pack index, if the hash cache is present.
+    ///
+    /// See [`name_hash()`][crate::bitmap::name_hash()] for computing it.
+    pub fn name_hash_at_index(&self, index: u32) -> Option<u32> {
+        let ofs = self.hash_cache_ofs?;
+        (index < self.num_objects).then(|| crate::read_u32(&self.data[ofs + index as usize * 4..][..4]))
+    }
+
+    /// Decode the reachability bitmap of the entry at `entry_index`, as obtained by [`lookup()`][File::lookup()],
+    /// with the bit of each object reachable from its commit set.
+    ///
+    /// This resolves the chain of bitmaps the entry was XORed with.
+    pub fn bitmap_at_entry(&self, entry_index: usize) -> Result<Bitmap, gix_bitmap::ewah::decode::Error> {
+        let mut chain = vec![entry_index];
+        let mut current = entry_index;
+        while let Some(base) = self.entries[current].xor_base {
+            let base = base as usize;
+            if base == current || chain.len() > self.entries.len() {
+                return Err(gix_bitmap::ewah::decode::Error::Corrupt {
+                    message: "bitmap XOR chain is cyclic",
+                });
+            }
+            chain.push(base);
+            current = base;
+        }
+
+        let mut out: Option<Bitmap> = None;
+        for entry_index in chain.into_iter().rev() {
+            let (bitmap, _) = gix_bitmap::ewah::decode(&self.data[self.entries[entry_index].bitmap_ofs..])?;
+            let bitmap = bitmap.to_plain()?;
+            match out.as_mut() {
+                Some(out) => out.xor_with(&bitmap),
+                None => out = Some(bitmap),
+            }
+        }
+        Ok(out.expect("at least one bitmap in the chain"))
+    }
+}
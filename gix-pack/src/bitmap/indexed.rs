@@ -0,0 +1,112 @@
+use std::path::Path;
+
+use crate::bitmap::{reachability, File, Reachability};
+
+/// The index whose objects are described by the bitmap of an [`Indexed`] instance.
+pub enum IndexFile {
+    /// The index of a single pack.
+    Pack(crate::index::File),
+    /// A multi-pack index.
+    MultiPack(crate::multi_index::File),
+}
+
+/// A bitmap [`File`] along with the index it belongs to, as found in a pack directory.
+pub struct Indexed {
+    /// The bitmap file.
+    pub bitmap: File,
+    /// The index of the objects described by `bitmap`.
+    pub index: IndexFile,
+}
+
+mod error {
+    /// The error returned by [Indexed::at_pack_dir()][super::Indexed::at_pack_dir()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not read the pack directory at '{path}'")]
+        Io {
+            source: std::io::Error,
+            path: std::path::PathBuf,
+        },
+        #[error(transparent)]
+        Bitmap(#[from] crate::bitmap::init::Error),
+        #[error(transparent)]
+        Index(#[from] crate::index::init::Error),
+        #[error(transparent)]
+        MultiIndex(#[from] crate::multi_index::init::Error),
+    }
+}
+
+pub use error::Error;
+
+/// Initialization
+impl Indexed {
+    /// Load the bitmap of the multi-pack index in `pack_dir`, or otherwise the bitmap of the first pack that has one by name,
+    /// along with the index it belongs to. The objects in `pack_dir` are expected to be hashed with `object_hash`.
+    ///
+    /// Return `None` if there is no such bitmap, which is also the case if `pack_dir` doesn't exist.
+    pub fn at_pack_dir(pack_dir: impl AsRef<Path>, object_hash: gix_hash::Kind) -> Result<Option<Self>, Error> {
+        let pack_dir = pack_dir.as_ref();
+        let multi_index_path = pack_dir.join("multi-pack-index");
+        if multi_index_path.is_file() {
+            let index = crate::multi_index::File::at(&multi_index_path)?;
+            let bitmap_path = pack_dir.join(format!("multi-pack-index-{}.bitmap", index.checksum()));
+            if bitmap_path.is_file() {
+                return Ok(Some(Indexed {
+                    bitmap: File::at(bitmap_path, object_hash)?,
+                    index: IndexFile::MultiPack(index),
+                }));
+            }
+        }
+
+        let entries = match std::fs::read_dir(pack_dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => {
+                return Err(Error::Io {
+                    source,
+                    path: pack_dir.to_owned(),
+                })
+            }
+        };
+        let mut bitmap_paths = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|source| Error::Io {
+                    source,
+                    path: pack_dir.to_owned(),
+                })?
+                .path();
+            let is_pack_bitmap = path.extension().map_or(false, |ext| ext == "bitmap")
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .map_or(false, |name| name.starts_with("pack-"));
+            if is_pack_bitmap {
+                bitmap_paths.push(path);
+            }
+        }
+        bitmap_paths.sort();
+        for bitmap_path in bitmap_paths {
+            let index_path = bitmap_path.with_extension("idx");
+            if index_path.is_file() {
+                return Ok(Some(Indexed {
+                    bitmap: File::at(&bitmap_path, object_hash)?,
+                    index: IndexFile::Pack(crate::index::File::at(index_path, object_hash)?),
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Access
+impl Indexed {
+    /// Create an instance to answer reachability queries with our bitmap, which fails if it doesn't belong to our index.
+    pub fn reachability(&self) -> Result<Reachability<'_>, reachability::init::Error> {
+        match &self.index {
+            IndexFile::Pack(index) => Reachability::new(&self.bitmap, index),
+            IndexFile::MultiPack(index) => Reachability::new(&self.bitmap, index),
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::path::Path;
+
+use crate::bitmap::{flags, Entry, File, Version};
+
+mod error {
+    /// The error returned by [File::at()][super::File::at()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not open bitmap file at '{path}'")]
+        Io {
+            source: std::io::Error,
+            path: std::path::PathBuf,
+        },
+        #[error("{message}")]
+        Corrupt { message: &'static str },
+        #[error("Unsupported bitmap version: {version}")]
+        UnsupportedVersion { version: u16 },
+        #[error("Bitmaps must be created for the full DAG, but flags were {flags:#x}")]
+        MissingFullDag { flags: u16 },
+        #[error("Could not decode the {kind} bitmap")]
+        Bitmap {
+            kind: &'static str,
+            source: gix_bitmap::ewah::decode::Error,
+        },
+    }
+}
+
+pub use error::Error;
+
+const LOOKUP_TABLE_TRIPLET_LEN: usize = 4 /*commit position*/ + 8 /*offset*/ + 4 /*xor row*/;
+const ENTRY_HEADER_LEN: usize = 4 /*commit position*/ + 1 /*xor offset*/ + 1 /*flags*/;
+
+/// Initialization
+impl File {
+    /// Open the bitmap file at the given `path`, which belongs to a pack or multi-pack index whose objects are hashed
+    /// with `object_hash`.
+    pub fn at(path: impl AsRef<Path>, object_hash: gix_hash::Kind) -> Result<Self, Error> {
+        Self::at_inner(path.as_ref(), object_hash)
+    }
+
+    fn at_inner(path: &Path, object_hash: gix_hash::Kind) -> Result<Self, Error> {
+        let data = crate::mmap::read_only(path).map_err(|source| Error::Io {
+            source,
+            path: path.to_owned(),
+        })?;
+        let hash_len = object_hash.len_in_bytes();
+        if data.len() < File::HEADER_LEN + hash_len /* pack checksum */ + hash_len
+        /* trailer */
+        {
+            return Err(Error::Corrupt {
+                message: "bitmap file is truncated and too short",
+            });
+        }
+
+        let (version, flags, num_entries) = {
+            let (signature, d) = data.split_at(4);
+            if signature != File::SIGNATURE {
+                return Err(Error::Corrupt {
+                    message: "Invalid signature",
+                });
+            }
+            let (version, d) = d.split_at(2);
+            let version = match read_u16(version) {
+                1 => Version::V1,
+                version => return Err(Error::UnsupportedVersion { version }),
+            };
+            let (flags, d) = d.split_at(2);
+            let flags = read_u16(flags);
+            if flags & flags::FULL_DAG == 0 {
+                return Err(Error::MissingFullDag { flags });
+            }
+            let num_entries = crate::read_u32(&d[..4]);
+            (version, flags, num_entries)
+        };
+
+        let mut ofs = File::HEADER_LEN + hash_len;
+        let next_bitmap = |kind: &'static str, ofs: &mut usize| {
+            let (bitmap, rest) =
+                gix_bitmap::ewah::decode(&data[*ofs..]).map_err(|source| Error::Bitmap { kind, source })?;
+            *ofs = data.len() - rest.len();
+            bitmap.to_plain().map_err(|source| Error::Bitmap { kind, source })
+        };
+        let commits = next_bitmap("commit", &mut ofs)?;
+        let trees = next_bitmap("tree", &mut ofs)?;
+        let blobs = next_bitmap("blob", &mut ofs)?;
+        let tags = next_bitmap("tag", &mut ofs)?;
+        let num_objects = commits.count_ones() + trees.count_ones() + blobs.count_ones() + tags.count_ones();
+        let num_objects = u32::try_from(num_objects).map_err(|_| Error::Corrupt {
+            message: "too many objects in type bitmaps",
+        })?;
+        let entries_ofs = ofs;
+
+        let mut end = data.len() - hash_len;
+        let hash_cache_ofs = if flags & flags::HASH_CACHE != 0 {
+            let cache_len = num_objects as usize * 4;
+            end = end
+                .checked_sub(cache_len)
+                .filter(|end| *end >= entries_ofs)
+                .ok_or(Error::Corrupt {
+                    message: "bitmap file is too short to fit the name-hash cache",
+                })?;
+            Some(end)
+        } else {
+            None
+        };
+
+        let mut entries = if flags & flags::LOOKUP_TABLE != 0 {
+            let table_len = num_entries as usize * LOOKUP_TABLE_TRIPLET_LEN;
+            let table_ofs = end
+                .checked_sub(table_len)
+                .filter(|ofs| *ofs >= entries_ofs)
+                .ok_or(Error::Corrupt {
+                    message: "bitmap file is too short to fit the lookup table",
+                })?;
+            end = table_ofs;
+            entries_from_lookup_table(&data, table_ofs..table_ofs + table_len, num_entries, entries_ofs..end)?
+        } else {
+            entries_from_scan(&data[..end], entries_ofs, num_entries)?
+        };
+        if entries.windows(2).any(|w| w[0].commit_index >= w[1].commit_index) {
+            // Entries are not required to be sorted on disk, so sort them while keeping their XOR bases intact.
+            let mut sorted: Vec<_> = (0..entries.len() as u32).collect();
+            sorted.sort_by_key(|idx| entries[*idx as usize].commit_index);
+            let mut new_position = vec![0; entries.len()];
+            for (new_idx, old_idx) in sorted.iter().enumerate() {
+                new_position[*old_idx as usize] = new_idx as u32;
+            }
+            entries = sorted
+                .into_iter()
+                .map(|old_idx| {
+                    let mut e = entries[old_idx as usize];
+                    e.xor_base = e.xor_base.map(|base| new_position[base as usize]);
+                    e
+                })
+                .collect();
+        }
+
+        Ok(File {
+            data,
+            path: path.to_owned(),
+            version,
+            flags,
+            hash_len,
+            object_hash,
+            num_objects,
+            commits,
+            trees,
+            blobs,
+            tags,
+            entries,
+            hash_cache_ofs,
+        })
+    }
+}
+
+/// Read all entries one after another, starting at `ofs`.
+fn entries_from_scan(data: &[u8], mut ofs: usize, num_entries: u32) -> Result<Vec<Entry>, Error> {
+    let mut entries = Vec::<Entry>::with_capacity(num_entries as usize);
+    for entry_idx in 0..num_entries {
+        let header = data.get(ofs..ofs + ENTRY_HEADER_LEN).ok_or(Error::Corrupt {
+            message: "bitmap entry is truncated",
+        })?;
+        let commit_index = crate::read_u32(&header[..4]);
+        let xor_offset = u32::from(header[4]);
+        let flags = header[5];
+        let xor_base = match xor_offset {
+            0 => None,
+            xor_offset => Some(entry_idx.checked_sub(xor_offset).ok_or(Error::Corrupt {
+                message: "bitmap entry refers to an XOR base before the first entry",
+            })?),
+        };
+        let bitmap_ofs = ofs + ENTRY_HEADER_LEN;
+        let (_, rest) = gix_bitmap::ewah::decode(&data[bitmap_ofs..]).map_err(|source| Error::Bitmap {
+            kind: "commit entry",
+            source,
+        })?;
+        ofs = data.len() - rest.len();
+        entries.push(Entry {
+            commit_index,
+            flags,
+            xor_base,
+            bitmap_ofs,
+        });
+    }
+    Ok(entries)
+}
+
+/// Use the lookup table to learn about all entries without having to decode any of their bitmaps.
+fn entries_from_lookup_table(
+    data: &[u8],
+    table: std::ops::Range<usize>,
+    num_entries: u32,
+    valid_entry_range: std::ops::Range<usize>,
+) -> Result<Vec<Entry>, Error> {
+    data[table]
+        .chunks_exact(LOOKUP_TABLE_TRIPLET_LEN)
+        .map(|triplet| {
+            let commit_index = crate::read_u32(&triplet[..4]);
+            let entry_ofs = usize::try_from(crate::read_u64(&triplet[4..12]))
+                .ok()
+                .filter(|ofs| valid_entry_range.contains(ofs) && ofs + ENTRY_HEADER_LEN <= valid_entry_range.end)
+                .ok_or(Error::Corrupt {
+                    message: "bitmap lookup table points outside of the bitmap entries",
+                })?;
+            let xor_row = crate::read_u32(&triplet[12..]);
+            let xor_base = match xor_row {
+                u32::MAX => None,
+                row if row < num_entries => Some(row),
+                _ => {
+                    return Err(Error::Corrupt {
+                        message: "bitmap lookup table refers to a non-existing XOR base",
+                    })
+                }
+            };
+            Ok(Entry {
+                commit_index,
+                flags: data[entry_ofs + ENTRY_HEADER_LEN - 1],
+                xor_base,
+                bitmap_ofs: entry_ofs + ENTRY_HEADER_LEN,
+            })
+        })
+        .collect()
+}
+
+#[inline]
+fn read_u16(b: &[u8]) -> u16 {
+    u16::from_be_bytes(b.try_into().unwrap())
+}
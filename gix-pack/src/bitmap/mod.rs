@@ -0,0 +1,93 @@
+//! Reachability bitmaps as stored in `pack-*.bitmap` and `multi-pack-index-*.bitmap` files.
+//!
+//! Each bit position corresponds to an object in _pack order_, that is the order in which objects are stored in the pack,
+//! or the _pseudo-pack order_ of a multi-pack index. Commits selected for bitmapping have a bitmap with all
+//! objects reachable from them set, which makes it possible to learn about the closure of a commit without traversing
+//! the commit graph.
+use std::path::PathBuf;
+
+use memmap2::Mmap;
+
+/// Known bitmap file versions
+#[derive(Default, PartialEq, Eq, Ord, PartialOrd, Debug, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum Version {
+    #[default]
+    V1 = 1,
+}
+
+/// The flags stored in the header of a bitmap file, signalling which optional extensions are present.
+pub mod flags {
+    /// All objects reachable from bitmapped commits are contained in the pack. Always set.
+    pub const FULL_DAG: u16 = 0x1;
+    /// A name-hash for each object is stored, in index order.
+    pub const HASH_CACHE: u16 = 0x4;
+    /// A table to find the bitmap of a commit without reading all bitmaps is stored.
+    pub const LOOKUP_TABLE: u16 = 0x10;
+}
+
+/// A bitmap for a single commit, as stored in a [bitmap file][File].
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry {
+    /// The position of the commit in the index of the pack or multi-pack index.
+    pub commit_index: u32,
+    /// The flags of the entry, with `0x1` indicating that the bitmap may be used as base for delta-reuse.
+    pub flags: u8,
+    /// The index of the entry whose bitmap this one is XORed with, if any.
+    pub(crate) xor_base: Option<u32>,
+    /// The offset to the EWAH encoded bitmap in the file.
+    pub(crate) bitmap_ofs: usize,
+}
+
+/// A representation of a file with reachability bitmaps for a pack or a multi-pack index.
+pub struct File {
+    data: Mmap,
+    path: PathBuf,
+    version: Version,
+    flags: u16,
+    hash_len: usize,
+    object_hash: gix_hash::Kind,
+    num_objects: u32,
+    commits: gix_bitmap::plain::Bitmap,
+    trees: gix_bitmap::plain::Bitmap,
+    blobs: gix_bitmap::plain::Bitmap,
+    tags: gix_bitmap::plain::Bitmap,
+    /// Entries sorted by their commit index position.
+    entries: Vec<Entry>,
+    hash_cache_ofs: Option<usize>,
+}
+
+/// Compute the name-hash of the `path` at which an object was found, which is used to place objects with similar names
+/// close to each other when looking for delta bases.
+///
+/// It's the same function that git uses, which is why hashes can be compared to the ones stored in bitmap files.
+pub fn name_hash(path: &[u8]) -> u32 {
+    path.iter()
+        .filter(|b| !matches!(b, b' ' | b'\t' | b'\n' | b'\r'))
+        .fold(0u32, |hash, b| (hash >> 2).wrapping_add(u32::from(*b) << 24))
+}
+
+///
+pub mod init;
+
+mod access;
+
+///
+pub mod order;
+pub use order::Order;
+
+///
+pub mod reachability;
+pub use reachability::Reachability;
+
+///
+pub mod indexed;
+pub use indexed::Indexed;
+
+///
+pub mod verify;
+
+///
+pub mod write;
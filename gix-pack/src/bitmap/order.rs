@@ -0,0 +1,166 @@
+use std::path::{Path, PathBuf};
+
+use crate::{index, multi_index};
+
+/// The error returned by [`Order::from_index()`] and [`Order::from_multi_index()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not read reverse index file at '{path}'")]
+    Io { source: std::io::Error, path: PathBuf },
+    #[error("The reverse index at '{path}' is corrupt: {message}")]
+    Corrupt { path: PathBuf, message: &'static str },
+    #[error("The multi-pack index at '{path}' has neither a reverse index chunk nor a .rev file, which is required for bitmaps")]
+    MissingReverseIndex { path: PathBuf },
+}
+
+/// A mapping between positions of objects in the index of a pack or multi-pack index, and the bit positions of
+/// the same objects in bitmaps, which are in pack order.
+///
+/// It's the in-memory version of a _reverse index_ which git stores in `.rev` files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Order {
+    index_by_bit: Vec<u32>,
+    bit_by_index: Vec<u32>,
+}
+
+/// Initialization
+impl Order {
+    /// Create an instance from a list of index positions in the order in which they appear in the pack.
+    pub fn from_index_by_bit(index_by_bit: Vec<u32>) -> Self {
+        let mut bit_by_index = vec![0; index_by_bit.len()];
+        for (bit, index) in index_by_bit.iter().enumerate() {
+            bit_by_index[*index as usize] = bit as u32;
+        }
+        Order {
+            index_by_bit,
+            bit_by_index,
+        }
+    }
+
+    /// Obtain the pack order of all objects in `index`, either by reading the `.rev` file next to it,
+    /// or by sorting its entries by pack offset if there is none.
+    pub fn from_index(index: &index::File) -> Result<Self, Error> {
+        let rev_path = index.path().with_extension("rev");
+        if let Some(index_by_bit) = read_rev_file(
+            &rev_path,
+            index.num_objects(),
+            index.object_hash(),
+            index.pack_checksum(),
+        )? {
+            return Ok(Self::from_index_by_bit(index_by_bit));
+        }
+
+        let mut offsets: Vec<_> = (0..index.num_objects())
+            .map(|idx| (index.pack_offset_at_index(idx), idx))
+            .collect();
+        offsets.sort_unstable();
+        Ok(Self::from_index_by_bit(
+            offsets.into_iter().map(|(_, idx)| idx).collect(),
+        ))
+    }
+
+    /// Obtain the pseudo-pack order of all objects in the multi-pack `index`, either from its reverse index chunk
+    /// or from the `multi-pack-index-<checksum>.rev` file next to it.
+    pub fn from_multi_index(index: &multi_index::File) -> Result<Self, Error> {
+        if index.index_at_pseudo_pack_position(0).is_some() || index.num_objects() == 0 {
+            return Ok(Self::from_index_by_bit(
+                (0..index.num_objects())
+                    .map(|pos| {
+                        index
+                            .index_at_pseudo_pack_position(pos)
+                            .expect("chunk is present and position is in bounds")
+                    })
+                    .collect(),
+            ));
+        }
+
+        let checksum = index.checksum();
+        let rev_path = index
+            .path()
+            .with_file_name(format!("multi-pack-index-{}.rev", checksum.to_hex()));
+        read_rev_file(&rev_path, index.num_objects(), index.object_hash(), checksum)?
+            .map(Self::from_index_by_bit)
+            .ok_or_else(|| Error::MissingReverseIndex {
+                path: index.path().to_owned(),
+            })
+    }
+}
+
+/// Access
+impl Order {
+    /// Return the position of the object in the index that corresponds to `bit`.
+    pub fn index_at_bit(&self, bit: u32) -> u32 {
+        self.index_by_bit[bit as usize]
+    }
+
+    /// Return the bit position of the object at `index` in its pack or multi-pack index.
+    pub fn bit_at_index(&self, index: u32) -> u32 {
+        self.bit_by_index[index as usize]
+    }
+
+    /// Return the amount of objects we are mapping.
+    pub fn len(&self) -> usize {
+        self.index_by_bit.len()
+    }
+
+    /// Return `true` if there are no objects.
+    pub fn is_empty(&self) -> bool {
+        self.index_by_bit.is_empty()
+    }
+}
+
+/// Read the reverse index at `path` and return all index positions in pack order, or `None` if it doesn't exist.
+fn read_rev_file(
+    path: &Path,
+    num_objects: u32,
+    object_hash: gix_hash::Kind,
+    expected_checksum: gix_hash::ObjectId,
+) -> Result<Option<Vec<u32>>, Error> {
+    const HEADER_LEN: usize = 4 /*signature*/ + 4 /*version*/ + 4 /*hash kind*/;
+    let data = match crate::mmap::read_only(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(source) => {
+            return Err(Error::Io {
+                source,
+                path: path.to_owned(),
+            })
+        }
+    };
+    let corrupt = |message| Error::Corrupt {
+        path: path.to_owned(),
+        message,
+    };
+    let hash_len = object_hash.len_in_bytes();
+    if data.len() != HEADER_LEN + num_objects as usize * 4 + hash_len * 2 {
+        return Err(corrupt("unexpected file size"));
+    }
+    if &data[..4] != b"RIDX" {
+        return Err(corrupt("invalid signature"));
+    }
+    if crate::read_u32(&data[4..8]) != 1 {
+        return Err(corrupt("unsupported version"));
+    }
+    let hash_id = match object_hash {
+        gix_hash::Kind::Sha1 => 1,
+        gix_hash::Kind::Sha256 => 2,
+    };
+    if crate::read_u32(&data[8..12]) != hash_id {
+        return Err(corrupt("unexpected hash kind"));
+    }
+    let checksum_ofs = data.len() - hash_len * 2;
+    if data[checksum_ofs..][..hash_len] != *expected_checksum.as_slice() {
+        return Err(corrupt(
+            "the reverse index belongs to a different pack or multi-pack index",
+        ));
+    }
+    let index_by_bit: Vec<_> = data[HEADER_LEN..checksum_ofs]
+        .chunks_exact(4)
+        .map(crate::read_u32)
+        .collect();
+    if index_by_bit.iter().any(|idx| *idx >= num_objects) {
+        return Err(corrupt("index position out of bounds"));
+    }
+    Ok(Some(index_by_bit))
+}
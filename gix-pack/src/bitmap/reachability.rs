@@ -0,0 +1,281 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use gix_bitmap::plain::Bitmap;
+use gix_hash::{oid, ObjectId};
+use gix_object::FindExt;
+
+use crate::{
+    bitmap::{File, Order},
+    index, multi_index,
+};
+
+/// The index whose objects are described by a bitmap [`File`].
+#[derive(Clone, Copy)]
+pub enum Index<'a> {
+    /// The index of a single pack, with bits in pack order.
+    Pack(&'a index::File),
+    /// A multi-pack index, with bits in pseudo-pack order.
+    MultiPack(&'a multi_index::File),
+}
+
+impl<'a> From<&'a index::File> for Index<'a> {
+    fn from(v: &'a index::File) -> Self {
+        Index::Pack(v)
+    }
+}
+
+impl<'a> From<&'a multi_index::File> for Index<'a> {
+    fn from(v: &'a multi_index::File) -> Self {
+        Index::MultiPack(v)
+    }
+}
+
+impl Index<'_> {
+    fn num_objects(&self) -> u32 {
+        match self {
+            Index::Pack(index) => index.num_objects(),
+            Index::MultiPack(index) => index.num_objects(),
+        }
+    }
+
+    fn lookup(&self, id: &oid) -> Option<u32> {
+        match self {
+            Index::Pack(index) => index.lookup(id),
+            Index::MultiPack(index) => index.lookup(id),
+        }
+    }
+
+    fn oid_at_index(&self, idx: u32) -> &oid {
+        match self {
+            Index::Pack(index) => index.oid_at_index(idx),
+            Index::MultiPack(index) => index.oid_at_index(idx),
+        }
+    }
+
+    /// The checksum that bitmaps store to refer to the pack or multi-pack index they belong to.
+    fn checksum(&self) -> ObjectId {
+        match self {
+            Index::Pack(index) => index.pack_checksum(),
+            Index::MultiPack(index) => index.checksum(),
+        }
+    }
+
+    fn order(&self) -> Result<Order, crate::bitmap::order::Error> {
+        match self {
+            Index::Pack(index) => Order::from_index(index),
+            Index::MultiPack(index) => Order::from_multi_index(index),
+        }
+    }
+}
+
+///
+pub mod init {
+    /// The error returned by [`Reachability::new()`][super::super::Reachability::new()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The bitmap belongs to {actual}, but the index belongs to {expected}")]
+        ChecksumMismatch {
+            expected: gix_hash::ObjectId,
+            actual: gix_hash::ObjectId,
+        },
+        #[error("The bitmap describes {actual} objects, but the index has {expected}")]
+        NumObjectsMismatch { expected: u32, actual: u32 },
+        #[error(transparent)]
+        Order(#[from] crate::bitmap::order::Error),
+    }
+}
+
+/// The error returned by [`Reachability::reachable_from()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Find(#[from] gix_object::find::existing::Error),
+    #[error(transparent)]
+    Decode(#[from] gix_object::decode::Error),
+    #[error("Could not decode a reachability bitmap")]
+    Bitmap(#[from] gix_bitmap::ewah::decode::Error),
+    #[error("Operation interrupted")]
+    Interrupted,
+}
+
+/// The set of objects reachable from a set of tips, as returned by [`Reachability::reachable_from()`].
+#[derive(Debug, Default, Clone)]
+pub struct Reachable {
+    /// The bit of each reachable object that is contained in the pack or multi-pack index is set.
+    pub bitmap: Bitmap,
+    /// Reachable objects that are not contained in the pack or multi-pack index.
+    pub extra: gix_hashtable::HashSet<ObjectId>,
+    /// The amount of objects that had to be decoded as they weren't covered by bitmaps.
+    pub decoded_objects: usize,
+}
+
+impl Reachable {
+    /// Return the amount of reachable objects.
+    pub fn len(&self) -> usize {
+        self.bitmap.count_ones() + self.extra.len()
+    }
+
+    /// Return `true` if no object is reachable.
+    pub fn is_empty(&self) -> bool {
+        self.bitmap.is_empty() && self.extra.is_empty()
+    }
+
+    /// Remove all objects from this set that are also contained in `other`.
+    ///
+    /// This is how one obtains all objects reachable from one set of tips that are not reachable from another.
+    pub fn remove(&mut self, other: &Reachable) {
+        self.bitmap.difference_with(&other.bitmap);
+        self.extra.retain(|id| !other.extra.contains(id));
+    }
+}
+
+/// Reachability queries using a bitmap [`File`] and the index whose objects it describes.
+pub struct Reachability<'a> {
+    bitmap: &'a File,
+    index: Index<'a>,
+    order: Order,
+}
+
+/// Initialization
+impl<'a> Reachability<'a> {
+    /// Create a new instance for answering reachability queries using `bitmap` and the `index` it belongs to.
+    ///
+    /// This fails if `bitmap` doesn't belong to `index`.
+    pub fn new(bitmap: &'a File, index: impl Into<Index<'a>>) -> Result<Self, init::Error> {
+        let index = index.into();
+        let expected = index.checksum();
+        let actual = bitmap.index_checksum();
+        if expected != actual {
+            return Err(init::Error::ChecksumMismatch { expected, actual });
+        }
+        if bitmap.num_objects() != index.num_objects() {
+            return Err(init::Error::NumObjectsMismatch {
+                expected: index.num_objects(),
+                actual: bitmap.num_objects(),
+            });
+        }
+        let order = index.order()?;
+        Ok(Reachability { bitmap, index, order })
+    }
+}
+
+/// Access
+impl Reachability<'_> {
+    /// Return the bitmap file we use.
+    pub fn bitmap(&self) -> &File {
+        self.bitmap
+    }
+
+    /// Return the mapping between index positions and bit positions.
+    pub fn order(&self) -> &Order {
+        &self.order
+    }
+
+    /// Return the bit position of the object with `id`, or `None` if it isn't contained in the pack or multi-pack index.
+    pub fn bit_of(&self, id: &oid) -> Option<u32> {
+        self.index.lookup(id).map(|idx| self.order.bit_at_index(idx))
+    }
+
+    /// Return the id of the object at `bit`.
+    pub fn oid_at_bit(&self, bit: u32) -> &oid {
+        self.index.oid_at_index(self.order.index_at_bit(bit))
+    }
+
+    /// Return the reachability bitmap of the commit with `id`, or `None` if there is no bitmap for it.
+    pub fn commit_bitmap(&self, id: &oid) -> Result<Option<Bitmap>, gix_bitmap::ewah::decode::Error> {
+        self.index
+            .lookup(id)
+            .and_then(|idx| self.bitmap.lookup(idx))
+            .map(|entry| self.bitmap.bitmap_at_entry(entry))
+            .transpose()
+    }
+
+    /// Return `true` if the object with `id` is contained in `reachable`.
+    pub fn contains(&self, reachable: &Reachable, id: &oid) -> bool {
+        match self.bit_of(id) {
+            Some(bit) => reachable.bitmap.get(bit as usize),
+            None => reachable.extra.contains(id),
+        }
+    }
+
+    /// Return an iterator over the ids of all objects in `reachable`, with packed objects in pack order
+    /// followed by all extra objects.
+    pub fn iter<'r>(&'r self, reachable: &'r Reachable) -> impl Iterator<Item = ObjectId> + 'r {
+        reachable
+            .bitmap
+            .iter_ones()
+            .map(|bit| self.oid_at_bit(bit as u32).to_owned())
+            .chain(reachable.extra.iter().copied())
+    }
+}
+
+/// Queries
+impl Reachability<'_> {
+    /// Find all objects reachable from `tips`, using `objects` to look up objects whose closure isn't known
+    /// from a bitmap.
+    ///
+    /// Whenever a commit with bitmap is encountered, its closure is added in one step. All other objects are traversed
+    /// like usual until a commit with bitmap is found, or an object that is already known to be reachable.
+    /// Objects not contained in the pack or multi-pack index are collected separately.
+    pub fn reachable_from(
+        &self,
+        tips: impl IntoIterator<Item = ObjectId>,
+        objects: &dyn gix_object::Find,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Reachable, Error> {
+        let mut out = Reachable::default();
+        let mut stack: Vec<ObjectId> = tips.into_iter().collect();
+        let mut buf = Vec::new();
+        while let Some(id) = stack.pop() {
+            if should_interrupt.load(Ordering::Relaxed) {
+                return Err(Error::Interrupted);
+            }
+            if !self.insert(&id, &mut out)? {
+                continue;
+            }
+
+            let obj = objects.find(&id, &mut buf)?;
+            out.decoded_objects += 1;
+            match obj.kind {
+                gix_object::Kind::Commit => {
+                    let mut iter = gix_object::CommitRefIter::from_bytes(obj.data);
+                    stack.push(iter.tree_id()?);
+                    stack.extend(iter.parent_ids());
+                }
+                gix_object::Kind::Tree => {
+                    for entry in gix_object::TreeRefIter::from_bytes(obj.data, obj.hash_kind) {
+                        let entry = entry?;
+                        if entry.mode.is_tree() {
+                            stack.push(entry.oid.to_owned());
+                        } else if !entry.mode.is_commit() {
+                            self.insert(entry.oid, &mut out)?;
+                        }
+                    }
+                }
+                gix_object::Kind::Tag => stack.push(gix_object::TagRefIter::from_bytes(obj.data).target_id()?),
+                gix_object::Kind::Blob => {}
+            }
+        }
+        Ok(out)
+    }
+
+    /// Add `id` to `out` and return `true` if it still needs to be traversed, or `false` if it was known already or
+    /// if its closure was added from a bitmap.
+    fn insert(&self, id: &oid, out: &mut Reachable) -> Result<bool, Error> {
+        let Some(idx) = self.index.lookup(id) else {
+            return Ok(out.extra.insert(id.to_owned()));
+        };
+        let bit = self.order.bit_at_index(idx) as usize;
+        if out.bitmap.get(bit) {
+            return Ok(false);
+        }
+        if let Some(entry) = self.bitmap.lookup(idx) {
+            out.bitmap.union_with(&self.bitmap.bitmap_at_entry(entry)?);
+            return Ok(false);
+        }
+        out.bitmap.set(bit);
+        Ok(true)
+    }
+}
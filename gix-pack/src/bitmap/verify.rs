@@ -0,0 +1,30 @@
+use std::sync::atomic::AtomicBool;
+
+use gix_features::progress::Progress;
+
+use crate::bitmap::File;
+
+///
+pub mod checksum {
+    /// Returned by [`bitmap::File::verify_checksum()`][crate::bitmap::File::verify_checksum()].
+    pub type Error = crate::verify::checksum::Error;
+}
+
+impl File {
+    /// Validate that our [`checksum()`][File::checksum()] matches the actual contents
+    /// of this bitmap file, and return it if it does.
+    pub fn verify_checksum(
+        &self,
+        progress: &mut dyn Progress,
+        should_interrupt: &AtomicBool,
+    ) -> Result<gix_hash::ObjectId, checksum::Error> {
+        crate::verify::checksum_on_disk_or_mmap(
+            self.path(),
+            &self.data,
+            self.checksum(),
+            self.object_hash,
+            progress,
+            should_interrupt,
+        )
+    }
+}
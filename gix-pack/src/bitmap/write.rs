@@ -0,0 +1,536 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
+
+use gix_bitmap::{ewah, plain::Bitmap};
+use gix_features::progress::{Count, DynNestedProgress, Progress};
+use gix_hash::ObjectId;
+use gix_object::Kind;
+
+use crate::bitmap::{flags, name_hash, File, Order, Version};
+
+mod error {
+    use gix_hash::ObjectId;
+
+    /// The error returned by [`File::write_from_bundle()`][crate::bitmap::File::write_from_bundle()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Io(#[from] std::io::Error),
+        #[error("Interrupted")]
+        Interrupted,
+        #[error(transparent)]
+        Order(#[from] crate::bitmap::order::Error),
+        #[error(transparent)]
+        Decode(#[from] crate::data::decode::Error),
+        #[error(transparent)]
+        DecodeObject(#[from] gix_object::decode::Error),
+        #[error("Object {id} is reachable from a commit in the pack, but isn't contained in it")]
+        NotClosed { id: ObjectId },
+    }
+}
+pub use error::Error;
+
+/// Options for use in [`File::write_from_bundle()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Options {
+    /// If `true`, store the name-hash of each object to help finding delta bases when packs are generated from this one.
+    pub hash_cache: bool,
+    /// If `true`, write a table to find the bitmap of a commit without having to read all bitmaps.
+    pub lookup_table: bool,
+}
+
+impl Default for Options {
+    /// Write all extensions, just like git does by default.
+    fn default() -> Self {
+        Options {
+            hash_cache: true,
+            lookup_table: true,
+        }
+    }
+}
+
+/// The result of [`File::write_from_bundle()`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    /// The checksum of the written bitmap file, as stored in its trailer.
+    pub checksum: ObjectId,
+    /// The amount of commits that received a reachability bitmap.
+    pub num_bitmaps: usize,
+}
+
+/// The progress ids used in [`write_from_bundle()`][File::write_from_bundle()].
+///
+/// Use this information to selectively extract the progress of interest in case the parent application has custom visualization.
+#[derive(Debug, Copy, Clone)]
+pub enum ProgressId {
+    /// The amount of objects whose kind was determined or whose name was looked up.
+    ObjectsTraversed,
+    /// The amount of reachability bitmaps computed for selected commits.
+    BitmapsComputed,
+    /// The amount of bytes written as part of the bitmap file.
+    BytesWritten,
+}
+
+impl From<ProgressId> for gix_features::progress::Id {
+    fn from(v: ProgressId) -> Self {
+        match v {
+            ProgressId::ObjectsTraversed => *b"BWOT",
+            ProgressId::BitmapsComputed => *b"BWBC",
+            ProgressId::BytesWritten => *b"BWBW",
+        }
+    }
+}
+
+/// A commit that may be selected for receiving a bitmap by [`select_commits()`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Candidate {
+    /// If `true`, the commit should be chosen over others in its vicinity, which is typically the case if a reference points to it.
+    pub is_preferred: bool,
+    /// If `true`, the commit has more than one parent.
+    pub is_merge: bool,
+}
+
+/// Select the commits that should receive a bitmap out of `candidates`, which are expected to be sorted by commit time
+/// with the most recent commit first, and return their positions in `candidates`.
+///
+/// This is the same heuristic that git uses: if there are less than 100 candidates, all of them are selected. Otherwise, each of
+/// the 100 most recent commits is selected, after which the distance between selected commits grows to 100 commits, and
+/// eventually to up to 5000 commits for commits far in the past. In each of these windows, the first preferred commit is
+/// chosen, or else the last merge commit, or else the oldest commit.
+pub fn select_commits(candidates: &[Candidate]) -> Vec<usize> {
+    if candidates.len() < 100 {
+        return (0..candidates.len()).collect();
+    }
+
+    let mut out = Vec::new();
+    let mut idx = 0;
+    loop {
+        let next = next_commit_distance(idx);
+        if idx + next >= candidates.len() {
+            break;
+        }
+        let chosen = if next == 0 {
+            idx
+        } else {
+            let window = idx..=idx + next;
+            window
+                .clone()
+                .find(|pos| candidates[*pos].is_preferred)
+                .or_else(|| window.rev().find(|pos| candidates[*pos].is_merge))
+                .unwrap_or(idx + next)
+        };
+        out.push(chosen);
+        idx += next + 1;
+    }
+    out
+}
+
+/// Return the amount of commits to skip before selecting the next one when at `idx` of all candidates.
+fn next_commit_distance(idx: usize) -> usize {
+    const MIN_COMMITS: usize = 100;
+    const MAX_COMMITS: usize = 5000;
+    const MUST_REGION: usize = 100;
+    const MIN_REGION: usize = 20000;
+
+    if idx <= MUST_REGION {
+        0
+    } else if idx <= MIN_REGION {
+        (idx - MUST_REGION).min(MIN_COMMITS)
+    } else {
+        (idx - MIN_REGION).clamp(MIN_COMMITS, MAX_COMMITS)
+    }
+}
+
+/// The amount of previously written bitmaps to try as XOR base for each bitmap.
+const MAX_XOR_OFFSET_SEARCH: usize = 10;
+
+struct Commit {
+    index: u32,
+    tree: ObjectId,
+    parents: Vec<u32>,
+    time: i64,
+}
+
+impl File {
+    /// Write a bitmap file for all objects in `bundle` to `out`, with bitmaps for commits selected with [`select_commits()`].
+    /// Commits that no other commit in the pack refers to as parent are preferred for selection.
+    ///
+    /// All objects reachable from commits in the pack must be contained in the pack as well, which typically is the case for
+    /// packs that contain all objects of a repository.
+    ///
+    /// Progress is sent to `progress` and interruptions checked via `should_interrupt`.
+    pub fn write_from_bundle(
+        bundle: &crate::Bundle,
+        out: &mut dyn std::io::Write,
+        progress: &mut dyn DynNestedProgress,
+        should_interrupt: &AtomicBool,
+        Options {
+            hash_cache,
+            lookup_table,
+        }: Options,
+    ) -> Result<Outcome, Error> {
+        let _span = gix_features::trace::coarse!("gix_pack::bitmap::File::write_from_bundle()");
+        let index = &bundle.index;
+        let num_objects = index.num_objects();
+        let order = Order::from_index(index)?;
+
+        let mut objects_progress =
+            progress.add_child_with_id("Traversing objects".into(), ProgressId::ObjectsTraversed.into());
+        let start = Instant::now();
+        objects_progress.init(Some(num_objects as usize), gix_features::progress::count("objects"));
+        let kinds = object_kinds(bundle, &order, should_interrupt, &objects_progress)?;
+        let mut type_bitmaps: [Bitmap; 4] = std::array::from_fn(|_| Bitmap::with_capacity(num_objects as usize));
+        for bit in 0..num_objects {
+            let kind = kinds[order.index_at_bit(bit) as usize];
+            type_bitmaps[type_bitmap_position(kind)].set(bit as usize);
+        }
+
+        let mut buf = Vec::new();
+        let mut inflate = gix_features::zlib::Inflate::default();
+        let mut commits = Vec::with_capacity(type_bitmaps[0].count_ones());
+        for idx in (0..num_objects).filter(|idx| kinds[*idx as usize] == Kind::Commit) {
+            let (obj, _) = bundle.get_object_by_index(idx, &mut buf, &mut inflate, &mut crate::cache::Never)?;
+            let commit = gix_object::CommitRef::from_bytes(obj.data)?;
+            commits.push(Commit {
+                index: idx,
+                tree: commit.tree(),
+                parents: commit
+                    .parents()
+                    .map(|id| index.lookup(id).ok_or(Error::NotClosed { id }))
+                    .collect::<Result<_, _>>()?,
+                time: commit.committer.time.seconds,
+            });
+            if should_interrupt.load(Ordering::Relaxed) {
+                return Err(Error::Interrupted);
+            }
+        }
+        commits.sort_by(|a, b| b.time.cmp(&a.time).then(a.index.cmp(&b.index)));
+        let commit_by_index: HashMap<u32, usize> = commits.iter().enumerate().map(|(pos, c)| (c.index, pos)).collect();
+
+        let names = if hash_cache {
+            objects_progress.set_name("Hashing names".into());
+            objects_progress.init(Some(num_objects as usize), gix_features::progress::count("objects"));
+            Some(name_hashes(bundle, &commits, should_interrupt, &objects_progress)?)
+        } else {
+            None
+        };
+        objects_progress.show_throughput(start);
+
+        let selected = {
+            let mut is_parent = vec![false; commits.len()];
+            for parent in commits.iter().flat_map(|c| c.parents.iter()) {
+                is_parent[commit_by_index[parent]] = true;
+            }
+            let candidates: Vec<_> = commits
+                .iter()
+                .zip(is_parent)
+                .map(|(c, is_parent)| Candidate {
+                    is_preferred: !is_parent,
+                    is_merge: c.parents.len() > 1,
+                })
+                .collect();
+            let mut selected = select_commits(&candidates);
+            // Compute bitmaps of ancestors first, so they can be reused for their descendants.
+            selected.reverse();
+            selected
+        };
+
+        let mut bitmaps_progress =
+            progress.add_child_with_id("Computing bitmaps".into(), ProgressId::BitmapsComputed.into());
+        let start = Instant::now();
+        bitmaps_progress.init(Some(selected.len()), gix_features::progress::count("bitmaps"));
+        let mut bitmaps = Vec::<Bitmap>::with_capacity(selected.len());
+        let mut bitmap_by_index = HashMap::<u32, usize>::with_capacity(selected.len());
+        for commit_pos in &selected {
+            let commit = &commits[*commit_pos];
+            let bitmap = reachable_from(
+                bundle,
+                &order,
+                commit.index,
+                &commits,
+                &commit_by_index,
+                &bitmaps,
+                &bitmap_by_index,
+                &mut buf,
+                &mut inflate,
+                should_interrupt,
+            )?;
+            bitmap_by_index.insert(commit.index, bitmaps.len());
+            bitmaps.push(bitmap);
+            bitmaps_progress.inc();
+        }
+        bitmaps_progress.show_throughput(start);
+
+        let mut data = Vec::new();
+        data.extend_from_slice(File::SIGNATURE);
+        data.extend_from_slice(&(Version::V1 as u16).to_be_bytes());
+        let flags = flags::FULL_DAG
+            | if hash_cache { flags::HASH_CACHE } else { 0 }
+            | if lookup_table { flags::LOOKUP_TABLE } else { 0 };
+        data.extend_from_slice(&flags.to_be_bytes());
+        data.extend_from_slice(&(bitmaps.len() as u32).to_be_bytes());
+        data.extend_from_slice(index.pack_checksum().as_slice());
+        for bitmap in &type_bitmaps {
+            ewah::Vec::from_plain(bitmap).write_to(&mut data)?;
+        }
+
+        let mut entry_offsets = Vec::with_capacity(bitmaps.len());
+        let mut xor_offsets = Vec::with_capacity(bitmaps.len());
+        for (pos, bitmap) in bitmaps.iter().enumerate() {
+            let (xor_offset, encoded) = compress(&bitmaps[..pos], bitmap);
+            entry_offsets.push(data.len() as u64);
+            xor_offsets.push(xor_offset);
+            data.extend_from_slice(&commits[selected[pos]].index.to_be_bytes());
+            data.push(xor_offset);
+            data.push(0 /* flags */);
+            encoded.write_to(&mut data)?;
+        }
+
+        if lookup_table {
+            let mut rows: Vec<_> = (0..bitmaps.len()).collect();
+            rows.sort_by_key(|pos| commits[selected[*pos]].index);
+            let mut row_by_pos = vec![0; rows.len()];
+            for (row, pos) in rows.iter().enumerate() {
+                row_by_pos[*pos] = row as u32;
+            }
+            for pos in rows {
+                data.extend_from_slice(&commits[selected[pos]].index.to_be_bytes());
+                data.extend_from_slice(&entry_offsets[pos].to_be_bytes());
+                let xor_row = match xor_offsets[pos] {
+                    0 => u32::MAX,
+                    xor_offset => row_by_pos[pos - xor_offset as usize],
+                };
+                data.extend_from_slice(&xor_row.to_be_bytes());
+            }
+        }
+        if let Some(names) = names {
+            for name in names {
+                data.extend_from_slice(&name.to_be_bytes());
+            }
+        }
+
+        let mut write_progress = progress.add_child_with_id("Writing bitmap".into(), ProgressId::BytesWritten.into());
+        let start = Instant::now();
+        write_progress.init(
+            Some(data.len() + index.object_hash().len_in_bytes()),
+            gix_features::progress::bytes(),
+        );
+        let mut hasher = gix_features::hash::hasher(index.object_hash());
+        hasher.update(&data);
+        let checksum = hasher.digest();
+        out.write_all(&data)?;
+        out.write_all(checksum.as_slice())?;
+        write_progress.inc_by(data.len() + checksum.as_slice().len());
+        write_progress.show_throughput(start);
+
+        Ok(Outcome {
+            checksum,
+            num_bitmaps: bitmaps.len(),
+        })
+    }
+}
+
+fn type_bitmap_position(kind: Kind) -> usize {
+    match kind {
+        Kind::Commit => 0,
+        Kind::Tree => 1,
+        Kind::Blob => 2,
+        Kind::Tag => 3,
+    }
+}
+
+/// Determine the kind of each object in `bundle` by following delta chains, in index order.
+fn object_kinds(
+    bundle: &crate::Bundle,
+    order: &Order,
+    should_interrupt: &AtomicBool,
+    progress: &dyn Count,
+) -> Result<Vec<Kind>, Error> {
+    let index = &bundle.index;
+    let offsets_in_pack_order: Vec<_> = (0..index.num_objects())
+        .map(|bit| index.pack_offset_at_index(order.index_at_bit(bit)))
+        .collect();
+    let mut kinds = vec![None; index.num_objects() as usize];
+    let mut chain = Vec::new();
+    for idx in 0..index.num_objects() {
+        let mut cursor = idx;
+        let kind = loop {
+            if let Some(kind) = kinds[cursor as usize] {
+                break kind;
+            }
+            chain.push(cursor);
+            let entry = bundle
+                .pack
+                .entry(index.pack_offset_at_index(cursor))
+                .map_err(crate::data::decode::Error::from)?;
+            cursor = match entry.header {
+                crate::data::entry::Header::OfsDelta { base_distance } => {
+                    let base_offset = entry.base_pack_offset(base_distance);
+                    let bit = offsets_in_pack_order.binary_search(&base_offset).map_err(|_| {
+                        crate::data::decode::Error::DeltaBaseUnresolved(index.oid_at_index(cursor).to_owned())
+                    })?;
+                    order.index_at_bit(bit as u32)
+                }
+                crate::data::entry::Header::RefDelta { base_id } => index
+                    .lookup(base_id)
+                    .ok_or(crate::data::decode::Error::DeltaBaseUnresolved(base_id))?,
+                header => break header.as_kind().expect("non-delta entries have a kind"),
+            };
+        };
+        for idx in chain.drain(..) {
+            kinds[idx as usize] = Some(kind);
+        }
+        progress.inc();
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+    }
+    Ok(kinds
+        .into_iter()
+        .map(|kind| kind.expect("all kinds were determined"))
+        .collect())
+}
+
+/// Compute the name-hash of each object in index order, based on the path at which it is first seen when traversing
+/// the trees of `commits` in order.
+fn name_hashes(
+    bundle: &crate::Bundle,
+    commits: &[Commit],
+    should_interrupt: &AtomicBool,
+    progress: &dyn Count,
+) -> Result<Vec<u32>, Error> {
+    let index = &bundle.index;
+    let lookup = |id: &gix_hash::oid| index.lookup(id).ok_or(Error::NotClosed { id: id.to_owned() });
+    let mut names = vec![0; index.num_objects() as usize];
+    let mut seen = Bitmap::with_capacity(names.len());
+    let mut buf = Vec::new();
+    let mut inflate = gix_features::zlib::Inflate::default();
+    let mut stack = Vec::new();
+    for commit in commits {
+        stack.push((commit.tree, Vec::new()));
+        while let Some((tree, path)) = stack.pop() {
+            let tree_idx = lookup(&tree)?;
+            if !seen.set(tree_idx as usize) {
+                continue;
+            }
+            names[tree_idx as usize] = name_hash(&path);
+            progress.inc();
+
+            let (obj, _) = bundle.get_object_by_index(tree_idx, &mut buf, &mut inflate, &mut crate::cache::Never)?;
+            for entry in gix_object::TreeRefIter::from_bytes(obj.data, obj.hash_kind) {
+                let entry = entry?;
+                if entry.mode.is_commit() {
+                    continue;
+                }
+                let mut entry_path = path.clone();
+                if !entry_path.is_empty() {
+                    entry_path.push(b'/');
+                }
+                entry_path.extend_from_slice(entry.filename);
+                if entry.mode.is_tree() {
+                    stack.push((entry.oid.to_owned(), entry_path));
+                } else {
+                    let idx = lookup(entry.oid)?;
+                    if seen.set(idx as usize) {
+                        names[idx as usize] = name_hash(&entry_path);
+                        progress.inc();
+                    }
+                }
+            }
+        }
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+    }
+    Ok(names)
+}
+
+/// Return the bitmap with all objects reachable from the commit at `commit_index` set, reusing the `bitmaps` of
+/// ancestors that were already computed.
+#[allow(clippy::too_many_arguments)]
+fn reachable_from(
+    bundle: &crate::Bundle,
+    order: &Order,
+    commit_index: u32,
+    commits: &[Commit],
+    commit_by_index: &HashMap<u32, usize>,
+    bitmaps: &[Bitmap],
+    bitmap_by_index: &HashMap<u32, usize>,
+    buf: &mut Vec<u8>,
+    inflate: &mut gix_features::zlib::Inflate,
+    should_interrupt: &AtomicBool,
+) -> Result<Bitmap, Error> {
+    enum Item {
+        Commit(u32),
+        Tree(ObjectId),
+    }
+    let index = &bundle.index;
+    let bit_of = |id: &gix_hash::oid| {
+        index
+            .lookup(id)
+            .map(|idx| order.bit_at_index(idx) as usize)
+            .ok_or(Error::NotClosed { id: id.to_owned() })
+    };
+
+    let mut out = Bitmap::with_capacity(index.num_objects() as usize);
+    let mut stack = vec![Item::Commit(commit_index)];
+    while let Some(item) = stack.pop() {
+        match item {
+            Item::Commit(idx) => {
+                let bit = order.bit_at_index(idx) as usize;
+                if out.get(bit) {
+                    continue;
+                }
+                if let Some(bitmap) = bitmap_by_index.get(&idx) {
+                    out.union_with(&bitmaps[*bitmap]);
+                    continue;
+                }
+                out.set(bit);
+                let commit = &commits[commit_by_index[&idx]];
+                stack.push(Item::Tree(commit.tree));
+                stack.extend(commit.parents.iter().copied().map(Item::Commit));
+            }
+            Item::Tree(id) => {
+                if !out.set(bit_of(&id)?) {
+                    continue;
+                }
+                let (obj, _) = bundle
+                    .find(&id, buf, inflate, &mut crate::cache::Never)?
+                    .expect("presence checked");
+                for entry in gix_object::TreeRefIter::from_bytes(obj.data, obj.hash_kind) {
+                    let entry = entry?;
+                    if entry.mode.is_tree() {
+                        stack.push(Item::Tree(entry.oid.to_owned()));
+                    } else if !entry.mode.is_commit() {
+                        out.set(bit_of(entry.oid)?);
+                    }
+                }
+            }
+        }
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+    }
+    Ok(out)
+}
+
+/// Encode `bitmap`, possibly XORed with one of the most recent `previous` bitmaps if that makes it smaller, and return
+/// the distance to the XOR base (or 0) along with the encoded bitmap.
+fn compress(previous: &[Bitmap], bitmap: &Bitmap) -> (u8, ewah::Vec) {
+    let mut best = (0, ewah::Vec::from_plain(bitmap));
+    for xor_offset in 1..=previous.len().min(MAX_XOR_OFFSET_SEARCH) {
+        let mut xored = bitmap.clone();
+        xored.xor_with(&previous[previous.len() - xor_offset]);
+        let encoded = ewah::Vec::from_plain(&xored);
+        if encoded.num_words() < best.1.num_words() {
+            best = (xor_offset as u8, encoded);
+        }
+    }
+    best
+}
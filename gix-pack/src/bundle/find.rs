@@ -55,10 +55,7 @@ impl crate::Bundle {
             )
             .map(move |r| {
                 (
-                    gix_object::Data {
-                        kind: r.kind,
-                        data: out.as_slice(),
-                    },
+                    gix_object::Data::new(r.kind, out.as_slice(), self.index.object_hash()),
                     crate::data::entry::Location {
                         pack_id: self.pack.id,
                         pack_offset: ofs,
@@ -14,4 +14,10 @@ pub enum Error {
     Persist(#[from] gix_tempfile::handle::persist::Error<Writable>),
     #[error(transparent)]
     IndexWrite(#[from] crate::index::write::Error),
+    #[cfg(feature = "bitmap")]
+    #[error("Could not open the newly written pack to create its bitmap")]
+    BundleInit(#[from] crate::bundle::init::Error),
+    #[cfg(feature = "bitmap")]
+    #[error(transparent)]
+    BitmapWrite(#[from] crate::bitmap::write::Error),
 }
@@ -109,7 +109,7 @@ impl crate::Bundle {
                         writer: data_file.clone(),
                     },
                     pack_version,
-                    gix_hash::Kind::Sha1, // Thin packs imply a pack being transported, and there we only ever know SHA1 at the moment.
+                    object_hash,
                 );
                 (Box::new(pack_entries_iter), pack_version)
             }
@@ -141,6 +141,7 @@ impl crate::Bundle {
             data_path,
             index_path,
             keep_path,
+            bitmap_path,
         } = crate::Bundle::inner_write(
             directory,
             progress,
@@ -158,6 +159,7 @@ impl crate::Bundle {
             data_path,
             index_path,
             keep_path,
+            bitmap_path,
         })
     }
 
@@ -241,6 +243,7 @@ impl crate::Bundle {
             data_path,
             index_path,
             keep_path,
+            bitmap_path,
         } = crate::Bundle::inner_write(
             directory,
             progress,
@@ -258,6 +261,7 @@ impl crate::Bundle {
             data_path,
             index_path,
             keep_path,
+            bitmap_path,
         })
     }
 
@@ -269,6 +273,8 @@ impl crate::Bundle {
             iteration_mode: _,
             index_version: index_kind,
             object_hash,
+            #[cfg(feature = "bitmap")]
+            bitmap,
         }: Options,
         data_file: SharedTempFile,
         mut pack_entries_iter: Box<dyn Iterator<Item = Result<data::input::Entry, data::input::Error>> + 'a>,
@@ -306,6 +312,7 @@ impl crate::Bundle {
                         data_path: None,
                         index_path: None,
                         keep_path: None,
+                        bitmap_path: None,
                     }
                 } else {
                     let data_path = directory.join(format!("pack-{}.pack", outcome.data_hash.to_hex()));
@@ -334,11 +341,21 @@ impl crate::Bundle {
                                 err
                             })?;
                     }
+                    #[cfg(feature = "bitmap")]
+                    let bitmap_path = match bitmap {
+                        Some(options) => {
+                            write_bitmap(&index_path, directory, object_hash, progress, should_interrupt, options)?
+                        }
+                        None => None,
+                    };
+                    #[cfg(not(feature = "bitmap"))]
+                    let bitmap_path = None;
                     WriteOutcome {
                         outcome,
                         data_path: Some(data_path),
                         index_path: Some(index_path),
                         keep_path,
+                        bitmap_path,
                     }
                 }
             }
@@ -357,6 +374,7 @@ impl crate::Bundle {
                 data_path: None,
                 index_path: None,
                 keep_path: None,
+                bitmap_path: None,
             },
         })
     }
@@ -378,9 +396,42 @@ fn new_pack_file_resolver(
     Ok((resolve_entry, mapped_file))
 }
 
+/// Write a bitmap for the pack belonging to the index at `index_path` and return its path, or `None` if the pack isn't
+/// self-contained so no bitmap can be written for it.
+#[cfg(feature = "bitmap")]
+fn write_bitmap(
+    index_path: &Path,
+    directory: &Path,
+    object_hash: gix_hash::Kind,
+    progress: &mut dyn DynNestedProgress,
+    should_interrupt: &AtomicBool,
+    options: crate::bitmap::write::Options,
+) -> Result<Option<PathBuf>, Error> {
+    let bitmap_path = index_path.with_extension("bitmap");
+    if bitmap_path.is_file() {
+        return Ok(Some(bitmap_path));
+    }
+    let bundle = crate::Bundle::at(index_path, object_hash)?;
+    let mut bitmap_file = gix_tempfile::new(directory, ContainingDirectory::Exists, AutoRemove::Tempfile)?;
+    match crate::bitmap::File::write_from_bundle(&bundle, &mut bitmap_file, progress, should_interrupt, options) {
+        Ok(_) => {}
+        Err(crate::bitmap::write::Error::NotClosed { .. }) => {
+            gix_features::trace::warn!(
+                "Not writing a bitmap for \"{}\" as the pack isn't self-contained",
+                index_path.display()
+            );
+            return Ok(None);
+        }
+        Err(err) => return Err(err.into()),
+    }
+    bitmap_file.persist(&bitmap_path)?;
+    Ok(Some(bitmap_path))
+}
+
 struct WriteOutcome {
     outcome: crate::index::write::Outcome,
     data_path: Option<PathBuf>,
     index_path: Option<PathBuf>,
     keep_path: Option<PathBuf>,
+    bitmap_path: Option<PathBuf>,
 }
@@ -14,6 +14,12 @@ pub struct Options {
     pub index_version: crate::index::Version,
     /// The kind of hash to use when writing the bundle.
     pub object_hash: gix_hash::Kind,
+    /// If `Some`, write a reachability bitmap next to the pack, configured with the given options.
+    ///
+    /// Note that this only works if all objects reachable from commits in the pack are contained in it, which is
+    /// typically not the case for packs received during a fetch. If that's not the case, no bitmap is written.
+    #[cfg(feature = "bitmap")]
+    pub bitmap: Option<crate::bitmap::write::Options>,
 }
 
 impl Default for Options {
@@ -24,6 +30,8 @@ impl Default for Options {
             iteration_mode: crate::data::input::Mode::Verify,
             index_version: Default::default(),
             object_hash: Default::default(),
+            #[cfg(feature = "bitmap")]
+            bitmap: None,
         }
     }
 }
@@ -51,6 +59,10 @@ pub struct Outcome {
     /// The file is created right before moving the pack data and index data into place (i.e. `data_path` and `index_path`)
     /// and is expected to be removed by the caller when ready.
     pub keep_path: Option<PathBuf>,
+    /// The path to the reachability bitmap file, if one was requested with `Options::bitmap` and could be written.
+    ///
+    /// It's always `None` without the `bitmap` feature.
+    pub bitmap_path: Option<PathBuf>,
 }
 
 impl Outcome {
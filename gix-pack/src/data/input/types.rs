@@ -18,6 +18,8 @@ pub enum Error {
     IncompletePack { actual: u64, expected: u64 },
     #[error("The object {object_id} could not be decoded or wasn't found")]
     NotFound { object_id: gix_hash::ObjectId },
+    #[error("The pack is part of a hash collision attack")]
+    CollisionAttack(#[from] gix_features::hash::Error),
 }
 
 /// Iteration Mode
@@ -41,6 +41,8 @@ impl Count {
 
 #[path = "objects/mod.rs"]
 mod objects_impl;
+#[cfg(feature = "bitmap")]
+pub use objects_impl::objects_with_bitmap;
 pub use objects_impl::{objects, objects_unthreaded};
 
 ///
@@ -0,0 +1,72 @@
+use std::sync::atomic::AtomicBool;
+
+use gix_hash::{oid, ObjectId};
+
+use super::types::{Error, Outcome};
+use crate::data::{output, output::count::PackLocation};
+
+/// Generate [`Count`][output::Count]s for all objects reachable from `tips` that aren't reachable from any object in
+/// `exclude`, using the reachability bitmaps of `reachability` to avoid traversing the commit graph and trees whenever possible.
+///
+/// With an empty `exclude` this is the same as expanding all `tips` with
+/// [`ObjectExpansion::TreeContents`][super::ObjectExpansion::TreeContents] and all their ancestors, as is needed for clones.
+/// Providing the objects the other side already has as `exclude` produces the objects needed for a fetch.
+///
+/// * `db` - the object store to use for accessing objects not covered by bitmaps.
+/// * `tips` - the objects whose closure to add to the pack.
+/// * `exclude` - the objects whose closure is not to be added to the pack.
+/// * `objects` - count the amount of objects we encounter.
+/// * `should_interrupt` - a flag that is set to true if the operation should stop.
+///
+/// The counts are returned in pack order for objects contained in the pack or multi-pack index of `reachability`, followed by
+/// all other objects. Their pack location is looked up when [entries are created][crate::data::output::entry::iter_from_counts()].
+pub fn objects_with_bitmap(
+    db: &dyn crate::Find,
+    reachability: &crate::bitmap::Reachability<'_>,
+    tips: &mut dyn Iterator<Item = Result<ObjectId, Box<dyn std::error::Error + Send + Sync + 'static>>>,
+    exclude: &mut dyn Iterator<Item = Result<ObjectId, Box<dyn std::error::Error + Send + Sync + 'static>>>,
+    objects: &dyn gix_features::progress::Count,
+    should_interrupt: &AtomicBool,
+) -> Result<(Vec<output::Count>, Outcome), Error> {
+    let tips = tips.collect::<Result<Vec<_>, _>>().map_err(Error::InputIteration)?;
+    let exclude = exclude.collect::<Result<Vec<_>, _>>().map_err(Error::InputIteration)?;
+    let db = Objects(db);
+
+    let mut outcome = Outcome {
+        input_objects: tips.len(),
+        ..Default::default()
+    };
+    let mut reachable = reachability.reachable_from(tips, &db, should_interrupt)?;
+    outcome.decoded_objects += reachable.decoded_objects;
+    if !exclude.is_empty() {
+        let excluded = reachability.reachable_from(exclude, &db, should_interrupt)?;
+        outcome.decoded_objects += excluded.decoded_objects;
+        reachable.remove(&excluded);
+    }
+
+    let out: Vec<_> = reachability
+        .iter(&reachable)
+        .map(|id| output::Count {
+            id,
+            entry_pack_location: PackLocation::NotLookedUp,
+        })
+        .collect();
+    objects
+        .counter()
+        .fetch_add(out.len(), std::sync::atomic::Ordering::Relaxed);
+    outcome.total_objects = out.len();
+    outcome.expanded_objects = out.len().saturating_sub(outcome.input_objects);
+    Ok((out, outcome))
+}
+
+struct Objects<'a>(&'a dyn crate::Find);
+
+impl gix_object::Find for Objects<'_> {
+    fn try_find<'a>(
+        &self,
+        id: &oid,
+        buffer: &'a mut Vec<u8>,
+    ) -> Result<Option<gix_object::Data<'a>>, gix_object::find::Error> {
+        Ok(self.0.try_find(id, buffer)?.map(|t| t.0))
+    }
+}
@@ -91,6 +91,9 @@ pub enum Error {
     TreeTraverse(gix_traverse::tree::breadthfirst::Error),
     #[error(transparent)]
     TreeChanges(gix_diff::tree::changes::Error),
+    #[cfg(feature = "bitmap")]
+    #[error(transparent)]
+    Reachability(#[from] crate::bitmap::reachability::Error),
     #[error("Operation interrupted")]
     Interrupted,
 }
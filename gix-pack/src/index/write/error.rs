@@ -22,4 +22,6 @@ pub enum Error {
     Tree(#[from] crate::cache::delta::Error),
     #[error(transparent)]
     TreeTraversal(#[from] crate::cache::delta::traverse::Error),
+    #[error("An object in the pack is part of a hash collision attack")]
+    CollisionAttack(#[from] gix_features::hash::Error),
 }
@@ -182,31 +182,36 @@ impl crate::index::File {
 
         let (resolver, pack) = make_resolver()?;
         let sorted_pack_offsets_by_oid = {
-            let traverse::Outcome { roots, children } = tree.traverse(
-                resolver,
-                &pack,
-                pack_entries_end,
-                |data,
-                 _progress,
-                 traverse::Context {
-                     entry,
-                     decompressed: bytes,
-                     ..
-                 }| {
-                    modify_base(data, entry, bytes, version.hash());
-                    Ok::<_, Error>(())
-                },
-                traverse::Options {
-                    object_progress: Box::new(
-                        root_progress.add_child_with_id("Resolving".into(), ProgressId::ResolveObjects.into()),
-                    ),
-                    size_progress: &mut root_progress
-                        .add_child_with_id("Decoding".into(), ProgressId::DecodedBytes.into()),
-                    thread_limit,
-                    should_interrupt,
-                    object_hash,
-                },
-            )?;
+            let traverse::Outcome { roots, children } = tree
+                .traverse(
+                    resolver,
+                    &pack,
+                    pack_entries_end,
+                    |data,
+                     _progress,
+                     traverse::Context {
+                         entry,
+                         decompressed: bytes,
+                         ..
+                     }| { modify_base(data, entry, bytes, object_hash) },
+                    traverse::Options {
+                        object_progress: Box::new(
+                            root_progress.add_child_with_id("Resolving".into(), ProgressId::ResolveObjects.into()),
+                        ),
+                        size_progress: &mut root_progress
+                            .add_child_with_id("Decoding".into(), ProgressId::DecodedBytes.into()),
+                        thread_limit,
+                        should_interrupt,
+                        object_hash,
+                    },
+                )
+                .map_err(|err| match err {
+                    traverse::Error::Inspect(err) => match err.downcast::<gix_features::hash::Error>() {
+                        Ok(err) => Error::CollisionAttack(*err),
+                        Err(err) => traverse::Error::Inspect(err).into(),
+                    },
+                    err => err.into(),
+                })?;
             root_progress.inc();
 
             let mut items = roots;
@@ -227,7 +232,7 @@ impl crate::index::File {
                 let header = crate::data::header::encode(pack_version, 0);
                 let mut hasher = gix_features::hash::hasher(object_hash);
                 hasher.update(&header);
-                gix_hash::ObjectId::from(hasher.digest())
+                hasher.digest()
             }
             None => return Err(Error::IteratorInvariantTrailer),
         };
@@ -253,8 +258,13 @@ impl crate::index::File {
     }
 }
 
-fn modify_base(entry: &mut TreeEntry, pack_entry: &crate::data::Entry, decompressed: &[u8], hash: gix_hash::Kind) {
+fn modify_base(
+    entry: &mut TreeEntry,
+    pack_entry: &crate::data::Entry,
+    decompressed: &[u8],
+    hash: gix_hash::Kind,
+) -> Result<(), gix_features::hash::Error> {
     let object_kind = pack_entry.header.as_kind().expect("base object as source of iteration");
-    let id = gix_object::compute_hash(hash, object_kind, decompressed);
-    entry.id = id;
+    entry.id = gix_object::try_compute_hash(hash, object_kind, decompressed)?;
+    Ok(())
 }
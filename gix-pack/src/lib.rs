@@ -31,6 +31,10 @@ pub struct Bundle {
 ///
 pub mod find;
 
+///
+#[cfg(feature = "bitmap")]
+pub mod bitmap;
+
 ///
 pub mod cache;
 ///
@@ -129,6 +129,16 @@ impl File {
         (pack_index, pack_offset)
     }
 
+    /// Return the index of the entry at `position` in pseudo-pack order, in which objects are sorted by pack and by offset
+    /// within their pack, with the preferred pack first.
+    ///
+    /// This is only available if the reverse index chunk is present, which is typically the case when the multi-pack index
+    /// has a bitmap. Otherwise, it may be stored in a separate `.rev` file.
+    pub fn index_at_pseudo_pack_position(&self, position: u32) -> Option<EntryIndex> {
+        let ofs = self.reverse_index_ofs?;
+        (position < self.num_objects).then(|| crate::read_u32(&self.data[ofs + position as usize * 4..][..4]))
+    }
+
     /// Return an iterator over all entries within this file.
     pub fn iter(&self) -> impl Iterator<Item = Entry> + '_ {
         (0..self.num_objects).map(move |idx| {
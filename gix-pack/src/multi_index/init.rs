@@ -81,7 +81,9 @@ impl TryFrom<&Path> for File {
 
             let (object_hash, data) = data.split_at(1);
             let object_hash = gix_hash::Kind::try_from(object_hash[0])
-                .map_err(|unknown| Error::UnsupportedObjectHash { kind: unknown })?;
+                .ok()
+                .filter(|kind| gix_features::hash::is_supported(*kind))
+                .ok_or(Error::UnsupportedObjectHash { kind: object_hash[0] })?;
             let (num_chunks, data) = data.split_at(1);
             let num_chunks = num_chunks[0];
 
@@ -129,6 +131,17 @@ impl TryFrom<&Path> for File {
             })
             .ok()
             .transpose()?;
+        let reverse_index = chunks
+            .validated_usize_offset_by_id(chunk::reverse_index::ID, |offset| {
+                chunk::reverse_index::is_valid(&offset, num_objects)
+                    .then_some(offset)
+                    .ok_or(Error::InvalidChunkSize {
+                        id: chunk::reverse_index::ID,
+                        message: "The chunk with entries in pseudo-pack order doesn't have the correct size",
+                    })
+            })
+            .ok()
+            .transpose()?;
 
         let checksum_offset = chunks.highest_offset() as usize;
         let trailer = &data[checksum_offset..];
@@ -150,6 +163,7 @@ impl TryFrom<&Path> for File {
             lookup_ofs: lookup.start,
             offsets_ofs: offsets.start,
             large_offsets_ofs: large_offsets.map(|r| r.start),
+            reverse_index_ofs: reverse_index.map(|r| r.start),
             num_objects,
             num_indices,
         })
@@ -0,0 +1,76 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+function write_files() {
+  local base_dir=${1:?directory to write them into}
+  local num_files=${2:?amount of files to write}
+  local nonce=${3:?something to make files more unique}
+
+  mkdir -p "$base_dir"
+  for file_id in $(seq -w "$num_files"); do
+    seq "$file_id" > "$base_dir/$file_id"
+    echo "$nonce" >> "$base_dir/$file_id"
+  done
+}
+
+function make_history() {
+  local dirs=(. a b a/b)
+  git checkout -q -b main
+  for round in $(seq 8); do
+    write_files "${dirs[$(( round % ${#dirs[@]} ))]}" $(( round * 3 )) "$round"
+    git add .
+    git commit -qm "main $round"
+  done
+
+  git checkout -q -b side main~4
+  for round in $(seq 3); do
+    write_files side-dir $(( round * 2 )) "side $round"
+    git add .
+    git commit -qm "side $round"
+  done
+  git checkout -q main
+  git merge -q --no-ff -m "merge side" side
+  git tag -m "annotated" v1 main~2
+
+  mkdir -p a/b
+  echo "name-hashed content" > a/b/unique-file
+  git add a/b/unique-file
+  git commit -qm "unique file"
+}
+
+function store_expectations() {
+  git rev-list --objects main | cut -d' ' -f1 | sort > expected-main
+  git rev-list --objects main~3 | cut -d' ' -f1 | sort > expected-main-3
+  git rev-list --objects side | cut -d' ' -f1 | sort > expected-side
+  git rev-list --objects v1 | cut -d' ' -f1 | sort > expected-v1
+  git rev-list --objects main ^side | cut -d' ' -f1 | sort > expected-main-without-side
+  git rev-list --objects main ^main~2 | cut -d' ' -f1 | sort > expected-main-without-main-2
+  git rev-parse main main~3 side v1 main~2 > expected-ids
+  git rev-parse main:a/b/unique-file >> expected-ids
+}
+
+git init -q single-pack
+(cd single-pack
+  make_history
+  git -c pack.writeBitmapHashCache=true -c pack.writeBitmapLookupTable=true repack -adbq
+  store_expectations
+)
+
+git init -q single-pack-without-extensions
+(cd single-pack-without-extensions
+  make_history
+  git -c pack.writeBitmapHashCache=false -c pack.writeBitmapLookupTable=false -c pack.writeReverseIndex=true repack -adbq
+  store_expectations
+)
+
+git init -q multi-pack
+(cd multi-pack
+  make_history
+  git repack -adq
+  echo "only in the second pack" > second
+  git add second
+  git commit -qm "second pack"
+  git repack -dq
+  git -c pack.writeBitmapHashCache=true -c pack.writeBitmapLookupTable=true multi-pack-index write --bitmap
+  store_expectations
+)
@@ -0,0 +1,546 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use gix_hash::ObjectId;
+use gix_pack::bitmap;
+
+fn fixture(name: &str) -> crate::Result<PathBuf> {
+    Ok(crate::scripted_fixture_read_only("make_repo_with_bitmaps.sh")?.join(name))
+}
+
+fn pack_dir(repo: &Path) -> PathBuf {
+    repo.join(".git/objects/pack")
+}
+
+fn file_with_extension(dir: &Path, extension: &str) -> crate::Result<PathBuf> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().map_or(false, |ext| ext == extension) {
+            return Ok(path);
+        }
+    }
+    Err(format!("no file with extension '{extension}' in '{}'", dir.display()).into())
+}
+
+fn single_pack(repo: &Path) -> crate::Result<(gix_pack::index::File, bitmap::File)> {
+    let bitmap = bitmap::File::at(file_with_extension(&pack_dir(repo), "bitmap")?, gix_hash::Kind::Sha1)?;
+    let index = gix_pack::index::File::at(bitmap.path().with_extension("idx"), gix_hash::Kind::Sha1)?;
+    Ok((index, bitmap))
+}
+
+fn multi_pack(repo: &Path) -> crate::Result<(gix_pack::multi_index::File, bitmap::File)> {
+    let index = gix_pack::multi_index::File::at(pack_dir(repo).join("multi-pack-index"))?;
+    let bitmap = bitmap::File::at(file_with_extension(&pack_dir(repo), "bitmap")?, gix_hash::Kind::Sha1)?;
+    Ok((index, bitmap))
+}
+
+fn odb(repo: &Path) -> crate::Result<gix_odb::HandleArc> {
+    let store = gix_odb::Store::at_opts(
+        repo.join(".git/objects"),
+        &mut None.into_iter(),
+        gix_odb::store::init::Options::default(),
+    )?;
+    Ok(Arc::new(store).to_cache_arc())
+}
+
+struct Ids {
+    main: ObjectId,
+    main_3: ObjectId,
+    side: ObjectId,
+    v1: ObjectId,
+    main_2: ObjectId,
+    unique_blob: ObjectId,
+}
+
+fn ids(repo: &Path) -> crate::Result<Ids> {
+    let ids = std::fs::read_to_string(repo.join("expected-ids"))?
+        .lines()
+        .map(|hex| ObjectId::from_hex(hex.as_bytes()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Ids {
+        main: ids[0],
+        main_3: ids[1],
+        side: ids[2],
+        v1: ids[3],
+        main_2: ids[4],
+        unique_blob: ids[5],
+    })
+}
+
+fn expected(repo: &Path, name: &str) -> crate::Result<Vec<ObjectId>> {
+    Ok(std::fs::read_to_string(repo.join(format!("expected-{name}")))?
+        .lines()
+        .map(|hex| ObjectId::from_hex(hex.as_bytes()))
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+fn sorted(reachability: &bitmap::Reachability<'_>, reachable: &bitmap::reachability::Reachable) -> Vec<ObjectId> {
+    let mut out: Vec<_> = reachability.iter(reachable).collect();
+    out.sort();
+    out
+}
+
+/// Assert that all reachability queries match what `git rev-list --objects` produced.
+fn assert_reachability_matches_git(repo: &Path, reachability: &bitmap::Reachability<'_>) -> crate::Result {
+    let ids = ids(repo)?;
+    let db = odb(repo)?;
+    let should_interrupt = AtomicBool::default();
+    for (tips, name) in [
+        (ids.main, "main"),
+        (ids.main_3, "main-3"),
+        (ids.side, "side"),
+        (ids.v1, "v1"),
+    ] {
+        let reachable = reachability.reachable_from(Some(tips), &db, &should_interrupt)?;
+        assert_eq!(sorted(reachability, &reachable), expected(repo, name)?, "{name}");
+        assert_eq!(reachable.len(), expected(repo, name)?.len());
+    }
+
+    for (tip, exclude, name) in [
+        (ids.main, ids.side, "main-without-side"),
+        (ids.main, ids.main_2, "main-without-main-2"),
+    ] {
+        let mut reachable = reachability.reachable_from(Some(tip), &db, &should_interrupt)?;
+        reachable.remove(&reachability.reachable_from(Some(exclude), &db, &should_interrupt)?);
+        assert_eq!(sorted(reachability, &reachable), expected(repo, name)?, "{name}");
+    }
+
+    let reachable = reachability.reachable_from(Some(ids.main_3), &db, &should_interrupt)?;
+    assert!(reachability.contains(&reachable, &ids.main_3));
+    assert!(!reachability.contains(&reachable, &ids.main));
+    Ok(())
+}
+
+mod file {
+    use std::sync::atomic::AtomicBool;
+
+    use gix_pack::bitmap;
+
+    use super::{fixture, ids, single_pack};
+
+    #[test]
+    fn with_lookup_table_and_hash_cache() -> crate::Result {
+        let repo = fixture("single-pack")?;
+        let (index, bitmap) = single_pack(&repo)?;
+        assert_eq!(bitmap.version(), bitmap::Version::V1);
+        assert!(bitmap.has_lookup_table());
+        assert!(bitmap.has_hash_cache());
+        assert_eq!(bitmap.num_objects(), index.num_objects());
+        assert_eq!(bitmap.index_checksum(), index.pack_checksum());
+        assert_eq!(
+            bitmap.verify_checksum(&mut gix_features::progress::Discard, &AtomicBool::default())?,
+            bitmap.checksum()
+        );
+        assert!(!bitmap.entries().is_empty(), "there are bitmaps for at least the tips");
+        assert!(
+            bitmap
+                .entries()
+                .windows(2)
+                .all(|w| w[0].commit_index < w[1].commit_index),
+            "entries are sorted for lookup"
+        );
+
+        let ids = ids(&repo)?;
+        let blob_index = index.lookup(ids.unique_blob).expect("blob is present");
+        assert_eq!(
+            bitmap.name_hash_at_index(blob_index),
+            Some(bitmap::name_hash(b"a/b/unique-file")),
+            "git stores the name-hash of the path at which it first saw the object"
+        );
+        assert_eq!(bitmap.name_hash_at_index(index.num_objects()), None);
+        Ok(())
+    }
+
+    #[test]
+    fn without_extensions() -> crate::Result {
+        let repo = fixture("single-pack-without-extensions")?;
+        let (index, bitmap) = single_pack(&repo)?;
+        assert!(!bitmap.has_lookup_table());
+        assert!(!bitmap.has_hash_cache());
+        assert_eq!(bitmap.name_hash_at_index(0), None);
+        assert_eq!(bitmap.num_objects(), index.num_objects());
+
+        let with_extensions = fixture("single-pack")?;
+        let (_, bitmap_with_extensions) = single_pack(&with_extensions)?;
+        assert_eq!(
+            bitmap.entries().len(),
+            bitmap_with_extensions.entries().len(),
+            "both repositories are the same, and so are the selected commits"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn object_kinds() -> crate::Result {
+        let repo = fixture("single-pack")?;
+        let (index, bitmap) = single_pack(&repo)?;
+        let order = bitmap::Order::from_index(&index)?;
+        let ids = ids(&repo)?;
+        for (id, kind) in [
+            (ids.main, gix_object::Kind::Commit),
+            (ids.v1, gix_object::Kind::Tag),
+            (ids.unique_blob, gix_object::Kind::Blob),
+        ] {
+            let bit = order.bit_at_index(index.lookup(id).expect("present"));
+            assert_eq!(bitmap.kind_at_bit(bit as usize), Some(kind));
+        }
+        let total: usize = [
+            gix_object::Kind::Commit,
+            gix_object::Kind::Tree,
+            gix_object::Kind::Blob,
+            gix_object::Kind::Tag,
+        ]
+        .into_iter()
+        .map(|kind| bitmap.objects_of_kind(kind).count_ones())
+        .sum();
+        assert_eq!(total, index.num_objects() as usize);
+        Ok(())
+    }
+}
+
+mod order {
+    use gix_pack::bitmap::Order;
+
+    use super::{fixture, single_pack};
+
+    #[test]
+    fn rev_file_matches_computed_order() -> crate::Result {
+        let repo = fixture("single-pack-without-extensions")?;
+        let (index, _) = single_pack(&repo)?;
+        assert!(index.path().with_extension("rev").is_file());
+        let order = Order::from_index(&index)?;
+        assert_eq!(order.len(), index.num_objects() as usize);
+
+        let mut offsets: Vec<_> = (0..index.num_objects())
+            .map(|idx| (index.pack_offset_at_index(idx), idx))
+            .collect();
+        offsets.sort();
+        assert_eq!(
+            order,
+            Order::from_index_by_bit(offsets.into_iter().map(|(_, idx)| idx).collect())
+        );
+        for idx in 0..index.num_objects() {
+            assert_eq!(order.index_at_bit(order.bit_at_index(idx)), idx);
+        }
+        Ok(())
+    }
+}
+
+mod reachability {
+    use gix_pack::bitmap;
+
+    use super::{assert_reachability_matches_git, fixture, multi_pack, single_pack};
+
+    #[test]
+    fn single_pack_with_lookup_table() -> crate::Result {
+        let repo = fixture("single-pack")?;
+        let (index, bitmap) = single_pack(&repo)?;
+        assert_reachability_matches_git(&repo, &bitmap::Reachability::new(&bitmap, &index)?)
+    }
+
+    #[test]
+    fn single_pack_without_lookup_table() -> crate::Result {
+        let repo = fixture("single-pack-without-extensions")?;
+        let (index, bitmap) = single_pack(&repo)?;
+        assert_reachability_matches_git(&repo, &bitmap::Reachability::new(&bitmap, &index)?)
+    }
+
+    #[test]
+    fn multi_pack_index() -> crate::Result {
+        let repo = fixture("multi-pack")?;
+        let (index, bitmap) = multi_pack(&repo)?;
+        assert_eq!(bitmap.index_checksum(), index.checksum());
+        assert!(
+            index.index_at_pseudo_pack_position(0).is_some(),
+            "git writes the reverse index chunk along with the bitmap"
+        );
+        assert_reachability_matches_git(&repo, &bitmap::Reachability::new(&bitmap, &index)?)
+    }
+
+    #[test]
+    fn bitmap_must_belong_to_index() -> crate::Result {
+        let (index, _) = single_pack(&fixture("single-pack")?)?;
+        let (_, other_bitmap) = multi_pack(&fixture("multi-pack")?)?;
+        assert!(matches!(
+            bitmap::Reachability::new(&other_bitmap, &index),
+            Err(bitmap::reachability::init::Error::ChecksumMismatch { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn commit_bitmaps_contain_their_commit() -> crate::Result {
+        let repo = fixture("single-pack")?;
+        let (index, bitmap) = single_pack(&repo)?;
+        let reachability = bitmap::Reachability::new(&bitmap, &index)?;
+        for entry in bitmap.entries() {
+            let id = index.oid_at_index(entry.commit_index);
+            let commit_bitmap = reachability.commit_bitmap(id)?.expect("entry has bitmap");
+            let bit = reachability.bit_of(id).expect("present");
+            assert!(commit_bitmap.get(bit as usize));
+            assert_eq!(reachability.oid_at_bit(bit), id);
+        }
+        Ok(())
+    }
+}
+
+mod indexed {
+    use gix_pack::bitmap;
+
+    use super::{assert_reachability_matches_git, fixture, pack_dir};
+
+    #[test]
+    fn at_pack_dir() -> crate::Result {
+        for (name, is_multi_pack) in [("single-pack", false), ("multi-pack", true)] {
+            let repo = fixture(name)?;
+            let indexed =
+                bitmap::Indexed::at_pack_dir(pack_dir(&repo), gix_hash::Kind::Sha1)?.expect("bitmap is present");
+            assert_eq!(
+                matches!(indexed.index, bitmap::indexed::IndexFile::MultiPack(_)),
+                is_multi_pack,
+                "the bitmap of the multi-pack index is preferred"
+            );
+            assert_reachability_matches_git(&repo, &indexed.reachability()?)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn at_pack_dir_without_bitmap() -> crate::Result {
+        let dir = gix_testtools::tempfile::TempDir::new()?;
+        assert!(bitmap::Indexed::at_pack_dir(dir.path(), gix_hash::Kind::Sha1)?.is_none());
+        assert!(
+            bitmap::Indexed::at_pack_dir(dir.path().join("does-not-exist"), gix_hash::Kind::Sha1)?.is_none(),
+            "a missing directory has no bitmaps either"
+        );
+        Ok(())
+    }
+}
+
+mod count {
+    use std::sync::atomic::AtomicBool;
+
+    use gix_features::progress;
+    use gix_pack::{bitmap, data::output};
+
+    use super::{expected, fixture, ids, odb, single_pack};
+
+    #[test]
+    fn objects_with_bitmap() -> crate::Result {
+        let repo = fixture("single-pack")?;
+        let (index, bitmap) = single_pack(&repo)?;
+        let reachability = bitmap::Reachability::new(&bitmap, &index)?;
+        let ids = ids(&repo)?;
+        let db = odb(&repo)?;
+
+        let (counts, outcome) = output::count::objects_with_bitmap(
+            &db,
+            &reachability,
+            &mut Some(Ok(ids.main)).into_iter(),
+            &mut Some(Ok(ids.side)).into_iter(),
+            &progress::Discard,
+            &AtomicBool::default(),
+        )?;
+        let mut actual: Vec<_> = counts.iter().map(|c| c.id).collect();
+        actual.sort();
+        assert_eq!(actual, expected(&repo, "main-without-side")?);
+        assert_eq!(outcome.input_objects, 1);
+        assert_eq!(outcome.total_objects, counts.len());
+        assert!(
+            counts
+                .iter()
+                .all(|c| c.entry_pack_location == output::count::PackLocation::NotLookedUp),
+            "locations are looked up later when creating entries"
+        );
+
+        let (counts, outcome) = output::count::objects_with_bitmap(
+            &db,
+            &reachability,
+            &mut Some(Ok(ids.main)).into_iter(),
+            &mut None.into_iter(),
+            &progress::Discard,
+            &AtomicBool::default(),
+        )?;
+        assert_eq!(counts.len(), expected(&repo, "main")?.len(), "a clone of main");
+        assert_eq!(
+            outcome.decoded_objects, 0,
+            "the tip has a bitmap, so nothing needs to be decoded"
+        );
+        Ok(())
+    }
+}
+
+mod write {
+    use std::sync::atomic::AtomicBool;
+
+    use gix_features::progress;
+    use gix_object::Kind;
+    use gix_pack::bitmap::{self, write::Candidate};
+    use gix_testtools::tempfile::TempDir;
+
+    use super::{assert_reachability_matches_git, fixture, ids, pack_dir, single_pack};
+
+    #[test]
+    fn from_bundle_matches_git() -> crate::Result {
+        let repo = fixture("single-pack")?;
+        let (index, git_bitmap) = single_pack(&repo)?;
+        let bundle = gix_pack::Bundle::at(git_bitmap.path().with_extension("pack"), gix_hash::Kind::Sha1)?;
+
+        let dir = TempDir::new()?;
+        for (options, name) in [
+            (bitmap::write::Options::default(), "all-extensions"),
+            (
+                bitmap::write::Options {
+                    hash_cache: false,
+                    lookup_table: false,
+                },
+                "no-extensions",
+            ),
+        ] {
+            let path = dir.path().join(format!("{name}.bitmap"));
+            let outcome = bitmap::File::write_from_bundle(
+                &bundle,
+                &mut std::fs::File::create(&path)?,
+                &mut progress::Discard,
+                &AtomicBool::default(),
+                options,
+            )?;
+            let bitmap = bitmap::File::at(&path, gix_hash::Kind::Sha1)?;
+            assert_eq!(bitmap.checksum(), outcome.checksum);
+            bitmap.verify_checksum(&mut progress::Discard, &AtomicBool::default())?;
+            assert_eq!(bitmap.has_hash_cache(), options.hash_cache);
+            assert_eq!(bitmap.has_lookup_table(), options.lookup_table);
+            assert_eq!(bitmap.index_checksum(), index.pack_checksum());
+            assert_eq!(bitmap.num_objects(), index.num_objects());
+            assert_eq!(
+                outcome.num_bitmaps,
+                bitmap.objects_of_kind(Kind::Commit).count_ones(),
+                "with less than 100 commits, each one gets a bitmap"
+            );
+            assert_eq!(bitmap.entries().len(), outcome.num_bitmaps);
+            for kind in [Kind::Commit, Kind::Tree, Kind::Blob, Kind::Tag] {
+                assert_eq!(bitmap.objects_of_kind(kind), git_bitmap.objects_of_kind(kind), "{kind}");
+            }
+            if options.hash_cache {
+                let blob_index = index.lookup(ids(&repo)?.unique_blob).expect("present");
+                assert_eq!(
+                    bitmap.name_hash_at_index(blob_index),
+                    git_bitmap.name_hash_at_index(blob_index)
+                );
+            }
+
+            let reachability = bitmap::Reachability::new(&bitmap, &index)?;
+            let git_reachability = bitmap::Reachability::new(&git_bitmap, &index)?;
+            for entry in git_bitmap.entries() {
+                let id = index.oid_at_index(entry.commit_index);
+                assert_eq!(
+                    reachability.commit_bitmap(id)?,
+                    git_reachability.commit_bitmap(id)?,
+                    "{id}"
+                );
+            }
+            assert_reachability_matches_git(&repo, &reachability)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn alongside_pack_written_to_directory() -> crate::Result {
+        let repo = fixture("single-pack")?;
+        let (index, _) = single_pack(&repo)?;
+        let dir = TempDir::new()?;
+        let pack_path = super::file_with_extension(&pack_dir(&repo), "pack")?;
+        let outcome = gix_pack::Bundle::write_to_directory(
+            &mut std::io::BufReader::new(std::fs::File::open(pack_path)?),
+            Some(dir.path()),
+            &mut progress::Discard,
+            &AtomicBool::default(),
+            None::<gix_object::find::Never>,
+            gix_pack::bundle::write::Options {
+                bitmap: Some(Default::default()),
+                ..Default::default()
+            },
+        )?;
+        let bitmap_path = outcome.bitmap_path.expect("the pack is self-contained");
+        assert_eq!(
+            Some(bitmap_path.with_extension("pack")),
+            outcome.data_path,
+            "the bitmap is placed next to the pack"
+        );
+        let bitmap = bitmap::File::at(bitmap_path, gix_hash::Kind::Sha1)?;
+        assert_reachability_matches_git(&repo, &bitmap::Reachability::new(&bitmap, &index)?)
+    }
+
+    #[test]
+    fn not_for_packs_that_are_not_self_contained() -> crate::Result {
+        let repo = fixture("multi-pack")?;
+        let mut incremental_pack = None;
+        for entry in std::fs::read_dir(pack_dir(&repo))? {
+            let path = entry?.path();
+            if path.extension().map_or(false, |ext| ext == "idx") {
+                let index = gix_pack::index::File::at(&path, gix_hash::Kind::Sha1)?;
+                if incremental_pack
+                    .as_ref()
+                    .map_or(true, |(num_objects, _)| index.num_objects() < *num_objects)
+                {
+                    incremental_pack = Some((index.num_objects(), path.with_extension("pack")));
+                }
+            }
+        }
+        let (_, pack_path) = incremental_pack.expect("two packs");
+
+        let dir = TempDir::new()?;
+        let outcome = gix_pack::Bundle::write_to_directory(
+            &mut std::io::BufReader::new(std::fs::File::open(pack_path)?),
+            Some(dir.path()),
+            &mut progress::Discard,
+            &AtomicBool::default(),
+            None::<gix_object::find::Never>,
+            gix_pack::bundle::write::Options {
+                bitmap: Some(Default::default()),
+                ..Default::default()
+            },
+        )?;
+        assert!(outcome.index_path.is_some(), "the pack is still written");
+        assert_eq!(
+            outcome.bitmap_path, None,
+            "but there is no bitmap for it as the parent of its commit is in another pack"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn select_commits() {
+        let candidates = vec![Candidate::default(); 99];
+        assert_eq!(
+            bitmap::write::select_commits(&candidates),
+            (0..99).collect::<Vec<_>>(),
+            "with less than 100 commits, all are selected"
+        );
+
+        let mut candidates = vec![Candidate::default(); 250];
+        let expected_most_recent: Vec<_> = (0..=100).collect();
+        let selected = bitmap::write::select_commits(&candidates);
+        assert_eq!(
+            &selected[..101],
+            expected_most_recent,
+            "the most recent commits are all selected"
+        );
+        assert_eq!(
+            &selected[101..],
+            [102, 106, 114, 130, 162, 226],
+            "the oldest commit of each growing window is selected, and commits at the very end are skipped"
+        );
+
+        candidates[104].is_merge = true;
+        candidates[110].is_preferred = true;
+        candidates[112].is_merge = true;
+        assert_eq!(
+            &bitmap::write::select_commits(&candidates)[101..],
+            [102, 104, 110, 130, 162, 226],
+            "merges are preferred over the oldest commit, and preferred commits over merges"
+        );
+    }
+}
@@ -111,6 +111,7 @@ mod write_to_directory {
             index_path: None,
             data_path: None,
             keep_path: None,
+            bitmap_path: None,
             object_hash: gix_hash::Kind::Sha1,
         })
     }
@@ -176,6 +177,7 @@ mod write_to_directory {
                 iteration_mode: pack::data::input::Mode::Verify,
                 index_version: pack::index::Version::V2,
                 object_hash: gix_hash::Kind::Sha1,
+                bitmap: None,
             },
         )
         .map_err(Into::into)
@@ -30,10 +30,11 @@ mod lookup_ref_delta_objects {
     }
 
     fn entry(header: Header, data: &'static [u8]) -> input::Entry {
-        let obj = gix_object::Data {
-            kind: header.as_kind().unwrap_or(gix_object::Kind::Blob),
+        let obj = gix_object::Data::new(
+            header.as_kind().unwrap_or(gix_object::Kind::Blob),
             data,
-        };
+            gix_hash::Kind::Sha1,
+        );
         let mut entry = input::Entry::from_data_obj(&obj, 0).expect("valid object");
         entry.header = header;
         entry.header_size = header.size(data.len() as u64) as u16;
@@ -78,15 +79,16 @@ mod lookup_ref_delta_objects {
     }
 
     impl gix_object::Find for FindData<'_> {
-        fn try_find<'a>(&self, _id: &oid, buf: &'a mut Vec<u8>) -> Result<Option<Data<'a>>, Error> {
+        fn try_find<'a>(&self, id: &oid, buf: &'a mut Vec<u8>) -> Result<Option<Data<'a>>, Error> {
             self.calls.fetch_add(1, Ordering::Relaxed);
             if let Some(data) = self.data {
                 buf.resize(data.len(), 0);
                 buf.copy_from_slice(data);
-                Ok(Some(gix_object::Data {
-                    kind: gix_object::Kind::Blob,
-                    data: buf.as_slice(),
-                }))
+                Ok(Some(gix_object::Data::new(
+                    gix_object::Kind::Blob,
+                    buf.as_slice(),
+                    id.kind(),
+                )))
             } else {
                 Ok(None)
             }
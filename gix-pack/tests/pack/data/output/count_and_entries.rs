@@ -336,6 +336,95 @@ fn empty_pack_is_allowed() {
     );
 }
 
+#[test]
+fn sha256_packs_can_be_written_indexed_and_decoded() -> crate::Result {
+    use gix_object::WriteTo;
+    let object_hash = gix_hash::Kind::Sha256;
+    let blob = b"hello".to_vec();
+    let blob_id = gix_object::compute_hash(object_hash, gix_object::Kind::Blob, &blob);
+    let mut tree = Vec::new();
+    gix_object::Tree {
+        entries: vec![gix_object::tree::Entry {
+            mode: gix_object::tree::EntryKind::Blob.into(),
+            filename: "file".into(),
+            oid: blob_id,
+        }],
+    }
+    .write_to(&mut tree)?;
+    let tree_id = gix_object::compute_hash(object_hash, gix_object::Kind::Tree, &tree);
+    let entries = [
+        (blob_id, gix_object::Kind::Blob, &blob),
+        (tree_id, gix_object::Kind::Tree, &tree),
+    ]
+    .into_iter()
+    .map(|(id, kind, data)| {
+        output::Entry::from_data(
+            &output::Count::from_data(id, None),
+            &gix_object::Data::new(kind, data, object_hash),
+        )
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+
+    let tmp_dir = gix_testtools::tempfile::TempDir::new()?;
+    let mut pack = Vec::new();
+    let mut pack_writer = output::bytes::FromEntriesIter::new(
+        std::iter::once(Ok::<_, entry::iter_from_counts::Error>(entries)),
+        &mut pack,
+        2,
+        pack::data::Version::V2,
+        object_hash,
+    );
+    for res in pack_writer.by_ref() {
+        res?;
+    }
+    let pack_hash = pack_writer.digest().expect("done");
+    assert_eq!(pack_hash.kind(), object_hash);
+
+    let should_interrupt = AtomicBool::new(false);
+    let outcome = pack::Bundle::write_to_directory(
+        &mut pack.as_slice(),
+        Some(tmp_dir.path()),
+        &mut progress::Discard,
+        &should_interrupt,
+        None::<gix_object::find::Never>,
+        pack::bundle::write::Options {
+            object_hash,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(outcome.index.data_hash, pack_hash);
+    let bundle = pack::Bundle::at(outcome.data_path.expect("written"), object_hash)?;
+    assert_eq!(bundle.index.num_objects(), 2);
+
+    let mut buf = Vec::new();
+    let (tree, _location) = bundle
+        .find(
+            &tree_id,
+            &mut buf,
+            &mut gix_features::zlib::Inflate::default(),
+            &mut pack::cache::Never,
+        )?
+        .expect("tree is present");
+    let tree = tree.decode()?.into_tree().expect("is tree");
+    assert_eq!(
+        tree.entries[0].oid,
+        blob_id.as_ref(),
+        "the tree is decoded with 32 byte ids"
+    );
+
+    bundle.verify_integrity(
+        &mut progress::Discard,
+        &should_interrupt,
+        gix_pack::index::verify::integrity::Options {
+            verify_mode: pack::index::verify::Mode::HashCrc32DecodeEncode,
+            traversal: pack::index::traverse::Algorithm::Lookup,
+            make_pack_lookup_cache: || pack::cache::Never,
+            thread_limit: None,
+        },
+    )?;
+    Ok(())
+}
+
 fn write_and_verify(
     db: gix_odb::HandleArc,
     entries: Vec<output::Entry>,
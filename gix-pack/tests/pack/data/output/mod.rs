@@ -6,7 +6,7 @@ use gix_pack::data::output;
 fn size_of_entry() {
     assert_eq!(
         std::mem::size_of::<output::Entry>(),
-        80,
+        112,
         "The size of the structure shouldn't change unexpectedly"
     );
 }
@@ -15,7 +15,7 @@ fn size_of_entry() {
 fn size_of_count() {
     assert_eq!(
         std::mem::size_of::<output::Count>(),
-        56,
+        72,
         "The size of the structure shouldn't change unexpectedly"
     );
 }
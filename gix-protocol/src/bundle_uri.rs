@@ -0,0 +1,93 @@
+mod error {
+    use bstr::BString;
+
+    /// The error returned by [`bundle_uri()`][crate::bundle_uri()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Io(#[from] std::io::Error),
+        #[error(transparent)]
+        Transport(#[from] gix_transport::client::Error),
+        #[error(transparent)]
+        DecodePacketline(#[from] gix_transport::packetline::decode::Error),
+        #[error("The bundle-uri line {line:?} isn't a key-value pair separated by '='")]
+        MalformedLine { line: BString },
+    }
+
+    impl gix_transport::IsSpuriousError for Error {
+        fn is_spurious(&self) -> bool {
+            match self {
+                Error::Io(err) => err.is_spurious(),
+                Error::Transport(err) => err.is_spurious(),
+                _ => false,
+            }
+        }
+    }
+}
+pub use error::Error;
+
+pub(crate) mod function {
+    use bstr::{BString, ByteSlice};
+    use gix_features::progress::Progress;
+    use gix_transport::client::{Capabilities, Transport, TransportV2Ext};
+    use maybe_async::maybe_async;
+
+    use super::Error;
+    use crate::{command::Feature, Command};
+
+    /// Invoke the `bundle-uri` V2 command on `transport`, which requires a prior handshake that yielded
+    /// server `capabilities` which advertise it, and return the key-value pairs of the bundle list the server sent,
+    /// like `("bundle.version", "1")`.
+    ///
+    /// `features` are sent along with the command, and are expected to contain the `(agent, Some(name))` of the client.
+    /// `progress` is used to provide feedback.
+    /// If `trace` is `true`, all packetlines received or sent will be passed to the facilities of the `gix-trace` crate.
+    #[maybe_async]
+    pub async fn bundle_uri(
+        mut transport: impl Transport,
+        capabilities: &Capabilities,
+        features: impl IntoIterator<Item = Feature>,
+        progress: &mut impl Progress,
+        trace: bool,
+    ) -> Result<Vec<(BString, BString)>, Error> {
+        let _span = gix_features::trace::detail!("gix_protocol::bundle_uri()", capabilities = ?capabilities);
+        let bundle_uri = Command::BundleUri;
+        let mut bundle_uri_features = bundle_uri.default_features(gix_transport::Protocol::V2, capabilities);
+        bundle_uri_features.extend(features);
+        bundle_uri.validate_argument_prefixes_or_panic(
+            gix_transport::Protocol::V2,
+            capabilities,
+            &[],
+            &bundle_uri_features,
+        );
+
+        progress.step();
+        progress.set_name("list bundles".into());
+        let mut response = transport
+            .invoke(
+                bundle_uri.as_str(),
+                bundle_uri_features.into_iter(),
+                None::<std::iter::Empty<BString>>,
+                trace,
+            )
+            .await?;
+        let mut pairs = Vec::new();
+        while let Some(line) = response
+            .readline()
+            .await
+            .transpose()?
+            .transpose()?
+            .and_then(|line| line.as_bstr())
+        {
+            let line = line.strip_suffix(b"\n").unwrap_or(line);
+            let (key, value) = line
+                .find_byte(b'=')
+                .map(|pos| (&line[..pos], &line[pos + 1..]))
+                .filter(|(key, _)| !key.is_empty())
+                .ok_or_else(|| Error::MalformedLine { line: line.into() })?;
+            pairs.push((key.into(), value.into()));
+        }
+        Ok(pairs)
+    }
+}
@@ -12,6 +12,8 @@ impl Command {
         match self {
             Command::LsRefs => "ls-refs",
             Command::Fetch => "fetch",
+            Command::BundleUri => "bundle-uri",
+            Command::ObjectInfo => "object-info",
         }
     }
 }
@@ -28,6 +30,8 @@ mod with_io {
         fn all_argument_prefixes(&self) -> &'static [&'static str] {
             match self {
                 Command::LsRefs => &["symrefs", "peel", "ref-prefix ", "unborn"],
+                Command::BundleUri => &[],
+                Command::ObjectInfo => &["size", "oid "],
                 Command::Fetch => &[
                     "want ", // hex oid
                     "have ", // hex oid
@@ -58,7 +62,7 @@ mod with_io {
 
         fn all_features(&self, version: gix_transport::Protocol) -> &'static [&'static str] {
             match self {
-                Command::LsRefs => &[],
+                Command::LsRefs | Command::BundleUri | Command::ObjectInfo => &[],
                 Command::Fetch => match version {
                     gix_transport::Protocol::V0 | gix_transport::Protocol::V1 => &[
                         "multi_ack",
@@ -98,25 +102,44 @@ mod with_io {
                     .iter()
                     .map(|s| s.as_bytes().as_bstr().to_owned())
                     .chain(
-                        [
-                            "sideband-all",
-                            /* "packfile-uris" */ // packfile-uris must be configurable and can't just be used. Some servers advertise it and reject it later.
-                        ]
-                        .iter()
-                        .filter(|f| features.iter().any(|(sf, _)| sf == *f))
-                        .map(|f| f.as_bytes().as_bstr().to_owned()),
+                        // packfile-uris must be configurable and can't just be used. Some servers advertise it and reject it later.
+                        // Hence it's set by the caller along with the protocols it can download packs with.
+                        ["sideband-all"]
+                            .iter()
+                            .filter(|f| features.iter().any(|(sf, _)| sf == *f))
+                            .map(|f| f.as_bytes().as_bstr().to_owned()),
                     )
                     .collect(),
                 Command::LsRefs => vec![b"symrefs".as_bstr().to_owned(), b"peel".as_bstr().to_owned()],
+                Command::BundleUri | Command::ObjectInfo => Vec::new(),
             }
         }
 
         /// Turns on all modern features for V1 and all supported features for V2, returning them as a vector of features.
         /// Note that this is the basis for any fetch operation as these features fulfil basic requirements and reasonably up-to-date servers.
+        ///
+        /// If the server advertises an `object-format` other than the implied `sha1`, the same is requested to let it know
+        /// we are compatible.
         pub fn default_features(
             &self,
             version: gix_transport::Protocol,
             server_capabilities: &Capabilities,
+        ) -> Vec<Feature> {
+            let mut features = self.default_features_inner(version, server_capabilities);
+            if let Some(object_format) = server_capabilities
+                .capability("object-format")
+                .and_then(|c| c.value().map(|v| v.to_str_lossy().into_owned()))
+                .filter(|object_format| object_format != "sha1")
+            {
+                features.push(("object-format", Some(object_format.into())));
+            }
+            features
+        }
+
+        fn default_features_inner(
+            &self,
+            version: gix_transport::Protocol,
+            server_capabilities: &Capabilities,
         ) -> Vec<Feature> {
             match self {
                 Command::Fetch => match version {
@@ -154,7 +177,7 @@ mod with_io {
                             .collect()
                     }
                 },
-                Command::LsRefs => vec![],
+                Command::LsRefs | Command::BundleUri | Command::ObjectInfo => vec![],
             }
         }
         /// Panics if the given arguments and features don't match what's statically known. It's considered a bug in the delegate.
@@ -200,7 +223,7 @@ mod with_io {
                             continue;
                         }
                         match *feature {
-                            "agent" => {}
+                            "agent" | "object-format" => {}
                             _ => panic!("{}: V2 feature/capability {} is not supported", self.as_str(), feature),
                         }
                     }
@@ -46,6 +46,17 @@ mod v1 {
                     "we don't enforce no-progress"
                 );
             }
+
+            #[test]
+            fn the_advertised_object_format_is_requested() {
+                assert_eq!(
+                    Command::Fetch.default_features(
+                        gix_transport::Protocol::V1,
+                        &capabilities("ofs-delta object-format=sha256")
+                    ),
+                    &[("ofs-delta", None), ("object-format", Some("sha256".into()))]
+                );
+            }
         }
     }
 }
@@ -93,7 +104,7 @@ mod v2 {
                         .iter()
                         .map(|s| s.as_bytes().as_bstr().to_owned())
                         .collect::<Vec<_>>(),
-                    "packfile-uris isn't really supported that well and we don't support it either yet"
+                    "packfile-uris needs to be requested along with the protocols the client can download packs with"
                 );
             }
         }
@@ -101,6 +112,8 @@ mod v2 {
 
     mod ls_refs {
         mod default_features {
+            use gix_transport::client::Capabilities;
+
             use crate::{command::tests::v2::capabilities, Command};
 
             #[test]
@@ -113,6 +126,20 @@ mod v2 {
                     &[]
                 );
             }
+
+            #[test]
+            fn the_advertised_object_format_is_requested() {
+                let capabilities = Capabilities::from_lines("version 2\nls-refs\nobject-format=sha256".into())
+                    .expect("valid input for V2 capabilities");
+                let features = Command::LsRefs.default_features(gix_transport::Protocol::V2, &capabilities);
+                assert_eq!(features, &[("object-format", Some("sha256".into()))]);
+                Command::LsRefs.validate_argument_prefixes_or_panic(
+                    gix_transport::Protocol::V2,
+                    &capabilities,
+                    &[],
+                    &features,
+                );
+            }
         }
 
         mod validate {
@@ -18,6 +18,7 @@ pub struct Arguments {
     deepen_not: bool,
     deepen_relative: bool,
     ref_in_want: bool,
+    packfile_uris: bool,
     supports_include_tag: bool,
 
     features_for_first_want: Option<Vec<String>>,
@@ -76,6 +77,12 @@ impl Arguments {
     pub fn can_use_ref_in_want(&self) -> bool {
         self.ref_in_want
     }
+    /// Return true if the 'packfile-uris' capability is supported.
+    ///
+    /// This allows the server to offload parts of the pack to be downloaded separately, in protocol v2 only.
+    pub fn can_use_packfile_uris(&self) -> bool {
+        self.packfile_uris
+    }
     /// Return true if the 'include-tag' capability is supported.
     pub fn can_use_include_tag(&self) -> bool {
         self.supports_include_tag
@@ -161,6 +168,18 @@ impl Arguments {
             self.prefixed("filter ", spec);
         }
     }
+    /// Let the server know that it may send URIs to packs with any of the given `protocols`, like `https`,
+    /// instead of sending all objects in the pack of its response.
+    ///
+    /// These show up in the [packfile URIs](crate::fetch::Response::packfile_uris()) of the response and have to be
+    /// downloaded separately. Nothing is sent if `protocols` is empty.
+    pub fn packfile_uris<'a>(&mut self, protocols: impl IntoIterator<Item = &'a str>) {
+        debug_assert!(self.packfile_uris, "'packfile-uris' feature required");
+        let protocols = protocols.into_iter().collect::<Vec<_>>();
+        if self.packfile_uris && !protocols.is_empty() {
+            self.prefixed("packfile-uris ", protocols.join(","));
+        }
+    }
     /// Permanently allow the server to include tags that point to commits or objects it would return.
     ///
     /// Needs to only be called once.
@@ -204,6 +223,7 @@ impl Arguments {
         let filter = has("filter");
         let shallow = has("shallow");
         let ref_in_want = has("ref-in-want");
+        let packfile_uris = has("packfile-uris");
         let mut deepen_since = shallow;
         let mut deepen_not = shallow;
         let mut deepen_relative = shallow;
@@ -243,6 +263,7 @@ impl Arguments {
             deepen_not,
             deepen_relative,
             ref_in_want,
+            packfile_uris,
             deepen_since,
             features_for_first_want,
             trace,
@@ -4,7 +4,7 @@ use gix_transport::{client, Protocol};
 
 use crate::fetch::{
     response,
-    response::{Acknowledgement, ShallowUpdate, WantedRef},
+    response::{Acknowledgement, PackfileUri, ShallowUpdate, V1Next, WantedRef},
     Response,
 };
 
@@ -33,15 +33,17 @@ fn parse_v2_section<'a, T>(
 impl Response {
     /// Parse a response of the given `version` of the protocol from `reader`.
     ///
-    /// `client_expects_pack` is only relevant for V1 stateful connections, and if `false`, causes us to stop parsing when seeing `NAK`,
-    /// and if `true` we will keep parsing until we get a pack as the client already signalled to the server that it's done.
-    /// This way of doing things allows us to exploit knowledge about more recent versions of the protocol, which keeps code easier
-    /// and more localized without having to support all the cruft that there is.
+    /// `client_expects_pack` and `wants_to_negotiate` are only relevant for V1, where the end of a response isn't marked,
+    /// and they are used to know where it ends instead.
+    /// `client_expects_pack` must be `true` if the client sent `done`, which is when the server sends a pack after all acknowledgements.
+    /// Otherwise, `wants_to_negotiate` must be `true` if the connection is stateful, as the server then waits for more `have` lines
+    /// after sending a `NAK`, or after its first plain `ACK` if neither `multi_ack` nor `multi_ack_detailed` are used.
+    /// Stateless connections end after each response, so with `wants_to_negotiate` set to `false` all of it is read, which may
+    /// include a pack if `ready` was sent along with `no-done`.
     ///
-    /// `wants_to_negotiate` should be `false` for clones which is when we don't have sent any haves. The reason for this flag to exist
-    /// is to predict how to parse V1 output only, and neither `client_expects_pack` nor `wants_to_negotiate` are relevant for V2.
-    /// This ugliness is in place to avoid having to resort to an [an even more complex ugliness](https://github.com/git/git/blob/9e49351c3060e1fa6e0d2de64505b7becf157f28/fetch-pack.c#L583-L594)
-    /// that `git` has to use to predict how many acks are supposed to be read. We also genuinely hope that this covers it all….
+    /// This allows us to know exactly where responses end as long as each response is read before sending the next request,
+    /// without having to [predict how many acknowledgements to expect](https://github.com/git/git/blob/9e49351c3060e1fa6e0d2de64505b7becf157f28/fetch-pack.c#L583-L594)
+    /// like `git` does.
     pub fn from_line_reader<'a>(
         version: Protocol,
         reader: &mut impl client::ExtendedBufRead<'a>,
@@ -53,17 +55,11 @@ impl Response {
                 let mut line = String::new();
                 let mut acks = Vec::<Acknowledgement>::new();
                 let mut shallows = Vec::<ShallowUpdate>::new();
-                let mut saw_ready = false;
                 let has_pack = 'lines: loop {
                     line.clear();
                     let peeked_line = match reader.peek_data_line() {
                         Some(Ok(Ok(line))) => String::from_utf8_lossy(line),
-                        // This special case (hang/block forever) deals with a single NAK being a legitimate EOF sometimes
-                        // Note that this might block forever in stateful connections as there it's not really clear
-                        // if something will be following or not by just looking at the response. Instead you have to know
-                        // [a lot](https://github.com/git/git/blob/9e49351c3060e1fa6e0d2de64505b7becf157f28/fetch-pack.c#L583-L594)
-                        // to deal with this correctly.
-                        // For now this is acceptable, as V2 can be used as a workaround, which also is the default.
+                        // Stateless connections end each response, which may legitimately happen after a `NAK`.
                         Some(Err(err)) if err.kind() == io::ErrorKind::UnexpectedEof => break 'lines false,
                         Some(Err(err)) => return Err(err.into()),
                         Some(Ok(Err(err))) => return Err(err.into()),
@@ -85,16 +81,18 @@ impl Response {
                         }
                     };
 
-                    if Response::parse_v1_ack_or_shallow_or_assume_pack(&mut acks, &mut shallows, &peeked_line) {
+                    let next = Response::parse_v1_line(
+                        &mut acks,
+                        &mut shallows,
+                        &peeked_line,
+                        client_expects_pack,
+                        wants_to_negotiate,
+                    );
+                    if let V1Next::Pack = next {
                         break 'lines true;
                     }
                     assert_ne!(reader.readline_str(&mut line)?, 0, "consuming a peeked line works");
-                    // When the server sends ready, we know there is going to be a pack so no need to stop early.
-                    saw_ready |= matches!(acks.last(), Some(Acknowledgement::Ready));
-                    if let Some(Acknowledgement::Nak) = acks.last().filter(|_| !client_expects_pack || !saw_ready) {
-                        if !wants_to_negotiate {
-                            continue;
-                        }
+                    if let V1Next::EndOfResponse = next {
                         break 'lines false;
                     }
                 };
@@ -102,6 +100,7 @@ impl Response {
                     acks,
                     shallows,
                     wanted_refs: vec![],
+                    packfile_uris: vec![],
                     has_pack,
                 })
             }
@@ -112,6 +111,7 @@ impl Response {
                 let mut acks = Vec::<Acknowledgement>::new();
                 let mut shallows = Vec::<ShallowUpdate>::new();
                 let mut wanted_refs = Vec::<WantedRef>::new();
+                let mut packfile_uris = Vec::<PackfileUri>::new();
                 let has_pack = 'section: loop {
                     line.clear();
                     if reader.readline_str(&mut line)? == 0 {
@@ -137,6 +137,11 @@ impl Response {
                                 break 'section false;
                             }
                         }
+                        "packfile-uris" => {
+                            if parse_v2_section(&mut line, reader, &mut packfile_uris, PackfileUri::from_line)? {
+                                break 'section false;
+                            }
+                        }
                         "packfile" => {
                             // what follows is the packfile itself, which can be read with a sideband enabled reader
                             break 'section true;
@@ -148,6 +153,7 @@ impl Response {
                     acks,
                     shallows,
                     wanted_refs,
+                    packfile_uris,
                     has_pack,
                 })
             }
@@ -51,7 +51,8 @@ impl gix_transport::IsSpuriousError for Error {
 #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Acknowledgement {
-    /// The contained `id` is in common.
+    /// The contained `id` is in common, as indicated by `ACK <id> common` with `multi_ack_detailed`, `ACK <id> continue`
+    /// with `multi_ack`, or a plain `ACK <id>`.
     Common(gix_hash::ObjectId),
     /// The server is ready to receive more lines.
     Ready,
@@ -79,6 +80,16 @@ pub struct WantedRef {
     pub path: BString,
 }
 
+/// A packfile-uri line received from the server, pointing to a pack that is to be downloaded separately.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PackfileUri {
+    /// The hash of the pack, as found in its trailer, which must match the pack after it was downloaded.
+    pub hash: gix_hash::ObjectId,
+    /// The URI to download the pack from.
+    pub uri: BString,
+}
+
 impl ShallowUpdate {
     /// Parse a `ShallowUpdate` from a `line` as received to the server.
     pub fn from_line(line: &str) -> Result<ShallowUpdate, Error> {
@@ -113,7 +124,7 @@ impl Acknowledgement {
                     };
                     if let Some(description) = description {
                         match description {
-                            "common" => {}
+                            "common" | "continue" => {}
                             "ready" => return Ok(Acknowledgement::Ready),
                             _ => return Err(Error::UnknownLineType { line: line.to_owned() }),
                         }
@@ -148,12 +159,27 @@ impl WantedRef {
     }
 }
 
+impl PackfileUri {
+    /// Parse a `PackfileUri` from a `line` as received from the server.
+    pub fn from_line(line: &str) -> Result<PackfileUri, Error> {
+        match line.trim_end().split_once(' ') {
+            Some((hash, uri)) if !uri.is_empty() => {
+                let hash = gix_hash::ObjectId::from_hex(hash.as_bytes())
+                    .map_err(|_| Error::UnknownLineType { line: line.to_owned() })?;
+                Ok(PackfileUri { hash, uri: uri.into() })
+            }
+            _ => Err(Error::UnknownLineType { line: line.to_owned() }),
+        }
+    }
+}
+
 /// A representation of a complete fetch response
 #[derive(Debug)]
 pub struct Response {
     acks: Vec<Acknowledgement>,
     shallows: Vec<ShallowUpdate>,
     wanted_refs: Vec<WantedRef>,
+    packfile_uris: Vec<PackfileUri>,
     has_pack: bool,
 }
 
@@ -172,12 +198,6 @@ impl Response {
         match version {
             Protocol::V0 | Protocol::V1 => {
                 let has = |name: &str| features.iter().any(|f| f.0 == name);
-                // Let's focus on V2 standards, and simply not support old servers to keep our code simpler
-                if !has("multi_ack_detailed") {
-                    return Err(Error::MissingServerCapability {
-                        feature: "multi_ack_detailed",
-                    });
-                }
                 // It's easy to NOT do sideband for us, but then again, everyone supports it.
                 // CORRECTION: If side-band is off, it would send the packfile without packet line encoding,
                 // which is nothing we ever want to deal with (despite it being more efficient). In V2, this
@@ -207,34 +227,75 @@ impl Response {
     pub fn wanted_refs(&self) -> &[WantedRef] {
         &self.wanted_refs
     }
+
+    /// Return all packfile URIs [parsed previously][Response::from_line_reader()].
+    ///
+    /// The packs they point to are not contained in the pack of this response and have to be downloaded separately
+    /// for the fetch to be complete.
+    pub fn packfile_uris(&self) -> &[PackfileUri] {
+        &self.packfile_uris
+    }
+}
+
+/// What to do after parsing a line of a V1 response.
+#[cfg(any(feature = "async-client", feature = "blocking-client"))]
+enum V1Next {
+    /// Keep reading lines, as the response isn't over yet.
+    ReadMore,
+    /// The response ended without a pack, as the server waits for the next request.
+    EndOfResponse,
+    /// The line we saw is the start of the pack.
+    Pack,
 }
 
 #[cfg(any(feature = "async-client", feature = "blocking-client"))]
 impl Response {
-    /// with a friendly server, we just assume that a non-ack line is a pack line
-    /// which is our hint to stop here.
-    fn parse_v1_ack_or_shallow_or_assume_pack(
+    /// Parse `peeked_line` into `acks` or `shallows` and return what to do next, or assume the pack starts if it's neither,
+    /// which is what a friendly server will do.
+    ///
+    /// If `client_expects_pack`, the client sent `done` and the server sends a pack after all acknowledgements.
+    /// Otherwise, if `wants_to_negotiate`, the server will wait for more `have` lines after sending `NAK`, or after
+    /// sending a plain `ACK <id>` if neither `multi_ack` nor `multi_ack_detailed` are in use. Without `wants_to_negotiate`,
+    /// which is used for stateless connections, we read until the end of the response, which might contain a pack if `no-done`
+    /// is in use.
+    fn parse_v1_line(
         acks: &mut Vec<Acknowledgement>,
         shallows: &mut Vec<ShallowUpdate>,
         peeked_line: &str,
-    ) -> bool {
-        match Acknowledgement::from_line(peeked_line) {
-            Ok(ack) => match ack.id() {
-                Some(id) => {
-                    if !acks.iter().any(|a| a.id() == Some(id)) {
-                        acks.push(ack);
+        client_expects_pack: bool,
+        wants_to_negotiate: bool,
+    ) -> V1Next {
+        let ack = match Acknowledgement::from_line(peeked_line) {
+            Ok(ack) => ack,
+            Err(_) => {
+                return match ShallowUpdate::from_line(peeked_line) {
+                    Ok(shallow) => {
+                        shallows.push(shallow);
+                        V1Next::ReadMore
                     }
+                    Err(_) => V1Next::Pack,
                 }
-                None => acks.push(ack),
-            },
-            Err(_) => match ShallowUpdate::from_line(peeked_line) {
-                Ok(shallow) => {
-                    shallows.push(shallow);
-                }
-                Err(_) => return true,
-            },
+            }
         };
-        false
+        let saw_ready = acks.contains(&Acknowledgement::Ready);
+        let is_plain_ack = ack.id().is_some() && peeked_line.trim_end().splitn(3, ' ').nth(2).is_none();
+        match ack.id() {
+            Some(id) => {
+                if !acks.iter().any(|a| a.id() == Some(id)) {
+                    acks.push(ack);
+                }
+            }
+            None => acks.push(ack),
+        }
+        if client_expects_pack || !wants_to_negotiate {
+            return V1Next::ReadMore;
+        }
+        match ack {
+            Acknowledgement::Nak => V1Next::EndOfResponse,
+            // Only with `no-done`, a plain `ACK` follows `ready`, and the pack follows.
+            Acknowledgement::Common(_) if is_plain_ack && !saw_ready => V1Next::EndOfResponse,
+            _ => V1Next::ReadMore,
+        }
     }
 }
 
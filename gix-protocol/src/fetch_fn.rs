@@ -44,10 +44,6 @@ pub enum FetchConnection {
 /// * If `trace` is `true`, all packetlines received or sent will be passed to the facilities of the `gix-trace` crate.
 ///
 /// _Note_ that depending on the `delegate`, the actual action performed can be `ls-refs`, `clone` or `fetch`.
-///
-/// # WARNING - Do not use!
-///
-/// As it will hang when having multiple negotiation rounds.
 #[allow(clippy::result_large_err)]
 #[maybe_async]
 // TODO: remove this without losing test coverage - we have the same but better in `gix` and it's
@@ -131,17 +127,14 @@ where
         progress.set_name(format!("negotiate (round {round})"));
         round += 1;
         let action = delegate.negotiate(&refs, &mut arguments, previous_response.as_ref())?;
-        let mut reader = arguments.send(&mut transport, action == Action::Cancel).await?;
+        let is_done = action == Action::Cancel;
+        let is_stateless = arguments.is_stateless(!transport.connection_persists_across_multiple_requests());
+        let mut reader = arguments.send(&mut transport, is_done).await?;
         if sideband_all {
             setup_remote_progress(&mut progress, &mut reader);
         }
-        let response = Response::from_line_reader(
-            protocol_version,
-            &mut reader,
-            true,  /* hack, telling us we don't want this delegate approach anymore */
-            false, /* just as much of a hack which causes us to expect a pack immediately */
-        )
-        .await?;
+        let response =
+            Response::from_line_reader(protocol_version, &mut reader, is_done, !is_done && !is_stateless).await?;
         previous_response = if response.has_pack() {
             progress.step();
             progress.set_name("receiving pack".into());
@@ -0,0 +1,73 @@
+use bstr::{BStr, BString};
+
+/// Describe which objects to omit from a pack, turning it into a partial pack and the repository receiving it into a *partial clone*.
+///
+/// Omitted objects are fetched on demand from the remote that sent the pack, which thus becomes a *promisor remote*.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Filter {
+    /// Omit all blobs, `blob:none`.
+    BlobNone,
+    /// Omit all blobs whose size is at least `bytes`, `blob:limit=<bytes>`.
+    BlobLimit {
+        /// The size in bytes at which blobs are omitted.
+        bytes: u64,
+    },
+    /// Omit all trees and blobs whose depth from the root tree is at least `depth`, `tree:<depth>`.
+    ///
+    /// With a `depth` of `0`, only commits and tags are sent.
+    TreeDepth {
+        /// The depth at which trees and blobs are omitted.
+        depth: u64,
+    },
+}
+
+/// The error returned by [`Filter::from_bytes()`].
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Object filter {spec:?} is invalid or unsupported, expected 'blob:none', 'blob:limit=<n>[kmg]' or 'tree:<depth>'"
+)]
+pub struct Error {
+    /// The filter specification that couldn't be parsed.
+    pub spec: BString,
+}
+
+impl Filter {
+    /// Parse a filter specification like `blob:none`, `blob:limit=1m` or `tree:0` as passed to `git clone --filter`
+    /// or sent by clients.
+    pub fn from_bytes(spec: &BStr) -> Result<Self, Error> {
+        fn parse_number(value: &[u8], allow_unit: bool) -> Option<u64> {
+            let (digits, factor) = match value.last().map(u8::to_ascii_lowercase) {
+                Some(b'k') if allow_unit => (&value[..value.len() - 1], 1024),
+                Some(b'm') if allow_unit => (&value[..value.len() - 1], 1024 * 1024),
+                Some(b'g') if allow_unit => (&value[..value.len() - 1], 1024 * 1024 * 1024),
+                _ => (value, 1),
+            };
+            std::str::from_utf8(digits)
+                .ok()?
+                .parse::<u64>()
+                .ok()?
+                .checked_mul(factor)
+        }
+        let spec_bytes: &[u8] = spec.as_ref();
+        let filter = if spec_bytes == b"blob:none" {
+            Some(Filter::BlobNone)
+        } else if let Some(limit) = spec_bytes.strip_prefix(b"blob:limit=") {
+            parse_number(limit, true).map(|bytes| Filter::BlobLimit { bytes })
+        } else if let Some(depth) = spec_bytes.strip_prefix(b"tree:") {
+            parse_number(depth, false).map(|depth| Filter::TreeDepth { depth })
+        } else {
+            None
+        };
+        filter.ok_or_else(|| Error { spec: spec.to_owned() })
+    }
+}
+
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Filter::BlobNone => f.write_str("blob:none"),
+            Filter::BlobLimit { bytes } => write!(f, "blob:limit={bytes}"),
+            Filter::TreeDepth { depth } => write!(f, "tree:{depth}"),
+        }
+    }
+}
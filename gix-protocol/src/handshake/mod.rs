@@ -95,6 +95,7 @@ mod error {
 }
 pub use error::Error;
 
+#[cfg(any(feature = "blocking-client", feature = "async-client"))]
 pub(crate) mod function;
 
 ///
@@ -75,5 +75,5 @@ mod blocking_io;
 #[cfg(feature = "blocking-client")]
 pub use blocking_io::{from_v1_refs_received_as_part_of_handshake_and_capabilities, from_v2_refs};
 
-#[cfg(test)]
+#[cfg(all(test, any(feature = "blocking-client", feature = "async-client")))]
 mod tests;
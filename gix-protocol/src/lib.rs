@@ -17,6 +17,10 @@ pub enum Command {
     LsRefs,
     /// Fetch a pack.
     Fetch,
+    /// List bundles to download before fetching.
+    BundleUri,
+    /// Obtain information about objects, like their size, without downloading them.
+    ObjectInfo,
 }
 pub mod command;
 
@@ -43,21 +47,49 @@ pub use fetch_fn::{fetch, FetchConnection};
 mod remote_progress;
 pub use remote_progress::RemoteProgress;
 
+///
+pub mod filter;
+pub use filter::Filter;
+
 #[cfg(all(feature = "blocking-client", feature = "async-client"))]
 compile_error!("Cannot set both 'blocking-client' and 'async-client' features as they are mutually exclusive");
 
 ///
-#[cfg(any(feature = "blocking-client", feature = "async-client"))]
+#[cfg(any(feature = "blocking-client", feature = "async-client", feature = "blocking-server"))]
 pub mod handshake;
 #[cfg(any(feature = "blocking-client", feature = "async-client"))]
 pub use handshake::function::handshake;
 
+///
+#[cfg(any(feature = "blocking-client", feature = "async-client"))]
+pub mod bundle_uri;
+#[cfg(any(feature = "blocking-client", feature = "async-client"))]
+pub use bundle_uri::function::bundle_uri;
+
+///
+#[cfg(any(feature = "blocking-client", feature = "async-client"))]
+pub mod object_info;
+#[cfg(any(feature = "blocking-client", feature = "async-client"))]
+pub use object_info::function::object_info;
+
 ///
 #[cfg(any(feature = "blocking-client", feature = "async-client"))]
 pub mod ls_refs;
 #[cfg(any(feature = "blocking-client", feature = "async-client"))]
 pub use ls_refs::function::ls_refs;
 
+///
+#[cfg(feature = "blocking-server")]
+pub mod upload_pack;
+#[cfg(feature = "blocking-server")]
+pub use upload_pack::function::upload_pack;
+
+///
+#[cfg(feature = "blocking-server")]
+pub mod receive_pack;
+#[cfg(feature = "blocking-server")]
+pub use receive_pack::function::receive_pack;
+
 mod util;
 pub use util::agent;
 #[cfg(any(feature = "blocking-client", feature = "async-client"))]
@@ -0,0 +1,132 @@
+use gix_hash::ObjectId;
+
+/// Information about an object as provided by the remote in response to the V2 `object-info` command.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Info {
+    /// The id of the object the information is about.
+    pub id: ObjectId,
+    /// The size of the object in bytes, or `None` if the remote doesn't have the object.
+    pub size: Option<u64>,
+}
+
+mod error {
+    use bstr::BString;
+
+    /// The error returned by [`object_info()`][crate::object_info()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Io(#[from] std::io::Error),
+        #[error(transparent)]
+        Transport(#[from] gix_transport::client::Error),
+        #[error(transparent)]
+        DecodePacketline(#[from] gix_transport::packetline::decode::Error),
+        #[error("The remote responded with attributes {attributes:?} even though only 'size' was requested")]
+        UnexpectedAttributes { attributes: BString },
+        #[error("The object-info line {line:?} isn't an object id followed by its size")]
+        MalformedLine { line: BString },
+    }
+
+    impl gix_transport::IsSpuriousError for Error {
+        fn is_spurious(&self) -> bool {
+            match self {
+                Error::Io(err) => err.is_spurious(),
+                Error::Transport(err) => err.is_spurious(),
+                _ => false,
+            }
+        }
+    }
+}
+pub use error::Error;
+
+pub(crate) mod function {
+    use bstr::{BString, ByteSlice};
+    use gix_features::progress::Progress;
+    use gix_hash::ObjectId;
+    use gix_transport::client::{Capabilities, Transport, TransportV2Ext};
+    use maybe_async::maybe_async;
+
+    use super::{Error, Info};
+    use crate::{command::Feature, Command};
+
+    /// Invoke the `object-info` V2 command on `transport` to learn the size of the objects with the given `ids`
+    /// without downloading them, which requires a prior handshake that yielded server `capabilities` which advertise it.
+    ///
+    /// The returned information is in the order the server sent it, which is the order of `ids`.
+    /// `features` are sent along with the command, and are expected to contain the `(agent, Some(name))` of the client.
+    /// `progress` is used to provide feedback.
+    /// If `trace` is `true`, all packetlines received or sent will be passed to the facilities of the `gix-trace` crate.
+    #[maybe_async]
+    pub async fn object_info(
+        mut transport: impl Transport,
+        capabilities: &Capabilities,
+        features: impl IntoIterator<Item = Feature>,
+        ids: impl IntoIterator<Item = ObjectId>,
+        progress: &mut impl Progress,
+        trace: bool,
+    ) -> Result<Vec<Info>, Error> {
+        let _span = gix_features::trace::detail!("gix_protocol::object_info()", capabilities = ?capabilities);
+        let object_info = Command::ObjectInfo;
+        let mut object_info_features = object_info.default_features(gix_transport::Protocol::V2, capabilities);
+        object_info_features.extend(features);
+        let arguments: Vec<BString> = Some("size".into())
+            .into_iter()
+            .chain(ids.into_iter().map(|id| format!("oid {id}").into()))
+            .collect();
+        object_info.validate_argument_prefixes_or_panic(
+            gix_transport::Protocol::V2,
+            capabilities,
+            &arguments,
+            &object_info_features,
+        );
+
+        progress.step();
+        progress.set_name("query object info".into());
+        let mut response = transport
+            .invoke(
+                object_info.as_str(),
+                object_info_features.into_iter(),
+                Some(arguments.into_iter()),
+                trace,
+            )
+            .await?;
+        let mut infos = Vec::new();
+        let mut saw_attributes = false;
+        while let Some(line) = response
+            .readline()
+            .await
+            .transpose()?
+            .transpose()?
+            .and_then(|line| line.as_bstr())
+        {
+            let line = line.strip_suffix(b"\n").unwrap_or(line);
+            if !saw_attributes {
+                saw_attributes = true;
+                if line != b"size" {
+                    return Err(Error::UnexpectedAttributes {
+                        attributes: line.into(),
+                    });
+                }
+                continue;
+            }
+            let malformed = || Error::MalformedLine { line: line.into() };
+            let (id, size) = line.split_once_str(" ").ok_or_else(malformed)?;
+            infos.push(Info {
+                id: ObjectId::from_hex(id).map_err(|_| malformed())?,
+                size: if size.is_empty() {
+                    None
+                } else {
+                    Some(
+                        size.to_str()
+                            .ok()
+                            .and_then(|size| size.parse().ok())
+                            .ok_or_else(malformed)?,
+                    )
+                },
+            });
+        }
+        Ok(infos)
+    }
+}
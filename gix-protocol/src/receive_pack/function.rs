@@ -0,0 +1,575 @@
+use std::{
+    ffi::OsString,
+    fmt::Write as _,
+    io,
+    io::Write as _,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use bstr::{BStr, ByteSlice};
+use gix_hash::ObjectId;
+use gix_object::{CommitRefIter, Exists, FindHeader, Kind, TagRefIter, TreeRefIter};
+use gix_packetline_blocking::{encode, Channel};
+use gix_ref::{
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    FullName, Target,
+};
+use gix_transport::Protocol;
+
+use crate::{
+    receive_pack::{hook, quarantine::Quarantine, Command, Error, Options, Outcome, Update},
+    upload_pack::{
+        line::{Line, Lines},
+        pack::{Band, MAX_BAND_DATA_LEN},
+    },
+};
+
+/// The capabilities the client chose with its first command.
+#[derive(Default)]
+struct Capabilities {
+    report_status: bool,
+    side_band: bool,
+    atomic: bool,
+    push_options: bool,
+}
+
+/// Serve a client that pushes into a repository with `refs` and objects in `objects_dir` like `git receive-pack` would,
+/// reading its requests from `input` and writing responses to `out` using protocol `version`.
+///
+/// All objects sent by the client are kept in a quarantine directory within `objects_dir` until they are known to be
+/// complete, and until the `pre-receive` and `update` hooks accepted the changes to references. Only then are they moved
+/// into `objects_dir` and references are updated, followed by the `post-receive` hook.
+/// If the client asked for an `atomic` push, all references are updated in a single transaction, and none of them is
+/// updated if a single command was rejected. Otherwise, each reference is updated on its own.
+///
+/// As `receive-pack` doesn't support protocol V2, clients asking for it are served with V0, just like `git` does.
+/// Errors that are caused by the client are also sent to it as `ERR` packet line before being returned.
+pub fn receive_pack(
+    refs: &gix_ref::file::Store,
+    objects_dir: &Path,
+    version: Protocol,
+    input: &mut dyn io::Read,
+    out: &mut dyn io::Write,
+    should_interrupt: &AtomicBool,
+    options: &Options,
+) -> Result<Outcome, Error> {
+    match serve(refs, objects_dir, version, input, out, should_interrupt, options) {
+        Err(err) if err.is_caused_by_client() => {
+            encode::error_to_write(err.to_string().as_bytes(), &mut *out)?;
+            out.flush()?;
+            Err(err)
+        }
+        res => res,
+    }
+}
+
+fn serve(
+    refs: &gix_ref::file::Store,
+    objects_dir: &Path,
+    version: Protocol,
+    input: &mut dyn io::Read,
+    out: &mut dyn io::Write,
+    should_interrupt: &AtomicBool,
+    options: &Options,
+) -> Result<Outcome, Error> {
+    if !options.stateless_rpc {
+        advertise(refs, version, out, options)?;
+        out.flush()?;
+    }
+    if options.advertise_refs {
+        return Ok(Outcome::default());
+    }
+
+    let mut capabilities = Capabilities::default();
+    let mut updates = Vec::new();
+    let mut push_options = Vec::new();
+    {
+        let mut lines = Lines::<'_, Error>::new(&mut *input, options.trace);
+        loop {
+            match lines.next()? {
+                None | Some(Line::Flush) => break,
+                Some(Line::Delimiter) => return Err(unexpected_line("0001")),
+                Some(Line::Data(line)) => {
+                    let line = match line.find_byte(0).filter(|_| updates.is_empty()) {
+                        Some(pos) => {
+                            capabilities = Capabilities::from_bytes(line[pos + 1..].as_bstr());
+                            line[..pos].as_bstr()
+                        }
+                        None => line,
+                    };
+                    updates.push(Update {
+                        command: parse_command(line)?,
+                        rejection: None,
+                    });
+                }
+            }
+        }
+        if updates.is_empty() {
+            return Ok(Outcome::default());
+        }
+        if capabilities.push_options {
+            loop {
+                match lines.next()? {
+                    None | Some(Line::Flush) => break,
+                    Some(Line::Delimiter) => return Err(unexpected_line("0001")),
+                    Some(Line::Data(option)) => push_options.push(option.to_owned()),
+                }
+            }
+        }
+    }
+
+    let mut quarantine = None;
+    let mut is_connected = true;
+    if updates.iter().any(|update| !update.command.is_delete()) {
+        let q = Quarantine::new(objects_dir).map_err(Error::CreateQuarantine)?;
+        match receive_objects(&q, input, should_interrupt, options) {
+            Ok(connected) => is_connected = connected,
+            Err(err) => {
+                for update in &mut updates {
+                    update.rejection = Some("unpacker error".into());
+                }
+                report_status(out, Some(&err), &updates, &capabilities)?;
+                if capabilities.side_band {
+                    encode::flush_to_write(&mut *out)?;
+                }
+                out.flush()?;
+                return Err(err);
+            }
+        }
+        quarantine = Some(q);
+    }
+    let db = quarantine.as_ref().map(|q| gix_odb::at(&q.path)).transpose()?;
+    for update in &mut updates {
+        update.rejection = check(&update.command, db.as_ref(), is_connected, options)?;
+    }
+
+    let mut push_env = Vec::<(OsString, OsString)>::new();
+    if capabilities.push_options {
+        push_env.push(("GIT_PUSH_OPTION_COUNT".into(), push_options.len().to_string().into()));
+        for (idx, option) in push_options.iter().enumerate() {
+            push_env.push((
+                format!("GIT_PUSH_OPTION_{idx}").into(),
+                gix_path::from_bstr(option.as_bstr()).into_owned().into(),
+            ));
+        }
+    }
+    if let Some(hooks_dir) = options.hooks_dir.as_deref() {
+        let mut env = push_env.clone();
+        if let Some(quarantine) = &quarantine {
+            env.push(("GIT_QUARANTINE_PATH".into(), quarantine.path.clone().into()));
+            env.push(("GIT_OBJECT_DIRECTORY".into(), quarantine.path.clone().into()));
+            env.push((
+                "GIT_ALTERNATE_OBJECT_DIRECTORIES".into(),
+                quarantine.objects_dir.clone().into(),
+            ));
+        }
+        let candidates = accepted(&mut updates).collect::<Vec<_>>();
+        if !candidates.is_empty()
+            && !hook::run(
+                hooks_dir,
+                "pre-receive",
+                &[],
+                hook_input(candidates.iter().map(|update| &update.command)),
+                &env,
+                refs.git_dir(),
+                &mut *client_stderr(out, &capabilities),
+            )?
+        {
+            for update in candidates {
+                update.rejection = Some("pre-receive hook declined".into());
+            }
+        }
+        for update in accepted(&mut updates) {
+            let Command { old, new, name } = &update.command;
+            let args = [
+                gix_path::from_bstr(name.as_bstr()).into_owned().into(),
+                old.to_string().into(),
+                new.to_string().into(),
+            ];
+            if !hook::run(
+                hooks_dir,
+                "update",
+                &args,
+                Vec::new(),
+                &env,
+                refs.git_dir(),
+                &mut *client_stderr(out, &capabilities),
+            )? {
+                update.rejection = Some("hook declined".into());
+            }
+        }
+    }
+    if capabilities.atomic && updates.iter().any(|update| update.rejection.is_some()) {
+        for update in accepted(&mut updates) {
+            update.rejection = Some("atomic push failure".into());
+        }
+    }
+
+    if should_interrupt.load(Ordering::Relaxed) {
+        return Err(Error::Interrupted);
+    }
+    drop(db);
+    if let Some(quarantine) = quarantine {
+        if updates
+            .iter()
+            .any(|update| update.rejection.is_none() && !update.command.is_delete())
+        {
+            quarantine.migrate().map_err(Error::MigrateQuarantine)?;
+        }
+    }
+    let committer = options.committer.as_ref().map(gix_actor::Signature::to_ref);
+    if capabilities.atomic {
+        let candidates = accepted(&mut updates).collect::<Vec<_>>();
+        if let Err(err) = update_refs(refs, candidates.iter().map(|update| &update.command), committer) {
+            writeln!(client_stderr(out, &capabilities), "error: {err}")?;
+            for update in candidates {
+                update.rejection = Some("atomic transaction failed".into());
+            }
+        }
+    } else {
+        for update in accepted(&mut updates) {
+            if let Err(err) = update_refs(refs, Some(&update.command), committer) {
+                writeln!(client_stderr(out, &capabilities), "error: {err}")?;
+                update.rejection = Some("failed to update ref".into());
+            }
+        }
+    }
+
+    report_status(out, None, &updates, &capabilities)?;
+    if let Some(hooks_dir) = options.hooks_dir.as_deref() {
+        let applied = updates
+            .iter()
+            .filter(|update| update.rejection.is_none())
+            .map(|update| &update.command)
+            .collect::<Vec<_>>();
+        if !applied.is_empty() {
+            // The outcome of this hook doesn't matter anymore as all changes have been made.
+            hook::run(
+                hooks_dir,
+                "post-receive",
+                &[],
+                hook_input(applied.into_iter()),
+                &push_env,
+                refs.git_dir(),
+                &mut *client_stderr(out, &capabilities),
+            )?;
+        }
+    }
+    if capabilities.side_band {
+        encode::flush_to_write(&mut *out)?;
+    }
+    out.flush()?;
+    Ok(Outcome { updates, push_options })
+}
+
+impl Capabilities {
+    fn from_bytes(capabilities: &BStr) -> Self {
+        let mut out = Capabilities::default();
+        for capability in capabilities.split_str(" ") {
+            match capability {
+                b"report-status" => out.report_status = true,
+                b"side-band-64k" => out.side_band = true,
+                b"atomic" => out.atomic = true,
+                b"push-options" => out.push_options = true,
+                _ => {}
+            }
+        }
+        out
+    }
+}
+
+fn advertise(
+    refs: &gix_ref::file::Store,
+    version: Protocol,
+    out: &mut dyn io::Write,
+    options: &Options,
+) -> Result<(), Error> {
+    if version == Protocol::V1 {
+        encode::text_to_write(b"version 1", &mut *out)?;
+    }
+    let mut capabilities = String::from("report-status delete-refs side-band-64k quiet atomic ofs-delta");
+    if options.advertise_push_options {
+        capabilities.push_str(" push-options");
+    }
+    write!(
+        capabilities,
+        " object-format={} agent={}",
+        options.object_hash.as_str(),
+        options.agent
+    )
+    .expect("cannot fail");
+
+    let mut capabilities = Some(capabilities);
+    let mut write_ref = |id: &gix_hash::oid, name: &BStr| -> io::Result<()> {
+        let mut line = format!("{id} ").into_bytes();
+        line.extend_from_slice(name);
+        if let Some(capabilities) = capabilities.take() {
+            line.push(0);
+            line.extend_from_slice(capabilities.as_bytes());
+        }
+        encode::text_to_write(&line, &mut *out).map(|_| ())
+    };
+    let platform = refs.iter()?;
+    let mut has_refs = false;
+    for r in platform.all()? {
+        let r = r?;
+        // Symbolic refs can't be pushed to directly, so they are not advertised.
+        if let Target::Object(id) = r.target {
+            has_refs = true;
+            write_ref(&id, r.name.as_bstr())?;
+        }
+    }
+    if !has_refs {
+        write_ref(&options.object_hash.null(), "capabilities^{}".into())?;
+    }
+    encode::flush_to_write(&mut *out)?;
+    Ok(())
+}
+
+fn parse_command(line: &BStr) -> Result<Command, Error> {
+    let mut tokens = line.splitn(3, |b| *b == b' ');
+    let (Some(old), Some(new), Some(name)) = (tokens.next(), tokens.next(), tokens.next()) else {
+        return Err(unexpected_line(line));
+    };
+    let parse_id = |hex: &[u8]| ObjectId::from_hex(hex).map_err(|_| unexpected_line(line));
+    Ok(Command {
+        old: parse_id(old)?,
+        new: parse_id(new)?,
+        name: name.into(),
+    })
+}
+
+/// Write the pack sent by the client into the `quarantine` and return `true` if all objects referenced by it are present.
+fn receive_objects(
+    quarantine: &Quarantine,
+    input: &mut dyn io::Read,
+    should_interrupt: &AtomicBool,
+    options: &Options,
+) -> Result<bool, Error> {
+    let mut input = io::BufReader::new(input);
+    let outcome = gix_pack::Bundle::write_to_directory(
+        &mut input,
+        Some(&quarantine.pack_dir()),
+        &mut gix_features::progress::Discard,
+        should_interrupt,
+        Some(gix_odb::at(&quarantine.objects_dir)?),
+        gix_pack::bundle::write::Options {
+            thread_limit: options.thread_limit,
+            iteration_mode: gix_pack::data::input::Mode::Verify,
+            index_version: Default::default(),
+            object_hash: options.object_hash,
+            bitmap: None,
+        },
+    )?;
+    match outcome.index_path {
+        Some(index_path) => is_connected(
+            &gix_odb::at(&quarantine.path)?,
+            &index_path,
+            options.object_hash,
+            should_interrupt,
+        ),
+        None => Ok(true),
+    }
+}
+
+/// Return `true` if all objects referenced by the objects in the pack index at `index_path` exist in `db`.
+///
+/// As all objects in the repository are expected to be connected, the received objects are connected as well then.
+fn is_connected(
+    db: &gix_odb::Handle,
+    index_path: &Path,
+    object_hash: gix_hash::Kind,
+    should_interrupt: &AtomicBool,
+) -> Result<bool, Error> {
+    let index = gix_pack::index::File::at(index_path, object_hash)?;
+    let mut buf = Vec::new();
+    let mut referenced = Vec::new();
+    for entry in index.iter() {
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+        let Some(object) = gix_object::Find::try_find(db, &entry.oid, &mut buf)? else {
+            return Ok(false);
+        };
+        referenced.clear();
+        match object.kind {
+            Kind::Commit => {
+                referenced.push(CommitRefIter::from_bytes(object.data).tree_id()?);
+                referenced.extend(CommitRefIter::from_bytes(object.data).parent_ids());
+            }
+            Kind::Tree => {
+                for tree_entry in TreeRefIter::from_bytes(object.data, object_hash) {
+                    let tree_entry = tree_entry?;
+                    // Submodule commits are not part of this repository.
+                    if !tree_entry.mode.is_commit() {
+                        referenced.push(tree_entry.oid.to_owned());
+                    }
+                }
+            }
+            Kind::Tag => referenced.push(TagRefIter::from_bytes(object.data).target_id()?),
+            Kind::Blob => {}
+        }
+        if !referenced.iter().all(|id| db.exists(id)) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Return the reason for rejecting `command` before running any hooks, or `None` if it's acceptable.
+fn check(
+    command: &Command,
+    db: Option<&gix_odb::Handle>,
+    is_connected: bool,
+    options: &Options,
+) -> Result<Option<String>, Error> {
+    if !command.name.starts_with(b"refs/") || FullName::try_from(command.name.clone()).is_err() {
+        return Ok(Some("funny refname".into()));
+    }
+    if command.is_delete() {
+        return Ok(options.deny_deletes.then(|| "deletion prohibited".into()));
+    }
+    let db = db.expect("objects were received for all commands that aren't deletions");
+    if !is_connected || !db.exists(&command.new) {
+        return Ok(Some("missing necessary objects".into()));
+    }
+    if options.deny_non_fast_forwards && !command.old.is_null() && command.name.starts_with(b"refs/heads/") {
+        let kind = |id: &gix_hash::oid| db.try_header(id).map(|header| header.map(|header| header.kind));
+        if kind(&command.old)? != Some(Kind::Commit) || kind(&command.new)? != Some(Kind::Commit) {
+            return Ok(Some("bad ref".into()));
+        }
+        let mut is_fast_forward = false;
+        for info in gix_traverse::commit::Simple::new(Some(command.new), db) {
+            if info?.id == command.old {
+                is_fast_forward = true;
+                break;
+            }
+        }
+        if !is_fast_forward {
+            return Ok(Some("non-fast-forward".into()));
+        }
+    }
+    Ok(None)
+}
+
+/// Apply all `commands` in a single transaction.
+fn update_refs<'a>(
+    refs: &gix_ref::file::Store,
+    commands: impl IntoIterator<Item = &'a Command>,
+    committer: Option<gix_actor::SignatureRef<'_>>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let edits = commands.into_iter().map(|command| RefEdit {
+        change: if command.is_delete() {
+            Change::Delete {
+                expected: if command.old.is_null() {
+                    PreviousValue::MustExist
+                } else {
+                    PreviousValue::MustExistAndMatch(Target::Object(command.old))
+                },
+                log: RefLog::AndReference,
+            }
+        } else {
+            Change::Update {
+                log: LogChange {
+                    mode: RefLog::AndReference,
+                    force_create_reflog: false,
+                    message: "push".into(),
+                },
+                expected: if command.old.is_null() {
+                    PreviousValue::MustNotExist
+                } else {
+                    PreviousValue::MustExistAndMatch(Target::Object(command.old))
+                },
+                new: Target::Object(command.new),
+            }
+        },
+        name: FullName::try_from(command.name.clone()).expect("validated before"),
+        deref: false,
+    });
+    refs.transaction()
+        .prepare(
+            edits,
+            gix_lock::acquire::Fail::Immediately,
+            gix_lock::acquire::Fail::Immediately,
+        )?
+        .commit(committer)?;
+    Ok(())
+}
+
+/// Tell the client what became of each of its commands if it asked for it, or about the `unpack_error`.
+fn report_status(
+    out: &mut dyn io::Write,
+    unpack_error: Option<&Error>,
+    updates: &[Update],
+    capabilities: &Capabilities,
+) -> Result<(), Error> {
+    if !capabilities.report_status {
+        return Ok(());
+    }
+    let mut buf = Vec::new();
+    match unpack_error {
+        None => encode::text_to_write(b"unpack ok", &mut buf)?,
+        Some(err) => encode::text_to_write(format!("unpack {err}").as_bytes(), &mut buf)?,
+    };
+    for update in updates {
+        let mut line = match &update.rejection {
+            None => b"ok ".to_vec(),
+            Some(_) => b"ng ".to_vec(),
+        };
+        line.extend_from_slice(&update.command.name);
+        if let Some(reason) = &update.rejection {
+            line.push(b' ');
+            line.extend_from_slice(reason.as_bytes());
+        }
+        encode::text_to_write(&line, &mut buf)?;
+    }
+    encode::flush_to_write(&mut buf)?;
+    if capabilities.side_band {
+        io::Write::write_all(
+            &mut Band {
+                out: &mut *out,
+                channel: Channel::Data,
+                max_data_len: MAX_BAND_DATA_LEN,
+            },
+            &buf,
+        )?;
+    } else {
+        out.write_all(&buf)?;
+    }
+    Ok(())
+}
+
+/// The input of the `pre-receive` and `post-receive` hooks, one line per command.
+fn hook_input<'a>(commands: impl Iterator<Item = &'a Command>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for Command { old, new, name } in commands {
+        buf.extend_from_slice(format!("{old} {new} ").as_bytes());
+        buf.extend_from_slice(name);
+        buf.push(b'\n');
+    }
+    buf
+}
+
+/// The output of hooks and error messages go to the client if possible, or to our own `stderr` otherwise, just like `git` does it.
+fn client_stderr<'a>(out: &'a mut dyn io::Write, capabilities: &Capabilities) -> Box<dyn io::Write + 'a> {
+    if capabilities.side_band {
+        Box::new(Band {
+            out,
+            channel: Channel::Progress,
+            max_data_len: MAX_BAND_DATA_LEN,
+        })
+    } else {
+        Box::new(io::stderr())
+    }
+}
+
+fn accepted(updates: &mut [Update]) -> impl Iterator<Item = &mut Update> {
+    updates.iter_mut().filter(|update| update.rejection.is_none())
+}
+
+fn unexpected_line(line: impl AsRef<[u8]>) -> Error {
+    Error::UnexpectedLine {
+        line: line.as_ref().into(),
+    }
+}
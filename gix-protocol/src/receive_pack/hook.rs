@@ -0,0 +1,62 @@
+use std::{
+    ffi::OsString,
+    io,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crate::receive_pack::Error;
+
+/// Run the hook `name` in `hooks_dir` with `args` and `env`, feeding it `stdin` and writing all of its output to `out`.
+///
+/// Return `true` if the hook succeeded or doesn't exist, and `false` if it failed.
+pub(crate) fn run(
+    hooks_dir: &Path,
+    name: &'static str,
+    args: &[OsString],
+    stdin: Vec<u8>,
+    env: &[(OsString, OsString)],
+    git_dir: &Path,
+    out: &mut dyn io::Write,
+) -> Result<bool, Error> {
+    let path = hooks_dir.join(name);
+    if !is_executable(&path) {
+        return Ok(true);
+    }
+    let err = |source| Error::RunHook { name, source };
+    let mut cmd: Command = gix_command::prepare(path)
+        .args(args.iter().cloned())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .into();
+    // Like `git`, hooks run in the `.git` directory.
+    cmd.current_dir(git_dir).env("GIT_DIR", ".").envs(env.iter().cloned());
+    let mut child = cmd.spawn().map_err(err)?;
+
+    let mut child_stdin = child.stdin.take().expect("configured");
+    let writer = std::thread::spawn(move || match child_stdin.write_all(&stdin) {
+        // It's fine if the hook doesn't care about its input.
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        res => res,
+    });
+    let output = child.wait_with_output().map_err(err)?;
+    writer.join().expect("no panic").map_err(err)?;
+    out.write_all(&output.stdout)?;
+    out.write_all(&output.stderr)?;
+    out.flush()?;
+    Ok(output.status.success())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map_or(false, |md| md.is_file() && md.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
@@ -0,0 +1,145 @@
+use std::path::PathBuf;
+
+use bstr::BString;
+use gix_hash::ObjectId;
+
+/// A single request of the client to change the reference `name` from `old` to `new`.
+///
+/// If `old` is the null id, the reference must not exist yet. If `new` is the null id, the reference is deleted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Command {
+    /// The value the client expects the reference to have.
+    pub old: ObjectId,
+    /// The value the reference should have after the update.
+    pub new: ObjectId,
+    /// The full name of the reference to change, like `refs/heads/main`.
+    pub name: BString,
+}
+
+impl Command {
+    /// Return `true` if this command deletes its reference.
+    pub fn is_delete(&self) -> bool {
+        self.new.is_null()
+    }
+}
+
+/// A command along with what became of it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Update {
+    /// The command as sent by the client.
+    pub command: Command,
+    /// The reason for rejecting the command, as reported to the client, or `None` if the reference was updated.
+    pub rejection: Option<String>,
+}
+
+/// The result of [`receive_pack()`][crate::receive_pack()].
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Outcome {
+    /// All commands sent by the client in order, along with what became of them.
+    ///
+    /// It's empty if the client didn't want to change anything.
+    pub updates: Vec<Update>,
+    /// The push options sent by the client, if any.
+    pub push_options: Vec<BString>,
+}
+
+/// Configuration for [`receive_pack()`][crate::receive_pack()].
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The kind of hash used by all objects of the served repository.
+    pub object_hash: gix_hash::Kind,
+    /// The name of the server to present to clients, like `git/gix-0.1.0`.
+    pub agent: String,
+    /// The identity to use in reference logs, which must be set if these are written.
+    pub committer: Option<gix_actor::Signature>,
+    /// The directory containing the `pre-receive`, `update` and `post-receive` hooks, or `None` to not run any hooks.
+    ///
+    /// It's typically `$GIT_DIR/hooks`, or the value of `core.hooksPath`.
+    pub hooks_dir: Option<PathBuf>,
+    /// If `true`, reject all commands that delete references, similar to `receive.denyDeletes`.
+    pub deny_deletes: bool,
+    /// If `true`, reject all commands that would lose commits, similar to `receive.denyNonFastForwards`.
+    pub deny_non_fast_forwards: bool,
+    /// If `true`, clients may send push options which are passed on to hooks, similar to `receive.advertisePushOptions`.
+    pub advertise_push_options: bool,
+    /// If `true`, only send the ref advertisement and return.
+    ///
+    /// This is what `git receive-pack --advertise-refs` does for stateless transports like HTTP.
+    pub advertise_refs: bool,
+    /// If `true`, don't send an advertisement but serve a single request.
+    ///
+    /// This is what `git receive-pack --stateless-rpc` does for stateless transports like HTTP.
+    pub stateless_rpc: bool,
+    /// The amount of threads to use when resolving the received pack, or `None` to use all logical cores.
+    pub thread_limit: Option<usize>,
+    /// If `true`, all packetlines received from the client will be passed to the facilities of the `gix-trace` crate.
+    pub trace: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            object_hash: gix_hash::Kind::Sha1,
+            agent: crate::agent(concat!("gix-protocol-", env!("CARGO_PKG_VERSION"))),
+            committer: None,
+            hooks_dir: None,
+            deny_deletes: false,
+            deny_non_fast_forwards: false,
+            advertise_push_options: false,
+            advertise_refs: false,
+            stateless_rpc: false,
+            thread_limit: None,
+            trace: false,
+        }
+    }
+}
+
+mod error {
+    use bstr::BString;
+
+    /// The error returned by [`receive_pack()`][crate::receive_pack()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Io(#[from] std::io::Error),
+        #[error(transparent)]
+        DecodePacketline(#[from] gix_packetline_blocking::decode::Error),
+        #[error("The client sent an unexpected line: {line:?}")]
+        UnexpectedLine { line: BString },
+        #[error("Could not open the packed-refs file")]
+        OpenPackedRefs(#[from] gix_ref::packed::buffer::open::Error),
+        #[error("Could not iterate references")]
+        IterReferences(#[from] gix_ref::file::iter::loose_then_packed::Error),
+        #[error("Could not create the quarantine directory for received objects")]
+        CreateQuarantine(#[source] std::io::Error),
+        #[error("Could not move received objects out of the quarantine directory")]
+        MigrateQuarantine(#[source] std::io::Error),
+        #[error("Could not receive the pack sent by the client")]
+        Unpack(#[from] gix_pack::bundle::write::Error),
+        #[error("Could not open the received pack index")]
+        OpenIndex(#[from] gix_pack::index::init::Error),
+        #[error(transparent)]
+        Find(#[from] gix_object::find::Error),
+        #[error(transparent)]
+        Decode(#[from] gix_object::decode::Error),
+        #[error(transparent)]
+        TraverseCommits(#[from] gix_traverse::commit::simple::Error),
+        #[error("Could not run the {name:?} hook")]
+        RunHook { name: &'static str, source: std::io::Error },
+        #[error("Interrupted")]
+        Interrupted,
+    }
+
+    impl Error {
+        /// Return `true` if this error was caused by the client, which is why it should be told about it.
+        pub fn is_caused_by_client(&self) -> bool {
+            matches!(self, Error::UnexpectedLine { .. })
+        }
+    }
+}
+pub use error::Error;
+
+pub(crate) mod function;
+mod hook;
+mod quarantine;
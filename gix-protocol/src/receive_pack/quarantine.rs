@@ -0,0 +1,85 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A temporary object directory that receives all objects sent by the client, which can see all objects of the repository
+/// through its `info/alternates` file.
+///
+/// Its objects are only moved into the repository once all checks passed, and are deleted otherwise.
+/// This is what `git` calls the `GIT_QUARANTINE_PATH`.
+pub(crate) struct Quarantine {
+    /// The temporary object directory.
+    pub path: PathBuf,
+    /// The object directory of the repository, as absolute path.
+    pub objects_dir: PathBuf,
+}
+
+impl Quarantine {
+    /// Create a new quarantine directory within `objects_dir`.
+    pub fn new(objects_dir: &Path) -> io::Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let objects_dir = if objects_dir.is_absolute() {
+            objects_dir.to_owned()
+        } else {
+            std::env::current_dir()?.join(objects_dir)
+        };
+        let path = loop {
+            let path = objects_dir.join(format!(
+                "tmp_objdir-incoming-{}-{}",
+                std::process::id(),
+                COUNT.fetch_add(1, Ordering::SeqCst)
+            ));
+            match std::fs::create_dir(&path) {
+                Ok(()) => break path,
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(err) => return Err(err),
+            }
+        };
+        let quarantine = Quarantine { path, objects_dir };
+        std::fs::create_dir(quarantine.pack_dir())?;
+        let info_dir = quarantine.path.join("info");
+        std::fs::create_dir(&info_dir)?;
+        let mut alternates = gix_path::into_bstr(quarantine.objects_dir.as_path()).into_owned();
+        alternates.push(b'\n');
+        std::fs::write(info_dir.join("alternates"), alternates)?;
+        Ok(quarantine)
+    }
+
+    /// The directory to write received packs to.
+    pub fn pack_dir(&self) -> PathBuf {
+        self.path.join("pack")
+    }
+
+    /// Move all received packs into the object directory of the repository, and delete the quarantine.
+    ///
+    /// Indices are moved last so packs are complete once other processes can see them, and `.keep` files are left behind
+    /// as they were only needed while the pack was written.
+    pub fn migrate(self) -> io::Result<()> {
+        let pack_dir = self.objects_dir.join("pack");
+        let mut files = std::fs::read_dir(self.pack_dir())?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        files.sort_by_key(|path| path.extension().map_or(false, |ext| ext == "idx"));
+        for file in files {
+            let Some(file_name) = file.file_name() else { continue };
+            if file.extension().map_or(false, |ext| ext == "keep") {
+                continue;
+            }
+            let destination = pack_dir.join(file_name);
+            if destination.is_file() {
+                // The same pack already exists, with the same content as it's named after its checksum.
+                continue;
+            }
+            std::fs::rename(&file, destination)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Quarantine {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.path).ok();
+    }
+}
@@ -0,0 +1,54 @@
+use std::{io, sync::atomic::AtomicBool};
+
+use gix_packetline_blocking::encode;
+use gix_transport::Protocol;
+
+use crate::{
+    handshake::Ref,
+    upload_pack::{v1, v2, Error, Options},
+};
+
+/// Serve a client that fetches or clones from a repository with `refs` and `objects` like `git upload-pack` would, reading
+/// its requests from `input` and writing responses to `out` using protocol `version`.
+///
+/// `refs` are advertised in the given order, except for `HEAD` which is always advertised first. All of their objects,
+/// along with everything reachable from them, must be contained in `objects`. If these are provided by `gix-odb`, the handle must be
+/// configured to prevent packs from being unloaded and to ignore replacements, as is required for creating packs.
+/// `version` is typically derived from the `GIT_PROTOCOL` environment variable or the extra parameters passed to `git daemon`.
+///
+/// The function returns when the client is done, which is when it closes the connection or sends an empty request,
+/// or after a single request in [stateless mode][Options::stateless_rpc].
+/// Errors that are caused by the client are also sent to it as `ERR` packet line before being returned.
+pub fn upload_pack<Find>(
+    refs: &[Ref],
+    objects: Find,
+    version: Protocol,
+    input: &mut dyn io::Read,
+    out: &mut dyn io::Write,
+    should_interrupt: &AtomicBool,
+    options: &Options,
+) -> Result<(), Error>
+where
+    Find: gix_pack::Find + gix_object::Find + gix_object::FindHeader + Send + Clone + 'static,
+{
+    let res = match version {
+        Protocol::V0 | Protocol::V1 => v1::serve(refs, objects, version, input, out, should_interrupt, options),
+        Protocol::V2 => v2::serve(refs, objects, input, out, should_interrupt, options),
+    };
+    match res {
+        Err(err) if err.is_caused_by_client() => {
+            encode::error_to_write(err.to_string().as_bytes(), &mut *out)?;
+            out.flush()?;
+            Err(err)
+        }
+        res => res,
+    }
+}
+
+/// Return all `refs`, but with `HEAD` first.
+pub(crate) fn head_first(refs: &[Ref]) -> impl Iterator<Item = &Ref> {
+    let is_head = |r: &&Ref| r.unpack().0 == "HEAD";
+    refs.iter()
+        .filter(is_head)
+        .chain(refs.iter().filter(move |r| !is_head(r)))
+}
@@ -0,0 +1,43 @@
+use std::{io, marker::PhantomData};
+
+use bstr::{BStr, ByteSlice};
+use gix_packetline_blocking::{decode, PacketLineRef, StreamingPeekableIter};
+
+/// Packet lines as sent by the client, failing with errors of type `E`.
+pub(crate) struct Lines<'a, E> {
+    inner: StreamingPeekableIter<&'a mut dyn io::Read>,
+    _error: PhantomData<E>,
+}
+
+/// A single packet line sent by the client, with the trailing newline removed from data lines.
+pub(crate) enum Line<'a> {
+    Flush,
+    Delimiter,
+    Data(&'a BStr),
+}
+
+impl<'a, E> Lines<'a, E>
+where
+    E: From<io::Error> + From<decode::Error>,
+{
+    pub(crate) fn new(read: &'a mut dyn io::Read, trace: bool) -> Self {
+        Lines {
+            inner: StreamingPeekableIter::new(read, &[], trace),
+            _error: PhantomData,
+        }
+    }
+
+    /// Return the next line, or `None` if the client closed the connection.
+    pub(crate) fn next(&mut self) -> Result<Option<Line<'_>>, E> {
+        let line = match self.inner.read_line() {
+            None => return Ok(None),
+            Some(Err(err)) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Some(line) => line??,
+        };
+        Ok(Some(match line {
+            PacketLineRef::Flush | PacketLineRef::ResponseEnd => Line::Flush,
+            PacketLineRef::Delimiter => Line::Delimiter,
+            PacketLineRef::Data(data) => Line::Data(data.strip_suffix(b"\n").unwrap_or(data).as_bstr()),
+        }))
+    }
+}
@@ -0,0 +1,151 @@
+use bstr::BString;
+
+/// An object that isn't sent in the pack but offloaded to a pack that clients download separately, similar to
+/// `uploadpack.blobPackfileUri`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PackfileUri {
+    /// The object to omit from the pack, typically a large blob.
+    pub object: gix_hash::ObjectId,
+    /// The hash of the pack that contains `object`, as found in its trailer.
+    pub pack_hash: gix_hash::ObjectId,
+    /// The URI to download the pack from, whose scheme has to be among the protocols accepted by the client.
+    pub uri: BString,
+}
+
+/// Configuration for [`upload_pack()`][crate::upload_pack()].
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The kind of hash used by all objects of the served repository.
+    pub object_hash: gix_hash::Kind,
+    /// The name of the server to present to clients, like `git/gix-0.1.0`.
+    pub agent: String,
+    /// If `true`, clients may send object filters to receive partial packs, similar to `uploadpack.allowFilter`.
+    pub allow_filter: bool,
+    /// If `true`, clients may want any object that exists instead of only the objects that advertised refs point to,
+    /// similar to `uploadpack.allowAnySHA1InWant`.
+    pub allow_any_object_in_want: bool,
+    /// If `true`, clients may want refs by name in protocol V2, similar to `uploadpack.allowRefInWant`.
+    pub allow_ref_in_want: bool,
+    /// Objects to offload to separately downloaded packs if clients support protocol V2 `packfile-uris` for the scheme of
+    /// their URI, similar to `uploadpack.blobPackfileUri`.
+    pub packfile_uris: Vec<PackfileUri>,
+    /// Key-value pairs like `("bundle.version", "1")` that describe a list of bundles for clients to download before fetching,
+    /// sent in response to the protocol V2 `bundle-uri` command, which is only advertised if this isn't empty.
+    ///
+    /// This is similar to `uploadpack.advertiseBundleURIs` along with the `bundle.*` configuration.
+    pub bundle_list: Vec<(BString, BString)>,
+    /// If `true`, advertise and serve the protocol V2 `object-info` command for clients to learn the size of objects
+    /// without downloading them, similar to `transfer.advertiseObjectInfo`.
+    pub advertise_object_info: bool,
+    /// If `true`, only send the ref advertisement, or the capabilities in case of protocol V2, and return.
+    ///
+    /// This is what `git upload-pack --advertise-refs` does for stateless transports like HTTP.
+    pub advertise_refs: bool,
+    /// If `true`, don't send an advertisement but serve a single request, which must contain all information the client
+    /// sent so far.
+    ///
+    /// This is what `git upload-pack --stateless-rpc` does for stateless transports like HTTP.
+    pub stateless_rpc: bool,
+    /// The amount of threads to use when creating pack entries, or `None` to use all logical cores.
+    pub thread_limit: Option<usize>,
+    /// The directory with the packs of the served repository, like `.git/objects/pack`, to find reachability bitmaps in.
+    ///
+    /// If a bitmap of a multi-pack index or of a pack is found there, it's used to count the objects to send much faster,
+    /// similar to `pack.useBitmaps`. Otherwise, or if `None`, objects are counted by traversing the commit graph.
+    pub pack_dir: Option<std::path::PathBuf>,
+    /// If `true`, all packetlines received from the client will be passed to the facilities of the `gix-trace` crate.
+    pub trace: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            object_hash: gix_hash::Kind::Sha1,
+            agent: crate::agent(concat!("gix-protocol-", env!("CARGO_PKG_VERSION"))),
+            allow_filter: false,
+            allow_any_object_in_want: false,
+            allow_ref_in_want: false,
+            packfile_uris: Vec::new(),
+            bundle_list: Vec::new(),
+            advertise_object_info: false,
+            advertise_refs: false,
+            stateless_rpc: false,
+            thread_limit: None,
+            pack_dir: None,
+            trace: false,
+        }
+    }
+}
+
+mod error {
+    use bstr::BString;
+    use gix_hash::ObjectId;
+    use gix_pack::data::output;
+
+    use crate::filter;
+
+    /// The error returned by [`upload_pack()`][crate::upload_pack()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Io(#[from] std::io::Error),
+        #[error(transparent)]
+        DecodePacketline(#[from] gix_packetline_blocking::decode::Error),
+        #[error("The client sent an unexpected line: {line:?}")]
+        UnexpectedLine { line: BString },
+        #[error("The client requested the unknown command {command:?}")]
+        UnknownCommand { command: BString },
+        #[error("upload-pack: not our ref {id}")]
+        NotOurRef { id: ObjectId },
+        #[error("unknown ref {name}")]
+        UnknownWantedRef { name: BString },
+        #[error("deepen-not is not a ref: {name}")]
+        InvalidDeepenNot { name: BString },
+        #[error("Filtering is not allowed by the server")]
+        FilterNotAllowed,
+        #[error(transparent)]
+        Filter(#[from] filter::Error),
+        #[error(transparent)]
+        FindExisting(#[from] gix_object::find::existing::Error),
+        #[error(transparent)]
+        FindExistingIter(#[from] gix_object::find::existing_iter::Error),
+        #[error(transparent)]
+        Find(gix_object::find::Error),
+        #[error(transparent)]
+        Decode(#[from] gix_object::decode::Error),
+        #[error(transparent)]
+        TraverseTree(#[from] gix_traverse::tree::breadthfirst::Error),
+        #[error(transparent)]
+        CountObjects(#[from] output::count::objects::Error),
+        #[error(transparent)]
+        CreateEntries(#[from] output::entry::iter_from_counts::Error),
+        #[error(transparent)]
+        WritePack(#[from] output::bytes::Error<output::entry::iter_from_counts::Error>),
+        #[error("Interrupted")]
+        Interrupted,
+    }
+
+    impl Error {
+        /// Return `true` if this error was caused by the client, which is why it should be told about it.
+        pub fn is_caused_by_client(&self) -> bool {
+            matches!(
+                self,
+                Error::UnexpectedLine { .. }
+                    | Error::UnknownCommand { .. }
+                    | Error::NotOurRef { .. }
+                    | Error::UnknownWantedRef { .. }
+                    | Error::InvalidDeepenNot { .. }
+                    | Error::FilterNotAllowed
+                    | Error::Filter(_)
+            )
+        }
+    }
+}
+pub use error::Error;
+
+pub(crate) mod function;
+pub(crate) mod line;
+pub(crate) mod pack;
+mod v1;
+mod v2;
@@ -0,0 +1,718 @@
+use std::{
+    collections::VecDeque,
+    io,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use bstr::BString;
+use gix_date::SecondsSinceUnixEpoch;
+use gix_features::{parallel::InOrderIter, progress};
+use gix_hash::{oid, ObjectId};
+use gix_hashtable::{HashMap, HashSet};
+use gix_object::{CommitRefIter, FindExt, Kind, TagRefIter};
+use gix_pack::data::{output, output::count::objects::ObjectExpansion};
+use gix_packetline_blocking::{encode, Channel};
+
+use crate::{
+    handshake::Ref,
+    upload_pack::{Error, Options},
+    Filter,
+};
+
+/// The largest amount of data that fits into a `side-band-64k` packet line.
+pub(crate) const MAX_BAND_DATA_LEN: usize = 65515;
+/// The largest amount of data that fits into a `side-band` packet line.
+pub(crate) const MAX_SMALL_BAND_DATA_LEN: usize = 995;
+
+/// The object database we serve from.
+pub(crate) trait Objects:
+    gix_pack::Find + gix_object::Find + gix_object::FindHeader + Send + Clone + 'static
+{
+}
+
+impl<T> Objects for T where T: gix_pack::Find + gix_object::Find + gix_object::FindHeader + Send + Clone + 'static {}
+
+/// What the client asked for when fetching, independently of the protocol version.
+#[derive(Default)]
+pub(crate) struct Request {
+    pub wants: Vec<ObjectId>,
+    pub client_shallow: Vec<ObjectId>,
+    pub depth: Option<u32>,
+    pub deepen_relative: bool,
+    pub deepen_since: Option<SecondsSinceUnixEpoch>,
+    pub deepen_not: Vec<BString>,
+    pub filter: Option<Filter>,
+    pub include_tag: bool,
+    pub no_progress: bool,
+}
+
+impl Request {
+    /// Return `true` if the client is shallow or wants to change the depth of its history.
+    pub fn is_shallow(&self) -> bool {
+        self.depth.is_some()
+            || self.deepen_since.is_some()
+            || !self.deepen_not.is_empty()
+            || !self.client_shallow.is_empty()
+    }
+
+    /// Assure all wanted objects are advertised or otherwise allowed.
+    pub fn validate_wants(&self, db: &impl Objects, refs: &[Ref], allow_any: bool) -> Result<(), Error> {
+        for want in &self.wants {
+            let is_allowed = if allow_any {
+                db.try_header(want).map_err(Error::Find)?.is_some()
+            } else {
+                refs.iter().any(|r| {
+                    let (_name, target, peeled) = r.unpack();
+                    target == Some(want.as_ref()) || peeled == Some(want.as_ref())
+                })
+            };
+            if !is_allowed {
+                return Err(Error::NotOurRef { id: *want });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The state of the negotiation, i.e. which objects we know the client has.
+#[derive(Default)]
+pub(crate) struct Negotiation {
+    /// Objects sent by the client as `have` that we have as well, in order.
+    pub common: Vec<ObjectId>,
+    seen: HashSet,
+    is_ready: bool,
+}
+
+impl Negotiation {
+    /// Register `id` as object the client has, and return `true` if we have it as well.
+    pub fn add_have(&mut self, db: &impl Objects, id: ObjectId) -> Result<bool, Error> {
+        if self.seen.contains(&id) {
+            return Ok(self.common.contains(&id));
+        }
+        self.seen.insert(id);
+        let is_common = db.try_header(&id).map_err(Error::Find)?.is_some();
+        if is_common {
+            self.common.push(id);
+        }
+        Ok(is_common)
+    }
+
+    /// Return `true` if each of the `wants` reaches one of our common commits, which is when a pack can be sent that the
+    /// client can use without receiving all history.
+    pub fn ok_to_give_up(&mut self, db: &impl Objects, wants: &[ObjectId]) -> Result<bool, Error> {
+        if self.is_ready {
+            return Ok(true);
+        }
+        let mut buf = Vec::new();
+        let common: HashSet = self.common.iter().copied().collect();
+        let mut oldest_common = None::<SecondsSinceUnixEpoch>;
+        for id in &self.common {
+            if let Some(commit) = find_commit(db, id, &mut buf)? {
+                oldest_common = Some(oldest_common.map_or(commit.time, |time| time.min(commit.time)));
+            }
+        }
+        let Some(oldest_common) = oldest_common else {
+            return Ok(false);
+        };
+
+        for want in wants {
+            let Some(want) = peel_to_commit(db, want, &mut buf)? else {
+                continue;
+            };
+            let mut seen = HashSet::default();
+            let mut stack = vec![want];
+            let mut reaches_common = false;
+            while let Some(id) = stack.pop() {
+                if common.contains(&id) {
+                    reaches_common = true;
+                    break;
+                }
+                if !seen.insert(id) {
+                    continue;
+                }
+                let Some(commit) = find_commit(db, &id, &mut buf)? else {
+                    continue;
+                };
+                if commit.time >= oldest_common {
+                    stack.extend(commit.parents);
+                }
+            }
+            if !reaches_common {
+                return Ok(false);
+            }
+        }
+        self.is_ready = true;
+        Ok(true)
+    }
+}
+
+/// The way the history of the client changes with the pack that we send.
+#[derive(Default)]
+pub(crate) struct Shallow {
+    /// Commits that will be shallow on the client side, and which weren't shallow before.
+    pub shallow: Vec<ObjectId>,
+    /// Commits that were shallow on the client side but will have their parents after receiving the pack.
+    pub unshallow: Vec<ObjectId>,
+    /// Commits whose parents won't be sent as they are the new shallow boundary of the client.
+    boundary: HashSet,
+}
+
+impl Shallow {
+    /// Compute the shallow boundary of the client after receiving the objects of `request`.
+    pub fn compute(db: &impl Objects, request: &Request, refs: &[Ref]) -> Result<Self, Error> {
+        let mut buf = Vec::new();
+        let mut want_commits = Vec::new();
+        for want in &request.wants {
+            want_commits.extend(peel_to_commit(db, want, &mut buf)?);
+        }
+        let mut client_shallow = HashSet::default();
+        for id in &request.client_shallow {
+            if find_commit(db, id, &mut buf)?.is_some() {
+                client_shallow.insert(*id);
+            }
+        }
+
+        let mut boundary = HashSet::default();
+        let mut not_shallow = HashSet::default();
+        if let Some(depth) = request.depth {
+            let (starts, limit) = if request.deepen_relative {
+                (client_shallow.iter().copied().collect(), depth.saturating_add(1))
+            } else {
+                (want_commits, depth)
+            };
+            let mut queue: VecDeque<_> = starts.into_iter().map(|id| (id, 1)).collect();
+            let mut seen = HashSet::default();
+            while let Some((id, depth)) = queue.pop_front() {
+                if !seen.insert(id) {
+                    continue;
+                }
+                let Some(commit) = find_commit(db, &id, &mut buf)? else {
+                    continue;
+                };
+                if depth >= limit {
+                    if !commit.parents.is_empty() {
+                        boundary.insert(id);
+                    }
+                    continue;
+                }
+                not_shallow.insert(id);
+                queue.extend(commit.parents.into_iter().map(|id| (id, depth + 1)));
+            }
+        } else if request.deepen_since.is_some() || !request.deepen_not.is_empty() {
+            let mut excluded = HashSet::default();
+            {
+                let mut stack = Vec::new();
+                for name in &request.deepen_not {
+                    let id = resolve_ref(refs, name.as_ref())
+                        .ok_or_else(|| Error::InvalidDeepenNot { name: name.clone() })?;
+                    stack.extend(peel_to_commit(db, &id, &mut buf)?);
+                }
+                while let Some(id) = stack.pop() {
+                    if !excluded.insert(id) {
+                        continue;
+                    }
+                    if let Some(commit) = find_commit(db, &id, &mut buf)? {
+                        stack.extend(commit.parents);
+                    }
+                }
+            }
+            let mut is_excluded = |id: &oid| -> Result<bool, Error> {
+                Ok(excluded.contains(id)
+                    || match (request.deepen_since, find_commit(db, id, &mut buf)?) {
+                        (Some(since), Some(commit)) => commit.time < since,
+                        _ => false,
+                    })
+            };
+
+            let mut stack = want_commits;
+            let mut seen = HashSet::default();
+            let mut commit_buf = Vec::new();
+            while let Some(id) = stack.pop() {
+                if !seen.insert(id) {
+                    continue;
+                }
+                let Some(commit) = find_commit(db, &id, &mut commit_buf)? else {
+                    continue;
+                };
+                let mut has_excluded_parent = false;
+                for parent in &commit.parents {
+                    has_excluded_parent |= is_excluded(parent)?;
+                }
+                if has_excluded_parent {
+                    boundary.insert(id);
+                } else {
+                    not_shallow.insert(id);
+                    stack.extend(commit.parents);
+                }
+            }
+        }
+
+        let mut shallow: Vec<_> = boundary
+            .iter()
+            .filter(|id| !client_shallow.contains(*id))
+            .copied()
+            .collect();
+        shallow.sort();
+        let mut unshallow: Vec<_> = client_shallow
+            .iter()
+            .filter(|id| not_shallow.contains(*id))
+            .copied()
+            .collect();
+        unshallow.sort();
+        Ok(Shallow {
+            shallow,
+            unshallow,
+            boundary,
+        })
+    }
+}
+
+/// Resolve `name` like `main`, `refs/tags/v1.0` or a full hex object id to the object it points to.
+pub(crate) fn resolve_ref(refs: &[Ref], name: &[u8]) -> Option<ObjectId> {
+    for candidate in [
+        name.to_owned(),
+        [b"refs/".as_slice(), name].concat(),
+        [b"refs/tags/".as_slice(), name].concat(),
+        [b"refs/heads/".as_slice(), name].concat(),
+    ] {
+        if let Some(target) = refs.iter().find_map(|r| {
+            let (ref_name, target, _peeled) = r.unpack();
+            (ref_name == candidate.as_slice()).then_some(target).flatten()
+        }) {
+            return Some(target.to_owned());
+        }
+    }
+    ObjectId::from_hex(name).ok()
+}
+
+/// Return all objects to send to the client so that it obtains all objects reachable from the wants of `request`,
+/// assuming it has the `common` objects along with everything reachable from them.
+///
+/// If there is a reachability bitmap in the [pack directory][Options::pack_dir], it's used to count objects unless the client
+/// is shallow or filters trees by depth.
+pub(crate) fn count_objects(
+    db: &impl Objects,
+    request: &Request,
+    common: &[ObjectId],
+    shallow: &Shallow,
+    refs: &[Ref],
+    options: &Options,
+    should_interrupt: &AtomicBool,
+) -> Result<Vec<output::Count>, Error> {
+    let mut buf = Vec::new();
+    let bitmap = options
+        .pack_dir
+        .as_ref()
+        .filter(|_| !request.is_shallow() && !matches!(request.filter, Some(Filter::TreeDepth { .. })))
+        .and_then(|pack_dir| {
+            gix_pack::bitmap::Indexed::at_pack_dir(pack_dir, options.object_hash)
+                .ok()
+                .flatten()
+        });
+    let Traversal {
+        mut counts,
+        hidden,
+        tree_roots,
+        trees_and_blobs,
+    } = match bitmap.as_ref().and_then(|bitmap| bitmap.reachability().ok()) {
+        Some(reachability) => {
+            let (counts, _outcome) = output::count::objects_with_bitmap(
+                db,
+                &reachability,
+                &mut request.wants.iter().copied().map(Ok),
+                &mut common.iter().copied().map(Ok),
+                &progress::Discard,
+                should_interrupt,
+            )?;
+            // All objects reachable from `common` are excluded already, and tags the client has can't point to objects it doesn't have.
+            Traversal {
+                counts,
+                ..Default::default()
+            }
+        }
+        None => count_objects_by_traversal(db, request, common, shallow, should_interrupt)?,
+    };
+
+    if request.include_tag {
+        let mut sent: HashSet = counts.iter().map(|count| count.id).collect();
+        let mut tags = Vec::new();
+        for r in refs {
+            let (_name, Some(tag), Some(peeled)) = r.unpack() else {
+                continue;
+            };
+            if !sent.contains(peeled) {
+                continue;
+            }
+            let mut id = tag.to_owned();
+            while !hidden.contains(&id) && sent.insert(id) {
+                let object = db.find(&id, &mut buf)?;
+                if object.kind != Kind::Tag {
+                    break;
+                }
+                tags.push(id);
+                id = TagRefIter::from_bytes(object.data).target_id()?;
+            }
+        }
+        count_objects_unthreaded(db, &mut counts, tags, ObjectExpansion::AsIs, should_interrupt)?;
+    }
+
+    let mut seen = HashSet::default();
+    counts.retain(|count| !hidden.contains(&count.id) && seen.insert(count.id));
+
+    if let Some(filter) = request.filter {
+        let explicitly_wanted: HashSet = request.wants.iter().copied().collect();
+        let within_depth = match filter {
+            Filter::TreeDepth { depth } if depth > 0 => Some(objects_within_tree_depth(
+                db,
+                &tree_roots,
+                &trees_and_blobs,
+                depth,
+                &mut buf,
+            )?),
+            _ => None,
+        };
+        let mut filtered = Vec::with_capacity(counts.len());
+        for count in counts {
+            let header = db
+                .try_header(&count.id)
+                .map_err(Error::Find)?
+                .expect("counted objects exist");
+            let keep = explicitly_wanted.contains(&count.id)
+                || match (header.kind, filter) {
+                    (Kind::Commit | Kind::Tag, _) => true,
+                    (Kind::Blob, Filter::BlobNone) => false,
+                    (Kind::Blob, Filter::BlobLimit { bytes }) => header.size < bytes,
+                    (Kind::Tree, Filter::BlobNone | Filter::BlobLimit { .. }) => true,
+                    (Kind::Tree | Kind::Blob, Filter::TreeDepth { .. }) => within_depth
+                        .as_ref()
+                        .map_or(false, |objects| objects.contains(&count.id)),
+                };
+            if keep {
+                filtered.push(count);
+            }
+        }
+        counts = filtered;
+    }
+    Ok(counts)
+}
+
+/// Objects to send, along with what's needed to filter them.
+#[derive(Default)]
+struct Traversal {
+    counts: Vec<output::Count>,
+    /// Objects the client has, which aren't sent.
+    hidden: HashSet,
+    /// The commits whose trees are filtered by depth.
+    tree_roots: Vec<ObjectId>,
+    /// Trees and blobs that were wanted directly.
+    trees_and_blobs: Vec<ObjectId>,
+}
+
+/// Count the objects to send by traversing the commit graph from the wants of `request`, while hiding the `common` objects
+/// and the objects reachable from them.
+fn count_objects_by_traversal(
+    db: &impl Objects,
+    request: &Request,
+    common: &[ObjectId],
+    shallow: &Shallow,
+    should_interrupt: &AtomicBool,
+) -> Result<Traversal, Error> {
+    let mut buf = Vec::new();
+    let hidden = {
+        let client_shallow: HashSet = request.client_shallow.iter().copied().collect();
+        let mut hidden = HashSet::default();
+        let mut edges = Vec::new();
+        for id in common.iter().chain(client_shallow.iter()) {
+            match db.try_header(id).map_err(Error::Find)?.map(|header| header.kind) {
+                Some(Kind::Commit) => edges.push(*id),
+                Some(_) => {
+                    hidden.insert(*id);
+                }
+                None => {}
+            }
+        }
+
+        let mut stack = edges.clone();
+        while let Some(id) = stack.pop() {
+            if !hidden.insert(id) {
+                continue;
+            }
+            if should_interrupt.load(Ordering::Relaxed) {
+                return Err(Error::Interrupted);
+            }
+            if !client_shallow.contains(&id) {
+                stack.extend(db.find_commit_iter(&id, &mut buf)?.parent_ids());
+            }
+        }
+        for id in edges {
+            let tree_id = db.find_commit_iter(&id, &mut buf)?.tree_id()?;
+            hidden.insert(tree_id);
+            let tree = db.find_tree_iter(&tree_id, &mut buf)?;
+            let mut recorder = gix_traverse::tree::Recorder::default().track_location(None);
+            gix_traverse::tree::breadthfirst(
+                tree,
+                gix_traverse::tree::breadthfirst::State::default(),
+                db,
+                &mut recorder,
+            )?;
+            hidden.extend(recorder.records.into_iter().map(|entry| entry.oid));
+        }
+        hidden
+    };
+
+    let (mut commits, mut boundary_commits, mut tags, mut trees_and_blobs) =
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    {
+        let mut seen = HashSet::default();
+        let mut stack = request.wants.clone();
+        for id in &shallow.unshallow {
+            stack.extend(db.find_commit_iter(id, &mut buf)?.parent_ids());
+        }
+        while let Some(id) = stack.pop() {
+            if hidden.contains(&id) || !seen.insert(id) {
+                continue;
+            }
+            if should_interrupt.load(Ordering::Relaxed) {
+                return Err(Error::Interrupted);
+            }
+            let object = db.find(&id, &mut buf)?;
+            match object.kind {
+                Kind::Tag => {
+                    stack.push(TagRefIter::from_bytes(object.data).target_id()?);
+                    tags.push(id);
+                }
+                Kind::Commit if shallow.boundary.contains(&id) => boundary_commits.push(id),
+                Kind::Commit => {
+                    stack.extend(CommitRefIter::from_bytes(object.data).parent_ids());
+                    commits.push(id);
+                }
+                Kind::Tree | Kind::Blob => trees_and_blobs.push(id),
+            }
+        }
+    }
+
+    let tree_roots: Vec<_> = if matches!(request.filter, Some(Filter::TreeDepth { depth }) if depth > 0) {
+        commits.iter().chain(boundary_commits.iter()).copied().collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut counts = Vec::new();
+    count_objects_unthreaded(
+        db,
+        &mut counts,
+        commits,
+        ObjectExpansion::TreeAdditionsComparedToAncestor,
+        should_interrupt,
+    )?;
+    count_objects_unthreaded(
+        db,
+        &mut counts,
+        boundary_commits
+            .into_iter()
+            .chain(trees_and_blobs.iter().copied())
+            .collect(),
+        ObjectExpansion::TreeContents,
+        should_interrupt,
+    )?;
+    count_objects_unthreaded(db, &mut counts, tags, ObjectExpansion::AsIs, should_interrupt)?;
+    Ok(Traversal {
+        counts,
+        hidden,
+        tree_roots,
+        trees_and_blobs,
+    })
+}
+
+/// Add the counts of `ids` and the objects they expand to according to `expansion` to `counts`.
+fn count_objects_unthreaded(
+    db: &impl Objects,
+    counts: &mut Vec<output::Count>,
+    ids: Vec<ObjectId>,
+    expansion: ObjectExpansion,
+    should_interrupt: &AtomicBool,
+) -> Result<(), Error> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let (new_counts, _outcome) = output::count::objects_unthreaded(
+        db,
+        &mut ids.into_iter().map(Ok),
+        &progress::Discard,
+        should_interrupt,
+        expansion,
+    )?;
+    counts.extend(new_counts);
+    Ok(())
+}
+
+/// Return all trees and blobs whose depth from the root trees of `commits` or from `trees_and_blobs` is less than `max_depth`.
+fn objects_within_tree_depth(
+    db: &impl Objects,
+    commits: &[ObjectId],
+    trees_and_blobs: &[ObjectId],
+    max_depth: u64,
+    buf: &mut Vec<u8>,
+) -> Result<HashSet, Error> {
+    let mut min_depth = HashMap::<ObjectId, u64>::default();
+    let mut stack = Vec::new();
+    for id in commits {
+        stack.push((db.find_commit_iter(id, buf)?.tree_id()?, 0));
+    }
+    for id in trees_and_blobs {
+        match db.try_header(id).map_err(Error::Find)?.map(|header| header.kind) {
+            Some(Kind::Tree) => stack.push((*id, 0)),
+            _ => {
+                min_depth.insert(*id, 0);
+            }
+        }
+    }
+    while let Some((tree_id, depth)) = stack.pop() {
+        if min_depth.get(&tree_id).map_or(false, |min| *min <= depth) {
+            continue;
+        }
+        min_depth.insert(tree_id, depth);
+        if depth + 1 >= max_depth {
+            continue;
+        }
+        for entry in db.find_tree_iter(&tree_id, buf)? {
+            let entry = entry?;
+            if entry.mode.is_tree() {
+                stack.push((entry.oid.to_owned(), depth + 1));
+            } else if !entry.mode.is_commit() {
+                let min = min_depth.entry(entry.oid.to_owned()).or_insert(depth + 1);
+                *min = (*min).min(depth + 1);
+            }
+        }
+    }
+    Ok(min_depth.into_keys().collect())
+}
+
+/// Write a pack with all objects in `counts` to `out`, using side-band packet lines with up to `max_band_data_len` bytes of
+/// data if set, or the raw pack otherwise. If `send_progress` is `true`, progress messages are sent on the side-band as well.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn write_pack(
+    db: impl Objects,
+    counts: Vec<output::Count>,
+    out: &mut dyn io::Write,
+    max_band_data_len: Option<usize>,
+    send_progress: bool,
+    object_hash: gix_hash::Kind,
+    thread_limit: Option<usize>,
+    should_interrupt: &AtomicBool,
+) -> Result<(), Error> {
+    let num_objects = counts.len();
+    let send_progress = send_progress && max_band_data_len.is_some();
+    if send_progress {
+        encode::band_to_write(
+            Channel::Progress,
+            format!("Enumerating objects: {num_objects}, done.\n").as_bytes(),
+            &mut *out,
+        )?;
+    }
+
+    let mut entries = InOrderIter::from(output::entry::iter_from_counts(
+        counts,
+        db,
+        Box::new(progress::Discard),
+        output::entry::iter_from_counts::Options {
+            thread_limit,
+            allow_thin_pack: false,
+            ..Default::default()
+        },
+    ));
+    {
+        let mut band;
+        let out: &mut dyn io::Write = match max_band_data_len {
+            Some(max_data_len) => {
+                band = io::BufWriter::with_capacity(
+                    max_data_len,
+                    Band {
+                        out: &mut *out,
+                        channel: Channel::Data,
+                        max_data_len,
+                    },
+                );
+                &mut band
+            }
+            None => &mut *out,
+        };
+        let mut pack = output::bytes::FromEntriesIter::new(
+            entries.by_ref(),
+            out,
+            num_objects as u32,
+            gix_pack::data::Version::V2,
+            object_hash,
+        );
+        for written in pack.by_ref() {
+            if should_interrupt.load(Ordering::Relaxed) {
+                return Err(Error::Interrupted);
+            }
+            written?;
+        }
+        pack.into_write().flush()?;
+    }
+
+    if send_progress {
+        encode::band_to_write(
+            Channel::Progress,
+            format!("Total {num_objects} (delta 0), reused 0 (delta 0), pack-reused 0\n").as_bytes(),
+            &mut *out,
+        )?;
+    }
+    Ok(())
+}
+
+/// Turn all data written into side-band packet lines on `channel`.
+pub(crate) struct Band<'a> {
+    pub out: &'a mut dyn io::Write,
+    pub channel: Channel,
+    pub max_data_len: usize,
+}
+
+impl io::Write for Band<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for chunk in buf.chunks(self.max_data_len) {
+            encode::band_to_write(self.channel, chunk, &mut *self.out)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+struct Commit {
+    parents: Vec<ObjectId>,
+    time: SecondsSinceUnixEpoch,
+}
+
+/// Return the commit with `id`, or `None` if it doesn't exist or isn't a commit.
+fn find_commit(db: &impl Objects, id: &oid, buf: &mut Vec<u8>) -> Result<Option<Commit>, Error> {
+    let Some(object) = gix_object::Find::try_find(db, id, buf).map_err(Error::Find)? else {
+        return Ok(None);
+    };
+    if object.kind != Kind::Commit {
+        return Ok(None);
+    }
+    Ok(Some(Commit {
+        parents: CommitRefIter::from_bytes(object.data).parent_ids().collect(),
+        time: CommitRefIter::from_bytes(object.data).committer()?.time.seconds,
+    }))
+}
+
+/// Follow tags starting at `id` until a commit is found, or return `None` if the chain ends in any other object.
+fn peel_to_commit(db: &impl Objects, id: &oid, buf: &mut Vec<u8>) -> Result<Option<ObjectId>, Error> {
+    let mut id = id.to_owned();
+    loop {
+        let Some(object) = gix_object::Find::try_find(db, &id, buf).map_err(Error::Find)? else {
+            return Ok(None);
+        };
+        match object.kind {
+            Kind::Tag => id = TagRefIter::from_bytes(object.data).target_id()?,
+            Kind::Commit => return Ok(Some(id)),
+            Kind::Tree | Kind::Blob => return Ok(None),
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use std::{fmt::Write, io, sync::atomic::AtomicBool};
+
+use bstr::{BStr, ByteSlice};
+use gix_hash::ObjectId;
+use gix_packetline_blocking::encode;
+use gix_transport::Protocol;
+
+use crate::{
+    handshake::Ref,
+    upload_pack::{
+        function::head_first,
+        line::{Line, Lines},
+        pack::{self, Negotiation, Objects, Request, Shallow},
+        Error, Options,
+    },
+    Filter,
+};
+
+#[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
+enum MultiAck {
+    #[default]
+    None,
+    Basic,
+    Detailed,
+}
+
+/// The capabilities the client chose with its first `want` line.
+#[derive(Default)]
+struct Capabilities {
+    multi_ack: MultiAck,
+    no_done: bool,
+    max_band_data_len: Option<usize>,
+}
+
+pub(crate) fn serve(
+    refs: &[Ref],
+    db: impl Objects,
+    version: Protocol,
+    input: &mut dyn io::Read,
+    out: &mut dyn io::Write,
+    should_interrupt: &AtomicBool,
+    options: &Options,
+) -> Result<(), Error> {
+    if !options.stateless_rpc {
+        advertise(refs, version, out, options)?;
+        out.flush()?;
+    }
+    if options.advertise_refs {
+        return Ok(());
+    }
+
+    let mut lines = Lines::<'_, Error>::new(input, options.trace);
+    let mut request = Request::default();
+    let mut capabilities = Capabilities::default();
+    loop {
+        match lines.next()? {
+            None => return Ok(()),
+            Some(Line::Flush) => break,
+            Some(Line::Delimiter) => return Err(unexpected_line("0001")),
+            Some(Line::Data(line)) => parse_request_line(line, &mut request, &mut capabilities, options)?,
+        }
+    }
+    if request.wants.is_empty() {
+        return Ok(());
+    }
+    request.validate_wants(&db, refs, options.allow_any_object_in_want)?;
+
+    let shallow = if request.is_shallow() {
+        let shallow = Shallow::compute(&db, &request, refs)?;
+        for id in &shallow.shallow {
+            encode::text_to_write(format!("shallow {id}").as_bytes(), &mut *out)?;
+        }
+        for id in &shallow.unshallow {
+            encode::text_to_write(format!("unshallow {id}").as_bytes(), &mut *out)?;
+        }
+        encode::flush_to_write(&mut *out)?;
+        out.flush()?;
+        shallow
+    } else {
+        Shallow::default()
+    };
+
+    let mut negotiation = Negotiation::default();
+    let mut last_common = None;
+    let (mut got_common, mut got_other, mut sent_ready) = (false, false, false);
+    loop {
+        let line = match lines.next()? {
+            None => return Ok(()),
+            Some(Line::Flush) => {
+                if capabilities.multi_ack == MultiAck::Detailed
+                    && got_common
+                    && !got_other
+                    && negotiation.ok_to_give_up(&db, &request.wants)?
+                {
+                    sent_ready = true;
+                    ack(out, last_common.as_ref(), " ready")?;
+                }
+                if negotiation.common.is_empty() || capabilities.multi_ack != MultiAck::None {
+                    encode::text_to_write(b"NAK", &mut *out)?;
+                }
+                if capabilities.no_done && sent_ready {
+                    ack(out, last_common.as_ref(), "")?;
+                    break;
+                }
+                out.flush()?;
+                if options.stateless_rpc {
+                    return Ok(());
+                }
+                got_common = false;
+                got_other = false;
+                continue;
+            }
+            Some(Line::Delimiter) => return Err(unexpected_line("0001")),
+            Some(Line::Data(line)) => line,
+        };
+        if let Some(id) = line.strip_prefix(b"have ") {
+            let id = parse_id(id, line)?;
+            if negotiation.add_have(&db, id)? {
+                got_common = true;
+                last_common = Some(id);
+                match capabilities.multi_ack {
+                    MultiAck::Detailed => ack(out, Some(&id), " common")?,
+                    MultiAck::Basic => ack(out, Some(&id), " continue")?,
+                    MultiAck::None if negotiation.common.len() == 1 => ack(out, Some(&id), "")?,
+                    MultiAck::None => {}
+                }
+            } else {
+                got_other = true;
+                if capabilities.multi_ack != MultiAck::None && negotiation.ok_to_give_up(&db, &request.wants)? {
+                    match capabilities.multi_ack {
+                        MultiAck::Detailed => {
+                            sent_ready = true;
+                            ack(out, Some(&id), " ready")?;
+                        }
+                        _ => ack(out, Some(&id), " continue")?,
+                    }
+                }
+            }
+        } else if line == "done" {
+            match last_common {
+                Some(id) if capabilities.multi_ack != MultiAck::None => ack(out, Some(&id), "")?,
+                Some(_) => {}
+                None => {
+                    encode::text_to_write(b"NAK", &mut *out)?;
+                }
+            }
+            break;
+        } else {
+            return Err(unexpected_line(line));
+        }
+    }
+
+    let counts = pack::count_objects(
+        &db,
+        &request,
+        &negotiation.common,
+        &shallow,
+        refs,
+        options,
+        should_interrupt,
+    )?;
+    pack::write_pack(
+        db,
+        counts,
+        out,
+        capabilities.max_band_data_len,
+        !request.no_progress,
+        options.object_hash,
+        options.thread_limit,
+        should_interrupt,
+    )?;
+    if capabilities.max_band_data_len.is_some() {
+        encode::flush_to_write(&mut *out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn advertise(refs: &[Ref], version: Protocol, out: &mut dyn io::Write, options: &Options) -> Result<(), Error> {
+    if version == Protocol::V1 {
+        encode::text_to_write(b"version 1", &mut *out)?;
+    }
+    let mut capabilities = String::from(
+        "multi_ack side-band side-band-64k ofs-delta shallow deepen-since deepen-not deepen-relative no-progress include-tag multi_ack_detailed",
+    );
+    if options.allow_any_object_in_want {
+        capabilities.push_str(" allow-tip-sha1-in-want allow-reachable-sha1-in-want");
+    }
+    capabilities.push_str(" no-done");
+    for r in head_first(refs) {
+        if let Ref::Symbolic {
+            full_ref_name, target, ..
+        }
+        | Ref::Unborn { full_ref_name, target } = r
+        {
+            write!(capabilities, " symref={full_ref_name}:{target}").expect("cannot fail");
+        }
+    }
+    if options.allow_filter {
+        capabilities.push_str(" filter");
+    }
+    write!(
+        capabilities,
+        " object-format={} agent={}",
+        options.object_hash.as_str(),
+        options.agent
+    )
+    .expect("cannot fail");
+
+    let mut capabilities = Some(capabilities);
+    let mut has_refs = false;
+    let mut write_ref = |id: &gix_hash::oid, name: &BStr| -> io::Result<()> {
+        let mut line = format!("{id} ").into_bytes();
+        line.extend_from_slice(name);
+        if let Some(capabilities) = capabilities.take() {
+            line.push(0);
+            line.extend_from_slice(capabilities.as_bytes());
+        }
+        encode::text_to_write(&line, &mut *out).map(|_| ())
+    };
+    for r in head_first(refs) {
+        let (name, Some(target), peeled) = r.unpack() else {
+            continue;
+        };
+        has_refs = true;
+        write_ref(target, name)?;
+        if let Some(peeled) = peeled {
+            write_ref(peeled, format!("{name}^{{}}").as_bytes().as_bstr())?;
+        }
+    }
+    if !has_refs {
+        write_ref(&options.object_hash.null(), "capabilities^{}".into())?;
+    }
+    encode::flush_to_write(&mut *out)?;
+    Ok(())
+}
+
+fn parse_request_line(
+    line: &BStr,
+    request: &mut Request,
+    capabilities: &mut Capabilities,
+    options: &Options,
+) -> Result<(), Error> {
+    if let Some(rest) = line.strip_prefix(b"want ") {
+        let (id, features) = match rest.find_byte(b' ') {
+            Some(pos) => (&rest[..pos], Some(&rest[pos + 1..])),
+            None => (rest, None),
+        };
+        let id = parse_id(id, line)?;
+        if request.wants.is_empty() {
+            for feature in features.unwrap_or_default().split_str(" ") {
+                match feature {
+                    b"multi_ack_detailed" => capabilities.multi_ack = MultiAck::Detailed,
+                    b"multi_ack" if capabilities.multi_ack == MultiAck::None => {
+                        capabilities.multi_ack = MultiAck::Basic;
+                    }
+                    b"no-done" => capabilities.no_done = true,
+                    b"side-band-64k" => capabilities.max_band_data_len = Some(pack::MAX_BAND_DATA_LEN),
+                    b"side-band" if capabilities.max_band_data_len.is_none() => {
+                        capabilities.max_band_data_len = Some(pack::MAX_SMALL_BAND_DATA_LEN);
+                    }
+                    b"no-progress" => request.no_progress = true,
+                    b"include-tag" => request.include_tag = true,
+                    b"deepen-relative" => request.deepen_relative = true,
+                    _ => {}
+                }
+            }
+        }
+        request.wants.push(id);
+    } else if let Some(id) = line.strip_prefix(b"shallow ") {
+        request.client_shallow.push(parse_id(id, line)?);
+    } else if let Some(depth) = line.strip_prefix(b"deepen ") {
+        request.depth = Some(parse_number(depth, line)?);
+    } else if let Some(time) = line.strip_prefix(b"deepen-since ") {
+        request.deepen_since = Some(parse_number(time, line)?);
+    } else if let Some(name) = line.strip_prefix(b"deepen-not ") {
+        request.deepen_not.push(name.into());
+    } else if let Some(spec) = line.strip_prefix(b"filter ") {
+        if !options.allow_filter {
+            return Err(Error::FilterNotAllowed);
+        }
+        request.filter = Some(Filter::from_bytes(spec.as_bstr())?);
+    } else {
+        return Err(unexpected_line(line));
+    }
+    Ok(())
+}
+
+fn ack(out: &mut dyn io::Write, id: Option<&ObjectId>, status: &str) -> Result<(), Error> {
+    let id = id.expect("BUG: acknowledgements are only sent after having found a common object");
+    encode::text_to_write(format!("ACK {id}{status}").as_bytes(), &mut *out)?;
+    Ok(())
+}
+
+pub(crate) fn parse_id(hex: &[u8], line: &BStr) -> Result<ObjectId, Error> {
+    ObjectId::from_hex(hex).map_err(|_| unexpected_line(line))
+}
+
+pub(crate) fn parse_number<T: std::str::FromStr>(number: &[u8], line: &BStr) -> Result<T, Error> {
+    number
+        .to_str()
+        .ok()
+        .and_then(|number| number.parse().ok())
+        .ok_or_else(|| unexpected_line(line))
+}
+
+pub(crate) fn unexpected_line(line: impl AsRef<[u8]>) -> Error {
+    Error::UnexpectedLine {
+        line: line.as_ref().into(),
+    }
+}
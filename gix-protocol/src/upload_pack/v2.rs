@@ -0,0 +1,360 @@
+use std::{fmt::Write, io, sync::atomic::AtomicBool};
+
+use bstr::{BStr, BString, ByteSlice};
+use gix_packetline_blocking::encode;
+
+use crate::{
+    handshake::Ref,
+    upload_pack::{
+        function::head_first,
+        line::{Line, Lines},
+        pack::{self, Negotiation, Objects, Request, Shallow},
+        v1::{parse_id, parse_number, unexpected_line},
+        Error, Options,
+    },
+    Filter,
+};
+
+/// A single command sent by the client, along with its arguments.
+struct Command {
+    name: BString,
+    arguments: Vec<BString>,
+}
+
+pub(crate) fn serve(
+    refs: &[Ref],
+    db: impl Objects,
+    input: &mut dyn io::Read,
+    out: &mut dyn io::Write,
+    should_interrupt: &AtomicBool,
+    options: &Options,
+) -> Result<(), Error> {
+    if !options.stateless_rpc {
+        advertise(out, options)?;
+        out.flush()?;
+    }
+    if options.advertise_refs {
+        return Ok(());
+    }
+
+    let mut lines = Lines::new(input, options.trace);
+    while let Some(command) = read_command(&mut lines)? {
+        match command.name.as_bytes() {
+            b"ls-refs" => ls_refs(refs, &command.arguments, out)?,
+            b"fetch" => fetch(refs, db.clone(), &command.arguments, out, should_interrupt, options)?,
+            b"object-info" if options.advertise_object_info => object_info(&db, &command.arguments, out)?,
+            b"bundle-uri" if !options.bundle_list.is_empty() => bundle_uri(options, out)?,
+            _ => return Err(Error::UnknownCommand { command: command.name }),
+        }
+        out.flush()?;
+        if options.stateless_rpc {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn advertise(out: &mut dyn io::Write, options: &Options) -> Result<(), Error> {
+    let mut fetch = String::from("fetch=shallow wait-for-done");
+    if options.allow_filter {
+        fetch.push_str(" filter");
+    }
+    if options.allow_ref_in_want {
+        fetch.push_str(" ref-in-want");
+    }
+    if !options.packfile_uris.is_empty() {
+        fetch.push_str(" packfile-uris");
+    }
+    let mut lines = vec![
+        "version 2".into(),
+        format!("agent={}", options.agent),
+        "ls-refs=unborn".into(),
+        fetch,
+        "server-option".into(),
+        format!("object-format={}", options.object_hash.as_str()),
+    ];
+    if options.advertise_object_info {
+        lines.push("object-info".into());
+    }
+    if !options.bundle_list.is_empty() {
+        lines.push("bundle-uri".into());
+    }
+    for line in lines {
+        encode::text_to_write(line.as_bytes(), &mut *out)?;
+    }
+    encode::flush_to_write(&mut *out)?;
+    Ok(())
+}
+
+/// Read the next command, or return `None` if the client is done.
+fn read_command(lines: &mut Lines<'_, Error>) -> Result<Option<Command>, Error> {
+    let name = match lines.next()? {
+        None | Some(Line::Flush) => return Ok(None),
+        Some(Line::Delimiter) => return Err(unexpected_line("0001")),
+        Some(Line::Data(line)) => match line.strip_prefix(b"command=") {
+            Some(name) => name.as_bstr().to_owned(),
+            None => return Err(unexpected_line(line)),
+        },
+    };
+    // Capabilities like `agent` or `object-format` don't affect how we respond, so they are skipped.
+    loop {
+        match lines.next()? {
+            None => return Ok(None),
+            Some(Line::Flush) => {
+                return Ok(Some(Command {
+                    name,
+                    arguments: Vec::new(),
+                }))
+            }
+            Some(Line::Delimiter) => break,
+            Some(Line::Data(_capability)) => {}
+        }
+    }
+    let mut arguments = Vec::new();
+    loop {
+        match lines.next()? {
+            None => return Ok(None),
+            Some(Line::Flush) => break,
+            Some(Line::Delimiter) => return Err(unexpected_line("0001")),
+            Some(Line::Data(argument)) => arguments.push(argument.to_owned()),
+        }
+    }
+    Ok(Some(Command { name, arguments }))
+}
+
+fn ls_refs(refs: &[Ref], arguments: &[BString], out: &mut dyn io::Write) -> Result<(), Error> {
+    let (mut symrefs, mut peel, mut unborn) = (false, false, false);
+    let mut prefixes = Vec::new();
+    for argument in arguments {
+        match argument.as_bytes() {
+            b"symrefs" => symrefs = true,
+            b"peel" => peel = true,
+            b"unborn" => unborn = true,
+            _ => match argument.strip_prefix(b"ref-prefix ") {
+                Some(prefix) => prefixes.push(prefix),
+                None => return Err(unexpected_line(argument)),
+            },
+        }
+    }
+
+    for r in head_first(refs) {
+        let (name, target, peeled) = r.unpack();
+        if !prefixes.is_empty() && !prefixes.iter().any(|prefix| name.starts_with(prefix)) {
+            continue;
+        }
+        let mut line = match target {
+            Some(target) => format!("{target} {name}"),
+            None if unborn => format!("unborn {name}"),
+            None => continue,
+        };
+        if symrefs {
+            if let Ref::Symbolic { target, .. } | Ref::Unborn { target, .. } = r {
+                write!(line, " symref-target:{target}").expect("cannot fail");
+            }
+        }
+        if let Some(peeled) = peeled.filter(|_| peel) {
+            write!(line, " peeled:{peeled}").expect("cannot fail");
+        }
+        encode::text_to_write(line.as_bytes(), &mut *out)?;
+    }
+    encode::flush_to_write(&mut *out)?;
+    Ok(())
+}
+
+fn object_info(db: &impl Objects, arguments: &[BString], out: &mut dyn io::Write) -> Result<(), Error> {
+    let mut ids = Vec::new();
+    let mut size = false;
+    for argument in arguments {
+        match argument.strip_prefix(b"oid ") {
+            Some(id) => ids.push(parse_id(id, argument.as_ref())?),
+            None if argument == "size" => size = true,
+            None => return Err(unexpected_line(argument)),
+        }
+    }
+
+    if size {
+        encode::text_to_write(b"size", &mut *out)?;
+    }
+    for id in ids {
+        let mut line = id.to_string();
+        if size {
+            line.push(' ');
+            if let Some(header) = db.try_header(&id).map_err(Error::Find)? {
+                write!(line, "{}", header.size).expect("cannot fail");
+            }
+        }
+        encode::text_to_write(line.as_bytes(), &mut *out)?;
+    }
+    encode::flush_to_write(&mut *out)?;
+    Ok(())
+}
+
+fn bundle_uri(options: &Options, out: &mut dyn io::Write) -> Result<(), Error> {
+    for (key, value) in &options.bundle_list {
+        let mut line = key.clone();
+        line.push(b'=');
+        line.extend_from_slice(value);
+        encode::text_to_write(&line, &mut *out)?;
+    }
+    encode::flush_to_write(&mut *out)?;
+    Ok(())
+}
+
+fn fetch(
+    refs: &[Ref],
+    db: impl Objects,
+    arguments: &[BString],
+    out: &mut dyn io::Write,
+    should_interrupt: &AtomicBool,
+    options: &Options,
+) -> Result<(), Error> {
+    let mut request = Request::default();
+    let mut wanted_refs = Vec::new();
+    let mut haves = Vec::new();
+    let mut uri_protocols = Vec::new();
+    let (mut done, mut wait_for_done) = (false, false);
+    for argument in arguments {
+        let line: &BStr = argument.as_ref();
+        match line.as_bytes() {
+            b"done" => done = true,
+            b"wait-for-done" => wait_for_done = true,
+            b"no-progress" => request.no_progress = true,
+            b"include-tag" => request.include_tag = true,
+            b"deepen-relative" => request.deepen_relative = true,
+            // We never send thin packs or deltas, and always use the side-band, so these don't change anything.
+            b"thin-pack" | b"ofs-delta" | b"sideband-all" => {}
+            _ => {
+                if let Some(id) = line.strip_prefix(b"want ") {
+                    request.wants.push(parse_id(id, line)?);
+                } else if let Some(name) = line.strip_prefix(b"want-ref ").filter(|_| options.allow_ref_in_want) {
+                    let id = refs
+                        .iter()
+                        .find_map(|r| {
+                            let (ref_name, target, _peeled) = r.unpack();
+                            (ref_name == name).then_some(target).flatten()
+                        })
+                        .ok_or_else(|| Error::UnknownWantedRef { name: name.into() })?;
+                    request.wants.push(id.to_owned());
+                    wanted_refs.push((id.to_owned(), name.as_bstr()));
+                } else if let Some(id) = line.strip_prefix(b"have ") {
+                    haves.push(parse_id(id, line)?);
+                } else if let Some(id) = line.strip_prefix(b"shallow ") {
+                    request.client_shallow.push(parse_id(id, line)?);
+                } else if let Some(depth) = line.strip_prefix(b"deepen ") {
+                    request.depth = Some(parse_number(depth, line)?);
+                } else if let Some(time) = line.strip_prefix(b"deepen-since ") {
+                    request.deepen_since = Some(parse_number(time, line)?);
+                } else if let Some(name) = line.strip_prefix(b"deepen-not ") {
+                    request.deepen_not.push(name.into());
+                } else if let Some(spec) = line.strip_prefix(b"filter ") {
+                    if !options.allow_filter {
+                        return Err(Error::FilterNotAllowed);
+                    }
+                    request.filter = Some(Filter::from_bytes(spec.as_bstr())?);
+                } else if let Some(protocols) = line.strip_prefix(b"packfile-uris ") {
+                    uri_protocols = protocols.split_str(",").map(ToOwned::to_owned).collect();
+                } else {
+                    return Err(unexpected_line(line));
+                }
+            }
+        }
+    }
+    request.validate_wants(&db, refs, options.allow_any_object_in_want)?;
+
+    let mut negotiation = Negotiation::default();
+    if !done && !haves.is_empty() {
+        let mut common_in_request = Vec::new();
+        for id in haves {
+            if negotiation.add_have(&db, id)? {
+                common_in_request.push(id);
+            }
+        }
+        encode::text_to_write(b"acknowledgments", &mut *out)?;
+        if common_in_request.is_empty() {
+            encode::text_to_write(b"NAK", &mut *out)?;
+        }
+        for id in &common_in_request {
+            encode::text_to_write(format!("ACK {id}").as_bytes(), &mut *out)?;
+        }
+        if wait_for_done || !negotiation.ok_to_give_up(&db, &request.wants)? {
+            encode::flush_to_write(&mut *out)?;
+            return Ok(());
+        }
+        encode::text_to_write(b"ready", &mut *out)?;
+        encode::delim_to_write(&mut *out)?;
+    } else {
+        for id in haves {
+            negotiation.add_have(&db, id)?;
+        }
+    }
+
+    if !wanted_refs.is_empty() {
+        encode::text_to_write(b"wanted-refs", &mut *out)?;
+        for (id, name) in &wanted_refs {
+            encode::text_to_write(format!("{id} {name}").as_bytes(), &mut *out)?;
+        }
+        encode::delim_to_write(&mut *out)?;
+    }
+    let shallow = if request.is_shallow() {
+        let shallow = Shallow::compute(&db, &request, refs)?;
+        encode::text_to_write(b"shallow-info", &mut *out)?;
+        for id in &shallow.shallow {
+            encode::text_to_write(format!("shallow {id}").as_bytes(), &mut *out)?;
+        }
+        for id in &shallow.unshallow {
+            encode::text_to_write(format!("unshallow {id}").as_bytes(), &mut *out)?;
+        }
+        encode::delim_to_write(&mut *out)?;
+        shallow
+    } else {
+        Shallow::default()
+    };
+
+    let mut counts = pack::count_objects(
+        &db,
+        &request,
+        &negotiation.common,
+        &shallow,
+        refs,
+        options,
+        should_interrupt,
+    )?;
+    let offloaded: Vec<_> = options
+        .packfile_uris
+        .iter()
+        .filter(|p| {
+            uri_protocols.iter().any(|protocol| {
+                p.uri
+                    .strip_prefix(protocol.as_slice())
+                    .map_or(false, |rest| rest.starts_with(b":"))
+            }) && counts.iter().any(|count| count.id == p.object)
+        })
+        .collect();
+    if !offloaded.is_empty() {
+        counts.retain(|count| !offloaded.iter().any(|p| p.object == count.id));
+        encode::text_to_write(b"packfile-uris", &mut *out)?;
+        let mut seen_packs = Vec::new();
+        for p in offloaded {
+            if !seen_packs.contains(&p.pack_hash) {
+                seen_packs.push(p.pack_hash);
+                let mut line = BString::from(format!("{} ", p.pack_hash));
+                line.extend_from_slice(&p.uri);
+                encode::text_to_write(&line, &mut *out)?;
+            }
+        }
+        encode::delim_to_write(&mut *out)?;
+    }
+    encode::text_to_write(b"packfile", &mut *out)?;
+    pack::write_pack(
+        db,
+        counts,
+        out,
+        Some(pack::MAX_BAND_DATA_LEN),
+        !request.no_progress,
+        options.object_hash,
+        options.thread_limit,
+        should_interrupt,
+    )?;
+    encode::flush_to_write(&mut *out)?;
+    Ok(())
+}
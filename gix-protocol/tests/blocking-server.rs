@@ -0,0 +1,69 @@
+use bstr::{BStr, ByteSlice};
+
+pub use gix_testtools::Result;
+
+/// Encode `lines` as packet lines, with `0000` and `0001` denoting flush and delimiter packets.
+pub fn packet_lines(lines: &[&str]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for line in lines {
+        match *line {
+            "0000" | "0001" => out.extend_from_slice(line.as_bytes()),
+            line => out.extend_from_slice(format!("{:04x}{line}\n", line.len() + 5).as_bytes()),
+        }
+    }
+    out
+}
+
+/// The decoded output of the server.
+#[derive(Default)]
+pub struct Response {
+    /// All text lines along with special packets, like `0000` for flush packets.
+    pub lines: Vec<String>,
+    /// All data sent on the first side-band channel.
+    pub pack: Vec<u8>,
+    /// All messages sent on the second side-band channel.
+    pub progress: Vec<String>,
+}
+
+impl Response {
+    pub fn from_bytes(mut input: &[u8]) -> Self {
+        let mut res = Response::default();
+        while !input.is_empty() {
+            let len = usize::from_str_radix(std::str::from_utf8(&input[..4]).expect("ascii"), 16).expect("hex");
+            if len < 4 {
+                res.lines.push(format!("{len:04}"));
+                input = &input[4..];
+                continue;
+            }
+            let data = &input[4..len];
+            input = &input[len..];
+            match data[0] {
+                1 => res.pack.extend_from_slice(&data[1..]),
+                2 => res.progress.push(data[1..].to_str_lossy().trim_end().into()),
+                _ => res
+                    .lines
+                    .push(BStr::new(data.strip_suffix(b"\n").unwrap_or(data)).to_string()),
+            }
+        }
+        res
+    }
+
+    /// Return the amount of objects in the pack after validating it.
+    pub fn pack_objects(&self) -> usize {
+        let entries = gix_pack::data::input::BytesToEntriesIter::new_from_header(
+            std::io::BufReader::new(self.pack.as_slice()),
+            gix_pack::data::input::Mode::Verify,
+            gix_pack::data::input::EntryDataMode::Ignore,
+            gix_hash::Kind::Sha1,
+        )
+        .expect("valid pack header");
+        let num_objects = entries.len();
+        for entry in entries {
+            entry.expect("valid entry");
+        }
+        num_objects
+    }
+}
+
+mod receive_pack;
+mod upload_pack;
@@ -138,6 +138,141 @@ mod v1 {
             assert_eq!(bytes_read, 9703, "should be able to read the whole pack");
             Ok(())
         }
+
+        #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+        async fn fetch_stateless_no_done_reads_acks_and_pack() -> crate::Result {
+            let mut provider = mock_reader("v1/fetch.response");
+            let mut reader = provider.as_read_without_sidebands();
+            let r = fetch::Response::from_line_reader(Protocol::V1, &mut reader, false, false).await?;
+            assert_eq!(
+                r.acknowledgements().len(),
+                5,
+                "the NAK doesn't end a stateless response"
+            );
+            assert!(
+                r.has_pack(),
+                "the server sent a pack after 'ready' as the client sent 'no-done'"
+            );
+
+            let mut provider = mock_reader("v1/fetch-no-pack.response");
+            let r = fetch::Response::from_line_reader(
+                Protocol::V1,
+                &mut provider.as_read_without_sidebands(),
+                false,
+                false,
+            )
+            .await?;
+            assert_eq!(r.acknowledgements().last(), Some(&Acknowledgement::Nak));
+            assert!(!r.has_pack(), "the end of the stream ends the response");
+            Ok(())
+        }
+
+        #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+        async fn fetch_stateful_multi_ack_detailed() -> crate::Result {
+            let (a, b) = (
+                id("1111111111111111111111111111111111111111"),
+                id("2222222222222222222222222222222222222222"),
+            );
+            let mut provider = mock_reader("v1/fetch-stateful-multi_ack_detailed.response");
+            let mut reader = provider.as_read_without_sidebands();
+            let r = fetch::Response::from_line_reader(Protocol::V1, &mut reader, false, true).await?;
+            assert_eq!(
+                r.acknowledgements(),
+                &[Acknowledgement::Common(a), Acknowledgement::Nak]
+            );
+            assert!(!r.has_pack());
+
+            let r = fetch::Response::from_line_reader(Protocol::V1, &mut reader, false, true).await?;
+            assert_eq!(
+                r.acknowledgements(),
+                &[Acknowledgement::Common(b), Acknowledgement::Ready, Acknowledgement::Nak],
+                "the NAK ends each round"
+            );
+            assert!(!r.has_pack());
+
+            let r = fetch::Response::from_line_reader(Protocol::V1, &mut reader, true, false).await?;
+            assert_eq!(
+                r.acknowledgements(),
+                &[Acknowledgement::Common(b)],
+                "the final ACK after 'done'"
+            );
+            assert!(r.has_pack());
+            let mut buf = Vec::new();
+            reader.read_to_end(&mut buf).await?;
+            assert!(buf.starts_with(b"PACK"));
+            Ok(())
+        }
+
+        #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+        async fn fetch_stateful_multi_ack() -> crate::Result {
+            let a = id("1111111111111111111111111111111111111111");
+            let mut provider = mock_reader("v1/fetch-stateful-multi_ack.response");
+            let mut reader = provider.as_read_without_sidebands();
+            let r = fetch::Response::from_line_reader(Protocol::V1, &mut reader, false, true).await?;
+            assert_eq!(
+                r.acknowledgements(),
+                &[Acknowledgement::Common(a), Acknowledgement::Nak],
+                "'continue' is the multi_ack way of saying 'common'"
+            );
+
+            let r = fetch::Response::from_line_reader(Protocol::V1, &mut reader, false, true).await?;
+            assert_eq!(r.acknowledgements(), &[Acknowledgement::Nak]);
+            assert!(!r.has_pack());
+
+            let r = fetch::Response::from_line_reader(Protocol::V1, &mut reader, true, false).await?;
+            assert_eq!(r.acknowledgements(), &[Acknowledgement::Common(a)]);
+            assert!(r.has_pack());
+            let mut buf = Vec::new();
+            reader.read_to_end(&mut buf).await?;
+            assert!(buf.starts_with(b"PACK"));
+            Ok(())
+        }
+
+        #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+        async fn fetch_stateful_single_ack() -> crate::Result {
+            let mut provider = mock_reader("v1/fetch-stateful-single-ack.response");
+            let mut reader = provider.as_read_without_sidebands();
+            let r = fetch::Response::from_line_reader(Protocol::V1, &mut reader, false, true).await?;
+            assert_eq!(r.acknowledgements(), &[Acknowledgement::Nak]);
+
+            let r = fetch::Response::from_line_reader(Protocol::V1, &mut reader, false, true).await?;
+            assert_eq!(
+                r.acknowledgements(),
+                &[Acknowledgement::Common(id("1111111111111111111111111111111111111111"))],
+                "the first ACK ends the response, and the server stops acknowledging"
+            );
+            assert!(!r.has_pack());
+
+            let r = fetch::Response::from_line_reader(Protocol::V1, &mut reader, true, false).await?;
+            assert!(r.acknowledgements().is_empty(), "the pack follows 'done' right away");
+            assert!(r.has_pack());
+            let mut buf = Vec::new();
+            reader.read_to_end(&mut buf).await?;
+            assert!(buf.starts_with(b"PACK"));
+            Ok(())
+        }
+
+        #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+        async fn fetch_stateful_with_shallow_updates() -> crate::Result {
+            let a = id("1111111111111111111111111111111111111111");
+            let mut provider = mock_reader("v1/fetch-stateful-shallow.response");
+            let mut reader = provider.as_read_without_sidebands();
+            let r = fetch::Response::from_line_reader(Protocol::V1, &mut reader, false, true).await?;
+            assert_eq!(
+                r.shallow_updates(),
+                &[ShallowUpdate::Shallow(id("3333333333333333333333333333333333333333"))]
+            );
+            assert_eq!(
+                r.acknowledgements(),
+                &[Acknowledgement::Common(a), Acknowledgement::Nak]
+            );
+
+            let r = fetch::Response::from_line_reader(Protocol::V1, &mut reader, true, false).await?;
+            assert!(r.shallow_updates().is_empty());
+            assert_eq!(r.acknowledgements(), &[Acknowledgement::Common(a)]);
+            assert!(r.has_pack());
+            Ok(())
+        }
     }
 
     mod arguments {
@@ -209,7 +344,7 @@ mod v2 {
         use gix_packetline::read::ProgressAction;
         use gix_protocol::fetch::{
             self,
-            response::{Acknowledgement, ShallowUpdate},
+            response::{Acknowledgement, PackfileUri, ShallowUpdate},
         };
         use gix_transport::Protocol;
 
@@ -324,6 +459,39 @@ mod v2 {
             Ok(())
         }
 
+        #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+        async fn clone_with_packfile_uris() -> crate::Result {
+            let mut provider = mock_reader("v2/clone-packfile-uris.response");
+            let mut reader = provider.as_read_without_sidebands();
+            let r = fetch::Response::from_line_reader(Protocol::V2, &mut reader, true, true).await?;
+            assert_eq!(
+                r.packfile_uris(),
+                &[
+                    PackfileUri {
+                        hash: id("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"),
+                        uri: "https://cdn.example.com/packs/pack-1.pack".into()
+                    },
+                    PackfileUri {
+                        hash: id("bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb"),
+                        uri: "https://cdn.example.com/packs/pack-2.pack".into()
+                    }
+                ]
+            );
+            assert!(r.has_pack(), "the remaining objects are sent inline");
+            reader.set_progress_handler(Some(Box::new(|_is_err, _text| ProgressAction::Continue)));
+            let mut buf = Vec::new();
+            let bytes_read = reader.read_to_end(&mut buf).await?;
+            assert_eq!(bytes_read, 876, "should be able to read the whole pack");
+            Ok(())
+        }
+
+        #[test]
+        fn packfile_uri_lines_need_a_hash_and_uri() {
+            assert!(PackfileUri::from_line("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\n").is_err());
+            assert!(PackfileUri::from_line("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa \n").is_err());
+            assert!(PackfileUri::from_line("not-a-hash https://example.com/pack\n").is_err());
+        }
+
         #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
         async fn fetch_acks_without_pack() -> crate::Result {
             let mut provider = mock_reader("v2/fetch-no-pack.response");
@@ -425,5 +593,31 @@ mod v2 {
 0032have bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb\n0009done\n0000");
             Ok(())
         }
+
+        #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+        async fn packfile_uris() -> crate::Result {
+            let caps = Capabilities::from_lines("version 2\nfetch=shallow packfile-uris".into())?;
+            let mut args = fetch::Arguments::new(
+                Protocol::V2,
+                Command::Fetch.default_features(Protocol::V2, &caps),
+                false,
+            );
+            assert!(args.can_use_packfile_uris());
+            args.packfile_uris(["https", "http"]);
+            args.want(id("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"));
+
+            let mut out = Vec::new();
+            let mut transport = transport(
+                &mut out,
+                "v1/clone.response",
+                Protocol::V2,
+                gix_transport::client::git::ConnectMode::Daemon,
+            );
+
+            let _response = args.send(&mut transport, true).await?;
+            drop(_response);
+            assert_eq!(out.as_slice().as_bstr(), "0012command=fetch\n0001000ethin-pack\n000eofs-delta\n001dpackfile-uris https,http\n0032want aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\n0009done\n0000");
+            Ok(())
+        }
     }
 }
@@ -202,3 +202,118 @@ async fn ref_in_want() -> crate::Result {
 
     Ok(())
 }
+
+#[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+async fn bundle_uri() -> crate::Result {
+    let mut transport = transport(
+        Vec::new(),
+        "v2/bundle-uri.response",
+        Protocol::V2,
+        gix_transport::client::git::ConnectMode::Daemon,
+    );
+    let handshake = gix_protocol::handshake(
+        &mut transport,
+        gix_transport::Service::UploadPack,
+        helper_unused,
+        Vec::new(),
+        &mut progress::Discard,
+    )
+    .await?;
+    assert!(handshake.capabilities.contains("bundle-uri"));
+
+    let pairs = gix_protocol::bundle_uri(
+        &mut transport,
+        &handshake.capabilities,
+        Some(("agent", Some(gix_protocol::agent("agent").into()))),
+        &mut progress::Discard,
+        false,
+    )
+    .await?;
+    assert_eq!(
+        pairs
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<_>>(),
+        [
+            "bundle.version=1",
+            "bundle.mode=all",
+            "bundle.heuristic=creationToken",
+            "bundle.main.uri=https://example.com/main.bundle",
+            "bundle.main.creationToken=1"
+        ]
+    );
+    assert_eq!(
+        transport.into_inner().1.as_bstr(),
+        format!(
+            "002fgit-upload-pack does/not/matter\0\0version=2\00017command=bundle-uri
+0014agent={}
+0000",
+            gix_protocol::agent("agent")
+        )
+        .as_bytes()
+        .as_bstr()
+    );
+    Ok(())
+}
+
+#[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+async fn object_info() -> crate::Result {
+    let mut transport = transport(
+        Vec::new(),
+        "v2/object-info.response",
+        Protocol::V2,
+        gix_transport::client::git::ConnectMode::Daemon,
+    );
+    let handshake = gix_protocol::handshake(
+        &mut transport,
+        gix_transport::Service::UploadPack,
+        helper_unused,
+        Vec::new(),
+        &mut progress::Discard,
+    )
+    .await?;
+    assert!(handshake.capabilities.contains("object-info"));
+
+    let (blob, missing) = (
+        gix_hash::ObjectId::from_hex(b"d616f7380ad325123fed6f628d02fa76e1ce77c3")?,
+        gix_hash::ObjectId::from_hex(b"0000000000000000000000000000000000000001")?,
+    );
+    let infos = gix_protocol::object_info(
+        &mut transport,
+        &handshake.capabilities,
+        Some(("agent", Some(gix_protocol::agent("agent").into()))),
+        [blob, missing],
+        &mut progress::Discard,
+        false,
+    )
+    .await?;
+    assert_eq!(
+        infos,
+        [
+            gix_protocol::object_info::Info {
+                id: blob,
+                size: Some(6)
+            },
+            gix_protocol::object_info::Info {
+                id: missing,
+                size: None
+            }
+        ],
+        "missing objects have no size"
+    );
+    assert_eq!(
+        transport.into_inner().1.as_bstr(),
+        format!(
+            "002fgit-upload-pack does/not/matter\0\0version=2\00018command=object-info
+0014agent={}
+00010009size
+0031oid {blob}
+0031oid {missing}
+0000",
+            gix_protocol::agent("agent")
+        )
+        .as_bytes()
+        .as_bstr()
+    );
+    Ok(())
+}
@@ -0,0 +1,42 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q client
+(cd client
+  git checkout -q -b main
+  mkdir dir
+  echo a > a
+  echo b > dir/b
+  git add . && git commit -q -m c1
+  echo a2 > a && git commit -q -am c2
+  git branch other
+  git tag -a -m "annotated" v1
+)
+
+git clone -q --bare client server.git
+(cd server.git
+  git pack-refs --all
+)
+
+(cd client
+  echo a3 > a && git commit -q -am c3
+  git checkout -q -b feature
+  echo c > dir/c && git add dir/c && git commit -q -m c4
+  git checkout -q -b rewritten other~1
+  echo a2-rewritten > a && git commit -q -am c2-rewritten
+  git checkout -q main
+)
+
+# A thin pack with everything the server doesn't have yet, just like `git push` would send it.
+git -C client pack-objects --revs --thin --stdout >update.pack <<EOF
+main
+feature
+rewritten
+^other
+EOF
+# A pack with commits whose parents the server doesn't have.
+git -C client pack-objects --revs --stdout >incomplete.pack <<EOF
+feature
+^main
+EOF
+git -C client pack-objects --revs --stdout >empty.pack </dev/null
@@ -0,0 +1,17 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+git checkout -q -b main
+
+mkdir dir
+echo a > a
+echo b > dir/b
+git add . && git commit -q -m c1
+echo a2 > a && git commit -q -am c2
+git tag -a -m "annotated" v1
+git branch other
+echo a3 > a && git commit -q -am c3
+
+git pack-refs --all
+git repack -adbq
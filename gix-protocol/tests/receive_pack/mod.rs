@@ -0,0 +1,488 @@
+use std::{path::PathBuf, sync::atomic::AtomicBool};
+
+use gix_hash::ObjectId;
+use gix_object::Exists;
+use gix_protocol::receive_pack;
+use gix_transport::Protocol;
+
+use crate::{packet_lines, Response};
+
+struct Fixture {
+    dir: gix_testtools::tempfile::TempDir,
+    refs: gix_ref::file::Store,
+    client: gix_ref::file::Store,
+}
+
+impl Fixture {
+    fn new() -> crate::Result<Self> {
+        let dir = gix_testtools::scripted_fixture_writable("make_receive_pack_repo.sh")?;
+        let refs = gix_ref::file::Store::at(dir.path().join("server.git"), Default::default());
+        let client = gix_ref::file::Store::at(dir.path().join("client").join(".git"), Default::default());
+        Ok(Fixture { dir, refs, client })
+    }
+
+    fn git_dir(&self) -> PathBuf {
+        self.dir.path().join("server.git")
+    }
+
+    /// Return the id of the client reference `name`.
+    fn id(&self, name: &str) -> ObjectId {
+        self.client.find(name).expect("client ref exists").target.into_id()
+    }
+
+    /// Return the id the server reference `name` points to, if it exists.
+    fn server_id(&self, name: &str) -> Option<ObjectId> {
+        self.refs
+            .try_find(name)
+            .expect("valid refs")
+            .map(|r| r.target.into_id())
+    }
+
+    fn has_object(&self, id: ObjectId) -> bool {
+        gix_odb::at(self.git_dir().join("objects"))
+            .expect("valid odb")
+            .exists(&id)
+    }
+
+    fn has_quarantine(&self) -> bool {
+        std::fs::read_dir(self.git_dir().join("objects"))
+            .expect("objects dir exists")
+            .any(|entry| {
+                entry
+                    .expect("readable")
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with("tmp_objdir")
+            })
+    }
+
+    #[cfg(unix)]
+    fn install_hook(&self, name: &str, script: &str) -> crate::Result {
+        use std::os::unix::fs::PermissionsExt;
+        let path = self.git_dir().join("hooks").join(name);
+        std::fs::create_dir_all(path.parent().expect("hooks dir"))?;
+        std::fs::write(&path, format!("#!/bin/sh\n{script}\n"))?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+        Ok(())
+    }
+
+    /// Serve `input` followed by the pack file named `pack`, if set.
+    fn serve(
+        &self,
+        version: Protocol,
+        input: &[&str],
+        pack: Option<&str>,
+        options: &receive_pack::Options,
+    ) -> (Result<receive_pack::Outcome, receive_pack::Error>, Response) {
+        let mut input = packet_lines(input);
+        if let Some(pack) = pack {
+            input.extend(std::fs::read(self.dir.path().join(pack)).expect("pack exists"));
+        }
+        let mut out = Vec::new();
+        let res = gix_protocol::receive_pack(
+            &self.refs,
+            &self.git_dir().join("objects"),
+            version,
+            &mut input.as_slice(),
+            &mut out,
+            &AtomicBool::default(),
+            options,
+        );
+        (res, Response::from_bytes(&out))
+    }
+}
+
+fn options() -> receive_pack::Options {
+    receive_pack::Options {
+        committer: Some(gix_actor::Signature {
+            name: "committer".into(),
+            email: "committer@example.com".into(),
+            time: gix_date::Time::new(0, 0),
+        }),
+        ..Default::default()
+    }
+}
+
+/// Return the lines sent after the advertisement, decoding them from the side-band if necessary.
+fn report(out: &Response) -> Vec<String> {
+    if out.pack.is_empty() {
+        let start = out.lines.iter().position(|line| line == "0000").expect("advertisement") + 1;
+        out.lines[start..].to_vec()
+    } else {
+        Response::from_bytes(&out.pack).lines
+    }
+}
+
+fn rejections(outcome: &receive_pack::Outcome) -> Vec<Option<&str>> {
+    outcome
+        .updates
+        .iter()
+        .map(|update| update.rejection.as_deref())
+        .collect()
+}
+
+#[test]
+fn advertisement_lists_refs_with_capabilities() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (res, out) = fixture.serve(Protocol::V1, &[], None, &options());
+    assert_eq!(res?, receive_pack::Outcome::default(), "nothing was requested");
+    let (main, other, tag) = (
+        fixture.server_id("refs/heads/main").expect("exists"),
+        fixture.server_id("refs/heads/other").expect("exists"),
+        fixture.server_id("refs/tags/v1").expect("exists"),
+    );
+    assert_eq!(
+        out.lines,
+        [
+            "version 1".into(),
+            format!(
+                "{main} refs/heads/main\0report-status delete-refs side-band-64k quiet atomic ofs-delta object-format=sha1 agent=git/gix-protocol-{}",
+                env!("CARGO_PKG_VERSION")
+            ),
+            format!("{other} refs/heads/other"),
+            format!("{tag} refs/tags/v1"),
+            "0000".into(),
+        ],
+        "symbolic refs and peeled tags are not advertised"
+    );
+
+    let (res, out) = fixture.serve(
+        Protocol::V2,
+        &[],
+        None,
+        &receive_pack::Options {
+            advertise_push_options: true,
+            ..options()
+        },
+    );
+    res?;
+    assert!(
+        out.lines[0].starts_with(&format!("{main} refs/heads/main\0")),
+        "V2 isn't supported and falls back to V0"
+    );
+    assert!(out.lines[0].contains(" ofs-delta push-options object-format=sha1 "));
+    Ok(())
+}
+
+#[test]
+fn push_updates_creates_and_deletes_refs() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (c2, c3, c4) = (fixture.id("other"), fixture.id("main"), fixture.id("feature"));
+    let null = ObjectId::null(gix_hash::Kind::Sha1);
+    assert!(!fixture.has_object(c3), "the server doesn't have the new commits yet");
+
+    let (res, out) = fixture.serve(
+        Protocol::V0,
+        &[
+            &format!("{c2} {c3} refs/heads/main\0report-status side-band-64k agent=git/2.0"),
+            &format!("{null} {c4} refs/heads/feature"),
+            &format!("{c2} {null} refs/heads/other"),
+            "0000",
+        ],
+        Some("update.pack"),
+        &options(),
+    );
+    let outcome = res?;
+    assert_eq!(rejections(&outcome), [None, None, None]);
+    assert_eq!(
+        report(&out),
+        [
+            "unpack ok",
+            "ok refs/heads/main",
+            "ok refs/heads/feature",
+            "ok refs/heads/other",
+            "0000"
+        ]
+    );
+    assert_eq!(
+        out.lines.last().map(String::as_str),
+        Some("0000"),
+        "side-band ends with flush"
+    );
+
+    assert_eq!(fixture.server_id("refs/heads/main"), Some(c3));
+    assert_eq!(fixture.server_id("refs/heads/feature"), Some(c4));
+    assert_eq!(
+        fixture.server_id("refs/heads/other"),
+        None,
+        "packed refs are deleted as well"
+    );
+    assert!(
+        fixture.has_object(c3) && fixture.has_object(c4),
+        "objects were moved into the repository"
+    );
+    assert!(!fixture.has_quarantine(), "the quarantine is removed");
+    Ok(())
+}
+
+#[test]
+fn ref_pointing_to_existing_object_without_side_band() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let c2 = fixture.id("other");
+    let null = ObjectId::null(gix_hash::Kind::Sha1);
+    let (res, out) = fixture.serve(
+        Protocol::V0,
+        &[&format!("{null} {c2} refs/heads/copy\0report-status"), "0000"],
+        Some("empty.pack"),
+        &options(),
+    );
+    res?;
+    assert_eq!(report(&out), ["unpack ok", "ok refs/heads/copy", "0000"]);
+    assert_eq!(fixture.server_id("refs/heads/copy"), Some(c2));
+    Ok(())
+}
+
+#[test]
+fn stale_and_invalid_commands_are_rejected() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (c2, c3) = (fixture.id("other"), fixture.id("main"));
+    let (res, out) = fixture.serve(
+        Protocol::V0,
+        &[
+            &format!("{c3} {c2} refs/heads/main\0report-status"),
+            &format!("{c3} {c2} HEAD"),
+            "0000",
+        ],
+        Some("update.pack"),
+        &options(),
+    );
+    res?;
+    assert_eq!(
+        report(&out),
+        [
+            "unpack ok",
+            "ng refs/heads/main failed to update ref",
+            "ng HEAD funny refname",
+            "0000"
+        ]
+    );
+    assert_eq!(fixture.server_id("refs/heads/main"), Some(c2), "nothing changed");
+    assert!(!fixture.has_quarantine());
+    Ok(())
+}
+
+#[test]
+fn non_fast_forwards_and_deletions_can_be_denied() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (c2, rewritten) = (fixture.id("other"), fixture.id("rewritten"));
+    let null = ObjectId::null(gix_hash::Kind::Sha1);
+    let input = [
+        format!("{c2} {rewritten} refs/heads/main\0report-status"),
+        format!("{c2} {null} refs/heads/other"),
+        "0000".into(),
+    ];
+    let input: Vec<_> = input.iter().map(String::as_str).collect();
+    let (res, out) = fixture.serve(
+        Protocol::V0,
+        &input,
+        Some("update.pack"),
+        &receive_pack::Options {
+            deny_non_fast_forwards: true,
+            deny_deletes: true,
+            ..options()
+        },
+    );
+    let outcome = res?;
+    assert_eq!(
+        rejections(&outcome),
+        [Some("non-fast-forward"), Some("deletion prohibited")]
+    );
+    assert_eq!(
+        report(&out),
+        [
+            "unpack ok",
+            "ng refs/heads/main non-fast-forward",
+            "ng refs/heads/other deletion prohibited",
+            "0000"
+        ]
+    );
+    assert_eq!(fixture.server_id("refs/heads/main"), Some(c2));
+
+    let (res, _out) = fixture.serve(Protocol::V0, &input, Some("update.pack"), &options());
+    assert_eq!(rejections(&res?), [None, None], "by default, everything is allowed");
+    assert_eq!(fixture.server_id("refs/heads/main"), Some(rewritten));
+    assert_eq!(fixture.server_id("refs/heads/other"), None);
+    Ok(())
+}
+
+#[test]
+fn missing_objects_are_rejected() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let c4 = fixture.id("feature");
+    let null = ObjectId::null(gix_hash::Kind::Sha1);
+    let (res, out) = fixture.serve(
+        Protocol::V0,
+        &[&format!("{null} {c4} refs/heads/feature\0report-status"), "0000"],
+        Some("incomplete.pack"),
+        &options(),
+    );
+    res?;
+    assert_eq!(
+        report(&out),
+        ["unpack ok", "ng refs/heads/feature missing necessary objects", "0000"]
+    );
+    assert!(!fixture.has_object(c4));
+    Ok(())
+}
+
+#[test]
+fn invalid_packs_are_reported() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let c3 = fixture.id("main");
+    let c2 = fixture.id("other");
+    let (res, out) = fixture.serve(
+        Protocol::V0,
+        &[&format!("{c2} {c3} refs/heads/main\0report-status"), "0000"],
+        None,
+        &options(),
+    );
+    assert!(matches!(res, Err(receive_pack::Error::Unpack(_))));
+    let report = report(&out);
+    assert!(report[0].starts_with("unpack "), "{report:?}");
+    assert_ne!(report[0], "unpack ok");
+    assert_eq!(report[1..], ["ng refs/heads/main unpacker error", "0000"]);
+    assert!(!fixture.has_quarantine());
+    Ok(())
+}
+
+#[test]
+fn atomic_pushes_apply_all_or_nothing() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (c2, c3) = (fixture.id("other"), fixture.id("main"));
+    let null = ObjectId::null(gix_hash::Kind::Sha1);
+    let (res, out) = fixture.serve(
+        Protocol::V0,
+        &[
+            &format!("{c2} {c3} refs/heads/main\0report-status atomic"),
+            &format!("{c2} {null} refs/heads/other"),
+            "0000",
+        ],
+        Some("update.pack"),
+        &receive_pack::Options {
+            deny_deletes: true,
+            ..options()
+        },
+    );
+    res?;
+    assert_eq!(
+        report(&out),
+        [
+            "unpack ok",
+            "ng refs/heads/main atomic push failure",
+            "ng refs/heads/other deletion prohibited",
+            "0000"
+        ]
+    );
+    assert_eq!(fixture.server_id("refs/heads/main"), Some(c2));
+    assert!(!fixture.has_object(c3));
+    Ok(())
+}
+
+#[test]
+fn malformed_commands_are_sent_to_the_client() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (res, out) = fixture.serve(Protocol::V0, &["not a command", "0000"], None, &options());
+    assert!(matches!(res, Err(receive_pack::Error::UnexpectedLine { .. })));
+    assert_eq!(
+        out.lines.last().map(String::as_str),
+        Some("ERR The client sent an unexpected line: \"not a command\"")
+    );
+    Ok(())
+}
+
+#[cfg(unix)]
+mod hooks {
+    use gix_hash::ObjectId;
+    use gix_protocol::receive_pack;
+    use gix_transport::Protocol;
+
+    use crate::receive_pack::{options, rejections, report, Fixture};
+
+    #[test]
+    fn pre_receive_sees_quarantined_objects_and_can_reject_everything() -> crate::Result {
+        let fixture = Fixture::new()?;
+        fixture.install_hook(
+            "pre-receive",
+            r#"echo "stdin: $(cat)"
+echo "option: $GIT_PUSH_OPTION_COUNT $GIT_PUSH_OPTION_0"
+test -d "$GIT_QUARANTINE_PATH" && test "$GIT_OBJECT_DIRECTORY" = "$GIT_QUARANTINE_PATH" && echo "quarantined"
+echo "rejected" >&2
+exit 1"#,
+        )?;
+        let (c2, c3) = (fixture.id("other"), fixture.id("main"));
+        let (res, out) = fixture.serve(
+            Protocol::V0,
+            &[
+                &format!("{c2} {c3} refs/heads/main\0report-status side-band-64k push-options"),
+                "0000",
+                "ci.skip",
+                "0000",
+            ],
+            Some("update.pack"),
+            &receive_pack::Options {
+                hooks_dir: Some(fixture.git_dir().join("hooks")),
+                advertise_push_options: true,
+                ..options()
+            },
+        );
+        let outcome = res?;
+        assert_eq!(outcome.push_options, ["ci.skip"]);
+        assert_eq!(
+            out.progress,
+            [
+                format!("stdin: {c2} {c3} refs/heads/main\noption: 1 ci.skip\nquarantined"),
+                "rejected".into()
+            ],
+            "hook output is sent to the client, stdout first"
+        );
+        assert_eq!(
+            report(&out),
+            ["unpack ok", "ng refs/heads/main pre-receive hook declined", "0000"]
+        );
+        assert!(!fixture.has_object(c3));
+        Ok(())
+    }
+
+    #[test]
+    fn update_rejects_single_refs_and_post_receive_sees_the_rest() -> crate::Result {
+        let fixture = Fixture::new()?;
+        fixture.install_hook("update", r#"test "$1" != refs/heads/feature"#)?;
+        fixture.install_hook(
+            "post-receive",
+            r#"cat > post-receive.out; test -z "$GIT_QUARANTINE_PATH""#,
+        )?;
+        let (c2, c3, c4) = (fixture.id("other"), fixture.id("main"), fixture.id("feature"));
+        let null = ObjectId::null(gix_hash::Kind::Sha1);
+        let (res, out) = fixture.serve(
+            Protocol::V0,
+            &[
+                &format!("{c2} {c3} refs/heads/main\0report-status"),
+                &format!("{null} {c4} refs/heads/feature"),
+                "0000",
+            ],
+            Some("update.pack"),
+            &receive_pack::Options {
+                hooks_dir: Some(fixture.git_dir().join("hooks")),
+                ..options()
+            },
+        );
+        assert_eq!(rejections(&res?), [None, Some("hook declined")]);
+        assert_eq!(
+            report(&out),
+            [
+                "unpack ok",
+                "ok refs/heads/main",
+                "ng refs/heads/feature hook declined",
+                "0000"
+            ]
+        );
+        assert_eq!(fixture.server_id("refs/heads/main"), Some(c3));
+        assert_eq!(fixture.server_id("refs/heads/feature"), None);
+        assert_eq!(
+            std::fs::read_to_string(fixture.git_dir().join("post-receive.out"))?,
+            format!("{c2} {c3} refs/heads/main\n"),
+            "hooks run in the git directory and only see applied updates"
+        );
+        Ok(())
+    }
+}
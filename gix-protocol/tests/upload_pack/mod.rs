@@ -0,0 +1,107 @@
+use std::sync::atomic::AtomicBool;
+
+use bstr::ByteSlice;
+use gix_hash::ObjectId;
+use gix_protocol::{handshake::Ref, upload_pack};
+use gix_transport::Protocol;
+
+use crate::{packet_lines, Response};
+
+struct Fixture {
+    refs: Vec<Ref>,
+    odb: gix_odb::HandleArc,
+    pack_dir: std::path::PathBuf,
+}
+
+impl Fixture {
+    fn new() -> crate::Result<Self> {
+        Self::from_script("make_upload_pack_repo.sh")
+    }
+
+    /// A fixture with all objects in a pack that has a reachability bitmap.
+    fn with_bitmap() -> crate::Result<Self> {
+        Self::from_script("make_upload_pack_repo_with_bitmap.sh")
+    }
+
+    fn from_script(name: &str) -> crate::Result<Self> {
+        let dir = gix_testtools::scripted_fixture_read_only(name)?;
+        let git_dir = dir.join(".git");
+        let mut refs = Vec::new();
+        let packed = std::fs::read(git_dir.join("packed-refs"))?;
+        for line in packed.lines().filter(|line| !line.starts_with(b"#")) {
+            if let Some(peeled) = line.strip_prefix(b"^") {
+                let Some(Ref::Direct { full_ref_name, object }) = refs.pop() else {
+                    unreachable!("peeled lines follow their ref")
+                };
+                refs.push(Ref::Peeled {
+                    full_ref_name,
+                    tag: object,
+                    object: ObjectId::from_hex(peeled)?,
+                });
+            } else {
+                let (id, name) = line.split_once_str(" ").expect("id and name");
+                refs.push(Ref::Direct {
+                    full_ref_name: name.into(),
+                    object: ObjectId::from_hex(id)?,
+                });
+            }
+        }
+        let head = std::fs::read(git_dir.join("HEAD"))?;
+        let target = head.trim().strip_prefix(b"ref: ").expect("symbolic HEAD");
+        let object = refs
+            .iter()
+            .find_map(|r| (r.unpack().0 == target).then(|| r.unpack().1.expect("born").to_owned()))
+            .expect("HEAD target exists");
+        refs.push(Ref::Symbolic {
+            full_ref_name: "HEAD".into(),
+            target: target.into(),
+            tag: None,
+            object,
+        });
+        let mut odb = gix_odb::at(git_dir.join("objects"))?.into_arc()?;
+        odb.prevent_pack_unload();
+        odb.ignore_replacements = true;
+        Ok(Fixture {
+            refs,
+            odb,
+            pack_dir: git_dir.join("objects").join("pack"),
+        })
+    }
+
+    fn id(&self, name: &str) -> ObjectId {
+        self.refs
+            .iter()
+            .find_map(|r| (r.unpack().0 == name).then(|| r.unpack().1.expect("born").to_owned()))
+            .expect("ref exists")
+    }
+
+    fn serve(
+        &self,
+        version: Protocol,
+        input: &[&str],
+        options: &upload_pack::Options,
+    ) -> (Result<(), upload_pack::Error>, Response) {
+        let input = packet_lines(input);
+        let mut out = Vec::new();
+        let res = gix_protocol::upload_pack(
+            &self.refs,
+            self.odb.clone(),
+            version,
+            &mut input.as_slice(),
+            &mut out,
+            &AtomicBool::default(),
+            options,
+        );
+        (res, Response::from_bytes(&out))
+    }
+}
+
+fn allow_filter() -> upload_pack::Options {
+    upload_pack::Options {
+        allow_filter: true,
+        ..Default::default()
+    }
+}
+
+mod v1;
+mod v2;
@@ -0,0 +1,200 @@
+use gix_protocol::upload_pack;
+use gix_transport::Protocol;
+
+use crate::upload_pack::{allow_filter, Fixture};
+
+#[test]
+fn advertisement_lists_head_first_and_peels_tags() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (res, out) = fixture.serve(Protocol::V1, &[], &Default::default());
+    res?;
+    let (main, tag, c2) = (
+        fixture.id("refs/heads/main"),
+        fixture.id("refs/tags/v1"),
+        fixture.id("refs/heads/other"),
+    );
+    assert_eq!(out.lines.len(), 7, "version, HEAD, 3 refs, a peeled tag and flush");
+    assert_eq!(out.lines[0], "version 1");
+    let (head, capabilities) = out.lines[1].split_once('\0').expect("capabilities");
+    assert_eq!(head, format!("{main} HEAD"));
+    assert!(capabilities.starts_with("multi_ack side-band side-band-64k ofs-delta shallow"));
+    assert!(capabilities.contains(" symref=HEAD:refs/heads/main "));
+    assert!(capabilities.contains(" object-format=sha1 agent=git/gix-protocol-"));
+    assert!(!capabilities.contains("filter"), "filters are off by default");
+    assert_eq!(
+        out.lines[2..],
+        [
+            format!("{main} refs/heads/main"),
+            format!("{c2} refs/heads/other"),
+            format!("{tag} refs/tags/v1"),
+            format!("{c2} refs/tags/v1^{{}}"),
+            "0000".into()
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn clone_without_haves() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let main = fixture.id("refs/heads/main");
+    let (res, out) = fixture.serve(
+        Protocol::V0,
+        &[
+            &format!("want {main} multi_ack_detailed side-band-64k ofs-delta"),
+            "0000",
+            "done",
+        ],
+        &Default::default(),
+    );
+    res?;
+    let response = &out.lines[out.lines.iter().position(|l| l == "0000").expect("advertisement") + 1..];
+    assert_eq!(response, ["NAK", "0000"]);
+    assert_eq!(
+        out.pack_objects(),
+        11,
+        "3 commits, 4 trees and 4 blobs, without the tag"
+    );
+    assert_eq!(
+        out.progress.last().map(String::as_str),
+        Some("Total 11 (delta 0), reused 0 (delta 0), pack-reused 0")
+    );
+    Ok(())
+}
+
+#[test]
+fn negotiation_with_multi_ack_detailed_sends_only_missing_objects() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (main, other) = (fixture.id("refs/heads/main"), fixture.id("refs/heads/other"));
+    let (res, out) = fixture.serve(
+        Protocol::V1,
+        &[
+            &format!("want {main} multi_ack_detailed side-band-64k no-progress"),
+            "0000",
+            &format!("have {other}"),
+            "0000",
+            "done",
+        ],
+        &Default::default(),
+    );
+    res?;
+    let response = &out.lines[out.lines.iter().position(|l| l == "0000").expect("advertisement") + 1..];
+    assert_eq!(
+        response,
+        [
+            format!("ACK {other} common"),
+            format!("ACK {other} ready"),
+            "NAK".into(),
+            format!("ACK {other}"),
+            "0000".into()
+        ]
+    );
+    assert_eq!(out.pack_objects(), 3, "a commit, its tree and a changed blob");
+    assert!(out.progress.is_empty(), "progress was turned off");
+    Ok(())
+}
+
+#[test]
+fn stateless_shallow_clone() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let main = fixture.id("refs/heads/main");
+    let (res, out) = fixture.serve(
+        Protocol::V1,
+        &[&format!("want {main} side-band-64k"), "deepen 1", "0000", "done"],
+        &upload_pack::Options {
+            stateless_rpc: true,
+            ..Default::default()
+        },
+    );
+    res?;
+    assert_eq!(
+        out.lines,
+        [format!("shallow {main}"), "0000".into(), "NAK".into(), "0000".into()]
+    );
+    assert_eq!(out.pack_objects(), 5, "a single commit with all of its trees and blobs");
+    Ok(())
+}
+
+#[test]
+fn stateless_no_done_sends_pack_once_ready_was_sent_for_an_unknown_have() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let main = fixture.id("refs/heads/main");
+    let common = fixture.id("refs/heads/other");
+    let unknown = "0101010101010101010101010101010101010101";
+    let (res, out) = fixture.serve(
+        Protocol::V1,
+        &[
+            &format!("want {main} multi_ack_detailed no-done side-band-64k"),
+            "0000",
+            &format!("have {common}"),
+            &format!("have {unknown}"),
+            "0000",
+        ],
+        &upload_pack::Options {
+            stateless_rpc: true,
+            ..Default::default()
+        },
+    );
+    res?;
+    assert_eq!(
+        out.lines,
+        [
+            format!("ACK {common} common"),
+            format!("ACK {unknown} ready"),
+            "NAK".into(),
+            format!("ACK {common}"),
+            "0000".into()
+        ],
+        "`done` isn't needed once the server said it's ready"
+    );
+    assert_eq!(out.pack_objects(), 3, "the commit, its root tree and the changed blob");
+    Ok(())
+}
+
+#[test]
+fn filters_must_be_allowed() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let main = fixture.id("refs/heads/main");
+    let input = [
+        &format!("want {main} side-band-64k filter") as &str,
+        "filter blob:none",
+        "0000",
+        "done",
+    ];
+    let options = upload_pack::Options {
+        stateless_rpc: true,
+        ..Default::default()
+    };
+    let (res, out) = fixture.serve(Protocol::V1, &input, &options);
+    assert!(matches!(res, Err(upload_pack::Error::FilterNotAllowed)));
+    assert_eq!(out.lines, ["ERR Filtering is not allowed by the server"]);
+
+    let (res, out) = fixture.serve(
+        Protocol::V1,
+        &input,
+        &upload_pack::Options {
+            stateless_rpc: true,
+            ..allow_filter()
+        },
+    );
+    res?;
+    assert_eq!(out.pack_objects(), 7, "only commits and trees");
+    Ok(())
+}
+
+#[test]
+fn wants_must_be_advertised() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let id = "0101010101010101010101010101010101010101";
+    let (res, out) = fixture.serve(
+        Protocol::V1,
+        &[&format!("want {id}"), "0000", "done"],
+        &upload_pack::Options {
+            stateless_rpc: true,
+            ..Default::default()
+        },
+    );
+    assert!(matches!(res, Err(upload_pack::Error::NotOurRef { .. })));
+    assert_eq!(out.lines, [format!("ERR upload-pack: not our ref {id}")]);
+    Ok(())
+}
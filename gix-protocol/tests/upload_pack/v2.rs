@@ -0,0 +1,453 @@
+use gix_protocol::upload_pack;
+use gix_transport::Protocol;
+
+use crate::upload_pack::{allow_filter, Fixture};
+
+fn stateless(options: upload_pack::Options) -> upload_pack::Options {
+    upload_pack::Options {
+        stateless_rpc: true,
+        ..options
+    }
+}
+
+#[test]
+fn capability_advertisement() -> crate::Result {
+    let fixture = Fixture::new()?;
+    for (options, expected_fetch) in [
+        (upload_pack::Options::default(), "fetch=shallow wait-for-done"),
+        (
+            upload_pack::Options {
+                allow_ref_in_want: true,
+                ..allow_filter()
+            },
+            "fetch=shallow wait-for-done filter ref-in-want",
+        ),
+    ] {
+        let (res, out) = fixture.serve(Protocol::V2, &[], &options);
+        res?;
+        assert_eq!(out.lines.len(), 7);
+        assert_eq!(out.lines[0], "version 2");
+        assert!(out.lines[1].starts_with("agent=git/gix-protocol-"));
+        assert_eq!(
+            out.lines[2..],
+            [
+                "ls-refs=unborn",
+                expected_fetch,
+                "server-option",
+                "object-format=sha1",
+                "0000"
+            ]
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn ls_refs_with_prefixes_symrefs_and_peeling() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (main, tag, c2) = (
+        fixture.id("refs/heads/main"),
+        fixture.id("refs/tags/v1"),
+        fixture.id("refs/heads/other"),
+    );
+    let (res, out) = fixture.serve(
+        Protocol::V2,
+        &[
+            "command=ls-refs",
+            "agent=git/2.42.0",
+            "0001",
+            "symrefs",
+            "peel",
+            "ref-prefix HEAD",
+            "ref-prefix refs/tags/",
+            "0000",
+        ],
+        &stateless(Default::default()),
+    );
+    res?;
+    assert_eq!(
+        out.lines,
+        [
+            format!("{main} HEAD symref-target:refs/heads/main"),
+            format!("{tag} refs/tags/v1 peeled:{c2}"),
+            "0000".into()
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn sessions_serve_multiple_commands_until_the_client_is_done() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (res, out) = fixture.serve(
+        Protocol::V2,
+        &[
+            "command=ls-refs",
+            "0000",
+            "command=ls-refs",
+            "0001",
+            "ref-prefix refs/heads/o",
+            "0000",
+            "0000",
+        ],
+        &Default::default(),
+    );
+    res?;
+    let other = fixture.id("refs/heads/other");
+    let response = &out.lines[out.lines.iter().position(|l| l == "0000").expect("advertisement") + 1..];
+    assert_eq!(response.len(), 5 + 2, "all refs, then just one");
+    assert_eq!(response[5..], [format!("{other} refs/heads/other"), "0000".into()]);
+    Ok(())
+}
+
+#[test]
+fn fetch_with_done_and_included_tags() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let main = fixture.id("refs/heads/main");
+    let (res, out) = fixture.serve(
+        Protocol::V2,
+        &[
+            "command=fetch",
+            "0001",
+            &format!("want {main}"),
+            "include-tag",
+            "done",
+            "0000",
+        ],
+        &stateless(Default::default()),
+    );
+    res?;
+    assert_eq!(out.lines, ["packfile", "0000"]);
+    assert_eq!(out.pack_objects(), 12, "everything, including the annotated tag");
+    assert!(!out.progress.is_empty());
+    Ok(())
+}
+
+#[test]
+fn fetch_with_negotiation() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (main, other) = (fixture.id("refs/heads/main"), fixture.id("refs/heads/other"));
+    let (res, out) = fixture.serve(
+        Protocol::V2,
+        &[
+            "command=fetch",
+            "0001",
+            &format!("want {main}"),
+            &format!("have {other}"),
+            "no-progress",
+            "0000",
+        ],
+        &stateless(Default::default()),
+    );
+    res?;
+    assert_eq!(
+        out.lines,
+        [
+            "acknowledgments".into(),
+            format!("ACK {other}"),
+            "ready".into(),
+            "0001".into(),
+            "packfile".into(),
+            "0000".into()
+        ]
+    );
+    assert_eq!(out.pack_objects(), 3);
+    assert!(out.progress.is_empty());
+
+    let (res, out) = fixture.serve(
+        Protocol::V2,
+        &[
+            "command=fetch",
+            "0001",
+            &format!("want {main}"),
+            &format!("have {other}"),
+            "wait-for-done",
+            "0000",
+        ],
+        &stateless(Default::default()),
+    );
+    res?;
+    assert_eq!(
+        out.lines,
+        ["acknowledgments".into(), format!("ACK {other}"), "0000".into()]
+    );
+    assert!(out.pack.is_empty(), "the client has to send 'done' first");
+    Ok(())
+}
+
+#[test]
+fn fetch_shallow_and_filtered() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let main = fixture.id("refs/heads/main");
+    let (res, out) = fixture.serve(
+        Protocol::V2,
+        &[
+            "command=fetch",
+            "0001",
+            &format!("want {main}"),
+            "deepen 1",
+            "filter blob:none",
+            "done",
+            "0000",
+        ],
+        &stateless(allow_filter()),
+    );
+    res?;
+    assert_eq!(
+        out.lines,
+        [
+            "shallow-info".into(),
+            format!("shallow {main}"),
+            "0001".into(),
+            "packfile".into(),
+            "0000".into()
+        ]
+    );
+    assert_eq!(out.pack_objects(), 3, "a commit and its two trees");
+    Ok(())
+}
+
+#[test]
+fn fetch_with_bitmap_sends_the_same_objects() -> crate::Result {
+    let (fixture, bitmap_fixture) = (Fixture::new()?, Fixture::with_bitmap()?);
+    assert!(
+        gix_pack::bitmap::Indexed::at_pack_dir(&bitmap_fixture.pack_dir, gix_hash::Kind::Sha1)?.is_some(),
+        "the pack has a bitmap"
+    );
+    let (main, other) = (fixture.id("refs/heads/main"), fixture.id("refs/heads/other"));
+    assert_eq!(
+        (main, other),
+        (
+            bitmap_fixture.id("refs/heads/main"),
+            bitmap_fixture.id("refs/heads/other")
+        ),
+        "both repositories are the same"
+    );
+    for (request, expected_objects) in [
+        (vec![format!("want {main}")], 11),
+        (vec![format!("want {main}"), format!("have {other}")], 3),
+        (vec![format!("want {main}"), "include-tag".into()], 12),
+        (vec![format!("want {main}"), "filter blob:none".into()], 7),
+    ] {
+        let input: Vec<_> = ["command=fetch", "0001"]
+            .into_iter()
+            .chain(request.iter().map(String::as_str))
+            .chain(["done", "0000"])
+            .collect();
+        for (fixture, pack_dir) in [
+            (&fixture, None),
+            (&bitmap_fixture, Some(bitmap_fixture.pack_dir.clone())),
+        ] {
+            let (res, out) = fixture.serve(
+                Protocol::V2,
+                &input,
+                &stateless(upload_pack::Options {
+                    pack_dir,
+                    ..allow_filter()
+                }),
+            );
+            res?;
+            assert_eq!(out.pack_objects(), expected_objects, "{request:?}");
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn fetch_with_packfile_uris() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let main = fixture.id("refs/heads/main");
+    let blob = gix_hash::ObjectId::from_hex(b"d616f7380ad325123fed6f628d02fa76e1ce77c3")?;
+    let pack_hash = gix_hash::ObjectId::from_hex(b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa")?;
+    let options = stateless(upload_pack::Options {
+        packfile_uris: vec![
+            upload_pack::PackfileUri {
+                object: blob,
+                pack_hash,
+                uri: "https://cdn.example.com/a.pack".into(),
+            },
+            upload_pack::PackfileUri {
+                object: gix_hash::ObjectId::from_hex(b"61780798228d17af2d34fce4cfbdf35556832472")?,
+                pack_hash,
+                uri: "https://cdn.example.com/a.pack".into(),
+            },
+        ],
+        ..Default::default()
+    });
+    let (res, out) = fixture.serve(
+        Protocol::V2,
+        &[],
+        &upload_pack::Options {
+            stateless_rpc: false,
+            ..options.clone()
+        },
+    );
+    res?;
+    assert_eq!(out.lines[3], "fetch=shallow wait-for-done packfile-uris");
+
+    for (protocols, expected_objects) in [("ftp,http", 11), ("ftp,https", 9)] {
+        let (res, out) = fixture.serve(
+            Protocol::V2,
+            &[
+                "command=fetch",
+                "0001",
+                &format!("packfile-uris {protocols}"),
+                &format!("want {main}"),
+                "done",
+                "0000",
+            ],
+            &options,
+        );
+        res?;
+        if expected_objects == 11 {
+            assert_eq!(
+                out.lines,
+                ["packfile", "0000"],
+                "the protocol isn't accepted by the client"
+            );
+        } else {
+            assert_eq!(
+                out.lines,
+                [
+                    "packfile-uris".into(),
+                    format!("{pack_hash} https://cdn.example.com/a.pack"),
+                    "0001".into(),
+                    "packfile".into(),
+                    "0000".into()
+                ],
+                "each pack is announced only once"
+            );
+        }
+        assert_eq!(out.pack_objects(), expected_objects);
+    }
+    Ok(())
+}
+
+#[test]
+fn fetch_wanted_refs() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let other = fixture.id("refs/heads/other");
+    let input = ["command=fetch", "0001", "want-ref refs/heads/other", "done", "0000"];
+    let (res, out) = fixture.serve(Protocol::V2, &input, &stateless(Default::default()));
+    assert!(
+        matches!(res, Err(upload_pack::Error::UnexpectedLine { .. })),
+        "want-ref must be allowed"
+    );
+    assert_eq!(out.lines.len(), 1);
+
+    let (res, out) = fixture.serve(
+        Protocol::V2,
+        &input,
+        &stateless(upload_pack::Options {
+            allow_ref_in_want: true,
+            ..Default::default()
+        }),
+    );
+    res?;
+    assert_eq!(
+        out.lines,
+        [
+            "wanted-refs".into(),
+            format!("{other} refs/heads/other"),
+            "0001".into(),
+            "packfile".into(),
+            "0000".into()
+        ]
+    );
+    assert_eq!(out.pack_objects(), 8);
+    Ok(())
+}
+
+#[test]
+fn bundle_uri() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (res, out) = fixture.serve(
+        Protocol::V2,
+        &["command=bundle-uri", "0000"],
+        &stateless(Default::default()),
+    );
+    assert!(
+        matches!(res, Err(upload_pack::Error::UnknownCommand { .. })),
+        "the command is only known if there are bundles to advertise"
+    );
+    assert_eq!(
+        out.lines,
+        ["ERR The client requested the unknown command \"bundle-uri\""]
+    );
+
+    let options = upload_pack::Options {
+        bundle_list: vec![
+            ("bundle.version".into(), "1".into()),
+            ("bundle.mode".into(), "all".into()),
+            ("bundle.main.uri".into(), "https://example.com/main.bundle".into()),
+        ],
+        ..Default::default()
+    };
+    let (res, out) = fixture.serve(Protocol::V2, &[], &options);
+    res?;
+    assert_eq!(out.lines[6..], ["bundle-uri", "0000"], "advertised last");
+
+    let (res, out) = fixture.serve(
+        Protocol::V2,
+        &["command=bundle-uri", "agent=git/2.45", "0001", "0000"],
+        &stateless(options),
+    );
+    res?;
+    assert_eq!(
+        out.lines,
+        [
+            "bundle.version=1",
+            "bundle.mode=all",
+            "bundle.main.uri=https://example.com/main.bundle",
+            "0000"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn object_info() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let blob = "d616f7380ad325123fed6f628d02fa76e1ce77c3";
+    let missing = "0000000000000000000000000000000000000001";
+    let input = [
+        "command=object-info",
+        "0001",
+        "size",
+        &format!("oid {blob}"),
+        &format!("oid {missing}"),
+        "0000",
+    ];
+    let (res, out) = fixture.serve(Protocol::V2, &input, &stateless(Default::default()));
+    assert!(
+        matches!(res, Err(upload_pack::Error::UnknownCommand { .. })),
+        "the command must be enabled"
+    );
+    assert_eq!(out.lines.len(), 1);
+
+    let options = upload_pack::Options {
+        advertise_object_info: true,
+        ..Default::default()
+    };
+    let (res, out) = fixture.serve(Protocol::V2, &[], &options);
+    res?;
+    assert_eq!(out.lines[6..], ["object-info", "0000"]);
+
+    let (res, out) = fixture.serve(Protocol::V2, &input, &stateless(options));
+    res?;
+    assert_eq!(
+        out.lines,
+        ["size".into(), format!("{blob} 3"), format!("{missing} "), "0000".into()],
+        "missing objects have no size"
+    );
+    Ok(())
+}
+
+#[test]
+fn unknown_commands_are_rejected() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (res, out) = fixture.serve(Protocol::V2, &["command=push", "0000"], &stateless(Default::default()));
+    assert!(matches!(res, Err(upload_pack::Error::UnknownCommand { .. })));
+    assert_eq!(out.lines, ["ERR The client requested the unknown command \"push\""]);
+    Ok(())
+}
@@ -29,7 +29,7 @@ use gix_object::bstr::{BStr, BString};
 
 #[path = "store/mod.rs"]
 mod store_impl;
-pub use store_impl::{file, packed};
+pub use store_impl::{file, packed, reftable};
 
 mod fullname;
 ///
@@ -70,8 +70,20 @@ pub mod store {
             /// to avoid side effects. This only needs to be `true` on Windows, but can be `true` on other platforms
             /// if they need to remain compatible with Windows.
             pub prohibit_windows_device_names: bool,
+            /// The format in which references are stored, as configured by `extensions.refStorage`.
+            pub ref_storage: super::RefStorage,
         }
     }
+    /// The format in which a store keeps its references.
+    #[derive(Default, Debug, PartialOrd, PartialEq, Ord, Eq, Hash, Clone, Copy)]
+    pub enum RefStorage {
+        /// Loose references in files, possibly packed into the `packed-refs` file, with reflogs in files as well.
+        #[default]
+        Files,
+        /// References and their logs are kept in a [stack of reftables](crate::reftable::Stack) in the `reftable`
+        /// directory, except for `FETCH_HEAD` and `MERGE_HEAD` which remain files.
+        Reftable,
+    }
     /// The way a file store handles the reflog
     #[derive(Default, Debug, PartialOrd, PartialEq, Ord, Eq, Hash, Clone, Copy)]
     pub enum WriteReflog {
@@ -147,6 +147,9 @@ impl file::Store {
         let full_name = precomposed_partial_name
             .unwrap_or(partial_name)
             .construct_full_name_ref(inbetween, path_buf);
+        if self.is_in_reftable(full_name) {
+            return self.find_in_reftable(full_name);
+        }
         let content_buf = self.ref_contents(full_name).map_err(|err| Error::ReadFileContents {
             source: err,
             path: self.reference_path(full_name),
@@ -389,6 +392,10 @@ mod error {
         PackedRef(#[from] packed::find::Error),
         #[error("Could not open the packed refs buffer when trying to find references.")]
         PackedOpen(#[from] packed::buffer::open::Error),
+        #[error("Could not load the reftables when trying to find references")]
+        ReftableOpen(#[from] crate::reftable::table::open::Error),
+        #[error("A reftable lookup failed")]
+        ReftableDecode(#[from] crate::reftable::decode::Error),
     }
 
     impl From<Infallible> for Error {
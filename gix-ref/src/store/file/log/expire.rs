@@ -0,0 +1,273 @@
+use std::{collections::HashSet, io::Write};
+
+use gix_actor::date::SecondsSinceUnixEpoch;
+use gix_hash::ObjectId;
+use gix_object::bstr::ByteSlice;
+
+use crate::{
+    file,
+    file::{
+        log::{iter::decode::LineNumber, LineRef},
+        ReferenceExt,
+    },
+    FullNameRef,
+};
+
+/// Options for use in [`file::Store::reflog_expire()`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Options {
+    /// Remove all entries older than this time, or keep them all if `None`.
+    ///
+    /// This is what `gc.reflogExpire` configures in `git`, with a default of 90 days ago.
+    pub expire: Option<SecondsSinceUnixEpoch>,
+    /// Remove all entries older than this time if their previous or new object isn't reachable from the tip of the reference,
+    /// or keep them if `None`.
+    /// For `HEAD`, the tips of all references are used to determine reachability.
+    ///
+    /// This is what `gc.reflogExpireUnreachable` configures in `git`, with a default of 30 days ago.
+    pub expire_unreachable: Option<SecondsSinceUnixEpoch>,
+    /// If `true`, after removing entries, set the previous object of each remaining entry to the new object of its
+    /// predecessor so the log stays consistent, similar to `git reflog expire --rewrite`.
+    pub rewrite: bool,
+    /// How to handle the locks on the reference and its log, or on `tables.list` if the references are stored in reftables.
+    pub lock_mode: gix_lock::acquire::Fail,
+}
+
+/// The outcome of [`file::Store::reflog_expire()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    /// The amount of entries that were kept.
+    pub kept: usize,
+    /// The amount of entries that were removed.
+    pub pruned: usize,
+}
+
+/// The error returned by [`file::Store::reflog_expire()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The reference or its reflog could not be locked")]
+    Lock(#[from] gix_lock::acquire::Error),
+    #[error("The reflog could not be read or written")]
+    Io(#[from] std::io::Error),
+    #[error("The reflog could not be parsed")]
+    Decode(#[from] file::log::iter::decode::Error),
+    #[error("The reference could not be found")]
+    Find(#[from] file::find::Error),
+    #[error("The references could not be iterated to find the tips of the history")]
+    Iter(#[from] file::iter::loose_then_packed::Error),
+    #[error("The packed-refs file could not be opened")]
+    PackedOpen(#[from] crate::packed::buffer::open::Error),
+    #[error("An object could not be read to determine its reachability")]
+    FindObject(#[from] gix_object::find::Error),
+    #[error("The reftable stack could not be opened")]
+    ReftableOpen(#[from] crate::reftable::table::open::Error),
+    #[error("The reftable stack could not be read")]
+    ReftableDecode(#[from] crate::reftable::decode::Error),
+    #[error("The table with the changed reflog could not be written")]
+    ReftableWrite(#[from] crate::reftable::write::Error),
+    #[error("The table with the changed reflog could not be added to the stack")]
+    ReftableAppend(#[from] crate::reftable::stack::append::Error),
+}
+
+impl file::Store {
+    /// Remove entries from the reflog of the reference `name` according to `options`, using `objects` to determine
+    /// the reachability of the objects each entry refers to, similar to `git reflog expire`.
+    ///
+    /// While the reflog is rewritten, the reference itself is locked so it can't be changed concurrently.
+    /// Nothing is done if there is no reflog.
+    pub fn reflog_expire(
+        &self,
+        name: &FullNameRef,
+        objects: impl gix_object::Find,
+        options: Options,
+    ) -> Result<Outcome, Error> {
+        if self.is_in_reftable(name) {
+            return self.reftable_reflog_expire(name, &objects, options);
+        }
+
+        let reflog_path = self.reflog_path(name);
+        if !reflog_path.is_file() {
+            return Ok(Outcome::default());
+        }
+        let (base, relative_path) = self.reference_path_with_base(name);
+        let _reference_lock = gix_lock::Marker::acquire_to_hold_resource(
+            base.join(relative_path.as_ref()),
+            options.lock_mode,
+            Some(base.into_owned()),
+        )?;
+        let data = std::fs::read(&reflog_path)?;
+        let mut lock = gix_lock::File::acquire_to_update_resource(&reflog_path, options.lock_mode, None)?;
+
+        let mut reachability = Reachability::new(self, name, &objects, options)?;
+        let mut out = Outcome::default();
+        let mut previous_kept_new_oid = None::<ObjectId>;
+        for (line_number, line) in data.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let parsed = LineRef::from_bytes(line)
+                .map_err(|err| file::log::iter::decode::Error::new(err, LineNumber::FromStart(line_number)))?;
+            if reachability.should_expire(parsed.signature.time.seconds, parsed.previous_oid(), parsed.new_oid())? {
+                out.pruned += 1;
+                continue;
+            }
+            out.kept += 1;
+            match previous_kept_new_oid.filter(|_| options.rewrite && out.pruned != 0) {
+                Some(previous) if previous != parsed.previous_oid() => {
+                    let hex_len = parsed.previous_oid.len();
+                    write!(lock, "{previous}")?;
+                    lock.write_all(&line[hex_len..])?;
+                }
+                _ => lock.write_all(line)?,
+            }
+            lock.write_all(b"\n")?;
+            previous_kept_new_oid = Some(parsed.new_oid());
+        }
+
+        if out.pruned != 0 {
+            lock.commit().map_err(|err| err.error)?;
+        }
+        Ok(out)
+    }
+
+    fn reftable_reflog_expire(
+        &self,
+        name: &FullNameRef,
+        objects: &dyn gix_object::Find,
+        options: Options,
+    ) -> Result<Outcome, Error> {
+        let (dir, record_name) = self.reftable_location(name);
+        let lock = crate::reftable::Stack::lock(&dir, options.lock_mode)?;
+        let mut stack = crate::reftable::Stack::at(&dir)?;
+        let mut logs = stack.logs_of(record_name.as_ref())?;
+        logs.reverse();
+
+        let mut reachability = Reachability::new(self, name, objects, options)?;
+        let update_index = stack.max_update_index() + 1;
+        let mut writer = crate::reftable::write::Writer::new(
+            update_index,
+            update_index,
+            crate::reftable::write::Options {
+                object_hash: self.object_hash,
+                ..Default::default()
+            },
+        );
+        let mut out = Outcome::default();
+        let mut previous_kept_new_oid = None::<ObjectId>;
+        for mut log in logs {
+            let line = log.line.as_mut().expect("tombstones are never returned");
+            if reachability.should_expire(line.signature.time.seconds, line.previous_oid, line.new_oid)? {
+                out.pruned += 1;
+                log.line = None;
+                writer.add_log(log);
+                continue;
+            }
+            out.kept += 1;
+            match previous_kept_new_oid.filter(|_| options.rewrite && out.pruned != 0) {
+                Some(previous) if previous != line.previous_oid => {
+                    line.previous_oid = previous;
+                    previous_kept_new_oid = Some(line.new_oid);
+                    writer.add_log(log);
+                }
+                _ => previous_kept_new_oid = Some(line.new_oid),
+            }
+        }
+
+        if out.pruned != 0 {
+            stack.append(lock, writer.finish()?)?;
+            stack.auto_compact(gix_lock::acquire::Fail::Immediately).ok();
+        }
+        Ok(out)
+    }
+}
+
+/// Determine which reflog entries to expire, lazily computing the set of reachable commits only if needed.
+struct Reachability<'a> {
+    options: Options,
+    objects: &'a dyn gix_object::Find,
+    /// The tips to start the traversal from, or `None` if everything that is old enough is considered unreachable.
+    tips: Option<Vec<ObjectId>>,
+    reachable: Option<HashSet<ObjectId>>,
+    buf: Vec<u8>,
+}
+
+impl<'a> Reachability<'a> {
+    fn new(
+        store: &file::Store,
+        name: &FullNameRef,
+        objects: &'a dyn gix_object::Find,
+        options: Options,
+    ) -> Result<Self, Error> {
+        let needs_tips = match (options.expire, options.expire_unreachable) {
+            (_, None) => false,
+            (Some(expire), Some(expire_unreachable)) => expire_unreachable > expire,
+            (None, Some(_)) => true,
+        };
+        let tips = if needs_tips {
+            let mut tips = Vec::new();
+            if name.as_bstr() == "HEAD" {
+                let platform = store.iter()?;
+                for reference in platform.all()? {
+                    let mut reference = match reference {
+                        Ok(r) => r,
+                        Err(file::iter::loose_then_packed::Error::ReferenceCreation { .. }) => continue,
+                        Err(err) => return Err(err.into()),
+                    };
+                    if let Ok(id) = reference.peel_to_id_in_place(store, objects) {
+                        tips.push(id);
+                    }
+                }
+            }
+            if let Some(mut reference) = store.try_find(name)? {
+                if let Ok(id) = reference.peel_to_id_in_place(store, objects) {
+                    tips.push(id);
+                }
+            }
+            (!tips.is_empty()).then_some(tips)
+        } else {
+            None
+        };
+        Ok(Reachability {
+            options,
+            objects,
+            tips,
+            reachable: None,
+            buf: Vec::new(),
+        })
+    }
+
+    fn should_expire(&mut self, time: SecondsSinceUnixEpoch, previous: ObjectId, new: ObjectId) -> Result<bool, Error> {
+        if self.options.expire.map_or(false, |expire| time < expire) {
+            return Ok(true);
+        }
+        if self.options.expire_unreachable.map_or(false, |expire| time < expire) {
+            if self.tips.is_none() {
+                return Ok(true);
+            }
+            for id in [previous, new] {
+                if !id.is_null() && !self.is_reachable(&id)? {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    fn is_reachable(&mut self, id: &gix_hash::oid) -> Result<bool, Error> {
+        if self.reachable.is_none() {
+            let mut reachable = HashSet::new();
+            let mut queue: Vec<_> = self.tips.clone().unwrap_or_default();
+            while let Some(id) = queue.pop() {
+                if !reachable.insert(id) {
+                    continue;
+                }
+                let Some(data) = self.objects.try_find(&id, &mut self.buf)? else {
+                    continue;
+                };
+                if data.kind == gix_object::Kind::Commit {
+                    queue.extend(gix_object::CommitRefIter::from_bytes(data.data).parent_ids());
+                }
+            }
+            self.reachable = Some(reachable);
+        }
+        Ok(self.reachable.as_ref().expect("just computed").contains(id))
+    }
+}
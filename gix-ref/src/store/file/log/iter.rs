@@ -90,7 +90,7 @@ pub struct Platform<'a, 's> {
 
 impl<'a, 's> Platform<'a, 's> {
     /// Return a forward iterator over all log-lines, most recent to oldest.
-    pub fn rev(&mut self) -> std::io::Result<Option<log::iter::Reverse<'_, std::fs::File>>> {
+    pub fn rev(&mut self) -> std::io::Result<Option<log::iter::Reverse<'_, Source>>> {
         self.buf.clear();
         self.buf.resize(512, 0);
         self.store
@@ -105,6 +105,32 @@ impl<'a, 's> Platform<'a, 's> {
     }
 }
 
+/// The data read by a [`Reverse`] iterator obtained from a [`file::Store`].
+pub enum Source {
+    /// The reflog file itself.
+    File(std::fs::File),
+    /// The log entries of a reference stored in reftables, serialized like a reflog file.
+    Buffer(std::io::Cursor<Vec<u8>>),
+}
+
+impl std::io::Read for Source {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Source::File(file) => file.read(buf),
+            Source::Buffer(buffer) => buffer.read(buf),
+        }
+    }
+}
+
+impl std::io::Seek for Source {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        match self {
+            Source::File(file) => file.seek(pos),
+            Source::Buffer(buffer) => buffer.seek(pos),
+        }
+    }
+}
+
 /// An iterator yielding parsed lines in a file in reverse, most recent to oldest.
 pub struct Reverse<'a, F> {
     buf: &'a mut [u8],
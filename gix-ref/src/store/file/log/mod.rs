@@ -4,6 +4,9 @@ pub use super::loose::reflog::{create_or_update, Error};
 
 ///
 pub mod iter;
+
+///
+pub mod expire;
 mod line;
 
 /// A parsed ref log line.
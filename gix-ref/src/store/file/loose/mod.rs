@@ -43,6 +43,7 @@ mod init {
                 object_hash,
                 precompose_unicode,
                 prohibit_windows_device_names,
+                ref_storage,
             }: crate::store::init::Options,
         ) -> Self {
             file::Store {
@@ -55,6 +56,8 @@ mod init {
                 packed: gix_fs::SharedFileSnapshotMut::new().into(),
                 object_hash,
                 precompose_unicode,
+                ref_storage,
+                reftables: Default::default(),
             }
         }
 
@@ -71,6 +74,7 @@ mod init {
                 object_hash,
                 precompose_unicode,
                 prohibit_windows_device_names,
+                ref_storage,
             }: crate::store::init::Options,
         ) -> Self {
             file::Store {
@@ -83,6 +87,8 @@ mod init {
                 packed: gix_fs::SharedFileSnapshotMut::new().into(),
                 object_hash,
                 precompose_unicode,
+                ref_storage,
+                reftables: Default::default(),
             }
         }
     }
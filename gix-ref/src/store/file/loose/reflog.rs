@@ -16,7 +16,16 @@ impl file::Store {
         Name: TryInto<&'a FullNameRef, Error = E>,
         crate::name::Error: From<E>,
     {
-        Ok(self.reflog_path(name.try_into()?).is_file())
+        let name = name.try_into()?;
+        if self.is_in_reftable(name) {
+            let (dir, name) = self.reftable_location(name);
+            return Ok(self
+                .reftable_stack_at(&dir)
+                .ok()
+                .and_then(|stack| stack.has_logs(name.as_ref()).ok())
+                .unwrap_or(false));
+        }
+        Ok(self.reflog_path(name).is_file())
     }
 
     /// Return a reflog reverse iterator for the given fully qualified `name`, reading chunks from the back into the fixed buffer `buf`.
@@ -27,18 +36,27 @@ impl file::Store {
         &self,
         name: Name,
         buf: &'b mut [u8],
-    ) -> Result<Option<log::iter::Reverse<'b, std::fs::File>>, Error>
+    ) -> Result<Option<log::iter::Reverse<'b, log::iter::Source>>, Error>
     where
         Name: TryInto<&'a FullNameRef, Error = E>,
         crate::name::Error: From<E>,
     {
         let name: &FullNameRef = name.try_into().map_err(|err| Error::RefnameValidation(err.into()))?;
+        if self.is_in_reftable(name) {
+            return Ok(match self.reftable_reflog(name)? {
+                Some(log) => Some(log::iter::reverse(
+                    log::iter::Source::Buffer(std::io::Cursor::new(log)),
+                    buf,
+                )?),
+                None => None,
+            });
+        }
         let path = self.reflog_path(name);
         if path.is_dir() {
             return Ok(None);
         }
         match std::fs::File::open(&path) {
-            Ok(file) => Ok(Some(log::iter::reverse(file, buf)?)),
+            Ok(file) => Ok(Some(log::iter::reverse(log::iter::Source::File(file), buf)?)),
             Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
             Err(err) => Err(err.into()),
         }
@@ -58,6 +76,12 @@ impl file::Store {
         crate::name::Error: From<E>,
     {
         let name: &FullNameRef = name.try_into().map_err(|err| Error::RefnameValidation(err.into()))?;
+        if self.is_in_reftable(name) {
+            return Ok(self.reftable_reflog(name)?.map(|log| {
+                *buf = log;
+                log::iter::forward(buf)
+            }));
+        }
         let path = self.reflog_path(name);
         match std::fs::File::open(&path) {
             Ok(mut file) => {
@@ -172,7 +196,7 @@ pub mod create_or_update {
             }
         }
 
-        fn should_autocreate_reflog(&self, full_name: &Path) -> bool {
+        pub(in crate::store_impl::file) fn should_autocreate_reflog(&self, full_name: &Path) -> bool {
             full_name.starts_with("refs/heads/")
                 || full_name.starts_with("refs/remotes/")
                 || full_name.starts_with("refs/notes/")
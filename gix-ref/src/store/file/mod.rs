@@ -3,7 +3,11 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use crate::{bstr::BStr, store::WriteReflog, Namespace};
+use crate::{
+    bstr::BStr,
+    store::{RefStorage, WriteReflog},
+    Namespace,
+};
 
 /// A store for reference which uses plain files.
 ///
@@ -41,6 +45,12 @@ pub struct Store {
     /// It's updated only in one spot, which is prior to reading it based on file stamps.
     /// Doing it like this has the benefit of being able to hand snapshots out to people without blocking others from updating it.
     packed: packed::modifiable::MutableSharedBuffer,
+    /// The format in which references are stored. If it's not the default files format, all references except for a few special ones
+    /// are read from and written to the respective backend instead of loose files and `packed-refs`.
+    ref_storage: RefStorage,
+    /// The stacks of reftables of the common directory and of a linked worktree, if `ref_storage` is set accordingly.
+    /// They are loaded on first use and reloaded whenever their list of tables changes.
+    reftables: reftable::MutableSharedStacks,
 }
 
 mod access {
@@ -81,6 +91,11 @@ mod access {
         pub fn common_dir_resolved(&self) -> &Path {
             self.common_dir.as_deref().unwrap_or(&self.git_dir)
         }
+
+        /// Return the format in which references are stored.
+        pub fn ref_storage(&self) -> crate::store::RefStorage {
+            self.ref_storage
+        }
     }
 }
 
@@ -90,6 +105,8 @@ pub struct Transaction<'s, 'p> {
     packed_transaction: Option<crate::store_impl::packed::Transaction>,
     updates: Option<Vec<transaction::Edit>>,
     packed_refs: transaction::PackedRefs<'p>,
+    /// The stacks of reftables locked by this transaction, if references are stored in reftables.
+    reftables: Vec<transaction::LockedReftable>,
 }
 
 pub(in crate::store_impl::file) fn path_to_name<'a>(path: impl Into<Cow<'a, Path>>) -> Cow<'a, BStr> {
@@ -120,8 +137,13 @@ pub mod find;
 ///
 pub mod transaction;
 
+///
+pub mod pack_refs;
+
 ///
 pub mod packed;
 
+mod reftable;
+
 mod raw_ext;
 pub use raw_ext::ReferenceExt;
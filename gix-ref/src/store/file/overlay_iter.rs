@@ -8,7 +8,9 @@ use std::{
 
 use crate::{
     file::{loose, loose::iter::SortedLoosePaths, path_to_name},
-    store_impl::{file, packed},
+    reftable,
+    store::RefStorage,
+    store_impl::{file, file::reftable::to_reference, packed},
     BString, FullName, Namespace, Reference,
 };
 
@@ -24,6 +26,8 @@ pub struct LooseThenPacked<'p, 's> {
     iter_git_dir: Peekable<SortedLoosePaths>,
     #[allow(dead_code)]
     iter_common_dir: Option<Peekable<SortedLoosePaths>>,
+    /// If set, all references are stored in reftables and loose and packed iterators are empty.
+    iter_reftable: Option<reftable::stack::Refs>,
     buf: Vec<u8>,
 }
 
@@ -116,6 +120,15 @@ impl<'p, 's> Iterator for LooseThenPacked<'p, 's> {
     type Item = Result<Reference, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(refs) = self.iter_reftable.as_mut() {
+            let res = refs.next()?;
+            return Some(
+                res.map(|r| to_reference(r.name, r.value).expect("deletions are never returned"))
+                    .map(|r| self.strip_namespace(r))
+                    .map_err(Error::Reftable),
+            );
+        }
+
         fn advance_to_non_private(iter: &mut Peekable<SortedLoosePaths>) {
             while let Some(Ok((_path, name))) = iter.peek() {
                 if name.category().map_or(false, |cat| cat.is_worktree_private()) {
@@ -410,6 +423,18 @@ impl file::Store {
         common_dir_info: Option<IterInfo<'_>>,
         packed: Option<&'p packed::Buffer>,
     ) -> std::io::Result<LooseThenPacked<'p, 's>> {
+        let iter_reftable = match self.ref_storage {
+            RefStorage::Files => None,
+            RefStorage::Reftable => {
+                let prefix = git_dir_info
+                    .prefix()
+                    .map_or(Cow::Borrowed(crate::bstr::BStr::new("refs/")), path_to_name);
+                Some(
+                    self.reftable_iter(prefix.as_ref())
+                        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?,
+                )
+            }
+        };
         Ok(LooseThenPacked {
             git_dir: self.git_dir(),
             common_dir: self.common_dir(),
@@ -426,6 +451,7 @@ impl file::Store {
             },
             iter_git_dir: git_dir_info.into_iter(),
             iter_common_dir: common_dir_info.map(IterInfo::into_iter),
+            iter_reftable,
             buf: Vec::new(),
             namespace: self.namespace.as_ref(),
         })
@@ -454,6 +480,8 @@ mod error {
         },
         #[error("Invalid reference in line {line_number}: {invalid_line:?}")]
         PackedReference { invalid_line: BString, line_number: usize },
+        #[error("A reference could not be read from a reftable")]
+        Reftable(#[source] crate::reftable::decode::Error),
     }
 }
 pub use error::Error;
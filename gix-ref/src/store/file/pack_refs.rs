@@ -0,0 +1,175 @@
+use gix_object::bstr::ByteSlice;
+
+use crate::{
+    file,
+    store::RefStorage,
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    Target,
+};
+
+/// Options for use in [`file::Store::pack_refs()`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Options {
+    /// If `true`, pack all references that aren't symbolic or private to a worktree, similar to `git pack-refs --all`.
+    ///
+    /// Otherwise, only tags and references that are already packed are written into `packed-refs`, which is the default
+    /// of `git pack-refs` as branches are expected to change frequently.
+    pub all: bool,
+    /// If `true`, remove the loose references after they were written into `packed-refs`, along with their then empty
+    /// parent directories, similar to `git pack-refs --prune`, which is the default there.
+    pub prune: bool,
+    /// How to handle locks on loose references as well as on `tables.list` if the references are stored in reftables.
+    pub lock_mode: gix_lock::acquire::Fail,
+    /// How to handle the lock on the `packed-refs` file.
+    pub packed_refs_lock_mode: gix_lock::acquire::Fail,
+}
+
+/// The outcome of [`file::Store::pack_refs()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    /// The amount of loose references that were written into `packed-refs`.
+    pub packed: usize,
+    /// The amount of loose references which were removed after packing them.
+    pub pruned: usize,
+    /// The amount of loose references that were skipped as they couldn't be parsed or point to objects that don't exist.
+    pub skipped_broken: usize,
+    /// If `true`, tables of the reftable stack were compacted into one as the references are stored in reftables.
+    pub compacted_reftables: bool,
+}
+
+/// The error returned by [`file::Store::pack_refs()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not iterate loose references")]
+    Iter(#[from] std::io::Error),
+    #[error("Could not read the packed-refs file")]
+    PackedOpen(#[from] crate::packed::buffer::open::Error),
+    #[error("A loose reference could not be decoded")]
+    IterEntry(#[from] file::iter::loose_then_packed::Error),
+    #[error("Failed to check if an object exists")]
+    FindObject(#[from] gix_object::find::Error),
+    #[error("Could not prepare the transaction to write the packed-refs file")]
+    Prepare(#[from] file::transaction::prepare::Error),
+    #[error("Could not commit the transaction to write the packed-refs file")]
+    Commit(#[from] file::transaction::commit::Error),
+    #[error("Could not compact the reftable stack")]
+    Compact(#[from] crate::reftable::stack::compact::Error),
+}
+
+impl file::Store {
+    /// Write loose references into the `packed-refs` file according to `options`, using `objects` to peel annotated tags
+    /// to store their peeled object along with them, similar to what `git pack-refs` does.
+    ///
+    /// This is done with a transaction that holds locks to all loose references to pack, as well as the `packed-refs` file,
+    /// so concurrent modifications will either be prevented or make the operation fail without loss of data.
+    /// References that can't be parsed or point to objects that don't exist in `objects` are skipped.
+    ///
+    /// If the references are stored in reftables, the stack of tables will be compacted into one table instead.
+    pub fn pack_refs(&self, objects: impl gix_object::Find, options: Options) -> Result<Outcome, Error> {
+        if self.ref_storage == RefStorage::Reftable {
+            return self.compact_reftables(options.lock_mode);
+        }
+
+        let packed = self.open_packed_buffer()?;
+        let mut out = Outcome::default();
+        let mut edits = Vec::new();
+        let mut buf = Vec::new();
+        for reference in self.loose_iter()? {
+            let reference = match reference {
+                Ok(r) => r,
+                Err(file::iter::loose_then_packed::Error::ReferenceCreation { .. }) => {
+                    out.skipped_broken += 1;
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
+            let id = match reference.target {
+                Target::Object(id) => id,
+                Target::Symbolic(_) => continue,
+            };
+            let name = reference.name;
+            if !name.as_bstr().starts_with(b"refs/") || name.category().map_or(false, |c| c.is_worktree_private()) {
+                continue;
+            }
+            let is_packed = packed.as_ref().map_or(false, |packed| {
+                let name = match &self.namespace {
+                    Some(namespace) => namespace.clone().into_namespaced_name(name.as_ref()),
+                    None => name.clone(),
+                };
+                packed.try_find(name.as_ref()).ok().flatten().is_some()
+            });
+            if !(options.all || is_packed || name.as_bstr().starts_with(b"refs/tags/")) {
+                continue;
+            }
+            if objects.try_find(&id, &mut buf)?.is_none() {
+                out.skipped_broken += 1;
+                continue;
+            }
+            edits.push(RefEdit {
+                change: Change::Update {
+                    log: LogChange {
+                        mode: RefLog::AndReference,
+                        force_create_reflog: false,
+                        message: "pack-refs".into(),
+                    },
+                    expected: PreviousValue::MustExistAndMatch(Target::Object(id)),
+                    new: Target::Object(id),
+                },
+                name,
+                deref: false,
+            });
+        }
+        if edits.is_empty() {
+            return Ok(out);
+        }
+
+        let packed_refs = if options.prune {
+            file::transaction::PackedRefs::DeletionsAndNonSymbolicUpdatesRemoveLooseSourceReference(Box::new(&objects))
+        } else {
+            file::transaction::PackedRefs::DeletionsAndNonSymbolicUpdates(Box::new(&objects))
+        };
+        let edits = self
+            .transaction()
+            .packed_refs(packed_refs)
+            .prepare(edits, options.lock_mode, options.packed_refs_lock_mode)?
+            .commit(None)?;
+        out.packed = edits.len();
+        if options.prune {
+            out.pruned = edits.len();
+            for edit in &edits {
+                self.remove_empty_parent_directories(edit.name.as_ref());
+            }
+        }
+        Ok(out)
+    }
+
+    /// Remove the empty parent directories of the loose reference `name`, while keeping the category directory like `refs/heads`.
+    fn remove_empty_parent_directories(&self, name: &crate::FullNameRef) {
+        let path = self.reference_path(name);
+        let num_components = name.as_bstr().split_str("/").count();
+        if num_components <= 3 {
+            return;
+        }
+        let boundary = path.ancestors().nth(num_components - 2).expect("enough components");
+        if let Some(parent) = path.parent() {
+            gix_tempfile::remove_dir::empty_upward_until_boundary(parent, boundary).ok();
+        }
+    }
+
+    fn compact_reftables(&self, lock_mode: gix_lock::acquire::Fail) -> Result<Outcome, Error> {
+        let mut dirs = vec![self.common_dir_resolved().join("reftable")];
+        if self.git_dir() != self.common_dir_resolved() {
+            dirs.push(self.git_dir().join("reftable"));
+        }
+        let mut out = Outcome::default();
+        for dir in dirs {
+            if !dir.join(crate::reftable::stack::TABLES_LIST).is_file() {
+                continue;
+            }
+            let mut stack = crate::reftable::Stack::at(dir).map_err(crate::reftable::stack::compact::Error::from)?;
+            out.compacted_reftables |= stack.compact_all(lock_mode)?;
+        }
+        Ok(out)
+    }
+}
@@ -107,7 +107,7 @@ impl ReferenceExt for Reference {
                 let mut oid = self.follow_to_object_in_place_packed(store, packed)?;
                 let mut buf = Vec::new();
                 let peeled_id = loop {
-                    let gix_object::Data { kind, data } =
+                    let gix_object::Data { kind, data, .. } =
                         objects
                             .try_find(&oid, &mut buf)?
                             .ok_or_else(|| peel::to_id::Error::NotFound {
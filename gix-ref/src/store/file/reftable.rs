@@ -0,0 +1,142 @@
+use std::path::{Path, PathBuf};
+
+use gix_features::threading::{lock, Mutable, OwnShared};
+use gix_object::bstr::BStr;
+
+use crate::{
+    file::find,
+    reftable::{self, table::open, Value},
+    store::RefStorage,
+    store_impl::file,
+    FullName, FullNameRef, Reference, Target,
+};
+
+/// The reftable stacks of a store, shared among all of its clones.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MutableSharedStacks(OwnShared<Stacks>);
+
+#[derive(Debug, Default)]
+struct Stacks {
+    /// The stack in the common directory, holding all shared references along with the private ones of the main worktree.
+    common: Mutable<Option<OwnShared<reftable::Stack>>>,
+    /// The stack in the `git_dir` of a linked worktree, holding its private references.
+    worktree: Mutable<Option<OwnShared<reftable::Stack>>>,
+}
+
+/// Reftables
+impl file::Store {
+    /// Return the stack of reftables in the common directory if references are stored as reftables, or `None` otherwise.
+    ///
+    /// The returned stack is a snapshot which is up to date at the time of the call.
+    pub fn reftable_stack(&self) -> Result<Option<OwnShared<reftable::Stack>>, open::Error> {
+        if self.ref_storage != RefStorage::Reftable {
+            return Ok(None);
+        }
+        self.reftable_stack_at(&self.common_dir_resolved().join("reftable"))
+            .map(Some)
+    }
+
+    /// Return `true` if the reference `name` is stored in a reftable, which is all of them if reftables are used,
+    /// except for the special `FETCH_HEAD` and `MERGE_HEAD`.
+    pub(crate) fn is_in_reftable(&self, name: &FullNameRef) -> bool {
+        self.ref_storage == RefStorage::Reftable && {
+            let (_base, name) = self.to_base_dir_and_relative_name(name, true);
+            name.as_bstr() != "FETCH_HEAD" && name.as_bstr() != "MERGE_HEAD"
+        }
+    }
+
+    /// Return the directory of the stack of reftables that stores `name`, along with the name of its records.
+    pub(crate) fn reftable_location(&self, name: &FullNameRef) -> (PathBuf, FullName) {
+        let (base, name) = self.to_base_dir_and_relative_name(name, true);
+        let name = match &self.namespace {
+            Some(namespace) => namespace.to_owned().into_namespaced_name(name),
+            None => name.to_owned(),
+        };
+        (base.join("reftable"), name)
+    }
+
+    /// Return an up-to-date stack of reftables in `dir`, which is cached for our own git and common directories.
+    pub(crate) fn reftable_stack_at(&self, dir: &Path) -> Result<OwnShared<reftable::Stack>, open::Error> {
+        let slot = if dir == self.common_dir_resolved().join("reftable") {
+            &self.reftables.0.common
+        } else if dir == self.git_dir.join("reftable") {
+            &self.reftables.0.worktree
+        } else {
+            return reftable::Stack::at(dir).map(OwnShared::new);
+        };
+        let mut slot = lock(slot);
+        let reloaded = match slot.as_ref() {
+            Some(stack) => stack.reload_if_changed()?,
+            None => Some(reftable::Stack::at(dir)?),
+        };
+        if let Some(stack) = reloaded {
+            *slot = Some(OwnShared::new(stack));
+        }
+        Ok(slot.clone().expect("loaded"))
+    }
+
+    /// Find `name` in the stack of reftables storing it.
+    pub(crate) fn find_in_reftable(&self, name: &FullNameRef) -> Result<Option<Reference>, find::Error> {
+        let (dir, key) = self.reftable_location(name);
+        let Some(r) = self.reftable_stack_at(&dir)?.find_ref(key.as_ref())? else {
+            return Ok(None);
+        };
+        let mut r = to_reference(name.to_owned(), r.value).expect("deletions are never returned");
+        if let Some(namespace) = &self.namespace {
+            r.strip_namespace(namespace);
+        }
+        Ok(Some(r))
+    }
+
+    /// Return all log entries of `name` serialized like a reflog file, from oldest to newest, or `None` if there is none.
+    pub(crate) fn reftable_reflog(&self, name: &FullNameRef) -> std::io::Result<Option<Vec<u8>>> {
+        use std::io::Write;
+        let (dir, key) = self.reftable_location(name);
+        let logs = self
+            .reftable_stack_at(&dir)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?
+            .logs_of(key.as_ref())
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+        if logs.is_empty() {
+            return Ok(None);
+        }
+        let mut out = Vec::new();
+        for line in logs.into_iter().rev().filter_map(|log| log.line) {
+            write!(out, "{} {} ", line.previous_oid, line.new_oid)?;
+            line.signature.write_to(&mut out)?;
+            if line.message.is_empty() {
+                writeln!(out)?;
+            } else {
+                writeln!(out, "\t{}", line.message)?;
+            }
+        }
+        Ok(Some(out))
+    }
+
+    /// Return an iterator over all references in reftables whose name starts with `prefix`, as seen from our worktree.
+    pub(crate) fn reftable_iter(&self, prefix: &BStr) -> Result<reftable::stack::Refs, find::Error> {
+        let common = self.reftable_stack_at(&self.common_dir_resolved().join("reftable"))?;
+        Ok(match self.common_dir() {
+            Some(_) => {
+                let worktree = self.reftable_stack_at(&self.git_dir.join("reftable"))?;
+                let skip_worktree_private = true;
+                reftable::stack::Refs::new(
+                    [(&*worktree, false), (&*common, skip_worktree_private)].into_iter(),
+                    prefix,
+                )?
+            }
+            None => common.refs(prefix)?,
+        })
+    }
+}
+
+/// Turn `value` into a reference named `name`, or return `None` if it's a deletion.
+pub(crate) fn to_reference(name: FullName, value: Value) -> Option<Reference> {
+    let (target, peeled) = match value {
+        Value::Deletion => return None,
+        Value::Object(id) => (Target::Object(id), None),
+        Value::Peeled { target, peeled } => (Target::Object(target), Some(peeled)),
+        Value::Symbolic(target) => (Target::Symbolic(target), None),
+    };
+    Some(Reference { name, target, peeled })
+}
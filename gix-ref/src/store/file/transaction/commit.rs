@@ -1,5 +1,12 @@
 use crate::{
-    store_impl::file::{transaction::PackedRefs, Transaction},
+    reftable,
+    store::WriteReflog,
+    store_impl::file::{
+        self,
+        log::create_or_update,
+        transaction::{Edit, LockedReftable, PackedRefs},
+        Transaction,
+    },
     transaction::{Change, LogChange, RefEdit, RefLog},
     Target,
 };
@@ -35,43 +42,24 @@ impl<'s, 'p> Transaction<'s, 'p> {
             self.packed_refs,
             PackedRefs::DeletionsAndNonSymbolicUpdatesRemoveLooseSourceReference(_)
         );
+        for (idx, locked) in self.reftables.into_iter().enumerate() {
+            let edits = updates.iter().filter(|change| change.reftable == Some(idx));
+            Self::commit_reftable(self.store, locked, edits, committer)?;
+        }
 
         // Perform updates first so live commits remain referenced
-        for change in &mut updates {
+        for change in updates.iter_mut().filter(|change| change.reftable.is_none()) {
             assert!(!change.update.deref, "Deref mode is turned into splits and turned off");
             match &change.update.change {
                 // reflog first, then reference
-                Change::Update { log, new, expected } => {
+                Change::Update { log, new, .. } => {
                     let lock = change.lock.take();
                     let (update_ref, update_reflog) = match log.mode {
                         RefLog::Only => (false, true),
                         RefLog::AndReference => (true, true),
                     };
                     if update_reflog {
-                        let log_update = match new {
-                            Target::Symbolic(_) => {
-                                // Special HACK: no reflog for symref changes as there is no OID involved which the reflog needs.
-                                // Unless, the ref is new and we can obtain a peeled id
-                                // identified by the expectation of what could be there, as is the case when cloning.
-                                match expected {
-                                    PreviousValue::ExistingMustMatch(Target::Object(oid)) => {
-                                        Some((Some(gix_hash::ObjectId::null(oid.kind())), oid))
-                                    }
-                                    _ => None,
-                                }
-                            }
-                            Target::Object(new_oid) => {
-                                let previous = match expected {
-                                    // Here, this means that the ref already existed, and that it will receive (even transitively)
-                                    // the given value
-                                    PreviousValue::MustExistAndMatch(Target::Object(oid)) => Some(oid.to_owned()),
-                                    _ => None,
-                                }
-                                .or(change.leaf_referent_previous_oid);
-                                Some((previous, new_oid))
-                            }
-                        };
-                        if let Some((previous, new_oid)) = log_update {
+                        if let Some((previous, new_oid)) = change.reflog_update() {
                             let do_update = previous.as_ref().map_or(true, |previous| previous != new_oid);
                             if do_update {
                                 self.store.reflog_create_or_append(
@@ -117,7 +105,7 @@ impl<'s, 'p> Transaction<'s, 'p> {
             }
         }
 
-        for change in &mut updates {
+        for change in updates.iter_mut().filter(|change| change.reftable.is_none()) {
             let (reflog_root, relative_name) = self.store.reflog_base_and_relative_path(change.update.name.as_ref());
             match &change.update.change {
                 Change::Update { .. } => {}
@@ -150,7 +138,7 @@ impl<'s, 'p> Transaction<'s, 'p> {
             self.store.force_refresh_packed_buffer().ok();
         }
 
-        for change in &mut updates {
+        for change in updates.iter_mut().filter(|change| change.reftable.is_none()) {
             let take_lock_and_delete = match &change.update.change {
                 Change::Update {
                     log: LogChange { mode, .. },
@@ -175,6 +163,125 @@ impl<'s, 'p> Transaction<'s, 'p> {
         }
         Ok(updates.into_iter().map(|edit| edit.update).collect())
     }
+
+    /// Write all `edits` of references stored in the `locked` stack of reftables into a new table on top of it.
+    fn commit_reftable<'e>(
+        store: &file::Store,
+        locked: LockedReftable,
+        edits: impl Iterator<Item = &'e Edit>,
+        committer: Option<gix_actor::SignatureRef<'_>>,
+    ) -> Result<(), Error> {
+        let LockedReftable { lock, mut stack } = locked;
+        let update_index = stack.max_update_index() + 1;
+        let mut writer = reftable::write::Writer::new(
+            update_index,
+            update_index,
+            reftable::write::Options {
+                object_hash: store.object_hash,
+                ..Default::default()
+            },
+        );
+        for change in edits {
+            let (_, name) = store.reftable_location(change.update.name.as_ref());
+            match &change.update.change {
+                Change::Update { log, new, .. } => {
+                    if log.mode == RefLog::AndReference {
+                        writer.add_ref(reftable::Ref {
+                            name: name.clone(),
+                            update_index,
+                            value: match new {
+                                Target::Object(id) => reftable::Value::Object(*id),
+                                Target::Symbolic(target) => reftable::Value::Symbolic(target.clone()),
+                            },
+                        });
+                    }
+                    let Some((previous, new_oid)) = change.reflog_update() else {
+                        continue;
+                    };
+                    if previous.as_ref() == Some(new_oid) {
+                        continue;
+                    }
+                    let write_log = match store.write_reflog {
+                        WriteReflog::Disable => false,
+                        WriteReflog::Always => true,
+                        WriteReflog::Normal => {
+                            log.force_create_reflog
+                                || store.should_autocreate_reflog(&gix_path::from_bstr(name.as_bstr()))
+                                || stack.has_logs(name.as_ref())?
+                        }
+                    };
+                    if write_log {
+                        let committer = committer.ok_or(create_or_update::Error::MissingCommitter)?;
+                        if log.message.contains(&b'\n') {
+                            return Err(create_or_update::Error::MessageWithNewlines.into());
+                        }
+                        writer.add_log(reftable::Log {
+                            name,
+                            update_index,
+                            line: Some(crate::log::Line {
+                                previous_oid: previous.unwrap_or_else(|| new_oid.kind().null()),
+                                new_oid: new_oid.to_owned(),
+                                signature: committer.to_owned(),
+                                message: log.message.clone(),
+                            }),
+                        });
+                    }
+                }
+                Change::Delete { log: mode, .. } => {
+                    // Like with reflog files, the log is deleted along with the reference.
+                    for log in stack.logs_of(name.as_ref())? {
+                        writer.add_log(reftable::Log { line: None, ..log });
+                    }
+                    if *mode == RefLog::AndReference {
+                        writer.add_ref(reftable::Ref {
+                            name,
+                            update_index,
+                            value: reftable::Value::Deletion,
+                        });
+                    }
+                }
+            }
+        }
+        if writer.is_empty() {
+            return Ok(());
+        }
+        stack.append(lock, writer.finish()?)?;
+        // Compaction is an optimization that may fail if others hold locks, and it's fine to leave that to later updates.
+        stack.auto_compact(gix_lock::acquire::Fail::Immediately).ok();
+        Ok(())
+    }
+}
+
+impl Edit {
+    /// Return the previous and the new object id to write into the reflog if this is an update.
+    fn reflog_update(&self) -> Option<(Option<gix_hash::ObjectId>, &gix_hash::ObjectId)> {
+        let Change::Update { new, expected, .. } = &self.update.change else {
+            return None;
+        };
+        match new {
+            Target::Symbolic(_) => {
+                // Special HACK: no reflog for symref changes as there is no OID involved which the reflog needs.
+                // Unless, the ref is new and we can obtain a peeled id
+                // identified by the expectation of what could be there, as is the case when cloning.
+                match expected {
+                    PreviousValue::ExistingMustMatch(Target::Object(oid)) => {
+                        Some((Some(gix_hash::ObjectId::null(oid.kind())), oid))
+                    }
+                    _ => None,
+                }
+            }
+            Target::Object(new_oid) => {
+                let previous = match expected {
+                    // Here, this means that the ref already existed, and that it will receive (even transitively)
+                    // the given value
+                    PreviousValue::MustExistAndMatch(Target::Object(oid)) => Some(oid.to_owned()),
+                    _ => None,
+                }
+                .or(self.leaf_referent_previous_oid);
+                Some((previous, new_oid))
+            }
+        }
+    }
 }
 mod error {
     use gix_object::bstr::BString;
@@ -197,6 +304,12 @@ mod error {
         DeleteReflog { full_name: BString, source: std::io::Error },
         #[error("The reflog could not be created or updated")]
         CreateOrUpdateRefLog(#[from] file::log::create_or_update::Error),
+        #[error("The log of a reference could not be read from a reftable")]
+        ReftableDecode(#[from] crate::reftable::decode::Error),
+        #[error("The new reftable could not be written")]
+        ReftableWrite(#[from] crate::reftable::write::Error),
+        #[error("The new reftable could not be added to its stack")]
+        ReftableAppend(#[from] crate::reftable::stack::append::Error),
     }
 }
 pub use error::Error;
@@ -39,6 +39,14 @@ pub(in crate::store_impl::file) struct Edit {
     /// For symbolic refs, this is the previous OID to put into the reflog instead of our own previous value. It's the
     /// peeled value of the leaf referent.
     leaf_referent_previous_oid: Option<ObjectId>,
+    /// If set, the reference is stored in the reftable stack of the transaction with the given index.
+    reftable: Option<usize>,
+}
+
+/// A stack of reftables that is locked by a transaction, as it was when the lock was obtained.
+pub(in crate::store_impl::file) struct LockedReftable {
+    lock: gix_lock::File,
+    stack: crate::reftable::Stack,
 }
 
 impl Edit {
@@ -73,6 +81,7 @@ impl file::Store {
             packed_transaction: None,
             updates: None,
             packed_refs: PackedRefs::default(),
+            reftables: Vec::new(),
         }
     }
 }
@@ -1,11 +1,14 @@
 use crate::{
     packed,
     packed::transaction::buffer_into_transaction,
+    reftable,
+    store::RefStorage,
     store_impl::{
         file,
         file::{
             loose,
-            transaction::{Edit, PackedRefs},
+            reftable::to_reference,
+            transaction::{Edit, LockedReftable, PackedRefs},
             Transaction,
         },
     },
@@ -14,10 +17,12 @@ use crate::{
 };
 
 impl<'s, 'p> Transaction<'s, 'p> {
+    #[allow(clippy::too_many_arguments)]
     fn lock_ref_and_apply_change(
         store: &file::Store,
         lock_fail_mode: gix_lock::acquire::Fail,
         packed: Option<&packed::Buffer>,
+        reftables: &mut Vec<LockedReftable>,
         change: &mut Edit,
         has_global_lock: bool,
         direct_to_packed_refs: bool,
@@ -28,34 +33,39 @@ impl<'s, 'p> Transaction<'s, 'p> {
             "locks can only be acquired once and it's all or nothing"
         );
 
-        let existing_ref = store
-            .ref_contents(change.update.name.as_ref())
-            .map_err(Error::from)
-            .and_then(|maybe_loose| {
-                maybe_loose
-                    .map(|buf| {
-                        loose::Reference::try_from_path(change.update.name.clone(), &buf)
-                            .map(Reference::from)
-                            .map_err(Error::from)
-                    })
-                    .transpose()
-            })
-            .or_else(|err| match err {
-                Error::ReferenceDecode(_) => Ok(None),
-                other => Err(other),
-            })
-            .and_then(|maybe_loose| match (maybe_loose, packed) {
-                (None, Some(packed)) => packed
-                    .try_find(change.update.name.as_ref())
-                    .map(|opt| opt.map(Into::into))
-                    .map_err(Error::from),
-                (None, None) => Ok(None),
-                (maybe_loose, _) => Ok(maybe_loose),
-            })?;
+        // The stacks of reftables are locked as a whole, and only once per transaction.
+        let reftable = if store.is_in_reftable(change.update.name.as_ref()) {
+            let (dir, name) = store.reftable_location(change.update.name.as_ref());
+            let idx = match reftables.iter().position(|locked| locked.stack.dir() == dir) {
+                Some(idx) => idx,
+                None => {
+                    let lock = reftable::Stack::lock(&dir, lock_fail_mode).map_err(|err| Error::LockAcquire {
+                        source: err,
+                        full_name: "borrowcheck won't allow change.name()".into(),
+                    })?;
+                    reftables.push(LockedReftable {
+                        stack: reftable::Stack::at(dir)?,
+                        lock,
+                    });
+                    reftables.len() - 1
+                }
+            };
+            change.reftable = Some(idx);
+            Some((&reftables[idx].stack, name))
+        } else {
+            None
+        };
+
+        let existing_ref = match &reftable {
+            Some((stack, name)) => stack
+                .find_ref(name.as_ref())?
+                .and_then(|r| to_reference(change.update.name.clone(), r.value)),
+            None => Self::find_existing_file_ref(store, packed, change)?,
+        };
         let lock = match &mut change.update.change {
             Change::Delete { expected, .. } => {
                 let (base, relative_path) = store.reference_path_with_base(change.update.name.as_ref());
-                let lock = if has_global_lock {
+                let lock = if has_global_lock || reftable.is_some() {
                     None
                 } else {
                     gix_lock::Marker::acquire_to_hold_resource(
@@ -117,7 +127,7 @@ impl<'s, 'p> Transaction<'s, 'p> {
                         full_name: "borrowcheck won't allow change.name() and this will be corrected by caller".into(),
                     })
                 };
-                let mut lock = (!has_global_lock).then(obtain_lock).transpose()?;
+                let mut lock = (!has_global_lock && reftable.is_none()).then(obtain_lock).transpose()?;
 
                 match (&expected, &existing_ref) {
                     (PreviousValue::Any, _)
@@ -178,7 +188,7 @@ impl<'s, 'p> Transaction<'s, 'p> {
                     (true, matches!(new, Target::Symbolic(_)))
                 };
 
-                if (is_effective && !direct_to_packed_refs) || is_symbolic {
+                if reftable.is_none() && ((is_effective && !direct_to_packed_refs) || is_symbolic) {
                     let mut lock = lock.take().map_or_else(obtain_lock, Ok)?;
 
                     lock.with_mut(|file| match new {
@@ -194,6 +204,38 @@ impl<'s, 'p> Transaction<'s, 'p> {
         change.lock = lock;
         Ok(())
     }
+
+    /// Find the current value of the reference to `change` as loose reference, or in `packed` refs.
+    fn find_existing_file_ref(
+        store: &file::Store,
+        packed: Option<&packed::Buffer>,
+        change: &Edit,
+    ) -> Result<Option<Reference>, Error> {
+        store
+            .ref_contents(change.update.name.as_ref())
+            .map_err(Error::from)
+            .and_then(|maybe_loose| {
+                maybe_loose
+                    .map(|buf| {
+                        loose::Reference::try_from_path(change.update.name.clone(), &buf)
+                            .map(Reference::from)
+                            .map_err(Error::from)
+                    })
+                    .transpose()
+            })
+            .or_else(|err| match err {
+                Error::ReferenceDecode(_) => Ok(None),
+                other => Err(other),
+            })
+            .and_then(|maybe_loose| match (maybe_loose, packed) {
+                (None, Some(packed)) => packed
+                    .try_find(change.update.name.as_ref())
+                    .map(|opt| opt.map(Into::into))
+                    .map_err(Error::from),
+                (None, None) => Ok(None),
+                (maybe_loose, _) => Ok(maybe_loose),
+            })
+    }
 }
 
 impl<'s, 'p> Transaction<'s, 'p> {
@@ -229,6 +271,7 @@ impl<'s, 'p> Transaction<'s, 'p> {
                 lock: None,
                 parent_index: None,
                 leaf_referent_previous_oid: None,
+                reftable: None,
             })
             .collect();
         updates
@@ -245,6 +288,7 @@ impl<'s, 'p> Transaction<'s, 'p> {
                     lock: None,
                     parent_index: Some(idx),
                     leaf_referent_previous_oid: None,
+                    reftable: None,
                 },
             )
             .map_err(Error::PreprocessingFailed)?;
@@ -254,9 +298,11 @@ impl<'s, 'p> Transaction<'s, 'p> {
             | PackedRefs::DeletionsAndNonSymbolicUpdatesRemoveLooseSourceReference(_) => Some(0_usize),
             PackedRefs::DeletionsOnly => None,
         };
-        if maybe_updates_for_packed_refs.is_some()
-            || self.store.packed_refs_path().is_file()
-            || self.store.packed_refs_lock_path().is_file()
+        // With reftables, there is nothing to do for packed refs.
+        if self.store.ref_storage == RefStorage::Files
+            && (maybe_updates_for_packed_refs.is_some()
+                || self.store.packed_refs_path().is_file()
+                || self.store.packed_refs_lock_path().is_file())
         {
             let mut edits_for_packed_transaction = Vec::<RefEdit>::new();
             let mut needs_packed_refs_lookups = false;
@@ -356,6 +402,7 @@ impl<'s, 'p> Transaction<'s, 'p> {
                 self.store,
                 ref_files_lock_fail_mode,
                 self.packed_transaction.as_ref().and_then(packed::Transaction::buffer),
+                &mut self.reftables,
                 change,
                 self.packed_transaction.is_some(),
                 matches!(
@@ -486,6 +533,10 @@ mod error {
         },
         #[error("Could not read reference")]
         ReferenceDecode(#[from] file::loose::reference::decode::Error),
+        #[error("The reftables could not be loaded")]
+        ReftableOpen(#[from] crate::reftable::table::open::Error),
+        #[error("A reference could not be read from a reftable")]
+        ReftableDecode(#[from] crate::reftable::decode::Error),
     }
 }
 
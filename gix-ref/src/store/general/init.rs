@@ -22,7 +22,7 @@ impl crate::Store {
     /// Note that if [`precompose_unicode`](crate::store::init::Options::precompose_unicode) is set in the options,
     /// the `git_dir` is also expected to use precomposed unicode, or else some operations that strip prefixes will fail.
     pub fn at(git_dir: PathBuf, opts: crate::store::init::Options) -> Result<Self, Error> {
-        // for now, just try to read the directory - later we will do that naturally as we have to figure out if it's a ref-table or not.
+        // The file store also handles reftables if configured in `opts`, so all we have to do is to validate the directory.
         std::fs::read_dir(&git_dir)?;
         Ok(crate::Store {
             inner: crate::store::State::Loose {
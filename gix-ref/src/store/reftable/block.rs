@@ -0,0 +1,306 @@
+use crate::store_impl::reftable::{
+    decode::{self, be24, corrupt, varint, Error},
+    Table,
+};
+
+pub(crate) mod kind {
+    pub const REF: u8 = b'r';
+    pub const LOG: u8 = b'g';
+    pub const INDEX: u8 = b'i';
+}
+
+/// The size of the header of each block, holding its kind and length.
+pub(crate) const HEADER_LEN: usize = 4;
+
+/// A decoded block, with log blocks being decompressed.
+pub(crate) struct Block {
+    pub kind: u8,
+    /// The data of the block, starting at the beginning of the block which includes the file header for the very first block.
+    data: Vec<u8>,
+    records_start: usize,
+    restarts_start: usize,
+    num_restarts: usize,
+    hash_len: usize,
+    /// The offset at which the next block in the table starts.
+    pub next_offset: usize,
+}
+
+impl Block {
+    /// Read the block at `offset` in `table`, or return `None` if there is no block there.
+    pub fn at(table: &Table, offset: usize) -> Result<Option<Block>, Error> {
+        let data = &table.data;
+        let end = table.footer_offset();
+        let header_off = if offset == 0 { table.header_len() } else { 0 };
+        if offset + header_off + HEADER_LEN > end {
+            return Ok(None);
+        }
+        let header = &data[offset + header_off..][..HEADER_LEN];
+        let kind = header[0];
+        if kind == 0 {
+            return Ok(None);
+        }
+        let len = be24(&header[1..]) as usize;
+        let records_start = header_off + HEADER_LEN;
+        if len < records_start + 2 {
+            return Err(corrupt("block is too short"));
+        }
+        let (block, full_len) = if kind == kind::LOG {
+            let mut block = vec![0; len];
+            block[..records_start].copy_from_slice(&data[offset..][..records_start]);
+            let mut inflate = gix_features::zlib::Inflate::default();
+            let compressed = &data[offset + records_start..end];
+            let (status, consumed_in, consumed_out) = inflate.once(compressed, &mut block[records_start..])?;
+            if status != gix_features::zlib::Status::StreamEnd || consumed_out != len - records_start {
+                return Err(corrupt("log block didn't decompress to its declared size"));
+            }
+            (block, records_start + consumed_in)
+        } else {
+            let block = data
+                .get(offset..offset + len)
+                .filter(|_| offset + len <= end)
+                .ok_or_else(|| corrupt("block extends past the end of the table"))?
+                .to_vec();
+            let block_size = table.block_size as usize;
+            // Blocks are padded to the block size, unless they are followed by another block right away.
+            let full_len = if block_size == 0 || (len < block_size && offset + len < end && data[offset + len] != 0) {
+                len
+            } else {
+                block_size.max(len)
+            };
+            (block, full_len)
+        };
+
+        let num_restarts = usize::from(u16::from_be_bytes([block[len - 2], block[len - 1]]));
+        let restarts_start = (len - 2)
+            .checked_sub(num_restarts * 3)
+            .filter(|start| *start >= records_start)
+            .ok_or_else(|| corrupt("restart table is larger than the block"))?;
+        Ok(Some(Block {
+            kind,
+            data: block,
+            records_start,
+            restarts_start,
+            num_restarts,
+            hash_len: table.object_hash.len_in_bytes(),
+            next_offset: offset + full_len,
+        }))
+    }
+
+    fn restart(&self, idx: usize) -> usize {
+        be24(&self.data[self.restarts_start + idx * 3..]) as usize
+    }
+
+    /// Decode the key of the record at `pos`, using `key` as previous key and replacing it with the key of the record.
+    /// Return the extra bits stored along with the key and the position of the record value.
+    pub fn decode_key(&self, pos: usize, key: &mut Vec<u8>) -> Result<(u8, usize), Error> {
+        let data = &self.data[..self.restarts_start];
+        let (prefix_len, pos) = varint(data, pos)?;
+        let (suffix_len_and_extra, pos) = varint(data, pos)?;
+        let prefix_len = prefix_len as usize;
+        if prefix_len > key.len() {
+            return Err(corrupt("key prefix is longer than the previous key"));
+        }
+        let suffix = decode::slice(data, pos, (suffix_len_and_extra >> 3) as usize)?;
+        key.truncate(prefix_len);
+        key.extend_from_slice(suffix);
+        Ok(((suffix_len_and_extra & 0x7) as u8, pos + suffix.len()))
+    }
+
+    /// Return the key of the first record in this block.
+    pub fn first_key(&self) -> Result<Vec<u8>, Error> {
+        let mut key = Vec::new();
+        self.decode_key(self.records_start, &mut key)?;
+        Ok(key)
+    }
+
+    /// Return the position of the record to start scanning from to find `key`, which is the last restart point
+    /// with a key not greater than `key`.
+    fn restart_for(&self, key: &[u8]) -> Result<usize, Error> {
+        let (mut lo, mut hi) = (0, self.num_restarts);
+        let mut buf = Vec::new();
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            buf.clear();
+            self.decode_key(self.restart(mid), &mut buf)?;
+            if buf.as_slice() <= key {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        Ok(if lo == 0 {
+            self.records_start
+        } else {
+            self.restart(lo - 1)
+        })
+    }
+}
+
+/// A position within the blocks of one kind in a table, able to step through their records.
+pub(crate) struct Cursor {
+    kind: u8,
+    block: Option<Block>,
+    pos: usize,
+    key: Vec<u8>,
+}
+
+/// A record as seen by a [`Cursor`], with the value still to be decoded according to the kind of the block.
+pub(crate) struct Record<'a> {
+    pub key: &'a [u8],
+    pub extra: u8,
+    pub block: &'a [u8],
+    pub value_pos: usize,
+    pub hash_len: usize,
+}
+
+impl Cursor {
+    /// Create a cursor which doesn't yield any record.
+    pub fn empty(kind: u8) -> Self {
+        Cursor {
+            kind,
+            block: None,
+            pos: 0,
+            key: Vec::new(),
+        }
+    }
+
+    /// Position a cursor of records of `kind` in `table` on the first record with a key equal to or greater than `key`,
+    /// starting the search at the blocks at `offset`, which may be the blocks of the index of the records.
+    pub fn seek(table: &Table, kind: u8, offset: usize, key: &[u8]) -> Result<Self, Error> {
+        let mut cursor = Cursor::empty(kind);
+        let Some(mut block) = Block::at(table, offset)?.filter(|b| b.kind == kind || b.kind == kind::INDEX) else {
+            return Ok(cursor);
+        };
+        // Skip all blocks whose successor starts with a key that isn't greater than the one we are looking for.
+        let level_kind = block.kind;
+        while let Some(next) = Block::at(table, block.next_offset)?.filter(|b| b.kind == level_kind) {
+            if next.first_key()?.as_slice() > key {
+                break;
+            }
+            block = next;
+        }
+        // Traverse the index down to the block that may contain the key.
+        while block.kind != kind {
+            let mut index = Cursor {
+                kind: kind::INDEX,
+                pos: block.restart_for(key)?,
+                block: Some(block),
+                key: Vec::new(),
+            };
+            let child = loop {
+                match index.next(table)? {
+                    Some(record) if record.key >= key => break varint(record.block, record.value_pos)?.0 as usize,
+                    Some(_) => {}
+                    None => return Ok(cursor),
+                }
+            };
+            block = Block::at(table, child)?
+                .filter(|b| b.kind == kind || b.kind == kind::INDEX)
+                .ok_or_else(|| corrupt("index points to a block of unexpected kind"))?;
+        }
+
+        cursor.pos = block.restart_for(key)?;
+        cursor.block = Some(block);
+        while cursor.ensure_record(table)? {
+            let block = cursor.block.as_ref().expect("ensured");
+            let mut next_key = cursor.key.clone();
+            let (extra, value_pos) = block.decode_key(cursor.pos, &mut next_key)?;
+            if next_key.as_slice() >= key {
+                break;
+            }
+            cursor.pos = record_end(block, kind, extra, value_pos)?;
+            cursor.key = next_key;
+        }
+        Ok(cursor)
+    }
+
+    /// Position a cursor at the very first record of `kind` in `table` at `offset`.
+    pub fn at(table: &Table, kind: u8, offset: usize) -> Result<Self, Error> {
+        let block = Block::at(table, offset)?.filter(|b| b.kind == kind);
+        Ok(Cursor {
+            kind,
+            pos: block.as_ref().map_or(0, |b| b.records_start),
+            block,
+            key: Vec::new(),
+        })
+    }
+
+    /// Move on to the next block of the same kind if all records of the current one were consumed,
+    /// and return `true` if there is a record to read.
+    fn ensure_record(&mut self, table: &Table) -> Result<bool, Error> {
+        loop {
+            let Some(block) = self.block.as_ref() else {
+                return Ok(false);
+            };
+            if self.pos < block.restarts_start {
+                return Ok(true);
+            }
+            self.block = Block::at(table, block.next_offset)?.filter(|b| b.kind == self.kind);
+            self.pos = self.block.as_ref().map_or(0, |b| b.records_start);
+            self.key.clear();
+        }
+    }
+
+    /// Return the next record, moving on to the following block of the same kind as needed.
+    pub fn next(&mut self, table: &Table) -> Result<Option<Record<'_>>, Error> {
+        if !self.ensure_record(table)? {
+            return Ok(None);
+        }
+        let block = self.block.as_ref().expect("ensured");
+        let (extra, value_pos) = block.decode_key(self.pos, &mut self.key)?;
+        self.pos = record_end(block, self.kind, extra, value_pos)?;
+        Ok(Some(Record {
+            key: &self.key,
+            extra,
+            block: &block.data,
+            value_pos,
+            hash_len: block.hash_len,
+        }))
+    }
+}
+
+/// Return the position past the value of a record of `kind` with `extra` bits, whose value starts at `pos` in `block`.
+fn record_end(block: &Block, kind: u8, extra: u8, pos: usize) -> Result<usize, Error> {
+    let data = &block.data[..block.restarts_start];
+    Ok(match kind {
+        kind::REF => {
+            let (_update_index_delta, pos) = varint(data, pos)?;
+            let hash_len = block.hash_len;
+            match extra {
+                0 => pos,
+                1 => pos + hash_len,
+                2 => pos + 2 * hash_len,
+                3 => {
+                    let (len, pos) = varint(data, pos)?;
+                    pos + len as usize
+                }
+                _ => return Err(corrupt("unknown reference value type")),
+            }
+        }
+        kind::LOG => match extra {
+            0 => pos,
+            1 => {
+                let hash_len = block.hash_len;
+                let mut pos = pos + 2 * hash_len;
+                for _name_and_email in 0..2 {
+                    let (len, next) = varint(data, pos)?;
+                    pos = next + len as usize;
+                }
+                let (_time, pos) = varint(data, pos)?;
+                let pos = pos + 2;
+                let (len, pos) = varint(data, pos)?;
+                pos + len as usize
+            }
+            _ => return Err(corrupt("unknown log value type")),
+        },
+        kind::INDEX => varint(data, pos)?.1,
+        _ => return Err(corrupt("unknown block type")),
+    })
+    .and_then(|end| {
+        if end > data.len() {
+            Err(corrupt("record extends past the end of its block"))
+        } else {
+            Ok(end)
+        }
+    })
+}
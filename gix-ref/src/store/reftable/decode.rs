@@ -0,0 +1,54 @@
+/// The error returned when reading reftables or their records.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The reftable is corrupt: {message}")]
+    Corrupt { message: &'static str },
+    #[error("Reftable version {version} is not supported")]
+    UnsupportedVersion { version: u8 },
+    #[error("Reftable hash id {hash_id:#x} is not supported")]
+    UnsupportedHash { hash_id: u32 },
+    #[error("The footer checksum {actual:#x} didn't match the expected one, {expected:#x}")]
+    Checksum { actual: u32, expected: u32 },
+    #[error("A log block could not be decompressed")]
+    Inflate(#[from] gix_features::zlib::inflate::Error),
+    #[error("A record contained an invalid reference name")]
+    RefName(#[from] crate::name::Error),
+}
+
+pub(crate) fn corrupt(message: &'static str) -> Error {
+    Error::Corrupt { message }
+}
+
+/// Decode a variable-length integer from `data` at `pos`, returning it along with the position past it.
+///
+/// Note that this is the same encoding as used for offsets in packs, which is more compact than `LEB128`.
+pub(crate) fn varint(data: &[u8], mut pos: usize) -> Result<(u64, usize), Error> {
+    let mut byte = *data.get(pos).ok_or_else(|| corrupt("varint out of bounds"))?;
+    pos += 1;
+    let mut value = u64::from(byte & 0x7f);
+    while byte & 0x80 != 0 {
+        byte = *data.get(pos).ok_or_else(|| corrupt("varint out of bounds"))?;
+        pos += 1;
+        value = value
+            .checked_add(1)
+            .and_then(|v| v.checked_mul(128))
+            .ok_or_else(|| corrupt("varint overflow"))?
+            | u64::from(byte & 0x7f);
+    }
+    Ok((value, pos))
+}
+
+pub(crate) fn be24(data: &[u8]) -> u32 {
+    u32::from(data[0]) << 16 | u32::from(data[1]) << 8 | u32::from(data[2])
+}
+
+pub(crate) fn be64(data: &[u8]) -> u64 {
+    u64::from_be_bytes(data[..8].try_into().expect("8 bytes"))
+}
+
+pub(crate) fn slice(data: &[u8], pos: usize, len: usize) -> Result<&[u8], Error> {
+    pos.checked_add(len)
+        .and_then(|end| data.get(pos..end))
+        .ok_or_else(|| corrupt("record extends past the end of its block"))
+}
@@ -0,0 +1,110 @@
+//! Support for [reftables](https://git-scm.com/docs/reftable), a binary format to store references and their logs.
+//!
+//! A reftable is a sorted, immutable file made of blocks of prefix-compressed records, optionally followed by
+//! indices to find the right block quickly. Log records are stored in their own, zlib-compressed blocks.
+//!
+//! A repository using the reftable backend keeps a [`Stack`] of such tables in `$GIT_DIR/reftable/`, as listed
+//! in its `tables.list` file. Newer tables shadow the records of older ones, and deletions are recorded as tombstones.
+//! Each modification adds a new table to the top of the stack, which is compacted to keep the amount of tables
+//! logarithmic in the amount of updates.
+use gix_object::bstr::BString;
+
+use crate::FullName;
+
+mod block;
+
+///
+pub mod decode;
+
+///
+pub mod table;
+
+///
+pub mod stack;
+
+///
+pub mod write;
+
+/// A single reftable, fully loaded into memory.
+#[derive(Debug)]
+pub struct Table {
+    data: Vec<u8>,
+    version: u8,
+    block_size: u32,
+    min_update_index: u64,
+    max_update_index: u64,
+    object_hash: gix_hash::Kind,
+    footer: table::Footer,
+}
+
+/// A stack of reftables as listed in the `tables.list` file of a reftable directory, with newer tables taking precedence.
+#[derive(Debug, Clone)]
+pub struct Stack {
+    dir: std::path::PathBuf,
+    /// The file names of all tables along with the tables themselves, from oldest to newest.
+    tables: Vec<(String, gix_features::threading::OwnShared<Table>)>,
+}
+
+/// A reference record as stored in a reftable.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Ref {
+    /// The full name of the reference.
+    pub name: FullName,
+    /// The index of the update that produced this record.
+    pub update_index: u64,
+    /// The value of the reference at this update.
+    pub value: Value,
+}
+
+/// The value of a [reference record](Ref).
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub enum Value {
+    /// The reference was deleted, hiding all values recorded in older tables.
+    Deletion,
+    /// The reference points to an object.
+    Object(gix_hash::ObjectId),
+    /// The reference points to an annotated tag `target`, which ultimately points to the `peeled` object.
+    Peeled {
+        /// The object the reference points to.
+        target: gix_hash::ObjectId,
+        /// The object that `target` peels to.
+        peeled: gix_hash::ObjectId,
+    },
+    /// The reference is symbolic and points to another reference.
+    Symbolic(FullName),
+}
+
+/// A log record as stored in a reftable.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Log {
+    /// The full name of the reference this log entry belongs to.
+    pub name: FullName,
+    /// The index of the update that produced this log entry.
+    pub update_index: u64,
+    /// The log entry itself, or `None` if it was deleted, hiding entries with the same `update_index` in older tables.
+    ///
+    /// Note that messages are stored without trailing newline.
+    pub line: Option<crate::log::Line>,
+}
+
+impl Ref {
+    /// Return the key under which this record is stored.
+    pub(crate) fn key(&self) -> &[u8] {
+        self.name.as_bstr()
+    }
+}
+
+impl Log {
+    /// Return the key under which this record is stored, which sorts newer entries of the same reference first.
+    pub(crate) fn key(&self) -> BString {
+        log_key(self.name.as_bstr(), self.update_index)
+    }
+}
+
+pub(crate) fn log_key(name: &[u8], update_index: u64) -> BString {
+    let mut key = Vec::with_capacity(name.len() + 9);
+    key.extend_from_slice(name);
+    key.push(0);
+    key.extend_from_slice(&(u64::MAX - update_index).to_be_bytes());
+    key.into()
+}
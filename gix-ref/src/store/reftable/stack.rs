@@ -0,0 +1,487 @@
+use std::{
+    collections::BTreeMap,
+    hash::{BuildHasher, Hasher},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use gix_features::threading::OwnShared;
+use gix_object::bstr::{BStr, BString, ByteSlice};
+
+use crate::{
+    store_impl::reftable::{
+        block::Cursor,
+        decode,
+        table::{decode_ref, open},
+        write, Log, Ref, Stack, Table, Value,
+    },
+    FullNameRef,
+};
+
+/// The name of the file listing all tables of a stack.
+pub const TABLES_LIST: &str = "tables.list";
+
+/// Initialization
+impl Stack {
+    /// Load all tables listed in the `tables.list` file in `dir`, typically `$GIT_DIR/reftable`.
+    ///
+    /// If the list doesn't exist, the stack is empty.
+    pub fn at(dir: impl Into<PathBuf>) -> Result<Self, open::Error> {
+        Self::load(dir.into(), &[])
+    }
+
+    /// Return a new instance with the tables currently listed on disk, reusing our tables where possible,
+    /// or `None` if the list of tables didn't change.
+    pub fn reload_if_changed(&self) -> Result<Option<Self>, open::Error> {
+        let names = read_list(&self.dir)?;
+        Ok(if names.iter().eq(self.tables.iter().map(|(name, _)| name)) {
+            None
+        } else {
+            Some(Self::load(self.dir.clone(), &self.tables)?)
+        })
+    }
+
+    fn load(dir: PathBuf, known: &[(String, OwnShared<Table>)]) -> Result<Self, open::Error> {
+        // Tables may be removed by concurrent compactions right after we read the list, so try again a few times
+        // in that case, just like `git` does.
+        let mut attempts_left = 10;
+        'retry: loop {
+            let mut tables = Vec::new();
+            for name in read_list(&dir)? {
+                if let Some((_, table)) = known.iter().find(|(known, _)| *known == name) {
+                    tables.push((name, table.clone()));
+                    continue;
+                }
+                match Table::at(&dir.join(&name)) {
+                    Ok(table) => tables.push((name, OwnShared::new(table))),
+                    Err(open::Error::Io { source, .. })
+                        if source.kind() == std::io::ErrorKind::NotFound && attempts_left > 0 =>
+                    {
+                        attempts_left -= 1;
+                        std::thread::sleep(std::time::Duration::from_millis(1));
+                        continue 'retry;
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+            return Ok(Stack { dir, tables });
+        }
+    }
+}
+
+fn read_list(dir: &Path) -> Result<Vec<String>, open::Error> {
+    let list_path = dir.join(TABLES_LIST);
+    let list = match std::fs::read(&list_path) {
+        Ok(list) => list,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => {
+            return Err(open::Error::Io {
+                source: err,
+                path: list_path,
+            })
+        }
+    };
+    Ok(list
+        .lines()
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_str_lossy().into_owned())
+        .collect())
+}
+
+/// Access
+impl Stack {
+    /// Return the directory containing the tables of this stack.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Return the path to the file listing all tables of this stack.
+    pub fn tables_list_path(&self) -> PathBuf {
+        self.dir.join(TABLES_LIST)
+    }
+
+    /// Return all tables from oldest to newest, along with their file names.
+    pub fn tables(&self) -> impl DoubleEndedIterator<Item = (&str, &Table)> + ExactSizeIterator + '_ {
+        self.tables.iter().map(|(name, table)| (name.as_str(), &**table))
+    }
+
+    /// Return the largest update index of all tables, or 0 if there is no table.
+    pub fn max_update_index(&self) -> u64 {
+        self.tables.last().map_or(0, |(_, t)| t.max_update_index())
+    }
+
+    /// Return the kind of hash used by the tables in this stack, if there is at least one table.
+    pub fn object_hash(&self) -> Option<gix_hash::Kind> {
+        self.tables.last().map(|(_, t)| t.object_hash())
+    }
+}
+
+/// Lookup and iteration
+impl Stack {
+    /// Find the reference `name`, or return `None` if it doesn't exist or was deleted.
+    pub fn find_ref(&self, name: &FullNameRef) -> Result<Option<Ref>, decode::Error> {
+        for (_, table) in self.tables.iter().rev() {
+            if let Some(r) = table.find_ref(name)? {
+                return Ok((r.value != Value::Deletion).then_some(r));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return an iterator over all existing references, sorted by name, whose name starts with `prefix`
+    /// or all of them if `prefix` is empty.
+    ///
+    /// The iterator keeps the tables alive on its own.
+    pub fn refs(&self, prefix: &BStr) -> Result<Refs, decode::Error> {
+        Refs::new(std::iter::once((self, false)), prefix)
+    }
+
+    /// Return all log entries of the reference `name` from newest to oldest, with deleted entries removed.
+    pub fn logs_of(&self, name: &FullNameRef) -> Result<Vec<Log>, decode::Error> {
+        let mut logs = BTreeMap::new();
+        for (_, table) in &self.tables {
+            for log in table.logs_of(name)? {
+                let log = log?;
+                logs.insert(log.update_index, log);
+            }
+        }
+        Ok(logs.into_values().rev().filter(|log| log.line.is_some()).collect())
+    }
+
+    /// Return `true` if there is at least one log entry for the reference `name`.
+    pub fn has_logs(&self, name: &FullNameRef) -> Result<bool, decode::Error> {
+        Ok(!self.logs_of(name)?.is_empty())
+    }
+
+    /// Merge the records of the tables in `range` so that newer records replace older ones,
+    /// and drop deletions if `keep_deletions` is `false`.
+    fn merged_records(
+        &self,
+        range: std::ops::Range<usize>,
+        keep_deletions: bool,
+    ) -> Result<(Vec<Ref>, Vec<Log>), decode::Error> {
+        let mut refs = BTreeMap::new();
+        let mut logs = BTreeMap::<BString, Log>::new();
+        for (_, table) in &self.tables[range] {
+            for r in table.refs()? {
+                let r = r?;
+                refs.insert(r.name.clone(), r);
+            }
+            for log in table.logs()? {
+                let log = log?;
+                logs.insert(log.key(), log);
+            }
+        }
+        Ok((
+            refs.into_values()
+                .filter(|r| keep_deletions || r.value != Value::Deletion)
+                .collect(),
+            logs.into_values()
+                .filter(|log| keep_deletions || log.line.is_some())
+                .collect(),
+        ))
+    }
+}
+
+/// An iterator over the references of a [`Stack`], in order and with deletions removed.
+pub struct Refs {
+    /// One source per table, from newest to oldest.
+    sources: Vec<Source>,
+    prefix: Vec<u8>,
+}
+
+struct Source {
+    table: OwnShared<Table>,
+    cursor: Cursor,
+    next: Option<Ref>,
+    exhausted: bool,
+    skip_worktree_private: bool,
+}
+
+impl Refs {
+    /// Merge the references of all `stacks`, with references in earlier stacks shadowing those in later ones.
+    /// If the flag of a stack is set, references that are private to a worktree are skipped in it.
+    pub(crate) fn new<'a>(
+        stacks: impl Iterator<Item = (&'a Stack, bool)>,
+        prefix: &BStr,
+    ) -> Result<Self, decode::Error> {
+        let mut sources = Vec::new();
+        for (stack, skip_worktree_private) in stacks {
+            for (_, table) in stack.tables.iter().rev() {
+                sources.push(Source {
+                    cursor: table.ref_cursor((!prefix.is_empty()).then_some(prefix.as_bytes()))?,
+                    table: table.clone(),
+                    next: None,
+                    exhausted: false,
+                    skip_worktree_private,
+                });
+            }
+        }
+        Ok(Refs {
+            sources,
+            prefix: prefix.to_vec(),
+        })
+    }
+}
+
+impl Iterator for Refs {
+    type Item = Result<Ref, decode::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            for source in self.sources.iter_mut() {
+                while source.next.is_none() && !source.exhausted {
+                    match source.cursor.next(&source.table) {
+                        Ok(Some(record)) if record.key.starts_with(&self.prefix) => {
+                            match decode_ref(&source.table, record) {
+                                Ok(r) => {
+                                    if !(source.skip_worktree_private
+                                        && r.name.category().map_or(false, |c| c.is_worktree_private()))
+                                    {
+                                        source.next = Some(r);
+                                    }
+                                }
+                                Err(err) => {
+                                    source.exhausted = true;
+                                    return Some(Err(err));
+                                }
+                            }
+                        }
+                        Ok(_) => source.exhausted = true,
+                        Err(err) => {
+                            source.exhausted = true;
+                            return Some(Err(err));
+                        }
+                    }
+                }
+            }
+
+            let mut newest_smallest: Option<usize> = None;
+            for (idx, source) in self.sources.iter().enumerate() {
+                if let Some(r) = &source.next {
+                    if newest_smallest.map_or(true, |best| {
+                        r.name < self.sources[best].next.as_ref().expect("set").name
+                    }) {
+                        newest_smallest = Some(idx);
+                    }
+                }
+            }
+            let r = self.sources[newest_smallest?].next.take().expect("set");
+            for source in &mut self.sources {
+                if source.next.as_ref().map_or(false, |other| other.name == r.name) {
+                    source.next = None;
+                }
+            }
+            if r.value != Value::Deletion {
+                return Some(Ok(r));
+            }
+        }
+    }
+}
+
+/// Mutation
+impl Stack {
+    /// Lock the `tables.list` file in `dir` to prevent others from changing the stack, creating `dir` if needed.
+    ///
+    /// The stack should be loaded only after the lock was obtained to be sure it's up to date.
+    pub fn lock(dir: &Path, fail_mode: gix_lock::acquire::Fail) -> Result<gix_lock::File, gix_lock::acquire::Error> {
+        std::fs::create_dir_all(dir).map_err(gix_lock::acquire::Error::Io)?;
+        gix_lock::File::acquire_to_update_resource(dir.join(TABLES_LIST), fail_mode, None)
+    }
+
+    /// Add the serialized table `data` on top of this stack by writing it into a new file and committing `lock`,
+    /// which must be the lock on our `tables.list` that was obtained before this instance was loaded.
+    pub fn append(&mut self, mut lock: gix_lock::File, data: Vec<u8>) -> Result<(), append::Error> {
+        let table = Table::from_bytes(data)?;
+        if table.min_update_index() <= self.max_update_index() && !self.tables.is_empty() {
+            return Err(append::Error::UpdateIndexNotIncreasing {
+                min_update_index: table.min_update_index(),
+                max_update_index: self.max_update_index(),
+            });
+        }
+        let name = self.write_table(&table)?;
+        let mut names: Vec<_> = self.tables.iter().map(|(name, _)| name.as_str()).collect();
+        names.push(&name);
+        write_list(&mut lock, &names)?;
+        lock.commit().map_err(|err| err.error)?;
+        self.tables.push((name, OwnShared::new(table)));
+        Ok(())
+    }
+
+    /// Write `table` into a new file in our directory and return its name.
+    fn write_table(&self, table: &Table) -> std::io::Result<String> {
+        let random = std::collections::hash_map::RandomState::new().build_hasher().finish() as u32;
+        let name = format!(
+            "{:012x}-{:012x}-{random:08x}.ref",
+            table.min_update_index(),
+            table.max_update_index()
+        );
+        let mut file = gix_lock::File::acquire_to_update_resource(
+            self.dir.join(&name),
+            gix_lock::acquire::Fail::Immediately,
+            None,
+        )
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+        file.write_all(table.data())?;
+        file.commit().map_err(|err| err.error)?;
+        Ok(name)
+    }
+
+    /// Compact tables, if needed, so that each table is at least twice as big as the one above it, just like `git` does after each update.
+    /// Return `true` if tables were compacted.
+    ///
+    /// This requires obtaining a new lock on the `tables.list` with `fail_mode`, and reloads the stack beforehand to be up to date.
+    pub fn auto_compact(&mut self, fail_mode: gix_lock::acquire::Fail) -> Result<bool, compact::Error> {
+        let lock = Stack::lock(&self.dir, fail_mode)?;
+        *self = Stack::at(&self.dir)?;
+        let sizes: Vec<_> = self.tables.iter().map(|(_, t)| t.payload_len()).collect();
+        let segment = suggest_compaction_segment(&sizes, 2);
+        if segment.len() < 2 {
+            return Ok(false);
+        }
+        self.compact_segment(lock, segment)
+    }
+
+    /// Compact all tables into one, removing all deletions in the process.
+    /// Return `true` if tables were compacted.
+    ///
+    /// This requires obtaining a new lock on the `tables.list` with `fail_mode`, and reloads the stack beforehand to be up to date.
+    pub fn compact_all(&mut self, fail_mode: gix_lock::acquire::Fail) -> Result<bool, compact::Error> {
+        let lock = Stack::lock(&self.dir, fail_mode)?;
+        *self = Stack::at(&self.dir)?;
+        if self.tables.len() < 2 {
+            return Ok(false);
+        }
+        self.compact_segment(lock, 0..self.tables.len())
+    }
+
+    fn compact_segment(
+        &mut self,
+        mut lock: gix_lock::File,
+        segment: std::ops::Range<usize>,
+    ) -> Result<bool, compact::Error> {
+        // Lock the tables to compact as well to play nice with concurrent compactions by `git`, which holds the locks
+        // of the tables it compacts only.
+        let mut table_locks = Vec::new();
+        for (name, _) in &self.tables[segment.clone()] {
+            match gix_lock::Marker::acquire_to_hold_resource(
+                self.dir.join(name),
+                gix_lock::acquire::Fail::Immediately,
+                None,
+            ) {
+                Ok(marker) => table_locks.push(marker),
+                Err(_) => return Ok(false),
+            }
+        }
+
+        let keep_deletions = segment.start != 0;
+        let (refs, logs) = self.merged_records(segment.clone(), keep_deletions)?;
+        let (first, last) = (&self.tables[segment.start].1, &self.tables[segment.end - 1].1);
+        let mut writer = write::Writer::new(
+            first.min_update_index(),
+            last.max_update_index(),
+            write::Options {
+                block_size: last.block_size(),
+                object_hash: last.object_hash(),
+                ..Default::default()
+            },
+        );
+        for r in refs {
+            writer.add_ref(r);
+        }
+        for log in logs {
+            writer.add_log(log);
+        }
+        let table = Table::from_bytes(writer.finish()?)?;
+        let name = self.write_table(&table)?;
+
+        let mut tables = self.tables[..segment.start].to_vec();
+        tables.push((name, OwnShared::new(table)));
+        tables.extend_from_slice(&self.tables[segment.end..]);
+        let names: Vec<_> = tables.iter().map(|(name, _)| name.as_str()).collect();
+        write_list(&mut lock, &names)?;
+        lock.commit().map_err(|err| err.error)?;
+
+        let removed = std::mem::replace(&mut self.tables, tables);
+        for (name, _) in &removed[segment] {
+            std::fs::remove_file(self.dir.join(name)).ok();
+        }
+        drop(table_locks);
+        Ok(true)
+    }
+}
+
+fn write_list(lock: &mut gix_lock::File, names: &[&str]) -> std::io::Result<()> {
+    for name in names {
+        writeln!(lock, "{name}")?;
+    }
+    Ok(())
+}
+
+/// Return the range of tables, identified by their `sizes` from oldest to newest, to compact to restore a geometric
+/// sequence of table sizes with the given `factor`, just like `git` does.
+pub(crate) fn suggest_compaction_segment(sizes: &[u64], factor: u64) -> std::ops::Range<usize> {
+    let mut segment = 0..0;
+    if sizes.len() <= 1 {
+        return segment;
+    }
+    // Find the newest table that is too big compared to the one below it, which is the end of the segment.
+    let mut idx = sizes.len() - 1;
+    let mut bytes = 0;
+    while idx > 0 {
+        if sizes[idx - 1] < sizes[idx] * factor {
+            segment.end = idx + 1;
+            bytes = sizes[idx];
+            break;
+        }
+        idx -= 1;
+    }
+    // Extend the segment downwards for as long as older tables are too small compared to all tables merged so far.
+    while idx > 0 {
+        let current = bytes;
+        bytes += sizes[idx - 1];
+        if sizes[idx - 1] < current * factor {
+            segment.start = idx - 1;
+        }
+        idx -= 1;
+    }
+    segment
+}
+
+///
+pub mod append {
+    /// The error returned by [`Stack::append()`](crate::reftable::Stack::append()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The table to add could not be decoded")]
+        Decode(#[from] crate::reftable::decode::Error),
+        #[error("The smallest update index of the new table, {min_update_index}, must be larger than the largest one of the stack, {max_update_index}")]
+        UpdateIndexNotIncreasing {
+            min_update_index: u64,
+            max_update_index: u64,
+        },
+        #[error("The table or the list of tables could not be written")]
+        Io(#[from] std::io::Error),
+    }
+}
+
+///
+pub mod compact {
+    /// The error returned by [`Stack::auto_compact()`](crate::reftable::Stack::auto_compact()) and
+    /// [`Stack::compact_all()`](crate::reftable::Stack::compact_all()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The list of tables could not be locked")]
+        Lock(#[from] gix_lock::acquire::Error),
+        #[error("The stack could not be reloaded")]
+        Open(#[from] crate::reftable::table::open::Error),
+        #[error("The tables to compact could not be read")]
+        Decode(#[from] crate::reftable::decode::Error),
+        #[error("The compacted table could not be written")]
+        Write(#[from] crate::reftable::write::Error),
+        #[error("The compacted table or the list of tables could not be written")]
+        Io(#[from] std::io::Error),
+    }
+}
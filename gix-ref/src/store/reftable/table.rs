@@ -0,0 +1,394 @@
+use std::path::Path;
+
+use gix_hash::ObjectId;
+use gix_object::bstr::BStr;
+
+use crate::{
+    store_impl::reftable::{
+        block::{kind, Cursor, Record},
+        decode::{self, be24, be64, corrupt, varint, Error},
+        Log, Ref, Table, Value,
+    },
+    FullName, FullNameRef,
+};
+
+pub(crate) const MAGIC: &[u8] = b"REFT";
+pub(crate) const SHA1_ID: u32 = u32::from_be_bytes(*b"sha1");
+pub(crate) const SHA256_ID: u32 = u32::from_be_bytes(*b"s256");
+
+/// Return the length of the header of a table of `version`.
+pub(crate) fn header_len(version: u8) -> usize {
+    if version == 1 {
+        24
+    } else {
+        28
+    }
+}
+
+/// Return the length of the footer of a table of `version`.
+pub(crate) fn footer_len(version: u8) -> usize {
+    header_len(version) + 5 * 8 + 4
+}
+
+/// The offsets of the sections of a table, as stored in its footer.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Footer {
+    pub ref_index: usize,
+    pub log: usize,
+    pub log_index: usize,
+}
+
+/// Initialization
+impl Table {
+    /// Read the table at `path` into memory.
+    pub fn at(path: &Path) -> Result<Self, open::Error> {
+        let data = std::fs::read(path).map_err(|err| open::Error::Io {
+            source: err,
+            path: path.to_owned(),
+        })?;
+        Table::from_bytes(data).map_err(|err| open::Error::Decode {
+            source: err,
+            path: path.to_owned(),
+        })
+    }
+
+    /// Create a table from its serialized `data`.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, Error> {
+        if data.len() < 5 || &data[..4] != MAGIC {
+            return Err(corrupt("the header signature is missing"));
+        }
+        let version = data[4];
+        if version != 1 && version != 2 {
+            return Err(Error::UnsupportedVersion { version });
+        }
+        let (header_len, footer_len) = (header_len(version), footer_len(version));
+        if data.len() < header_len + footer_len {
+            return Err(corrupt("the table is too short to hold header and footer"));
+        }
+        let footer = &data[data.len() - footer_len..];
+        if footer[..header_len] != data[..header_len] {
+            return Err(corrupt("the header in the footer doesn't match the one of the file"));
+        }
+        let (checksummed, checksum) = footer.split_at(footer_len - 4);
+        let expected = u32::from_be_bytes(checksum.try_into().expect("4 bytes"));
+        let actual = gix_features::hash::crc32(checksummed);
+        if actual != expected {
+            return Err(Error::Checksum { actual, expected });
+        }
+
+        let object_hash = if version == 1 {
+            gix_hash::Kind::Sha1
+        } else {
+            match u32::from_be_bytes(data[24..28].try_into().expect("4 bytes")) {
+                SHA1_ID => gix_hash::Kind::Sha1,
+                SHA256_ID => gix_hash::Kind::Sha256,
+                hash_id => return Err(Error::UnsupportedHash { hash_id }),
+            }
+        };
+        let offsets = &footer[header_len..];
+        let offset = |idx: usize| -> Result<usize, Error> {
+            usize::try_from(be64(&offsets[idx * 8..]))
+                .ok()
+                .filter(|offset| *offset < data.len() - footer_len)
+                .map_or_else(|| Err(corrupt("section offset is out of bounds")), Ok)
+        };
+        let footer = Footer {
+            ref_index: offset(0)?,
+            log: offset(3)?,
+            log_index: offset(4)?,
+        };
+        Ok(Table {
+            version,
+            block_size: be24(&data[5..8]),
+            min_update_index: be64(&data[8..]),
+            max_update_index: be64(&data[16..]),
+            object_hash,
+            footer,
+            data,
+        })
+    }
+}
+
+/// Access
+impl Table {
+    /// Return the smallest update index of the records in this table.
+    pub fn min_update_index(&self) -> u64 {
+        self.min_update_index
+    }
+
+    /// Return the largest update index of the records in this table.
+    pub fn max_update_index(&self) -> u64 {
+        self.max_update_index
+    }
+
+    /// Return the kind of hash used for object ids in this table.
+    pub fn object_hash(&self) -> gix_hash::Kind {
+        self.object_hash
+    }
+
+    /// Return the size to which blocks are padded.
+    pub fn block_size(&self) -> u32 {
+        self.block_size
+    }
+
+    /// Return the format version of this table.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Return the serialized table.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub(crate) fn header_len(&self) -> usize {
+        header_len(self.version)
+    }
+
+    pub(crate) fn footer_offset(&self) -> usize {
+        self.data.len() - footer_len(self.version)
+    }
+
+    /// Return the size of all blocks in the table, which is used to decide on compaction.
+    pub(crate) fn payload_len(&self) -> u64 {
+        (self.data.len() - self.header_len() - footer_len(self.version)) as u64
+    }
+
+    fn first_block_kind(&self) -> Option<u8> {
+        let pos = self.header_len();
+        (pos < self.footer_offset()).then(|| self.data[pos])
+    }
+
+    fn log_offset(&self) -> Option<usize> {
+        (self.footer.log != 0 || self.first_block_kind() == Some(kind::LOG)).then_some(self.footer.log)
+    }
+
+    pub(crate) fn ref_cursor(&self, key: Option<&[u8]>) -> Result<Cursor, Error> {
+        if self.first_block_kind() != Some(kind::REF) {
+            return Ok(Cursor::empty(kind::REF));
+        }
+        match key {
+            Some(key) => Cursor::seek(self, kind::REF, self.footer.ref_index, key),
+            None => Cursor::at(self, kind::REF, 0),
+        }
+    }
+
+    pub(crate) fn log_cursor(&self, key: Option<&[u8]>) -> Result<Cursor, Error> {
+        let Some(log_offset) = self.log_offset() else {
+            return Ok(Cursor::empty(kind::LOG));
+        };
+        match key {
+            Some(key) => {
+                let start = if self.footer.log_index != 0 {
+                    self.footer.log_index
+                } else {
+                    log_offset
+                };
+                Cursor::seek(self, kind::LOG, start, key)
+            }
+            None => Cursor::at(self, kind::LOG, log_offset),
+        }
+    }
+}
+
+/// Lookup and iteration
+impl Table {
+    /// Find the record of the reference `name`, which may be a [deletion](Value::Deletion).
+    pub fn find_ref(&self, name: &FullNameRef) -> Result<Option<Ref>, Error> {
+        let mut cursor = self.ref_cursor(Some(name.as_bstr()))?;
+        match cursor.next(self)? {
+            Some(record) if record.key == name.as_bstr() => decode_ref(self, record).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Return an iterator over all reference records, including deletions, sorted by name.
+    pub fn refs(&self) -> Result<Refs<'_>, Error> {
+        Ok(Refs {
+            table: self,
+            cursor: self.ref_cursor(None)?,
+            prefix: Vec::new(),
+        })
+    }
+
+    /// Return an iterator over all reference records whose name starts with `prefix`, including deletions, sorted by name.
+    pub fn refs_prefixed(&self, prefix: &BStr) -> Result<Refs<'_>, Error> {
+        Ok(Refs {
+            table: self,
+            cursor: self.ref_cursor(Some(prefix))?,
+            prefix: prefix.to_vec(),
+        })
+    }
+
+    /// Return an iterator over all log records, including deletions, sorted by reference name and from newest to oldest.
+    pub fn logs(&self) -> Result<Logs<'_>, Error> {
+        Ok(Logs {
+            table: self,
+            cursor: self.log_cursor(None)?,
+            prefix: Vec::new(),
+        })
+    }
+
+    /// Return an iterator over all log records of the reference `name`, including deletions, from newest to oldest.
+    pub fn logs_of(&self, name: &FullNameRef) -> Result<Logs<'_>, Error> {
+        let mut prefix = name.as_bstr().to_vec();
+        prefix.push(0);
+        Ok(Logs {
+            table: self,
+            cursor: self.log_cursor(Some(&prefix))?,
+            prefix,
+        })
+    }
+}
+
+/// An iterator over the reference records of a [`Table`].
+pub struct Refs<'a> {
+    table: &'a Table,
+    cursor: Cursor,
+    prefix: Vec<u8>,
+}
+
+impl Iterator for Refs<'_> {
+    type Item = Result<Ref, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.cursor.next(self.table) {
+            Ok(Some(record)) if record.key.starts_with(&self.prefix) => Some(decode_ref(self.table, record)),
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+/// An iterator over the log records of a [`Table`].
+pub struct Logs<'a> {
+    table: &'a Table,
+    cursor: Cursor,
+    prefix: Vec<u8>,
+}
+
+impl Iterator for Logs<'_> {
+    type Item = Result<Log, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.cursor.next(self.table) {
+            Ok(Some(record)) if record.key.starts_with(&self.prefix) => Some(decode_log(record)),
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+fn full_name(name: &[u8]) -> Result<FullName, Error> {
+    FullName::try_from(BStr::new(name)).map_err(Error::RefName)
+}
+
+fn object_id(data: &[u8], pos: usize, hash_len: usize) -> Result<(ObjectId, usize), Error> {
+    let bytes = decode::slice(data, pos, hash_len)?;
+    Ok((ObjectId::from_bytes_or_panic(bytes), pos + hash_len))
+}
+
+fn length_prefixed(data: &[u8], pos: usize) -> Result<(&[u8], usize), Error> {
+    let (len, pos) = varint(data, pos)?;
+    let bytes = decode::slice(data, pos, len as usize)?;
+    Ok((bytes, pos + bytes.len()))
+}
+
+pub(crate) fn decode_ref(table: &Table, record: Record<'_>) -> Result<Ref, Error> {
+    let Record {
+        key,
+        extra,
+        block: data,
+        value_pos: pos,
+        hash_len,
+    } = record;
+    let (update_index_delta, pos) = varint(data, pos)?;
+    let value = match extra {
+        0 => Value::Deletion,
+        1 => Value::Object(object_id(data, pos, hash_len)?.0),
+        2 => {
+            let (target, pos) = object_id(data, pos, hash_len)?;
+            Value::Peeled {
+                target,
+                peeled: object_id(data, pos, hash_len)?.0,
+            }
+        }
+        3 => Value::Symbolic(full_name(length_prefixed(data, pos)?.0)?),
+        _ => return Err(corrupt("unknown reference value type")),
+    };
+    Ok(Ref {
+        name: full_name(key)?,
+        update_index: table.min_update_index + update_index_delta,
+        value,
+    })
+}
+
+pub(crate) fn decode_log(record: Record<'_>) -> Result<Log, Error> {
+    let Record {
+        key,
+        extra,
+        block: data,
+        value_pos: pos,
+        hash_len,
+    } = record;
+    if key.len() < 9 || key[key.len() - 9] != 0 {
+        return Err(corrupt("log key is malformed"));
+    }
+    let (name, update_index) = key.split_at(key.len() - 9);
+    let update_index = u64::MAX - be64(&update_index[1..]);
+    let line = match extra {
+        0 => None,
+        1 => {
+            let (previous_oid, pos) = object_id(data, pos, hash_len)?;
+            let (new_oid, pos) = object_id(data, pos, hash_len)?;
+            let (name, pos) = length_prefixed(data, pos)?;
+            let (email, pos) = length_prefixed(data, pos)?;
+            let (seconds, pos) = varint(data, pos)?;
+            let offset_in_minutes = i16::from_be_bytes(decode::slice(data, pos, 2)?.try_into().expect("2 bytes"));
+            let (message, _pos) = length_prefixed(data, pos + 2)?;
+            let message = message.strip_suffix(b"\n").unwrap_or(message);
+            Some(crate::log::Line {
+                previous_oid,
+                new_oid,
+                signature: gix_actor::Signature {
+                    name: name.into(),
+                    email: email.into(),
+                    time: gix_actor::date::Time {
+                        seconds: seconds as gix_actor::date::SecondsSinceUnixEpoch,
+                        offset: i32::from(offset_in_minutes) * 60,
+                        sign: if offset_in_minutes < 0 {
+                            gix_actor::date::time::Sign::Minus
+                        } else {
+                            gix_actor::date::time::Sign::Plus
+                        },
+                    },
+                },
+                message: message.into(),
+            })
+        }
+        _ => return Err(corrupt("unknown log value type")),
+    };
+    Ok(Log {
+        name: full_name(name)?,
+        update_index,
+        line,
+    })
+}
+
+///
+pub mod open {
+    use std::path::PathBuf;
+
+    /// The error returned by [`Table::at()`](crate::reftable::Table::at()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The reftable at {path:?} could not be read")]
+        Io { source: std::io::Error, path: PathBuf },
+        #[error("The reftable at {path:?} could not be decoded")]
+        Decode {
+            source: crate::reftable::decode::Error,
+            path: PathBuf,
+        },
+    }
+}
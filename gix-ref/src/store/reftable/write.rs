@@ -0,0 +1,402 @@
+use std::io::Write;
+
+use gix_hash::oid;
+
+use crate::store_impl::reftable::{
+    block::{self, kind},
+    table, Log, Ref, Value,
+};
+
+/// Options for writing reftables.
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    /// The size of blocks, to which all blocks except for log blocks are padded.
+    ///
+    /// Defaults to 4096 bytes, just like in `git`.
+    pub block_size: u32,
+    /// The amount of records after which prefix compression is reset, to allow binary searches within a block.
+    ///
+    /// Defaults to 16.
+    pub restart_interval: u16,
+    /// The kind of hash used by all object ids in the table.
+    pub object_hash: gix_hash::Kind,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            block_size: 4096,
+            restart_interval: 16,
+            object_hash: gix_hash::Kind::Sha1,
+        }
+    }
+}
+
+/// A utility to write a single reftable from reference and log records added in any order.
+#[derive(Debug, Clone)]
+pub struct Writer {
+    options: Options,
+    min_update_index: u64,
+    max_update_index: u64,
+    refs: Vec<Ref>,
+    logs: Vec<Log>,
+}
+
+impl Writer {
+    /// Create a new writer for a table whose records are all produced by updates between `min_update_index`
+    /// and `max_update_index`, inclusive, configured with `options`.
+    pub fn new(min_update_index: u64, max_update_index: u64, options: Options) -> Self {
+        Writer {
+            options,
+            min_update_index,
+            max_update_index,
+            refs: Vec::new(),
+            logs: Vec::new(),
+        }
+    }
+
+    /// Add the reference record `r`.
+    pub fn add_ref(&mut self, r: Ref) -> &mut Self {
+        self.refs.push(r);
+        self
+    }
+
+    /// Add the log record `log`.
+    ///
+    /// Unlike reference records, its update index may be older than the range of the table, which is
+    /// needed to delete log entries written by earlier updates.
+    pub fn add_log(&mut self, log: Log) -> &mut Self {
+        self.logs.push(log);
+        self
+    }
+
+    /// Return `true` if no record was added yet.
+    pub fn is_empty(&self) -> bool {
+        self.refs.is_empty() && self.logs.is_empty()
+    }
+
+    /// Produce the serialized table from all added records.
+    pub fn finish(mut self) -> Result<Vec<u8>, Error> {
+        let hash_len = self.options.object_hash.len_in_bytes();
+        let check_update_index = |name: &crate::FullName, update_index: u64| {
+            if (self.min_update_index..=self.max_update_index).contains(&update_index) {
+                Ok(())
+            } else {
+                Err(Error::UpdateIndexOutOfRange {
+                    name: name.as_bstr().to_owned(),
+                    update_index,
+                    min: self.min_update_index,
+                    max: self.max_update_index,
+                })
+            }
+        };
+        let check_hash = |name: &crate::FullName, id: &oid| {
+            if id.kind() == self.options.object_hash {
+                Ok(())
+            } else {
+                Err(Error::ObjectHashMismatch {
+                    name: name.as_bstr().to_owned(),
+                    actual: id.kind(),
+                    expected: self.options.object_hash,
+                })
+            }
+        };
+
+        self.refs.sort_by(|a, b| a.name.cmp(&b.name));
+        if let Some(dup) = self.refs.windows(2).find(|w| w[0].name == w[1].name) {
+            return Err(Error::DuplicateRef {
+                name: dup[0].name.as_bstr().to_owned(),
+            });
+        }
+        let mut refs = Vec::with_capacity(self.refs.len());
+        for r in &self.refs {
+            check_update_index(&r.name, r.update_index)?;
+            let mut value = Vec::with_capacity(1 + 2 * hash_len);
+            put_varint(&mut value, r.update_index - self.min_update_index);
+            let extra = match &r.value {
+                Value::Deletion => 0,
+                Value::Object(id) => {
+                    check_hash(&r.name, id)?;
+                    value.extend_from_slice(id.as_bytes());
+                    1
+                }
+                Value::Peeled { target, peeled } => {
+                    check_hash(&r.name, target)?;
+                    check_hash(&r.name, peeled)?;
+                    value.extend_from_slice(target.as_bytes());
+                    value.extend_from_slice(peeled.as_bytes());
+                    2
+                }
+                Value::Symbolic(target) => {
+                    put_varint(&mut value, target.as_bstr().len() as u64);
+                    value.extend_from_slice(target.as_bstr());
+                    3
+                }
+            };
+            refs.push((r.key().to_vec(), extra, value));
+        }
+
+        let mut logs = Vec::with_capacity(self.logs.len());
+        for log in &self.logs {
+            let mut value = Vec::new();
+            let extra = match &log.line {
+                None => 0,
+                Some(line) => {
+                    check_hash(&log.name, &line.previous_oid)?;
+                    check_hash(&log.name, &line.new_oid)?;
+                    if line.message.contains(&b'\n') {
+                        return Err(Error::MessageWithNewlines {
+                            name: log.name.as_bstr().to_owned(),
+                        });
+                    }
+                    value.extend_from_slice(line.previous_oid.as_bytes());
+                    value.extend_from_slice(line.new_oid.as_bytes());
+                    for field in [&line.signature.name, &line.signature.email] {
+                        put_varint(&mut value, field.len() as u64);
+                        value.extend_from_slice(field);
+                    }
+                    let time = &line.signature.time;
+                    put_varint(&mut value, time.seconds.max(0) as u64);
+                    value.extend_from_slice(&((time.offset / 60) as i16).to_be_bytes());
+                    put_varint(&mut value, line.message.len() as u64 + 1);
+                    value.extend_from_slice(&line.message);
+                    value.push(b'\n');
+                    1
+                }
+            };
+            logs.push((Vec::from(log.key()), extra, value));
+        }
+        logs.sort_by(|a, b| a.0.cmp(&b.0));
+        if let Some(dup) = logs.windows(2).find(|w| w[0].0 == w[1].0) {
+            return Err(Error::DuplicateLog {
+                key: dup[0].0.clone().into(),
+            });
+        }
+
+        let header = self.header();
+        let mut out = Vec::new();
+        let ref_index = self.write_section(&mut out, &header, kind::REF, refs)?;
+        let log_offset = out.len();
+        let log_index = self.write_section(&mut out, &header, kind::LOG, logs)?;
+        let has_logs = out.len() != log_offset;
+        if out.is_empty() {
+            out.extend_from_slice(&header);
+        }
+
+        let footer_start = out.len();
+        out.extend_from_slice(&header);
+        for offset in [
+            ref_index as u64,
+            0, /* object section offset and the length of object id prefixes */
+            0, /* object index offset */
+            if has_logs { log_offset as u64 } else { 0 },
+            log_index as u64,
+        ] {
+            out.extend_from_slice(&offset.to_be_bytes());
+        }
+        let crc = gix_features::hash::crc32(&out[footer_start..]);
+        out.extend_from_slice(&crc.to_be_bytes());
+        Ok(out)
+    }
+
+    fn header(&self) -> Vec<u8> {
+        // Like git, only use version 2 if the hash kind can't be implied.
+        let version = match self.options.object_hash {
+            gix_hash::Kind::Sha1 => 1,
+            gix_hash::Kind::Sha256 => 2,
+        };
+        let mut header = Vec::with_capacity(table::header_len(version));
+        header.extend_from_slice(table::MAGIC);
+        header.push(version);
+        header.extend_from_slice(&self.options.block_size.to_be_bytes()[1..]);
+        header.extend_from_slice(&self.min_update_index.to_be_bytes());
+        header.extend_from_slice(&self.max_update_index.to_be_bytes());
+        if version == 2 {
+            header.extend_from_slice(&table::SHA256_ID.to_be_bytes());
+        }
+        header
+    }
+
+    /// Write all `records` into blocks of `kind`, followed by an index if there are enough blocks, and return the offset
+    /// of the index or 0 if there is none.
+    fn write_section(
+        &self,
+        out: &mut Vec<u8>,
+        header: &[u8],
+        kind: u8,
+        records: Vec<(Vec<u8>, u8, Vec<u8>)>,
+    ) -> Result<usize, Error> {
+        let mut index = self.write_blocks(out, header, kind, records.into_iter())?;
+        // Like git, only write an index if it saves reading a few blocks.
+        let mut index_offset = 0;
+        while index.len() > 3 {
+            index_offset = out.len();
+            let records = index.into_iter().map(|(last_key, offset)| {
+                let mut value = Vec::new();
+                put_varint(&mut value, offset as u64);
+                (last_key, 0, value)
+            });
+            index = self.write_blocks(out, header, kind::INDEX, records)?;
+        }
+        Ok(index_offset)
+    }
+
+    /// Write `records` into as many blocks of `kind` as needed and return the last key and offset of each block.
+    fn write_blocks(
+        &self,
+        out: &mut Vec<u8>,
+        header: &[u8],
+        kind: u8,
+        records: impl Iterator<Item = (Vec<u8>, u8, Vec<u8>)>,
+    ) -> Result<Vec<(Vec<u8>, usize)>, Error> {
+        let mut blocks = Vec::new();
+        let mut block: Option<BlockWriter> = None;
+        for (key, extra, value) in records {
+            let current = block.get_or_insert_with(|| BlockWriter::new(kind, out.is_empty().then_some(header)));
+            if !current.add(&key, extra, &value, self.options) {
+                blocks.push(block.take().expect("present").finish(out, self.options)?);
+                let current = block.insert(BlockWriter::new(kind, None));
+                if !current.add(&key, extra, &value, self.options) {
+                    return Err(Error::RecordTooLarge {
+                        key: key.into(),
+                        block_size: self.options.block_size,
+                    });
+                }
+            }
+        }
+        if let Some(block) = block {
+            blocks.push(block.finish(out, self.options)?);
+        }
+        Ok(blocks)
+    }
+}
+
+/// A block that is being filled with records.
+struct BlockWriter {
+    kind: u8,
+    buf: Vec<u8>,
+    header_len: usize,
+    restarts: Vec<usize>,
+    num_records: usize,
+    last_key: Vec<u8>,
+}
+
+impl BlockWriter {
+    fn new(kind: u8, file_header: Option<&[u8]>) -> Self {
+        let mut buf = file_header.map(ToOwned::to_owned).unwrap_or_default();
+        let header_len = buf.len();
+        buf.extend_from_slice(&[kind, 0, 0, 0]);
+        BlockWriter {
+            kind,
+            buf,
+            header_len,
+            restarts: Vec::new(),
+            num_records: 0,
+            last_key: Vec::new(),
+        }
+    }
+
+    /// Add the given record and return `true`, or return `false` if it doesn't fit into the block.
+    fn add(&mut self, key: &[u8], extra: u8, value: &[u8], options: Options) -> bool {
+        let is_restart = self.num_records % usize::from(options.restart_interval.max(1)) == 0;
+        let prefix_len = if is_restart {
+            0
+        } else {
+            self.last_key.iter().zip(key).take_while(|(a, b)| a == b).count()
+        };
+        let suffix = &key[prefix_len..];
+        let mut record = Vec::with_capacity(suffix.len() + value.len() + 4);
+        put_varint(&mut record, prefix_len as u64);
+        put_varint(&mut record, (suffix.len() as u64) << 3 | u64::from(extra));
+        record.extend_from_slice(suffix);
+        record.extend_from_slice(value);
+
+        let num_restarts = self.restarts.len() + usize::from(is_restart);
+        if self.buf.len() + record.len() + 3 * num_restarts + 2 > options.block_size as usize
+            || num_restarts > usize::from(u16::MAX)
+        {
+            return false;
+        }
+        if is_restart {
+            self.restarts.push(self.buf.len());
+        }
+        self.buf.extend_from_slice(&record);
+        self.num_records += 1;
+        self.last_key.clear();
+        self.last_key.extend_from_slice(key);
+        true
+    }
+
+    /// Write this block into `out` and return its last key along with its offset.
+    fn finish(mut self, out: &mut Vec<u8>, options: Options) -> Result<(Vec<u8>, usize), Error> {
+        for restart in &self.restarts {
+            self.buf.extend_from_slice(&(*restart as u32).to_be_bytes()[1..]);
+        }
+        self.buf.extend_from_slice(&(self.restarts.len() as u16).to_be_bytes());
+        let len = self.buf.len() as u32;
+        self.buf[self.header_len + 1..][..3].copy_from_slice(&len.to_be_bytes()[1..]);
+
+        let offset = if self.header_len == 0 { out.len() } else { 0 };
+        let records_start = self.header_len + block::HEADER_LEN;
+        if self.kind == kind::LOG {
+            out.extend_from_slice(&self.buf[..records_start]);
+            let mut deflate = gix_features::zlib::stream::deflate::Write::new(&mut *out);
+            deflate.write_all(&self.buf[records_start..])?;
+            deflate.flush()?;
+        } else {
+            out.extend_from_slice(&self.buf);
+            let padding = (options.block_size as usize).saturating_sub(self.buf.len());
+            out.resize(out.len() + padding, 0);
+        }
+        Ok((self.last_key, offset))
+    }
+}
+
+/// Append `value` to `out` as variable-length integer.
+pub(crate) fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    let mut buf = [0u8; 10];
+    let mut pos = buf.len() - 1;
+    buf[pos] = (value & 0x7f) as u8;
+    value >>= 7;
+    while value != 0 {
+        value -= 1;
+        pos -= 1;
+        buf[pos] = 0x80 | (value & 0x7f) as u8;
+        value >>= 7;
+    }
+    out.extend_from_slice(&buf[pos..]);
+}
+
+mod error {
+    use gix_object::bstr::BString;
+
+    /// The error returned by [`Writer::finish()`](super::Writer::finish()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The update index {update_index} of a record of {name:?} is not between {min} and {max}")]
+        UpdateIndexOutOfRange {
+            name: BString,
+            update_index: u64,
+            min: u64,
+            max: u64,
+        },
+        #[error("The reference {name:?} was added more than once")]
+        DuplicateRef { name: BString },
+        #[error("A log record with key {key:?} was added more than once")]
+        DuplicateLog { key: BString },
+        #[error("An object id of {name:?} was {actual}, but the table is written with {expected}")]
+        ObjectHashMismatch {
+            name: BString,
+            actual: gix_hash::Kind,
+            expected: gix_hash::Kind,
+        },
+        #[error("The log message of {name:?} must not contain newlines")]
+        MessageWithNewlines { name: BString },
+        #[error("The record with key {key:?} doesn't fit into a block of {block_size} bytes")]
+        RecordTooLarge { key: BString, block_size: u32 },
+        #[error("A log block could not be compressed")]
+        Io(#[from] std::io::Error),
+    }
+}
+pub use error::Error;
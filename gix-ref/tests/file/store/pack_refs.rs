@@ -0,0 +1,145 @@
+use gix_lock::acquire::Fail;
+use gix_ref::{
+    file::pack_refs::{Options, Outcome},
+    Target,
+};
+
+use crate::file::store_writable;
+
+fn store_and_odb() -> crate::Result<(gix_testtools::tempfile::TempDir, gix_ref::file::Store, gix_odb::Handle)> {
+    let (dir, store) = store_writable("make_ref_repository.sh")?;
+    let odb = gix_odb::at(store.git_dir().join("objects"))?;
+    Ok((dir, store, odb))
+}
+
+fn options(all: bool, prune: bool) -> Options {
+    Options {
+        all,
+        prune,
+        lock_mode: Fail::Immediately,
+        packed_refs_lock_mode: Fail::Immediately,
+    }
+}
+
+fn refs(store: &gix_ref::file::Store) -> crate::Result<Vec<(String, Target)>> {
+    Ok(store
+        .iter()?
+        .all()?
+        .filter_map(Result::ok)
+        .map(|r| (r.name.as_bstr().to_string(), r.target))
+        .collect())
+}
+
+fn packed_names(store: &gix_ref::file::Store) -> crate::Result<Vec<String>> {
+    Ok(store
+        .open_packed_buffer()?
+        .expect("packed-refs were written")
+        .iter()?
+        .map(|r| r.map(|r| r.name.as_bstr().to_string()))
+        .collect::<Result<_, _>>()?)
+}
+
+#[test]
+fn by_default_only_tags_are_packed_and_empty_directories_are_removed() -> crate::Result {
+    let (_dir, store, odb) = store_and_odb()?;
+    let git_dir = store.git_dir().to_owned();
+    std::fs::create_dir_all(git_dir.join("refs/tags/ci/123"))?;
+    std::fs::copy(git_dir.join("refs/tags/t1"), git_dir.join("refs/tags/ci/123/build"))?;
+    let expected_refs = refs(&store)?;
+
+    let out = store.pack_refs(&odb, options(false, true))?;
+    assert_eq!(
+        out,
+        Outcome {
+            packed: 3,
+            pruned: 3,
+            skipped_broken: 1,
+            compacted_reftables: false
+        },
+        "symbolic references are never packed, and the broken reference is skipped"
+    );
+    assert_eq!(
+        packed_names(&store)?,
+        ["refs/tags/ci/123/build", "refs/tags/dt1", "refs/tags/t1"]
+    );
+    assert!(!git_dir.join("refs/tags/t1").exists(), "loose refs were pruned");
+    assert!(!git_dir.join("refs/tags/ci").exists(), "empty directories were removed");
+    assert!(git_dir.join("refs/tags").is_dir(), "but not the one of the category");
+    assert!(git_dir.join("refs/heads/main").is_file(), "branches are left alone");
+    assert_eq!(refs(&store)?, expected_refs, "no reference changed its value");
+
+    let packed = store.open_packed_buffer()?.expect("written");
+    let dt1 = packed.find("refs/tags/dt1")?;
+    assert!(dt1.object.is_some(), "annotated tags are peeled");
+    assert_eq!(
+        dt1.object(),
+        store.find("main")?.target.try_id().expect("id").to_owned(),
+        "it points to the commit at main"
+    );
+    Ok(())
+}
+
+#[test]
+fn all_references_with_prune() -> crate::Result {
+    let (_dir, store, odb) = store_and_odb()?;
+    let expected_refs = refs(&store)?;
+
+    let out = store.pack_refs(&odb, options(true, true))?;
+    assert_eq!(out.packed, 8);
+    assert_eq!(out.pruned, 8);
+    assert_eq!(
+        packed_names(&store)?,
+        [
+            "refs/d1",
+            "refs/heads/d1",
+            "refs/heads/dt1",
+            "refs/heads/main",
+            "refs/remotes/origin/main",
+            "refs/remotes/origin/multi-link-target3",
+            "refs/tags/dt1",
+            "refs/tags/t1"
+        ]
+    );
+    let git_dir = store.git_dir();
+    assert!(!git_dir.join("refs/heads/main").exists());
+    assert!(
+        git_dir.join("refs/remotes/origin/HEAD").is_file(),
+        "symbolic refs stay loose"
+    );
+    assert!(git_dir.join("HEAD").is_file(), "HEAD is never packed");
+    assert_eq!(refs(&store)?, expected_refs, "no reference changed its value");
+    Ok(())
+}
+
+#[test]
+fn all_references_without_prune_and_repacking_already_packed_refs() -> crate::Result {
+    let (_dir, store, odb) = store_and_odb()?;
+    let out = store.pack_refs(&odb, options(true, false))?;
+    assert_eq!(out.packed, 8);
+    assert_eq!(out.pruned, 0);
+    assert!(
+        store.git_dir().join("refs/heads/main").is_file(),
+        "loose references are kept"
+    );
+
+    let out = store.pack_refs(&odb, options(false, true))?;
+    assert_eq!(out.packed, 8, "references which are already packed are packed again");
+    assert_eq!(out.pruned, 8);
+    assert!(!store.git_dir().join("refs/heads/main").exists());
+    assert_eq!(
+        store.find("main")?.target.try_id(),
+        Some(store.find("d1")?.target.try_id().expect("id"))
+    );
+    Ok(())
+}
+
+#[test]
+fn locked_packed_refs_fail_without_changes() -> crate::Result {
+    let (_dir, store, odb) = store_and_odb()?;
+    std::fs::write(store.git_dir().join("packed-refs.lock"), b"")?;
+    let expected_refs = refs(&store)?;
+    assert!(store.pack_refs(&odb, options(true, true)).is_err());
+    assert!(store.git_dir().join("refs/heads/main").is_file());
+    assert_eq!(refs(&store)?, expected_refs);
+    Ok(())
+}
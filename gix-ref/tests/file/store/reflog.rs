@@ -67,3 +67,137 @@ mod iter_rev {
         Ok(())
     }
 }
+
+mod expire {
+    use gix_lock::acquire::Fail;
+    use gix_ref::file::log::expire::{Options, Outcome};
+
+    use crate::file::store_writable;
+
+    fn store_and_odb() -> crate::Result<(gix_testtools::tempfile::TempDir, gix_ref::file::Store, gix_odb::Handle)> {
+        let (dir, store) = store_writable("make_repo_for_reflog_expiry.sh")?;
+        let odb = gix_odb::at(store.git_dir().join("objects"))?;
+        Ok((dir, store, odb))
+    }
+
+    fn messages(store: &gix_ref::file::Store, name: &str) -> crate::Result<Vec<String>> {
+        let mut buf = Vec::new();
+        Ok(store
+            .reflog_iter(name, &mut buf)?
+            .expect("exists")
+            .map(|line| line.map(|line| line.message.to_string()))
+            .collect::<Result<_, _>>()?)
+    }
+
+    fn lines(store: &gix_ref::file::Store, name: &str) -> crate::Result<Vec<gix_ref::log::Line>> {
+        let mut buf = Vec::new();
+        Ok(store
+            .reflog_iter(name, &mut buf)?
+            .expect("exists")
+            .map(|line| line.map(Into::into))
+            .collect::<Result<_, _>>()?)
+    }
+
+    fn options(expire: Option<i64>, expire_unreachable: Option<i64>) -> Options {
+        Options {
+            expire,
+            expire_unreachable,
+            rewrite: false,
+            lock_mode: Fail::Immediately,
+        }
+    }
+
+    #[test]
+    fn nothing_is_pruned_if_nothing_is_old_enough() -> crate::Result {
+        let (_dir, store, odb) = store_and_odb()?;
+        let reflog = std::fs::read(store.git_dir().join("logs/refs/heads/main"))?;
+        for opts in [options(None, None), options(Some(1000000000), Some(1000000000))] {
+            let out = store.reflog_expire("refs/heads/main".try_into()?, &odb, opts)?;
+            assert_eq!(out, Outcome { kept: 4, pruned: 0 });
+        }
+        assert_eq!(
+            std::fs::read(store.git_dir().join("logs/refs/heads/main"))?,
+            reflog,
+            "the reflog wasn't touched"
+        );
+
+        let out = store.reflog_expire("refs/heads/missing".try_into()?, &odb, options(Some(i64::MAX), None))?;
+        assert_eq!(out, Outcome::default(), "a missing reflog is no error");
+        Ok(())
+    }
+
+    #[test]
+    fn by_age() -> crate::Result {
+        let (_dir, store, odb) = store_and_odb()?;
+        let out = store.reflog_expire("refs/heads/main".try_into()?, &odb, options(Some(1000150000), None))?;
+        assert_eq!(out, Outcome { kept: 2, pruned: 2 });
+        assert_eq!(
+            messages(&store, "refs/heads/main")?,
+            ["commit (amend): c2-amended", "commit: c3"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unreachable_entries_by_age_with_rewrite() -> crate::Result {
+        let (_dir, store, odb) = store_and_odb()?;
+        let out = store.reflog_expire(
+            "refs/heads/main".try_into()?,
+            &odb,
+            Options {
+                rewrite: true,
+                ..options(None, Some(1000250000))
+            },
+        )?;
+        assert_eq!(
+            out,
+            Outcome { kept: 2, pruned: 2 },
+            "the entries mentioning the amended commit aren't reachable from main anymore"
+        );
+        let lines = lines(&store, "refs/heads/main")?;
+        assert_eq!(
+            lines.iter().map(|l| l.message.to_string()).collect::<Vec<_>>(),
+            ["commit (initial): c1", "commit: c3"]
+        );
+        assert_eq!(
+            lines[1].previous_oid, lines[0].new_oid,
+            "the previous object was rewritten to match the previous entry"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unreachable_entries_by_age_without_rewrite() -> crate::Result {
+        let (_dir, store, odb) = store_and_odb()?;
+        let before = lines(&store, "refs/heads/main")?;
+        store.reflog_expire("refs/heads/main".try_into()?, &odb, options(None, Some(1000250000)))?;
+        let after = lines(&store, "refs/heads/main")?;
+        assert_eq!(after.len(), 2);
+        assert_eq!(after[1], before[3], "kept entries are unchanged");
+        Ok(())
+    }
+
+    #[test]
+    fn head_considers_all_references_for_reachability() -> crate::Result {
+        let (_dir, store, odb) = store_and_odb()?;
+        let out = store.reflog_expire("HEAD".try_into()?, &odb, options(None, Some(1000250000)))?;
+        assert_eq!(
+            out,
+            Outcome { kept: 4, pruned: 0 },
+            "the original second commit is still reachable from the 'keep' branch"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn locked_references_are_not_changed() -> crate::Result {
+        let (_dir, store, odb) = store_and_odb()?;
+        std::fs::write(store.git_dir().join("refs/heads/main.lock"), b"")?;
+        let err = store
+            .reflog_expire("refs/heads/main".try_into()?, &odb, options(Some(i64::MAX), None))
+            .unwrap_err();
+        assert!(matches!(err, gix_ref::file::log::expire::Error::Lock(_)));
+        assert_eq!(messages(&store, "refs/heads/main")?.len(), 4);
+        Ok(())
+    }
+}
@@ -0,0 +1,11 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+
+git checkout -q -b main
+GIT_COMMITTER_DATE="1000000000 +0000" git commit -q --allow-empty -m c1
+GIT_COMMITTER_DATE="1000100000 +0000" git commit -q --allow-empty -m c2
+git branch keep
+GIT_COMMITTER_DATE="1000200000 +0000" git commit -q --allow-empty --amend -m c2-amended
+GIT_COMMITTER_DATE="1000300000 +0000" git commit -q --allow-empty -m c3
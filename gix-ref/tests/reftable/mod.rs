@@ -0,0 +1,54 @@
+use gix_ref::{
+    reftable::{write, Log, Ref, Table, Value},
+    FullName,
+};
+
+use crate::hex_to_id;
+
+pub fn name(name: &str) -> FullName {
+    name.try_into().expect("valid")
+}
+
+pub fn id(n: usize) -> gix_hash::ObjectId {
+    hex_to_id(&format!("{n:040x}"))
+}
+
+pub fn object_ref(n: &str, update_index: u64, value: usize) -> Ref {
+    Ref {
+        name: name(n),
+        update_index,
+        value: Value::Object(id(value)),
+    }
+}
+
+pub fn log(n: &str, update_index: u64, message: &str) -> Log {
+    Log {
+        name: name(n),
+        update_index,
+        line: Some(gix_ref::log::Line {
+            previous_oid: id(update_index as usize),
+            new_oid: id(update_index as usize + 1),
+            signature: gix_actor::Signature {
+                name: "committer".into(),
+                email: "committer@example.com".into(),
+                time: gix_date::Time::new(1_000_000 + update_index as i64, -3600),
+            },
+            message: message.into(),
+        }),
+    }
+}
+
+fn table(min: u64, max: u64, options: write::Options, refs: Vec<Ref>, logs: Vec<Log>) -> crate::Result<Table> {
+    let mut writer = write::Writer::new(min, max, options);
+    for r in refs {
+        writer.add_ref(r);
+    }
+    for log in logs {
+        writer.add_log(log);
+    }
+    Ok(Table::from_bytes(writer.finish()?)?)
+}
+
+mod stack;
+mod store;
+mod table;
@@ -0,0 +1,134 @@
+use gix_ref::reftable::{write, Ref, Stack, Value};
+
+use crate::reftable::{log, name, object_ref, table};
+
+fn append(stack: &mut Stack, refs: Vec<Ref>, logs: Vec<gix_ref::reftable::Log>) -> crate::Result {
+    let update_index = stack.max_update_index() + 1;
+    let mut writer = write::Writer::new(update_index, update_index, Default::default());
+    for r in refs {
+        writer.add_ref(Ref { update_index, ..r });
+    }
+    for log in logs {
+        writer.add_log(gix_ref::reftable::Log { update_index, ..log });
+    }
+    let lock = Stack::lock(stack.dir(), gix_lock::acquire::Fail::Immediately)?;
+    stack.append(lock, writer.finish()?)?;
+    Ok(())
+}
+
+#[test]
+fn missing_directory_is_an_empty_stack() -> crate::Result {
+    let dir = gix_testtools::tempfile::tempdir()?;
+    let stack = Stack::at(dir.path().join("reftable"))?;
+    assert_eq!(stack.tables().len(), 0);
+    assert_eq!(stack.max_update_index(), 0);
+    assert_eq!(stack.refs("".into())?.count(), 0);
+    Ok(())
+}
+
+#[test]
+fn newer_tables_shadow_older_ones_and_deletions_hide_refs() -> crate::Result {
+    let dir = gix_testtools::tempfile::tempdir()?;
+    let mut stack = Stack::at(dir.path().join("reftable"))?;
+    append(
+        &mut stack,
+        vec![object_ref("refs/heads/a", 0, 1), object_ref("refs/heads/b", 0, 1)],
+        vec![log("refs/heads/a", 0, "create a")],
+    )?;
+    append(
+        &mut stack,
+        vec![
+            object_ref("refs/heads/a", 0, 2),
+            Ref {
+                name: name("refs/heads/b"),
+                update_index: 0,
+                value: Value::Deletion,
+            },
+            object_ref("refs/heads/c", 0, 3),
+        ],
+        vec![log("refs/heads/a", 0, "update a")],
+    )?;
+
+    let stack = Stack::at(stack.dir())?;
+    assert_eq!(stack.tables().len(), 2, "the list was written");
+    assert_eq!(stack.max_update_index(), 2);
+    assert_eq!(
+        stack.find_ref(name("refs/heads/a").as_ref())?.map(|r| r.value),
+        Some(Value::Object(crate::reftable::id(2)))
+    );
+    assert_eq!(stack.find_ref(name("refs/heads/b").as_ref())?, None);
+    assert_eq!(
+        stack
+            .refs("refs/heads/".into())?
+            .map(|r| r.map(|r| r.name.as_bstr().to_string()))
+            .collect::<Result<Vec<_>, _>>()?,
+        ["refs/heads/a", "refs/heads/c"]
+    );
+    assert_eq!(
+        stack
+            .logs_of(name("refs/heads/a").as_ref())?
+            .into_iter()
+            .map(|log| log.line.expect("present").message.to_string())
+            .collect::<Vec<_>>(),
+        ["update a", "create a"]
+    );
+    Ok(())
+}
+
+#[test]
+fn compaction_keeps_the_stack_small_and_drops_deletions() -> crate::Result {
+    let dir = gix_testtools::tempfile::tempdir()?;
+    let mut stack = Stack::at(dir.path().join("reftable"))?;
+    for n in 0..20 {
+        append(
+            &mut stack,
+            vec![
+                object_ref("refs/heads/main", 0, n + 1),
+                object_ref(&format!("refs/heads/b{n:02}"), 0, n + 1),
+            ],
+            vec![log("refs/heads/main", 0, "commit")],
+        )?;
+        stack.auto_compact(gix_lock::acquire::Fail::Immediately)?;
+    }
+    assert!(
+        stack.tables().len() < 8,
+        "compaction keeps the amount of tables logarithmic, got {}",
+        stack.tables().len()
+    );
+    assert_eq!(stack.refs("".into())?.count(), 21);
+    assert_eq!(stack.logs_of(name("refs/heads/main").as_ref())?.len(), 20);
+
+    append(
+        &mut stack,
+        vec![Ref {
+            name: name("refs/heads/b00"),
+            update_index: 0,
+            value: Value::Deletion,
+        }],
+        vec![],
+    )?;
+    assert!(stack.compact_all(gix_lock::acquire::Fail::Immediately)?);
+    assert_eq!(stack.tables().len(), 1);
+    let (_, table) = stack.tables().next().expect("one table");
+    assert_eq!(table.min_update_index(), 1);
+    assert_eq!(table.max_update_index(), 21);
+    assert_eq!(table.refs()?.count(), 20, "the deletion was dropped along with the ref");
+    assert_eq!(
+        std::fs::read_dir(stack.dir())?.count(),
+        2,
+        "only the list and the compacted table remain"
+    );
+    assert_eq!(Stack::at(stack.dir())?.refs("".into())?.count(), 20);
+    Ok(())
+}
+
+#[test]
+fn tables_written_by_the_writer_can_be_opened_from_disk() -> crate::Result {
+    let dir = gix_testtools::tempfile::tempdir()?;
+    let table = table(1, 1, Default::default(), vec![object_ref("HEAD", 1, 1)], vec![])?;
+    let path = dir.path().join("t.ref");
+    std::fs::write(&path, table.data())?;
+    let reopened = gix_ref::reftable::Table::at(&path)?;
+    assert_eq!(reopened.refs()?.count(), 1);
+    Ok(())
+}
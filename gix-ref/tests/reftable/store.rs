@@ -0,0 +1,296 @@
+use gix_date::{time::Sign, Time};
+use gix_lock::acquire::Fail;
+use gix_ref::{
+    file,
+    store::{init, RefStorage},
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    Target,
+};
+
+use super::id;
+
+fn reftable_store() -> crate::Result<(gix_testtools::tempfile::TempDir, file::Store)> {
+    let dir = gix_testtools::tempfile::TempDir::new()?;
+    let store = file::Store::at(
+        dir.path().into(),
+        init::Options {
+            ref_storage: RefStorage::Reftable,
+            ..Default::default()
+        },
+    );
+    Ok((dir, store))
+}
+
+fn committer() -> gix_actor::Signature {
+    gix_actor::Signature {
+        name: "committer".into(),
+        email: "committer@example.com".into(),
+        time: Time {
+            seconds: 1234,
+            offset: 1800,
+            sign: Sign::Plus,
+        },
+    }
+}
+
+fn update(name: &str, expected: PreviousValue, new: Target, message: &str) -> RefEdit {
+    RefEdit {
+        change: Change::Update {
+            log: LogChange {
+                mode: RefLog::AndReference,
+                force_create_reflog: true,
+                message: message.into(),
+            },
+            expected,
+            new,
+        },
+        name: name.try_into().expect("valid"),
+        deref: false,
+    }
+}
+
+fn delete(name: &str, expected: PreviousValue) -> RefEdit {
+    RefEdit {
+        change: Change::Delete {
+            expected,
+            log: RefLog::AndReference,
+        },
+        name: name.try_into().expect("valid"),
+        deref: false,
+    }
+}
+
+fn commit(store: &file::Store, edits: impl IntoIterator<Item = RefEdit>) -> crate::Result<Vec<RefEdit>> {
+    Ok(store
+        .transaction()
+        .prepare(edits, Fail::Immediately, Fail::Immediately)?
+        .commit(committer().to_ref())?)
+}
+
+fn names(store: &file::Store) -> crate::Result<Vec<String>> {
+    Ok(store
+        .iter()?
+        .all()?
+        .map(|r| r.map(|r| r.name.as_bstr().to_string()))
+        .collect::<Result<_, _>>()?)
+}
+
+#[test]
+fn transactions_are_visible_to_lookups_iteration_and_reflogs() -> crate::Result {
+    let (dir, store) = reftable_store()?;
+    assert_eq!(store.ref_storage(), RefStorage::Reftable);
+    assert!(
+        store.reftable_stack()?.expect("reftable storage").tables().len() == 0,
+        "nothing was written yet"
+    );
+
+    commit(
+        &store,
+        [
+            update(
+                "refs/heads/main",
+                PreviousValue::MustNotExist,
+                Target::Object(id(1)),
+                "first",
+            ),
+            update(
+                "refs/tags/v1",
+                PreviousValue::MustNotExist,
+                Target::Object(id(2)),
+                "tag",
+            ),
+            update(
+                "HEAD",
+                PreviousValue::Any,
+                Target::Symbolic("refs/heads/main".try_into()?),
+                "",
+            ),
+        ],
+    )?;
+    assert!(
+        !dir.path().join("refs/heads/main").exists(),
+        "nothing is written as loose reference"
+    );
+    assert!(dir.path().join("reftable").join("tables.list").is_file());
+
+    let main = store.find("main")?;
+    assert_eq!(main.target, Target::Object(id(1)));
+    let head = store.find("HEAD")?;
+    assert_eq!(head.target, Target::Symbolic("refs/heads/main".try_into()?));
+    assert!(store.try_find("refs/heads/missing")?.is_none());
+
+    assert_eq!(names(&store)?, ["refs/heads/main", "refs/tags/v1"]);
+    let tags = store
+        .iter()?
+        .prefixed("refs/tags/".as_ref())?
+        .map(|r| r.map(|r| r.name.as_bstr().to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(tags, ["refs/tags/v1"]);
+
+    commit(
+        &store,
+        Some(update(
+            "refs/heads/main",
+            PreviousValue::MustExistAndMatch(Target::Object(id(1))),
+            Target::Object(id(3)),
+            "second",
+        )),
+    )?;
+    assert_eq!(store.find("main")?.target, Target::Object(id(3)));
+
+    assert!(store.reflog_exists("refs/heads/main")?);
+    let mut buf = Vec::new();
+    let lines = store
+        .reflog_iter("refs/heads/main", &mut buf)?
+        .expect("present")
+        .map(|line| line.map(|line| (line.previous_oid(), line.new_oid(), line.message.to_string())))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        lines,
+        [
+            (gix_hash::Kind::Sha1.null(), id(1), "first".into()),
+            (id(1), id(3), "second".into())
+        ],
+        "reflogs are returned oldest first"
+    );
+    let mut buf = [0u8; 256];
+    let newest = store
+        .reflog_iter_rev("refs/heads/main", &mut buf)?
+        .expect("present")
+        .next()
+        .expect("one line")?;
+    assert_eq!(newest.message, "second");
+
+    let err = commit(
+        &store,
+        Some(update(
+            "refs/heads/main",
+            PreviousValue::MustExistAndMatch(Target::Object(id(1))),
+            Target::Object(id(4)),
+            "stale",
+        )),
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("should have content"),
+        "expectations are verified against the stack: {err}"
+    );
+    assert!(commit(
+        &store,
+        Some(update(
+            "refs/tags/v1",
+            PreviousValue::MustNotExist,
+            Target::Object(id(4)),
+            "exists"
+        ))
+    )
+    .is_err());
+
+    commit(
+        &store,
+        Some(delete(
+            "refs/heads/main",
+            PreviousValue::MustExistAndMatch(Target::Object(id(3))),
+        )),
+    )?;
+    assert!(store.try_find("refs/heads/main")?.is_none());
+    assert!(
+        !store.reflog_exists("refs/heads/main")?,
+        "the reflog is deleted as well"
+    );
+    assert_eq!(names(&store)?, ["refs/tags/v1"]);
+    assert_eq!(
+        store.find("HEAD")?.target,
+        Target::Symbolic("refs/heads/main".try_into()?),
+        "HEAD now points to an unborn branch"
+    );
+    Ok(())
+}
+
+#[test]
+fn many_transactions_are_compacted_automatically() -> crate::Result {
+    let (_dir, store) = reftable_store()?;
+    for n in 1..=32usize {
+        commit(
+            &store,
+            Some(update(
+                "refs/heads/main",
+                PreviousValue::Any,
+                Target::Object(id(n)),
+                "update",
+            )),
+        )?;
+    }
+    let stack = store.reftable_stack()?.expect("written");
+    assert!(
+        stack.tables().len() < 6,
+        "geometric compaction keeps the stack short, got {}",
+        stack.tables().len()
+    );
+    assert_eq!(stack.max_update_index(), 32);
+    assert_eq!(store.find("main")?.target, Target::Object(id(32)));
+
+    let mut buf = Vec::new();
+    assert_eq!(
+        store
+            .reflog_iter("refs/heads/main", &mut buf)?
+            .expect("present")
+            .count(),
+        32,
+        "compaction retains all reflog entries"
+    );
+    Ok(())
+}
+
+#[test]
+fn special_refs_remain_loose_files() -> crate::Result {
+    let (dir, store) = reftable_store()?;
+    commit(
+        &store,
+        Some(update(
+            "FETCH_HEAD",
+            PreviousValue::MustNotExist,
+            Target::Object(id(1)),
+            "",
+        )),
+    )?;
+    assert!(dir.path().join("FETCH_HEAD").is_file());
+    assert!(store.reftable_stack()?.expect("reftable storage").tables().len() == 0);
+    assert_eq!(store.find("FETCH_HEAD")?.target, Target::Object(id(1)));
+    Ok(())
+}
+
+#[test]
+fn pack_refs_compacts_all_tables_and_reflog_expire_writes_deletions() -> crate::Result {
+    let (_dir, store) = reftable_store()?;
+    for n in 1..=3usize {
+        commit(
+            &store,
+            Some(update(
+                "refs/heads/main",
+                PreviousValue::Any,
+                Target::Object(id(n)),
+                "update",
+            )),
+        )?;
+    }
+
+    let out = store.reflog_expire(
+        "refs/heads/main".try_into()?,
+        gix_object::find::Never,
+        gix_ref::file::log::expire::Options {
+            expire: Some(1235),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(out.pruned, 3, "all entries were written at 1234 seconds");
+    assert!(!store.reflog_exists("refs/heads/main")?);
+
+    let num_tables = store.reftable_stack()?.expect("reftable storage").tables().len();
+    let out = store.pack_refs(gix_object::find::Never, Default::default())?;
+    assert_eq!(out.compacted_reftables, num_tables > 1);
+    let stack = store.reftable_stack()?.expect("reftable storage");
+    assert_eq!(stack.tables().len(), 1, "everything is in one table now");
+    assert_eq!(store.find("main")?.target, Target::Object(id(3)));
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use gix_ref::reftable::{write, Table, Value};
+
+use crate::reftable::{id, log, name, object_ref, table};
+
+#[test]
+fn empty_table_roundtrips() -> crate::Result {
+    let table = table(1, 1, Default::default(), vec![], vec![])?;
+    assert_eq!(table.min_update_index(), 1);
+    assert_eq!(table.max_update_index(), 1);
+    assert_eq!(table.version(), 1);
+    assert_eq!(table.object_hash(), gix_hash::Kind::Sha1);
+    assert_eq!(table.data().len(), 24 + 68, "just header and footer");
+    assert_eq!(table.refs()?.count(), 0);
+    assert_eq!(table.logs()?.count(), 0);
+    assert_eq!(table.find_ref(name("refs/heads/main").as_ref())?, None);
+    Ok(())
+}
+
+#[test]
+fn sha256_tables_use_version_2() -> crate::Result {
+    let id = gix_hash::ObjectId::empty_tree(gix_hash::Kind::Sha256);
+    let r = gix_ref::reftable::Ref {
+        name: name("refs/heads/main"),
+        update_index: 1,
+        value: Value::Object(id),
+    };
+    let table = table(
+        1,
+        1,
+        write::Options {
+            object_hash: gix_hash::Kind::Sha256,
+            ..Default::default()
+        },
+        vec![r.clone()],
+        vec![],
+    )?;
+    assert_eq!(table.version(), 2);
+    assert_eq!(table.object_hash(), gix_hash::Kind::Sha256);
+    assert_eq!(table.refs()?.collect::<Result<Vec<_>, _>>()?, vec![r]);
+    Ok(())
+}
+
+#[test]
+fn all_value_types_roundtrip() -> crate::Result {
+    let refs = vec![
+        object_ref("refs/heads/main", 3, 1),
+        gix_ref::reftable::Ref {
+            name: name("HEAD"),
+            update_index: 2,
+            value: Value::Symbolic(name("refs/heads/main")),
+        },
+        gix_ref::reftable::Ref {
+            name: name("refs/tags/v1"),
+            update_index: 3,
+            value: Value::Peeled {
+                target: id(2),
+                peeled: id(1),
+            },
+        },
+        gix_ref::reftable::Ref {
+            name: name("refs/heads/gone"),
+            update_index: 2,
+            value: Value::Deletion,
+        },
+    ];
+    let logs = vec![log("refs/heads/main", 2, "first"), log("refs/heads/main", 3, "second")];
+    let table = table(2, 3, Default::default(), refs.clone(), logs.clone())?;
+
+    let mut expected = refs;
+    expected.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(table.refs()?.collect::<Result<Vec<_>, _>>()?, expected);
+    assert_eq!(
+        table.logs()?.collect::<Result<Vec<_>, _>>()?,
+        vec![logs[1].clone(), logs[0].clone()],
+        "newest entries come first"
+    );
+    assert_eq!(
+        table.find_ref(name("refs/tags/v1").as_ref())?.map(|r| r.value),
+        Some(Value::Peeled {
+            target: id(2),
+            peeled: id(1)
+        })
+    );
+    assert_eq!(
+        table.find_ref(name("refs/heads/gone").as_ref())?.map(|r| r.value),
+        Some(Value::Deletion),
+        "deletions are visible at the table level"
+    );
+    Ok(())
+}
+
+#[test]
+fn many_records_use_multiple_blocks_and_indices() -> crate::Result {
+    let options = write::Options {
+        block_size: 256,
+        restart_interval: 4,
+        ..Default::default()
+    };
+    let names: Vec<_> = (0..500).map(|n| format!("refs/heads/branch-{n:04}")).collect();
+    let refs = names
+        .iter()
+        .enumerate()
+        .map(|(n, name)| object_ref(name, 1, n))
+        .collect();
+    let logs = names.iter().map(|name| log(name, 1, "created")).collect();
+    let table = table(1, 1, options, refs, logs)?;
+    assert!(table.data().len() > 100 * 256, "there are many blocks");
+
+    for (n, name) in names.iter().enumerate() {
+        let r = table.find_ref(crate::reftable::name(name).as_ref())?.expect("present");
+        assert_eq!(r.value, Value::Object(id(n)));
+        let logs = table
+            .logs_of(crate::reftable::name(name).as_ref())?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(logs.len(), 1);
+    }
+    assert_eq!(table.find_ref(name("refs/heads/branch-0000a").as_ref())?, None);
+    assert_eq!(table.find_ref(name("refs/heads/zzz").as_ref())?, None);
+    assert_eq!(table.find_ref(name("refs/heads/a").as_ref())?, None);
+    assert_eq!(table.refs()?.count(), names.len());
+    assert_eq!(table.logs()?.count(), names.len());
+    assert_eq!(
+        table.refs_prefixed("refs/heads/branch-01".into())?.count(),
+        100,
+        "prefixes are found through the index"
+    );
+    Ok(())
+}
+
+#[test]
+fn corruption_is_detected() -> crate::Result {
+    let table = table(
+        1,
+        1,
+        Default::default(),
+        vec![object_ref("refs/heads/main", 1, 1)],
+        vec![],
+    )?;
+    let mut data = table.data().to_vec();
+    let last = data.len() - 10;
+    data[last] ^= 1;
+    assert!(matches!(
+        Table::from_bytes(data),
+        Err(gix_ref::reftable::decode::Error::Checksum { .. })
+    ));
+    assert!(Table::from_bytes(b"not a table".to_vec()).is_err());
+    Ok(())
+}
+
+#[test]
+fn writer_rejects_invalid_records() {
+    let mut writer = write::Writer::new(1, 1, Default::default());
+    writer.add_ref(object_ref("refs/heads/main", 2, 1));
+    assert!(matches!(
+        writer.finish(),
+        Err(write::Error::UpdateIndexOutOfRange { .. })
+    ));
+
+    let mut writer = write::Writer::new(1, 1, Default::default());
+    writer.add_ref(object_ref("refs/heads/main", 1, 1));
+    writer.add_ref(object_ref("refs/heads/main", 1, 2));
+    assert!(matches!(writer.finish(), Err(write::Error::DuplicateRef { .. })));
+
+    let mut writer = write::Writer::new(1, 1, Default::default());
+    writer.add_log(log("refs/heads/main", 1, "two\nlines"));
+    assert!(matches!(writer.finish(), Err(write::Error::MessageWithNewlines { .. })));
+}
@@ -214,7 +214,7 @@ pub(crate) mod function {
     // TODO(ST): Should this type be used for `describe` as well?
     #[derive(Debug, Clone, Copy)]
     struct GenThenTime {
-        /// Note that the special [`GENERATION_NUMBER_INFINITY`](gix_commitgraph::GENERATION_NUMBER_INFINITY) is used to indicate
+        /// Note that the special [`GENERATION_NUMBER_V2_INFINITY`](gix_commitgraph::GENERATION_NUMBER_V2_INFINITY) is used to indicate
         /// that no commitgraph is avaialble.
         generation: gix_revwalk::graph::Generation,
         time: gix_date::SecondsSinceUnixEpoch,
@@ -223,7 +223,9 @@ pub(crate) mod function {
     impl From<&graph::Commit<Flags>> for GenThenTime {
         fn from(commit: &graph::Commit<Flags>) -> Self {
             GenThenTime {
-                generation: commit.generation.unwrap_or(gix_commitgraph::GENERATION_NUMBER_INFINITY),
+                generation: commit
+                    .generation
+                    .unwrap_or(gix_commitgraph::GENERATION_NUMBER_V2_INFINITY),
                 time: commit.commit_time,
             }
         }
@@ -47,11 +47,13 @@ mod errors {
 pub use errors::{get_or_insert_default, insert_parents};
 use gix_date::SecondsSinceUnixEpoch;
 
-/// The generation away from the HEAD of graph, useful to limit algorithms by topological depth as well.
+/// The generation of a commit, useful to limit algorithms by topological depth as well.
 ///
-/// 0 would mean the starting point of the hierarchy, and 1 their parents.
+/// It's the [effective generation](gix_commitgraph::file::Commit::effective_generation()) of a commit, i.e. its corrected
+/// commit date if the commit-graph has them, or its topological generation number otherwise, which makes it comparable
+/// among all commits of the same commit-graph.
 /// This number is only available natively if there is a commit-graph.
-pub type Generation = u32;
+pub type Generation = u64;
 
 impl<'find, 'cache, T: std::fmt::Debug> std::fmt::Debug for Graph<'find, 'cache, T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -405,7 +407,7 @@ pub struct Commit<T> {
     /// The time at which the commit was created.
     pub commit_time: SecondsSinceUnixEpoch,
     /// The generation of the commit, if available.
-    pub generation: Option<u32>,
+    pub generation: Option<Generation>,
     /// Any kind of data to associate with this commit.
     pub data: T,
 }
@@ -4,7 +4,7 @@ mod graph {
         fn size_of_commit() {
             assert_eq!(
                 std::mem::size_of::<gix_revwalk::graph::Commit<()>>(),
-                48,
+                72,
                 "We might see quite a lot of these, so they shouldn't grow unexpectedly"
             );
         }
@@ -1,13 +1,16 @@
 pub use crate::client::non_io_types::connect::{Error, Options};
 
-#[cfg(feature = "async-std")]
+#[cfg(any(feature = "async-std", feature = "async-http-client-reqwest"))]
 pub(crate) mod function {
-    use crate::client::{git, non_io_types::connect::Error};
+    #[cfg(feature = "async-std")]
+    use crate::client::git;
+    use crate::client::non_io_types::connect::Error;
 
     /// A general purpose connector connecting to a repository identified by the given `url`.
     ///
     /// This includes connections to
-    /// [git daemons][crate::client::git::connect()] only at the moment.
+    /// [git daemons][crate::client::git::connect()] if the `async-std` feature is enabled, and
+    /// [http(s) servers][crate::client::http::connect()] if the `async-http-client-reqwest` feature is enabled.
     ///
     /// Use `options` to further control specifics of the transport resulting from the connection.
     pub async fn connect<Url, E>(
@@ -18,8 +21,10 @@ pub(crate) mod function {
         Url: TryInto<gix_url::Url, Error = E>,
         gix_url::parse::Error: From<E>,
     {
+        #[cfg_attr(not(feature = "async-std"), allow(unused_mut))]
         let mut url = url.try_into().map_err(gix_url::parse::Error::from)?;
         Ok(match url.scheme {
+            #[cfg(feature = "async-std")]
             gix_url::Scheme::Git => {
                 if url.user().is_some() {
                     return Err(Error::UnsupportedUrlTokens {
@@ -40,6 +45,12 @@ pub(crate) mod function {
                     .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?,
                 )
             }
+            #[cfg(feature = "async-http-client-reqwest")]
+            gix_url::Scheme::Https | gix_url::Scheme::Http => {
+                Box::new(crate::client::http::connect(url, options.version, options.trace))
+            }
+            #[cfg(not(feature = "async-http-client-reqwest"))]
+            gix_url::Scheme::Https | gix_url::Scheme::Http => return Err(Error::CompiledWithoutHttp(url.scheme)),
             scheme => return Err(Error::UnsupportedScheme(scheme)),
         })
     }
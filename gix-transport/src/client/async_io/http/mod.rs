@@ -0,0 +1,344 @@
+use std::{
+    any::Any,
+    borrow::Cow,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_trait::async_trait;
+use bstr::{BStr, ByteSlice};
+use futures_io::{AsyncBufRead, AsyncRead};
+use futures_lite::{future, AsyncReadExt};
+use gix_packetline::PacketLineRef;
+pub use traits::{Error, GetResponse, Http, PostBodyDataKind, PostResponse};
+
+pub use crate::client::non_io_types::http::{options, Options};
+use crate::{
+    client::{
+        self, capabilities, non_io_types::http, Capabilities, ExtendedBufRead, HandleProgress, MessageKind,
+        ReadlineBufRead, RequestWriter,
+    },
+    Protocol, Service,
+};
+
+/// The async `reqwest` backend, which needs to run within a `tokio` runtime.
+#[cfg(feature = "async-http-client-reqwest")]
+pub mod reqwest;
+
+mod traits;
+
+/// The actual http client implementation, using reqwest
+#[cfg(feature = "async-http-client-reqwest")]
+pub type Impl = reqwest::Remote;
+
+/// A transport for supporting arbitrary async http clients by abstracting interactions with them into the [Http] trait.
+pub struct Transport<H: Http> {
+    url: String,
+    user_agent_header: &'static str,
+    desired_version: Protocol,
+    actual_version: Protocol,
+    http: H,
+    service: Option<Service>,
+    line_provider: Option<gix_packetline::StreamingPeekableIter<H::ResponseBody>>,
+    identity: Option<gix_sec::identity::Account>,
+    trace: bool,
+}
+
+impl<H: Http> Transport<H> {
+    /// Create a new instance with `http` as implementation to communicate to `url` using the given `desired_version`.
+    /// Note that we will always fallback to other versions as supported by the server.
+    /// If `trace` is `true`, all packetlines received or sent will be passed to the facilities of the `gix-trace` crate.
+    pub fn new_http(http: H, url: gix_url::Url, desired_version: Protocol, trace: bool) -> Self {
+        let identity = url
+            .user()
+            .zip(url.password())
+            .map(|(user, pass)| gix_sec::identity::Account {
+                username: user.to_string(),
+                password: pass.to_string(),
+            });
+        Transport {
+            url: url.to_bstring().to_string(),
+            user_agent_header: concat!("User-Agent: git/oxide-", env!("CARGO_PKG_VERSION")),
+            desired_version,
+            actual_version: Default::default(),
+            service: None,
+            http,
+            line_provider: None,
+            identity,
+            trace,
+        }
+    }
+
+    /// Returns the identity that the transport uses when connecting to the remote.
+    pub fn identity(&self) -> Option<&gix_sec::identity::Account> {
+        self.identity.as_ref()
+    }
+}
+
+#[cfg(feature = "async-http-client-reqwest")]
+impl Transport<Impl> {
+    /// Create a new instance to communicate to `url` using the given `desired_version` of the `git` protocol.
+    /// If `trace` is `true`, all packetlines received or sent will be passed to the facilities of the `gix-trace` crate.
+    ///
+    /// Note that the actual implementation depends on feature toggles.
+    pub fn new(url: gix_url::Url, desired_version: Protocol, trace: bool) -> Self {
+        Self::new_http(Impl::default(), url, desired_version, trace)
+    }
+}
+
+impl<H: Http> Transport<H> {
+    fn add_basic_auth_if_present(&self, headers: &mut Vec<Cow<'_, str>>) -> Result<(), client::Error> {
+        http::add_basic_auth_if_present(&self.url, self.identity.as_ref(), headers)
+    }
+}
+
+fn check_content_type(service: Service, kind: &str, headers: &[u8]) -> Result<(), client::Error> {
+    let lines = headers
+        .lines()
+        .map(|line| {
+            line.to_str()
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    http::check_content_type(service, kind, lines)
+}
+
+impl<H: Http> client::TransportWithoutIO for Transport<H> {
+    fn set_identity(&mut self, identity: gix_sec::identity::Account) -> Result<(), client::Error> {
+        self.identity = Some(identity);
+        Ok(())
+    }
+
+    fn request(
+        &mut self,
+        write_mode: client::WriteMode,
+        on_into_read: MessageKind,
+        trace: bool,
+    ) -> Result<RequestWriter<'_>, client::Error> {
+        let service = self.service.expect("handshake() must have been called first");
+        let url = http::append_url(&self.url, service.as_str());
+        let static_headers = &[
+            Cow::Borrowed(self.user_agent_header),
+            Cow::Owned(format!("Content-Type: application/x-{}-request", service.as_str())),
+            format!("Accept: application/x-{}-result", service.as_str()).into(),
+        ];
+        let mut dynamic_headers = Vec::new();
+        self.add_basic_auth_if_present(&mut dynamic_headers)?;
+        if self.actual_version != Protocol::V1 {
+            dynamic_headers.push(Cow::Owned(format!(
+                "Git-Protocol: version={}",
+                self.actual_version as usize
+            )));
+        }
+
+        let PostResponse {
+            headers,
+            body,
+            post_body,
+        } = self.http.post(
+            &url,
+            &self.url,
+            static_headers.iter().chain(&dynamic_headers),
+            write_mode.into(),
+        )?;
+        let line_provider = self
+            .line_provider
+            .as_mut()
+            .expect("handshake to have been called first");
+        line_provider.replace(body);
+        Ok(RequestWriter::new_from_bufread(
+            post_body,
+            Box::new(HeadersThenBody::<H, _> {
+                service,
+                headers: Some(headers),
+                header_buf: Vec::new(),
+                body: line_provider.as_read_without_sidebands(),
+            }),
+            write_mode,
+            on_into_read,
+            trace,
+        ))
+    }
+
+    fn to_url(&self) -> Cow<'_, BStr> {
+        Cow::Borrowed(self.url.as_str().into())
+    }
+
+    fn connection_persists_across_multiple_requests(&self) -> bool {
+        false
+    }
+
+    fn configure(&mut self, config: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        self.http.configure(config)
+    }
+}
+
+#[async_trait(?Send)]
+impl<H: Http> client::Transport for Transport<H> {
+    async fn handshake<'a>(
+        &mut self,
+        service: Service,
+        extra_parameters: &'a [(&'a str, Option<&'a str>)],
+    ) -> Result<client::SetServiceResponse<'_>, client::Error> {
+        let url = http::append_url(self.url.as_ref(), &format!("info/refs?service={}", service.as_str()));
+        let static_headers = [Cow::Borrowed(self.user_agent_header)];
+        let mut dynamic_headers = Vec::<Cow<'_, str>>::new();
+        if let Some(header) = http::git_protocol_header(self.desired_version, extra_parameters) {
+            dynamic_headers.push(header.into());
+        }
+        self.add_basic_auth_if_present(&mut dynamic_headers)?;
+        let GetResponse { mut headers, body } = self
+            .http
+            .get(url.as_ref(), &self.url, static_headers.iter().chain(&dynamic_headers))
+            .await?;
+        let mut header_buf = Vec::new();
+        headers.read_to_end(&mut header_buf).await?;
+        check_content_type(service, "advertisement", &header_buf)?;
+
+        let trace = self.trace;
+        let line_reader = self
+            .line_provider
+            .get_or_insert_with(|| gix_packetline::StreamingPeekableIter::new(body, &[PacketLineRef::Flush], trace));
+
+        // the service announcement is only sent sometimes depending on the exact server/protocol version/used protocol (http?)
+        // eat the announcement when its there to avoid errors later (and check that the correct service was announced).
+        // Ignore the announcement otherwise.
+        let line_ = line_reader
+            .peek_line()
+            .await
+            .ok_or(client::Error::ExpectedLine("capabilities, version or service"))???;
+        let line = line_.as_text().ok_or(client::Error::ExpectedLine("text"))?;
+
+        if let Some(announced_service) = line.as_bstr().strip_prefix(b"# service=") {
+            if announced_service != service.as_str().as_bytes() {
+                return Err(client::Error::Http(Error::Detail {
+                    description: format!(
+                        "Expected to see service {:?}, but got {:?}",
+                        service.as_str(),
+                        announced_service
+                    ),
+                }));
+            }
+
+            line_reader.as_read().read_to_end(&mut Vec::new()).await?;
+        }
+
+        let capabilities::recv::Outcome {
+            capabilities,
+            refs,
+            protocol: actual_protocol,
+        } = Capabilities::from_lines_with_version_detection(line_reader).await?;
+        self.actual_version = actual_protocol;
+        self.service = Some(service);
+        Ok(client::SetServiceResponse {
+            actual_protocol,
+            capabilities,
+            refs,
+        })
+    }
+}
+
+struct HeadersThenBody<H: Http, B: Unpin> {
+    service: Service,
+    headers: Option<H::Headers>,
+    header_buf: Vec<u8>,
+    body: B,
+}
+
+impl<H: Http, B: Unpin> HeadersThenBody<H, B> {
+    fn poll_handle_headers(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if let Some(headers) = self.headers.as_mut() {
+            loop {
+                let buf = futures_lite::ready!(Pin::new(&mut *headers).poll_fill_buf(cx))?;
+                if buf.is_empty() {
+                    break;
+                }
+                let len = buf.len();
+                self.header_buf.extend_from_slice(buf);
+                Pin::new(&mut *headers).consume(len);
+            }
+            self.headers = None;
+            check_content_type(self.service, "result", &self.header_buf)
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    async fn handle_headers(&mut self) -> io::Result<()> {
+        future::poll_fn(|cx| self.poll_handle_headers(cx)).await
+    }
+}
+
+impl<H: Http, B: AsyncRead + Unpin> AsyncRead for HeadersThenBody<H, B> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        futures_lite::ready!(this.poll_handle_headers(cx))?;
+        Pin::new(&mut this.body).poll_read(cx, buf)
+    }
+}
+
+impl<H: Http, B: AsyncBufRead + Unpin> AsyncBufRead for HeadersThenBody<H, B> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        futures_lite::ready!(this.poll_handle_headers(cx))?;
+        Pin::new(&mut this.body).poll_fill_buf(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        Pin::new(&mut self.get_mut().body).consume(amt);
+    }
+}
+
+#[async_trait(?Send)]
+impl<H: Http, B: ReadlineBufRead + Unpin> ReadlineBufRead for HeadersThenBody<H, B> {
+    async fn readline(&mut self) -> Option<io::Result<Result<PacketLineRef<'_>, gix_packetline::decode::Error>>> {
+        if let Err(err) = self.handle_headers().await {
+            return Some(Err(err));
+        }
+        self.body.readline().await
+    }
+
+    async fn readline_str(&mut self, line: &mut String) -> io::Result<usize> {
+        self.handle_headers().await?;
+        self.body.readline_str(line).await
+    }
+}
+
+#[async_trait(?Send)]
+impl<'a, H: Http, B: ExtendedBufRead<'a> + Unpin> ExtendedBufRead<'a> for HeadersThenBody<H, B> {
+    fn set_progress_handler(&mut self, handle_progress: Option<HandleProgress<'a>>) {
+        self.body.set_progress_handler(handle_progress);
+    }
+
+    async fn peek_data_line(&mut self) -> Option<io::Result<Result<&[u8], client::Error>>> {
+        if let Err(err) = self.handle_headers().await {
+            return Some(Err(err));
+        }
+        self.body.peek_data_line().await
+    }
+
+    fn reset(&mut self, version: Protocol) {
+        self.body.reset(version);
+    }
+
+    fn stopped_at(&self) -> Option<MessageKind> {
+        self.body.stopped_at()
+    }
+}
+
+/// Connect to the given `url` via HTTP/S using the `desired_version` of the `git` protocol, with `http` as implementation.
+/// If `trace` is `true`, all packetlines received or sent will be passed to the facilities of the `gix-trace` crate.
+pub fn connect_http<H: Http>(http: H, url: gix_url::Url, desired_version: Protocol, trace: bool) -> Transport<H> {
+    Transport::new_http(http, url, desired_version, trace)
+}
+
+/// Connect to the given `url` via HTTP/S using the `desired_version` of the `git` protocol.
+/// If `trace` is `true`, all packetlines received or sent will be passed to the facilities of the `gix-trace` crate.
+#[cfg(feature = "async-http-client-reqwest")]
+pub fn connect(url: gix_url::Url, desired_version: Protocol, trace: bool) -> Transport<Impl> {
+    Transport::new(url, desired_version, trace)
+}
+
+#[cfg(feature = "async-http-client-reqwest")]
+pub use crate::client::non_io_types::http::redirect;
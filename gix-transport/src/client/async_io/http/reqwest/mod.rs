@@ -0,0 +1,38 @@
+/// An implementation for HTTP requests via the async version of `reqwest`.
+///
+/// Note that all requests need to be made from within a `tokio` runtime, as `POST` requests are performed on a task
+/// spawned onto it while the body is written.
+pub struct Remote {
+    /// The client to perform requests with, created lazily from `config` and recreated when it changes.
+    client: Option<reqwest::Client>,
+    /// The redirect handling that was decided upon with the first request.
+    follow: Option<crate::client::http::options::FollowRedirects>,
+    /// Shared with the redirect policy of `client` to control if redirects are followed for the next request.
+    allow_redirects: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    /// The base url we were redirected to, if any, which will be used instead of the original base url for all future requests.
+    redirected_base_url: std::sync::Arc<std::sync::Mutex<Option<String>>>,
+    /// The credentials helper action to store or erase the proxy credentials with once the first request completed.
+    proxy_auth_action: Option<(
+        gix_credentials::helper::NextAction,
+        std::sync::Arc<std::sync::Mutex<crate::client::http::options::AuthenticateFn>>,
+    )>,
+    /// A mechanism for configuring the remote.
+    config: crate::client::http::Options,
+}
+
+/// A function to configure a single request prior to sending it, support most complex configuration beyond what's possible with
+/// basic `git` http configuration.
+pub type ConfigureRequestFn = dyn FnMut(&mut reqwest::Request) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>
+    + Send
+    + Sync
+    + 'static;
+
+/// Options to configure the reqwest HTTP handler.
+#[derive(Default)]
+pub struct Options {
+    /// A function to configure the request that is about to be made.
+    pub configure_request: Option<Box<ConfigureRequestFn>>,
+}
+
+///
+pub mod remote;
@@ -0,0 +1,471 @@
+use std::{
+    any::Any,
+    io,
+    pin::Pin,
+    str::FromStr,
+    sync::{atomic, Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures_channel::{mpsc, oneshot};
+use futures_io::{AsyncBufRead, AsyncWrite};
+use futures_util::{future, stream, StreamExt, TryStreamExt};
+
+use crate::client::http::{self, options::FollowRedirects, redirect, reqwest::Remote, PostBodyDataKind};
+
+/// The error returned by the 'remote' helper, a purely internal construct to perform http requests.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error("Request configuration failed")]
+    ConfigureRequest(#[from] Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error(transparent)]
+    Redirect(#[from] redirect::Error),
+    #[error("Could not obtain or store the credentials to authenticate with the proxy")]
+    ProxyAuthenticate(#[source] Box<gix_credentials::protocol::Error>),
+    #[cfg(any(
+        feature = "async-http-client-reqwest-rust-tls",
+        feature = "async-http-client-reqwest-native-tls"
+    ))]
+    #[error("Could not read CA certificates from {path:?}")]
+    ReadCaInfo {
+        path: std::path::PathBuf,
+        source: io::Error,
+    },
+}
+
+impl crate::IsSpuriousError for Error {
+    fn is_spurious(&self) -> bool {
+        match self {
+            Error::Reqwest(err) => {
+                err.is_timeout() || err.is_connect() || err.status().map_or(false, |status| status.is_server_error())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The type used to provide the headers and the body of a response.
+pub type Reader = Pin<Box<dyn AsyncBufRead + Send>>;
+
+/// The writer receiving the body of a `POST` request while the request is performed in the background.
+///
+/// It must be closed or dropped to finish the request.
+pub struct Writer {
+    chunks: mpsc::Sender<Vec<u8>>,
+}
+
+impl AsyncWrite for Writer {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        futures_lite::ready!(this.chunks.poll_ready(cx))
+            .map_err(|err| io::Error::new(io::ErrorKind::BrokenPipe, err))?;
+        this.chunks
+            .start_send(buf.to_vec())
+            .map_err(|err| io::Error::new(io::ErrorKind::BrokenPipe, err))?;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().chunks.close_channel();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Default for Remote {
+    fn default() -> Self {
+        Remote {
+            client: None,
+            follow: None,
+            allow_redirects: Arc::new(atomic::AtomicBool::new(false)),
+            redirected_base_url: Default::default(),
+            proxy_auth_action: None,
+            config: http::Options::default(),
+        }
+    }
+}
+
+/// utilities
+impl Remote {
+    fn client(&mut self) -> Result<reqwest::Client, Error> {
+        if let Some(client) = &self.client {
+            return Ok(client.clone());
+        }
+        let http::Options {
+            low_speed_limit_bytes_per_second,
+            low_speed_time_seconds,
+            connect_timeout,
+            proxy,
+            no_proxy,
+            proxy_authenticate,
+            user_agent,
+            http_version,
+            ..
+        } = &self.config;
+
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(connect_timeout.unwrap_or(std::time::Duration::from_secs(20)))
+            .http1_title_case_headers()
+            .redirect(reqwest::redirect::Policy::custom({
+                let allow_redirects = self.allow_redirects.clone();
+                move |attempt| {
+                    if allow_redirects.load(atomic::Ordering::Relaxed) {
+                        let curr_url = attempt.url();
+                        let prev_urls = attempt.previous();
+
+                        match prev_urls.first() {
+                            Some(prev_url) if prev_url.host_str() != curr_url.host_str() => {
+                                // git does not want to be redirected to a different host.
+                                attempt.stop()
+                            }
+                            _ => {
+                                // emulate default git behaviour which relies on curl default behaviour apparently.
+                                const CURL_DEFAULT_REDIRS: usize = 50;
+                                if prev_urls.len() >= CURL_DEFAULT_REDIRS {
+                                    attempt.error("too many redirects")
+                                } else {
+                                    attempt.follow()
+                                }
+                            }
+                        }
+                    } else {
+                        attempt.stop()
+                    }
+                }
+            }));
+        if let Some(user_agent) = user_agent {
+            builder = builder.user_agent(user_agent.as_str());
+        }
+        // `reqwest` can only abort connections that don't receive any data for a while, which is the closest
+        // we can get to a minimal transfer speed.
+        if *low_speed_time_seconds > 0 && *low_speed_limit_bytes_per_second > 0 {
+            builder = builder.read_timeout(std::time::Duration::from_secs(*low_speed_time_seconds));
+        }
+        if let Some(http::options::HttpVersion::V1_1) = http_version {
+            builder = builder.http1_only();
+        }
+        match proxy.as_deref() {
+            Some("") => builder = builder.no_proxy(),
+            Some(proxy) => {
+                let mut proxy =
+                    reqwest::Proxy::all(proxy)?.no_proxy(no_proxy.as_deref().and_then(reqwest::NoProxy::from_string));
+                // Only basic authentication is supported by `reqwest`, independently of the configured method.
+                if let Some((obtain_creds_action, authenticate)) = proxy_authenticate {
+                    let creds = authenticate.lock().expect("no panics in other threads")(obtain_creds_action.clone())
+                        .map_err(|err| Error::ProxyAuthenticate(Box::new(err)))?
+                        .expect("action to fetch credentials");
+                    proxy = proxy.basic_auth(&creds.identity.username, &creds.identity.password);
+                    self.proxy_auth_action = Some((creds.next, authenticate.clone()));
+                }
+                builder = builder.proxy(proxy);
+            }
+            None => {}
+        }
+        #[cfg(any(
+            feature = "async-http-client-reqwest-rust-tls",
+            feature = "async-http-client-reqwest-native-tls"
+        ))]
+        {
+            builder = builder.danger_accept_invalid_certs(!self.config.ssl_verify);
+            if let Some(ca_info) = &self.config.ssl_ca_info {
+                let pem = std::fs::read(ca_info).map_err(|source| Error::ReadCaInfo {
+                    path: ca_info.clone(),
+                    source,
+                })?;
+                for certificate in reqwest::Certificate::from_pem_bundle(&pem)? {
+                    builder = builder.add_root_certificate(certificate);
+                }
+            }
+            if let Some(ssl_version) = self.config.ssl_version {
+                let (min, max) = ssl_version.min_max();
+                if let Some(min) = to_tls_version(min) {
+                    builder = builder.min_tls_version(min);
+                }
+                if let Some(max) = to_tls_version(max) {
+                    builder = builder.max_tls_version(max);
+                }
+            }
+        }
+
+        let client = builder.build()?;
+        self.client = Some(client.clone());
+        Ok(client)
+    }
+
+    fn make_request(
+        &mut self,
+        url: &str,
+        base_url: &str,
+        headers: impl IntoIterator<Item = impl AsRef<str>>,
+        upload_body_kind: Option<PostBodyDataKind>,
+    ) -> Result<Request, http::Error> {
+        let client = self
+            .client()
+            .map_err(|err| http::Error::InitHttpClient { source: Box::new(err) })?;
+        let mut header_map = reqwest::header::HeaderMap::new();
+        for header_line in headers
+            .into_iter()
+            .map(|line| line.as_ref().to_owned())
+            .chain(self.config.extra_headers.iter().cloned())
+        {
+            let colon_pos = header_line
+                .find(':')
+                .expect("header line must contain a colon to separate key and value");
+            let header_name = &header_line[..colon_pos];
+            let value = &header_line[colon_pos + 1..];
+
+            match reqwest::header::HeaderName::from_str(header_name)
+                .ok()
+                .zip(reqwest::header::HeaderValue::try_from(value.trim()).ok())
+            {
+                Some((key, val)) => header_map.append(key, val),
+                None => continue,
+            };
+        }
+
+        let effective_url = redirect::swap_tails(
+            self.redirected_base_url
+                .lock()
+                .expect("no panics while holding the lock")
+                .as_deref(),
+            base_url,
+            url.to_owned(),
+        );
+        let builder = if upload_body_kind.is_some() {
+            client.post(&effective_url)
+        } else {
+            client.get(&effective_url)
+        }
+        .headers(header_map);
+
+        let follow = self.follow.get_or_insert(self.config.follow_redirects);
+        self.allow_redirects.store(
+            matches!(follow, FollowRedirects::Initial | FollowRedirects::All),
+            atomic::Ordering::Relaxed,
+        );
+        if *follow == FollowRedirects::Initial {
+            *follow = FollowRedirects::None;
+        }
+
+        Ok(Request {
+            client,
+            builder,
+            url: url.to_owned(),
+            base_url: base_url.to_owned(),
+            effective_url,
+            redirected_base_url: self.redirected_base_url.clone(),
+            backend: self.config.backend.clone(),
+            proxy_auth_action: self.proxy_auth_action.take(),
+        })
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl http::Http for Remote {
+    type Headers = Reader;
+    type ResponseBody = Reader;
+    type PostBody = Writer;
+
+    async fn get(
+        &mut self,
+        url: &str,
+        base_url: &str,
+        headers: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<http::GetResponse<Self::Headers, Self::ResponseBody>, http::Error> {
+        let request = self.make_request(url, base_url, headers, None)?;
+        Ok(match request.execute(None).await {
+            Ok((headers, res)) => http::GetResponse {
+                headers: headers_reader(future::ready(Ok(headers))),
+                body: body_reader(res),
+            },
+            Err(err) => http::GetResponse {
+                headers: headers_reader(future::ready(Err(err))),
+                body: Box::pin(futures_lite::io::empty()),
+            },
+        })
+    }
+
+    fn post(
+        &mut self,
+        url: &str,
+        base_url: &str,
+        headers: impl IntoIterator<Item = impl AsRef<str>>,
+        post_body_kind: PostBodyDataKind,
+    ) -> Result<http::PostResponse<Self::Headers, Self::ResponseBody, Self::PostBody>, http::Error> {
+        let request = self.make_request(url, base_url, headers, Some(post_body_kind))?;
+        let (chunks_tx, chunks_rx) = mpsc::channel::<Vec<u8>>(0);
+        let (headers_tx, headers_rx) = oneshot::channel();
+        let (response_tx, response_rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let body = match post_body_kind {
+                PostBodyDataKind::BoundedAndFitsIntoMemory => reqwest::Body::from(chunks_rx.concat().await),
+                PostBodyDataKind::Unbounded => reqwest::Body::wrap_stream(chunks_rx.map(Ok::<_, io::Error>)),
+            };
+            match request.execute(Some(body)).await {
+                Ok((headers, res)) => {
+                    // The receiver may have decided not to read the response at all, which is fine with us.
+                    headers_tx.send(Ok(headers)).ok();
+                    response_tx.send(res).ok();
+                }
+                Err(err) => {
+                    headers_tx.send(Err(err)).ok();
+                }
+            }
+        });
+
+        Ok(http::PostResponse {
+            headers: headers_reader(async move {
+                headers_rx.await.unwrap_or_else(|_| {
+                    Err(io::Error::new(
+                        io::ErrorKind::BrokenPipe,
+                        "the task performing the request vanished",
+                    ))
+                })
+            }),
+            body: Box::pin(
+                stream::once(response_rx)
+                    .filter_map(|res| future::ready(res.ok()))
+                    .flat_map(reqwest::Response::bytes_stream)
+                    .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+                    .into_async_read(),
+            ),
+            post_body: Writer { chunks: chunks_tx },
+        })
+    }
+
+    fn configure(&mut self, config: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        if let Some(config) = config.downcast_ref::<http::Options>() {
+            self.config = config.clone();
+            self.client = None;
+        }
+        Ok(())
+    }
+}
+
+fn headers_reader(headers: impl std::future::Future<Output = io::Result<Vec<u8>>> + Send + 'static) -> Reader {
+    Box::pin(stream::once(headers).into_async_read())
+}
+
+fn body_reader(res: reqwest::Response) -> Reader {
+    Box::pin(
+        res.bytes_stream()
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+            .into_async_read(),
+    )
+}
+
+#[cfg(any(
+    feature = "async-http-client-reqwest-rust-tls",
+    feature = "async-http-client-reqwest-native-tls"
+))]
+fn to_tls_version(version: http::options::SslVersion) -> Option<reqwest::tls::Version> {
+    use http::options::SslVersion::*;
+    Some(match version {
+        Default | SslV2 | SslV3 => return None,
+        TlsV1 | TlsV1_0 => reqwest::tls::Version::TLS_1_0,
+        TlsV1_1 => reqwest::tls::Version::TLS_1_1,
+        TlsV1_2 => reqwest::tls::Version::TLS_1_2,
+        TlsV1_3 => reqwest::tls::Version::TLS_1_3,
+    })
+}
+
+/// A request that is ready to be sent, along with everything needed to process its response.
+struct Request {
+    client: reqwest::Client,
+    builder: reqwest::RequestBuilder,
+    url: String,
+    base_url: String,
+    effective_url: String,
+    redirected_base_url: Arc<Mutex<Option<String>>>,
+    backend: Option<Arc<Mutex<dyn Any + Send + Sync + 'static>>>,
+    proxy_auth_action: Option<(
+        gix_credentials::helper::NextAction,
+        Arc<Mutex<http::options::AuthenticateFn>>,
+    )>,
+}
+
+impl Request {
+    /// Perform the request with the given `body` and return the response headers, one `name:value` per line, along with the response
+    /// itself to stream the body from.
+    async fn execute(self, body: Option<reqwest::Body>) -> io::Result<(Vec<u8>, reqwest::Response)> {
+        let Request {
+            client,
+            builder,
+            url,
+            base_url,
+            effective_url,
+            redirected_base_url,
+            backend,
+            proxy_auth_action,
+        } = self;
+        let builder = match body {
+            Some(body) => builder.body(body),
+            None => builder,
+        };
+        let mut req = builder
+            .build()
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        if let Some(ref mut request_options) = backend.as_ref().and_then(|backend| backend.lock().ok()) {
+            if let Some(options) = request_options.downcast_mut::<super::Options>() {
+                if let Some(configure_request) = &mut options.configure_request {
+                    configure_request(&mut req)
+                        .map_err(|err| io::Error::new(io::ErrorKind::Other, Error::ConfigureRequest(err)))?;
+                }
+            }
+        }
+
+        let res = client.execute(req).await;
+        if let Some((action, authenticate)) = proxy_auth_action {
+            let succeeded = res.as_ref().map_or(false, |res| res.status().is_success());
+            authenticate.lock().expect("no panics in other threads")(if succeeded {
+                action.store()
+            } else {
+                action.erase()
+            })
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, Error::ProxyAuthenticate(Box::new(err))))?;
+        }
+        let res = match res.and_then(reqwest::Response::error_for_status) {
+            Ok(res) => res,
+            Err(err) => {
+                let (kind, err) = match err.status() {
+                    Some(status) => {
+                        let kind = if status == reqwest::StatusCode::UNAUTHORIZED {
+                            io::ErrorKind::PermissionDenied
+                        } else if status == reqwest::StatusCode::NOT_FOUND {
+                            io::ErrorKind::NotFound
+                        } else if status.is_server_error() {
+                            io::ErrorKind::ConnectionAborted
+                        } else {
+                            io::ErrorKind::Other
+                        };
+                        (kind, format!("Received HTTP status {}", status.as_str()))
+                    }
+                    None => (io::ErrorKind::Other, err.to_string()),
+                };
+                return Err(io::Error::new(kind, err));
+            }
+        };
+
+        let actual_url = res.url().as_str();
+        if actual_url != effective_url.as_str() {
+            let base = redirect::base_url(actual_url, &base_url, url)
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, Error::Redirect(err)))?;
+            *redirected_base_url.lock().expect("no panics while holding the lock") = Some(base);
+        }
+
+        let mut headers = Vec::new();
+        for (name, value) in res.headers() {
+            headers.extend_from_slice(name.as_str().as_bytes());
+            headers.push(b':');
+            headers.extend_from_slice(value.as_bytes());
+            headers.push(b'\n');
+        }
+        Ok((headers, res))
+    }
+}
@@ -32,6 +32,18 @@ pub(crate) mod function {
                         .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?,
                 )
             }
+            #[cfg(feature = "ssh-client-native")]
+            gix_url::Scheme::Ssh if options.ssh.native => Box::new({
+                crate::client::blocking_io::ssh::native::connect(
+                    url,
+                    options.version,
+                    crate::client::blocking_io::ssh::native::Options::from_env(),
+                    options.trace,
+                )
+                .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?
+            }),
+            #[cfg(not(feature = "ssh-client-native"))]
+            gix_url::Scheme::Ssh if options.ssh.native => return Err(Error::CompiledWithoutNativeSsh),
             gix_url::Scheme::Ssh => Box::new({
                 crate::client::blocking_io::ssh::connect(url, options.version, options.ssh, options.trace)
                     .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?
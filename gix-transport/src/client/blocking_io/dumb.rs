@@ -0,0 +1,80 @@
+use bstr::{BStr, ByteSlice};
+
+use crate::client::Error;
+
+/// Read-only access to the files of a remote repository, as served by a *dumb* server that doesn't understand the git protocol.
+///
+/// Such servers only serve static files, which means that instead of negotiating a pack the client has to walk the commit graph
+/// itself by downloading loose objects and packs.
+/// To help the client, the server is expected to provide `info/refs` and `objects/info/packs`, as created by `git update-server-info`.
+pub trait Files {
+    /// Return the content of the file at `path` relative to the root of the remote repository, like `objects/info/packs`,
+    /// or `None` if it doesn't exist.
+    fn get(&mut self, path: &str) -> Result<Option<Box<dyn std::io::BufRead + '_>>, Error>;
+}
+
+impl<T: Files + ?Sized> Files for &mut T {
+    fn get(&mut self, path: &str) -> Result<Option<Box<dyn std::io::BufRead + '_>>, Error> {
+        (**self).get(path)
+    }
+}
+
+/// Parse the names of all packs listed in the content of `objects/info/packs`, like `pack-<hash>.pack`.
+pub fn parse_packs(packs: &[u8]) -> impl Iterator<Item = &BStr> {
+    packs
+        .lines()
+        .filter_map(|line| line.strip_prefix(b"P "))
+        .map(|name| name.trim().as_bstr())
+}
+
+/// Turn the content of the `info/refs` file along with the content of the `HEAD` file into the reference advertisement
+/// of protocol V1, encoded as packet lines, so it can be parsed like the one sent by a smart server.
+///
+/// If `HEAD` is a symbolic ref to a branch that exists, it's advertised using the `symref` capability.
+/// As dumb servers don't advertise their object format, it's assumed to be SHA1.
+#[cfg(feature = "http-client")]
+pub(crate) fn advertisement(info_refs: &[u8], head: Option<&[u8]>) -> Result<Vec<u8>, Error> {
+    let refs: Vec<_> = info_refs
+        .lines()
+        .filter_map(|line| {
+            let mut tokens = line.splitn(2, |b| *b == b'\t');
+            let id = tokens.next()?.trim().as_bstr();
+            let name = tokens.next()?.trim().as_bstr();
+            (!id.is_empty() && !name.is_empty()).then_some((id, name))
+        })
+        .collect();
+
+    let head = head.map(ByteSlice::trim);
+    let mut capabilities = vec!["object-format=sha1".to_owned()];
+    let head_id = match head.and_then(|head| head.strip_prefix(b"ref:")) {
+        Some(target) => {
+            let target = target.trim().as_bstr();
+            refs.iter().find(|(_, name)| *name == target).map(|(id, _)| {
+                capabilities.push(format!("symref=HEAD:{target}"));
+                *id
+            })
+        }
+        None => head.filter(|head| !head.is_empty()).map(ByteSlice::as_bstr),
+    };
+
+    let mut lines = Vec::<Vec<u8>>::new();
+    lines.extend(head_id.map(|id| format!("{id} HEAD").into_bytes()));
+    lines.extend(refs.iter().map(|(id, name)| format!("{id} {name}").into_bytes()));
+    let capabilities = capabilities.join(" ");
+    match lines.first_mut() {
+        Some(first) => {
+            first.push(0);
+            first.extend_from_slice(capabilities.as_bytes());
+        }
+        None => lines
+            .push(format!("0000000000000000000000000000000000000000 capabilities^{{}}\0{capabilities}").into_bytes()),
+    }
+
+    let mut out = Vec::new();
+    for mut line in lines {
+        line.push(b'\n');
+        gix_packetline::encode::data_to_write(&line, &mut out)?;
+    }
+    gix_packetline::encode::flush_to_write(&mut out)?;
+    Ok(out)
+}
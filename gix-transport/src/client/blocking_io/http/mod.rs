@@ -2,22 +2,17 @@ use std::{
     any::Any,
     borrow::Cow,
     io::{BufRead, Read},
-    path::PathBuf,
-    sync::{Arc, Mutex},
 };
 
-use base64::Engine;
 use bstr::BStr;
 use gix_packetline::PacketLineRef;
 pub use traits::{Error, GetResponse, Http, PostBodyDataKind, PostResponse};
 
+pub use crate::client::non_io_types::http::{options, Options};
 use crate::{
     client::{
-        self,
-        blocking_io::bufread_ext::ReadlineBufRead,
-        capabilities,
-        http::options::{HttpVersion, SslVersionRangeInclusive},
-        Capabilities, ExtendedBufRead, HandleProgress, MessageKind, RequestWriter,
+        self, blocking_io::bufread_ext::ReadlineBufRead, capabilities, dumb, non_io_types::http, Capabilities,
+        ExtendedBufRead, HandleProgress, MessageKind, RequestWriter,
     },
     Protocol, Service,
 };
@@ -38,180 +33,6 @@ pub mod reqwest;
 
 mod traits;
 
-///
-pub mod options {
-    /// A function to authenticate a URL.
-    pub type AuthenticateFn =
-        dyn FnMut(gix_credentials::helper::Action) -> gix_credentials::protocol::Result + Send + Sync;
-
-    /// Possible settings for the `http.followRedirects` configuration option.
-    #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
-    pub enum FollowRedirects {
-        /// Follow only the first redirect request, most suitable for typical git requests.
-        #[default]
-        Initial,
-        /// Follow all redirect requests from the server unconditionally
-        All,
-        /// Follow no redirect request.
-        None,
-    }
-
-    /// The way to configure a proxy for authentication if a username is present in the configured proxy.
-    #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
-    pub enum ProxyAuthMethod {
-        /// Automatically pick a suitable authentication method.
-        #[default]
-        AnyAuth,
-        ///HTTP basic authentication.
-        Basic,
-        /// Http digest authentication to prevent a password to be passed in clear text.
-        Digest,
-        /// GSS negotiate authentication.
-        Negotiate,
-        /// NTLM authentication
-        Ntlm,
-    }
-
-    /// Available SSL version numbers.
-    #[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd)]
-    #[allow(missing_docs)]
-    pub enum SslVersion {
-        /// The implementation default, which is unknown to this layer of abstraction.
-        Default,
-        TlsV1,
-        SslV2,
-        SslV3,
-        TlsV1_0,
-        TlsV1_1,
-        TlsV1_2,
-        TlsV1_3,
-    }
-
-    /// Available HTTP version numbers.
-    #[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd)]
-    #[allow(missing_docs)]
-    pub enum HttpVersion {
-        /// Equivalent to HTTP/1.1
-        V1_1,
-        /// Equivalent to HTTP/2
-        V2,
-    }
-
-    /// The desired range of acceptable SSL versions, or the single version to allow if both are set to the same value.
-    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-    pub struct SslVersionRangeInclusive {
-        /// The smallest allowed ssl version to use.
-        pub min: SslVersion,
-        /// The highest allowed ssl version to use.
-        pub max: SslVersion,
-    }
-
-    impl SslVersionRangeInclusive {
-        /// Return `min` and `max` fields in the right order so `min` is smaller or equal to `max`.
-        pub fn min_max(&self) -> (SslVersion, SslVersion) {
-            if self.min > self.max {
-                (self.max, self.min)
-            } else {
-                (self.min, self.max)
-            }
-        }
-    }
-}
-
-/// Options to configure http requests.
-// TODO: testing most of these fields requires a lot of effort, unless special flags to introspect ongoing requests are added.
-#[derive(Clone)]
-pub struct Options {
-    /// Headers to be added to every request.
-    /// They are applied unconditionally and are expected to be valid as they occur in an HTTP request, like `header: value`, without newlines.
-    ///
-    /// Refers to `http.extraHeader` multi-var.
-    pub extra_headers: Vec<String>,
-    /// How to handle redirects.
-    ///
-    /// Refers to `http.followRedirects`.
-    pub follow_redirects: options::FollowRedirects,
-    /// Used in conjunction with `low_speed_time_seconds`, any non-0 value signals the amount of bytes per second at least to avoid
-    /// aborting the connection.
-    ///
-    /// Refers to `http.lowSpeedLimit`.
-    pub low_speed_limit_bytes_per_second: u32,
-    /// Used in conjunction with `low_speed_bytes_per_second`, any non-0 value signals the amount seconds the minimal amount
-    /// of bytes per second isn't reached.
-    ///
-    /// Refers to `http.lowSpeedTime`.
-    pub low_speed_time_seconds: u64,
-    /// A curl-style proxy declaration of the form `[protocol://][user[:password]@]proxyhost[:port]`.
-    ///
-    /// Note that an empty string means the proxy is disabled entirely.
-    /// Refers to `http.proxy`.
-    pub proxy: Option<String>,
-    /// The comma-separated list of hosts to not send through the `proxy`, or `*` to entirely disable all proxying.
-    pub no_proxy: Option<String>,
-    /// The way to authenticate against the proxy if the `proxy` field contains a username.
-    ///
-    /// Refers to `http.proxyAuthMethod`.
-    pub proxy_auth_method: options::ProxyAuthMethod,
-    /// If authentication is needed for the proxy as its URL contains a username, this method must be set to provide a password
-    /// for it before making the request, and to store it if the connection succeeds.
-    pub proxy_authenticate: Option<(
-        gix_credentials::helper::Action,
-        Arc<std::sync::Mutex<options::AuthenticateFn>>,
-    )>,
-    /// The `HTTP` `USER_AGENT` string presented to an `HTTP` server, notably not the user agent present to the `git` server.
-    ///
-    /// If not overridden, it defaults to the user agent provided by `curl`, which is a deviation from how `git` handles this.
-    /// Thus it's expected from the callers to set it to their application, or use higher-level crates which make it easy to do this
-    /// more correctly.
-    ///
-    /// Using the correct user-agent might affect how the server treats the request.
-    ///
-    /// Refers to `http.userAgent`.
-    pub user_agent: Option<String>,
-    /// The amount of time we wait until aborting a connection attempt.
-    ///
-    /// If `None`, this typically defaults to 2 minutes to 5 minutes.
-    /// Refers to `gitoxide.http.connectTimeout`.
-    pub connect_timeout: Option<std::time::Duration>,
-    /// If enabled, emit additional information about connections and possibly the data received or written.
-    pub verbose: bool,
-    /// If set, use this path to point to a file with CA certificates to verify peers.
-    pub ssl_ca_info: Option<PathBuf>,
-    /// The SSL version or version range to use, or `None` to let the TLS backend determine which versions are acceptable.
-    pub ssl_version: Option<SslVersionRangeInclusive>,
-    /// Controls whether to perform SSL identity verification or not. Turning this off is not recommended and can lead to
-    /// various security risks. An example where this may be needed is when an internal git server uses a self-signed
-    /// certificate and the user accepts the associated security risks.
-    pub ssl_verify: bool,
-    /// The HTTP version to enforce. If unset, it is implementation defined.
-    pub http_version: Option<HttpVersion>,
-    /// Backend specific options, if available.
-    pub backend: Option<Arc<Mutex<dyn Any + Send + Sync + 'static>>>,
-}
-
-impl Default for Options {
-    fn default() -> Self {
-        Options {
-            extra_headers: vec![],
-            follow_redirects: Default::default(),
-            low_speed_limit_bytes_per_second: 0,
-            low_speed_time_seconds: 0,
-            proxy: None,
-            no_proxy: None,
-            proxy_auth_method: Default::default(),
-            proxy_authenticate: None,
-            user_agent: None,
-            connect_timeout: None,
-            verbose: false,
-            ssl_ca_info: None,
-            ssl_version: None,
-            ssl_verify: true,
-            http_version: None,
-            backend: None,
-        }
-    }
-}
-
 /// The actual http client implementation, using curl
 #[cfg(feature = "http-client-curl")]
 pub type Impl = curl::Curl;
@@ -228,6 +49,9 @@ pub struct Transport<H: Http> {
     http: H,
     service: Option<Service>,
     line_provider: Option<gix_packetline::StreamingPeekableIter<H::ResponseBody>>,
+    /// The reference advertisement we made up from the files of a server that only supports the *dumb* protocol,
+    /// which is also the indicator for talking to such a server.
+    dumb_advertisement: Option<gix_packetline::StreamingPeekableIter<std::io::Cursor<Vec<u8>>>>,
     identity: Option<gix_sec::identity::Account>,
     trace: bool,
 }
@@ -252,6 +76,7 @@ impl<H: Http> Transport<H> {
             service: None,
             http,
             line_provider: None,
+            dumb_advertisement: None,
             identity,
             trace,
         }
@@ -278,47 +103,68 @@ impl Transport<Impl> {
 
 impl<H: Http> Transport<H> {
     fn check_content_type(service: Service, kind: &str, headers: <H as Http>::Headers) -> Result<(), client::Error> {
-        let wanted_content_type = format!("application/x-{}-{}", service.as_str(), kind);
-        if !headers.lines().collect::<Result<Vec<_>, _>>()?.iter().any(|l| {
-            let mut tokens = l.split(':');
-            tokens.next().zip(tokens.next()).map_or(false, |(name, value)| {
-                name.eq_ignore_ascii_case("content-type") && value.trim() == wanted_content_type
-            })
-        }) {
-            return Err(client::Error::Http(Error::Detail {
-                description: format!(
-                    "Didn't find '{wanted_content_type}' header to indicate 'smart' protocol, and 'dumb' protocol is not supported."
-                ),
-            }));
-        }
-        Ok(())
+        let lines = headers.lines().collect::<Result<Vec<_>, _>>()?;
+        http::check_content_type(service, kind, lines.iter().map(String::as_str))
     }
 
-    #[allow(clippy::unnecessary_wraps, unknown_lints)]
     fn add_basic_auth_if_present(&self, headers: &mut Vec<Cow<'_, str>>) -> Result<(), client::Error> {
-        if let Some(gix_sec::identity::Account { username, password }) = &self.identity {
-            #[cfg(not(debug_assertions))]
-            if self.url.starts_with("http://") {
-                return Err(client::Error::AuthenticationRefused(
-                    "Will not send credentials in clear text over http",
-                ));
+        http::add_basic_auth_if_present(&self.url, self.identity.as_ref(), headers)
+    }
+
+    /// Produce the reference advertisement from `info_refs` and the `HEAD` file of a server that only supports the *dumb* protocol.
+    fn handshake_dumb(
+        &mut self,
+        service: Service,
+        mut info_refs: H::ResponseBody,
+    ) -> Result<client::SetServiceResponse<'_>, client::Error> {
+        let mut buf = Vec::new();
+        info_refs.read_to_end(&mut buf)?;
+        let head = match dumb::Files::get(self, "HEAD")? {
+            Some(mut head) => {
+                let mut buf = Vec::new();
+                head.read_to_end(&mut buf)?;
+                Some(buf)
             }
-            headers.push(Cow::Owned(format!(
-                "Authorization: Basic {}",
-                base64::engine::general_purpose::STANDARD.encode(format!("{username}:{password}"))
-            )));
-        }
-        Ok(())
+            None => None,
+        };
+        let advertisement = dumb::advertisement(&buf, head.as_deref())?;
+        let line_reader = self
+            .dumb_advertisement
+            .insert(gix_packetline::StreamingPeekableIter::new(
+                std::io::Cursor::new(advertisement),
+                &[PacketLineRef::Flush],
+                self.trace,
+            ));
+        let capabilities::recv::Outcome {
+            capabilities,
+            refs,
+            protocol: actual_protocol,
+        } = Capabilities::from_lines_with_version_detection(line_reader)?;
+        self.actual_version = actual_protocol;
+        self.service = Some(service);
+        Ok(client::SetServiceResponse {
+            actual_protocol,
+            capabilities,
+            refs,
+        })
     }
 }
 
-fn append_url(base: &str, suffix: &str) -> String {
-    let mut buf = base.to_owned();
-    if base.as_bytes().last() != Some(&b'/') {
-        buf.push('/');
+impl<H: Http> dumb::Files for Transport<H> {
+    fn get(&mut self, path: &str) -> Result<Option<Box<dyn BufRead + '_>>, client::Error> {
+        let url = http::append_url(&self.url, path);
+        let static_headers = [Cow::Borrowed(self.user_agent_header)];
+        let mut dynamic_headers = Vec::<Cow<'_, str>>::new();
+        self.add_basic_auth_if_present(&mut dynamic_headers)?;
+        let GetResponse { headers, body } =
+            self.http
+                .get(url.as_ref(), &self.url, static_headers.iter().chain(&dynamic_headers))?;
+        match headers.lines().collect::<Result<Vec<_>, _>>() {
+            Ok(_) => Ok(Some(Box::new(body))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
     }
-    buf.push_str(suffix);
-    buf
 }
 
 impl<H: Http> client::TransportWithoutIO for Transport<H> {
@@ -334,7 +180,13 @@ impl<H: Http> client::TransportWithoutIO for Transport<H> {
         trace: bool,
     ) -> Result<RequestWriter<'_>, client::Error> {
         let service = self.service.expect("handshake() must have been called first");
-        let url = append_url(&self.url, service.as_str());
+        if self.dumb_advertisement.is_some() {
+            return Err(client::Error::Http(Error::Detail {
+                description: "The server only supports the 'dumb' protocol, which can't be used to make requests"
+                    .into(),
+            }));
+        }
+        let url = http::append_url(&self.url, service.as_str());
         let static_headers = &[
             Cow::Borrowed(self.user_agent_header),
             Cow::Owned(format!("Content-Type: application/x-{}-request", service.as_str())),
@@ -396,36 +248,23 @@ impl<H: Http> client::Transport for Transport<H> {
         service: Service,
         extra_parameters: &'a [(&'a str, Option<&'a str>)],
     ) -> Result<client::SetServiceResponse<'_>, client::Error> {
-        let url = append_url(self.url.as_ref(), &format!("info/refs?service={}", service.as_str()));
+        let url = http::append_url(self.url.as_ref(), &format!("info/refs?service={}", service.as_str()));
         let static_headers = [Cow::Borrowed(self.user_agent_header)];
         let mut dynamic_headers = Vec::<Cow<'_, str>>::new();
-        if self.desired_version != Protocol::V1 || !extra_parameters.is_empty() {
-            let mut parameters = if self.desired_version != Protocol::V1 {
-                let mut p = format!("version={}", self.desired_version as usize);
-                if !extra_parameters.is_empty() {
-                    p.push(':');
-                }
-                p
-            } else {
-                String::new()
-            };
-            parameters.push_str(
-                &extra_parameters
-                    .iter()
-                    .map(|(key, value)| match value {
-                        Some(value) => format!("{key}={value}"),
-                        None => key.to_string(),
-                    })
-                    .collect::<Vec<_>>()
-                    .join(":"),
-            );
-            dynamic_headers.push(format!("Git-Protocol: {parameters}").into());
+        if let Some(header) = http::git_protocol_header(self.desired_version, extra_parameters) {
+            dynamic_headers.push(header.into());
         }
         self.add_basic_auth_if_present(&mut dynamic_headers)?;
         let GetResponse { headers, body } =
             self.http
                 .get(url.as_ref(), &self.url, static_headers.iter().chain(&dynamic_headers))?;
-        <Transport<H>>::check_content_type(service, "advertisement", headers)?;
+        let headers = headers.lines().collect::<Result<Vec<_>, _>>()?;
+        let headers = headers.iter().map(String::as_str);
+        // Like `git`, assume a server that doesn't speak the smart protocol serves static files.
+        if service == Service::UploadPack && !http::has_content_type(service, "advertisement", headers.clone()) {
+            return self.handshake_dumb(service, body);
+        }
+        http::check_content_type(service, "advertisement", headers)?;
 
         let line_reader = self.line_provider.get_or_insert_with(|| {
             gix_packetline::StreamingPeekableIter::new(body, &[PacketLineRef::Flush], self.trace)
@@ -466,6 +305,14 @@ impl<H: Http> client::Transport for Transport<H> {
             refs,
         })
     }
+
+    fn dumb_files(&mut self) -> Option<&mut dyn dumb::Files> {
+        if self.dumb_advertisement.is_some() {
+            Some(self)
+        } else {
+            None
+        }
+    }
 }
 
 struct HeadersThenBody<H: Http, B: Unpin> {
@@ -551,6 +398,5 @@ pub fn connect(url: gix_url::Url, desired_version: Protocol, trace: bool) -> Tra
     Transport::new(url, desired_version, trace)
 }
 
-///
 #[cfg(any(feature = "http-client-curl", feature = "http-client-reqwest"))]
-pub mod redirect;
+pub use crate::client::non_io_types::http::redirect;
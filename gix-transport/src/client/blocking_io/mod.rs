@@ -1,6 +1,9 @@
 ///
 pub mod connect;
 
+///
+pub mod dumb;
+
 ///
 pub mod file;
 ///
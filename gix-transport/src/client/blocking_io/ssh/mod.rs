@@ -35,6 +35,10 @@ pub enum ProgramKind {
 
 mod program_kind;
 
+///
+#[cfg(feature = "ssh-client-native")]
+pub mod native;
+
 ///
 pub mod invocation {
     use std::ffi::OsString;
@@ -77,6 +81,9 @@ pub mod connect {
         /// when invoking the program.
         /// If unset, the `program` basename determines the variant, or an invocation of the `command` itself.
         pub kind: Option<ProgramKind>,
+        /// If `true`, use the in-process ssh client instead of invoking a program, which ignores all other fields.
+        /// This requires the `ssh-client-native` feature, and connecting fails without it.
+        pub native: bool,
     }
 
     impl Options {
@@ -0,0 +1,145 @@
+//! A parser for the subset of `ssh_config` relevant to us, as described in
+//! [ssh_config(5)](https://man.openbsd.org/ssh_config).
+
+use std::path::{Path, PathBuf};
+
+use super::StrictHostKeyChecking;
+
+/// The configuration for a single host, as obtained from all matching `Host` sections.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Host {
+    /// The actual name of the host to connect to, from `HostName`.
+    pub host_name: Option<String>,
+    /// The port to connect to, from `Port`.
+    pub port: Option<u16>,
+    /// The user to authenticate as, from `User`.
+    pub user: Option<String>,
+    /// The private keys to try, from all `IdentityFile` entries.
+    pub identity_files: Vec<PathBuf>,
+    /// If `true`, only the `identity_files` are used and keys from the agent are ignored, from `IdentitiesOnly`.
+    pub identities_only: Option<bool>,
+    /// The files with known host keys, from `UserKnownHostsFile`.
+    pub user_known_hosts_files: Vec<PathBuf>,
+    /// How to treat unknown host keys, from `StrictHostKeyChecking`.
+    pub strict_host_key_checking: Option<StrictHostKeyChecking>,
+}
+
+impl Host {
+    /// Parse `config` and return the configuration for the host named `alias` as it appears in URLs,
+    /// expanding `~` to `home_dir` in paths.
+    ///
+    /// Just like `ssh`, the first value obtained for each keyword wins, except for `IdentityFile` which accumulates.
+    /// `Match` blocks are skipped, as are `Include` directives.
+    pub fn from_config(config: &str, alias: &str, home_dir: Option<&Path>) -> Host {
+        let mut host = Host::default();
+        let mut is_active = true;
+        for line in config.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (keyword, args) = match line.find(|c: char| c.is_whitespace() || c == '=') {
+                Some(pos) => (
+                    &line[..pos],
+                    line[pos..]
+                        .trim_start_matches(|c: char| c.is_whitespace() || c == '=')
+                        .trim(),
+                ),
+                None => (line, ""),
+            };
+            let keyword = keyword.to_ascii_lowercase();
+            match keyword.as_str() {
+                "host" => {
+                    is_active = host_matches(args.split_whitespace(), alias);
+                    continue;
+                }
+                "match" => {
+                    is_active = false;
+                    continue;
+                }
+                _ if !is_active => continue,
+                _ => {}
+            }
+            let first_arg = args.split_whitespace().next().map(unquote).unwrap_or_default();
+            match keyword.as_str() {
+                "hostname" if host.host_name.is_none() => host.host_name = Some(first_arg.replace("%h", alias)),
+                "port" if host.port.is_none() => host.port = first_arg.parse().ok(),
+                "user" if host.user.is_none() => host.user = Some(first_arg.to_owned()),
+                "identityfile" => host.identity_files.push(expand_path(first_arg, alias, home_dir)),
+                "identitiesonly" if host.identities_only.is_none() => host.identities_only = parse_bool(first_arg),
+                "userknownhostsfile" if host.user_known_hosts_files.is_empty() => {
+                    host.user_known_hosts_files = args
+                        .split_whitespace()
+                        .map(|path| expand_path(unquote(path), alias, home_dir))
+                        .collect();
+                }
+                "stricthostkeychecking" if host.strict_host_key_checking.is_none() => {
+                    host.strict_host_key_checking = match first_arg.to_ascii_lowercase().as_str() {
+                        "yes" | "ask" => Some(StrictHostKeyChecking::Yes),
+                        "accept-new" => Some(StrictHostKeyChecking::AcceptNew),
+                        "no" | "off" => Some(StrictHostKeyChecking::No),
+                        _ => None,
+                    };
+                }
+                _ => {}
+            }
+        }
+        host
+    }
+}
+
+/// Return `true` if any of the `patterns` matches `host` and none of the negated ones does.
+pub(crate) fn host_matches<'a>(patterns: impl IntoIterator<Item = &'a str>, host: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        match pattern.strip_prefix('!') {
+            Some(pattern) => {
+                if wildmatch(pattern.as_bytes(), host.as_bytes()) {
+                    return false;
+                }
+            }
+            None => matched |= wildmatch(pattern.as_bytes(), host.as_bytes()),
+        }
+    }
+    matched
+}
+
+/// Match `text` against `pattern` case-insensitively, where `*` matches any amount of characters and `?` matches exactly one.
+fn wildmatch(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|skip| wildmatch(rest, &text[skip..])),
+        Some((b'?', rest)) => !text.is_empty() && wildmatch(rest, &text[1..]),
+        Some((c, rest)) => text
+            .split_first()
+            .map_or(false, |(t, text)| c.eq_ignore_ascii_case(t) && wildmatch(rest, text)),
+    }
+}
+
+fn unquote(arg: &str) -> &str {
+    arg.strip_prefix('"')
+        .and_then(|arg| arg.strip_suffix('"'))
+        .unwrap_or(arg)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "yes" | "true" => Some(true),
+        "no" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Expand `~` and the `%d` and `%h` tokens in `path`.
+fn expand_path(path: &str, alias: &str, home_dir: Option<&Path>) -> PathBuf {
+    let home = home_dir.map(|dir| dir.to_string_lossy().into_owned());
+    let path = match (path.strip_prefix("~/"), &home) {
+        (Some(rest), Some(home)) => format!("{home}/{rest}"),
+        _ => path.to_owned(),
+    };
+    let path = match &home {
+        Some(home) => path.replace("%d", home),
+        None => path,
+    };
+    path.replace("%h", alias).into()
+}
@@ -0,0 +1,82 @@
+//! Verification of host keys using `known_hosts` files, as described in
+//! [sshd(8)](https://man.openbsd.org/sshd#SSH_KNOWN_HOSTS_FILE_FORMAT).
+
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use hmac::{Hmac, Mac};
+use ssh_key::{
+    known_hosts::{HostPatterns, Marker},
+    KnownHosts, PublicKey,
+};
+
+use super::config::host_matches;
+
+/// The result of looking up a host key.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Outcome {
+    /// The key is known for the host.
+    Match,
+    /// The host is known with a different key, or the key was revoked.
+    Mismatch,
+    /// Nothing is known about the host.
+    Unknown,
+}
+
+/// Return the name under which `host` is stored in `known_hosts` files, which includes the `port` if it's not the default.
+pub(crate) fn host_name(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_owned()
+    } else {
+        format!("[{host}]:{port}")
+    }
+}
+
+/// Look up `key` for the host stored as `host` in all `files` that exist.
+pub(crate) fn check(files: &[PathBuf], host: &str, key: &PublicKey) -> io::Result<Outcome> {
+    let mut outcome = Outcome::Unknown;
+    for path in files {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        // Lines we can't parse may use key types we don't know, and are ignored just like `ssh` does.
+        for entry in KnownHosts::new(&content).flatten() {
+            let matches = match entry.host_patterns() {
+                HostPatterns::Patterns(patterns) => host_matches(patterns.iter().map(String::as_str), host),
+                HostPatterns::HashedName { salt, hash } => {
+                    let mut mac = Hmac::<sha1::Sha1>::new_from_slice(salt).expect("any key length works");
+                    mac.update(host.as_bytes());
+                    mac.verify_slice(hash).is_ok()
+                }
+            };
+            if !matches {
+                continue;
+            }
+            let is_same_key = entry.public_key().key_data() == key.key_data();
+            match entry.marker() {
+                Some(Marker::Revoked) if is_same_key => return Ok(Outcome::Mismatch),
+                Some(_) => {}
+                None if is_same_key => outcome = Outcome::Match,
+                None if outcome == Outcome::Unknown => outcome = Outcome::Mismatch,
+                None => {}
+            }
+        }
+    }
+    Ok(outcome)
+}
+
+/// Remember `key` for `host` by appending it to the `known_hosts` file at `path`.
+pub(crate) fn add(path: &std::path::Path, host: &str, key: &PublicKey) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let key = PublicKey::new(key.key_data().clone(), "")
+        .to_openssh()
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{host} {key}")
+}
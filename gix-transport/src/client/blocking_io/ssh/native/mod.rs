@@ -0,0 +1,332 @@
+//! An in-process ssh client which doesn't need an `ssh` program, supporting authentication with keys from files and the ssh agent,
+//! host key verification with `known_hosts` files, and the `Host` sections of the ssh configuration file.
+//!
+//! The ssh protocol itself is implemented by `libssh2`, while the configuration and host key verification match what `ssh` would do.
+//! Host keys must be `ssh-ed25519`, `ecdsa-sha2-nistp256` or `rsa-sha2-*`.
+//! Passphrase-protected keys are skipped as there is no way to ask for the passphrase, so these have to be provided by the agent.
+use std::{any::Any, borrow::Cow, path::PathBuf};
+
+use bstr::{BStr, BString, ByteSlice};
+
+use crate::{
+    client::{self, git, MessageKind, RequestWriter, SetServiceResponse, WriteMode},
+    Protocol, Service,
+};
+
+mod config;
+mod known_hosts;
+mod session;
+
+pub use config::Host;
+
+/// What to do if the key of a host isn't known yet.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum StrictHostKeyChecking {
+    /// Refuse to connect, which is also what happens if `ssh` would ask.
+    #[default]
+    Yes,
+    /// Connect and remember the key of the host in the first `known_hosts` file.
+    AcceptNew,
+    /// Connect without remembering the key of the host.
+    No,
+}
+
+/// The options for use when [connecting][connect()] with the in-process ssh client.
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    /// The ssh configuration file to apply `Host` sections from, like `~/.ssh/config`.
+    pub config_file: Option<PathBuf>,
+    /// The home directory to substitute for `~` in paths of the configuration file.
+    pub home_dir: Option<PathBuf>,
+    /// The files to look up host keys in, unless the configuration names them with `UserKnownHostsFile`.
+    /// New keys are added to the first one.
+    pub known_hosts_files: Vec<PathBuf>,
+    /// The private keys to try, unless the configuration names them with `IdentityFile`. Files that don't exist are skipped.
+    pub identity_files: Vec<PathBuf>,
+    /// If `true`, try the keys of the ssh agent found via the `SSH_AUTH_SOCK` environment variable before trying `identity_files`.
+    pub use_agent: bool,
+    /// What to do with keys of unknown hosts, unless the configuration says otherwise with `StrictHostKeyChecking`.
+    pub strict_host_key_checking: StrictHostKeyChecking,
+    /// The user to authenticate as if neither the URL nor the configuration name one.
+    pub user: Option<String>,
+}
+
+impl Options {
+    /// Return options with the locations `ssh` uses by default, based on the home directory of the current user,
+    /// and with the agent if the `SSH_AUTH_SOCK` environment variable is set.
+    pub fn from_env() -> Self {
+        let home_dir = std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .map(PathBuf::from);
+        let ssh_dir = home_dir.as_ref().map(|home| home.join(".ssh"));
+        Options {
+            config_file: ssh_dir.as_ref().map(|dir| dir.join("config")),
+            known_hosts_files: ssh_dir
+                .iter()
+                .flat_map(|dir| [dir.join("known_hosts"), dir.join("known_hosts2")])
+                .collect(),
+            identity_files: ssh_dir
+                .iter()
+                .flat_map(|dir| ["id_ed25519", "id_ecdsa", "id_rsa"].map(|name| dir.join(name)))
+                .collect(),
+            use_agent: std::env::var_os("SSH_AUTH_SOCK").is_some(),
+            home_dir,
+            strict_host_key_checking: StrictHostKeyChecking::Yes,
+            user: std::env::var("USER").or_else(|_| std::env::var("USERNAME")).ok(),
+        }
+    }
+}
+
+/// The error used by the in-process ssh client.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The scheme in \"{}\" is not usable for an ssh connection", .0.to_bstring())]
+    UnsupportedScheme(Box<gix_url::Url>),
+    #[error("Could not connect to {host}:{port}")]
+    Connect {
+        source: std::io::Error,
+        host: String,
+        port: u16,
+    },
+    #[error("Could not read the ssh configuration file at '{}'", path.display())]
+    ReadConfig { source: std::io::Error, path: PathBuf },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Ssh(#[from] ssh2::Error),
+    #[error(transparent)]
+    Key(#[from] ssh_key::Error),
+    #[error("The ssh server didn't present a host key")]
+    MissingHostKey,
+    #[error("The host key of '{host}' with fingerprint {fingerprint} is unknown")]
+    UnknownHostKey { host: String, fingerprint: String },
+    #[error("The host key of '{host}' with fingerprint {fingerprint} doesn't match the known one or was revoked - someone may be impersonating the host")]
+    HostKeyMismatch { host: String, fingerprint: String },
+    #[error("Could not determine the user to authenticate as")]
+    MissingUser,
+    #[error("Authentication as '{user}' failed after trying {keys} key(s)")]
+    Authentication { user: String, keys: usize },
+    #[error("The remote command failed with {status}: {message}")]
+    CommandFailed { status: String, message: String },
+}
+
+impl crate::IsSpuriousError for Error {
+    fn is_spurious(&self) -> bool {
+        match self {
+            Error::Io(err) | Error::Connect { source: err, .. } => err.is_spurious(),
+            _ => false,
+        }
+    }
+}
+
+impl Error {
+    fn into_io(self) -> std::io::Error {
+        match self {
+            Error::Io(err) => err,
+            err => std::io::Error::new(std::io::ErrorKind::Other, err),
+        }
+    }
+}
+
+/// A transport connecting to `git` on a remote host using the in-process ssh client, similar to what the `ssh` program would do.
+///
+/// It can only be instantiated using [`connect()`].
+pub struct Connection {
+    url: gix_url::Url,
+    path: BString,
+    desired_version: Protocol,
+    options: Options,
+    session: Option<session::Session>,
+    connection: Option<git::Connection<session::ChannelReader, session::ChannelWriter>>,
+    trace: bool,
+}
+
+impl Connection {
+    /// Connect to the host of our URL and authenticate, according to the ssh configuration and our options.
+    fn open_session(&self) -> Result<session::Session, Error> {
+        let alias = self.url.host().expect("validated on connect");
+        let host = match &self.options.config_file {
+            Some(path) => match std::fs::read_to_string(path) {
+                Ok(config) => Host::from_config(&config, alias, self.options.home_dir.as_deref()),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Host::default(),
+                Err(source) => {
+                    return Err(Error::ReadConfig {
+                        source,
+                        path: path.clone(),
+                    })
+                }
+            },
+            None => Host::default(),
+        };
+        let host_name = host.host_name.as_deref().unwrap_or(alias);
+        let port = self.url.port.or(host.port).unwrap_or(22);
+        let user = self
+            .url
+            .user()
+            .map(ToOwned::to_owned)
+            .or(host.user)
+            .or_else(|| self.options.user.clone())
+            .ok_or(Error::MissingUser)?;
+
+        let known_hosts_files = if host.user_known_hosts_files.is_empty() {
+            &self.options.known_hosts_files
+        } else {
+            &host.user_known_hosts_files
+        };
+        let strict_host_key_checking = host
+            .strict_host_key_checking
+            .unwrap_or(self.options.strict_host_key_checking);
+        let session = session::Session::connect(host_name, port, |key| {
+            let host = known_hosts::host_name(host_name, port);
+            let fingerprint = key.fingerprint(Default::default()).to_string();
+            match known_hosts::check(known_hosts_files, &host, key)? {
+                known_hosts::Outcome::Match => Ok(()),
+                known_hosts::Outcome::Mismatch => Err(Error::HostKeyMismatch { host, fingerprint }),
+                known_hosts::Outcome::Unknown => match strict_host_key_checking {
+                    StrictHostKeyChecking::Yes => Err(Error::UnknownHostKey { host, fingerprint }),
+                    StrictHostKeyChecking::AcceptNew => {
+                        if let Some(path) = known_hosts_files.first() {
+                            known_hosts::add(path, &host, key)?;
+                        }
+                        Ok(())
+                    }
+                    StrictHostKeyChecking::No => Ok(()),
+                },
+            }
+        })?;
+
+        let identity_files = if host.identity_files.is_empty() {
+            &self.options.identity_files
+        } else {
+            &host.identity_files
+        };
+        let mut identities = Vec::new();
+        if self.options.use_agent && host.identities_only != Some(true) {
+            identities.push(session::Identity::Agent);
+        }
+        for path in identity_files.iter().filter(|path| path.is_file()) {
+            match ssh_key::PrivateKey::read_openssh_file(path) {
+                Ok(key) if key.is_encrypted() => {
+                    gix_features::trace::debug!(path = ?path, "skipping passphrase-protected ssh key");
+                }
+                _ => identities.push(session::Identity::File(path.clone())),
+            }
+        }
+        session.authenticate(&user, identities)?;
+        Ok(session)
+    }
+}
+
+impl client::TransportWithoutIO for Connection {
+    fn set_identity(&mut self, identity: gix_sec::identity::Account) -> Result<(), client::Error> {
+        self.url
+            .set_user((!identity.username.is_empty()).then_some(identity.username));
+        Ok(())
+    }
+
+    fn request(
+        &mut self,
+        write_mode: WriteMode,
+        on_into_read: MessageKind,
+        trace: bool,
+    ) -> Result<RequestWriter<'_>, client::Error> {
+        self.connection
+            .as_mut()
+            .expect("handshake() to have been called first")
+            .request(write_mode, on_into_read, trace)
+    }
+
+    fn to_url(&self) -> Cow<'_, BStr> {
+        Cow::Owned(self.url.to_bstring())
+    }
+
+    fn connection_persists_across_multiple_requests(&self) -> bool {
+        true
+    }
+
+    fn configure(&mut self, _config: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        Ok(())
+    }
+}
+
+impl client::Transport for Connection {
+    fn handshake<'a>(
+        &mut self,
+        service: Service,
+        extra_parameters: &'a [(&'a str, Option<&'a str>)],
+    ) -> Result<SetServiceResponse<'_>, client::Error> {
+        if self.path.trim().first() == Some(&b'-') {
+            return Err(client::Error::AmbiguousPath {
+                path: self.path.clone(),
+            });
+        }
+        let session = self.open_session().map_err(client::Error::SshNative)?;
+        let command = format!(
+            "{} {}",
+            service.as_str(),
+            gix_quote::single(self.path.as_ref()).to_str_lossy()
+        );
+        let version = format!("version={}", self.desired_version as usize);
+        let env: &[_] = if self.desired_version != Protocol::V1 {
+            &[("GIT_PROTOCOL", version.as_str())]
+        } else {
+            &[]
+        };
+        let (reader, writer) = session.exec(&command, env).map_err(client::Error::SshNative)?;
+
+        self.connection = Some(git::Connection::new_for_spawned_process(
+            reader,
+            writer,
+            self.desired_version,
+            self.path.clone(),
+            self.trace,
+        ));
+        self.session = Some(session);
+        self.connection
+            .as_mut()
+            .expect("connection to be there right after setting it")
+            .handshake(service, extra_parameters)
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if let Some(session) = self.session.take() {
+            session.disconnect().ok();
+        }
+    }
+}
+
+/// Connect to the host in `url` using the in-process ssh client configured with `options` to obtain data from the repository
+/// at the path of `url` on the remote.
+///
+/// The `desired_version` is the preferred protocol version when establishing the connection, but note that it can be
+/// downgraded by servers not supporting it.
+/// If `trace` is `true`, all packetlines received or sent will be passed to the facilities of the `gix-trace` crate.
+///
+/// Note that the connection is established only when the [handshake][client::Transport::handshake()] is performed.
+#[allow(clippy::result_large_err)]
+pub fn connect(
+    url: gix_url::Url,
+    desired_version: Protocol,
+    options: Options,
+    trace: bool,
+) -> Result<Connection, Error> {
+    if url.scheme != gix_url::Scheme::Ssh || url.host().is_none() {
+        return Err(Error::UnsupportedScheme(url.into()));
+    }
+    let path = gix_url::expand_path::for_shell(url.path.clone());
+    Ok(Connection {
+        url,
+        path,
+        desired_version,
+        options,
+        session: None,
+        connection: None,
+        trace,
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,161 @@
+//! The ssh session and the channel running the remote command, implemented with [`ssh2`].
+
+use std::{
+    io::{self, Read, Write},
+    net::TcpStream,
+    path::PathBuf,
+};
+
+use bstr::ByteSlice;
+use ssh_key::PublicKey;
+
+use super::Error;
+
+/// The host key algorithms we prefer, limited to the ones we can verify against `known_hosts` files.
+const HOST_KEY_ALGORITHMS: &str = "ssh-ed25519,ecdsa-sha2-nistp256,rsa-sha2-512,rsa-sha2-256";
+
+/// The ways to prove our identity to the server, tried in order.
+pub(crate) enum Identity {
+    /// All keys kept by the ssh agent found via the `SSH_AUTH_SOCK` environment variable.
+    Agent,
+    /// The private key stored in the given file.
+    File(PathBuf),
+}
+
+/// An authenticated ssh session.
+pub(crate) struct Session(ssh2::Session);
+
+impl Session {
+    /// Connect to `host` on `port`, exchange keys and call `verify_host_key` to validate the key the server identified itself with.
+    pub fn connect(
+        host: &str,
+        port: u16,
+        verify_host_key: impl FnOnce(&PublicKey) -> Result<(), Error>,
+    ) -> Result<Self, Error> {
+        let stream = TcpStream::connect((host, port)).map_err(|source| Error::Connect {
+            source,
+            host: host.into(),
+            port,
+        })?;
+        stream.set_nodelay(true).ok();
+        let mut session = ssh2::Session::new()?;
+        session.method_pref(ssh2::MethodType::HostKey, HOST_KEY_ALGORITHMS)?;
+        session.set_tcp_stream(stream);
+        session.handshake()?;
+        let (host_key, _) = session.host_key().ok_or(Error::MissingHostKey)?;
+        verify_host_key(&PublicKey::from_bytes(host_key)?)?;
+        Ok(Session(session))
+    }
+
+    /// Authenticate as `user` using all `identities` in order until one is accepted.
+    pub fn authenticate(&self, user: &str, identities: impl IntoIterator<Item = Identity>) -> Result<(), Error> {
+        let mut tried = 0;
+        for identity in identities {
+            match identity {
+                Identity::Agent => {
+                    let Ok(mut agent) = self.0.agent() else { continue };
+                    if agent.connect().and_then(|()| agent.list_identities()).is_err() {
+                        continue;
+                    }
+                    for key in agent.identities().unwrap_or_default() {
+                        tried += 1;
+                        if agent.userauth(user, &key).is_ok() {
+                            agent.disconnect().ok();
+                            return Ok(());
+                        }
+                    }
+                    agent.disconnect().ok();
+                }
+                Identity::File(path) => {
+                    tried += 1;
+                    if self.0.userauth_pubkey_file(user, None, &path, None).is_ok() {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        Err(Error::Authentication {
+            user: user.into(),
+            keys: tried,
+        })
+    }
+
+    /// Open a channel and execute `command` on it after setting the given `env`ironment variables, which the server may ignore.
+    /// Return the reading and the writing end of the channel.
+    pub fn exec(&self, command: &str, env: &[(&str, &str)]) -> Result<(ChannelReader, ChannelWriter), Error> {
+        let mut channel = self.0.channel_session()?;
+        for (name, value) in env {
+            // Servers only accept variables they are configured for, just like with `SendEnv` of the `ssh` program.
+            channel.setenv(name, value).ok();
+        }
+        channel.exec(command)?;
+        let writer = ChannelWriter(channel.stream(0));
+        Ok((
+            ChannelReader {
+                channel,
+                is_done: false,
+            },
+            writer,
+        ))
+    }
+
+    /// Tell the server that we are done.
+    pub fn disconnect(&self) -> Result<(), Error> {
+        Ok(self.0.disconnect(None, "", None)?)
+    }
+}
+
+/// The part of the channel that reads the output of the executed command.
+pub(crate) struct ChannelReader {
+    channel: ssh2::Channel,
+    /// If `true`, the command's output was read entirely and its exit status was checked.
+    is_done: bool,
+}
+
+impl ChannelReader {
+    /// Wait for the command to exit after it closed its output, and fail if it didn't succeed.
+    fn finish(&mut self) -> Result<(), Error> {
+        self.is_done = true;
+        let mut stderr = Vec::new();
+        self.channel.stderr().read_to_end(&mut stderr)?;
+        io::stderr().write_all(&stderr).ok();
+        self.channel.wait_close()?;
+        let status = self.channel.exit_status()?;
+        match (status, self.channel.exit_signal()?.exit_signal) {
+            (0, None) => Ok(()),
+            (status, signal) => Err(Error::CommandFailed {
+                status: signal.map_or_else(|| format!("status {status}"), |signal| format!("signal {signal}")),
+                message: stderr
+                    .lines()
+                    .next_back()
+                    .unwrap_or_default()
+                    .to_str_lossy()
+                    .into_owned(),
+            }),
+        }
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.channel.read(buf)?;
+        if n == 0 && !buf.is_empty() && !self.is_done {
+            self.finish().map_err(Error::into_io)?;
+        }
+        Ok(n)
+    }
+}
+
+/// The part of the channel that writes to the input of the executed command.
+pub(crate) struct ChannelWriter(ssh2::Stream);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // Flushing an `ssh2::Stream` discards data that wasn't read yet, and writes are sent right away anyway.
+        Ok(())
+    }
+}
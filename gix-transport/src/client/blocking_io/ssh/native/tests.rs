@@ -0,0 +1,479 @@
+type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+mod config {
+    use std::path::{Path, PathBuf};
+
+    use crate::client::ssh::native::{Host, StrictHostKeyChecking};
+
+    const CONFIG: &str = r#"
+# comment
+Host *.example.com !secret.example.com
+    HostName %h.internal
+    Port 2222
+
+Host gh github
+    HostName github.com
+    User git
+    IdentityFile ~/.ssh/id_github
+    IdentitiesOnly yes
+
+Match host github.com
+    User ignored
+
+Host *
+    User=fallback
+    Port 22
+    IdentityFile "%d/.ssh/id_ed25519"
+    UserKnownHostsFile ~/.ssh/known_hosts /etc/known_hosts
+    StrictHostKeyChecking accept-new
+"#;
+
+    fn host(alias: &str) -> Host {
+        Host::from_config(CONFIG, alias, Some(Path::new("/home/user")))
+    }
+
+    #[test]
+    fn first_value_wins_and_identity_files_accumulate() {
+        assert_eq!(
+            host("gh"),
+            Host {
+                host_name: Some("github.com".into()),
+                port: Some(22),
+                user: Some("git".into()),
+                identity_files: vec![
+                    PathBuf::from("/home/user/.ssh/id_github"),
+                    PathBuf::from("/home/user/.ssh/id_ed25519")
+                ],
+                identities_only: Some(true),
+                user_known_hosts_files: vec!["/home/user/.ssh/known_hosts".into(), "/etc/known_hosts".into()],
+                strict_host_key_checking: Some(StrictHostKeyChecking::AcceptNew),
+            },
+            "`Match` blocks are skipped"
+        );
+    }
+
+    #[test]
+    fn wildcards_and_negations() {
+        let h = host("repo.example.com");
+        assert_eq!(h.host_name.as_deref(), Some("repo.example.com.internal"));
+        assert_eq!(h.port, Some(2222));
+        assert_eq!(h.user.as_deref(), Some("fallback"));
+
+        let h = host("secret.example.com");
+        assert_eq!(h.host_name, None, "negated patterns exclude hosts");
+        assert_eq!(h.port, Some(22));
+
+        assert_eq!(host("GH").user.as_deref(), Some("git"), "matching ignores case");
+    }
+
+    #[test]
+    fn empty_config() {
+        assert_eq!(Host::from_config("", "host", None), Host::default());
+    }
+}
+
+mod known_hosts {
+    use ssh_key::{rand_core::OsRng, Algorithm, PrivateKey};
+
+    use crate::client::ssh::native::known_hosts::{add, check, host_name, Outcome};
+
+    #[test]
+    fn non_default_ports_are_part_of_the_host_name() {
+        assert_eq!(host_name("example.com", 22), "example.com");
+        assert_eq!(host_name("example.com", 2222), "[example.com]:2222");
+    }
+
+    #[test]
+    fn lookup_with_patterns_hashes_and_markers() -> super::Result {
+        let dir = tempfile::tempdir()?;
+        let key = PrivateKey::random(&mut OsRng, Algorithm::Ed25519)?.public_key().clone();
+        let other = PrivateKey::random(&mut OsRng, Algorithm::Ed25519)?.public_key().clone();
+        let files = [dir.path().join("missing"), dir.path().join("known_hosts")];
+
+        assert_eq!(check(&files, "example.com", &key)?, Outcome::Unknown);
+        add(&files[1], "example.com", &key)?;
+        assert_eq!(check(&files, "example.com", &key)?, Outcome::Match);
+        assert_eq!(check(&files, "[example.com]:2222", &key)?, Outcome::Unknown);
+        assert_eq!(check(&files, "example.com", &other)?, Outcome::Mismatch);
+
+        // `|1|base64(salt)|base64(hmac-sha1(salt, host))` for `hashed.example.com`.
+        let hashed = "|1|c2FsdA==|upyg0BYhjcnX9vDmiWmWGAnZB4o=";
+        std::fs::write(
+            &files[1],
+            format!(
+                "{hashed} {key}\n*.example.org,!evil.example.org {key}\n@revoked * {other}\n",
+                key = key.to_openssh()?,
+                other = other.to_openssh()?
+            ),
+        )?;
+        assert_eq!(check(&files, "hashed.example.com", &key)?, Outcome::Match);
+        assert_eq!(check(&files, "git.example.org", &key)?, Outcome::Match);
+        assert_eq!(check(&files, "evil.example.org", &key)?, Outcome::Unknown);
+        assert_eq!(
+            check(&files, "git.example.org", &other)?,
+            Outcome::Mismatch,
+            "revoked keys are never accepted"
+        );
+        Ok(())
+    }
+}
+
+mod connect {
+    use std::io::Write;
+
+    use crate::{
+        client::{ssh::native, Transport},
+        Protocol, Service,
+    };
+
+    #[test]
+    fn unsupported_urls() {
+        let url = gix_url::parse("https://example.com/repo".into()).expect("valid");
+        assert!(matches!(
+            native::connect(url, Protocol::V2, Default::default(), false),
+            Err(native::Error::UnsupportedScheme(_))
+        ));
+    }
+
+    #[test]
+    fn servers_that_do_not_speak_ssh_are_rejected() -> super::Result {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let port = listener.local_addr()?.port();
+        let server = std::thread::spawn(move || -> std::io::Result<()> {
+            let (mut stream, _) = listener.accept()?;
+            stream.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n")
+        });
+        let url = gix_url::parse(format!("ssh://git@127.0.0.1:{port}/repo.git").as_str().into())?;
+        let err = native::connect(url, Protocol::V2, Default::default(), false)?
+            .handshake(Service::UploadPack, &[])
+            .map(|_| ())
+            .expect_err("not an ssh server");
+        assert!(
+            matches!(err, crate::client::Error::SshNative(native::Error::Ssh(_))),
+            "{err:?}"
+        );
+        server.join().expect("no panic")?;
+        Ok(())
+    }
+}
+
+/// Tests against an `sshd` program which is started for each test, and which are skipped if it isn't installed.
+///
+/// `sshd` runs as the current user, which is also the one to authenticate as, and executes commands with their shell.
+#[cfg(unix)]
+mod sshd {
+    use std::{
+        io::{Read, Write},
+        path::{Path, PathBuf},
+        process::{Child, Command, Stdio},
+        time::{Duration, Instant},
+    };
+
+    use ssh_key::{rand_core::OsRng, Algorithm, LineEnding, PrivateKey, PublicKey};
+
+    use crate::{
+        client::{
+            ssh::native::{
+                self,
+                session::{Identity, Session},
+                Error, StrictHostKeyChecking,
+            },
+            Transport,
+        },
+        Protocol, Service,
+    };
+
+    fn identity(dir: &Path, name: &str) -> super::Result<(PathBuf, PrivateKey)> {
+        let key = PrivateKey::random(&mut OsRng, Algorithm::Ed25519)?;
+        let path = dir.join(name);
+        key.write_openssh_file(&path, LineEnding::LF)?;
+        Ok((path, key))
+    }
+
+    fn git(dir: &Path, args: &[&str]) -> super::Result {
+        let status = Command::new("git")
+            .args([
+                "-c",
+                "user.name=a",
+                "-c",
+                "user.email=a@example.com",
+                "-c",
+                "init.defaultBranch=main",
+            ])
+            .args(args)
+            .current_dir(dir)
+            .stdout(Stdio::null())
+            .status()?;
+        assert!(status.success(), "git {args:?} failed");
+        Ok(())
+    }
+
+    struct Fixture {
+        dir: tempfile::TempDir,
+        sshd: Child,
+        port: u16,
+        host_key: PublicKey,
+        user: String,
+        /// A repository with a single commit on `main`.
+        repo: PathBuf,
+        options: native::Options,
+    }
+
+    impl Fixture {
+        /// Start `sshd` to accept the key `id_ed25519`, or return `None` if it isn't installed.
+        fn new() -> super::Result<Option<Self>> {
+            let Some(sshd) = std::env::var_os("PATH")
+                .iter()
+                .flat_map(std::env::split_paths)
+                .chain(["/usr/sbin".into(), "/usr/local/sbin".into()])
+                .map(|dir| dir.join("sshd"))
+                .find(|path| path.is_file())
+            else {
+                eprintln!("Skipping test as `sshd` isn't installed");
+                return Ok(None);
+            };
+            let dir = tempfile::tempdir()?;
+            let (wrong_key, _) = identity(dir.path(), "id_wrong")?;
+            let (right_key, key) = identity(dir.path(), "id_ed25519")?;
+            let (host_key_path, host_key) = identity(dir.path(), "host_key")?;
+            let authorized_keys = dir.path().join("authorized_keys");
+            std::fs::write(&authorized_keys, format!("{}\n", key.public_key().to_openssh()?))?;
+
+            let port = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+            let config = dir.path().join("sshd_config");
+            std::fs::write(
+                &config,
+                format!(
+                    "ListenAddress 127.0.0.1:{port}\nHostKey {}\nAuthorizedKeysFile {}\nPidFile none\nStrictModes no\n\
+                     UsePAM no\nPasswordAuthentication no\nKbdInteractiveAuthentication no\nAcceptEnv GIT_PROTOCOL\n",
+                    host_key_path.display(),
+                    authorized_keys.display()
+                ),
+            )?;
+            let sshd = Command::new(sshd)
+                .args(["-D", "-e", "-f"])
+                .arg(&config)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()?;
+
+            let repo = dir.path().join("repo");
+            std::fs::create_dir(&repo)?;
+            git(&repo, &["init", "-q"])?;
+            git(&repo, &["commit", "-q", "--allow-empty", "-m", "first"])?;
+
+            let user = Command::new("id").arg("-un").output()?.stdout;
+            let known_hosts = dir.path().join("known_hosts");
+            std::fs::write(
+                &known_hosts,
+                format!("[127.0.0.1]:{port} {}\n", host_key.public_key().to_openssh()?),
+            )?;
+            let fixture = Fixture {
+                options: native::Options {
+                    known_hosts_files: vec![known_hosts],
+                    identity_files: vec![dir.path().join("missing"), wrong_key, right_key],
+                    ..Default::default()
+                },
+                dir,
+                sshd,
+                port,
+                host_key: host_key.public_key().clone(),
+                user: String::from_utf8(user)?.trim().to_owned(),
+                repo,
+            };
+
+            let start = Instant::now();
+            while std::net::TcpStream::connect(("127.0.0.1", port)).is_err() {
+                assert!(start.elapsed() < Duration::from_secs(10), "sshd didn't start listening");
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Ok(Some(fixture))
+        }
+
+        fn connect(&self, path: &Path, version: Protocol) -> native::Connection {
+            let url = gix_url::parse(
+                format!("ssh://{}@127.0.0.1:{}{}", self.user, self.port, path.display())
+                    .as_str()
+                    .into(),
+            )
+            .expect("valid url");
+            native::connect(url, version, self.options.clone(), false).expect("valid url")
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            self.sshd.kill().ok();
+            self.sshd.wait().ok();
+        }
+    }
+
+    fn native_error(err: crate::client::Error) -> Error {
+        match err {
+            crate::client::Error::SshNative(err) => err,
+            err => panic!("unexpected error: {err:?}"),
+        }
+    }
+
+    #[test]
+    fn handshake_v2_sends_protocol_version_in_environment() -> super::Result {
+        let Some(fixture) = Fixture::new()? else { return Ok(()) };
+        let mut connection = fixture.connect(&fixture.repo, Protocol::V2);
+        let res = connection.handshake(Service::UploadPack, &[])?;
+        assert_eq!(
+            res.actual_protocol,
+            Protocol::V2,
+            "keys that can't be read are skipped, the wrong one is rejected, and the server is asked for V2"
+        );
+        assert!(res.capabilities.contains("fetch"));
+        assert!(res.refs.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn handshake_v1_provides_refs() -> super::Result {
+        let Some(fixture) = Fixture::new()? else { return Ok(()) };
+        let mut connection = fixture.connect(&fixture.repo, Protocol::V1);
+        let mut res = connection.handshake(Service::ReceivePack, &[])?;
+        assert_eq!(res.actual_protocol, Protocol::V1);
+        assert!(res.capabilities.contains("report-status"));
+        let mut refs = String::new();
+        res.refs.take().expect("refs in V1").read_to_string(&mut refs)?;
+        assert!(refs.contains(" refs/heads/main\n"), "{refs}");
+        Ok(())
+    }
+
+    #[test]
+    fn failing_remote_commands_report_their_last_error_line() -> super::Result {
+        let Some(fixture) = Fixture::new()? else { return Ok(()) };
+        let path = fixture.dir.path().join("missing.git");
+        let err = fixture
+            .connect(&path, Protocol::V2)
+            .handshake(Service::UploadPack, &[])
+            .map(|_| ())
+            .expect_err("the command failed");
+        assert_eq!(
+            err.to_string(),
+            "An IO error occurred when talking to the server",
+            "it's an IO error as the failure is noticed while reading"
+        );
+        let source = std::error::Error::source(&err).expect("IO error").to_string();
+        assert_eq!(
+            source,
+            format!(
+                "The remote command failed with status 128: fatal: '{}' does not appear to be a git repository",
+                path.display()
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn host_keys_are_verified() -> super::Result {
+        let Some(mut fixture) = Fixture::new()? else {
+            return Ok(());
+        };
+        let known_hosts = fixture.options.known_hosts_files[0].clone();
+        let other_key = PrivateKey::random(&mut OsRng, Algorithm::Ed25519)?;
+        let host = format!("[127.0.0.1]:{}", fixture.port);
+        std::fs::write(
+            &known_hosts,
+            format!("{host} {}\n", other_key.public_key().to_openssh()?),
+        )?;
+        let err = fixture
+            .connect(&fixture.repo, Protocol::V2)
+            .handshake(Service::UploadPack, &[])
+            .map(|_| ())
+            .expect_err("mismatch");
+        assert!(matches!(native_error(err), Error::HostKeyMismatch { host: h, .. } if h == host));
+
+        std::fs::write(&known_hosts, "")?;
+        let err = fixture
+            .connect(&fixture.repo, Protocol::V2)
+            .handshake(Service::UploadPack, &[])
+            .map(|_| ())
+            .expect_err("unknown");
+        assert!(matches!(native_error(err), Error::UnknownHostKey { .. }));
+
+        fixture.options.strict_host_key_checking = StrictHostKeyChecking::AcceptNew;
+        fixture
+            .connect(&fixture.repo, Protocol::V2)
+            .handshake(Service::UploadPack, &[])?;
+        assert_eq!(
+            std::fs::read_to_string(&known_hosts)?,
+            format!("{host} {}\n", fixture.host_key.to_openssh()?),
+            "the new key was remembered"
+        );
+
+        fixture.options.strict_host_key_checking = StrictHostKeyChecking::Yes;
+        fixture
+            .connect(&fixture.repo, Protocol::V2)
+            .handshake(Service::UploadPack, &[])?;
+        Ok(())
+    }
+
+    #[test]
+    fn authentication_failure() -> super::Result {
+        let Some(mut fixture) = Fixture::new()? else {
+            return Ok(());
+        };
+        fixture.options.identity_files.pop();
+        let err = fixture
+            .connect(&fixture.repo, Protocol::V2)
+            .handshake(Service::UploadPack, &[])
+            .map(|_| ())
+            .expect_err("no usable key");
+        assert!(matches!(
+            native_error(err),
+            Error::Authentication { user, keys: 1 } if user == fixture.user
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn ssh_config_is_applied() -> super::Result {
+        let Some(mut fixture) = Fixture::new()? else {
+            return Ok(());
+        };
+        let config = fixture.dir.path().join("config");
+        std::fs::write(
+            &config,
+            format!(
+                "Host alias\n  HostName 127.0.0.1\n  Port {}\n  User {}\n  IdentityFile {}\n",
+                fixture.port,
+                fixture.user,
+                fixture.dir.path().join("id_ed25519").display()
+            ),
+        )?;
+        fixture.options.config_file = Some(config);
+        fixture.options.identity_files.clear();
+        let url = gix_url::parse(format!("alias:{}", fixture.repo.display()).as_str().into())?;
+        let mut connection = native::connect(url, Protocol::V2, fixture.options.clone(), false)?;
+        let res = connection.handshake(Service::UploadPack, &[])?;
+        assert_eq!(res.actual_protocol, Protocol::V2);
+        Ok(())
+    }
+
+    #[test]
+    fn large_transfers_in_both_directions() -> super::Result {
+        let Some(fixture) = Fixture::new()? else { return Ok(()) };
+        let session = Session::connect("127.0.0.1", fixture.port, |_| Ok(()))?;
+        session.authenticate(
+            &fixture.user,
+            [Identity::File(fixture.options.identity_files[2].clone())],
+        )?;
+        let (mut reader, mut writer) = session.exec("cat", &[])?;
+
+        let data: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        let mut echoed = Vec::new();
+        for chunk in data.chunks(1024 * 1024) {
+            writer.write_all(chunk)?;
+            writer.flush()?;
+            let mut buf = vec![0; chunk.len()];
+            reader.read_exact(&mut buf)?;
+            echoed.extend_from_slice(&buf);
+        }
+        assert!(echoed == data, "all data is echoed back");
+        Ok(())
+    }
+}
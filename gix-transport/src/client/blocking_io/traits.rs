@@ -39,6 +39,15 @@ pub trait Transport: TransportWithoutIO {
         service: Service,
         extra_parameters: &'a [(&'a str, Option<&'a str>)],
     ) -> Result<SetServiceResponse<'_>, Error>;
+
+    /// Return access to the files of the remote repository if the [handshake](Transport::handshake()) revealed that the
+    /// server only supports the *dumb* protocol, or `None` if the server speaks the git protocol.
+    ///
+    /// With a dumb server, [requests](TransportWithoutIO::request()) can't be made, and objects have to be obtained
+    /// from the files of the remote repository instead.
+    fn dumb_files(&mut self) -> Option<&mut dyn crate::client::dumb::Files> {
+        None
+    }
 }
 
 // Would be nice if the box implementation could auto-forward to all implemented traits.
@@ -50,6 +59,10 @@ impl<T: Transport + ?Sized> Transport for Box<T> {
     ) -> Result<SetServiceResponse<'_>, Error> {
         self.deref_mut().handshake(service, extra_parameters)
     }
+
+    fn dumb_files(&mut self) -> Option<&mut dyn crate::client::dumb::Files> {
+        self.deref_mut().dumb_files()
+    }
 }
 
 impl<T: Transport + ?Sized> Transport for &mut T {
@@ -60,6 +73,10 @@ impl<T: Transport + ?Sized> Transport for &mut T {
     ) -> Result<SetServiceResponse<'_>, Error> {
         self.deref_mut().handshake(service, extra_parameters)
     }
+
+    fn dumb_files(&mut self) -> Option<&mut dyn crate::client::dumb::Files> {
+        self.deref_mut().dumb_files()
+    }
 }
 
 /// An extension trait to add more methods to everything implementing [`Transport`].
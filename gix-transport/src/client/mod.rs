@@ -1,5 +1,7 @@
 #[cfg(feature = "async-client")]
 mod async_io;
+#[cfg(feature = "async-http-client")]
+pub use async_io::http;
 #[cfg(feature = "async-client")]
 pub use async_io::{
     connect, ExtendedBufRead, HandleProgress, ReadlineBufRead, RequestWriter, SetServiceResponse, Transport,
@@ -15,8 +17,8 @@ mod blocking_io;
 pub use blocking_io::http;
 #[cfg(feature = "blocking-client")]
 pub use blocking_io::{
-    connect, file, ssh, ExtendedBufRead, HandleProgress, ReadlineBufRead, RequestWriter, SetServiceResponse, Transport,
-    TransportV2Ext,
+    connect, dumb, file, ssh, ExtendedBufRead, HandleProgress, ReadlineBufRead, RequestWriter, SetServiceResponse,
+    Transport, TransportV2Ext,
 };
 #[cfg(feature = "blocking-client")]
 #[doc(inline)]
@@ -61,11 +61,18 @@ pub(crate) mod connect {
         },
         #[error("The '{0}' protocol is currently unsupported")]
         UnsupportedScheme(gix_url::Scheme),
-        #[cfg(not(any(feature = "http-client-curl", feature = "http-client-reqwest")))]
+        #[cfg(not(any(
+            feature = "http-client-curl",
+            feature = "http-client-reqwest",
+            feature = "async-http-client-reqwest"
+        )))]
         #[error(
-            "'{0}' is not compiled in. Compile with the 'http-client-curl' or 'http-client-reqwest' cargo feature"
+            "'{0}' is not compiled in. Compile with the 'http-client-curl' or 'http-client-reqwest' cargo feature, or 'async-http-client-reqwest' for async clients"
         )]
         CompiledWithoutHttp(gix_url::Scheme),
+        #[cfg(all(feature = "blocking-client", not(feature = "ssh-client-native")))]
+        #[error("The in-process ssh client is not compiled in. Compile with the 'ssh-client-native' cargo feature")]
+        CompiledWithoutNativeSsh,
     }
 
     // TODO: maybe fix this workaround: want `IsSpuriousError`  in `Connection(…)`
@@ -88,25 +95,476 @@ pub(crate) mod connect {
     }
 }
 
+#[cfg(any(feature = "http-client", feature = "async-http-client"))]
+pub(crate) mod http {
+    use std::{
+        any::Any,
+        path::PathBuf,
+        sync::{Arc, Mutex},
+    };
+
+    use std::borrow::Cow;
+
+    use base64::Engine;
+
+    use crate::{
+        client::{self, WriteMode},
+        Protocol, Service,
+    };
+
+    ///
+    pub mod options {
+        /// A function to authenticate a URL.
+        pub type AuthenticateFn =
+            dyn FnMut(gix_credentials::helper::Action) -> gix_credentials::protocol::Result + Send + Sync;
+
+        /// Possible settings for the `http.followRedirects` configuration option.
+        #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+        pub enum FollowRedirects {
+            /// Follow only the first redirect request, most suitable for typical git requests.
+            #[default]
+            Initial,
+            /// Follow all redirect requests from the server unconditionally
+            All,
+            /// Follow no redirect request.
+            None,
+        }
+
+        /// The way to configure a proxy for authentication if a username is present in the configured proxy.
+        #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+        pub enum ProxyAuthMethod {
+            /// Automatically pick a suitable authentication method.
+            #[default]
+            AnyAuth,
+            ///HTTP basic authentication.
+            Basic,
+            /// Http digest authentication to prevent a password to be passed in clear text.
+            Digest,
+            /// GSS negotiate authentication.
+            Negotiate,
+            /// NTLM authentication
+            Ntlm,
+        }
+
+        /// Available SSL version numbers.
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd)]
+        #[allow(missing_docs)]
+        pub enum SslVersion {
+            /// The implementation default, which is unknown to this layer of abstraction.
+            Default,
+            TlsV1,
+            SslV2,
+            SslV3,
+            TlsV1_0,
+            TlsV1_1,
+            TlsV1_2,
+            TlsV1_3,
+        }
+
+        /// Available HTTP version numbers.
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd)]
+        #[allow(missing_docs)]
+        pub enum HttpVersion {
+            /// Equivalent to HTTP/1.1
+            V1_1,
+            /// Equivalent to HTTP/2
+            V2,
+        }
+
+        /// The desired range of acceptable SSL versions, or the single version to allow if both are set to the same value.
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub struct SslVersionRangeInclusive {
+            /// The smallest allowed ssl version to use.
+            pub min: SslVersion,
+            /// The highest allowed ssl version to use.
+            pub max: SslVersion,
+        }
+
+        impl SslVersionRangeInclusive {
+            /// Return `min` and `max` fields in the right order so `min` is smaller or equal to `max`.
+            pub fn min_max(&self) -> (SslVersion, SslVersion) {
+                if self.min > self.max {
+                    (self.max, self.min)
+                } else {
+                    (self.min, self.max)
+                }
+            }
+        }
+    }
+
+    /// Options to configure http requests.
+    // TODO: testing most of these fields requires a lot of effort, unless special flags to introspect ongoing requests are added.
+    #[derive(Clone)]
+    pub struct Options {
+        /// Headers to be added to every request.
+        /// They are applied unconditionally and are expected to be valid as they occur in an HTTP request, like `header: value`, without newlines.
+        ///
+        /// Refers to `http.extraHeader` multi-var.
+        pub extra_headers: Vec<String>,
+        /// How to handle redirects.
+        ///
+        /// Refers to `http.followRedirects`.
+        pub follow_redirects: options::FollowRedirects,
+        /// Used in conjunction with `low_speed_time_seconds`, any non-0 value signals the amount of bytes per second at least to avoid
+        /// aborting the connection.
+        ///
+        /// Refers to `http.lowSpeedLimit`.
+        pub low_speed_limit_bytes_per_second: u32,
+        /// Used in conjunction with `low_speed_bytes_per_second`, any non-0 value signals the amount seconds the minimal amount
+        /// of bytes per second isn't reached.
+        ///
+        /// Refers to `http.lowSpeedTime`.
+        pub low_speed_time_seconds: u64,
+        /// A curl-style proxy declaration of the form `[protocol://][user[:password]@]proxyhost[:port]`.
+        ///
+        /// Note that an empty string means the proxy is disabled entirely.
+        /// Refers to `http.proxy`.
+        pub proxy: Option<String>,
+        /// The comma-separated list of hosts to not send through the `proxy`, or `*` to entirely disable all proxying.
+        pub no_proxy: Option<String>,
+        /// The way to authenticate against the proxy if the `proxy` field contains a username.
+        ///
+        /// Refers to `http.proxyAuthMethod`.
+        pub proxy_auth_method: options::ProxyAuthMethod,
+        /// If authentication is needed for the proxy as its URL contains a username, this method must be set to provide a password
+        /// for it before making the request, and to store it if the connection succeeds.
+        pub proxy_authenticate: Option<(
+            gix_credentials::helper::Action,
+            Arc<std::sync::Mutex<options::AuthenticateFn>>,
+        )>,
+        /// The `HTTP` `USER_AGENT` string presented to an `HTTP` server, notably not the user agent present to the `git` server.
+        ///
+        /// If not overridden, it defaults to the user agent provided by `curl`, which is a deviation from how `git` handles this.
+        /// Thus it's expected from the callers to set it to their application, or use higher-level crates which make it easy to do this
+        /// more correctly.
+        ///
+        /// Using the correct user-agent might affect how the server treats the request.
+        ///
+        /// Refers to `http.userAgent`.
+        pub user_agent: Option<String>,
+        /// The amount of time we wait until aborting a connection attempt.
+        ///
+        /// If `None`, this typically defaults to 2 minutes to 5 minutes.
+        /// Refers to `gitoxide.http.connectTimeout`.
+        pub connect_timeout: Option<std::time::Duration>,
+        /// If enabled, emit additional information about connections and possibly the data received or written.
+        pub verbose: bool,
+        /// If set, use this path to point to a file with CA certificates to verify peers.
+        pub ssl_ca_info: Option<PathBuf>,
+        /// The SSL version or version range to use, or `None` to let the TLS backend determine which versions are acceptable.
+        pub ssl_version: Option<options::SslVersionRangeInclusive>,
+        /// Controls whether to perform SSL identity verification or not. Turning this off is not recommended and can lead to
+        /// various security risks. An example where this may be needed is when an internal git server uses a self-signed
+        /// certificate and the user accepts the associated security risks.
+        pub ssl_verify: bool,
+        /// The HTTP version to enforce. If unset, it is implementation defined.
+        pub http_version: Option<options::HttpVersion>,
+        /// Backend specific options, if available.
+        pub backend: Option<Arc<Mutex<dyn Any + Send + Sync + 'static>>>,
+    }
+
+    impl Default for Options {
+        fn default() -> Self {
+            Options {
+                extra_headers: vec![],
+                follow_redirects: Default::default(),
+                low_speed_limit_bytes_per_second: 0,
+                low_speed_time_seconds: 0,
+                proxy: None,
+                no_proxy: None,
+                proxy_auth_method: Default::default(),
+                proxy_authenticate: None,
+                user_agent: None,
+                connect_timeout: None,
+                verbose: false,
+                ssl_ca_info: None,
+                ssl_version: None,
+                ssl_verify: true,
+                http_version: None,
+                backend: None,
+            }
+        }
+    }
+
+    /// The error used by the `Http` trait.
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not initialize the http client")]
+        InitHttpClient {
+            source: Box<dyn std::error::Error + Send + Sync + 'static>,
+        },
+        #[error("{description}")]
+        Detail { description: String },
+        #[error("An IO error occurred while uploading the body of a POST request")]
+        PostBody(#[from] std::io::Error),
+    }
+
+    impl crate::IsSpuriousError for Error {
+        fn is_spurious(&self) -> bool {
+            match self {
+                Error::PostBody(err) => err.is_spurious(),
+                #[cfg(any(
+                    feature = "http-client-reqwest",
+                    feature = "http-client-curl",
+                    feature = "async-http-client-reqwest"
+                ))]
+                Error::InitHttpClient { source } => {
+                    #[cfg(feature = "http-client-curl")]
+                    if let Some(err) = source.downcast_ref::<crate::client::http::curl::Error>() {
+                        return err.is_spurious();
+                    };
+                    #[cfg(any(feature = "http-client-reqwest", feature = "async-http-client-reqwest"))]
+                    if let Some(err) = source.downcast_ref::<crate::client::http::reqwest::remote::Error>() {
+                        return err.is_spurious();
+                    };
+                    false
+                }
+                _ => false,
+            }
+        }
+    }
+
+    /// The return value of `Http::get()`.
+    pub struct GetResponse<H, B> {
+        /// The response headers.
+        pub headers: H,
+        /// The response body.
+        pub body: B,
+    }
+
+    /// The return value of `Http::post()`.
+    pub struct PostResponse<H, B, PB> {
+        /// The body to post to the server as part of the request.
+        ///
+        /// **Note**: Implementations should drop the handle to avoid deadlocks.
+        pub post_body: PB,
+        /// The headers of the post response.
+        pub headers: H,
+        /// The body of the post response.
+        pub body: B,
+    }
+
+    /// Whether or not the post body is expected to fit into memory or not.
+    #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+    pub enum PostBodyDataKind {
+        /// We know how much data we are sending and think it will fit into memory. This allows to collect it into a buffer
+        /// and send it with `Content-Length: <body-len>`.
+        BoundedAndFitsIntoMemory,
+        /// We don't know how much data we will send and assume it won't fit into memory. This enables streaming mode.
+        Unbounded,
+    }
+
+    impl From<WriteMode> for PostBodyDataKind {
+        fn from(m: WriteMode) -> Self {
+            match m {
+                WriteMode::Binary => PostBodyDataKind::Unbounded,
+                WriteMode::OneLfTerminatedLinePerWriteCall => PostBodyDataKind::BoundedAndFitsIntoMemory,
+            }
+        }
+    }
+
+    impl<A, B, C> From<PostResponse<A, B, C>> for GetResponse<A, B> {
+        fn from(v: PostResponse<A, B, C>) -> Self {
+            GetResponse {
+                headers: v.headers,
+                body: v.body,
+            }
+        }
+    }
+
+    /// Return `base` with `suffix` appended, separated by a single slash.
+    pub(crate) fn append_url(base: &str, suffix: &str) -> String {
+        let mut buf = base.to_owned();
+        if base.as_bytes().last() != Some(&b'/') {
+            buf.push('/');
+        }
+        buf.push_str(suffix);
+        buf
+    }
+
+    /// Produce the `Git-Protocol` header to request `desired_version` along with `extra_parameters`, if one is needed.
+    pub(crate) fn git_protocol_header(
+        desired_version: Protocol,
+        extra_parameters: &[(&str, Option<&str>)],
+    ) -> Option<String> {
+        if desired_version == Protocol::V1 && extra_parameters.is_empty() {
+            return None;
+        }
+        let mut parameters = if desired_version != Protocol::V1 {
+            let mut p = format!("version={}", desired_version as usize);
+            if !extra_parameters.is_empty() {
+                p.push(':');
+            }
+            p
+        } else {
+            String::new()
+        };
+        parameters.push_str(
+            &extra_parameters
+                .iter()
+                .map(|(key, value)| match value {
+                    Some(value) => format!("{key}={value}"),
+                    None => key.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(":"),
+        );
+        Some(format!("Git-Protocol: {parameters}"))
+    }
+
+    /// Fail unless the header `lines` indicate that the server speaks the 'smart' protocol for `service`,
+    /// with `kind` being either `advertisement` or `result`.
+    pub(crate) fn check_content_type<'a>(
+        service: Service,
+        kind: &str,
+        lines: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), client::Error> {
+        if !has_content_type(service, kind, lines) {
+            return Err(client::Error::Http(Error::Detail {
+                description: format!(
+                    "Didn't find 'application/x-{}-{kind}' header to indicate 'smart' protocol, and 'dumb' protocol is not supported.",
+                    service.as_str()
+                ),
+            }));
+        }
+        Ok(())
+    }
+
+    /// Return `true` if the header `lines` contain the content type of the `smart` protocol for `service` and the response `kind`.
+    pub(crate) fn has_content_type<'a>(service: Service, kind: &str, lines: impl IntoIterator<Item = &'a str>) -> bool {
+        let wanted_content_type = format!("application/x-{}-{}", service.as_str(), kind);
+        lines.into_iter().any(|l| {
+            let mut tokens = l.split(':');
+            tokens.next().zip(tokens.next()).map_or(false, |(name, value)| {
+                name.eq_ignore_ascii_case("content-type") && value.trim() == wanted_content_type
+            })
+        })
+    }
+
+    /// Add an `Authorization` header to `headers` if there is an `identity`, refusing to do so in clear text over `http://`
+    /// in release builds.
+    #[allow(clippy::unnecessary_wraps, unknown_lints)]
+    #[cfg_attr(debug_assertions, allow(unused_variables))]
+    pub(crate) fn add_basic_auth_if_present(
+        url: &str,
+        identity: Option<&gix_sec::identity::Account>,
+        headers: &mut Vec<Cow<'_, str>>,
+    ) -> Result<(), client::Error> {
+        if let Some(gix_sec::identity::Account { username, password }) = identity {
+            #[cfg(not(debug_assertions))]
+            if url.starts_with("http://") {
+                return Err(client::Error::AuthenticationRefused(
+                    "Will not send credentials in clear text over http",
+                ));
+            }
+            headers.push(Cow::Owned(format!(
+                "Authorization: Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(format!("{username}:{password}"))
+            )));
+        }
+        Ok(())
+    }
+
+    ///
+    #[cfg(any(
+        feature = "http-client-curl",
+        feature = "http-client-reqwest",
+        feature = "async-http-client-reqwest"
+    ))]
+    pub mod redirect {
+        /// The error provided when redirection went beyond what we deem acceptable.
+        #[derive(Debug, thiserror::Error)]
+        #[error("Redirect url {redirect_url:?} could not be reconciled with original url {expected_url} as they don't share the same suffix")]
+        pub struct Error {
+            redirect_url: String,
+            expected_url: String,
+        }
+
+        pub(crate) fn base_url(redirect_url: &str, base_url: &str, url: String) -> Result<String, Error> {
+            let tail = url
+                .strip_prefix(base_url)
+                .expect("BUG: caller assures `base_url` is subset of `url`");
+            redirect_url
+                .strip_suffix(tail)
+                .ok_or_else(|| Error {
+                    redirect_url: redirect_url.into(),
+                    expected_url: url,
+                })
+                .map(ToOwned::to_owned)
+        }
+
+        pub(crate) fn swap_tails(effective_base_url: Option<&str>, base_url: &str, mut url: String) -> String {
+            match effective_base_url {
+                Some(effective_base) => {
+                    url.replace_range(..base_url.len(), effective_base);
+                    url
+                }
+                None => url,
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            #[test]
+            fn base_url_complete() {
+                assert_eq!(
+                    base_url(
+                        "https://redirected.org/b/info/refs?hi",
+                        "https://original/a",
+                        "https://original/a/info/refs?hi".into()
+                    )
+                    .unwrap(),
+                    "https://redirected.org/b"
+                );
+            }
+
+            #[test]
+            fn swap_tails_complete() {
+                assert_eq!(
+                    swap_tails(None, "not interesting", "used".into()),
+                    "used",
+                    "without effective base url, it passes url, no redirect happened yet"
+                );
+                assert_eq!(
+                    swap_tails(
+                        Some("https://redirected.org/b"),
+                        "https://original/a",
+                        "https://original/a/info/refs?something".into()
+                    ),
+                    "https://redirected.org/b/info/refs?something",
+                    "the tail stays the same if redirection happened"
+                );
+            }
+        }
+    }
+}
+
 mod error {
     use std::ffi::OsString;
 
     use bstr::BString;
 
     use crate::client::capabilities;
-    #[cfg(feature = "http-client")]
+    #[cfg(any(feature = "http-client", feature = "async-http-client"))]
     use crate::client::http;
     #[cfg(feature = "blocking-client")]
     use crate::client::ssh;
 
-    #[cfg(feature = "http-client")]
+    #[cfg(any(feature = "http-client", feature = "async-http-client"))]
     type HttpError = http::Error;
     #[cfg(feature = "blocking-client")]
     type SshInvocationError = ssh::invocation::Error;
-    #[cfg(not(feature = "http-client"))]
+    #[cfg(not(any(feature = "http-client", feature = "async-http-client")))]
     type HttpError = std::convert::Infallible;
     #[cfg(not(feature = "blocking-client"))]
     type SshInvocationError = std::convert::Infallible;
+    #[cfg(feature = "ssh-client-native")]
+    type SshNativeError = ssh::native::Error;
+    #[cfg(not(feature = "ssh-client-native"))]
+    type SshNativeError = std::convert::Infallible;
 
     /// The error used in most methods of the [`client`][crate::client] module
     #[derive(thiserror::Error, Debug)]
@@ -140,6 +598,8 @@ mod error {
         Http(#[from] HttpError),
         #[error(transparent)]
         SshInvocation(SshInvocationError),
+        #[error(transparent)]
+        SshNative(SshNativeError),
         #[error("The repository path '{path}' could be mistaken for a command-line argument")]
         AmbiguousPath { path: BString },
     }
@@ -149,6 +609,8 @@ mod error {
             match self {
                 Error::Io(err) => err.is_spurious(),
                 Error::Http(err) => err.is_spurious(),
+                #[cfg(feature = "ssh-client-native")]
+                Error::SshNative(err) => err.is_spurious(),
                 _ => false,
             }
         }
@@ -84,8 +84,17 @@ pub use traits::IsSpuriousError;
 ///
 pub mod client;
 
+///
+pub mod server;
+
 #[doc(inline)]
-#[cfg(any(feature = "blocking-client", all(feature = "async-client", feature = "async-std")))]
+#[cfg(any(
+    feature = "blocking-client",
+    all(
+        feature = "async-client",
+        any(feature = "async-std", feature = "async-http-client-reqwest")
+    )
+))]
 pub use client::connect;
 
 #[cfg(all(feature = "async-client", feature = "blocking-client"))]
@@ -0,0 +1,183 @@
+use bstr::{BStr, BString, ByteSlice};
+
+use crate::{Protocol, Service};
+
+/// The first message a client sends to a `git` daemon, naming the service to invoke on a repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    /// The service the client wants to use.
+    pub service: Service,
+    /// The path to the repository as sent by the client, like `/project.git` or `~user/project.git`.
+    pub repository_path: BString,
+    /// The host and port the client connected to, if it told us, for use in virtual hosting.
+    pub virtual_host: Option<(String, Option<u16>)>,
+    /// Additional parameters like `version=2`, as key and optional value.
+    pub extra_parameters: Vec<(BString, Option<BString>)>,
+}
+
+impl Request {
+    /// Return the protocol version the client asked for with the `version` extra parameter, or `V0` if there is none
+    /// or if it is unknown, just like `git` does.
+    pub fn desired_version(&self) -> Protocol {
+        self.extra_parameters
+            .iter()
+            .filter(|(key, _)| key == "version")
+            .find_map(|(_, value)| match value.as_ref()?.as_slice() {
+                b"1" => Some(Protocol::V1),
+                b"2" => Some(Protocol::V2),
+                _ => None,
+            })
+            .unwrap_or(Protocol::V0)
+    }
+
+    /// Parse the `data` of the first packet line sent by a client, like `git-upload-pack /path\0host=example.com\0\0version=2\0`.
+    ///
+    /// The host is only valid directly after the path, and extra parameters must follow an empty field.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, parse::Error> {
+        let data = data.strip_suffix(b"\n").unwrap_or(data);
+        let mut fields = data.split_str(b"\0");
+        let command = fields.next().unwrap_or_default();
+        let (service, repository_path) = command
+            .split_once_str(b" ")
+            .and_then(|(service, path)| {
+                let service = match service {
+                    b"git-upload-pack" => Service::UploadPack,
+                    b"git-receive-pack" => Service::ReceivePack,
+                    _ => return None,
+                };
+                Some((service, path))
+            })
+            .ok_or_else(|| parse::Error::UnknownService {
+                command: command.into(),
+            })?;
+        if repository_path.is_empty() {
+            return Err(parse::Error::MissingPath);
+        }
+
+        let mut virtual_host = None;
+        let mut extra_parameters = Vec::new();
+        let mut in_extra_parameters = false;
+        for field in fields {
+            if field.is_empty() {
+                in_extra_parameters = true;
+                continue;
+            }
+            if in_extra_parameters {
+                extra_parameters.push(match field.split_once_str(b"=") {
+                    Some((key, value)) => (key.into(), Some(value.into())),
+                    None => (field.into(), None),
+                });
+            } else {
+                let host = field
+                    .strip_prefix(b"host=")
+                    .ok_or_else(|| parse::Error::UnexpectedParameter { field: field.into() })?;
+                virtual_host = Some(parse_host(host.as_bstr())?);
+            }
+        }
+
+        Ok(Request {
+            service,
+            repository_path: repository_path.into(),
+            virtual_host,
+            extra_parameters,
+        })
+    }
+}
+
+fn parse_host(input: &BStr) -> Result<(String, Option<u16>), parse::Error> {
+    let invalid = || parse::Error::InvalidHost { host: input.to_owned() };
+    let host = input.to_str().map_err(|_| invalid())?;
+    let (host, port) = match host.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, rest) = bracketed.split_once(']').ok_or_else(invalid)?;
+            let port = match rest {
+                "" => None,
+                rest => Some(rest.strip_prefix(':').ok_or_else(invalid)?),
+            };
+            (host, port)
+        }
+        None => match host.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (host, None),
+        },
+    };
+    if host.is_empty() {
+        return Err(invalid());
+    }
+    let port = port.map(|port| port.parse().map_err(|_| invalid())).transpose()?;
+    Ok((host.to_owned(), port))
+}
+
+///
+pub mod parse {
+    use bstr::BString;
+
+    /// The error returned by [`Request::from_bytes()`][super::Request::from_bytes()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Unknown or unsupported service requested in {command:?}")]
+        UnknownService { command: BString },
+        #[error("The path to the repository is missing")]
+        MissingPath,
+        #[error("Expected the host parameter, got {field:?}")]
+        UnexpectedParameter { field: BString },
+        #[error("The host {host:?} is invalid")]
+        InvalidHost { host: BString },
+    }
+}
+
+///
+pub mod read {
+    /// The error returned by [`read_request()`][super::read_request()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Io(#[from] std::io::Error),
+        #[error(transparent)]
+        Decode(#[from] gix_packetline::decode::Error),
+        #[error("Expected a data line with the request, but got a special packet line")]
+        NoRequestLine,
+        #[error(transparent)]
+        Parse(#[from] super::parse::Error),
+    }
+}
+
+/// Read the first packet line of a client connected to a `git` daemon from `input` and parse it into a [`Request`].
+///
+/// Note that `input` is read exactly up to the end of the packet line, so it can be passed on to the service afterwards.
+pub fn read_request(input: &mut dyn std::io::Read) -> Result<Request, read::Error> {
+    use gix_packetline::{decode, PacketLineRef};
+
+    let mut hex_len = [0u8; 4];
+    input.read_exact(&mut hex_len)?;
+    let wanted = match decode::hex_prefix(&hex_len)? {
+        decode::PacketLineOrWantedSize::Wanted(wanted) => wanted,
+        decode::PacketLineOrWantedSize::Line(_) => return Err(read::Error::NoRequestLine),
+    };
+    let mut buf = vec![0; usize::from(wanted)];
+    input.read_exact(&mut buf)?;
+    let data = match decode::to_data_line(&buf)? {
+        PacketLineRef::Data(data) => data,
+        _ => return Err(read::Error::NoRequestLine),
+    };
+    Ok(Request::from_bytes(data)?)
+}
+
+/// Write `message` as `ERR` packet line to `out` to inform a client that its request can't be served, and flush `out`.
+pub fn write_error(message: &[u8], out: &mut dyn std::io::Write) -> std::io::Result<()> {
+    const PREFIX: &[u8] = b"ERR ";
+    const MAX_LINE_LEN: usize = 65520;
+    let len = 4 + PREFIX.len() + message.len();
+    if len > MAX_LINE_LEN {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "The error message doesn't fit into a packet line",
+        ));
+    }
+    out.write_all(format!("{len:04x}").as_bytes())?;
+    out.write_all(PREFIX)?;
+    out.write_all(message)?;
+    out.flush()
+}
@@ -1,3 +1,5 @@
+#[cfg(feature = "async-http-client-reqwest")]
+mod async_io;
 #[cfg(feature = "blocking-client")]
 mod blocking_io;
 mod capabilities;
@@ -0,0 +1,108 @@
+mod git {
+    use gix_transport::{
+        server::git::{read_request, Request},
+        Protocol, Service,
+    };
+
+    #[test]
+    fn request_with_host_and_version() -> Result<(), Box<dyn std::error::Error>> {
+        let request = Request::from_bytes(b"git-upload-pack /path/project.git\0host=example.com:9418\0\0version=2\0")?;
+        assert_eq!(
+            request,
+            Request {
+                service: Service::UploadPack,
+                repository_path: "/path/project.git".into(),
+                virtual_host: Some(("example.com".into(), Some(9418))),
+                extra_parameters: vec![("version".into(), Some("2".into()))],
+            }
+        );
+        assert_eq!(request.desired_version(), Protocol::V2);
+        Ok(())
+    }
+
+    #[test]
+    fn request_without_anything_but_the_path() -> Result<(), Box<dyn std::error::Error>> {
+        for input in [&b"git-receive-pack /project\0"[..], b"git-receive-pack /project\n"] {
+            let request = Request::from_bytes(input)?;
+            assert_eq!(request.service, Service::ReceivePack);
+            assert_eq!(request.repository_path, "/project");
+            assert_eq!(request.virtual_host, None);
+            assert!(request.extra_parameters.is_empty());
+            assert_eq!(
+                request.desired_version(),
+                Protocol::V0,
+                "the default when nothing is specified"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn request_with_extra_parameters_but_without_version() -> Result<(), Box<dyn std::error::Error>> {
+        let request = Request::from_bytes(b"git-upload-pack hello\\world\0host=host\0\0key=value\0value-only\0")?;
+        assert_eq!(request.virtual_host, Some(("host".into(), None)));
+        assert_eq!(
+            request.extra_parameters,
+            vec![("key".into(), Some("value".into())), ("value-only".into(), None)]
+        );
+        assert_eq!(request.desired_version(), Protocol::V0);
+
+        let request = Request::from_bytes(b"git-upload-pack /p\0\0version=1\0")?;
+        assert_eq!(request.desired_version(), Protocol::V1);
+        Ok(())
+    }
+
+    #[test]
+    fn request_with_ipv6_host() -> Result<(), Box<dyn std::error::Error>> {
+        let request = Request::from_bytes(b"git-upload-pack /p\0host=[::1]:1234\0")?;
+        assert_eq!(request.virtual_host, Some(("::1".into(), Some(1234))));
+        let request = Request::from_bytes(b"git-upload-pack /p\0host=[::1]\0")?;
+        assert_eq!(request.virtual_host, Some(("::1".into(), None)));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_requests() {
+        for (input, expected) in [
+            (
+                &b"git-upload-archive /p\0"[..],
+                "Unknown or unsupported service requested in \"git-upload-archive /p\"",
+            ),
+            (
+                b"git-upload-pack\0",
+                "Unknown or unsupported service requested in \"git-upload-pack\"",
+            ),
+            (b"git-upload-pack \0", "The path to the repository is missing"),
+            (
+                b"git-upload-pack /p\0key=value\0",
+                "Expected the host parameter, got \"key=value\"",
+            ),
+            (
+                b"git-upload-pack /p\0host=example.com:port\0",
+                "The host \"example.com:port\" is invalid",
+            ),
+            (b"git-upload-pack /p\0host=[::1\0", "The host \"[::1\" is invalid"),
+        ] {
+            assert_eq!(
+                Request::from_bytes(input).unwrap_err().to_string(),
+                expected,
+                "{:?}",
+                bstr::BStr::new(input)
+            );
+        }
+    }
+
+    #[test]
+    fn read_request_stops_at_the_end_of_the_first_line() -> Result<(), Box<dyn std::error::Error>> {
+        let mut input = &b"0028git-upload-pack /p\0host=example.com\x000000"[..];
+        let request = read_request(&mut input)?;
+        assert_eq!(request.virtual_host, Some(("example.com".into(), None)));
+        assert_eq!(input, b"0000", "the rest of the input is untouched");
+
+        assert!(matches!(
+            read_request(&mut &b"0000"[..]),
+            Err(gix_transport::server::git::read::Error::NoRequestLine)
+        ));
+        Ok(())
+    }
+}
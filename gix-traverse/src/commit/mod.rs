@@ -34,7 +34,7 @@ pub struct Topo<Find, Predicate> {
     indegree_queue: PriorityQueue<topo::iter::GenAndCommitTime, ObjectId>,
     topo_queue: topo::iter::Queue,
     parents: Parents,
-    min_gen: u32,
+    min_gen: u64,
     buf: Vec<u8>,
 }
 
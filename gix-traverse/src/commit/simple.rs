@@ -239,6 +239,11 @@ mod init {
         pub fn commit_data(&self) -> &[u8] {
             &self.state.buf
         }
+
+        /// Return the commit-graph used to accelerate the traversal, if one was [set](Self::commit_graph()).
+        pub fn commit_graph_cache(&self) -> Option<&gix_commitgraph::Graph> {
+            self.cache.as_ref()
+        }
     }
 
     impl<Find, Predicate> Iterator for Simple<Find, Predicate>
@@ -172,11 +172,9 @@ where
             obj.data.len()
         }
         gix_index::entry::Mode::DIR => {
-            gix_features::trace::warn!(
-                "Skipped sparse directory at '{entry_path}' ({id}) as it cannot yet be handled",
-                id = entry.id
-            );
-            0
+            return Err(crate::checkout::Error::SparseDirectory {
+                rela_path: entry_path.to_owned(),
+            })
         }
         gix_index::entry::Mode::COMMIT => {
             gix_features::trace::warn!(
@@ -96,6 +96,8 @@ pub enum Error {
     FilterPathUnknown { rela_path: BString },
     #[error("The following paths were delayed and apparently forgotten to be processed by the filter driver: ")]
     FilterPathsUnprocessed { rela_paths: Vec<BString> },
+    #[error("The sparse directory at '{rela_path}' can't be checked out as sparse indices aren't supported yet")]
+    SparseDirectory { rela_path: BString },
 }
 
 mod chunk;
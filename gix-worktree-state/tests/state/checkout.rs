@@ -62,6 +62,39 @@ fn submodules_are_instantiated_as_directories() -> crate::Result {
     Ok(())
 }
 
+#[test]
+fn entries_marked_with_skip_worktree_are_not_checked_out() -> crate::Result {
+    let source_tree = fixture_path("make_mixed_without_submodules_and_symlinks");
+    let git_dir = source_tree.join(".git");
+    let mut index = gix_index::File::at(git_dir.join("index"), gix_hash::Kind::Sha1, false, Default::default())?;
+    for (entry, path) in index.entries_mut_with_paths() {
+        if path.starts_with(b"dir/") {
+            entry.flags.insert(gix_index::entry::Flags::SKIP_WORKTREE);
+        }
+    }
+    let odb = gix_odb::at(git_dir.join("objects"))?.into_inner().into_arc()?;
+    let destination = gix_testtools::tempfile::tempdir_in(std::env::current_dir()?)?;
+
+    let outcome = gix_worktree_state::checkout(
+        &mut index,
+        destination.path(),
+        odb,
+        &progress::Discard,
+        &progress::Discard,
+        &AtomicBool::default(),
+        opts_from_probe(),
+    )?;
+
+    assert_eq!(
+        stripped_prefix(&destination, &dir_structure(&destination)),
+        paths(["empty", "executable"]),
+        "skipped entries aren't written, and neither are their leading directories"
+    );
+    assert_eq!(outcome.files_updated, 5, "skipped entries are counted as processed");
+    assert!(outcome.errors.is_empty() && outcome.collisions.is_empty());
+    Ok(())
+}
+
 #[test]
 fn accidental_writes_through_symlinks_are_prevented_if_overwriting_is_forbidden() {
     let mut opts = opts_from_probe();
@@ -35,6 +35,20 @@ impl PrepareFetch {
         self
     }
 
+    /// Obtain objects from the bundle or bundle list at `uri` before fetching, similar to `git clone --bundle-uri`,
+    /// so the remote only has to send what the bundles don't contain.
+    ///
+    /// `uri` may be a path or an HTTP URL. If it points to a bundle list that uses the `creationToken` heuristic,
+    /// it's stored in `fetch.bundleURI` so that later fetches will pick up new bundles as well.
+    /// Without it, the bundles the remote advertises are used if `transfer.bundleURI` is enabled.
+    ///
+    /// Note that bundles are only used with the `blocking-network-client` feature,
+    /// and that failing to use them isn't fatal as the remote will send everything that's missing.
+    pub fn with_bundle_uri(mut self, uri: impl Into<BString>) -> Self {
+        self.bundle_uri = Some(uri.into());
+        self
+    }
+
     /// Apply the given configuration `values` right before readying the actual fetch from the remote.
     /// The configuration is marked with [source API](gix_config::Source::Api), and will not be written back, it's
     /// retained only in memory.
@@ -35,6 +35,12 @@ pub mod main_worktree {
         FindHead(#[from] crate::reference::find::existing::Error),
         #[error("The HEAD reference could not be located")]
         PeelHeadToId(#[from] crate::head::peel::Error),
+        #[error("Could not fetch the blobs to check out from the promisor remote")]
+        FetchMissingObjects(#[from] gix_odb::store::find::Error),
+        #[error(transparent)]
+        SparseCheckoutPatterns(#[from] crate::sparse_checkout::patterns::Error),
+        #[error(transparent)]
+        SetupSparseCheckout(#[from] crate::sparse_checkout::update::Error),
     }
 
     /// The progress ids used in [`PrepareCheckout::main_worktree()`].
@@ -90,8 +96,12 @@ pub mod main_worktree {
             let _span = gix_trace::coarse!("gix::clone::PrepareCheckout::main_worktree()");
             let repo = self
                 .repo
-                .as_ref()
+                .as_mut()
                 .expect("BUG: this method may only be called until it is successful");
+            if let Some(patterns) = self.sparse_checkout.take() {
+                repo.write_sparse_checkout(&patterns)?;
+            }
+            let repo = &*repo;
             let workdir = repo.work_dir().ok_or_else(|| Error::BareRepository {
                 git_dir: repo.git_dir().to_owned(),
             })?;
@@ -117,6 +127,19 @@ pub mod main_worktree {
                     source: err,
                 })?;
             let mut index = gix_index::File::from_state(index, repo.index_path());
+            if let Some(patterns) = repo.sparse_checkout_patterns()? {
+                crate::sparse_checkout::mark_excluded_entries(&patterns, &mut index);
+            }
+            // In partial clones, get all blobs we need at once instead of having each of them fetched on demand.
+            repo.objects.fetch_missing(
+                index
+                    .entries()
+                    .iter()
+                    .filter(|entry| {
+                        !entry.mode.is_submodule() && !entry.flags.contains(gix_index::entry::Flags::SKIP_WORKTREE)
+                    })
+                    .map(|entry| entry.id),
+            )?;
 
             let mut opts = repo
                 .config
@@ -148,6 +171,20 @@ pub mod main_worktree {
     }
 }
 
+/// Builder
+impl PrepareCheckout {
+    /// Set up a sparse checkout with `patterns` before checking out the main worktree, so only the files they include
+    /// are checked out, like `git clone --sparse` followed by `git sparse-checkout set` would.
+    ///
+    /// Note that the repository is configured to use a sparse checkout as well, so `patterns` remain in effect afterwards.
+    /// Without calling this method, a sparse checkout is still performed if `core.sparseCheckout` is enabled and
+    /// `info/sparse-checkout` exists.
+    pub fn with_sparse_checkout(mut self, patterns: crate::sparse_checkout::Patterns) -> Self {
+        self.sparse_checkout = Some(patterns);
+        self
+    }
+}
+
 /// Access
 impl PrepareCheckout {
     /// Get access to the repository while the checkout isn't yet completed.
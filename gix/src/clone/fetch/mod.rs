@@ -30,6 +30,8 @@ pub enum Error {
     SaveConfig(#[from] crate::remote::save::AsError),
     #[error("Failed to write repository configuration to disk")]
     SaveConfigIo(#[from] std::io::Error),
+    #[error("Failed to configure the remote as promisor remote of a partial clone")]
+    PartialCloneConfig(#[from] gix_config::file::set_raw_value::Error),
     #[error("The remote HEAD points to a reference named {head_ref_name:?} which is invalid.")]
     InvalidHeadRef {
         source: gix_validate::reference::name::Error,
@@ -37,6 +39,11 @@ pub enum Error {
     },
     #[error("Failed to update HEAD with values from remote")]
     HeadUpdate(#[from] crate::reference::edit::Error),
+    #[error("The remote uses {remote} for object hashes, but the repository was initialized to use {local}. Set `object_hash` in the options used to create it.")]
+    IncompatibleObjectHash {
+        local: gix_hash::Kind,
+        remote: gix_hash::Kind,
+    },
     #[error("The remote didn't have any ref that matched '{}'", wanted.as_ref().as_bstr())]
     RefNameMissing { wanted: gix_ref::PartialName },
     #[error("The remote has {} refs for '{}', try to use a specific name: {}", candidates.len(), wanted.as_ref().as_bstr(), candidates.iter().filter_map(|n| n.to_str().ok()).collect::<Vec<_>>().join(", "))]
@@ -121,7 +128,7 @@ impl PrepareFetch {
             clone_fetch_tags = remote::fetch::Tags::All.into();
         }
 
-        let config = util::write_remote_to_local_config_file(&mut remote, remote_name.clone())?;
+        let config = util::write_remote_to_local_config_file(&mut remote, remote_name.clone(), self.filter)?;
 
         // Now we are free to apply remote configuration we don't want to be written to disk.
         if let Some(fetch_tags) = clone_fetch_tags {
@@ -191,13 +198,24 @@ impl PrepareFetch {
             util::find_custom_refname(pending_pack.ref_map(), ref_name)?;
         }
         if pending_pack.ref_map().object_hash != repo.object_hash() {
-            unimplemented!("configure repository to expect a different object hash as advertised by the server")
+            return Err(Error::IncompatibleObjectHash {
+                local: repo.object_hash(),
+                remote: pending_pack.ref_map().object_hash,
+            });
         }
         let reflog_message = {
             let mut b = self.url.to_bstring();
             b.insert_str(0, "clone: from ");
             b
         };
+        let pending_pack = match self.filter {
+            Some(filter) => pending_pack.with_filter(filter),
+            None => pending_pack,
+        };
+        #[cfg(feature = "blocking-network-client")]
+        let pending_pack = pending_pack
+            .with_bundle_uri(self.bundle_uri.clone())
+            .with_advertised_bundles(true);
         let outcome = pending_pack
             .with_write_packed_refs_only(true)
             .with_reflog_message(RefLogMessage::Override {
@@ -208,6 +226,12 @@ impl PrepareFetch {
             .await?;
 
         util::append_config_to_repo_config(repo, config);
+        #[cfg(feature = "blocking-network-client")]
+        util::append_bundle_config_to_repo_config(repo)?;
+        #[cfg(feature = "blocking-network-client")]
+        if self.filter.is_some() {
+            crate::repository::init::setup_promisor(repo);
+        }
         util::update_head(
             repo,
             &outcome.ref_map,
@@ -235,6 +259,7 @@ impl PrepareFetch {
             crate::clone::PrepareCheckout {
                 repo: repo.into(),
                 ref_name: self.ref_name.clone(),
+                sparse_checkout: None,
             },
             fetch_outcome,
         ))
@@ -8,6 +8,7 @@ use gix_ref::{
 use super::Error;
 use crate::{
     bstr::{BStr, BString, ByteSlice},
+    config::tree::{Extensions, Remote},
     Repository,
 };
 
@@ -20,9 +21,21 @@ enum WriteMode {
 pub fn write_remote_to_local_config_file(
     remote: &mut crate::Remote<'_>,
     remote_name: BString,
+    filter: Option<crate::remote::fetch::Filter>,
 ) -> Result<gix_config::File<'static>, Error> {
     let mut config = gix_config::File::new(local_config_meta(remote.repo));
-    remote.save_as_to(remote_name, &mut config)?;
+    remote.save_as_to(remote_name.clone(), &mut config)?;
+    if let Some(filter) = filter {
+        let name = Some(remote_name.as_bstr());
+        config.set_raw_value_by("remote", name, Remote::PROMISOR.name, "true")?;
+        config.set_raw_value_by(
+            "remote",
+            name,
+            Remote::PARTIAL_CLONE_FILTER.name,
+            filter.to_string().as_str(),
+        )?;
+        config.set_raw_value(&Extensions::PARTIAL_CLONE, remote_name.as_bstr())?;
+    }
 
     write_to_local_config(&config, WriteMode::Append)?;
     Ok(config)
@@ -53,6 +66,31 @@ fn write_to_local_config(config: &gix_config::File<'static>, mode: WriteMode) ->
     config.write_to_filter(&mut local_config, &mut |s| s.meta().source == gix_config::Source::Local)
 }
 
+/// Bring `fetch.bundleURI` and `fetch.bundleCreationToken` into the configuration of `repo` if they were written to its
+/// local configuration file while fetching, to keep them when the file is rewritten from memory.
+#[cfg(feature = "blocking-network-client")]
+#[allow(clippy::result_large_err)]
+pub fn append_bundle_config_to_repo_config(repo: &mut Repository) -> Result<(), Error> {
+    use crate::config::tree::Fetch;
+
+    let meta = local_config_meta(repo);
+    let on_disk = gix_config::File::from_path_no_includes(
+        meta.path.clone().expect("local config with path set"),
+        gix_config::Source::Local,
+    )?;
+    let mut config = gix_config::File::new(meta);
+    if let Ok(uri) = on_disk.raw_value(&Fetch::BUNDLE_URI) {
+        config.set_raw_value(&Fetch::BUNDLE_URI, uri.as_ref())?;
+    }
+    if let Ok(token) = on_disk.raw_value(&Fetch::BUNDLE_CREATION_TOKEN) {
+        config.set_raw_value(&Fetch::BUNDLE_CREATION_TOKEN, token.as_ref())?;
+    }
+    if !config.is_void() {
+        append_config_to_repo_config(repo, config);
+    }
+    Ok(())
+}
+
 pub fn append_config_to_repo_config(repo: &mut Repository, config: gix_config::File<'static>) {
     let repo_config = gix_features::threading::OwnShared::make_mut(&mut repo.config.resolved);
     repo_config.append(config);
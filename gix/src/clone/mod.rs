@@ -34,9 +34,16 @@ pub struct PrepareFetch {
     /// How to handle shallow clones
     #[cfg_attr(not(feature = "blocking-network-client"), allow(dead_code))]
     shallow: remote::fetch::Shallow,
+    /// The filter to make this a partial clone, if set.
+    #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
+    #[cfg_attr(not(feature = "blocking-network-client"), allow(dead_code))]
+    filter: Option<remote::fetch::Filter>,
     /// The name of the reference to fetch. If `None`, the reference pointed to by `HEAD` will be checked out.
     #[cfg_attr(not(feature = "blocking-network-client"), allow(dead_code))]
     ref_name: Option<gix_ref::PartialName>,
+    /// The bundle or bundle list to obtain objects from before fetching.
+    #[cfg_attr(not(feature = "blocking-network-client"), allow(dead_code))]
+    bundle_uri: Option<BString>,
 }
 
 /// The error returned by [`PrepareFetch::new()`].
@@ -62,6 +69,8 @@ impl PrepareFetch {
     /// Note that this is merely a handle to perform the actual connection to the remote, and if any of it fails the freshly initialized repository
     /// will be removed automatically as soon as this instance drops.
     ///
+    /// The [object hash](crate::create::Options::object_hash) of `create_opts` must match the one used by the remote.
+    ///
     /// # Deviation
     ///
     /// Similar to `git`, a missing user name and email configuration is not terminal and we will fill it in with dummy values. However,
@@ -125,7 +134,10 @@ impl PrepareFetch {
             #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
             configure_connection: None,
             shallow: remote::fetch::Shallow::NoChange,
+            #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
+            filter: None,
             ref_name: None,
+            bundle_uri: None,
         })
     }
 }
@@ -140,6 +152,8 @@ pub struct PrepareCheckout {
     pub(self) repo: Option<crate::Repository>,
     /// The name of the reference to check out. If `None`, the reference pointed to by `HEAD` will be checked out.
     pub(self) ref_name: Option<gix_ref::PartialName>,
+    /// The patterns of the sparse checkout to set up before checking out, if any.
+    pub(self) sparse_checkout: Option<crate::sparse_checkout::Patterns>,
 }
 
 // This module encapsulates functionality that works with both feature toggles. Can be combined with `fetch`
@@ -170,11 +184,23 @@ mod access_feat {
             self.fetch_options = opts;
             self
         }
+
+        /// Make this clone a partial one by having the remote omit objects according to `filter`.
+        ///
+        /// The remote is then configured as *promisor remote* to fetch omitted objects from when they are needed.
+        pub fn with_filter(mut self, filter: crate::remote::fetch::Filter) -> Self {
+            self.filter = Some(filter);
+            self
+        }
     }
 }
 
 ///
-#[cfg(any(feature = "async-network-client-async-std", feature = "blocking-network-client"))]
+#[cfg(any(
+    feature = "async-network-client-async-std",
+    feature = "async-http-transport-reqwest",
+    feature = "blocking-network-client"
+))]
 pub mod fetch;
 
 mod access;
@@ -129,7 +129,11 @@ impl Cache {
     }
 
     /// Returns a user agent for use with servers.
-    #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
+    #[cfg(any(
+        feature = "async-network-client",
+        feature = "blocking-network-client",
+        feature = "blocking-network-server"
+    ))]
     pub(crate) fn user_agent_tuple(&self) -> (&'static str, Option<Cow<'static, str>>) {
         use config::tree::Gitoxide;
         let agent = self
@@ -455,7 +459,8 @@ impl Cache {
     /// if it actually wants to use the home directory - we don't want to fail prematurely.
     #[cfg(any(
         feature = "blocking-http-transport-reqwest",
-        feature = "blocking-http-transport-curl"
+        feature = "blocking-http-transport-curl",
+        feature = "async-http-transport-reqwest"
     ))]
     pub(crate) fn home_dir(&self) -> Option<PathBuf> {
         home_dir(self.environment)
@@ -26,6 +26,7 @@ impl Cache {
             lossy,
             is_bare,
             object_hash,
+            ref_storage: _,
             reflog: _,
             precompose_unicode: _,
             protect_windows: _,
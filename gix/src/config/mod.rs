@@ -261,8 +261,12 @@ pub mod ssh_connect_options {
     /// The error produced when obtaining ssh connection configuration.
     #[derive(Debug, thiserror::Error)]
     #[allow(missing_docs)]
-    #[error(transparent)]
-    pub struct Error(#[from] super::key::GenericErrorWithValue);
+    pub enum Error {
+        #[error(transparent)]
+        Variant(#[from] super::key::GenericErrorWithValue),
+        #[error(transparent)]
+        Native(#[from] super::boolean::Error),
+    }
 }
 
 ///
@@ -37,6 +37,8 @@ pub(crate) mod root {
         pub const EXTENSIONS: sections::Extensions = sections::Extensions;
         /// The `fetch` section.
         pub const FETCH: sections::Fetch = sections::Fetch;
+        /// The `gc` section.
+        pub const GC: sections::Gc = sections::Gc;
         /// The `gitoxide` section.
         pub const GITOXIDE: sections::Gitoxide = sections::Gitoxide;
         /// The `http` section.
@@ -53,6 +55,8 @@ pub(crate) mod root {
         pub const PROTOCOL: sections::Protocol = sections::Protocol;
         /// The `push` section.
         pub const PUSH: sections::Push = sections::Push;
+        /// The `receive` section.
+        pub const RECEIVE: sections::Receive = sections::Receive;
         /// The `remote` section.
         pub const REMOTE: sections::Remote = sections::Remote;
         /// The `safe` section.
@@ -62,6 +66,10 @@ pub(crate) mod root {
         /// The `status` section.
         #[cfg(feature = "status")]
         pub const STATUS: sections::Status = sections::Status;
+        /// The `transfer` section.
+        pub const TRANSFER: sections::Transfer = sections::Transfer;
+        /// The `uploadpack` section.
+        pub const UPLOAD_PACK: sections::UploadPack = sections::UploadPack;
         /// The `user` section.
         pub const USER: sections::User = sections::User;
         /// The `url` section.
@@ -81,6 +89,7 @@ pub(crate) mod root {
                 &Self::DIFF,
                 &Self::EXTENSIONS,
                 &Self::FETCH,
+                &Self::GC,
                 &Self::GITOXIDE,
                 &Self::HTTP,
                 &Self::INDEX,
@@ -89,11 +98,14 @@ pub(crate) mod root {
                 &Self::PACK,
                 &Self::PROTOCOL,
                 &Self::PUSH,
+                &Self::RECEIVE,
                 &Self::REMOTE,
                 &Self::SAFE,
                 &Self::SSH,
                 #[cfg(feature = "status")]
                 &Self::STATUS,
+                &Self::TRANSFER,
+                &Self::UPLOAD_PACK,
                 &Self::USER,
                 &Self::URL,
             ]
@@ -103,9 +115,9 @@ pub(crate) mod root {
 
 mod sections;
 pub use sections::{
-    branch, checkout, core, credential, extensions, fetch, gitoxide, http, index, protocol, push, remote, ssh, Author,
-    Branch, Checkout, Clone, Committer, Core, Credential, Extensions, Fetch, Gitoxide, Http, Index, Init, Mailmap,
-    Pack, Protocol, Push, Remote, Safe, Ssh, Url, User,
+    branch, checkout, core, credential, extensions, fetch, gc, gitoxide, http, index, protocol, push, remote, ssh,
+    Author, Branch, Checkout, Clone, Committer, Core, Credential, Extensions, Fetch, Gc, Gitoxide, Http, Index, Init,
+    Mailmap, Pack, Protocol, Push, Receive, Remote, Safe, Ssh, Transfer, UploadPack, Url, User,
 };
 #[cfg(feature = "blob-diff")]
 pub use sections::{diff, Diff};
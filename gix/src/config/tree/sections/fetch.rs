@@ -10,6 +10,13 @@ impl Fetch {
         &config::Tree::FETCH,
         validate::NegotiationAlgorithm,
     );
+    /// The `fetch.uriProtocols` key.
+    pub const URI_PROTOCOLS: keys::String = keys::String::new_string("uriProtocols", &config::Tree::FETCH);
+    /// The `fetch.bundleURI` key.
+    pub const BUNDLE_URI: keys::String = keys::String::new_string("bundleURI", &config::Tree::FETCH);
+    /// The `fetch.bundleCreationToken` key.
+    pub const BUNDLE_CREATION_TOKEN: keys::UnsignedInteger =
+        keys::UnsignedInteger::new_unsigned_integer("bundleCreationToken", &config::Tree::FETCH);
     /// The `fetch.recurseSubmodules` key.
     #[cfg(feature = "attributes")]
     pub const RECURSE_SUBMODULES: RecurseSubmodules =
@@ -24,6 +31,9 @@ impl Section for Fetch {
     fn keys(&self) -> &[&dyn Key] {
         &[
             &Self::NEGOTIATION_ALGORITHM,
+            &Self::URI_PROTOCOLS,
+            &Self::BUNDLE_URI,
+            &Self::BUNDLE_CREATION_TOKEN,
             #[cfg(feature = "attributes")]
             &Self::RECURSE_SUBMODULES,
         ]
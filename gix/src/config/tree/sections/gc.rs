@@ -0,0 +1,74 @@
+use crate::config::{
+    tree::{keys, Gc, Key, Section},
+    Tree,
+};
+
+impl Gc {
+    /// The `gc.reflogExpire` key.
+    pub const REFLOG_EXPIRE: ReflogExpire =
+        ReflogExpire::new_with_validate("reflogExpire", &Tree::GC, validate::ReflogExpire);
+    /// The `gc.reflogExpireUnreachable` key.
+    pub const REFLOG_EXPIRE_UNREACHABLE: ReflogExpire =
+        ReflogExpire::new_with_validate("reflogExpireUnreachable", &Tree::GC, validate::ReflogExpire);
+}
+
+impl Section for Gc {
+    fn name(&self) -> &'static str {
+        "gc"
+    }
+
+    fn keys(&self) -> &[&dyn Key] {
+        &[&Self::REFLOG_EXPIRE, &Self::REFLOG_EXPIRE_UNREACHABLE]
+    }
+}
+
+/// The `gc.reflogExpire` and `gc.reflogExpireUnreachable` keys.
+pub type ReflogExpire = keys::Any<validate::ReflogExpire>;
+
+mod reflog_expire {
+    use std::{borrow::Cow, time::SystemTime};
+
+    use crate::{
+        bstr::{BStr, ByteSlice},
+        config,
+        config::tree::gc::ReflogExpire,
+    };
+
+    impl ReflogExpire {
+        /// Convert `value` into the time before which reflog entries expire, relative to `now`,
+        /// or `None` if they never expire, as indicated by `never` or `false`.
+        ///
+        /// `now` and `all` expire all entries, and approximate dates like `90.days.ago` or `2 weeks` are supported as well.
+        pub fn try_into_expiry_date(
+            &'static self,
+            value: Cow<'_, BStr>,
+            now: SystemTime,
+        ) -> Result<Option<gix_date::SecondsSinceUnixEpoch>, config::key::GenericErrorWithValue> {
+            let err = || config::key::GenericErrorWithValue::from_value(self, value.clone().into_owned());
+            let input = value.to_str().map_err(|_| err())?.trim();
+            Ok(match input {
+                "never" | "false" => None,
+                "now" | "all" => Some(gix_date::SecondsSinceUnixEpoch::MAX),
+                _ => {
+                    let input = input.replace('.', " ");
+                    let time = gix_date::parse(&input, Some(now))
+                        .or_else(|_| gix_date::parse(&format!("{input} ago"), Some(now)))
+                        .map_err(|_| err())?;
+                    Some(time.seconds)
+                }
+            })
+        }
+    }
+}
+
+mod validate {
+    use crate::{bstr::BStr, config::tree::keys};
+
+    pub struct ReflogExpire;
+    impl keys::Validate for ReflogExpire {
+        fn validate(&self, value: &BStr) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+            super::Gc::REFLOG_EXPIRE.try_into_expiry_date(value.into(), std::time::SystemTime::now())?;
+            Ok(())
+        }
+    }
+}
@@ -51,6 +51,10 @@ impl Http {
     /// The `http.schannelCheckRevoke` key.
     pub const SCHANNEL_CHECK_REVOKE: keys::Boolean =
         keys::Boolean::new_boolean("schannelCheckRevoke", &config::Tree::HTTP);
+    /// The `http.uploadpack` key.
+    pub const UPLOAD_PACK: keys::Boolean = keys::Boolean::new_boolean("uploadpack", &config::Tree::HTTP);
+    /// The `http.receivepack` key.
+    pub const RECEIVE_PACK: keys::Boolean = keys::Boolean::new_boolean("receivepack", &config::Tree::HTTP);
 }
 
 impl Section for Http {
@@ -73,6 +77,8 @@ impl Section for Http {
             &Self::SCHANNEL_USE_SSL_CA_INFO,
             &Self::SSL_CA_INFO,
             &Self::SCHANNEL_CHECK_REVOKE,
+            &Self::UPLOAD_PACK,
+            &Self::RECEIVE_PACK,
         ]
     }
 }
@@ -112,7 +118,8 @@ mod key_impls {
 
     #[cfg(any(
         feature = "blocking-http-transport-reqwest",
-        feature = "blocking-http-transport-curl"
+        feature = "blocking-http-transport-curl",
+        feature = "async-http-transport-reqwest"
     ))]
     impl crate::config::tree::http::FollowRedirects {
         /// Convert `value` into the redirect specification, or query the same value as `boolean`
@@ -165,7 +172,8 @@ mod key_impls {
 
     #[cfg(any(
         feature = "blocking-http-transport-reqwest",
-        feature = "blocking-http-transport-curl"
+        feature = "blocking-http-transport-curl",
+        feature = "async-http-transport-reqwest"
     ))]
     impl super::Version {
         pub fn try_into_http_version(
@@ -193,7 +201,8 @@ mod key_impls {
 
     #[cfg(any(
         feature = "blocking-http-transport-reqwest",
-        feature = "blocking-http-transport-curl"
+        feature = "blocking-http-transport-curl",
+        feature = "async-http-transport-reqwest"
     ))]
     impl ProxyAuthMethod {
         pub fn try_into_proxy_auth_method(
@@ -224,7 +233,8 @@ mod key_impls {
 
     #[cfg(any(
         feature = "blocking-http-transport-reqwest",
-        feature = "blocking-http-transport-curl"
+        feature = "blocking-http-transport-curl",
+        feature = "async-http-transport-reqwest"
     ))]
     impl SslVersion {
         pub fn try_into_ssl_version(
@@ -263,7 +273,8 @@ pub mod validate {
         fn validate(&self, _value: &BStr) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
             #[cfg(any(
                 feature = "blocking-http-transport-reqwest",
-                feature = "blocking-http-transport-curl"
+                feature = "blocking-http-transport-curl",
+                feature = "async-http-transport-reqwest"
             ))]
             super::Http::SSL_VERSION.try_into_ssl_version(std::borrow::Cow::Borrowed(_value))?;
 
@@ -276,7 +287,8 @@ pub mod validate {
         fn validate(&self, _value: &BStr) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
             #[cfg(any(
                 feature = "blocking-http-transport-reqwest",
-                feature = "blocking-http-transport-curl"
+                feature = "blocking-http-transport-curl",
+                feature = "async-http-transport-reqwest"
             ))]
             super::Http::PROXY_AUTH_METHOD.try_into_proxy_auth_method(std::borrow::Cow::Borrowed(_value))?;
 
@@ -289,7 +301,8 @@ pub mod validate {
         fn validate(&self, _value: &BStr) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
             #[cfg(any(
                 feature = "blocking-http-transport-reqwest",
-                feature = "blocking-http-transport-curl"
+                feature = "blocking-http-transport-curl",
+                feature = "async-http-transport-reqwest"
             ))]
             super::Http::VERSION.try_into_http_version(std::borrow::Cow::Borrowed(_value))?;
 
@@ -310,7 +323,8 @@ pub mod validate {
         fn validate(&self, _value: &BStr) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
             #[cfg(any(
                 feature = "blocking-http-transport-reqwest",
-                feature = "blocking-http-transport-curl"
+                feature = "blocking-http-transport-curl",
+                feature = "async-http-transport-reqwest"
             ))]
             super::Http::FOLLOW_REDIRECTS.try_into_follow_redirects(std::borrow::Cow::Borrowed(_value), || {
                 gix_config::Boolean::try_from(_value).map(|b| Some(b.0))
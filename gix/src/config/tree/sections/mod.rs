@@ -52,6 +52,11 @@ pub mod extensions;
 pub struct Fetch;
 pub mod fetch;
 
+/// The `gc` top-level section.
+#[derive(Copy, Clone, Default)]
+pub struct Gc;
+pub mod gc;
+
 /// The `gitoxide` top-level section.
 #[derive(Copy, Clone, Default)]
 pub struct Gitoxide;
@@ -91,6 +96,11 @@ pub mod protocol;
 pub struct Push;
 pub mod push;
 
+/// The `receive` top-level section.
+#[derive(Copy, Clone, Default)]
+pub struct Receive;
+mod receive;
+
 /// The `remote` top-level section.
 #[derive(Copy, Clone, Default)]
 pub struct Remote;
@@ -113,6 +123,16 @@ pub struct Status;
 #[cfg(feature = "status")]
 pub mod status;
 
+/// The `transfer` top-level section.
+#[derive(Copy, Clone, Default)]
+pub struct Transfer;
+mod transfer;
+
+/// The `uploadpack` top-level section.
+#[derive(Copy, Clone, Default)]
+pub struct UploadPack;
+mod upload_pack;
+
 /// The `user` top-level section.
 #[derive(Copy, Clone, Default)]
 pub struct User;
@@ -0,0 +1,29 @@
+use crate::{
+    config,
+    config::tree::{keys, Key, Receive, Section},
+};
+
+impl Receive {
+    /// The `receive.denyDeletes` key
+    pub const DENY_DELETES: keys::Boolean = keys::Boolean::new_boolean("denyDeletes", &config::Tree::RECEIVE);
+    /// The `receive.denyNonFastForwards` key
+    pub const DENY_NON_FAST_FORWARDS: keys::Boolean =
+        keys::Boolean::new_boolean("denyNonFastForwards", &config::Tree::RECEIVE);
+    /// The `receive.advertisePushOptions` key
+    pub const ADVERTISE_PUSH_OPTIONS: keys::Boolean =
+        keys::Boolean::new_boolean("advertisePushOptions", &config::Tree::RECEIVE);
+}
+
+impl Section for Receive {
+    fn name(&self) -> &'static str {
+        "receive"
+    }
+
+    fn keys(&self) -> &[&dyn Key] {
+        &[
+            &Self::DENY_DELETES,
+            &Self::DENY_NON_FAST_FORWARDS,
+            &Self::ADVERTISE_PUSH_OPTIONS,
+        ]
+    }
+}
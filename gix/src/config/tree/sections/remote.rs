@@ -31,6 +31,16 @@ impl Remote {
         http::ProxyAuthMethod::new_proxy_auth_method("proxyAuthMethod", &config::Tree::REMOTE)
             .with_subsection_requirement(NAME_PARAMETER)
             .with_deviation("implemented like git, but never actually tried");
+    /// The `remote.<name>.promisor` key
+    pub const PROMISOR: keys::Boolean =
+        keys::Boolean::new_boolean("promisor", &config::Tree::REMOTE).with_subsection_requirement(NAME_PARAMETER);
+    /// The `remote.<name>.partialCloneFilter` key
+    pub const PARTIAL_CLONE_FILTER: PartialCloneFilter = PartialCloneFilter::new_with_validate(
+        "partialCloneFilter",
+        &config::Tree::REMOTE,
+        validate::PartialCloneFilter,
+    )
+    .with_subsection_requirement(NAME_PARAMETER);
 }
 
 impl Section for Remote {
@@ -48,6 +58,8 @@ impl Section for Remote {
             &Self::PUSH,
             &Self::PROXY,
             &Self::PROXY_AUTH_METHOD,
+            &Self::PROMISOR,
+            &Self::PARTIAL_CLONE_FILTER,
         ]
     }
 }
@@ -55,6 +67,27 @@ impl Section for Remote {
 /// The `remote.<name>.tagOpt` key type.
 pub type TagOpt = keys::Any<validate::TagOpt>;
 
+/// The `remote.<name>.partialCloneFilter` key type.
+pub type PartialCloneFilter = keys::Any<validate::PartialCloneFilter>;
+
+#[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
+mod partial_clone_filter {
+    use std::borrow::Cow;
+
+    use crate::{bstr::BStr, config, config::tree::remote::PartialCloneFilter, remote};
+
+    impl PartialCloneFilter {
+        /// Try to interpret `value` as filter specification for partial clones.
+        pub fn try_into_filter(
+            &'static self,
+            value: Cow<'_, BStr>,
+        ) -> Result<remote::fetch::Filter, config::key::GenericErrorWithValue> {
+            remote::fetch::Filter::from_bytes(value.as_ref())
+                .map_err(|_| config::key::GenericErrorWithValue::from_value(self, value.into_owned()))
+        }
+    }
+}
+
 mod tag_opts {
     use std::borrow::Cow;
 
@@ -98,4 +131,13 @@ pub mod validate {
             Ok(())
         }
     }
+
+    pub struct PartialCloneFilter;
+    impl Validate for PartialCloneFilter {
+        fn validate(&self, _value: &BStr) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+            #[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
+            super::Remote::PARTIAL_CLONE_FILTER.try_into_filter(Cow::Borrowed(_value))?;
+            Ok(())
+        }
+    }
 }
@@ -0,0 +1,22 @@
+use crate::{
+    config,
+    config::tree::{keys, Key, Section, Transfer},
+};
+
+impl Transfer {
+    /// The `transfer.bundleURI` key
+    pub const BUNDLE_URI: keys::Boolean = keys::Boolean::new_boolean("bundleURI", &config::Tree::TRANSFER);
+    /// The `transfer.advertiseObjectInfo` key
+    pub const ADVERTISE_OBJECT_INFO: keys::Boolean =
+        keys::Boolean::new_boolean("advertiseObjectInfo", &config::Tree::TRANSFER);
+}
+
+impl Section for Transfer {
+    fn name(&self) -> &'static str {
+        "transfer"
+    }
+
+    fn keys(&self) -> &[&dyn Key] {
+        &[&Self::BUNDLE_URI, &Self::ADVERTISE_OBJECT_INFO]
+    }
+}
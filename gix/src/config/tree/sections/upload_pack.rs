@@ -0,0 +1,36 @@
+use crate::{
+    config,
+    config::tree::{keys, Key, Section, UploadPack},
+};
+
+impl UploadPack {
+    /// The `uploadpack.allowFilter` key
+    pub const ALLOW_FILTER: keys::Boolean = keys::Boolean::new_boolean("allowFilter", &config::Tree::UPLOAD_PACK);
+    /// The `uploadpack.allowAnySHA1InWant` key
+    pub const ALLOW_ANY_SHA1_IN_WANT: keys::Boolean =
+        keys::Boolean::new_boolean("allowAnySHA1InWant", &config::Tree::UPLOAD_PACK);
+    /// The `uploadpack.allowRefInWant` key
+    pub const ALLOW_REF_IN_WANT: keys::Boolean =
+        keys::Boolean::new_boolean("allowRefInWant", &config::Tree::UPLOAD_PACK);
+    /// The `uploadpack.advertiseBundleURIs` key
+    pub const ADVERTISE_BUNDLE_URIS: keys::Boolean =
+        keys::Boolean::new_boolean("advertiseBundleURIs", &config::Tree::UPLOAD_PACK);
+    /// The `uploadpack.blobPackfileUri` key
+    pub const BLOB_PACKFILE_URI: keys::String = keys::String::new_string("blobPackfileUri", &config::Tree::UPLOAD_PACK);
+}
+
+impl Section for UploadPack {
+    fn name(&self) -> &'static str {
+        "uploadpack"
+    }
+
+    fn keys(&self) -> &[&dyn Key] {
+        &[
+            &Self::ALLOW_FILTER,
+            &Self::ALLOW_ANY_SHA1_IN_WANT,
+            &Self::ALLOW_REF_IN_WANT,
+            &Self::ADVERTISE_BUNDLE_URIS,
+            &Self::BLOB_PACKFILE_URI,
+        ]
+    }
+}
@@ -0,0 +1,195 @@
+//! A `git://` daemon to serve repositories to clients connecting over TCP, similar to `git daemon`.
+//!
+//! Each connection starts with a request naming the service and the repository to use, which is resolved and
+//! checked against the [`Options`] before it's served with [`Repository::upload_pack()`](crate::Repository::upload_pack())
+//! or [`Repository::receive_pack()`](crate::Repository::receive_pack()).
+use std::{
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::{Component, Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use gix_protocol::transport::{
+    server::git::{read_request, write_error, Request},
+    Service,
+};
+
+/// Configuration for [`serve()`] and [`serve_connection()`].
+#[derive(Default, Debug, Clone)]
+pub struct Options {
+    /// If set, the paths of all requested repositories are relative to this directory, similar to `git daemon --base-path`.
+    pub base_path: Option<PathBuf>,
+    /// If `true`, serve all repositories, even those without a `git-daemon-export-ok` file, similar to `git daemon --export-all`.
+    pub export_all: bool,
+    /// If not empty, only serve repositories in these directories or their subdirectories, similar to the directories
+    /// passed to `git daemon`.
+    pub allowed_directories: Vec<PathBuf>,
+    /// If `true`, clients may also push into repositories, similar to `git daemon --enable=receive-pack`.
+    pub enable_receive_pack: bool,
+}
+
+///
+pub mod serve_connection {
+    use crate::bstr::BString;
+    use gix_protocol::transport::Service;
+
+    /// The error returned by [`serve_connection()`](super::serve_connection()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Io(#[from] std::io::Error),
+        #[error("Could not read the request of the client")]
+        ReadRequest(#[from] gix_protocol::transport::server::git::read::Error),
+        #[error("The repository at {path:?} doesn't exist or isn't exported")]
+        AccessDenied { path: BString },
+        #[error("The {service:?} service isn't enabled, but was requested for {path:?}")]
+        ServiceNotEnabled { service: Service, path: BString },
+        #[error(transparent)]
+        UploadPackOptions(#[from] crate::repository::upload_pack_options::Error),
+        #[error(transparent)]
+        UploadPack(#[from] crate::repository::upload_pack::Error),
+        #[error(transparent)]
+        ReceivePackOptions(#[from] crate::repository::receive_pack_options::Error),
+        #[error(transparent)]
+        ReceivePack(#[from] gix_protocol::receive_pack::Error),
+    }
+}
+
+/// Accept connections on `listener` and serve each of them on its own thread according to `options`, until `should_interrupt`
+/// is set.
+///
+/// As a single failing connection must not stop the daemon, errors that occur while serving a connection are passed to
+/// `on_error` along with the address of the client. Only once all connections have been served is this function returning.
+pub fn serve(
+    listener: &TcpListener,
+    options: &Options,
+    should_interrupt: &AtomicBool,
+    on_error: &(dyn Fn(SocketAddr, serve_connection::Error) + Sync),
+) -> std::io::Result<()> {
+    for_each_connection(listener, should_interrupt, &|stream, peer| {
+        let mut input = stream.try_clone()?;
+        let mut out = stream;
+        if let Err(err) = serve_connection(&mut input, &mut out, options, should_interrupt) {
+            on_error(peer, err);
+        }
+        Ok(())
+    })
+}
+
+/// Accept connections on `listener` and call `handle` with each of them on its own thread, until `should_interrupt` is set.
+pub(crate) fn for_each_connection(
+    listener: &TcpListener,
+    should_interrupt: &AtomicBool,
+    handle: &(dyn Fn(TcpStream, SocketAddr) -> std::io::Result<()> + Sync),
+) -> std::io::Result<()> {
+    // Polling allows us to notice interrupts even if no client is connecting.
+    listener.set_nonblocking(true)?;
+    std::thread::scope(|scope| {
+        while !should_interrupt.load(Ordering::Relaxed) {
+            let (stream, peer) = match listener.accept() {
+                Ok(connection) => connection,
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+            stream.set_nonblocking(false)?;
+            // There is nobody to tell about failing to clone the stream, so the connection is simply closed.
+            scope.spawn(move || handle(stream, peer).ok());
+        }
+        Ok(())
+    })
+}
+
+/// Serve a single client that connected to the daemon, reading its request from `input` and writing responses to `out`,
+/// according to `options`.
+///
+/// Clients asking for repositories that don't exist or that may not be served receive the same error, so they can't
+/// tell these cases apart.
+pub fn serve_connection(
+    input: &mut dyn std::io::Read,
+    out: &mut dyn std::io::Write,
+    options: &Options,
+    should_interrupt: &AtomicBool,
+) -> Result<(), serve_connection::Error> {
+    use serve_connection::Error;
+
+    let request = read_request(input)?;
+    let Some(repo) = open_exported_repository(&request, options) else {
+        write_error(
+            format!("access denied or repository not exported: {}", request.repository_path).as_bytes(),
+            out,
+        )?;
+        return Err(Error::AccessDenied {
+            path: request.repository_path,
+        });
+    };
+
+    let version = request.desired_version();
+    match request.service {
+        Service::UploadPack => {
+            let upload_pack_options = repo.upload_pack_options()?;
+            repo.upload_pack(version, input, out, should_interrupt, &upload_pack_options)?;
+        }
+        Service::ReceivePack if options.enable_receive_pack => {
+            let receive_pack_options = repo.receive_pack_options()?;
+            repo.receive_pack(version, input, out, should_interrupt, &receive_pack_options)?;
+        }
+        Service::ReceivePack => {
+            write_error(
+                format!("service not enabled: {}", request.repository_path).as_bytes(),
+                out,
+            )?;
+            return Err(Error::ServiceNotEnabled {
+                service: request.service,
+                path: request.repository_path,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Resolve the repository requested by the client and open it if it may be served.
+fn open_exported_repository(request: &Request, options: &Options) -> Option<crate::Repository> {
+    // Like `git`, we don't support `~user` paths, and all others must be absolute.
+    let path = request.repository_path.strip_prefix(b"/")?;
+    let repo = open_repository(gix_path::try_from_byte_slice(path).ok()?, options.base_path.as_deref())?;
+    let git_dir = repo.git_dir();
+    if !options.export_all && !is_exported(&repo) {
+        return None;
+    }
+    if !options.allowed_directories.is_empty() && !is_in_allowed_directory(git_dir, &options.allowed_directories) {
+        return None;
+    }
+    Some(repo)
+}
+
+/// Open the repository at `path` relative to `base_path` or the root of the file system, with or without `.git` suffix.
+pub(crate) fn open_repository(path: &Path, base_path: Option<&Path>) -> Option<crate::Repository> {
+    if path.components().any(|c| matches!(c, Component::ParentDir)) {
+        return None;
+    }
+    let path = base_path.unwrap_or_else(|| Path::new("/")).join(path);
+    let mut with_suffix = path.clone().into_os_string();
+    with_suffix.push(".git");
+    [path, with_suffix.into()]
+        .into_iter()
+        .find_map(|candidate| crate::open(candidate).ok())
+}
+
+/// Return `true` if `repo` is marked as exported with a `git-daemon-export-ok` file.
+pub(crate) fn is_exported(repo: &crate::Repository) -> bool {
+    repo.git_dir().join("git-daemon-export-ok").is_file()
+}
+
+fn is_in_allowed_directory(git_dir: &Path, allowed: &[PathBuf]) -> bool {
+    let Ok(git_dir) = gix_path::realpath(git_dir) else {
+        return false;
+    };
+    allowed
+        .iter()
+        .filter_map(|dir| gix_path::realpath(dir).ok())
+        .any(|dir| git_dir.starts_with(dir))
+}
@@ -0,0 +1,306 @@
+//! A handler for the smart HTTP protocol to serve fetches and pushes, similar to `git http-backend`.
+//!
+//! It's independent of any HTTP server, which passes the relevant parts of each [`Request`] to [`respond()`], sends
+//! the status and headers of the returned [`Response`], and finally lets it [write its body](Response::write_body()).
+//! A minimal blocking HTTP server to do just that is available with [`serve()`].
+use std::sync::atomic::AtomicBool;
+
+use gix_protocol::transport::{Protocol, Service};
+
+use crate::{
+    config::{cache::util::ApplyLeniencyDefault, tree::Http},
+    Repository,
+};
+
+/// The parts of an HTTP request that are relevant to serve it.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct Request<'a> {
+    /// The HTTP method, like `GET` or `POST`.
+    pub method: &'a str,
+    /// The path of the request relative to the URL of the repository, like `info/refs` or `/git-upload-pack`.
+    pub path: &'a str,
+    /// The query of the request URL without the leading `?`, like `service=git-upload-pack`.
+    pub query: Option<&'a str>,
+    /// The value of the `Content-Type` header.
+    pub content_type: Option<&'a str>,
+    /// The value of the `Content-Encoding` header, which may be `gzip` to indicate a compressed request body.
+    pub content_encoding: Option<&'a str>,
+    /// The value of the `Git-Protocol` header, like `version=2`.
+    pub git_protocol: Option<&'a str>,
+    /// The name of the authenticated user, if the server authenticated the client.
+    ///
+    /// Like in `git`, pushes are only allowed to authenticated users unless `http.receivepack` is configured.
+    pub remote_user: Option<&'a str>,
+}
+
+/// The response to a [`Request`], whose status and headers are to be sent before its body is [written](Response::write_body()).
+pub struct Response<'repo> {
+    /// The HTTP status code, like `200`.
+    pub status: u16,
+    /// The reason phrase of the status code, like `OK`.
+    pub reason: &'static str,
+    /// The headers to send along with the response, as pairs of name and value.
+    pub headers: Vec<(&'static str, String)>,
+    body: Body<'repo>,
+}
+
+enum Body<'repo> {
+    Text(String),
+    Advertisement {
+        repo: &'repo Repository,
+        service: Service,
+        version: Protocol,
+    },
+    Rpc {
+        repo: &'repo Repository,
+        service: Service,
+        version: Protocol,
+        gzip: bool,
+    },
+}
+
+///
+pub mod respond {
+    /// The error returned by [`respond()`](super::respond()).
+    pub type Error = crate::config::boolean::Error;
+}
+
+///
+pub mod write_body {
+    /// The error returned by [`Response::write_body()`](super::Response::write_body()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Io(#[from] std::io::Error),
+        #[error(transparent)]
+        UploadPackOptions(#[from] crate::repository::upload_pack_options::Error),
+        #[error(transparent)]
+        UploadPack(#[from] crate::repository::upload_pack::Error),
+        #[error(transparent)]
+        ReceivePackOptions(#[from] crate::repository::receive_pack_options::Error),
+        #[error(transparent)]
+        ReceivePack(#[from] gix_protocol::receive_pack::Error),
+    }
+}
+
+/// Respond to `request` for `repo`, which is expected to be the repository the request URL points to.
+///
+/// Which services are available is controlled by `http.uploadpack`, which is enabled by default, and `http.receivepack`,
+/// which defaults to being enabled only if the client [was authenticated](Request::remote_user).
+/// Only the smart HTTP protocol is supported, so requests that don't name a service are refused.
+pub fn respond<'repo>(repo: &'repo Repository, request: &Request<'_>) -> Result<Response<'repo>, respond::Error> {
+    let path = request.path.trim_start_matches('/');
+    let (service, is_advertisement) = match path {
+        "info/refs" => match request
+            .query
+            .into_iter()
+            .flat_map(|query| query.split('&'))
+            .find_map(|param| param.strip_prefix("service="))
+        {
+            Some(service) => match to_service(service) {
+                Some(service) => (service, true),
+                None => {
+                    return Ok(Response::text(
+                        403,
+                        "Forbidden",
+                        format!("Unsupported service: '{service}'"),
+                    ))
+                }
+            },
+            None => {
+                return Ok(Response::text(
+                    403,
+                    "Forbidden",
+                    "Only the smart HTTP protocol is supported".into(),
+                ))
+            }
+        },
+        "git-upload-pack" => (Service::UploadPack, false),
+        "git-receive-pack" => (Service::ReceivePack, false),
+        _ => return Ok(Response::text(404, "Not Found", "Request not supported".into())),
+    };
+
+    let expected_method = if is_advertisement { "GET" } else { "POST" };
+    if request.method != expected_method {
+        let mut res = Response::text(405, "Method Not Allowed", "Method not allowed".into());
+        res.headers.push(("Allow", expected_method.into()));
+        return Ok(res);
+    }
+    if !is_enabled(repo, service, request.remote_user.is_some())? {
+        return Ok(Response::text(
+            403,
+            "Forbidden",
+            format!("Service not enabled: '{}'", service.as_str()),
+        ));
+    }
+
+    let version = desired_version(request.git_protocol);
+    let mut headers = no_cache_headers();
+    let body = if is_advertisement {
+        headers.push((
+            "Content-Type",
+            format!("application/x-{}-advertisement", service.as_str()),
+        ));
+        Body::Advertisement { repo, service, version }
+    } else {
+        let wanted_content_type = format!("application/x-{}-request", service.as_str());
+        if request.content_type != Some(wanted_content_type.as_str()) {
+            return Ok(Response::text(
+                415,
+                "Unsupported Media Type",
+                format!("Expected content type '{wanted_content_type}'"),
+            ));
+        }
+        let gzip = match request.content_encoding {
+            None | Some("identity") => false,
+            Some("gzip" | "x-gzip") => true,
+            Some(encoding) => {
+                return Ok(Response::text(
+                    415,
+                    "Unsupported Media Type",
+                    format!("Unsupported content encoding '{encoding}'"),
+                ))
+            }
+        };
+        headers.push(("Content-Type", format!("application/x-{}-result", service.as_str())));
+        Body::Rpc {
+            repo,
+            service,
+            version,
+            gzip,
+        }
+    };
+    Ok(Response {
+        status: 200,
+        reason: "OK",
+        headers,
+        body,
+    })
+}
+
+impl Response<'_> {
+    fn text(status: u16, reason: &'static str, message: String) -> Self {
+        let mut headers = no_cache_headers();
+        headers.push(("Content-Type", "text/plain; charset=utf-8".into()));
+        Response {
+            status,
+            reason,
+            headers,
+            body: Body::Text(message),
+        }
+    }
+
+    /// Write the body of this response to `out`, reading the body of the request from `input` if needed.
+    ///
+    /// `should_interrupt` can be set to abort serving the request.
+    pub fn write_body(
+        self,
+        input: &mut dyn std::io::Read,
+        out: &mut dyn std::io::Write,
+        should_interrupt: &AtomicBool,
+    ) -> Result<(), write_body::Error> {
+        match self.body {
+            Body::Text(message) => {
+                out.write_all(message.as_bytes())?;
+                out.write_all(b"\n")?;
+            }
+            Body::Advertisement { repo, service, version } => {
+                // Protocol V2 starts with its own version line, everything else is expected to announce the service.
+                if !(service == Service::UploadPack && version == Protocol::V2) {
+                    let line = format!("# service={}\n", service.as_str());
+                    write!(out, "{:04x}{line}0000", line.len() + 4)?;
+                }
+                run_service(repo, service, version, input, out, should_interrupt, true)?;
+            }
+            Body::Rpc {
+                repo,
+                service,
+                version,
+                gzip,
+            } => {
+                if gzip {
+                    let mut input = flate2::read::GzDecoder::new(input);
+                    run_service(repo, service, version, &mut input, out, should_interrupt, false)?;
+                } else {
+                    run_service(repo, service, version, input, out, should_interrupt, false)?;
+                }
+            }
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+fn run_service(
+    repo: &Repository,
+    service: Service,
+    version: Protocol,
+    input: &mut dyn std::io::Read,
+    out: &mut dyn std::io::Write,
+    should_interrupt: &AtomicBool,
+    advertise_refs: bool,
+) -> Result<(), write_body::Error> {
+    match service {
+        Service::UploadPack => {
+            let mut options = repo.upload_pack_options()?;
+            options.advertise_refs = advertise_refs;
+            options.stateless_rpc = !advertise_refs;
+            repo.upload_pack(version, input, out, should_interrupt, &options)?;
+        }
+        Service::ReceivePack => {
+            let mut options = repo.receive_pack_options()?;
+            options.advertise_refs = advertise_refs;
+            options.stateless_rpc = !advertise_refs;
+            repo.receive_pack(version, input, out, should_interrupt, &options)?;
+        }
+    }
+    Ok(())
+}
+
+fn to_service(name: &str) -> Option<Service> {
+    Some(match name {
+        "git-upload-pack" => Service::UploadPack,
+        "git-receive-pack" => Service::ReceivePack,
+        _ => return None,
+    })
+}
+
+fn is_enabled(repo: &Repository, service: Service, is_authenticated: bool) -> Result<bool, respond::Error> {
+    let (key, default) = match service {
+        Service::UploadPack => (&Http::UPLOAD_PACK, true),
+        Service::ReceivePack => (&Http::RECEIVE_PACK, is_authenticated),
+    };
+    Ok(repo
+        .config
+        .resolved
+        .boolean(key)
+        .map(|res| key.enrich_error(res))
+        .transpose()
+        .with_lenient_default(repo.config.lenient_config)?
+        .unwrap_or(default))
+}
+
+/// Parse the protocol version out of the `Git-Protocol` header, like `version=2:key=value`, defaulting to `V0` like `git` does.
+fn desired_version(git_protocol: Option<&str>) -> Protocol {
+    git_protocol
+        .into_iter()
+        .flat_map(|value| value.split(':'))
+        .find_map(|param| match param.strip_prefix("version=")? {
+            "1" => Some(Protocol::V1),
+            "2" => Some(Protocol::V2),
+            _ => None,
+        })
+        .unwrap_or(Protocol::V0)
+}
+
+fn no_cache_headers() -> Vec<(&'static str, String)> {
+    vec![
+        ("Expires", "Fri, 01 Jan 1980 00:00:00 GMT".into()),
+        ("Pragma", "no-cache".into()),
+        ("Cache-Control", "no-cache, max-age=0, must-revalidate".into()),
+    ]
+}
+
+mod serve;
+pub use serve::{serve, serve_connection, Options};
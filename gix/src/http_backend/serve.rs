@@ -0,0 +1,282 @@
+use std::{
+    io::{BufRead, Read, Write},
+    net::{SocketAddr, TcpListener},
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+
+use crate::{
+    daemon::{for_each_connection, is_exported, open_repository},
+    http_backend::{respond, Request},
+};
+
+/// Configuration for [`serve()`](super::serve()).
+#[derive(Default, Debug, Clone)]
+pub struct Options {
+    /// The directory containing all repositories to serve, which the paths of request URLs are relative to,
+    /// similar to `GIT_PROJECT_ROOT`.
+    pub base_path: PathBuf,
+    /// If `true`, serve all repositories, even those without a `git-daemon-export-ok` file, similar to `GIT_HTTP_EXPORT_ALL`.
+    pub export_all: bool,
+}
+
+///
+pub mod serve_connection {
+    /// The error returned by [`serve_connection()`](super::serve_connection()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Io(#[from] std::io::Error),
+        #[error("The HTTP request was malformed: {message}")]
+        InvalidRequest { message: &'static str },
+        #[error(transparent)]
+        Respond(#[from] crate::http_backend::respond::Error),
+        #[error(transparent)]
+        WriteBody(#[from] crate::http_backend::write_body::Error),
+    }
+}
+
+/// The routes of the smart HTTP protocol, which follow the path to the repository.
+const ROUTES: &[&str] = &["/info/refs", "/git-upload-pack", "/git-receive-pack"];
+/// The maximum length of the request line and of each header line.
+const MAX_LINE_LEN: u64 = 16 * 1024;
+/// The maximum amount of headers we accept.
+const MAX_HEADERS: usize = 128;
+
+/// Accept HTTP connections on `listener` and serve each of them on its own thread with repositories in [`Options::base_path`],
+/// until `should_interrupt` is set.
+///
+/// This is a minimal HTTP/1.1 server without support for authentication or TLS, which closes each connection after
+/// a single request. Errors that occur while serving a connection are passed to `on_error` along with the address of the client.
+pub fn serve(
+    listener: &TcpListener,
+    options: &Options,
+    should_interrupt: &AtomicBool,
+    on_error: &(dyn Fn(SocketAddr, serve_connection::Error) + Sync),
+) -> std::io::Result<()> {
+    for_each_connection(listener, should_interrupt, &|stream, peer| {
+        let mut input = std::io::BufReader::new(stream.try_clone()?);
+        let mut out = std::io::BufWriter::new(stream);
+        if let Err(err) = serve_connection(&mut input, &mut out, options, should_interrupt) {
+            on_error(peer, err);
+        }
+        Ok(())
+    })
+}
+
+/// Serve a single HTTP request read from `input` by writing the response to `out`, according to `options`.
+pub fn serve_connection(
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+    options: &Options,
+    should_interrupt: &AtomicBool,
+) -> Result<(), serve_connection::Error> {
+    let request_line = read_line(input)?;
+    let mut tokens = request_line.split(' ');
+    let (Some(method), Some(target), Some(version)) = (tokens.next(), tokens.next(), tokens.next()) else {
+        return bad_request(out, "invalid request line");
+    };
+    if !version.starts_with("HTTP/1.") {
+        return bad_request(out, "unsupported HTTP version");
+    }
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(input)?;
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return bad_request(out, "too many headers");
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            return bad_request(out, "invalid header");
+        };
+        headers.push((name.trim().to_ascii_lowercase(), value.trim().to_owned()));
+    }
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find_map(|(key, value)| (key == name).then_some(value.as_str()))
+    };
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (target, None),
+    };
+    let Some(path) = percent_decode(path) else {
+        return bad_request(out, "invalid percent-encoding in path");
+    };
+    let Some((repo_path, route)) = ROUTES
+        .iter()
+        .find_map(|route| path.strip_suffix(route).map(|repo_path| (repo_path, route)))
+    else {
+        return write_text(out, 404, "Not Found", "Request not supported");
+    };
+    let repo = open_repository(
+        Path::new(repo_path.trim_start_matches('/')),
+        Some(options.base_path.as_path()),
+    )
+    .filter(|repo| options.export_all || is_exported(repo));
+    let Some(repo) = repo else {
+        return write_text(out, 404, "Not Found", "Repository not found");
+    };
+
+    let response = respond(
+        &repo,
+        &Request {
+            method,
+            path: route,
+            query,
+            content_type: header("content-type"),
+            content_encoding: header("content-encoding"),
+            git_protocol: header("git-protocol"),
+            remote_user: None,
+        },
+    )?;
+
+    let mut body: Box<dyn Read + '_> =
+        if header("transfer-encoding").map_or(false, |v| v.eq_ignore_ascii_case("chunked")) {
+            Box::new(Chunked::new(input))
+        } else {
+            let len = match header("content-length").map(str::parse::<u64>) {
+                Some(Ok(len)) => len,
+                Some(Err(_)) => return bad_request(out, "invalid content length"),
+                None => 0,
+            };
+            Box::new(input.take(len))
+        };
+    if response.status == 200 && header("expect").map_or(false, |v| v.eq_ignore_ascii_case("100-continue")) {
+        out.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+        out.flush()?;
+    }
+
+    write_head(out, response.status, response.reason, &response.headers)?;
+    response.write_body(&mut body, out, should_interrupt)?;
+    out.flush()?;
+    Ok(())
+}
+
+fn read_line(input: &mut dyn BufRead) -> Result<String, serve_connection::Error> {
+    let mut line = String::new();
+    input.take(MAX_LINE_LEN).read_line(&mut line)?;
+    if !line.ends_with('\n') {
+        return Err(serve_connection::Error::InvalidRequest {
+            message: "line too long or truncated",
+        });
+    }
+    line.truncate(line.trim_end_matches(['\r', '\n']).len());
+    Ok(line)
+}
+
+fn write_head(
+    out: &mut dyn Write,
+    status: u16,
+    reason: &str,
+    headers: &[(&'static str, String)],
+) -> std::io::Result<()> {
+    write!(out, "HTTP/1.1 {status} {reason}\r\n")?;
+    for (name, value) in headers {
+        write!(out, "{name}: {value}\r\n")?;
+    }
+    // Without content length, the end of the body is marked by closing the connection.
+    out.write_all(b"Connection: close\r\n\r\n")
+}
+
+fn write_text(
+    out: &mut dyn Write,
+    status: u16,
+    reason: &'static str,
+    message: &str,
+) -> Result<(), serve_connection::Error> {
+    write_head(
+        out,
+        status,
+        reason,
+        &[("Content-Type", "text/plain; charset=utf-8".into())],
+    )?;
+    writeln!(out, "{message}")?;
+    out.flush()?;
+    Ok(())
+}
+
+fn bad_request(out: &mut dyn Write, message: &'static str) -> Result<(), serve_connection::Error> {
+    write_text(out, 400, "Bad Request", message)?;
+    Err(serve_connection::Error::InvalidRequest { message })
+}
+
+fn percent_decode(input: &str) -> Option<String> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut bytes = input.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let hex = [bytes.next()?, bytes.next()?];
+            out.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            out.push(byte);
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// A reader for request bodies sent with `Transfer-Encoding: chunked`.
+struct Chunked<'a> {
+    inner: &'a mut dyn BufRead,
+    remaining_in_chunk: u64,
+    done: bool,
+}
+
+impl<'a> Chunked<'a> {
+    fn new(inner: &'a mut dyn BufRead) -> Self {
+        Chunked {
+            inner,
+            remaining_in_chunk: 0,
+            done: false,
+        }
+    }
+
+    fn invalid(message: &str) -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+    }
+
+    fn read_line(&mut self) -> std::io::Result<String> {
+        let mut line = String::new();
+        (&mut self.inner).take(MAX_LINE_LEN).read_line(&mut line)?;
+        if !line.ends_with('\n') {
+            return Err(Self::invalid("chunk header too long or truncated"));
+        }
+        line.truncate(line.trim_end_matches(['\r', '\n']).len());
+        Ok(line)
+    }
+}
+
+impl Read for Chunked<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining_in_chunk == 0 {
+            let line = self.read_line()?;
+            let size = line.split(';').next().unwrap_or_default().trim();
+            self.remaining_in_chunk = u64::from_str_radix(size, 16).map_err(|_| Self::invalid("invalid chunk size"))?;
+            if self.remaining_in_chunk == 0 {
+                // Skip trailers until the empty line that ends the body.
+                while !self.read_line()?.is_empty() {}
+                self.done = true;
+                return Ok(0);
+            }
+        }
+        let max = buf
+            .len()
+            .min(usize::try_from(self.remaining_in_chunk).unwrap_or(usize::MAX));
+        let read = self.inner.read(&mut buf[..max])?;
+        if read == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        self.remaining_in_chunk -= read as u64;
+        if self.remaining_in_chunk == 0 && !self.read_line()?.is_empty() {
+            return Err(Self::invalid("chunk wasn't terminated by a newline"));
+        }
+        Ok(read)
+    }
+}
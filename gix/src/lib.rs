@@ -95,6 +95,8 @@
 pub use gix_actor as actor;
 #[cfg(feature = "attributes")]
 pub use gix_attributes as attrs;
+#[cfg(feature = "blocking-network-client")]
+pub use gix_bundle as bundle;
 #[cfg(feature = "command")]
 pub use gix_command as command;
 pub use gix_commitgraph as commitgraph;
@@ -291,6 +293,11 @@ pub fn open_opts(directory: impl Into<std::path::PathBuf>, options: open::Option
 ///
 pub mod create;
 
+#[cfg(feature = "blocking-network-server")]
+pub mod daemon;
+#[cfg(feature = "blocking-network-server")]
+pub mod http_backend;
+
 ///
 pub mod open;
 
@@ -325,6 +332,10 @@ pub mod status;
 ///
 pub mod shallow;
 
+///
+#[cfg(feature = "worktree-mutation")]
+pub mod sparse_checkout;
+
 ///
 pub mod discover;
 
@@ -88,12 +88,13 @@ impl<'a, 'old> Platform<'a, 'old> {
             tracked: self.rewrites.map(rewrites::Tracker::new),
             err: None,
         };
-        match gix_diff::tree::Changes::from(TreeRefIter::from_bytes(&self.lhs.data)).needed_to_obtain(
-            TreeRefIter::from_bytes(&other.data),
-            &mut self.state,
-            &repo.objects,
-            &mut delegate,
-        ) {
+        match gix_diff::tree::Changes::from(TreeRefIter::from_bytes(&self.lhs.data, repo.object_hash()))
+            .needed_to_obtain(
+                TreeRefIter::from_bytes(&other.data, repo.object_hash()),
+                &mut self.state,
+                &repo.objects,
+                &mut delegate,
+            ) {
             Ok(()) => {
                 let outcome = Outcome {
                     rewrites: delegate.process_tracked_changes(resource_cache)?,
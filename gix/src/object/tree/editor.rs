@@ -0,0 +1,74 @@
+use gix_hash::ObjectId;
+pub use gix_object::tree::editor::Error;
+use gix_object::tree::EntryKind;
+
+use crate::{
+    bstr::{BStr, ByteSlice},
+    ext::ObjectIdExt,
+    Id, Repository,
+};
+
+/// A way to edit trees at arbitrary depths by inserting, replacing or removing entries at slash-separated paths,
+/// to write only the trees that were affected into the object database.
+///
+/// Create it with [`Repository::edit_tree()`] or [`Tree::edit()`](crate::Tree::edit()).
+pub struct Editor<'repo> {
+    inner: gix_object::tree::Editor<'repo>,
+    repo: &'repo Repository,
+}
+
+impl std::fmt::Debug for Editor<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Editor").finish_non_exhaustive()
+    }
+}
+
+/// Lifecycle
+impl<'repo> Editor<'repo> {
+    /// Create a new instance to edit `root`, loading sub-trees from the object database of `repo` as needed.
+    pub fn new(root: gix_object::Tree, repo: &'repo Repository) -> Self {
+        Editor {
+            inner: gix_object::tree::Editor::new(root, &repo.objects, repo.object_hash()),
+            repo,
+        }
+    }
+}
+
+/// Editing
+impl<'repo> Editor<'repo> {
+    /// Insert a new entry of `kind` with `id` at the slash-separated `rela_path`, or replace the entry that is already there.
+    ///
+    /// Intermediate trees are created as needed, replacing non-tree entries that are in the way.
+    /// If `kind` is [`EntryKind::Tree`], `id` must be an existing tree which replaces everything that was at `rela_path`.
+    pub fn upsert(
+        &mut self,
+        rela_path: impl AsRef<BStr>,
+        kind: EntryKind,
+        id: impl Into<ObjectId>,
+    ) -> Result<&mut Self, Error> {
+        self.inner.upsert(to_components(rela_path.as_ref()), kind, id.into())?;
+        Ok(self)
+    }
+
+    /// Remove the entry at the slash-separated `rela_path`, along with all of its children if it is a tree.
+    ///
+    /// Nothing happens if there is no such entry, and trees that become empty this way are removed when writing.
+    pub fn remove(&mut self, rela_path: impl AsRef<BStr>) -> Result<&mut Self, Error> {
+        self.inner.remove(to_components(rela_path.as_ref()))?;
+        Ok(self)
+    }
+
+    /// Write all trees that were affected by edits into the object database and return the id of the new root tree.
+    ///
+    /// The editor can be used to continue editing the written tree afterwards.
+    pub fn write(&mut self) -> Result<Id<'repo>, crate::object::write::Error> {
+        let repo = self.repo;
+        self.inner
+            .write(|tree| repo.write_object(tree).map(Id::detach))
+            .map(|id| id.attach(repo))
+    }
+}
+
+fn to_components(rela_path: &BStr) -> impl Iterator<Item = &BStr> {
+    rela_path.split_str("/").map(ByteSlice::as_bstr)
+}
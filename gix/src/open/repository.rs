@@ -218,6 +218,7 @@ impl ThreadSafeRepository {
                 object_hash,
                 precompose_unicode: repo_config.precompose_unicode,
                 prohibit_windows_device_names: repo_config.protect_windows,
+                ref_storage: repo_config.ref_storage,
             };
             match &common_dir {
                 Some(common_dir) => {
@@ -4,7 +4,7 @@ use std::borrow::Cow;
 
 use gix_protocol::transport::client::Transport;
 
-use crate::{config::tree::Protocol, remote::Connection, Remote};
+use crate::{bstr::ByteSlice, config::tree::Protocol, remote::Connection, Remote};
 
 mod error {
     use crate::{bstr::BString, config, remote};
@@ -34,6 +34,12 @@ mod error {
             source: Box<gix_discover::is_git::Error>,
             url: gix_url::Url,
         },
+        #[cfg(feature = "blocking-network-client")]
+        #[error(transparent)]
+        OpenBundle(#[from] gix_bundle::open::Error),
+        #[cfg(feature = "blocking-network-client")]
+        #[error(transparent)]
+        BundlePrerequisites(#[from] gix_bundle::verify::Error),
     }
 
     impl gix_protocol::transport::IsSpuriousError for Error {
@@ -73,10 +79,17 @@ impl<'repo> Remote<'repo> {
     /// Note that the `protocol.version` configuration key affects the transport protocol used to connect,
     /// with `2` being the default.
     ///
+    /// If the url points to a [bundle file](gix_bundle) when fetching, it's served like a remote repository would be,
+    /// after making sure that all objects it depends on are present in our repository.
+    ///
     /// The transport used for connection can be configured via `transport_mut().configure()` assuming the actually
     /// used transport is well known. If that's not the case, the transport can be created by hand and passed to
     /// [to_connection_with_transport()][Self::to_connection_with_transport()].
-    #[cfg(any(feature = "blocking-network-client", feature = "async-network-client-async-std"))]
+    #[cfg(any(
+        feature = "blocking-network-client",
+        feature = "async-network-client-async-std",
+        feature = "async-http-transport-reqwest"
+    ))]
     #[gix_protocol::maybe_async::maybe_async]
     pub async fn connect(
         &self,
@@ -84,6 +97,10 @@ impl<'repo> Remote<'repo> {
     ) -> Result<Connection<'_, 'repo, Box<dyn Transport + Send>>, Error> {
         let (url, version) = self.sanitized_url_and_version(direction)?;
         #[cfg(feature = "blocking-network-client")]
+        if let Some(transport) = self.bundle_transport(&url, direction)? {
+            return Ok(self.to_connection_with_transport(transport));
+        }
+        #[cfg(feature = "blocking-network-client")]
         let scheme_is_ssh = url.scheme == gix_url::Scheme::Ssh;
         let transport = gix_protocol::transport::connect(
             url,
@@ -101,6 +118,34 @@ impl<'repo> Remote<'repo> {
         Ok(self.to_connection_with_transport(transport))
     }
 
+    /// Return a transport to fetch from the bundle file `url` points to, or `None` if it's not a bundle.
+    #[cfg(feature = "blocking-network-client")]
+    fn bundle_transport(
+        &self,
+        url: &gix_url::Url,
+        direction: crate::remote::Direction,
+    ) -> Result<Option<Box<dyn Transport + Send>>, Error> {
+        if url.scheme != gix_url::Scheme::File || direction != crate::remote::Direction::Fetch {
+            return Ok(None);
+        }
+        let path = gix_path::from_bstr(url.path.as_bstr());
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let bundle = gix_bundle::Bundle::at(path.into_owned())?;
+        bundle.verify_prerequisites(&self.repo.objects)?;
+        let (read, write) = bundle.into_connection();
+        Ok(Some(Box::new(gix_protocol::transport::client::git::Connection::new(
+            read,
+            write,
+            gix_protocol::transport::Protocol::V2,
+            url.path.clone(),
+            None::<(String, Option<u16>)>,
+            gix_protocol::transport::client::git::ConnectMode::Process,
+            self.repo.config.trace_packet(),
+        ))))
+    }
+
     /// Produce the sanitized URL and protocol version to use as obtained by querying the repository configuration.
     ///
     /// This can be useful when using custom transports to allow additional configuration.
@@ -109,7 +154,8 @@ impl<'repo> Remote<'repo> {
         direction: crate::remote::Direction,
     ) -> Result<(gix_url::Url, gix_protocol::transport::Protocol), Error> {
         fn sanitize(mut url: gix_url::Url) -> Result<gix_url::Url, Error> {
-            if url.scheme == gix_url::Scheme::File {
+            // Files are bundles, and only directories can contain repositories.
+            if url.scheme == gix_url::Scheme::File && !gix_path::from_bstr(url.path.as_bstr()).is_file() {
                 let mut dir = gix_path::to_native_path_on_windows(Cow::Borrowed(url.path.as_ref()));
                 let kind = gix_discover::is_git(dir.as_ref())
                     .or_else(|_| {
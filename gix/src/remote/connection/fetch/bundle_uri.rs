@@ -0,0 +1,488 @@
+use std::{path::PathBuf, sync::atomic::AtomicBool};
+
+use gix_bundle::list::{Heuristic, Mode};
+use gix_protocol::transport::client::Transport;
+
+use crate::{
+    bstr::{BStr, BString},
+    config::{
+        cache::util::ApplyLeniency,
+        tree::{Fetch, Key, Transfer},
+    },
+    Repository,
+};
+
+/// The maximum amount of bundle lists that may refer to each other, just like in `git`.
+const MAX_LIST_DEPTH: usize = 4;
+
+#[derive(Debug, thiserror::Error)]
+pub(super) enum Error {
+    #[error("Could not download {uri:?}")]
+    Download {
+        uri: BString,
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+    #[error("Cannot obtain {uri:?} as it's neither a local path nor an HTTP URL that can be downloaded")]
+    UnsupportedScheme { uri: BString },
+    #[error(transparent)]
+    Open(#[from] gix_bundle::open::Error),
+    #[error("{uri:?} is neither a bundle nor a bundle list")]
+    ParseList {
+        uri: BString,
+        source: gix_config::file::init::Error,
+    },
+    #[error("The bundle list at {uri:?} is invalid")]
+    List {
+        uri: BString,
+        source: gix_bundle::list::update::Error,
+    },
+    #[error("The bundle list at {uri:?} refers to more than {} nested lists", MAX_LIST_DEPTH)]
+    TooDeep { uri: BString },
+    #[error("Could not unbundle {uri:?}")]
+    Unbundle {
+        uri: BString,
+        source: gix_bundle::unbundle::Error,
+    },
+    #[error("Could not create references for the bundle at {uri:?}")]
+    EditReferences {
+        uri: BString,
+        source: crate::reference::edit::Error,
+    },
+    #[error("Failed to remove .keep file at \"{}\"", path.display())]
+    RemovePackKeepFile { path: PathBuf, source: std::io::Error },
+    #[error(transparent)]
+    CreationTokenConfig(#[from] crate::config::unsigned_integer::Error),
+    #[error(transparent)]
+    TransferConfig(#[from] crate::config::boolean::Error),
+    #[error("Could not load the repository configuration to store the bundle creation token")]
+    LoadConfig(#[from] gix_config::file::init::from_paths::Error),
+    #[error("Could not store the bundle creation token in the repository configuration")]
+    SetConfig(#[from] gix_config::file::set_raw_value::Error),
+    #[error("Could not write the repository configuration")]
+    WriteConfig(#[from] std::io::Error),
+}
+
+/// What we learned from applying bundles.
+#[derive(Default)]
+struct Outcome {
+    /// The heuristic of the bundle list, if there was one.
+    heuristic: Option<Heuristic>,
+    /// The greatest creation token among all bundles that were applied.
+    creation_token: Option<u64>,
+}
+
+/// A downloaded or local bundle, or the bundle list it turned out to be.
+enum Obtained {
+    Bundle {
+        bundle: gix_bundle::Bundle,
+        /// The file the bundle was downloaded into, which is removed once it is dropped.
+        tempfile: Option<gix_tempfile::Handle<gix_tempfile::handle::Writable>>,
+    },
+    List(gix_bundle::List),
+}
+
+/// Everything needed to apply bundles to a repository.
+pub(super) struct Bundles<'a> {
+    pub repo: &'a Repository,
+    #[cfg_attr(
+        not(any(
+            feature = "blocking-http-transport-reqwest",
+            feature = "blocking-http-transport-curl"
+        )),
+        allow(dead_code)
+    )]
+    pub remote_name: Option<&'a BStr>,
+    pub should_interrupt: &'a AtomicBool,
+    pub options: gix_pack::bundle::write::Options,
+}
+
+impl Bundles<'_> {
+    /// Obtain objects from bundles before fetching from the remote, so that only what they don't contain needs to be fetched.
+    ///
+    /// Bundles are taken from `explicit_uri` if set, which is also written to `fetch.bundleURI` along with the creation token
+    /// if the bundle list there uses the creation token heuristic, so later fetches can incrementally pick up new bundles.
+    /// Otherwise, `fetch.bundleURI` is used, or the list of bundles the remote `advertised`.
+    ///
+    /// Like in `git`, failing to use bundles isn't fatal as the fetch will obtain all objects that are still missing,
+    /// which is why errors are merely reported through `progress`.
+    pub(super) fn apply(
+        &self,
+        explicit_uri: Option<&BStr>,
+        advertised: Option<gix_bundle::List>,
+        progress: &mut dyn crate::DynNestedProgress,
+    ) {
+        if let Err(err) = self.apply_inner(explicit_uri, advertised, progress) {
+            progress.fail(format!("Could not obtain bundles: {err}"));
+        }
+    }
+
+    fn apply_inner(
+        &self,
+        explicit_uri: Option<&BStr>,
+        advertised: Option<gix_bundle::List>,
+        progress: &mut dyn crate::DynNestedProgress,
+    ) -> Result<(), Error> {
+        let config = &self.repo.config.resolved;
+        let configured_uri = config.string_filter(
+            Fetch::BUNDLE_URI.logical_name().as_str(),
+            &mut self.repo.filter_config_section(),
+        );
+        let min_creation_token = config
+            .integer_filter(
+                Fetch::BUNDLE_CREATION_TOKEN.logical_name().as_str(),
+                &mut self.repo.filter_config_section(),
+            )
+            .map(|token| Fetch::BUNDLE_CREATION_TOKEN.try_into_u64(token))
+            .transpose()
+            .with_leniency(self.repo.options.lenient_config)?;
+
+        let outcome = match (explicit_uri, configured_uri.as_deref(), advertised) {
+            (Some(uri), _, _) | (None, Some(uri), _) => self.apply_uri(uri, min_creation_token, 0, progress)?,
+            (None, None, Some(list)) => self.apply_list(list, "".into(), min_creation_token, 0, progress)?,
+            (None, None, None) => return Ok(()),
+        };
+
+        if outcome.heuristic != Some(Heuristic::CreationToken) {
+            return Ok(());
+        }
+        let explicit_uri = explicit_uri.filter(|uri| configured_uri.as_deref() != Some(*uri));
+        let new_token = outcome
+            .creation_token
+            .filter(|token| min_creation_token.map_or(true, |min| *token > min));
+        if explicit_uri.is_some() || new_token.is_some() {
+            self.write_config(explicit_uri, new_token.or(min_creation_token))?;
+        }
+        Ok(())
+    }
+
+    /// Apply the bundle or bundle list at `uri`, which is `depth` lists deep.
+    fn apply_uri(
+        &self,
+        uri: &BStr,
+        min_creation_token: Option<u64>,
+        depth: usize,
+        progress: &mut dyn crate::DynNestedProgress,
+    ) -> Result<Outcome, Error> {
+        match self.obtain(uri, progress)? {
+            Obtained::Bundle {
+                bundle,
+                tempfile: _tempfile,
+            } => {
+                self.unbundle(&bundle, uri, progress)?;
+                Ok(Outcome::default())
+            }
+            Obtained::List(mut list) => {
+                list.resolve_relative_uris(uri);
+                self.apply_list(list, uri, min_creation_token, depth + 1, progress)
+            }
+        }
+    }
+
+    /// Apply the bundles in `list`, which was obtained from `uri` at `depth`.
+    ///
+    /// With the creation token heuristic, only bundles with a token greater than `min_creation_token` are applied,
+    /// in order of their tokens.
+    fn apply_list(
+        &self,
+        mut list: gix_bundle::List,
+        uri: &BStr,
+        min_creation_token: Option<u64>,
+        depth: usize,
+        progress: &mut dyn crate::DynNestedProgress,
+    ) -> Result<Outcome, Error> {
+        if depth > MAX_LIST_DEPTH {
+            return Err(Error::TooDeep { uri: uri.into() });
+        }
+        list.bundles.retain(|entry| !entry.uri.is_empty());
+        if list.heuristic == Some(Heuristic::CreationToken) {
+            list.bundles.retain(|entry| {
+                min_creation_token.map_or(true, |min| entry.creation_token.map_or(false, |token| token > min))
+            });
+            list.bundles.sort_by_key(|entry| entry.creation_token);
+        }
+        let mut outcome = Outcome {
+            heuristic: list.heuristic,
+            creation_token: None,
+        };
+
+        if list.mode == Mode::Any {
+            for entry in &list.bundles {
+                match self.apply_uri(entry.uri.as_ref(), min_creation_token, depth, progress) {
+                    Ok(_) => {
+                        outcome.creation_token = outcome.creation_token.max(entry.creation_token);
+                        break;
+                    }
+                    Err(err) => progress.fail(format!("Skipping bundle {:?}: {err}", entry.id)),
+                }
+            }
+            return Ok(outcome);
+        }
+
+        let mut pending = Vec::new();
+        for entry in &list.bundles {
+            match self.obtain(entry.uri.as_ref(), progress) {
+                Ok(Obtained::Bundle { bundle, tempfile }) => pending.push((entry, bundle, tempfile)),
+                Ok(Obtained::List(mut nested)) => {
+                    nested.resolve_relative_uris(entry.uri.as_ref());
+                    match self.apply_list(nested, entry.uri.as_ref(), min_creation_token, depth + 1, progress) {
+                        Ok(_) => outcome.creation_token = outcome.creation_token.max(entry.creation_token),
+                        Err(err) => progress.fail(format!("Skipping bundle list {:?}: {err}", entry.id)),
+                    }
+                }
+                Err(err) => progress.fail(format!("Skipping bundle {:?}: {err}", entry.id)),
+            }
+        }
+        // Bundles may depend on each other, so apply those whose prerequisites are present until no progress can be made.
+        loop {
+            let num_pending = pending.len();
+            let mut idx = 0;
+            while idx < pending.len() {
+                let (_, bundle, _) = &pending[idx];
+                if bundle.verify_prerequisites(&self.repo.objects).is_err() {
+                    idx += 1;
+                    continue;
+                }
+                let (entry, bundle, _tempfile) = pending.remove(idx);
+                match self.unbundle(&bundle, entry.uri.as_ref(), progress) {
+                    Ok(()) => outcome.creation_token = outcome.creation_token.max(entry.creation_token),
+                    Err(err) => progress.fail(format!("Skipping bundle {:?}: {err}", entry.id)),
+                }
+            }
+            if pending.len() == num_pending {
+                break;
+            }
+        }
+        for (entry, bundle, _) in pending {
+            if let Err(err) = bundle.verify_prerequisites(&self.repo.objects) {
+                progress.fail(format!("Skipping bundle {:?}: {err}", entry.id));
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Obtain the bundle or bundle list at `uri`, downloading it if needed.
+    fn obtain(&self, uri: &BStr, progress: &mut dyn crate::DynNestedProgress) -> Result<Obtained, Error> {
+        let url = gix_url::parse(uri).map_err(|err| Error::Download {
+            uri: uri.into(),
+            source: err.into(),
+        })?;
+        let (path, tempfile) = match url.scheme {
+            gix_url::Scheme::File => (gix_path::from_bstring(url.path), None),
+            gix_url::Scheme::Http | gix_url::Scheme::Https => {
+                progress.step();
+                progress.set_name(format!("download {uri}"));
+                let mut tempfile = self.download(uri)?;
+                let path = tempfile
+                    .with_mut(|file| file.path().to_owned())
+                    .map_err(|err| Error::Download {
+                        uri: uri.into(),
+                        source: err.into(),
+                    })?;
+                (path, Some(tempfile))
+            }
+            _ => return Err(Error::UnsupportedScheme { uri: uri.into() }),
+        };
+        match gix_bundle::Bundle::at(path.clone()) {
+            Ok(bundle) => Ok(Obtained::Bundle { bundle, tempfile }),
+            Err(gix_bundle::open::Error::Header { .. }) => {
+                let data = std::fs::read(&path).map_err(|err| Error::Download {
+                    uri: uri.into(),
+                    source: err.into(),
+                })?;
+                let config = gix_config::File::from_bytes_no_includes(
+                    &data,
+                    gix_config::file::Metadata::api(),
+                    Default::default(),
+                )
+                .map_err(|source| Error::ParseList {
+                    uri: uri.into(),
+                    source,
+                })?;
+                let mut list = gix_bundle::List::default();
+                for section in config.sections_by_name("bundle").into_iter().flatten() {
+                    let mut prefix = BString::from("bundle.");
+                    if let Some(subsection) = section.header().subsection_name() {
+                        prefix.extend_from_slice(subsection);
+                        prefix.push(b'.');
+                    }
+                    for (name, value) in section.body().clone() {
+                        let mut key = prefix.clone();
+                        key.extend_from_slice(name.as_ref().as_bytes());
+                        list.update(key.as_ref(), value.as_ref())
+                            .map_err(|source| Error::List {
+                                uri: uri.into(),
+                                source,
+                            })?;
+                    }
+                }
+                Ok(Obtained::List(list))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    #[cfg(any(
+        feature = "blocking-http-transport-reqwest",
+        feature = "blocking-http-transport-curl"
+    ))]
+    fn download(&self, uri: &BStr) -> Result<gix_tempfile::Handle<gix_tempfile::handle::Writable>, Error> {
+        let download_error = |source: Box<dyn std::error::Error + Send + Sync + 'static>| Error::Download {
+            uri: uri.into(),
+            source,
+        };
+        let mut body = super::download::http_get(self.repo, self.remote_name, uri).map_err(download_error)?;
+        let mut file = gix_tempfile::new(
+            self.repo.git_dir(),
+            gix_tempfile::ContainingDirectory::Exists,
+            gix_tempfile::AutoRemove::Tempfile,
+        )
+        .map_err(|err| download_error(err.into()))?;
+        std::io::copy(&mut body, &mut file).map_err(|err| download_error(err.into()))?;
+        Ok(file)
+    }
+
+    #[cfg(not(any(
+        feature = "blocking-http-transport-reqwest",
+        feature = "blocking-http-transport-curl"
+    )))]
+    fn download(&self, uri: &BStr) -> Result<gix_tempfile::Handle<gix_tempfile::handle::Writable>, Error> {
+        Err(Error::UnsupportedScheme { uri: uri.into() })
+    }
+
+    /// Write the pack of `bundle` obtained from `uri` into the object database, and make its branches available
+    /// as `refs/bundles/<branch>` so that negotiation can tell the remote which objects we already have.
+    fn unbundle(
+        &self,
+        bundle: &gix_bundle::Bundle,
+        uri: &BStr,
+        progress: &mut dyn crate::DynNestedProgress,
+    ) -> Result<(), Error> {
+        use gix_ref::{
+            transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+            Target,
+        };
+
+        progress.step();
+        progress.set_name(format!("unbundle {uri}"));
+        let repo = self.repo;
+        let mut outcome = bundle
+            .unbundle(
+                Some(&repo.objects.store_ref().path().join("pack")),
+                repo.objects.clone(),
+                progress,
+                self.should_interrupt,
+                self.options.clone(),
+            )
+            .map_err(|source| Error::Unbundle {
+                uri: uri.into(),
+                source,
+            })?;
+
+        let edits = bundle.header.refs.iter().filter_map(|r| {
+            let branch = r.name.strip_prefix(b"refs/heads/")?;
+            let mut name = BString::from("refs/bundles/");
+            name.extend_from_slice(branch);
+            Some(RefEdit {
+                change: Change::Update {
+                    log: LogChange {
+                        mode: RefLog::AndReference,
+                        force_create_reflog: false,
+                        message: format!("bundle-uri: {uri}").into(),
+                    },
+                    expected: PreviousValue::Any,
+                    new: Target::Object(r.target),
+                },
+                name: name.try_into().ok()?,
+                deref: false,
+            })
+        });
+        repo.edit_references(edits).map_err(|source| Error::EditReferences {
+            uri: uri.into(),
+            source,
+        })?;
+        // Looking up the tips makes the object database pick up the new pack, as negotiation won't refresh it.
+        for r in &bundle.header.refs {
+            repo.has_object(r.target);
+        }
+        if let Some(path) = outcome.keep_path.take() {
+            std::fs::remove_file(&path).map_err(|source| Error::RemovePackKeepFile { path, source })?;
+        }
+        Ok(())
+    }
+
+    /// Persist `uri` as `fetch.bundleURI` if set, along with `creation_token` as `fetch.bundleCreationToken` in the
+    /// configuration file of the repository.
+    fn write_config(&self, uri: Option<&BStr>, creation_token: Option<u64>) -> Result<(), Error> {
+        let path = self.repo.common_dir().join("config");
+        let mut config = gix_config::File::from_path_no_includes(path.clone(), gix_config::Source::Local)?;
+        if let Some(uri) = uri {
+            config.set_raw_value(&Fetch::BUNDLE_URI, uri)?;
+        }
+        if let Some(token) = creation_token {
+            config.set_raw_value(&Fetch::BUNDLE_CREATION_TOKEN, token.to_string().as_str())?;
+        }
+        let mut file = std::fs::File::create(path)?;
+        config.write_to(&mut file)?;
+        Ok(())
+    }
+}
+
+/// Ask the remote at `remote_url` for the bundles it advertises, if `transfer.bundleURI` is enabled and the remote
+/// supports the `bundle-uri` command of protocol V2.
+///
+/// Failures are reported through `progress` as the remote can be used to obtain all objects instead.
+pub(super) fn advertised_list<T: Transport>(
+    repo: &Repository,
+    remote_url: &BStr,
+    transport: &mut T,
+    handshake: &gix_protocol::handshake::Outcome,
+    progress: &mut dyn crate::DynNestedProgress,
+    trace: bool,
+) -> Option<gix_bundle::List> {
+    let enabled = repo
+        .config
+        .resolved
+        .boolean_filter(
+            Transfer::BUNDLE_URI.logical_name().as_str(),
+            &mut repo.filter_config_section(),
+        )
+        .map(|value| Transfer::BUNDLE_URI.enrich_error(value))
+        .transpose()
+        .with_leniency(repo.options.lenient_config);
+    let enabled = match enabled {
+        Ok(enabled) => enabled.unwrap_or(false),
+        Err(err) => {
+            progress.fail(format!("Could not obtain bundles: {}", Error::from(err)));
+            return None;
+        }
+    };
+    if !enabled
+        || handshake.server_protocol_version != gix_protocol::transport::Protocol::V2
+        || !handshake.capabilities.contains("bundle-uri")
+    {
+        return None;
+    }
+    match gix_protocol::bundle_uri(
+        transport,
+        &handshake.capabilities,
+        Some(repo.config.user_agent_tuple()),
+        &mut progress.add_child("bundle-uri".into()),
+        trace,
+    ) {
+        Ok(pairs) => {
+            let mut list = gix_bundle::List::default();
+            for (key, value) in pairs {
+                if let Err(err) = list.update(key.as_ref(), value.as_ref()) {
+                    progress.fail(format!("The bundle list advertised by the remote is invalid: {err}"));
+                    return None;
+                }
+            }
+            list.resolve_relative_uris(remote_url);
+            Some(list)
+        }
+        Err(err) => {
+            progress.fail(format!("Could not list bundles advertised by the remote: {err}"));
+            None
+        }
+    }
+}
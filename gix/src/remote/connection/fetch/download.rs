@@ -0,0 +1,21 @@
+use crate::{
+    bstr::{BStr, ByteSlice},
+    Repository,
+};
+
+/// Perform an HTTP `GET` request for `url` with a transport configured like the one for `remote_name`,
+/// and return the body of the response.
+pub(super) fn http_get(
+    repo: &Repository,
+    remote_name: Option<&BStr>,
+    url: &BStr,
+) -> Result<impl std::io::BufRead, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    use gix_protocol::transport::client::http::{self, Http};
+
+    let url_str = url.to_str()?;
+    let mut client = http::Impl::default();
+    if let Some(config) = repo.transport_options(url, remote_name)? {
+        client.configure(&*config)?;
+    }
+    Ok(client.get(url_str, url_str, std::iter::empty::<&str>())?.body)
+}
@@ -0,0 +1,357 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    io::Read,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use gix_object::{Exists, FindExt};
+use gix_protocol::transport::client::{dumb::Files, Transport};
+
+use crate::{
+    bstr::{BString, ByteSlice},
+    remote,
+    remote::{
+        connection::fetch::config,
+        fetch,
+        fetch::{negotiate, refs, Error, Outcome, Prepare, RefLogMessage, Shallow, Status},
+        Connection,
+    },
+    Repository,
+};
+
+impl<'remote, 'repo, T> Prepare<'remote, 'repo, T>
+where
+    T: Transport,
+{
+    /// Obtain the objects the remote refs point to by walking the commit graph from them and downloading the missing
+    /// objects one by one, as the remote is a *dumb* server that can only serve files.
+    pub(super) fn receive_dumb(
+        &mut self,
+        mut con: Connection<'remote, 'repo, T>,
+        progress: &mut dyn crate::DynNestedProgress,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, Error> {
+        let repo = &super::receive_pack::without_promisor(con.remote.repo);
+        if self.shallow != Shallow::NoChange || repo.is_shallow() {
+            return Err(Error::MissingServerFeature {
+                feature: "shallow",
+                description: "dumb servers only serve files and can't cut the history at a shallow boundary",
+            });
+        }
+        if self.filter.is_some() {
+            return Err(Error::MissingServerFeature {
+                feature: "filter",
+                description: "dumb servers only serve files and can't omit objects as instructed by the filter",
+            });
+        }
+        if self.ref_map.object_hash != repo.object_hash() {
+            return Err(Error::IncompatibleObjectHash {
+                local: repo.object_hash(),
+                remote: self.ref_map.object_hash,
+            });
+        }
+
+        let mut packs = Vec::new();
+        let mut num_loose_objects = 0;
+        if matches!(self.dry_run, fetch::DryRun::No) {
+            let files = con
+                .transport
+                .dumb_files()
+                .expect("only called if the remote is a dumb server");
+            let options = gix_pack::bundle::write::Options {
+                thread_limit: config::index_threads(repo)?,
+                index_version: config::pack_index_version(repo)?,
+                iteration_mode: gix_pack::data::input::Mode::Verify,
+                object_hash: repo.object_hash(),
+                ..Default::default()
+            };
+            let is_implicit_tag = negotiate::make_refmapping_ignore_predicate(con.remote.fetch_tags, &self.ref_map);
+            let mut walk = Walk {
+                repo,
+                files,
+                remote_packs: None,
+                received: HashSet::new(),
+                seen: HashSet::new(),
+                progress,
+                should_interrupt,
+                options,
+                packs: &mut packs,
+                num_loose_objects: &mut num_loose_objects,
+            };
+            walk.run(
+                self.ref_map
+                    .mappings
+                    .iter()
+                    .filter(|m| !is_implicit_tag(m))
+                    .filter_map(|m| m.remote.as_id())
+                    .map(ToOwned::to_owned),
+            )?;
+            // Like `include-tag`, obtain the annotated tags pointing to objects we now have.
+            walk.run(
+                self.ref_map
+                    .mappings
+                    .iter()
+                    .filter(|m| is_implicit_tag(m))
+                    .filter_map(|m| match &m.remote {
+                        fetch::Source::Ref(gix_protocol::handshake::Ref::Peeled { tag, object, .. }) => {
+                            repo.objects.exists(object).then(|| tag.to_owned())
+                        }
+                        _ => None,
+                    }),
+            )?;
+        }
+
+        let update_refs = refs::update(
+            repo,
+            self.reflog_message
+                .take()
+                .unwrap_or_else(|| RefLogMessage::Prefixed { action: "fetch".into() }),
+            &self.ref_map.mappings,
+            con.remote.refspecs(remote::Direction::Fetch),
+            &self.ref_map.extra_refspecs,
+            con.remote.fetch_tags,
+            self.dry_run,
+            self.write_packed_refs,
+        )?;
+
+        for bundle in &mut packs {
+            if !update_refs.edits.is_empty() || bundle.index.num_objects == 0 {
+                if let Some(path) = bundle.keep_path.take() {
+                    std::fs::remove_file(&path).map_err(|err| Error::RemovePackKeepFile { path, source: err })?;
+                }
+            }
+        }
+
+        Ok(Outcome {
+            ref_map: std::mem::take(&mut self.ref_map),
+            status: if packs.is_empty() && num_loose_objects == 0 {
+                Status::NoPackReceived {
+                    dry_run: matches!(self.dry_run, fetch::DryRun::Yes),
+                    negotiate: None,
+                    update_refs,
+                }
+            } else {
+                Status::DumbTransfer {
+                    packs,
+                    num_loose_objects,
+                    update_refs,
+                }
+            },
+        })
+    }
+}
+
+/// A pack on the remote along with its downloaded index, to learn which objects it contains.
+struct RemotePack {
+    name: BString,
+    index: gix_pack::index::File,
+    _index_file: gix_tempfile::Handle<gix_tempfile::handle::Writable>,
+}
+
+/// The state for walking the object graph of the remote, starting at objects we don't have.
+struct Walk<'a> {
+    repo: &'a Repository,
+    files: &'a mut dyn Files,
+    /// The packs listed in `objects/info/packs` which we didn't download yet, obtained on first use.
+    remote_packs: Option<Vec<RemotePack>>,
+    /// The objects of the packs we downloaded, which need to be traversed even though they exist locally now.
+    received: HashSet<gix_hash::ObjectId>,
+    seen: HashSet<gix_hash::ObjectId>,
+    progress: &'a mut dyn crate::DynNestedProgress,
+    should_interrupt: &'a AtomicBool,
+    options: gix_pack::bundle::write::Options,
+    packs: &'a mut Vec<gix_pack::bundle::write::Outcome>,
+    num_loose_objects: &'a mut usize,
+}
+
+impl Walk<'_> {
+    /// Obtain all objects reachable from `tips` that we don't have, assuming that all objects we have locally are complete.
+    fn run(&mut self, tips: impl IntoIterator<Item = gix_hash::ObjectId>) -> Result<(), Error> {
+        let mut queue: VecDeque<_> = tips.into_iter().collect();
+        let mut buf = Vec::new();
+        while let Some(id) = queue.pop_front() {
+            if !self.seen.insert(id) {
+                continue;
+            }
+            if self.should_interrupt.load(Ordering::Relaxed) {
+                return Err(Error::Interrupted);
+            }
+            if !self.received.contains(&id) {
+                if self.repo.objects.exists(&id) {
+                    continue;
+                }
+                self.progress.step();
+                self.progress.set_name(format!("walk {id}"));
+                if self.download_loose_object(&id)? {
+                    *self.num_loose_objects += 1;
+                } else {
+                    self.download_pack_containing(&id)?;
+                }
+            }
+
+            let object = self.repo.objects.find(&id, &mut buf)?;
+            match object.kind {
+                gix_object::Kind::Commit => {
+                    let commit = object.decode()?.into_commit().expect("kind was checked");
+                    queue.push_back(commit.tree());
+                    queue.extend(commit.parents());
+                }
+                gix_object::Kind::Tree => {
+                    let tree = object.decode()?.into_tree().expect("kind was checked");
+                    // Submodule commits are not part of this repository.
+                    queue.extend(
+                        tree.entries
+                            .iter()
+                            .filter(|entry| !entry.mode.is_commit())
+                            .map(|entry| entry.oid.to_owned()),
+                    );
+                }
+                gix_object::Kind::Tag => {
+                    let tag = object.decode()?.into_tag().expect("kind was checked");
+                    queue.push_back(tag.target());
+                }
+                gix_object::Kind::Blob => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Download the loose object `id` and write it into our object database, or return `false` if it doesn't exist.
+    fn download_loose_object(&mut self, id: &gix_hash::oid) -> Result<bool, Error> {
+        let hex = id.to_hex().to_string();
+        let path = format!("objects/{}/{}", &hex[..2], &hex[2..]);
+        let mut compressed = Vec::new();
+        match self.files.get(&path)? {
+            Some(mut body) => body
+                .read_to_end(&mut compressed)
+                .map_err(|err| download_error(&path, err))?,
+            None => return Ok(false),
+        };
+
+        let (kind, data) = inflate_loose_object(&compressed).map_err(|err| download_error(&path, err))?;
+        let actual = gix_object::compute_hash(self.repo.object_hash(), kind, &data);
+        if actual != id {
+            return Err(Error::DumbObjectHashMismatch {
+                expected: id.to_owned(),
+                actual,
+            });
+        }
+        gix_odb::Write::write_buf(&self.repo.objects, kind, &data).map_err(|err| download_error(&path, err))?;
+        Ok(true)
+    }
+
+    /// Download the remote pack that contains `id` and write it into our object database.
+    fn download_pack_containing(&mut self, id: &gix_hash::oid) -> Result<(), Error> {
+        if self.remote_packs.is_none() {
+            self.remote_packs = Some(self.remote_packs()?);
+        }
+        let remote_packs = self.remote_packs.as_mut().expect("just set");
+        let pack = remote_packs
+            .iter()
+            .position(|pack| pack.index.lookup(id).is_some())
+            .map(|idx| remote_packs.remove(idx))
+            .ok_or_else(|| Error::DumbObjectMissing { id: id.to_owned() })?;
+
+        let path = format!("objects/pack/{}", pack.name);
+        self.progress.step();
+        self.progress.set_name(format!("receiving {}", pack.name));
+        let mut body = self
+            .files
+            .get(&path)?
+            .ok_or_else(|| download_error(&path, "the pack listed in 'objects/info/packs' doesn't exist"))?;
+        let bundle = gix_pack::Bundle::write_to_directory(
+            &mut body,
+            Some(&self.repo.objects.store_ref().path().join("pack")),
+            self.progress,
+            self.should_interrupt,
+            None::<gix_object::find::Never>,
+            self.options.clone(),
+        )?;
+        self.received.extend(pack.index.iter().map(|entry| entry.oid));
+        self.packs.push(bundle);
+        Ok(())
+    }
+
+    /// Download the index of each pack listed in `objects/info/packs` that we don't have yet.
+    fn remote_packs(&mut self) -> Result<Vec<RemotePack>, Error> {
+        let path = "objects/info/packs";
+        let mut packs = Vec::new();
+        if let Some(mut body) = self.files.get(path)? {
+            body.read_to_end(&mut packs).map_err(|err| download_error(path, err))?;
+        }
+        let pack_dir = self.repo.objects.store_ref().path().join("pack");
+        let mut out = Vec::new();
+        for name in gix_protocol::transport::client::dumb::parse_packs(&packs) {
+            let Some(stem) = name.strip_suffix(b".pack") else {
+                continue;
+            };
+            if pack_dir.join(gix_path::from_bstr(name)).is_file() {
+                continue;
+            }
+            let path = format!("objects/pack/{stem}.idx", stem = stem.as_bstr());
+            let Some(mut body) = self.files.get(&path)? else {
+                continue;
+            };
+            let mut index_file = gix_tempfile::new(
+                self.repo.git_dir(),
+                gix_tempfile::ContainingDirectory::Exists,
+                gix_tempfile::AutoRemove::Tempfile,
+            )
+            .map_err(|err| download_error(&path, err))?;
+            std::io::copy(&mut body, &mut index_file).map_err(|err| download_error(&path, err))?;
+            let index_path = index_file
+                .with_mut(|file| file.path().to_owned())
+                .map_err(|err| download_error(&path, err))?;
+            let index = gix_pack::index::File::at(index_path, self.repo.object_hash())
+                .map_err(|err| download_error(&path, err))?;
+            out.push(RemotePack {
+                name: name.to_owned(),
+                index,
+                _index_file: index_file,
+            });
+        }
+        Ok(out)
+    }
+}
+
+/// Decompress the loose object in `compressed` and return its kind and data.
+///
+/// As the size in its header is controlled by the remote, it's only trusted once the data was decompressed in full.
+fn inflate_loose_object(
+    compressed: &[u8],
+) -> Result<(gix_object::Kind, Vec<u8>), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    use gix_features::zlib::{stream::inflate::read, Decompress};
+    /// A generous upper bound for the compression ratio of typical objects, which limits how much we allocate upfront.
+    const MAX_EXPECTED_COMPRESSION_RATIO: usize = 16;
+
+    let mut input = compressed;
+    let mut state = Decompress::new(true);
+    let mut buf = [0; 8192];
+    let header_len = read(&mut input, &mut state, &mut buf[..64])?;
+    let (kind, size, header_size) = gix_object::decode::loose_header(&buf[..header_len])?;
+    let size = usize::try_from(size)?;
+
+    let mut data = Vec::with_capacity(size.min(compressed.len().saturating_mul(MAX_EXPECTED_COMPRESSION_RATIO)));
+    data.extend_from_slice(&buf[header_size..header_len]);
+    loop {
+        let len = read(&mut input, &mut state, &mut buf)?;
+        if len == 0 {
+            break;
+        }
+        if data.len() + len > size {
+            return Err(format!("object data exceeds the {size} bytes announced in its header").into());
+        }
+        data.extend_from_slice(&buf[..len]);
+    }
+    if data.len() != size {
+        return Err(format!("expected {size} decompressed bytes, got {}", data.len()).into());
+    }
+    Ok((kind, data))
+}
+
+fn download_error(path: &str, err: impl Into<Box<dyn std::error::Error + Send + Sync + 'static>>) -> Error {
+    Error::DumbDownload {
+        path: path.into(),
+        source: err.into(),
+    }
+}
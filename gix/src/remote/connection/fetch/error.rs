@@ -45,6 +45,46 @@ pub enum Error {
     RejectShallowRemote,
     #[error(transparent)]
     NegotiationAlgorithmConfig(#[from] config::key::GenericErrorWithValue),
+    #[error("Could not determine if the remote is a promisor remote")]
+    PromisorConfig(#[source] config::boolean::Error),
+    #[error(transparent)]
+    PartialCloneFilterConfig(config::key::GenericErrorWithValue),
+    #[error("Could not write the '.promisor' file at \"{}\"", path.display())]
+    WritePromisorFile {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Could not download the pack at {uri:?}")]
+    PackfileUriDownload {
+        uri: crate::bstr::BString,
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+    #[error("The pack downloaded from {uri:?} has hash {actual}, but the remote announced {expected}")]
+    PackfileUriChecksumMismatch {
+        uri: crate::bstr::BString,
+        expected: gix_hash::ObjectId,
+        actual: gix_hash::ObjectId,
+    },
+    #[error("The remote offloaded objects to {uri:?}, but there is no HTTP transport to download them with")]
+    PackfileUriUnsupported { uri: crate::bstr::BString },
+    #[error("The object {id} could neither be found as loose object nor in any of the packs of the dumb server")]
+    DumbObjectMissing { id: gix_hash::ObjectId },
+    #[error("Could not download {path:?} from the dumb server")]
+    DumbDownload {
+        path: String,
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+    #[error("The loose object downloaded from the dumb server hashes to {actual}, but {expected} was expected")]
+    DumbObjectHashMismatch {
+        expected: gix_hash::ObjectId,
+        actual: gix_hash::ObjectId,
+    },
+    #[error(transparent)]
+    FindExistingObject(#[from] gix_object::find::existing::Error),
+    #[error(transparent)]
+    DecodeObject(#[from] gix_object::decode::Error),
+    #[error("The operation was interrupted")]
+    Interrupted,
     #[error("Failed to read remaining bytes in stream")]
     ReadRemainingBytes(#[source] std::io::Error),
     #[error("None of the refspec(s) {} matched any of the {num_remote_refs} refs on the remote", refspecs.iter().map(|r| r.to_ref().instruction().to_bstring().to_string()).collect::<Vec<_>>().join(", "))]
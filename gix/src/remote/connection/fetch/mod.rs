@@ -62,6 +62,19 @@ pub enum Status {
         negotiate: outcome::Negotiate,
         /// Information collected while writing the pack and its index.
         write_pack_bundle: gix_pack::bundle::write::Outcome,
+        /// Information collected while writing the packs that the remote offloaded to be downloaded separately,
+        /// as configured with `fetch.uriProtocols`, in the order in which they were received.
+        packfile_uri_bundles: Vec<gix_pack::bundle::write::Outcome>,
+        /// Information collected while updating references.
+        update_refs: refs::update::Outcome,
+    },
+    /// The remote is a *dumb* server that only serves files, so instead of negotiating a pack, the objects that were
+    /// missing locally were obtained by walking the commit graph and downloading loose objects and packs as needed.
+    DumbTransfer {
+        /// Information collected while writing each of the packs that contained objects we were missing.
+        packs: Vec<gix_pack::bundle::write::Outcome>,
+        /// The amount of loose objects that were downloaded.
+        num_loose_objects: usize,
         /// Information collected while updating references.
         update_refs: refs::update::Outcome,
     },
@@ -139,12 +152,15 @@ pub(crate) mod negotiate;
 
 ///
 pub mod prepare {
-    /// The error returned by [`prepare_fetch()`][super::Connection::prepare_fetch()].
+    /// The error returned by [`prepare_fetch()`][super::Connection::prepare_fetch()] and
+    /// [`prepare_fetch_objects()`][super::Connection::prepare_fetch_objects()].
     #[derive(Debug, thiserror::Error)]
     #[allow(missing_docs)]
     pub enum Error {
         #[error("Cannot perform a meaningful fetch operation without any configured ref-specs")]
         MissingRefSpecs,
+        #[error("Cannot fetch objects without any object ids to fetch")]
+        MissingObjectIds,
         #[error(transparent)]
         RefMap(#[from] crate::remote::ref_map::Error),
     }
@@ -193,6 +209,78 @@ where
             reflog_message: None,
             write_packed_refs: WritePackedRefs::Never,
             shallow: Default::default(),
+            filter: None,
+            #[cfg(feature = "blocking-network-client")]
+            bundle_uri: None,
+            #[cfg(feature = "blocking-network-client")]
+            use_advertised_bundles: false,
+            objects_only: false,
+        })
+    }
+
+    /// Perform a handshake with the remote and prepare to fetch exactly the objects with the given `ids`, without listing
+    /// the remote refs and without telling the remote which objects we have.
+    ///
+    /// This is useful to lazily obtain objects that were omitted from a *partial clone*, and requires the remote to allow
+    /// any object to be wanted, for instance via `uploadpack.allowAnySHA1InWant`.
+    /// As no reference points to the received objects, neither refs nor `.keep` files are written,
+    /// and the [`ref_map`][Prepare::ref_map()] maps each id using an implicit refspec.
+    ///
+    /// Note that the [limitations of `prepare_fetch()`][Self::prepare_fetch()] apply here as well.
+    #[allow(clippy::result_large_err)]
+    #[gix_protocol::maybe_async::maybe_async]
+    pub async fn prepare_fetch_objects(
+        mut self,
+        mut progress: impl Progress,
+        ids: impl IntoIterator<Item = gix_hash::ObjectId>,
+    ) -> Result<Prepare<'remote, 'repo, T>, prepare::Error> {
+        let _span = gix_trace::coarse!("remote::Connection::prepare_fetch_objects()");
+        let ids: Vec<_> = ids.into_iter().collect();
+        if ids.is_empty() {
+            return Err(prepare::Error::MissingObjectIds);
+        }
+        let mut handshake = self.handshake_inner(Vec::new(), &mut progress).await?;
+        let object_hash = ref_map::extract_object_format(self.remote.repo, &handshake)?;
+        let extra_refspecs = ids
+            .iter()
+            .map(|id| {
+                gix_refspec::parse(
+                    id.to_hex().to_string().as_str().into(),
+                    gix_refspec::parse::Operation::Fetch,
+                )
+                .expect("object ids are valid refspecs")
+                .to_owned()
+            })
+            .collect();
+        let mappings = ids
+            .into_iter()
+            .enumerate()
+            .map(|(idx, id)| remote::fetch::Mapping {
+                remote: remote::fetch::Source::ObjectId(id),
+                local: None,
+                spec_index: remote::fetch::SpecIndex::Implicit(idx),
+            })
+            .collect();
+        Ok(Prepare {
+            ref_map: RefMap {
+                mappings,
+                extra_refspecs,
+                fixes: Vec::new(),
+                remote_refs: handshake.refs.take().unwrap_or_default(),
+                handshake,
+                object_hash,
+            },
+            con: Some(self),
+            dry_run: DryRun::No,
+            reflog_message: None,
+            write_packed_refs: WritePackedRefs::Never,
+            shallow: Default::default(),
+            filter: None,
+            #[cfg(feature = "blocking-network-client")]
+            bundle_uri: None,
+            #[cfg(feature = "blocking-network-client")]
+            use_advertised_bundles: false,
+            objects_only: true,
         })
     }
 }
@@ -207,7 +295,19 @@ where
     }
 }
 
+#[cfg(feature = "blocking-network-client")]
+mod bundle_uri;
 mod config;
+#[cfg(any(
+    feature = "blocking-http-transport-reqwest",
+    feature = "blocking-http-transport-curl"
+))]
+mod download;
+#[cfg(feature = "blocking-network-client")]
+mod dumb;
+mod packfile_uris;
+#[cfg(feature = "blocking-network-client")]
+pub(crate) mod promisor;
 mod receive_pack;
 ///
 #[path = "update_refs/mod.rs"]
@@ -224,6 +324,13 @@ where
     reflog_message: Option<RefLogMessage>,
     write_packed_refs: WritePackedRefs,
     shallow: remote::fetch::Shallow,
+    filter: Option<remote::fetch::Filter>,
+    #[cfg(feature = "blocking-network-client")]
+    bundle_uri: Option<crate::bstr::BString>,
+    #[cfg(feature = "blocking-network-client")]
+    use_advertised_bundles: bool,
+    /// If `true`, only the objects in the ref-map are fetched, without negotiation and without any ref pointing to them.
+    objects_only: bool,
 }
 
 /// Builder
@@ -265,6 +372,30 @@ where
         self.shallow = shallow;
         self
     }
+
+    /// Ask the remote to omit objects according to `filter`, which makes the pack we receive part of a *partial clone*.
+    ///
+    /// If unset, the `remote.<name>.partialCloneFilter` is used if the remote is a *promisor remote*.
+    pub fn with_filter(mut self, filter: remote::fetch::Filter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Obtain objects from the bundle or bundle list at `uri` before negotiating with the remote, instead of
+    /// from `fetch.bundleURI`.
+    #[cfg(feature = "blocking-network-client")]
+    pub(crate) fn with_bundle_uri(mut self, uri: Option<crate::bstr::BString>) -> Self {
+        self.bundle_uri = uri;
+        self
+    }
+
+    /// If `enabled`, ask the remote for the bundles it advertises if neither an explicit bundle URI nor `fetch.bundleURI`
+    /// are set and `transfer.bundleURI` is `true`, which is what `git` does when cloning.
+    #[cfg(feature = "blocking-network-client")]
+    pub(crate) fn with_advertised_bundles(mut self, enabled: bool) -> Self {
+        self.use_advertised_bundles = enabled;
+        self
+    }
 }
 
 impl<'remote, 'repo, T> Drop for Prepare<'remote, 'repo, T>
@@ -326,6 +326,18 @@ fn mark_alternate_complete(
     Ok(())
 }
 
+/// Return `true` if the server indicated in `response` that it has enough information to send a pack, which is when we should
+/// send `done` without any more haves. `is_single_ack` is `true` if neither `multi_ack` nor `multi_ack_detailed` are in use,
+/// in which case the server won't respond to any more haves after acknowledging the first common commit.
+pub(crate) fn server_is_ready(response: &gix_protocol::fetch::Response, is_single_ack: bool) -> bool {
+    use gix_protocol::fetch::response::Acknowledgement;
+    response.acknowledgements().iter().any(|ack| match ack {
+        Acknowledgement::Ready => true,
+        Acknowledgement::Common(_) => is_single_ack,
+        Acknowledgement::Nak => false,
+    })
+}
+
 /// Negotiate the nth `round` with `negotiator` sending `haves_to_send` after possibly making the known common commits
 /// as sent by the remote known to `negotiator` using `previous_response` if this isn't the first round.
 /// All `haves` are added to `arguments` accordingly.
@@ -0,0 +1,112 @@
+use std::sync::atomic::AtomicBool;
+
+use gix_protocol::fetch::response::PackfileUri;
+
+use crate::{
+    bstr::{BStr, ByteSlice},
+    config::tree::{Fetch, Key},
+    remote::fetch::Error,
+    Repository,
+};
+
+/// The protocols we can download offloaded packs with, which is only possible if there is an HTTP transport.
+const SUPPORTED_PROTOCOLS: &[&str] = if cfg!(any(
+    feature = "blocking-http-transport-reqwest",
+    feature = "blocking-http-transport-curl"
+)) {
+    &["http", "https"]
+} else {
+    &[]
+};
+
+/// Return the protocols configured in `fetch.uriProtocols` that we can download packs with, in the order they were configured.
+///
+/// Like in `git`, nothing is returned if the key isn't set, so servers won't offload any part of the pack.
+pub(super) fn accepted_protocols(repo: &Repository) -> Vec<String> {
+    repo.config
+        .resolved
+        .string_filter(
+            Fetch::URI_PROTOCOLS.logical_name().as_str(),
+            &mut repo.filter_config_section(),
+        )
+        .map(|protocols| {
+            protocols
+                .to_str_lossy()
+                .split(',')
+                .map(str::trim)
+                .filter(|protocol| SUPPORTED_PROTOCOLS.contains(protocol))
+                .map(ToOwned::to_owned)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Download the pack that `uri` points to with an HTTP transport configured like the one for `remote_name`, and write it
+/// along with its index into the object database of `repo`.
+///
+/// The pack is removed again if its hash doesn't match the one announced by the remote.
+#[cfg_attr(
+    not(any(
+        feature = "blocking-http-transport-reqwest",
+        feature = "blocking-http-transport-curl"
+    )),
+    allow(unused_variables)
+)]
+pub(super) fn download(
+    repo: &Repository,
+    remote_name: Option<&BStr>,
+    uri: &PackfileUri,
+    progress: &mut dyn crate::DynNestedProgress,
+    should_interrupt: &AtomicBool,
+    options: gix_pack::bundle::write::Options,
+) -> Result<gix_pack::bundle::write::Outcome, Error> {
+    #[cfg(not(any(
+        feature = "blocking-http-transport-reqwest",
+        feature = "blocking-http-transport-curl"
+    )))]
+    {
+        Err(Error::PackfileUriUnsupported { uri: uri.uri.clone() })
+    }
+    #[cfg(any(
+        feature = "blocking-http-transport-reqwest",
+        feature = "blocking-http-transport-curl"
+    ))]
+    {
+        let mut body = super::download::http_get(repo, remote_name, uri.uri.as_bstr()).map_err(|source| {
+            Error::PackfileUriDownload {
+                uri: uri.uri.clone(),
+                source,
+            }
+        })?;
+        let bundle = gix_pack::Bundle::write_to_directory(
+            &mut body,
+            Some(&repo.objects.store_ref().path().join("pack")),
+            progress,
+            should_interrupt,
+            Some(Box::new({
+                let repo = repo.clone();
+                repo.objects
+            })),
+            options,
+        )
+        .map_err(|err| Error::PackfileUriDownload {
+            uri: uri.uri.clone(),
+            source: err.into(),
+        })?;
+
+        if bundle.index.data_hash != uri.hash {
+            for path in [&bundle.data_path, &bundle.index_path, &bundle.keep_path]
+                .into_iter()
+                .flatten()
+            {
+                std::fs::remove_file(path).ok();
+            }
+            return Err(Error::PackfileUriChecksumMismatch {
+                uri: uri.uri.clone(),
+                expected: uri.hash,
+                actual: bundle.index.data_hash,
+            });
+        }
+        Ok(bundle)
+    }
+}
@@ -0,0 +1,50 @@
+use std::{path::PathBuf, sync::atomic::AtomicBool};
+
+use crate::{
+    bstr::{BString, ByteSlice},
+    config::tree::{Extensions, Key},
+    remote, Repository,
+};
+
+/// Fetch objects that are missing in a *partial clone* from its promisor remote, as configured by `extensions.partialClone`.
+///
+/// It opens the repository anew for each fetch to stay independent of the thread-local repository it's used by.
+pub(crate) struct Promisor {
+    git_dir: PathBuf,
+    options: crate::open::Options,
+    remote_name: BString,
+}
+
+impl Promisor {
+    /// Return a promisor for `repo` if it's a partial clone, or `None` if all objects are expected to be present locally.
+    pub(crate) fn from_repo(repo: &Repository) -> Option<Self> {
+        let remote_name = repo
+            .config
+            .resolved
+            .string_filter(
+                Extensions::PARTIAL_CLONE.logical_name().as_str(),
+                &mut repo.filter_config_section(),
+            )?
+            .into_owned();
+        Some(Promisor {
+            git_dir: repo.git_dir().to_owned(),
+            options: repo.options.clone().open_path_as_is(true),
+            remote_name,
+        })
+    }
+}
+
+impl gix_odb::Promisor for Promisor {
+    fn fetch(&self, ids: &[gix_hash::ObjectId]) -> Result<(), gix_odb::promisor::Error> {
+        // Objects that are missing while fetching aren't fetched themselves as fetches don't use the promisor.
+        let repo = crate::ThreadSafeRepository::open_opts(&self.git_dir, self.options.clone())?.to_thread_local();
+        repo.find_remote(self.remote_name.as_bstr())?
+            .with_fetch_tags(remote::fetch::Tags::None)
+            .connect(remote::Direction::Fetch)?
+            .prepare_fetch_objects(gix_features::progress::Discard, ids.iter().copied())?
+            // Like `git`, avoid receiving all blobs of the trees we want.
+            .with_filter(remote::fetch::Filter::BlobNone)
+            .receive(gix_features::progress::Discard, &AtomicBool::default())?;
+        Ok(())
+    }
+}
@@ -3,20 +3,23 @@ use std::{
     sync::atomic::{AtomicBool, Ordering},
 };
 
+use gix_object::Exists;
 use gix_odb::store::RefreshMode;
 use gix_protocol::{
     fetch::Arguments,
     transport::{client::Transport, packetline::read::ProgressAction},
 };
 
+#[cfg(feature = "blocking-network-client")]
+use crate::remote::connection::fetch::bundle_uri;
 use crate::{
     config::{
         cache::util::ApplyLeniency,
-        tree::{Clone, Fetch, Key},
+        tree::{Clone, Extensions, Fetch, Key, Remote},
     },
     remote,
     remote::{
-        connection::fetch::config,
+        connection::fetch::{config, packfile_uris},
         fetch,
         fetch::{
             negotiate, negotiate::Algorithm, outcome, refs, Error, Outcome, Prepare, ProgressId, RefLogMessage,
@@ -63,6 +66,20 @@ where
     /// force only applies to refs pointing to commits and if they don't, they will be updated either way in our
     /// implementation as well.
     ///
+    /// ### Shallow Repositories
+    ///
+    /// When writing the updated shallow boundary, commits that don't exist in the object database are pruned, just like `git` does.
+    ///
+    /// If the repository is shallow and [tags are included](fetch::Tags::Included), another request is made to receive
+    /// all advertised annotated tags that point to commits we have, but that weren't sent by the remote as they are not part of the pack.
+    /// This isn't possible on stateful connections using protocol V1 once a pack was received, as the remote closes them.
+    ///
+    /// ### Dumb Servers
+    ///
+    /// If the remote only serves files, the objects we lack are obtained by walking the commit graph and downloading
+    /// loose objects and packs as needed, which is reported as [`Status::DumbTransfer`].
+    /// Shallow and partial fetches aren't possible in this case.
+    ///
     /// ### Async Mode Shortcoming
     ///
     /// Currently the entire process of resolving a pack is blocking the executor. This can be fixed using the `blocking` crate, but it
@@ -100,20 +117,35 @@ where
             });
         }
 
+        #[cfg(feature = "blocking-network-client")]
+        if con.transport.dumb_files().is_some() {
+            return self.receive_dumb(con, progress, should_interrupt);
+        }
+
         let handshake = &self.ref_map.handshake;
         let protocol_version = handshake.server_protocol_version;
 
         let fetch = gix_protocol::Command::Fetch;
-        let repo = con.remote.repo;
+        let repo = &without_promisor(con.remote.repo);
         let fetch_features = {
             let mut f = fetch.default_features(protocol_version, &handshake.capabilities);
+            if con.transport.connection_persists_across_multiple_requests() {
+                // Like `git`, only use `no-done` with stateless connections. Otherwise, we couldn't know if a pack follows
+                // the end of a response, and we send `done` once the server is ready instead.
+                f.retain(|(name, _)| *name != "no-done");
+            }
             f.push(repo.config.user_agent_tuple());
             f
         };
+        // Without `multi_ack*`, the server only acknowledges the first common commit and then waits for `done`.
+        let is_single_ack = protocol_version != gix_protocol::transport::Protocol::V2
+            && !fetch_features
+                .iter()
+                .any(|(name, _)| *name == "multi_ack" || *name == "multi_ack_detailed");
 
         gix_protocol::fetch::Response::check_required_features(protocol_version, &fetch_features)?;
         let sideband_all = fetch_features.iter().any(|(n, _)| *n == "sideband-all");
-        let mut arguments = gix_protocol::fetch::Arguments::new(protocol_version, fetch_features, con.trace);
+        let mut arguments = gix_protocol::fetch::Arguments::new(protocol_version, fetch_features.clone(), con.trace);
         if matches!(con.remote.fetch_tags, fetch::Tags::Included) {
             if !arguments.can_use_include_tag() {
                 return Err(Error::MissingServerFeature {
@@ -125,7 +157,23 @@ where
             }
             arguments.use_include_tag();
         }
+        let (filter, is_promisor) = filter_and_promisor(con.remote, self.filter)?;
+        let mut filter_was_sent = false;
+        if let Some(filter) = filter {
+            if arguments.can_use_filter() {
+                arguments.filter(filter.to_string().as_str());
+                filter_was_sent = true;
+            } else if self.filter.is_some() {
+                return Err(Error::MissingServerFeature {
+                    feature: "filter",
+                    description: "partial clones need the server to omit objects as instructed by the filter",
+                });
+            }
+        }
         let (shallow_commits, mut shallow_lock) = add_shallow_args(&mut arguments, &self.shallow, repo)?;
+        if arguments.can_use_packfile_uris() {
+            arguments.packfile_uris(packfile_uris::accepted_protocols(repo).iter().map(String::as_str));
+        }
 
         if self.ref_map.object_hash != repo.object_hash() {
             return Err(Error::IncompatibleObjectHash {
@@ -134,19 +182,59 @@ where
             });
         }
 
+        let options = gix_pack::bundle::write::Options {
+            thread_limit: config::index_threads(repo)?,
+            index_version: config::pack_index_version(repo)?,
+            iteration_mode: gix_pack::data::input::Mode::Verify,
+            object_hash: con.remote.repo.object_hash(),
+            ..Default::default()
+        };
+
+        #[cfg(feature = "blocking-network-client")]
+        if matches!(self.dry_run, fetch::DryRun::No) && !self.objects_only {
+            let advertised = if self.use_advertised_bundles {
+                con.remote
+                    .url(remote::Direction::Fetch)
+                    .map(gix_url::Url::to_bstring)
+                    .and_then(|url| {
+                        bundle_uri::advertised_list(
+                            repo,
+                            url.as_ref(),
+                            &mut con.transport,
+                            handshake,
+                            progress,
+                            con.trace,
+                        )
+                    })
+            } else {
+                None
+            };
+            bundle_uri::Bundles {
+                repo,
+                remote_name: con.remote.name().map(remote::Name::as_bstr),
+                should_interrupt,
+                options: options.clone(),
+            }
+            .apply(self.bundle_uri.as_ref().map(AsRef::as_ref), advertised, progress);
+        }
+
         let negotiate_span = gix_trace::detail!(
             "negotiate",
             protocol_version = self.ref_map.handshake.server_protocol_version as usize
         );
-        let mut negotiator = repo
-            .config
-            .resolved
-            .string(Fetch::NEGOTIATION_ALGORITHM.logical_name().as_str())
-            .map(|n| Fetch::NEGOTIATION_ALGORITHM.try_into_negotiation_algorithm(n))
-            .transpose()
-            .with_leniency(repo.config.lenient_config)?
-            .unwrap_or(Algorithm::Consecutive)
-            .into_negotiator();
+        let mut negotiator = if self.objects_only {
+            // The objects we have say nothing about which of the wanted objects are missing, so don't send any.
+            Algorithm::Noop
+        } else {
+            repo.config
+                .resolved
+                .string(Fetch::NEGOTIATION_ALGORITHM.logical_name().as_str())
+                .map(|n| Fetch::NEGOTIATION_ALGORITHM.try_into_negotiation_algorithm(n))
+                .transpose()
+                .with_leniency(repo.config.lenient_config)?
+                .unwrap_or(Algorithm::Consecutive)
+        }
+        .into_negotiator();
         let graph_repo = {
             let mut r = repo.clone();
             // assure that checking for unknown server refs doesn't trigger ODB refreshes.
@@ -166,13 +254,8 @@ where
             negotiate::make_refmapping_ignore_predicate(con.remote.fetch_tags, &self.ref_map),
         )?;
         let mut previous_response = None::<gix_protocol::fetch::Response>;
-        let (mut write_pack_bundle, negotiate) = match &action {
-            negotiate::Action::NoChange | negotiate::Action::SkipToRefUpdate => {
-                gix_protocol::indicate_end_of_interaction(&mut con.transport, con.trace)
-                    .await
-                    .ok();
-                (None, None)
-            }
+        let (mut write_pack_bundle, mut packfile_uri_bundles, negotiate) = match &action {
+            negotiate::Action::NoChange | negotiate::Action::SkipToRefUpdate => (None, Vec::new(), None),
             negotiate::Action::MustNegotiate {
                 remote_ref_target_known,
             } => {
@@ -191,11 +274,17 @@ where
                 let mut seen_ack = false;
                 let mut in_vain = 0;
                 let mut common = is_stateless.then(Vec::new);
-                let mut reader = 'negotiation: loop {
+                let reader = 'negotiation: loop {
                     let _round = gix_trace::detail!("negotiate round", round = rounds.len() + 1);
                     progress.step();
                     progress.set_name(format!("negotiate (round {})", rounds.len() + 1));
 
+                    let server_is_ready = previous_response
+                        .as_ref()
+                        .map_or(false, |response| negotiate::server_is_ready(response, is_single_ack));
+                    if server_is_ready {
+                        haves_to_send = 0;
+                    }
                     let is_done = match negotiate::one_round(
                         negotiator.deref_mut(),
                         &mut graph,
@@ -216,7 +305,8 @@ where
                                 haves_to_send,
                                 previous_response_had_at_least_one_in_common: ack_seen,
                             });
-                            let is_done = haves_sent != haves_to_send || (seen_ack && in_vain >= 256);
+                            let is_done =
+                                server_is_ready || haves_sent != haves_to_send || (seen_ack && in_vain >= 256);
                             haves_to_send = gix_negotiate::window_size(is_stateless, Some(haves_to_send));
                             is_done
                         }
@@ -235,7 +325,7 @@ where
                         protocol_version,
                         &mut reader,
                         is_done,
-                        !is_done,
+                        !is_done && !is_stateless,
                     )
                     .await?;
                     let has_pack = response.has_pack();
@@ -268,67 +358,93 @@ where
                     shallow_lock = acquire_shallow_lock(repo).map(Some)?;
                 }
 
-                let options = gix_pack::bundle::write::Options {
-                    thread_limit: config::index_threads(repo)?,
-                    index_version: config::pack_index_version(repo)?,
-                    iteration_mode: gix_pack::data::input::Mode::Verify,
-                    object_hash: con.remote.repo.object_hash(),
-                };
-
                 let write_pack_bundle = if matches!(self.dry_run, fetch::DryRun::No) {
-                    #[cfg(not(feature = "async-network-client"))]
-                    let mut rd = reader;
-                    #[cfg(feature = "async-network-client")]
-                    let mut rd = gix_protocol::futures_lite::io::BlockOn::new(reader);
-                    let res = gix_pack::Bundle::write_to_directory(
-                        &mut rd,
-                        Some(&repo.objects.store_ref().path().join("pack")),
-                        progress,
-                        should_interrupt,
-                        Some(Box::new({
-                            let repo = repo.clone();
-                            repo.objects
-                        })),
-                        options,
-                    )?;
-                    // Assure the final flush packet is consumed.
-                    #[cfg(feature = "async-network-client")]
-                    let has_read_to_end = { rd.get_ref().stopped_at().is_some() };
-                    #[cfg(not(feature = "async-network-client"))]
-                    let has_read_to_end = { rd.stopped_at().is_some() };
-                    if !has_read_to_end {
-                        std::io::copy(&mut rd, &mut std::io::sink()).map_err(Error::ReadRemainingBytes)?;
-                    }
-                    #[cfg(feature = "async-network-client")]
-                    {
-                        reader = rd.into_inner();
-                    }
-
-                    #[cfg(not(feature = "async-network-client"))]
-                    {
-                        reader = rd;
+                    let res = write_pack(reader, repo, progress, should_interrupt, options.clone())?;
+                    if is_promisor || filter_was_sent {
+                        if let Some(data_path) = res.data_path.as_deref() {
+                            write_promisor_file(data_path.with_extension("promisor"), &self.ref_map)?;
+                        }
                     }
                     Some(res)
                 } else {
+                    drop(reader);
                     None
                 };
-                drop(reader);
 
-                if matches!(protocol_version, gix_protocol::transport::Protocol::V2) {
-                    gix_protocol::indicate_end_of_interaction(&mut con.transport, con.trace)
-                        .await
-                        .ok();
+                // The objects the remote offloaded are only complete once all of their packs are downloaded.
+                let mut packfile_uri_bundles = Vec::new();
+                if write_pack_bundle.is_some() {
+                    for uri in previous_response.packfile_uris() {
+                        progress.step();
+                        progress.set_name(format!("receiving pack from {}", uri.uri));
+                        let bundle = packfile_uris::download(
+                            repo,
+                            con.remote.name().map(remote::Name::as_bstr),
+                            uri,
+                            progress,
+                            should_interrupt,
+                            options.clone(),
+                        )?;
+                        if is_promisor || filter_was_sent {
+                            if let Some(data_path) = bundle.data_path.as_deref() {
+                                write_promisor_file(data_path.with_extension("promisor"), &self.ref_map)?;
+                            }
+                        }
+                        packfile_uri_bundles.push(bundle);
+                    }
                 }
 
                 if let Some(shallow_lock) = shallow_lock {
-                    if !previous_response.shallow_updates().is_empty() {
-                        crate::shallow::write(shallow_lock, shallow_commits, previous_response.shallow_updates())?;
+                    // In dry-run mode, the new boundary commits don't exist and would be pruned.
+                    if !previous_response.shallow_updates().is_empty() && matches!(self.dry_run, fetch::DryRun::No) {
+                        crate::shallow::write(
+                            shallow_lock,
+                            shallow_commits,
+                            previous_response.shallow_updates(),
+                            &repo.objects,
+                        )?;
+                        repo.force_refresh_shallow_commits()?;
                     }
                 }
-                (write_pack_bundle, Some(outcome::Negotiate { graph, rounds }))
+                (
+                    write_pack_bundle,
+                    packfile_uri_bundles,
+                    Some(outcome::Negotiate { graph, rounds }),
+                )
             }
         };
 
+        // After receiving a pack, stateful V1 connections are closed by the remote, so no further request can be made.
+        let can_send_request = matches!(protocol_version, gix_protocol::transport::Protocol::V2)
+            || !matches!(action, negotiate::Action::MustNegotiate { .. })
+            || !con.transport.connection_persists_across_multiple_requests();
+        let backfilled_tags_bundle = if can_send_request
+            && matches!(self.dry_run, fetch::DryRun::No)
+            && !self.objects_only
+            && matches!(con.remote.fetch_tags, fetch::Tags::Included)
+            && repo.is_shallow()
+        {
+            backfill_tags(
+                repo,
+                &mut con.transport,
+                &self.ref_map,
+                con.remote.fetch_tags,
+                gix_protocol::fetch::Arguments::new(protocol_version, fetch_features, con.trace),
+                sideband_all,
+                progress,
+                should_interrupt,
+                options,
+            )
+            .await?
+        } else {
+            None
+        };
+        if can_send_request {
+            gix_protocol::indicate_end_of_interaction(&mut con.transport, con.trace)
+                .await
+                .ok();
+        }
+
         let update_refs = refs::update(
             repo,
             self.reflog_message
@@ -342,19 +458,24 @@ where
             self.write_packed_refs,
         )?;
 
-        if let Some(bundle) = write_pack_bundle.as_mut() {
-            if !update_refs.edits.is_empty() || bundle.index.num_objects == 0 {
+        for bundle in write_pack_bundle.iter_mut().chain(packfile_uri_bundles.iter_mut()) {
+            if !update_refs.edits.is_empty() || bundle.index.num_objects == 0 || self.objects_only {
                 if let Some(path) = bundle.keep_path.take() {
                     std::fs::remove_file(&path).map_err(|err| Error::RemovePackKeepFile { path, source: err })?;
                 }
             }
         }
 
+        if let Some(path) = backfilled_tags_bundle.and_then(|bundle| bundle.keep_path) {
+            std::fs::remove_file(&path).map_err(|err| Error::RemovePackKeepFile { path, source: err })?;
+        }
+
         let out = Outcome {
             ref_map: std::mem::take(&mut self.ref_map),
             status: match write_pack_bundle {
                 Some(write_pack_bundle) => Status::Change {
                     write_pack_bundle,
+                    packfile_uri_bundles,
                     update_refs,
                     negotiate: negotiate.expect("if we have a pack, we always negotiated it"),
                 },
@@ -369,6 +490,111 @@ where
     }
 }
 
+/// Return a clone of `repo` that doesn't fetch missing objects from its promisor remote, just like `git` doesn't while fetching.
+///
+/// Otherwise, looking up objects during negotiation would trigger a fetch for each of the many objects we don't have.
+pub(super) fn without_promisor(repo: &Repository) -> Repository {
+    let mut repo = repo.clone();
+    repo.objects.promisor = None;
+    repo
+}
+
+/// Write the pack sent by the remote via `reader` into the object database of `repo`.
+fn write_pack<'a>(
+    reader: Box<dyn gix_protocol::transport::client::ExtendedBufRead<'a> + Unpin + 'a>,
+    repo: &Repository,
+    progress: &mut dyn crate::DynNestedProgress,
+    should_interrupt: &AtomicBool,
+    options: gix_pack::bundle::write::Options,
+) -> Result<gix_pack::bundle::write::Outcome, Error> {
+    #[cfg(not(feature = "async-network-client"))]
+    let mut rd = reader;
+    #[cfg(feature = "async-network-client")]
+    let mut rd = gix_protocol::futures_lite::io::BlockOn::new(reader);
+    let res = gix_pack::Bundle::write_to_directory(
+        &mut rd,
+        Some(&repo.objects.store_ref().path().join("pack")),
+        progress,
+        should_interrupt,
+        Some(Box::new({
+            let repo = repo.clone();
+            repo.objects
+        })),
+        options,
+    )?;
+    // Assure the final flush packet is consumed.
+    #[cfg(feature = "async-network-client")]
+    let has_read_to_end = { rd.get_ref().stopped_at().is_some() };
+    #[cfg(not(feature = "async-network-client"))]
+    let has_read_to_end = { rd.stopped_at().is_some() };
+    if !has_read_to_end {
+        std::io::copy(&mut rd, &mut std::io::sink()).map_err(Error::ReadRemainingBytes)?;
+    }
+    Ok(res)
+}
+
+/// Fetch the annotated tags advertised by the remote whose peeled objects we have, but which we don't have ourselves.
+///
+/// In shallow repositories, `include-tag` isn't enough as the remote only sends tags that point to objects in the pack,
+/// which misses tags pointing to commits at or behind a shallow boundary that we received previously.
+/// This is what Git does as well, using another request that only `want`s these tags.
+#[gix_protocol::maybe_async::maybe_async]
+#[allow(clippy::too_many_arguments)]
+async fn backfill_tags<T: Transport>(
+    repo: &Repository,
+    transport: &mut T,
+    ref_map: &fetch::RefMap,
+    fetch_tags: fetch::Tags,
+    mut arguments: Arguments,
+    sideband_all: bool,
+    progress: &mut dyn crate::DynNestedProgress,
+    should_interrupt: &AtomicBool,
+    options: gix_pack::bundle::write::Options,
+) -> Result<Option<gix_pack::bundle::write::Outcome>, Error> {
+    let is_implicit_tag = negotiate::make_refmapping_ignore_predicate(fetch_tags, ref_map);
+    let tags: Vec<_> = ref_map
+        .mappings
+        .iter()
+        .filter(|m| is_implicit_tag(m))
+        .filter_map(|m| match &m.remote {
+            fetch::Source::Ref(gix_protocol::handshake::Ref::Peeled { tag, object, .. }) => Some((tag, object)),
+            _ => None,
+        })
+        .filter(|(tag, object)| !repo.objects.exists(tag) && repo.objects.exists(object))
+        .collect();
+    if tags.is_empty() {
+        return Ok(None);
+    }
+
+    if arguments.can_use_shallow() {
+        if let Some(shallow_commits) = repo.shallow_commits()? {
+            for commit in shallow_commits.iter() {
+                arguments.shallow(commit);
+            }
+        }
+    }
+    for (tag, object) in tags {
+        arguments.want(tag);
+        arguments.have(object);
+    }
+
+    progress.step();
+    progress.set_name("receiving tags".into());
+    let mut reader = arguments.send(transport, true).await?;
+    if sideband_all {
+        setup_remote_progress(progress, &mut reader, should_interrupt);
+    }
+    let protocol_version = ref_map.handshake.server_protocol_version;
+    let response = gix_protocol::fetch::Response::from_line_reader(protocol_version, &mut reader, true, false).await?;
+    if !response.has_pack() {
+        return Ok(None);
+    }
+    if !sideband_all {
+        setup_remote_progress(progress, &mut reader, should_interrupt);
+    }
+    write_pack(reader, repo, progress, should_interrupt, options).map(Some)
+}
+
 fn acquire_shallow_lock(repo: &Repository) -> Result<gix_lock::File, Error> {
     gix_lock::File::acquire_to_update_resource(repo.shallow_file(), gix_lock::acquire::Fail::Immediately, None)
         .map_err(Into::into)
@@ -420,6 +646,57 @@ fn add_shallow_args(
     Ok((shallow_commits, shallow_lock))
 }
 
+/// Return the filter to ask the remote to apply, which is `filter` or the configured one for promisor remotes,
+/// along with a flag to indicate if `remote` is a promisor remote.
+fn filter_and_promisor(
+    remote: &crate::Remote<'_>,
+    filter: Option<fetch::Filter>,
+) -> Result<(Option<fetch::Filter>, bool), Error> {
+    let Some(name) = remote.name().map(remote::Name::as_bstr) else {
+        return Ok((filter, false));
+    };
+    let repo = remote.repo;
+    let config = &repo.config.resolved;
+    let mut section_filter = repo.filter_config_section();
+    let is_promisor = config
+        .boolean_filter(format!("remote.{name}.{}", Remote::PROMISOR.name), &mut section_filter)
+        .map(|val| Remote::PROMISOR.enrich_error(val))
+        .transpose()
+        .with_leniency(repo.config.lenient_config)
+        .map_err(Error::PromisorConfig)?
+        .unwrap_or(false)
+        || config
+            .string_filter(Extensions::PARTIAL_CLONE.logical_name().as_str(), &mut section_filter)
+            .map_or(false, |promisor_name| promisor_name.as_ref() == name);
+    let filter = match filter {
+        Some(filter) => Some(filter),
+        None if is_promisor => config
+            .string_filter(
+                format!("remote.{name}.{}", Remote::PARTIAL_CLONE_FILTER.name),
+                &mut section_filter,
+            )
+            .map(|spec| Remote::PARTIAL_CLONE_FILTER.try_into_filter(spec))
+            .transpose()
+            .with_leniency(repo.config.lenient_config)
+            .map_err(Error::PartialCloneFilterConfig)?,
+        None => None,
+    };
+    Ok((filter, is_promisor))
+}
+
+/// Write the `.promisor` file that marks a pack as received from a promisor remote, listing the refs we fetched like `git` does.
+fn write_promisor_file(path: std::path::PathBuf, ref_map: &fetch::RefMap) -> Result<(), Error> {
+    let mut content = Vec::new();
+    for mapping in &ref_map.mappings {
+        if let (Some(id), Some(name)) = (mapping.remote.as_id(), mapping.remote.as_name()) {
+            content.extend_from_slice(format!("{id} ").as_bytes());
+            content.extend_from_slice(name);
+            content.push(b'\n');
+        }
+    }
+    std::fs::write(&path, content).map_err(|source| Error::WritePromisorFile { path, source })
+}
+
 fn setup_remote_progress<'a>(
     progress: &mut dyn crate::DynNestedProgress,
     reader: &mut Box<dyn gix_protocol::transport::client::ExtendedBufRead<'a> + Unpin + 'a>,
@@ -25,5 +25,8 @@ mod access;
 ///
 pub mod ref_map;
 
+///
+pub mod object_info;
+
 ///
 pub mod fetch;
@@ -0,0 +1,75 @@
+use gix_features::progress::Progress;
+use gix_protocol::transport::client::Transport;
+
+use crate::remote::{ref_map, Connection};
+
+/// The error returned by [`Connection::object_info()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Handshake(#[from] ref_map::Error),
+    #[error("The remote doesn't support the 'object-info' command, which requires protocol V2")]
+    Unsupported,
+    #[error(transparent)]
+    ObjectInfo(#[from] gix_protocol::object_info::Error),
+}
+
+impl gix_protocol::transport::IsSpuriousError for Error {
+    fn is_spurious(&self) -> bool {
+        match self {
+            Error::Handshake(err) => err.is_spurious(),
+            Error::ObjectInfo(err) => err.is_spurious(),
+            _ => false,
+        }
+    }
+}
+
+impl<T> Connection<'_, '_, T>
+where
+    T: Transport,
+{
+    /// Ask the remote for information about the objects with the given `ids`, like their size, without downloading them.
+    ///
+    /// The returned information is in the order of `ids`, and the size of objects the remote doesn't have is `None`.
+    /// This requires the remote to speak protocol V2 and to advertise the `object-info` capability, which `git` servers
+    /// do if `transfer.advertiseObjectInfo` is `true`.
+    ///
+    /// # Consumption
+    ///
+    /// Like [`ref_map()`][Self::ref_map()], the connection is consumed as it's only used for a single interaction.
+    ///
+    /// ### Configuration
+    ///
+    /// - `gitoxide.userAgent` is read to obtain the application user agent for git servers and for HTTP servers as well.
+    #[allow(clippy::result_large_err)]
+    #[gix_protocol::maybe_async::maybe_async]
+    pub async fn object_info(
+        mut self,
+        ids: impl IntoIterator<Item = gix_hash::ObjectId>,
+        mut progress: impl Progress,
+    ) -> Result<Vec<gix_protocol::object_info::Info>, Error> {
+        let _span = gix_trace::coarse!("remote::Connection::object_info()");
+        let handshake = self.handshake_inner(Vec::new(), &mut progress).await?;
+        let res = if handshake.server_protocol_version == gix_protocol::transport::Protocol::V2
+            && handshake.capabilities.contains("object-info")
+        {
+            gix_protocol::object_info(
+                &mut self.transport,
+                &handshake.capabilities,
+                Some(self.remote.repo.config.user_agent_tuple()),
+                ids,
+                &mut progress,
+                self.trace,
+            )
+            .await
+            .map_err(Into::into)
+        } else {
+            Err(Error::Unsupported)
+        };
+        gix_protocol::indicate_end_of_interaction(&mut self.transport, self.trace)
+            .await
+            .ok();
+        res
+    }
+}
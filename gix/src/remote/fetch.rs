@@ -7,10 +7,12 @@ pub mod negotiate {
     pub use super::super::connection::fetch::negotiate::Error;
     #[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
     pub(crate) use super::super::connection::fetch::negotiate::{
-        add_wants, make_refmapping_ignore_predicate, mark_complete_and_common_ref, one_round, Action,
+        add_wants, make_refmapping_ignore_predicate, mark_complete_and_common_ref, one_round, server_is_ready, Action,
     };
 }
 
+#[cfg(feature = "blocking-network-client")]
+pub(crate) use super::connection::fetch::promisor;
 #[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
 pub use super::connection::fetch::{
     outcome, prepare, refs, Error, Outcome, Prepare, ProgressId, RefLogMessage, Status,
@@ -108,6 +110,9 @@ impl Shallow {
     }
 }
 
+#[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
+pub use gix_protocol::{filter, Filter};
+
 /// Information about the relationship between our refspecs, and remote references with their local counterparts.
 #[derive(Default, Debug, Clone)]
 #[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
@@ -70,6 +70,12 @@ impl crate::Repository {
                 .and_then(|variant| Ssh::VARIANT.try_into_variant(variant).transpose())
                 .transpose()
                 .with_leniency(self.options.lenient_config)?,
+            native: config
+                .boolean_filter(gitoxide::Ssh::NATIVE, &mut trusted)
+                .map(|value| gitoxide::Ssh::NATIVE.enrich_error(value))
+                .transpose()
+                .with_leniency(self.options.lenient_config)?
+                .unwrap_or_default(),
         };
         Ok(opts)
     }
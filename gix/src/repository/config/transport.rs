@@ -17,7 +17,8 @@ impl crate::Repository {
     #[cfg_attr(
         not(any(
             feature = "blocking-http-transport-reqwest",
-            feature = "blocking-http-transport-curl"
+            feature = "blocking-http-transport-curl",
+            feature = "async-http-transport-reqwest"
         )),
         allow(unused_variables)
     )]
@@ -33,14 +34,16 @@ impl crate::Repository {
             Http | Https => {
                 #[cfg(not(any(
                     feature = "blocking-http-transport-reqwest",
-                    feature = "blocking-http-transport-curl"
+                    feature = "blocking-http-transport-curl",
+                    feature = "async-http-transport-reqwest"
                 )))]
                 {
                     Ok(None)
                 }
                 #[cfg(any(
                     feature = "blocking-http-transport-reqwest",
-                    feature = "blocking-http-transport-curl"
+                    feature = "blocking-http-transport-curl",
+                    feature = "async-http-transport-reqwest"
                 ))]
                 {
                     use std::{
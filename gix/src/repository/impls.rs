@@ -1,6 +1,7 @@
 impl Clone for crate::Repository {
     fn clone(&self) -> Self {
-        crate::Repository::from_refs_and_objects(
+        #[cfg_attr(not(feature = "blocking-network-client"), allow(unused_mut))]
+        let mut repo = crate::Repository::from_refs_and_objects(
             self.refs.clone(),
             self.objects.clone(),
             self.work_tree.clone(),
@@ -12,7 +13,13 @@ impl Clone for crate::Repository {
             self.shallow_commits.clone(),
             #[cfg(feature = "attributes")]
             self.modules.clone(),
-        )
+        );
+        // Clones that were made not to fetch missing objects must stay that way.
+        #[cfg(feature = "blocking-network-client")]
+        {
+            repo.objects.promisor.clone_from(&self.objects.promisor);
+        }
+        repo
     }
 }
 
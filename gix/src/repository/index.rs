@@ -124,6 +124,19 @@ impl crate::Repository {
             self.git_dir().join("index"),
         ))
     }
+
+    /// Write the entries of `index` as trees into the object database and return the id of the root tree, similar to `git write-tree`.
+    ///
+    /// Valid trees of the `TREE` extension are reused and the extension is refreshed, so `index` should be written back
+    /// to disk to speed up future calls.
+    pub fn write_tree_from_index(
+        &self,
+        index: &mut gix_index::State,
+    ) -> Result<crate::Id<'_>, super::write_tree_from_index::Error> {
+        index
+            .write_tree(|tree| self.write_object(tree).map(crate::Id::detach))
+            .map(|id| crate::Id::from_id(id, self))
+    }
 }
 
 impl std::ops::Deref for IndexPersistedOrInMemory {
@@ -14,7 +14,8 @@ impl crate::Repository {
         #[cfg(feature = "attributes")] modules: crate::submodule::ModulesFileStorage,
     ) -> Self {
         setup_objects(&mut objects, &config);
-        crate::Repository {
+        #[cfg_attr(not(feature = "blocking-network-client"), allow(unused_mut))]
+        let mut repo = crate::Repository {
             bufs: RefCell::new(Vec::with_capacity(4)),
             work_tree,
             common_dir,
@@ -27,7 +28,10 @@ impl crate::Repository {
             shallow_commits,
             #[cfg(feature = "attributes")]
             modules,
-        }
+        };
+        #[cfg(feature = "blocking-network-client")]
+        setup_promisor(&mut repo);
+        repo
     }
 
     /// Convert this instance into a [`ThreadSafeRepository`][crate::ThreadSafeRepository] by dropping all thread-local data.
@@ -60,3 +64,10 @@ pub(crate) fn setup_objects(objects: &mut crate::OdbHandle, config: &crate::conf
         }
     }
 }
+
+/// Let `repo` fetch missing objects from its promisor remote if it's a partial clone.
+#[cfg(feature = "blocking-network-client")]
+pub(crate) fn setup_promisor(repo: &mut crate::Repository) {
+    repo.objects.promisor = crate::remote::fetch::promisor::Promisor::from_repo(repo)
+        .map(|promisor| std::sync::Arc::new(promisor) as std::sync::Arc<dyn gix_odb::Promisor>);
+}
@@ -65,7 +65,11 @@ mod pathspec;
 mod reference;
 mod remote;
 mod revision;
+#[cfg(feature = "blocking-network-server")]
+mod server;
 mod shallow;
+#[cfg(feature = "worktree-mutation")]
+mod sparse_checkout;
 mod state;
 #[cfg(feature = "attributes")]
 mod submodule;
@@ -138,6 +142,26 @@ pub mod index_from_tree {
     }
 }
 
+///
+#[cfg(feature = "index")]
+pub mod write_tree_from_index {
+    /// The error returned by [Repository::write_tree_from_index()](crate::Repository::write_tree_from_index).
+    pub type Error = gix_index::write_tree::Error<crate::object::write::Error>;
+}
+
+///
+pub mod edit_tree {
+    /// The error returned by [Repository::edit_tree()](crate::Repository::edit_tree).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        FindTree(#[from] crate::object::find::existing::with_conversion::Error),
+        #[error(transparent)]
+        DecodeTree(#[from] gix_object::decode::Error),
+    }
+}
+
 ///
 pub mod branch_remote_ref_name {
 
@@ -227,6 +251,64 @@ pub mod worktree_stream {
     }
 }
 
+///
+#[cfg(feature = "blocking-network-server")]
+pub mod upload_pack_options {
+    use crate::bstr::BString;
+
+    /// The error returned by [`Repository::upload_pack_options()`][crate::Repository::upload_pack_options()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Boolean(#[from] crate::config::boolean::Error),
+        #[error("The value of `uploadpack.blobPackfileUri` must be of the form '<object-hash> <pack-hash> <uri>', got {value:?}")]
+        BlobPackfileUri { value: BString },
+    }
+}
+
+///
+#[cfg(feature = "blocking-network-server")]
+pub mod upload_pack {
+    /// The error returned by [`Repository::upload_pack()`][crate::Repository::upload_pack()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        FindReference(#[from] crate::reference::find::existing::Error),
+        #[error(transparent)]
+        InitReferenceIter(#[from] crate::reference::iter::Error),
+        #[error(transparent)]
+        InitReferences(#[from] crate::reference::iter::init::Error),
+        #[error(transparent)]
+        IterReferences(Box<dyn std::error::Error + Send + Sync + 'static>),
+        #[error(transparent)]
+        FollowReference(#[from] crate::reference::follow::to_object::Error),
+        #[error(transparent)]
+        PeelReference(#[from] crate::reference::peel::Error),
+        #[error("Could not prepare the object database for sending packs")]
+        OpenObjects(#[from] std::io::Error),
+        #[error(transparent)]
+        UploadPack(#[from] gix_protocol::upload_pack::Error),
+    }
+}
+
+///
+#[cfg(feature = "blocking-network-server")]
+pub mod receive_pack_options {
+    /// The error returned by [`Repository::receive_pack_options()`][crate::Repository::receive_pack_options()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Boolean(#[from] crate::config::boolean::Error),
+        #[error(transparent)]
+        HooksPath(#[from] gix_config::path::interpolate::Error),
+        #[error(transparent)]
+        Committer(#[from] crate::config::time::Error),
+    }
+}
+
 ///
 #[cfg(feature = "worktree-archive")]
 pub mod worktree_archive {
@@ -54,6 +54,16 @@ impl crate::Repository {
         Ok(self.find_object(id)?.try_into_tree()?)
     }
 
+    /// Start editing the tree with `id` to write the changed trees into the object database, without the need for a worktree.
+    ///
+    /// Use [`empty_tree()`](Self::empty_tree()) to start from scratch.
+    pub fn edit_tree(
+        &self,
+        id: impl Into<ObjectId>,
+    ) -> Result<crate::object::tree::Editor<'_>, crate::repository::edit_tree::Error> {
+        Ok(self.find_tree(id)?.edit()?)
+    }
+
     /// Find an annotated tag with `id` or fail if there was no object or the object wasn't a tag.
     pub fn find_tag(&self, id: impl Into<ObjectId>) -> Result<Tag<'_>, object::find::existing::with_conversion::Error> {
         Ok(self.find_object(id)?.try_into_tag()?)
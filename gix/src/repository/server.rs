@@ -0,0 +1,209 @@
+use std::sync::atomic::AtomicBool;
+
+use gix_protocol::{handshake::Ref, receive_pack, transport::Protocol, upload_pack};
+use gix_ref::TargetRef;
+
+use crate::{
+    config::{
+        cache::util::ApplyLeniencyDefault,
+        tree::{Core, Key, Receive, Transfer, UploadPack},
+    },
+    head,
+    repository::{receive_pack_options, upload_pack as error, upload_pack_options},
+    Reference, Repository,
+};
+
+impl Repository {
+    /// Return options for use with [`upload_pack()`](Self::upload_pack()), configured according to `uploadpack.*` settings
+    /// and `transfer.advertiseObjectInfo`.
+    pub fn upload_pack_options(&self) -> Result<upload_pack::Options, upload_pack_options::Error> {
+        let boolean = |key: &'static crate::config::tree::keys::Boolean| -> Result<bool, upload_pack_options::Error> {
+            Ok(self
+                .config
+                .resolved
+                .boolean(key)
+                .map(|res| key.enrich_error(res))
+                .transpose()
+                .with_lenient_default(self.config.lenient_config)?
+                .unwrap_or_default())
+        };
+        Ok(upload_pack::Options {
+            object_hash: self.object_hash(),
+            agent: self.config.user_agent_tuple().1.expect("always set").into_owned(),
+            allow_filter: boolean(&UploadPack::ALLOW_FILTER)?,
+            allow_any_object_in_want: boolean(&UploadPack::ALLOW_ANY_SHA1_IN_WANT)?,
+            allow_ref_in_want: boolean(&UploadPack::ALLOW_REF_IN_WANT)?,
+            packfile_uris: self
+                .config
+                .resolved
+                .strings(UploadPack::BLOB_PACKFILE_URI.logical_name().as_str())
+                .unwrap_or_default()
+                .into_iter()
+                .map(|value| {
+                    parse_blob_packfile_uri(value.as_ref()).ok_or_else(|| upload_pack_options::Error::BlobPackfileUri {
+                        value: value.into_owned(),
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+            bundle_list: if boolean(&UploadPack::ADVERTISE_BUNDLE_URIS)? {
+                self.bundle_list_config()
+            } else {
+                Vec::new()
+            },
+            advertise_object_info: boolean(&Transfer::ADVERTISE_OBJECT_INFO)?,
+            pack_dir: Some(self.objects.store_ref().path().join("pack")),
+            ..Default::default()
+        })
+    }
+
+    /// Return all values of the `bundle` section and its subsections as key-value pairs like `bundle.<id>.uri=<uri>`,
+    /// with lower-cased section and value names just like `git` advertises them.
+    fn bundle_list_config(&self) -> Vec<(crate::bstr::BString, crate::bstr::BString)> {
+        let mut pairs = Vec::new();
+        for section in self.config.resolved.sections_by_name("bundle").into_iter().flatten() {
+            let mut prefix = crate::bstr::BString::from("bundle.");
+            if let Some(subsection) = section.header().subsection_name() {
+                prefix.extend_from_slice(subsection);
+                prefix.push(b'.');
+            }
+            for (name, value) in section.body().clone() {
+                let mut key = prefix.clone();
+                key.extend_from_slice(name.as_ref().to_ascii_lowercase().as_bytes());
+                pairs.push((key, value.into_owned()));
+            }
+        }
+        pairs
+    }
+
+    /// Serve a client that fetches or clones from this repository like `git upload-pack` would, reading its requests from `input`
+    /// and writing responses to `out` using protocol `version`, while configuring the server with `options`.
+    ///
+    /// All references are advertised, and `version` is typically derived from the `GIT_PROTOCOL` environment variable.
+    /// This makes it possible to serve clients over SSH by invoking this method with standard input and output.
+    pub fn upload_pack(
+        &self,
+        version: Protocol,
+        input: &mut dyn std::io::Read,
+        out: &mut dyn std::io::Write,
+        should_interrupt: &AtomicBool,
+        options: &upload_pack::Options,
+    ) -> Result<(), error::Error> {
+        let refs = self.upload_pack_refs()?;
+        let mut objects = self.objects.clone().into_arc()?;
+        objects.prevent_pack_unload();
+        objects.ignore_replacements = true;
+        gix_protocol::upload_pack(&refs, objects, version, input, out, should_interrupt, options)?;
+        Ok(())
+    }
+
+    /// Return options for use with [`receive_pack()`](Self::receive_pack()), configured according to `receive.*` settings.
+    ///
+    /// Hooks are run from `core.hooksPath`, or from the `hooks` directory of the repository if it's unset,
+    /// and the configured committer is used for reference logs.
+    pub fn receive_pack_options(&self) -> Result<receive_pack::Options, receive_pack_options::Error> {
+        let boolean = |key: &'static crate::config::tree::keys::Boolean| -> Result<bool, receive_pack_options::Error> {
+            Ok(self
+                .config
+                .resolved
+                .boolean(key)
+                .map(|res| key.enrich_error(res))
+                .transpose()
+                .with_lenient_default(self.config.lenient_config)?
+                .unwrap_or_default())
+        };
+        let hooks_dir = match self.config.trusted_file_path(Core::HOOKS_PATH).transpose()? {
+            Some(path) => self.git_dir().join(path),
+            None => self.common_dir().join("hooks"),
+        };
+        Ok(receive_pack::Options {
+            object_hash: self.object_hash(),
+            agent: self.config.user_agent_tuple().1.expect("always set").into_owned(),
+            committer: self.committer().transpose()?.map(|committer| committer.to_owned()),
+            hooks_dir: Some(hooks_dir),
+            deny_deletes: boolean(&Receive::DENY_DELETES)?,
+            deny_non_fast_forwards: boolean(&Receive::DENY_NON_FAST_FORWARDS)?,
+            advertise_push_options: boolean(&Receive::ADVERTISE_PUSH_OPTIONS)?,
+            ..Default::default()
+        })
+    }
+
+    /// Serve a client that pushes to this repository like `git receive-pack` would, reading its requests from `input`
+    /// and writing responses to `out` using protocol `version`, while configuring the server with `options`.
+    ///
+    /// Received objects are kept in a quarantine directory until all checks and hooks passed, and the returned outcome
+    /// informs about which reference updates were applied or rejected.
+    pub fn receive_pack(
+        &self,
+        version: Protocol,
+        input: &mut dyn std::io::Read,
+        out: &mut dyn std::io::Write,
+        should_interrupt: &AtomicBool,
+        options: &receive_pack::Options,
+    ) -> Result<receive_pack::Outcome, receive_pack::Error> {
+        gix_protocol::receive_pack(
+            &self.refs,
+            self.objects.store_ref().path(),
+            version,
+            input,
+            out,
+            should_interrupt,
+            options,
+        )
+    }
+
+    /// Return all references in a form suitable for advertising them to clients, with `HEAD` first.
+    fn upload_pack_refs(&self) -> Result<Vec<Ref>, error::Error> {
+        let mut refs = Vec::new();
+        match self.head()?.kind {
+            head::Kind::Unborn(target) => refs.push(Ref::Unborn {
+                full_ref_name: "HEAD".into(),
+                target: target.as_bstr().into(),
+            }),
+            head::Kind::Symbolic(_) | head::Kind::Detached { .. } => {
+                refs.push(to_ref(self.find_reference("HEAD")?)?);
+            }
+        }
+        for reference in self.references()?.all()? {
+            refs.push(to_ref(reference.map_err(error::Error::IterReferences)?)?);
+        }
+        Ok(refs)
+    }
+}
+
+fn to_ref(mut reference: Reference<'_>) -> Result<Ref, error::Error> {
+    let full_ref_name = reference.name().as_bstr().to_owned();
+    let symbolic_target = match reference.target() {
+        TargetRef::Symbolic(target) => Some(target.as_bstr().to_owned()),
+        TargetRef::Object(_) => None,
+    };
+    let target = reference.follow_to_object()?.detach();
+    let object = reference.peel_to_id_in_place()?.detach();
+    let tag = (object != target).then_some(target);
+    Ok(match (symbolic_target, tag) {
+        (Some(target), tag) => Ref::Symbolic {
+            full_ref_name,
+            target,
+            tag,
+            object,
+        },
+        (None, Some(tag)) => Ref::Peeled {
+            full_ref_name,
+            tag,
+            object,
+        },
+        (None, None) => Ref::Direct { full_ref_name, object },
+    })
+}
+
+/// Parse `<object-hash> <pack-hash> <uri>` as configured in `uploadpack.blobPackfileUri`.
+fn parse_blob_packfile_uri(value: &crate::bstr::BStr) -> Option<upload_pack::PackfileUri> {
+    let mut tokens = value.splitn(3, |b| *b == b' ');
+    let object = gix_hash::ObjectId::from_hex(tokens.next()?).ok()?;
+    let pack_hash = gix_hash::ObjectId::from_hex(tokens.next()?).ok()?;
+    let uri = tokens.next().filter(|uri| !uri.is_empty())?;
+    Some(upload_pack::PackfileUri {
+        object,
+        pack_hash,
+        uri: uri.into(),
+    })
+}
@@ -1,5 +1,7 @@
 use std::{borrow::Cow, path::PathBuf};
 
+use gix_object::Exists;
+
 use crate::{
     bstr::ByteSlice,
     config::tree::{gitoxide, Key},
@@ -25,28 +27,37 @@ impl Repository {
     pub fn shallow_commits(&self) -> Result<Option<crate::shallow::Commits>, crate::shallow::open::Error> {
         self.shallow_commits.recent_snapshot(
             || self.shallow_file().metadata().ok().and_then(|m| m.modified().ok()),
-            || {
-                let buf = match std::fs::read(self.shallow_file()) {
-                    Ok(buf) => buf,
-                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
-                    Err(err) => return Err(err.into()),
-                };
-
-                let mut commits = buf
-                    .lines()
-                    .map(gix_hash::ObjectId::from_hex)
-                    .collect::<Result<Vec<_>, _>>()?;
-
-                commits.sort();
-                if commits.is_empty() {
-                    Ok(None)
-                } else {
-                    Ok(Some(commits))
-                }
-            },
+            || self.read_shallow_commits(),
         )
     }
 
+    /// Remove all commits from the shallow boundary which aren't present in the object database, for instance because
+    /// they were garbage-collected, and return the amount of removed commits.
+    ///
+    /// The `shallow` file is rewritten while holding its lock, and removed entirely if no shallow commit remains.
+    /// Nothing is written if there was nothing to prune.
+    pub fn prune_shallow_commits(&self) -> Result<usize, crate::shallow::prune::Error> {
+        let lock = gix_lock::File::acquire_to_update_resource(
+            self.shallow_file(),
+            gix_lock::acquire::Fail::Immediately,
+            None,
+        )?;
+        let Some(shallow_commits) = self.shallow_commits()? else {
+            return Ok(0);
+        };
+        let remaining: Vec<_> = shallow_commits
+            .iter()
+            .filter(|id| self.objects.exists(id))
+            .copied()
+            .collect();
+        let num_pruned = shallow_commits.len() - remaining.len();
+        if num_pruned != 0 {
+            crate::shallow::write::commits(lock, remaining)?;
+            self.force_refresh_shallow_commits()?;
+        }
+        Ok(num_pruned)
+    }
+
     /// Return the path to the `shallow` file which contains hashes, one per line, that describe commits that don't have their
     /// parents within this repository.
     ///
@@ -62,4 +73,35 @@ impl Repository {
             .unwrap_or_else(|| Cow::Borrowed("shallow".into()));
         self.common_dir().join(gix_path::from_bstr(shallow_name))
     }
+
+    /// Reload the shared list of shallow commits after we changed the `shallow` file ourselves, as its modification time
+    /// might not have changed enough for us to notice.
+    pub(crate) fn force_refresh_shallow_commits(&self) -> Result<(), crate::shallow::open::Error> {
+        self.shallow_commits.force_refresh(|| {
+            let Some(modified) = self.shallow_file().metadata().ok().and_then(|m| m.modified().ok()) else {
+                return Ok(None);
+            };
+            Ok(self.read_shallow_commits()?.map(|commits| (modified, commits)))
+        })
+    }
+
+    fn read_shallow_commits(&self) -> Result<Option<Vec<gix_hash::ObjectId>>, crate::shallow::open::Error> {
+        let buf = match std::fs::read(self.shallow_file()) {
+            Ok(buf) => buf,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut commits = buf
+            .lines()
+            .map(gix_hash::ObjectId::from_hex)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        commits.sort();
+        if commits.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(commits))
+        }
+    }
 }
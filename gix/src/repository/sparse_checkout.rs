@@ -0,0 +1,312 @@
+use std::{path::PathBuf, sync::atomic::AtomicBool};
+
+use gix_index::entry::Flags;
+
+use crate::{
+    bstr::BStr,
+    config::{
+        cache::util::ApplyLeniencyDefault,
+        tree::{keys, Core},
+    },
+    sparse_checkout::{patterns, set_skip_worktree, update, Outcome, Patterns},
+    worktree, Repository,
+};
+
+/// Sparse checkout
+impl Repository {
+    /// Return the path to the `info/sparse-checkout` file, which contains the patterns of a sparse checkout.
+    ///
+    /// Note that it may not exist if the repository doesn't use a sparse checkout.
+    pub fn sparse_checkout_file(&self) -> PathBuf {
+        self.git_dir().join("info").join("sparse-checkout")
+    }
+
+    /// Return the patterns of the sparse checkout if `core.sparseCheckout` is enabled, interpreted in cone mode
+    /// if `core.sparseCheckoutCone` isn't disabled.
+    ///
+    /// Return `None` if sparse checkout isn't enabled, or if the `info/sparse-checkout` file doesn't exist.
+    pub fn sparse_checkout_patterns(&self) -> Result<Option<Patterns>, patterns::Error> {
+        let is_enabled = self
+            .config
+            .resolved
+            .boolean(Core::SPARSE_CHECKOUT)
+            .map(|res| Core::SPARSE_CHECKOUT.enrich_error(res))
+            .transpose()
+            .with_lenient_default(self.config.lenient_config)?
+            .unwrap_or_default();
+        if !is_enabled {
+            return Ok(None);
+        }
+        let cone = self
+            .config
+            .resolved
+            .boolean(Core::SPARSE_CHECKOUT_CONE)
+            .map(|res| Core::SPARSE_CHECKOUT_CONE.enrich_error(res))
+            .transpose()
+            .with_lenient_default(self.config.lenient_config)?
+            .unwrap_or(true);
+        let path = self.sparse_checkout_file();
+        match std::fs::read(&path) {
+            Ok(input) => Ok(Some(Patterns::from_bytes(&input, cone))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(source) => Err(patterns::Error::Io { path, source }),
+        }
+    }
+
+    /// Enable sparse checkout with `patterns` and update the worktree and index to match them,
+    /// like `git sparse-checkout set` does.
+    ///
+    /// The patterns are written to the [`info/sparse-checkout`](Self::sparse_checkout_file()) file,
+    /// and `core.sparseCheckout` and `core.sparseCheckoutCone` are set in the local configuration file as well as in memory.
+    pub fn sparse_checkout_set(
+        &mut self,
+        patterns: Patterns,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, update::Error> {
+        self.write_sparse_checkout(&patterns)?;
+        self.apply_sparse_checkout(Some(&patterns), should_interrupt)
+    }
+
+    /// Add `patterns` to the ones of the currently enabled sparse checkout and update the worktree and index to match them,
+    /// like `git sparse-checkout add` does.
+    ///
+    /// In cone mode, `patterns` are directories to check out recursively, otherwise they are lines in `.gitignore` syntax.
+    pub fn sparse_checkout_add(
+        &mut self,
+        patterns: impl IntoIterator<Item = impl AsRef<BStr>>,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, update::Error> {
+        let mut current = self.sparse_checkout_patterns()?.ok_or(update::Error::NotEnabled)?;
+        current.extend(patterns);
+        self.sparse_checkout_set(current, should_interrupt)
+    }
+
+    /// Update the worktree and index to match the patterns of the currently enabled sparse checkout,
+    /// like `git sparse-checkout reapply` does.
+    ///
+    /// Files that aren't included anymore are removed from the worktree unless they were modified,
+    /// and their index entries are marked with the `skip-worktree` flag.
+    /// Files that are now included lose the flag and are checked out.
+    ///
+    /// Note that sparse indices, which contain entries for entire directories outside of the sparse checkout, aren't supported
+    /// and cause an error.
+    pub fn sparse_checkout_reapply(&self, should_interrupt: &AtomicBool) -> Result<Outcome, update::Error> {
+        let patterns = self.sparse_checkout_patterns()?.ok_or(update::Error::NotEnabled)?;
+        self.apply_sparse_checkout(Some(&patterns), should_interrupt)
+    }
+
+    /// Check out all files that were excluded by the sparse checkout and disable it by setting `core.sparseCheckout` to `false`,
+    /// like `git sparse-checkout disable` does.
+    ///
+    /// The [`info/sparse-checkout`](Self::sparse_checkout_file()) file is kept so the patterns can be reused later.
+    pub fn sparse_checkout_disable(&mut self, should_interrupt: &AtomicBool) -> Result<Outcome, update::Error> {
+        let outcome = self.apply_sparse_checkout(None, should_interrupt)?;
+        self.write_sparse_checkout_config(&[(&Core::SPARSE_CHECKOUT, "false")])?;
+        Ok(outcome)
+    }
+}
+
+/// Utilities
+impl Repository {
+    /// Write `patterns` to the `info/sparse-checkout` file and enable sparse checkout in the local configuration.
+    pub(crate) fn write_sparse_checkout(&mut self, patterns: &Patterns) -> Result<(), update::Error> {
+        let path = self.sparse_checkout_file();
+        let mut lock = gix_lock::File::acquire_to_update_resource(
+            &path,
+            gix_lock::acquire::Fail::Immediately,
+            Some(self.git_dir().to_owned()),
+        )
+        .map_err(|source| update::Error::Lock {
+            path: path.clone(),
+            source,
+        })?;
+        patterns
+            .write_to(&mut lock)
+            .map_err(|source| update::Error::Write { path, source })?;
+        lock.commit()?;
+
+        self.write_sparse_checkout_config(&[
+            (&Core::SPARSE_CHECKOUT, "true"),
+            (
+                &Core::SPARSE_CHECKOUT_CONE,
+                if patterns.is_cone() { "true" } else { "false" },
+            ),
+        ])
+    }
+
+    /// Set `values` in the local configuration file as well as in our in-memory configuration.
+    fn write_sparse_checkout_config(
+        &mut self,
+        values: &[(&'static keys::Boolean, &'static str)],
+    ) -> Result<(), update::Error> {
+        let path = self.common_dir().join("config");
+        let mut config = gix_config::File::from_path_no_includes(path.clone(), gix_config::Source::Local)?;
+        for &(key, value) in values {
+            config.set_raw_value(key, value)?;
+        }
+        let mut lock = gix_lock::File::acquire_to_update_resource(&path, gix_lock::acquire::Fail::Immediately, None)
+            .map_err(|source| update::Error::Lock {
+                path: path.clone(),
+                source,
+            })?;
+        config
+            .write_to(&mut lock)
+            .map_err(|source| update::Error::Write { path, source })?;
+        lock.commit()?;
+
+        let mut snapshot = self.config_snapshot_mut();
+        for &(key, value) in values {
+            snapshot.set_value(key, value)?;
+        }
+        snapshot.commit()?;
+        Ok(())
+    }
+
+    /// Update the `skip-worktree` flags of all index entries according to `patterns`, or clear them all if `None`,
+    /// and remove or check out files in the worktree accordingly.
+    fn apply_sparse_checkout(
+        &self,
+        patterns: Option<&Patterns>,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, update::Error> {
+        let workdir = self.work_dir().ok_or_else(|| update::Error::BareRepository {
+            git_dir: self.git_dir().to_owned(),
+        })?;
+        let mut index = match self.open_index() {
+            Ok(index) => index,
+            Err(worktree::open_index::Error::IndexFile(gix_index::file::init::Error::Io(err)))
+                if err.kind() == std::io::ErrorKind::NotFound =>
+            {
+                return Ok(Outcome::default());
+            }
+            Err(err) => return Err(err.into()),
+        };
+        if index.is_sparse() {
+            return Err(update::Error::SparseIndex {
+                path: index.path().to_owned(),
+            });
+        }
+        let stat_options = self.stat_options()?;
+
+        let mut outcome = Outcome::default();
+        let mut needs_checkout = vec![false; index.entries().len()];
+        for ((entry, rela_path), needs_checkout) in index.entries_mut_with_paths().zip(needs_checkout.iter_mut()) {
+            if entry.stage_raw() != 0 {
+                continue;
+            }
+            let is_included = patterns.map_or(true, |patterns| patterns.is_included(rela_path));
+            let is_skipped = entry.flags.contains(Flags::SKIP_WORKTREE);
+            if is_included && is_skipped {
+                set_skip_worktree(&mut entry.flags, false);
+                *needs_checkout = !entry.mode.is_submodule();
+            } else if !is_included && !is_skipped {
+                if !entry.mode.is_submodule() {
+                    let path = workdir.join(gix_path::from_bstr(rela_path));
+                    match gix_index::fs::Metadata::from_path_no_follow(&path) {
+                        Ok(metadata) => {
+                            let stat = gix_index::entry::Stat::from_fs(&metadata).map_err(|source| {
+                                update::Error::FileTime {
+                                    path: path.clone(),
+                                    source,
+                                }
+                            })?;
+                            if !entry.stat.matches(&stat, stat_options)
+                                && !has_same_content(entry, &path, &metadata, self.object_hash()).map_err(|source| {
+                                    update::Error::ReadFile {
+                                        path: path.clone(),
+                                        source,
+                                    }
+                                })?
+                            {
+                                outcome.kept_modified.push(rela_path.to_owned());
+                                continue;
+                            }
+                            std::fs::remove_file(&path).map_err(|source| update::Error::RemoveFile {
+                                path: path.clone(),
+                                source,
+                            })?;
+                            outcome.files_removed += 1;
+                            remove_empty_leading_directories(&path, workdir);
+                        }
+                        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                        Err(source) => return Err(update::Error::RemoveFile { path, source }),
+                    }
+                }
+                set_skip_worktree(&mut entry.flags, true);
+            }
+        }
+
+        if needs_checkout.iter().any(|needs_checkout| *needs_checkout) {
+            // Only check out the newly included files by skipping all others for the time being.
+            let was_skipped: Vec<_> = index
+                .entries()
+                .iter()
+                .map(|entry| entry.flags.contains(Flags::SKIP_WORKTREE))
+                .collect();
+            for (entry, needs_checkout) in index.entries_mut().iter_mut().zip(&needs_checkout) {
+                if !needs_checkout {
+                    entry.flags.insert(Flags::SKIP_WORKTREE);
+                }
+            }
+            // In partial clones, get all blobs we need at once instead of having each of them fetched on demand.
+            self.objects.fetch_missing(
+                index
+                    .entries()
+                    .iter()
+                    .zip(&needs_checkout)
+                    .filter_map(|(entry, needs_checkout)| needs_checkout.then_some(entry.id)),
+            )?;
+
+            let mut opts = self.config.checkout_options(
+                self,
+                gix_worktree::stack::state::attributes::Source::WorktreeThenIdMapping,
+            )?;
+            opts.destination_is_initially_empty = false;
+            opts.overwrite_existing = false;
+            let res = gix_worktree_state::checkout(
+                &mut index,
+                workdir,
+                self.objects.clone().into_arc().map_err(update::Error::OpenArcOdb)?,
+                &gix_features::progress::Discard,
+                &gix_features::progress::Discard,
+                should_interrupt,
+                opts,
+            );
+            for (entry, was_skipped) in index.entries_mut().iter_mut().zip(was_skipped) {
+                entry.flags.set(Flags::SKIP_WORKTREE, was_skipped);
+            }
+            outcome.checkout = res?;
+        }
+
+        index.write(Default::default())?;
+        Ok(outcome)
+    }
+}
+
+/// Return `true` if the file at `path` has the content `entry` refers to, even though its stat information differs.
+///
+/// Note that filters aren't applied, so files that are converted on checkout are considered modified.
+fn has_same_content(
+    entry: &gix_index::Entry,
+    path: &std::path::Path,
+    metadata: &gix_index::fs::Metadata,
+    object_hash: gix_hash::Kind,
+) -> std::io::Result<bool> {
+    let data = if metadata.is_symlink() {
+        gix_path::into_bstr(std::fs::read_link(path)?).into_owned().into()
+    } else {
+        std::fs::read(path)?
+    };
+    Ok(gix_object::compute_hash(object_hash, gix_object::Kind::Blob, &data) == entry.id)
+}
+
+/// Remove all empty directories leading up to the removed file at `path`, but not `workdir` itself.
+fn remove_empty_leading_directories(path: &std::path::Path, workdir: &std::path::Path) {
+    let mut dir = path.parent();
+    while let Some(current) = dir.filter(|dir| *dir != workdir && dir.starts_with(workdir)) {
+        if std::fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
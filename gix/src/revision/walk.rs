@@ -1,7 +1,7 @@
 use gix_hash::ObjectId;
 use gix_object::FindExt;
 
-use crate::{ext::ObjectIdExt, revision, Repository};
+use crate::{bstr::BString, ext::ObjectIdExt, revision, Repository};
 
 /// The error returned by [`Platform::all()`] and [`Platform::selected()`].
 #[derive(Debug, thiserror::Error)]
@@ -155,6 +155,7 @@ pub struct Platform<'repo> {
     pub(crate) parents: gix_traverse::commit::Parents,
     pub(crate) use_commit_graph: Option<bool>,
     pub(crate) commit_graph: Option<gix_commitgraph::Graph>,
+    pub(crate) paths: Vec<BString>,
 }
 
 impl<'repo> Platform<'repo> {
@@ -167,6 +168,7 @@ impl<'repo> Platform<'repo> {
             use_commit_graph: None,
             commit_graph: None,
             prune: Vec::new(),
+            paths: Vec::new(),
         }
     }
 }
@@ -185,8 +187,9 @@ impl<'repo> Platform<'repo> {
         self
     }
 
-    /// Allow using the commitgraph, if present, if `toggle` is `true`, or disallow it with `false`. Set it to `None` to leave
-    /// control over this to the configuration of `core.commitGraph` (the default).
+    /// Allow using the commitgraph, if present, if `toggle` is `true`, or disallow it with `false`, even if one was
+    /// [set explicitly](Platform::with_commit_graph()). Set it to `None` to leave control over this to the configuration
+    /// of `core.commitGraph` (the default), which is ignored if a commit-graph was set explicitly.
     ///
     /// Errors when loading the graph lead to falling back to the object database, it's treated as optional cache.
     pub fn use_commit_graph(mut self, toggle: impl Into<Option<bool>>) -> Self {
@@ -205,6 +208,28 @@ impl<'repo> Platform<'repo> {
         self
     }
 
+    /// Only return commits that changed any of the given `paths` compared to their parents, similar to
+    /// `git log --full-history -- <paths>`.
+    ///
+    /// `paths` are slash-separated and relative to the root of the repository, and may point to files or directories.
+    /// A commit is returned unless the entries at all `paths` are the same as in each of its parents, and commits without
+    /// parents are returned if they contain any of `paths`, which includes the commits at the boundary of a shallow clone.
+    /// Note that the history isn't simplified, so all parents of all commits are still traversed.
+    ///
+    /// If available, the changed-path Bloom filters of the commit-graph are used to avoid looking up `paths` in the trees
+    /// of commits that definitely didn't change them compared to their first parent.
+    pub fn with_paths(mut self, paths: impl IntoIterator<Item = impl Into<BString>>) -> Self {
+        use crate::bstr::ByteSlice;
+        self.paths.extend(
+            paths
+                .into_iter()
+                .map(Into::into)
+                .map(|path| path.trim_end_with(|c| c == '/').into())
+                .filter(|path: &BString| !path.is_empty()),
+        );
+        self
+    }
+
     /// Prune the commit with the given `ids` such that they won't be returned, and such that none of their ancestors is returned either.
     ///
     /// Note that this forces the [sorting](Self::sorting) to
@@ -255,53 +280,80 @@ impl<'repo> Platform<'repo> {
             use_commit_graph,
             commit_graph,
             mut prune,
+            paths,
         } = self;
         prune.sort();
-        Ok(revision::Walk {
+        let commit_graph = match use_commit_graph {
+            Some(false) => None,
+            Some(true) => commit_graph.or_else(|| repo.commit_graph().ok()),
+            None => match commit_graph {
+                Some(graph) => Some(graph),
+                None => repo
+                    .config
+                    .may_use_commit_graph()?
+                    .then(|| repo.commit_graph().ok())
+                    .flatten(),
+            },
+        };
+        // Note that specific shallow handling for commit-graphs isn't needed as these contain
+        // all information there is, and exclude shallow parents to be structurally consistent.
+        let shallow_commits = repo.shallow_commits()?;
+        let path_filter = (!paths.is_empty()).then(|| PathFilter {
             repo,
-            inner: Box::new(
-                gix_traverse::commit::Simple::filtered(tips, &repo.objects, {
-                    // Note that specific shallow handling for commit-graphs isn't needed as these contain
-                    // all information there is, and exclude shallow parents to be structurally consistent.
-                    let shallow_commits = repo.shallow_commits()?;
-                    let mut grafted_parents_to_skip = Vec::new();
-                    let mut buf = Vec::new();
-                    move |id| {
-                        if !filter(id) {
-                            return false;
-                        }
-                        let id = id.to_owned();
-                        if prune.binary_search(&id).is_ok() {
+            shallow_commits: shallow_commits.clone(),
+            paths,
+            keys: Vec::new(),
+            buf: Vec::new(),
+        });
+        let mut inner = gix_traverse::commit::Simple::filtered(tips, &repo.objects, {
+            let mut grafted_parents_to_skip = Vec::new();
+            let mut buf = Vec::new();
+            move |id| {
+                if !filter(id) {
+                    return false;
+                }
+                let id = id.to_owned();
+                if prune.binary_search(&id).is_ok() {
+                    return false;
+                }
+                match shallow_commits.as_ref() {
+                    Some(commits) => {
+                        if let Ok(idx) = grafted_parents_to_skip.binary_search(&id) {
+                            grafted_parents_to_skip.remove(idx);
                             return false;
                         }
-                        match shallow_commits.as_ref() {
-                            Some(commits) => {
-                                if let Ok(idx) = grafted_parents_to_skip.binary_search(&id) {
-                                    grafted_parents_to_skip.remove(idx);
-                                    return false;
-                                };
-                                if commits.binary_search(&id).is_ok() {
-                                    if let Ok(commit) = repo.objects.find_commit_iter(&id, &mut buf) {
-                                        grafted_parents_to_skip.extend(commit.parent_ids());
-                                        grafted_parents_to_skip.sort();
-                                    }
-                                };
-                                true
+                        if commits.binary_search(&id).is_ok() {
+                            if let Ok(commit) = repo.objects.find_commit_iter(&id, &mut buf) {
+                                grafted_parents_to_skip.extend(commit.parent_ids());
+                                grafted_parents_to_skip.sort();
                             }
-                            None => true,
                         }
+                        true
                     }
-                })
-                .sorting(sorting.into_simple().expect("for now there is nothing else"))?
-                .parents(parents)
-                .commit_graph(
-                    commit_graph.or(use_commit_graph
-                        .map_or_else(|| self.repo.config.may_use_commit_graph(), Ok)?
-                        .then(|| self.repo.commit_graph().ok())
-                        .flatten()),
-                )
-                .map(|res| res.map_err(iter::Error::from)),
-            ),
+                    None => true,
+                }
+            }
+        })
+        .sorting(sorting.into_simple().expect("for now there is nothing else"))?
+        .parents(parents)
+        .commit_graph(commit_graph);
+        Ok(revision::Walk {
+            repo,
+            inner: match path_filter {
+                // The path filter uses the commit-graph of the traversal, so it has to drive it directly.
+                Some(mut path_filter) => Box::new(std::iter::from_fn(move || loop {
+                    let info = match inner.next()? {
+                        Ok(info) => info,
+                        Err(err) => return Some(Err(err.into())),
+                    };
+                    match path_filter.is_changed(&info, inner.commit_graph_cache()) {
+                        Ok(true) => return Some(Ok(info)),
+                        Ok(false) => {}
+                        Err(err) => return Some(Err(err)),
+                    }
+                })),
+                None => Box::new(inner.map(|res| res.map_err(iter::Error::from))),
+            },
         })
     }
     /// Return an iterator to traverse all commits reachable as configured by the [Platform].
@@ -323,6 +375,100 @@ pub mod iter {
     pub enum Error {
         #[error(transparent)]
         SimpleTraversal(#[from] gix_traverse::commit::simple::Error),
+        #[error(transparent)]
+        FindObject(#[from] crate::object::find::existing::with_conversion::Error),
+        #[error(transparent)]
+        DecodeCommit(#[from] gix_object::decode::Error),
+        #[error(transparent)]
+        LookupEntry(#[from] crate::object::find::existing::Error),
+    }
+}
+
+/// Decides if a commit changed any of the paths a walk is limited to.
+struct PathFilter<'repo> {
+    repo: &'repo Repository,
+    /// The commits at the boundary of a shallow clone, whose parents aren't available and which are treated like roots.
+    shallow_commits: Option<crate::shallow::Commits>,
+    paths: Vec<BString>,
+    /// The Bloom filter keys of all paths and their leading directories, for each kind of settings encountered so far.
+    keys: Vec<(gix_commitgraph::bloom::Settings, Vec<Vec<gix_commitgraph::bloom::Key>>)>,
+    buf: Vec<u8>,
+}
+
+impl PathFilter<'_> {
+    /// Return `true` if the commit described by `info` isn't the same as all of its parents in our paths.
+    /// Use `graph` to accelerate the lookup, if present.
+    fn is_changed(
+        &mut self,
+        info: &gix_traverse::commit::Info,
+        graph: Option<&gix_commitgraph::Graph>,
+    ) -> Result<bool, iter::Error> {
+        let is_shallow = self
+            .shallow_commits
+            .as_ref()
+            .map_or(false, |commits| commits.binary_search(&info.id).is_ok());
+        let parent_ids = if is_shallow { &[][..] } else { &info.parent_ids[..] };
+        let same_as_first_parent = !parent_ids.is_empty() && !self.may_differ_from_first_parent(&info.id, graph);
+        if same_as_first_parent && parent_ids.len() == 1 {
+            return Ok(false);
+        }
+        let entries = self.entries_at_paths(info.id, graph)?;
+        if parent_ids.is_empty() {
+            return Ok(entries.iter().any(Option::is_some));
+        }
+        for parent_id in parent_ids.iter().skip(usize::from(same_as_first_parent)) {
+            if self.entries_at_paths(*parent_id, graph)? != entries {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Return `false` if the Bloom filter of commit `id` says that none of our paths were changed compared to its first parent.
+    fn may_differ_from_first_parent(&mut self, id: &gix_hash::oid, graph: Option<&gix_commitgraph::Graph>) -> bool {
+        let Some(filter) = graph
+            .and_then(|graph| graph.commit_by_id(id))
+            .and_then(|commit| commit.bloom_filter())
+        else {
+            return true;
+        };
+        let keys = match self.keys.iter().position(|(settings, _)| settings == filter.settings()) {
+            Some(idx) => &self.keys[idx].1,
+            None => {
+                let keys = self
+                    .paths
+                    .iter()
+                    .map(|path| {
+                        gix_commitgraph::bloom::Key::for_path_and_leading_directories(path.as_ref(), filter.settings())
+                    })
+                    .collect();
+                self.keys.push((*filter.settings(), keys));
+                &self.keys.last().expect("just pushed").1
+            }
+        };
+        keys.iter()
+            .any(|path_keys| path_keys.iter().all(|key| filter.may_contain(key)))
+    }
+
+    /// Return the mode and id of the entries at each of our paths in the tree of commit `id`.
+    fn entries_at_paths(
+        &mut self,
+        id: ObjectId,
+        graph: Option<&gix_commitgraph::Graph>,
+    ) -> Result<Vec<Option<(gix_object::tree::EntryMode, ObjectId)>>, iter::Error> {
+        let tree_id = match graph.and_then(|graph| graph.commit_by_id(id)) {
+            Some(commit) => commit.root_tree_id().to_owned(),
+            None => self.repo.find_commit(id)?.tree_id()?.detach(),
+        };
+        let tree = self.repo.find_tree(tree_id)?;
+        self.paths
+            .iter()
+            .map(|path| {
+                Ok(tree
+                    .lookup_entry(path.split(|b| *b == b'/'), &mut self.buf)?
+                    .map(|entry| (entry.mode(), entry.oid().to_owned())))
+            })
+            .collect()
     }
 }
 
@@ -5,11 +5,9 @@ pub(crate) type CommitsStorage =
 pub type Commits = gix_fs::SharedFileSnapshot<Vec<gix_hash::ObjectId>>;
 
 ///
-#[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
 pub mod write {
+    #[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
     pub(crate) mod function {
-        use std::io::Write;
-
         use gix_protocol::fetch::response::ShallowUpdate;
 
         use crate::shallow::{write::Error, Commits};
@@ -17,15 +15,14 @@ pub mod write {
         /// Write the previously obtained (possibly non-existing) `shallow_commits` to the shallow `file`
         /// after applying all `updates`.
         ///
+        /// Like Git, all shallow commits that aren't present in `objects` are pruned while writing, as a shallow
+        /// boundary makes no sense for commits that don't exist.
         /// If this leaves the list of shallow commits empty, the file is removed.
-        ///
-        /// ### Deviation
-        ///
-        /// Git also prunes the set of shallow commits while writing, we don't until we support some sort of pruning.
         pub fn write(
-            mut file: gix_lock::File,
+            file: gix_lock::File,
             shallow_commits: Option<Commits>,
             updates: &[ShallowUpdate],
+            objects: &impl gix_object::Exists,
         ) -> Result<(), Error> {
             let mut shallow_commits = shallow_commits.map(|sc| (**sc).to_owned()).unwrap_or_default();
             for update in updates {
@@ -36,31 +33,35 @@ pub mod write {
                     ShallowUpdate::Unshallow(id) => shallow_commits.retain(|oid| oid != id),
                 }
             }
-            if shallow_commits.is_empty() {
-                std::fs::remove_file(file.resource_path())?;
-                drop(file);
-                return Ok(());
-            }
+            shallow_commits.retain(|id| objects.exists(id));
+            super::commits(file, shallow_commits)
+        }
+    }
 
-            if shallow_commits.is_empty() {
-                if let Err(err) = std::fs::remove_file(file.resource_path()) {
-                    if err.kind() != std::io::ErrorKind::NotFound {
-                        return Err(err.into());
-                    }
-                }
-            } else {
-                shallow_commits.sort();
-                let mut buf = Vec::<u8>::new();
-                for commit in shallow_commits {
-                    commit.write_hex_to(&mut buf).map_err(Error::Io)?;
-                    buf.push(b'\n');
+    /// Write `shallow_commits` into the shallow `file` in sorted order, or remove the shallow file if there are none.
+    pub(crate) fn commits(mut file: gix_lock::File, mut shallow_commits: Vec<gix_hash::ObjectId>) -> Result<(), Error> {
+        use std::io::Write;
+
+        if shallow_commits.is_empty() {
+            if let Err(err) = std::fs::remove_file(file.resource_path()) {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    return Err(err.into());
                 }
-                file.write_all(&buf).map_err(Error::Io)?;
-                file.flush()?;
             }
-            file.commit()?;
-            Ok(())
+            drop(file);
+            return Ok(());
         }
+
+        shallow_commits.sort();
+        let mut buf = Vec::<u8>::new();
+        for commit in shallow_commits {
+            commit.write_hex_to(&mut buf).map_err(Error::Io)?;
+            buf.push(b'\n');
+        }
+        file.write_all(&buf).map_err(Error::Io)?;
+        file.flush().map_err(Error::Io)?;
+        file.commit()?;
+        Ok(())
     }
 
     /// The error returned by [`write()`][crate::shallow::write()].
@@ -90,3 +91,18 @@ pub mod open {
         DecodeHash(#[from] gix_hash::decode::Error),
     }
 }
+
+///
+pub mod prune {
+    /// The error returned by [`Repository::prune_shallow_commits()`][crate::Repository::prune_shallow_commits()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not lock the shallow file for writing")]
+        Lock(#[from] gix_lock::acquire::Error),
+        #[error(transparent)]
+        Open(#[from] super::open::Error),
+        #[error(transparent)]
+        Write(#[from] super::write::Error),
+    }
+}
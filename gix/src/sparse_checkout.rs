@@ -0,0 +1,322 @@
+use std::collections::BTreeSet;
+
+use crate::bstr::{BStr, BString, ByteSlice};
+
+/// The patterns of a sparse checkout as stored in `info/sparse-checkout`, which determine the paths that are checked out
+/// into the worktree. All other index entries are marked with the `skip-worktree` flag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Patterns {
+    /// Directories whose entire content is checked out, along with the files directly contained in their leading directories
+    /// and the root of the repository, as used if `core.sparseCheckoutCone` is enabled.
+    Cone {
+        /// The directories to check out recursively, without leading or trailing slashes.
+        recursive: BTreeSet<BString>,
+        /// The leading directories of `recursive` ones, of which only the files they directly contain are checked out.
+        parents: BTreeSet<BString>,
+    },
+    /// Patterns in `.gitignore` syntax, where the last pattern that matches a path or one of its leading directories
+    /// decides if it's checked out.
+    NonCone(Vec<gix_glob::Pattern>),
+}
+
+/// Instantiation
+impl Patterns {
+    /// Parse `input`, typically the content of the `info/sparse-checkout` file.
+    ///
+    /// If `cone` is `true`, the patterns are interpreted in cone mode, unless they don't look like the ones written in cone mode
+    /// in which case they are interpreted with `.gitignore` syntax, just like `git` does.
+    pub fn from_bytes(input: &[u8], cone: bool) -> Self {
+        let lines = || {
+            input
+                .lines()
+                .map(ByteSlice::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with(b"#"))
+        };
+        if cone {
+            if let Some(patterns) = Self::parse_cone(lines()) {
+                return patterns;
+            }
+        }
+        Patterns::NonCone(lines().filter_map(gix_glob::parse).collect())
+    }
+
+    /// Create patterns in cone mode which check out `directories` recursively, like `git sparse-checkout set --cone` does.
+    ///
+    /// Leading and trailing slashes are ignored, and the root of the repository is always checked out non-recursively.
+    pub fn from_directories(directories: impl IntoIterator<Item = impl AsRef<BStr>>) -> Self {
+        let mut patterns = Patterns::Cone {
+            recursive: Default::default(),
+            parents: Default::default(),
+        };
+        patterns.extend(directories);
+        patterns
+    }
+
+    fn parse_cone<'a>(lines: impl Iterator<Item = &'a [u8]>) -> Option<Self> {
+        let mut has_root = false;
+        let mut excludes_root_directories = false;
+        let mut directories = BTreeSet::new();
+        let mut parents = BTreeSet::new();
+        for line in lines {
+            match line {
+                b"/*" => has_root = true,
+                b"!/*/" => excludes_root_directories = true,
+                _ => {
+                    if let Some(dir) = line.strip_prefix(b"!/").and_then(|l| l.strip_suffix(b"/*/")) {
+                        parents.insert(dir.as_bstr().to_owned());
+                    } else if let Some(dir) = line.strip_prefix(b"/").and_then(|l| l.strip_suffix(b"/")) {
+                        if dir.is_empty() || dir.contains(&b'*') {
+                            return None;
+                        }
+                        directories.insert(dir.as_bstr().to_owned());
+                    } else {
+                        return None;
+                    }
+                }
+            }
+        }
+        if !(has_root && excludes_root_directories) {
+            return None;
+        }
+        let recursive = directories.difference(&parents).cloned().collect();
+        let mut patterns = Patterns::Cone { recursive, parents };
+        patterns.normalize();
+        Some(patterns)
+    }
+}
+
+/// Modification
+impl Patterns {
+    /// Add `patterns`, which are directories in cone mode, or lines with `.gitignore` syntax otherwise.
+    pub fn extend(&mut self, patterns: impl IntoIterator<Item = impl AsRef<BStr>>) {
+        match self {
+            Patterns::Cone { recursive, .. } => {
+                recursive.extend(
+                    patterns
+                        .into_iter()
+                        .map(|dir| dir.as_ref().trim_with(|c| c == '/').as_bstr().to_owned())
+                        .filter(|dir| !dir.is_empty()),
+                );
+                self.normalize();
+            }
+            Patterns::NonCone(list) => {
+                list.extend(patterns.into_iter().filter_map(|p| gix_glob::parse(p.as_ref())));
+            }
+        }
+    }
+
+    /// Remove recursive directories contained in other recursive directories, and make sure all of their leading directories
+    /// are parents.
+    fn normalize(&mut self) {
+        let Patterns::Cone { recursive, parents } = self else {
+            return;
+        };
+        let has_recursive_ancestor =
+            |recursive: &BTreeSet<BString>, dir: &BStr| leading_directories(dir).any(|d| recursive.contains(d));
+        let covered: Vec<_> = recursive
+            .iter()
+            .filter(|dir| has_recursive_ancestor(recursive, dir.as_bstr()))
+            .cloned()
+            .collect();
+        for dir in covered {
+            recursive.remove(&dir);
+        }
+        parents.extend(
+            recursive
+                .iter()
+                .flat_map(|dir| leading_directories(dir.as_bstr()))
+                .map(ToOwned::to_owned),
+        );
+        parents.retain(|dir| !recursive.contains(dir) && !has_recursive_ancestor(recursive, dir.as_bstr()));
+    }
+}
+
+/// Access
+impl Patterns {
+    /// Return `true` if these patterns are interpreted in cone mode.
+    pub fn is_cone(&self) -> bool {
+        matches!(self, Patterns::Cone { .. })
+    }
+
+    /// Return `true` if the file at the slash-separated `rela_path` is checked out into the worktree.
+    pub fn is_included(&self, rela_path: &BStr) -> bool {
+        match self {
+            Patterns::Cone { recursive, parents } => match rela_path.rfind_byte(b'/') {
+                None => true,
+                Some(pos) => {
+                    let dir = rela_path[..pos].as_bstr();
+                    parents.contains(dir)
+                        || recursive.contains(dir)
+                        || leading_directories(dir).any(|d| recursive.contains(d))
+                }
+            },
+            Patterns::NonCone(patterns) => {
+                let mut path = rela_path;
+                let mut is_dir = false;
+                loop {
+                    let basename_start_pos = path.rfind_byte(b'/').map(|pos| pos + 1);
+                    if let Some(pattern) = patterns.iter().rev().find(|pattern| {
+                        pattern.matches_repo_relative_path(
+                            path,
+                            basename_start_pos,
+                            Some(is_dir),
+                            gix_glob::pattern::Case::Sensitive,
+                            gix_glob::wildmatch::Mode::NO_MATCH_SLASH_LITERAL,
+                        )
+                    }) {
+                        return !pattern.is_negative();
+                    }
+                    match basename_start_pos {
+                        Some(pos) => {
+                            path = path[..pos - 1].as_bstr();
+                            is_dir = true;
+                        }
+                        None => return false,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Serialize these patterns into `out` in the format of the `info/sparse-checkout` file.
+    pub fn write_to(&self, out: &mut dyn std::io::Write) -> std::io::Result<()> {
+        match self {
+            Patterns::Cone { recursive, parents } => {
+                out.write_all(b"/*\n!/*/\n")?;
+                let mut directories: Vec<_> = recursive.iter().chain(parents.iter()).collect();
+                directories.sort();
+                for dir in directories {
+                    if parents.contains(dir) {
+                        writeln!(out, "/{dir}/\n!/{dir}/*/")?;
+                    } else {
+                        writeln!(out, "/{dir}/")?;
+                    }
+                }
+            }
+            Patterns::NonCone(patterns) => {
+                for pattern in patterns {
+                    writeln!(out, "{pattern}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Return all leading directories of `dir`, excluding `dir` itself.
+fn leading_directories(dir: &BStr) -> impl Iterator<Item = &BStr> {
+    dir.find_iter(b"/").map(move |pos| dir[..pos].as_bstr())
+}
+
+/// Set the `skip-worktree` flag on all entries of `index` that aren't included by `patterns`, so they won't be checked out.
+pub(crate) fn mark_excluded_entries(patterns: &Patterns, index: &mut gix_index::State) {
+    for (entry, path) in index.entries_mut_with_paths() {
+        if !patterns.is_included(path) {
+            set_skip_worktree(&mut entry.flags, true);
+        }
+    }
+}
+
+/// Set or clear the `skip-worktree` flag in `flags`, along with the `extended` flag that is needed to persist it.
+pub(crate) fn set_skip_worktree(flags: &mut gix_index::entry::Flags, skip: bool) {
+    use gix_index::entry::Flags;
+
+    flags.set(Flags::SKIP_WORKTREE, skip);
+    let needs_extended = flags.intersects(Flags::SKIP_WORKTREE | Flags::INTENT_TO_ADD);
+    flags.set(Flags::EXTENDED, needs_extended);
+}
+
+/// The outcome of [`Repository::sparse_checkout_reapply()`][crate::Repository::sparse_checkout_reapply()] and related methods.
+#[derive(Debug, Default)]
+pub struct Outcome {
+    /// The amount of files that were removed from the worktree as they are not included by the sparse checkout patterns anymore.
+    pub files_removed: usize,
+    /// Files that aren't included by the sparse checkout patterns anymore, but which were kept in the worktree along with
+    /// their index entries as they were modified.
+    pub kept_modified: Vec<BString>,
+    /// The outcome of checking out the files that are now included by the sparse checkout patterns.
+    pub checkout: gix_worktree_state::checkout::Outcome,
+}
+
+///
+pub mod patterns {
+    /// The error returned by [`Repository::sparse_checkout_patterns()`][crate::Repository::sparse_checkout_patterns()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        ConfigBoolean(#[from] crate::config::boolean::Error),
+        #[error("Could not read the sparse checkout patterns at '{}'", path.display())]
+        Io {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+    }
+}
+
+///
+pub mod update {
+    /// The error returned by [`Repository::sparse_checkout_reapply()`][crate::Repository::sparse_checkout_reapply()]
+    /// and related methods.
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Sparse checkout isn't enabled")]
+        NotEnabled,
+        #[error("A sparse checkout needs a worktree, but the repository at '{}' is bare", git_dir.display())]
+        BareRepository { git_dir: std::path::PathBuf },
+        #[error("The index at '{}' is sparse, which isn't supported yet - expand it with `git sparse-checkout set --no-sparse-index` first", path.display())]
+        SparseIndex { path: std::path::PathBuf },
+        #[error(transparent)]
+        Patterns(#[from] super::patterns::Error),
+        #[error("Could not lock '{}' for writing", path.display())]
+        Lock {
+            path: std::path::PathBuf,
+            source: gix_lock::acquire::Error,
+        },
+        #[error("Could not write '{}'", path.display())]
+        Write {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+        #[error(transparent)]
+        Commit(#[from] gix_lock::commit::Error<gix_lock::File>),
+        #[error(transparent)]
+        LoadConfig(#[from] gix_config::file::init::from_paths::Error),
+        #[error(transparent)]
+        SetConfigOnDisk(#[from] gix_config::file::set_raw_value::Error),
+        #[error(transparent)]
+        SetConfig(#[from] crate::config::set_value::Error),
+        #[error(transparent)]
+        CommitConfig(#[from] crate::config::Error),
+        #[error(transparent)]
+        OpenIndex(#[from] crate::worktree::open_index::Error),
+        #[error(transparent)]
+        WriteIndex(#[from] gix_index::file::write::Error),
+        #[error(transparent)]
+        StatOptions(#[from] crate::config::stat_options::Error),
+        #[error(transparent)]
+        CheckoutOptions(#[from] crate::config::checkout_options::Error),
+        #[error(transparent)]
+        Checkout(#[from] gix_worktree_state::checkout::Error),
+        #[error("Could not fetch the blobs to check out from the promisor remote")]
+        FetchMissingObjects(#[from] gix_odb::store::find::Error),
+        #[error("Could not read '{}' to see if it was modified", path.display())]
+        ReadFile {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+        #[error("Could not remove '{}' from the worktree", path.display())]
+        RemoveFile {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+        #[error("Could not obtain the modification time of '{}'", path.display())]
+        FileTime {
+            path: std::path::PathBuf,
+            source: std::time::SystemTimeError,
+        },
+        #[error("Failed to reopen object database as Arc (only if thread-safety wasn't compiled in)")]
+        OpenArcOdb(#[source] std::io::Error),
+    }
+}
@@ -9,7 +9,7 @@ mod blocking_io {
         bstr::BString,
         config::tree::{Clone, Core, Init, Key},
         remote::{
-            fetch::{Shallow, SpecIndex},
+            fetch::{Filter, Shallow, SpecIndex},
             Direction,
         },
     };
@@ -484,6 +484,136 @@ mod blocking_io {
         assert!(path.is_absolute(), "{path:?} must be absolute");
     }
 
+    #[test]
+    fn partial_clone_fetches_missing_objects_on_demand() -> crate::Result {
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let (repo, out) = gix::prepare_clone_bare(remote::repo_path("base.allow-filter.git").as_path(), tmp.path())?
+            .with_filter(Filter::BlobNone)
+            .fetch_only(gix::progress::Discard, &std::sync::atomic::AtomicBool::default())?;
+
+        let config = repo.config_snapshot();
+        assert_eq!(
+            config.string("extensions.partialClone").expect("set").as_ref(),
+            "origin",
+            "the remote is registered as promisor"
+        );
+        assert_eq!(config.boolean("remote.origin.promisor"), Some(true));
+        assert_eq!(
+            config.string("remote.origin.partialCloneFilter").expect("set").as_ref(),
+            "blob:none"
+        );
+
+        let gix::remote::fetch::Status::Change { write_pack_bundle, .. } = out.status else {
+            unreachable!("a pack was received")
+        };
+        assert!(
+            write_pack_bundle
+                .data_path
+                .expect("written")
+                .with_extension("promisor")
+                .is_file(),
+            "the pack is marked as received from a promisor remote"
+        );
+
+        let tree = repo.head_commit()?.tree()?;
+        let blob_id = tree
+            .iter()
+            .map(|entry| entry.map(|entry| (entry.mode(), entry.oid().to_owned())))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .find_map(|(mode, id)| mode.is_blob().then_some(id))
+            .expect("at least one blob");
+        assert!(!repo.has_object(blob_id), "blobs were omitted");
+        assert_eq!(
+            repo.find_object(blob_id)?.kind,
+            gix::object::Kind::Blob,
+            "it's fetched on demand"
+        );
+        assert!(repo.has_object(blob_id), "and is available locally from now on");
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_into_partial_clone_does_not_fetch_missing_objects_on_demand() -> crate::Result {
+        let server = gix_testtools::scripted_fixture_writable("make_remote_repos.sh")?;
+        let server_repo = gix::open_opts(server.path().join("base.allow-filter.git"), restricted())?;
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let (repo, _out) = gix::prepare_clone_bare(server_repo.path(), tmp.path())?
+            .with_filter(Filter::BlobNone)
+            .fetch_only(gix::progress::Discard, &std::sync::atomic::AtomicBool::default())?;
+        let num_packs = || -> std::io::Result<usize> {
+            Ok(std::fs::read_dir(repo.objects.store_ref().path().join("pack"))?
+                .filter_map(Result::ok)
+                .filter(|entry| entry.path().extension() == Some("pack".as_ref()))
+                .count())
+        };
+        assert_eq!(num_packs()?, 1);
+
+        let head = server_repo.head_commit()?;
+        let new_commit = server_repo.commit("HEAD", "new", head.tree_id()?, Some(head.id))?;
+        repo.find_remote("origin")?
+            .connect(Direction::Fetch)?
+            .prepare_fetch(gix::progress::Discard, Default::default())?
+            .receive(gix::progress::Discard, &AtomicBool::default())?;
+        assert_eq!(
+            num_packs()?,
+            2,
+            "the new commit isn't fetched on demand while negotiating, but only with the fetch itself"
+        );
+        assert_eq!(repo.find_reference("refs/remotes/origin/main")?.id(), new_commit);
+        Ok(())
+    }
+
+    #[test]
+    fn partial_clone_and_checkout() -> crate::Result {
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let (mut checkout, _out) =
+            gix::prepare_clone(remote::repo_path("base.allow-filter.git").as_path(), tmp.path())?
+                .with_filter(Filter::TreeDepth { depth: 0 })
+                .fetch_then_checkout(gix::progress::Discard, &std::sync::atomic::AtomicBool::default())?;
+        let (repo, _) = checkout.main_worktree(gix::progress::Discard, &std::sync::atomic::AtomicBool::default())?;
+
+        let index = repo.index()?;
+        assert_eq!(index.entries().len(), 1, "trees and blobs were fetched as needed");
+        assure_index_entries_on_disk(&index, repo.work_dir().expect("non-bare"));
+        Ok(())
+    }
+
+    #[test]
+    fn clone_from_bundle_and_checkout() -> crate::Result {
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let (mut checkout, out) = gix::prepare_clone(remote::repo_path("base.bundle").as_path(), tmp.path())?
+            .fetch_then_checkout(gix::progress::Discard, &std::sync::atomic::AtomicBool::default())?;
+        let (repo, _) = checkout.main_worktree(gix::progress::Discard, &std::sync::atomic::AtomicBool::default())?;
+
+        let remote_repo = remote::repo("base");
+        assert_eq!(repo.head_id()?, remote_repo.head_id()?, "HEAD is set from the bundle");
+        assert_eq!(
+            repo.head_name()?.expect("not detached").as_bstr(),
+            remote_repo.head_name()?.expect("not detached").as_bstr(),
+            "the branch HEAD points to is inferred from the bundle"
+        );
+        assert_eq!(
+            out.ref_map.mappings.len(),
+            remote_repo.references()?.all()?.count() + 1,
+            "all refs and HEAD are advertised"
+        );
+        for name in ["refs/remotes/origin/main", "refs/remotes/origin/a", "refs/tags/b-tag"] {
+            assert_eq!(
+                repo.find_reference(name)?.peel_to_id_in_place()?,
+                remote_repo
+                    .find_reference(name.replace("remotes/origin", "heads").as_str())?
+                    .peel_to_id_in_place()?,
+                "{name} was created from the bundle"
+            );
+        }
+
+        let index = repo.index()?;
+        assert_eq!(index.entries().len(), 1, "All entries are known as per HEAD tree");
+        assure_index_entries_on_disk(&index, repo.work_dir().expect("non-bare"));
+        Ok(())
+    }
+
     #[test]
     fn fetch_and_checkout() -> crate::Result {
         let tmp = gix_testtools::tempfile::TempDir::new()?;
@@ -504,6 +634,53 @@ mod blocking_io {
         assure_index_entries_on_disk(&index, repo.work_dir().expect("non-bare"));
         Ok(())
     }
+    #[test]
+    fn fetch_and_checkout_sparse() -> crate::Result {
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let mut prepare = gix::clone::PrepareFetch::new(
+            gix_testtools::scripted_fixture_read_only("make_sparse_checkout_repo.sh")?,
+            tmp.path(),
+            gix::create::Kind::WithWorktree,
+            Default::default(),
+            restricted(),
+        )?;
+        let (checkout, _out) = prepare.fetch_then_checkout(gix::progress::Discard, &AtomicBool::default())?;
+        let (repo, _) = checkout
+            .with_sparse_checkout(gix::sparse_checkout::Patterns::from_directories(Some("a/b")))
+            .main_worktree(gix::progress::Discard, &AtomicBool::default())?;
+
+        let workdir = repo.work_dir().expect("non-bare");
+        for (path, is_included) in [
+            ("root", true),
+            ("a/file", true),
+            ("a/b/file", true),
+            ("c/file", false),
+            ("d/file", false),
+        ] {
+            assert_eq!(workdir.join(path).is_file(), is_included, "{path}");
+        }
+        assert!(
+            !workdir.join("c").exists(),
+            "directories of excluded files aren't created"
+        );
+        assert!(
+            repo.sparse_checkout_patterns()?.is_some(),
+            "the sparse checkout remains configured"
+        );
+
+        let out = std::process::Command::new(gix::path::env::exe_invocation())
+            .args(["ls-files", "-t"])
+            .current_dir(workdir)
+            .output()?;
+        assert!(out.status.success());
+        assert_eq!(
+            out.stdout.as_bstr(),
+            "H a/b/file\nH a/file\nS c/file\nS d/file\nH root\n",
+            "git sees excluded entries as skipped"
+        );
+        Ok(())
+    }
+
     #[test]
     fn fetch_and_checkout_specific_ref() -> crate::Result {
         let tmp = gix_testtools::tempfile::TempDir::new()?;
@@ -717,6 +894,425 @@ mod blocking_io {
         }
         Ok(())
     }
+
+    fn git(cwd: &Path, args: &[&str]) -> crate::Result<String> {
+        let out = std::process::Command::new(gix::path::env::exe_invocation())
+            .args(["-c", "user.name=committer", "-c", "user.email=committer@example.com"])
+            .args(args)
+            .current_dir(cwd)
+            .output()?;
+        assert!(out.status.success(), "{args:?} failed: {}", out.stderr.as_bstr());
+        Ok(out.stdout.to_str()?.trim().to_owned())
+    }
+
+    #[test]
+    fn fetch_only_with_bundle_uri() -> crate::Result {
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let (repo, out) = gix::prepare_clone_bare(remote::repo_path("base"), tmp.path())?
+            .with_bundle_uri(gix::path::into_bstr(remote::repo_path("base.bundle")).into_owned())
+            .fetch_only(gix::progress::Discard, &std::sync::atomic::AtomicBool::default())?;
+
+        assert!(
+            matches!(out.status, gix::remote::fetch::Status::NoPackReceived { .. }),
+            "the bundle contains all objects, so there is nothing left to fetch"
+        );
+        let remote_repo = remote::repo("base");
+        for name in ["main", "a"] {
+            assert_eq!(
+                repo.find_reference(format!("refs/bundles/{name}").as_str())?.id(),
+                remote_repo.find_reference(name)?.id(),
+                "branches of the bundle are available for negotiation"
+            );
+            assert_eq!(
+                repo.find_reference(format!("refs/remotes/origin/{name}").as_str())?
+                    .id(),
+                remote_repo.find_reference(name)?.id(),
+                "refs are still set from the remote"
+            );
+        }
+        assert_eq!(
+            repo.config_snapshot().string("fetch.bundleURI"),
+            None,
+            "single bundles aren't remembered"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_only_with_bundle_list_then_fetch_new_bundles_by_creation_token() -> crate::Result {
+        let server = gix_testtools::scripted_fixture_writable("make_basic_repo.sh")?;
+        let bundles = gix_testtools::tempfile::TempDir::new()?;
+        let one_bundle = bundles.path().join("one.bundle");
+        git(
+            server.path(),
+            &[
+                "bundle",
+                "create",
+                "-q",
+                one_bundle.to_str().expect("valid UTF-8"),
+                "main",
+            ],
+        )?;
+        let list = bundles.path().join("list");
+        std::fs::write(
+            &list,
+            "[bundle]\n\tversion = 1\n\tmode = all\n\theuristic = creationToken\n[bundle \"one\"]\n\turi = one.bundle\n\tcreationToken = 1\n",
+        )?;
+
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let (repo, out) = gix::prepare_clone_bare(server.path(), tmp.path())?
+            .with_bundle_uri(gix::path::into_bstr(list.as_path()).into_owned())
+            .fetch_only(gix::progress::Discard, &std::sync::atomic::AtomicBool::default())?;
+        assert!(matches!(out.status, gix::remote::fetch::Status::NoPackReceived { .. }));
+        assert_eq!(
+            repo.find_reference("refs/bundles/main")?.id(),
+            repo.find_reference("refs/remotes/origin/main")?.id()
+        );
+        let repo = gix::open_opts(repo.git_dir(), restricted())?;
+        {
+            let config = repo.config_snapshot();
+            assert_eq!(
+                config.trusted_path("fetch.bundleURI").transpose()?.as_deref(),
+                Some(list.as_path()),
+                "lists with creation tokens are remembered for later fetches"
+            );
+            assert_eq!(config.integer("fetch.bundleCreationToken"), Some(1));
+        }
+
+        let previous_main = git(server.path(), &["rev-parse", "main"])?;
+        git(server.path(), &["commit", "-q", "--allow-empty", "-m", "c3"])?;
+        let main = git(server.path(), &["rev-parse", "main"])?;
+        let two_bundle = bundles.path().join("two.bundle");
+        git(
+            server.path(),
+            &[
+                "bundle",
+                "create",
+                "-q",
+                two_bundle.to_str().expect("valid UTF-8"),
+                "main",
+                &format!("^{previous_main}"),
+            ],
+        )?;
+        std::fs::remove_file(one_bundle)?;
+        let mut list_file = std::fs::OpenOptions::new().append(true).open(&list)?;
+        std::io::Write::write_all(
+            &mut list_file,
+            b"[bundle \"two\"]\n\turi = two.bundle\n\tcreationToken = 2\n",
+        )?;
+
+        let out = repo
+            .find_remote("origin")?
+            .connect(Direction::Fetch)?
+            .prepare_fetch(gix::progress::Discard, Default::default())?
+            .receive(gix::progress::Discard, &std::sync::atomic::AtomicBool::default())?;
+        assert!(
+            matches!(out.status, gix::remote::fetch::Status::NoPackReceived { .. }),
+            "only the new bundle was needed, the one we already had was skipped"
+        );
+        assert_eq!(repo.find_reference("refs/bundles/main")?.id(), hex_to_id(&main));
+        assert_eq!(repo.find_reference("refs/remotes/origin/main")?.id(), hex_to_id(&main));
+        let repo = gix::open_opts(repo.git_dir(), restricted())?;
+        assert_eq!(
+            repo.config_snapshot().integer("fetch.bundleCreationToken"),
+            Some(2),
+            "the greatest token is remembered"
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(any(
+        feature = "blocking-http-transport-reqwest",
+        feature = "blocking-http-transport-curl"
+    ))]
+    fn fetch_only_with_bundle_list_over_http() -> crate::Result {
+        use std::io::{BufRead, Write};
+        use std::sync::atomic::Ordering;
+
+        let bundle = std::fs::read(remote::repo_path("base.bundle"))?;
+        let list = "[bundle]\n\tversion = 1\n\tmode = any\n[bundle \"base\"]\n\turi = ../base.bundle\n";
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let stop = AtomicBool::default();
+        let (res, requests) = std::thread::scope(|scope| {
+            let server = scope.spawn(|| -> std::io::Result<Vec<String>> {
+                let mut requests = Vec::new();
+                loop {
+                    let (stream, _) = listener.accept()?;
+                    if stop.load(Ordering::SeqCst) {
+                        break Ok(requests);
+                    }
+                    let mut request = std::io::BufReader::new(&stream);
+                    let mut line = String::new();
+                    request.read_line(&mut line)?;
+                    let path = line.split(' ').nth(1).unwrap_or_default().to_owned();
+                    line.clear();
+                    while request.read_line(&mut line)? > 2 {
+                        line.clear();
+                    }
+                    let body = if path.ends_with(".bundle") {
+                        bundle.as_slice()
+                    } else {
+                        list.as_bytes()
+                    };
+                    let mut out = &stream;
+                    write!(
+                        out,
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    )?;
+                    out.write_all(body)?;
+                    requests.push(path);
+                }
+            });
+            let res = gix::prepare_clone_bare(remote::repo_path("base"), tmp.path()).map(|prepare| {
+                prepare
+                    .with_bundle_uri(format!("http://{addr}/lists/all"))
+                    .fetch_only(gix::progress::Discard, &AtomicBool::default())
+            });
+            stop.store(true, Ordering::SeqCst);
+            std::net::TcpStream::connect(addr)?;
+            Ok::<_, std::io::Error>((res, server.join().expect("no panic")?))
+        })?;
+        let (repo, out) = res??;
+
+        assert_eq!(requests, ["/lists/all", "/base.bundle"]);
+        assert!(matches!(out.status, gix::remote::fetch::Status::NoPackReceived { .. }));
+        assert_eq!(
+            repo.find_reference("refs/bundles/main")?.id(),
+            remote::repo("base").find_reference("main")?.id()
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(any(
+        feature = "blocking-http-transport-reqwest",
+        feature = "blocking-http-transport-curl"
+    ))]
+    fn fetch_only_from_dumb_http_server() -> crate::Result {
+        let (server, loose_commit) = dumb_http_remote()?;
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let (repo, out) = serve_dumb_http(server.path(), |addr| {
+            gix::prepare_clone_bare(format!("http://{addr}/remote.git"), tmp.path())
+                .map(|mut prepare| prepare.fetch_only(gix::progress::Discard, &AtomicBool::default()))
+        })???;
+
+        let gix::remote::fetch::Status::DumbTransfer {
+            packs,
+            num_loose_objects,
+            update_refs,
+        } = out.status
+        else {
+            panic!("a dumb server only serves files, so objects are downloaded one by one or as pack");
+        };
+        assert_eq!(num_loose_objects, 1, "only the new commit is loose");
+        assert_eq!(packs.len(), 1, "the pack of the clone contains all other objects");
+        assert!(
+            packs[0].keep_path.is_none(),
+            "keep files are removed as refs were written"
+        );
+        assert!(!update_refs.edits.is_empty());
+
+        assert_eq!(repo.find_reference("refs/heads/main")?.id().to_string(), loose_commit);
+        assert_eq!(
+            repo.head_name()?.expect("not detached").as_bstr(),
+            "refs/heads/main",
+            "the HEAD file of the remote is used to learn about the default branch"
+        );
+        git(repo.git_dir(), &["fsck", "--connectivity-only", "--no-dangling"])?;
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(any(
+        feature = "blocking-http-transport-reqwest",
+        feature = "blocking-http-transport-curl"
+    ))]
+    fn fetch_only_from_dumb_http_server_rejects_loose_objects_with_wrong_size() -> crate::Result {
+        use std::io::Write;
+
+        let (server, loose_commit) = dumb_http_remote()?;
+        let remote_dir = server.path().join("remote.git");
+        let commit = git(&remote_dir, &["cat-file", "commit", &loose_commit])?;
+        let loose_path = remote_dir
+            .join("objects")
+            .join(&loose_commit[..2])
+            .join(&loose_commit[2..]);
+        let actual_size = commit.len() + 1;
+        for (size, expected) in [
+            (u64::MAX >> 1, "expected"),
+            (actual_size as u64 - 1, "exceeds"),
+            (actual_size as u64 + 1, "expected"),
+        ] {
+            let mut object = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            writeln!(object, "commit {size}\0{commit}")?;
+            std::fs::write(&loose_path, object.finish()?)?;
+
+            let tmp = gix_testtools::tempfile::TempDir::new()?;
+            let err = serve_dumb_http(server.path(), |addr| {
+                gix::prepare_clone_bare(format!("http://{addr}/remote.git"), tmp.path())
+                    .map(|mut prepare| prepare.fetch_only(gix::progress::Discard, &AtomicBool::default()))
+            })??
+            .expect_err("the size in the header doesn't match the data");
+            let gix::clone::fetch::Error::Fetch(gix::remote::fetch::Error::DumbDownload { source, .. }) = err else {
+                panic!("unexpected error: {err:?}")
+            };
+            assert!(source.to_string().contains(expected), "{size}: {source}");
+        }
+        Ok(())
+    }
+
+    /// Create a bare clone of the `base` repository named `remote.git` in a new directory, with a commit on top of `main`
+    /// that is only available as loose object. Return the directory along with the id of this commit.
+    #[cfg(any(
+        feature = "blocking-http-transport-reqwest",
+        feature = "blocking-http-transport-curl"
+    ))]
+    fn dumb_http_remote() -> crate::Result<(gix_testtools::tempfile::TempDir, String)> {
+        let server = gix_testtools::tempfile::TempDir::new()?;
+        let remote_dir = server.path().join("remote.git");
+        let base = gix::path::realpath(remote::repo_path("base"))?;
+        git(
+            server.path(),
+            &[
+                "clone",
+                "--bare",
+                "--no-local",
+                "-q",
+                base.to_str().expect("valid UTF-8"),
+                "remote.git",
+            ],
+        )?;
+        // Add a commit that is only available as loose object, next to the pack that was received.
+        let loose_commit = git(
+            &remote_dir,
+            &["commit-tree", "-p", "main", "-m", "loose", "main^{tree}"],
+        )?;
+        git(&remote_dir, &["update-ref", "refs/heads/main", &loose_commit])?;
+        git(&remote_dir, &["update-server-info"])?;
+        Ok((server, loose_commit))
+    }
+
+    /// Serve the files in `root` like a dumb HTTP server would while calling `f` with its address.
+    #[cfg(any(
+        feature = "blocking-http-transport-reqwest",
+        feature = "blocking-http-transport-curl"
+    ))]
+    fn serve_dumb_http<T>(root: &Path, f: impl FnOnce(std::net::SocketAddr) -> T) -> std::io::Result<T> {
+        use std::io::{BufRead, Write};
+        use std::sync::atomic::Ordering;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let stop = AtomicBool::default();
+        std::thread::scope(|scope| {
+            let handle = scope.spawn(|| -> std::io::Result<()> {
+                loop {
+                    let (stream, _) = listener.accept()?;
+                    if stop.load(Ordering::SeqCst) {
+                        break Ok(());
+                    }
+                    let mut request = std::io::BufReader::new(&stream);
+                    let mut line = String::new();
+                    request.read_line(&mut line)?;
+                    let path = line.split(' ').nth(1).unwrap_or_default().to_owned();
+                    line.clear();
+                    while request.read_line(&mut line)? > 2 {
+                        line.clear();
+                    }
+                    let path = path.split('?').next().unwrap_or_default().trim_start_matches('/');
+                    let mut out = &stream;
+                    match std::fs::read(root.join(path)) {
+                        Ok(body) => {
+                            write!(
+                                out,
+                                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                                body.len()
+                            )?;
+                            out.write_all(&body)?;
+                        }
+                        Err(_) => write!(
+                            out,
+                            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        )?,
+                    }
+                }
+            });
+            let res = f(addr);
+            stop.store(true, Ordering::SeqCst);
+            std::net::TcpStream::connect(addr)?;
+            handle.join().expect("no panic")?;
+            Ok(res)
+        })
+    }
+
+    #[test]
+    #[cfg(feature = "blocking-network-server")]
+    fn fetch_only_with_bundles_advertised_by_the_remote() -> crate::Result {
+        use std::io::Write;
+        use std::sync::atomic::Ordering;
+
+        let server = gix_testtools::scripted_fixture_writable("make_basic_repo.sh")?;
+        let bundles = gix_testtools::tempfile::TempDir::new()?;
+        let bundle = bundles.path().join("main.bundle");
+        git(
+            server.path(),
+            &["bundle", "create", "-q", bundle.to_str().expect("valid UTF-8"), "main"],
+        )?;
+        writeln!(
+            std::fs::OpenOptions::new()
+                .append(true)
+                .open(server.path().join(".git").join("config"))?,
+            "[uploadpack]\n\tadvertiseBundleURIs = true\n[bundle]\n\tversion = 1\n\tmode = all\n[bundle \"main\"]\n\turi = {}",
+            bundle.display()
+        )?;
+
+        let daemon = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("git://{}/.git", daemon.local_addr()?);
+        let options = gix::daemon::Options {
+            base_path: Some(server.path().into()),
+            export_all: true,
+            ..Default::default()
+        };
+        let stop_daemon = AtomicBool::default();
+        let clones = std::thread::scope(|scope| {
+            let daemon = scope.spawn(|| {
+                gix::daemon::serve(&daemon, &options, &stop_daemon, &|_peer, err| {
+                    panic!("the daemon doesn't fail: {err}")
+                })
+            });
+            let clones = [false, true].map(|use_bundles| -> crate::Result<_> {
+                let tmp = gix_testtools::tempfile::TempDir::new()?;
+                let (repo, out) = gix::prepare_clone_bare(url.as_str(), tmp.path())?
+                    .with_in_memory_config_overrides([
+                        "protocol.version=2".to_owned(),
+                        format!("transfer.bundleURI={use_bundles}"),
+                    ])
+                    .fetch_only(gix::progress::Discard, &AtomicBool::default())?;
+                Ok((tmp, repo, out))
+            });
+            stop_daemon.store(true, Ordering::SeqCst);
+            daemon.join().expect("no panic")?;
+            Ok::<_, std::io::Error>(clones)
+        })?;
+
+        for (use_bundles, clone) in [false, true].into_iter().zip(clones) {
+            let (_tmp, repo, out) = clone?;
+            assert_eq!(
+                repo.try_find_reference("refs/bundles/main")?.is_some(),
+                use_bundles,
+                "bundles are only used if `transfer.bundleURI` is enabled"
+            );
+            assert_eq!(
+                matches!(out.status, gix::remote::fetch::Status::NoPackReceived { .. }),
+                use_bundles
+            );
+        }
+        Ok(())
+    }
 }
 
 #[test]
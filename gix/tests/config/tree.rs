@@ -678,6 +678,67 @@ mod extensions {
         assert!(Extensions::OBJECT_FORMAT.validate("invalid".into()).is_err());
         Ok(())
     }
+
+    #[test]
+    fn ref_storage() -> crate::Result {
+        assert_eq!(
+            Extensions::REF_STORAGE.try_into_ref_storage(bcow("files"))?,
+            gix::refs::store::RefStorage::Files
+        );
+        assert_eq!(
+            Extensions::REF_STORAGE.try_into_ref_storage(bcow("reftable"))?,
+            gix::refs::store::RefStorage::Reftable
+        );
+        assert_eq!(
+            Extensions::REF_STORAGE
+                .try_into_ref_storage(bcow("invalid"))
+                .unwrap_err()
+                .to_string(),
+            "The key \"extensions.refStorage=invalid\" was invalid"
+        );
+        assert!(Extensions::REF_STORAGE.validate("reftable".into()).is_ok());
+        assert!(Extensions::REF_STORAGE.validate("invalid".into()).is_err());
+        Ok(())
+    }
+}
+
+mod gc {
+    use std::time::{Duration, SystemTime};
+
+    use gix::config::tree::{Gc, Key};
+
+    use crate::config::tree::bcow;
+
+    #[test]
+    fn reflog_expire() -> crate::Result {
+        const DAY: u64 = 24 * 60 * 60;
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(100 * DAY);
+        for key in [&Gc::REFLOG_EXPIRE, &Gc::REFLOG_EXPIRE_UNREACHABLE] {
+            assert_eq!(
+                key.try_into_expiry_date(bcow("90.days.ago"), now)?,
+                Some(10 * DAY as i64)
+            );
+            assert_eq!(
+                key.try_into_expiry_date(bcow("2 weeks"), now)?,
+                Some(86 * DAY as i64),
+                "'ago' is implied"
+            );
+            assert_eq!(key.try_into_expiry_date(bcow("never"), now)?, None);
+            assert_eq!(key.try_into_expiry_date(bcow("false"), now)?, None);
+            assert_eq!(key.try_into_expiry_date(bcow("now"), now)?, Some(i64::MAX));
+            assert_eq!(key.try_into_expiry_date(bcow("all"), now)?, Some(i64::MAX));
+            assert!(key.validate("30.days.ago".into()).is_ok());
+            assert!(key.validate("invalid".into()).is_err());
+        }
+        assert_eq!(
+            Gc::REFLOG_EXPIRE
+                .try_into_expiry_date(bcow("invalid"), now)
+                .unwrap_err()
+                .to_string(),
+            "The key \"gc.reflogExpire=invalid\" was invalid"
+        );
+        Ok(())
+    }
 }
 
 mod checkout {
@@ -891,7 +952,8 @@ mod gitoxide {
 
 #[cfg(any(
     feature = "blocking-http-transport-reqwest",
-    feature = "blocking-http-transport-curl"
+    feature = "blocking-http-transport-curl",
+    feature = "async-http-transport-reqwest"
 ))]
 mod http {
     use std::borrow::Cow;
@@ -1069,6 +1131,46 @@ mod remote {
         Ok(())
     }
 
+    #[test]
+    #[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
+    fn partial_clone_filter() -> crate::Result {
+        for (spec, expected) in [
+            ("blob:none", remote::fetch::Filter::BlobNone),
+            ("blob:limit=42", remote::fetch::Filter::BlobLimit { bytes: 42 }),
+            ("blob:limit=2k", remote::fetch::Filter::BlobLimit { bytes: 2 * 1024 }),
+            ("blob:limit=1M", remote::fetch::Filter::BlobLimit { bytes: 1024 * 1024 }),
+            ("tree:0", remote::fetch::Filter::TreeDepth { depth: 0 }),
+            ("tree:3", remote::fetch::Filter::TreeDepth { depth: 3 }),
+        ] {
+            let filter = Remote::PARTIAL_CLONE_FILTER.try_into_filter(bcow(spec))?;
+            assert_eq!(filter, expected);
+            assert!(Remote::PARTIAL_CLONE_FILTER.validate(spec.into()).is_ok());
+        }
+        assert_eq!(
+            remote::fetch::Filter::BlobLimit { bytes: 2048 }.to_string(),
+            "blob:limit=2048",
+            "units aren't reproduced"
+        );
+
+        for invalid in [
+            "blob:limit=",
+            "blob:limit=1x",
+            "tree:1k",
+            "sparse:oid=HEAD",
+            "combine:blob:none+tree:0",
+        ] {
+            assert!(Remote::PARTIAL_CLONE_FILTER.validate(invalid.into()).is_err());
+        }
+        assert_eq!(
+            Remote::PARTIAL_CLONE_FILTER
+                .try_into_filter(bcow("blob:some"))
+                .unwrap_err()
+                .to_string(),
+            "The key \"remote.<name>.partialCloneFilter=blob:some\" was invalid"
+        );
+        Ok(())
+    }
+
     #[test]
     fn url_and_push_url() {
         assert!(Remote::URL.try_into_url(bcow("http://example.org")).is_ok());
@@ -0,0 +1,136 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use gix::daemon::{serve_connection, Options};
+
+#[cfg(feature = "blocking-network-client")]
+/// Run a daemon with `options` on a free port on the loopback interface while `f` is called with its base URL.
+fn with_daemon<T>(options: &Options, f: impl FnOnce(&str) -> T) -> crate::Result<T> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("git://{}", listener.local_addr()?);
+    let should_interrupt = AtomicBool::default();
+    let errors = std::sync::Mutex::new(Vec::new());
+    let res = std::thread::scope(|scope| {
+        let daemon = scope.spawn(|| {
+            gix::daemon::serve(&listener, options, &should_interrupt, &|_peer, err| {
+                errors.lock().expect("not poisoned").push(err.to_string());
+            })
+        });
+        let res = f(&url);
+        should_interrupt.store(true, Ordering::SeqCst);
+        daemon.join().expect("no panic")?;
+        Ok::<_, std::io::Error>(res)
+    })?;
+    let errors = errors.into_inner()?;
+    assert!(errors.is_empty(), "no connection failed unexpectedly: {errors:?}");
+    Ok(res)
+}
+
+/// Send `request` to a daemon configured with `options` and return its response along with the outcome.
+fn response_to(request: &str, options: &Options) -> (Vec<u8>, Result<(), serve_connection::Error>) {
+    let request = format!("{:04x}{request}", request.len() + 4);
+    let mut out = Vec::new();
+    let res = serve_connection(&mut request.as_bytes(), &mut out, options, &AtomicBool::default());
+    (out, res)
+}
+
+#[cfg(feature = "blocking-network-client")]
+#[test]
+fn clone_with_each_protocol_version() -> crate::Result {
+    use gix::remote::fetch::Status;
+
+    let options = Options {
+        base_path: Some(crate::remote::repo_path("")),
+        export_all: true,
+        ..Default::default()
+    };
+    with_daemon(&options, |url| -> crate::Result {
+        for version in ["0", "1", "2"] {
+            let tmp = gix_testtools::tempfile::tempdir()?;
+            let (repo, outcome) = gix::prepare_clone_bare(format!("{url}/base"), tmp.path())?
+                .with_in_memory_config_overrides(Some(format!("protocol.version={version}")))
+                .fetch_only(gix::progress::Discard, &AtomicBool::default())?;
+            assert!(matches!(outcome.status, Status::Change { .. }), "{version}");
+            let server = crate::remote::repo("base");
+            assert_eq!(
+                repo.find_reference("refs/remotes/origin/main")?.id(),
+                server.find_reference("main")?.id(),
+                "{version}"
+            );
+        }
+        Ok(())
+    })?
+}
+
+#[test]
+fn only_exported_repositories_are_served() -> crate::Result {
+    let tmp = gix_testtools::scripted_fixture_writable("make_basic_repo.sh")?;
+    std::fs::write(tmp.path().join("bare.git").join("git-daemon-export-ok"), b"")?;
+    let options = Options {
+        base_path: Some(tmp.path().to_owned()),
+        ..Default::default()
+    };
+    for path in ["/bare.git", "/bare"] {
+        let (out, res) = response_to(&format!("git-upload-pack {path}\0"), &options);
+        res?;
+        assert!(out.ends_with(b"0000"), "{path}: it's a valid advertisement");
+    }
+
+    for path in [
+        "/",
+        "/bare-repo-with-index.git",
+        "/does-not-exist",
+        "/bare.git/../bare.git",
+        "bare.git",
+    ] {
+        let (out, res) = response_to(&format!("git-upload-pack {path}\0"), &options);
+        assert!(
+            matches!(res, Err(serve_connection::Error::AccessDenied { .. })),
+            "{path}: {res:?}"
+        );
+        assert_eq!(
+            out.as_slice(),
+            format!(
+                "{:04x}ERR access denied or repository not exported: {path}",
+                path.len() + 50
+            )
+            .as_bytes(),
+            "{path}: the client is told why it didn't get anything"
+        );
+    }
+
+    let options = Options {
+        export_all: true,
+        allowed_directories: vec![tmp.path().join("bare-repo-with-index.git")],
+        ..options
+    };
+    let (_out, res) = response_to("git-upload-pack /bare-repo-with-index.git\0", &options);
+    res?;
+    let (_out, res) = response_to("git-upload-pack /bare.git\0", &options);
+    assert!(
+        matches!(res, Err(serve_connection::Error::AccessDenied { .. })),
+        "everything outside of allowed directories is denied"
+    );
+    Ok(())
+}
+
+#[test]
+fn receive_pack_must_be_enabled() -> crate::Result {
+    let tmp = gix_testtools::scripted_fixture_writable("make_basic_repo.sh")?;
+    let mut options = Options {
+        base_path: Some(tmp.path().to_owned()),
+        export_all: true,
+        ..Default::default()
+    };
+    let (out, res) = response_to("git-receive-pack /bare.git\0", &options);
+    assert!(matches!(res, Err(serve_connection::Error::ServiceNotEnabled { .. })));
+    assert!(out.ends_with(b"ERR service not enabled: /bare.git"));
+
+    options.enable_receive_pack = true;
+    let (out, res) = response_to("git-receive-pack /bare.git\0", &options);
+    res?;
+    assert!(
+        out.windows(b"report-status".len()).any(|w| w == b"report-status"),
+        "receive-pack capabilities are advertised"
+    );
+    Ok(())
+}
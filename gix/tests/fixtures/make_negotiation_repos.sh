@@ -0,0 +1,37 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+# Create a commit with the empty tree and the given `parents` at `time`, and print its id.
+function commit_at() {
+  local time=${1:?time} message=${2:?message}
+  shift 2
+  GIT_AUTHOR_DATE="@$time +0000" GIT_COMMITTER_DATE="@$time +0000" git commit-tree -m "$message" "$@" "$(git mktree </dev/null)"
+}
+
+git init -q --bare server
+(cd server
+  git update-ref refs/heads/main "$(commit_at 1000 base)"
+)
+
+git clone -q --bare server client
+(cd client
+  # The oldest commits are unrelated to the server, so they are sent last and only if the server doesn't stop negotiation early.
+  tip=$(commit_at 1 "unrelated 1")
+  for i in $(seq 2 100); do
+    tip=$(commit_at "$i" "unrelated $i" -p "$tip")
+  done
+  git update-ref refs/heads/unrelated "$tip"
+
+  # The newest commits aren't known to the server either, and are sent in the first rounds before `base`.
+  tip=$(git rev-parse main)
+  for i in $(seq 20); do
+    tip=$(commit_at "$((1000 + i))" "local $i" -p "$tip")
+  done
+  git update-ref refs/heads/local "$tip"
+  git symbolic-ref HEAD refs/heads/local
+  git update-ref -d refs/heads/main
+)
+
+(cd server
+  git update-ref refs/heads/main "$(commit_at 2000 new -p main)"
+)
@@ -0,0 +1,35 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+
+mkdir dir
+echo 1 > dir/f
+echo 1 > other
+git add . && git commit -q -m c1
+
+echo 2 > other
+git commit -q -am c2-other
+
+echo 2 > dir/f
+git commit -q -am c3-dir
+
+git checkout -q -b side HEAD~1
+echo side > dir/g
+git add . && git commit -q -m c4-side-dir
+echo 3 > other
+git commit -q -am c5-side-other
+
+git checkout -q main
+git merge -q --no-edit -m merge-both side
+
+git checkout -q -b side2 HEAD~1
+echo x > other2
+git add . && git commit -q -m c6-side2-other
+git checkout -q main
+git merge -q --no-edit -m merge-same-as-first side2
+
+echo 4 > dir/f
+git commit -q -am c7-dir
+
+git commit-graph write --no-progress --reachable --changed-paths
@@ -110,6 +110,14 @@ git init base
 
 git clone --shared --depth 2 file://$PWD/base base.shallow
 
+git clone --bare --shared base base.allow-filter.git
+(cd base.allow-filter.git
+  git config uploadpack.allowFilter true
+  git config uploadpack.allowAnySHA1InWant true
+)
+
+git -C base bundle create ../base.bundle --all
+
 
 git clone --shared base clone
 (cd clone
@@ -0,0 +1,12 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+
+mkdir -p a/b c d
+for file in root a/file a/b/file c/file d/file; do
+  echo "$file" > "$file"
+done
+
+git add .
+git commit -q -m init
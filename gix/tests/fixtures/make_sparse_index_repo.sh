@@ -0,0 +1,14 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+
+mkdir -p a/b c d
+for file in root a/file a/b/file c/file d/file; do
+  echo "$file" > "$file"
+done
+
+git add .
+git commit -q -m init
+
+git sparse-checkout set --cone --sparse-index c
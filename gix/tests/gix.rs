@@ -5,9 +5,13 @@ use util::*;
 mod clone;
 mod commit;
 mod config;
+#[cfg(feature = "blocking-network-server")]
+mod daemon;
 #[cfg(feature = "blob-diff")]
 mod diff;
 mod head;
+#[cfg(feature = "blocking-network-server")]
+mod http_backend;
 mod id;
 mod init;
 mod object;
@@ -0,0 +1,316 @@
+use std::{io::Write, sync::atomic::AtomicBool};
+
+use gix::http_backend::{respond, serve_connection, Options, Request};
+
+/// Encode `lines` as packet lines, with `None` representing a delimiter packet, followed by a flush packet.
+fn packet_lines(lines: &[Option<&str>]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for line in lines {
+        match line {
+            Some(line) => out.extend_from_slice(format!("{:04x}{line}", line.len() + 4).as_bytes()),
+            None => out.extend_from_slice(b"0001"),
+        }
+    }
+    out.extend_from_slice(b"0000");
+    out
+}
+
+/// Respond to `request` for `repo` and return the status, the content type and the body of the response.
+fn response(repo: &gix::Repository, request: Request<'_>, mut body: &[u8]) -> crate::Result<(u16, String, Vec<u8>)> {
+    let response = respond(repo, &request)?;
+    let status = response.status;
+    let content_type = response
+        .headers
+        .iter()
+        .find_map(|(name, value)| (*name == "Content-Type").then(|| value.clone()))
+        .expect("always set");
+    let mut out = Vec::new();
+    response.write_body(&mut body, &mut out, &AtomicBool::default())?;
+    Ok((status, content_type, out))
+}
+
+fn get_info_refs(service: &str) -> String {
+    format!("service={service}")
+}
+
+#[test]
+fn advertisement_with_each_protocol_version() -> crate::Result {
+    let repo = crate::remote::repo("base");
+    let query = get_info_refs("git-upload-pack");
+    let request = Request {
+        method: "GET",
+        path: "/info/refs",
+        query: Some(&query),
+        ..Default::default()
+    };
+    for git_protocol in [None, Some("version=1")] {
+        let (status, content_type, body) = response(
+            &repo,
+            Request {
+                git_protocol,
+                ..request
+            },
+            b"",
+        )?;
+        assert_eq!(status, 200);
+        assert_eq!(content_type, "application/x-git-upload-pack-advertisement");
+        assert!(
+            body.starts_with(b"001e# service=git-upload-pack\n0000"),
+            "{git_protocol:?}: the service is announced first"
+        );
+        assert!(body.ends_with(b"0000"));
+    }
+
+    let (status, _, body) = response(
+        &repo,
+        Request {
+            git_protocol: Some("version=2:object-format=sha1"),
+            ..request
+        },
+        b"",
+    )?;
+    assert_eq!(status, 200);
+    assert!(body.starts_with(b"000eversion 2\n"), "V2 doesn't announce the service");
+    Ok(())
+}
+
+#[test]
+fn rpc_with_optionally_compressed_request() -> crate::Result {
+    let repo = crate::remote::repo("base");
+    let request = packet_lines(&[Some("command=ls-refs\n"), None, Some("ref-prefix refs/heads/main\n")]);
+    let mut compressed = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    compressed.write_all(&request)?;
+    let compressed = compressed.finish()?;
+
+    let main = repo.find_reference("main")?.id().to_string();
+    for (content_encoding, body) in [(None, &request), (Some("gzip"), &compressed)] {
+        let (status, content_type, body) = response(
+            &repo,
+            Request {
+                method: "POST",
+                path: "git-upload-pack",
+                content_type: Some("application/x-git-upload-pack-request"),
+                content_encoding,
+                git_protocol: Some("version=2"),
+                ..Default::default()
+            },
+            body,
+        )?;
+        assert_eq!(status, 200);
+        assert_eq!(content_type, "application/x-git-upload-pack-result");
+        assert_eq!(
+            body,
+            packet_lines(&[Some(&format!("{main} refs/heads/main\n"))]),
+            "{content_encoding:?}"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn invalid_requests() -> crate::Result {
+    let repo = crate::remote::repo("base");
+    let upload_pack = get_info_refs("git-upload-pack");
+    let receive_pack = get_info_refs("git-receive-pack");
+    let post_upload_pack = Request {
+        method: "POST",
+        path: "git-upload-pack",
+        content_type: Some("application/x-git-upload-pack-request"),
+        ..Default::default()
+    };
+    for (request, expected_status, expected_body) in [
+        (
+            Request {
+                method: "GET",
+                path: "info/refs",
+                ..Default::default()
+            },
+            403,
+            "Only the smart HTTP protocol is supported\n",
+        ),
+        (
+            Request {
+                method: "GET",
+                path: "objects/info/packs",
+                ..Default::default()
+            },
+            404,
+            "Request not supported\n",
+        ),
+        (
+            Request {
+                method: "POST",
+                path: "info/refs",
+                query: Some(&upload_pack),
+                ..Default::default()
+            },
+            405,
+            "Method not allowed\n",
+        ),
+        (
+            Request {
+                content_type: Some("text/plain"),
+                ..post_upload_pack
+            },
+            415,
+            "Expected content type 'application/x-git-upload-pack-request'\n",
+        ),
+        (
+            Request {
+                content_encoding: Some("br"),
+                ..post_upload_pack
+            },
+            415,
+            "Unsupported content encoding 'br'\n",
+        ),
+        (
+            Request {
+                method: "GET",
+                path: "info/refs",
+                query: Some(&receive_pack),
+                ..Default::default()
+            },
+            403,
+            "Service not enabled: 'git-receive-pack'\n",
+        ),
+    ] {
+        let (status, content_type, body) = response(&repo, request, b"")?;
+        assert_eq!(status, expected_status, "{request:?}");
+        assert_eq!(content_type, "text/plain; charset=utf-8");
+        assert_eq!(body.as_slice(), expected_body.as_bytes());
+    }
+
+    let (status, content_type, body) = response(
+        &repo,
+        Request {
+            method: "GET",
+            path: "info/refs",
+            query: Some(&receive_pack),
+            remote_user: Some("user"),
+            ..Default::default()
+        },
+        b"",
+    )?;
+    assert_eq!(status, 200, "authenticated users may push by default");
+    assert_eq!(content_type, "application/x-git-receive-pack-advertisement");
+    assert!(body.starts_with(b"001f# service=git-receive-pack\n0000"));
+    Ok(())
+}
+
+#[test]
+fn push_with_chunked_request_body_to_reference_server() -> crate::Result {
+    let tmp = gix_testtools::scripted_fixture_writable("make_basic_repo.sh")?;
+    let repo = gix::open_opts(tmp.path(), crate::restricted())?;
+    let id = repo.head_id()?.detach();
+    repo.reference(
+        "refs/heads/topic",
+        id,
+        gix::refs::transaction::PreviousValue::MustNotExist,
+        "",
+    )?;
+    let options = Options {
+        base_path: tmp.path().to_owned(),
+        export_all: true,
+    };
+
+    let request_for_repo = |body: &[u8]| {
+        let mut request = b"POST /.git/git-receive-pack HTTP/1.1\r\nHost: localhost\r\n\
+            Content-Type: application/x-git-receive-pack-request\r\nTransfer-Encoding: chunked\r\n\r\n"
+            .to_vec();
+        let (first, second) = body.split_at(body.len() / 2);
+        for chunk in [first, second] {
+            request.extend_from_slice(format!("{:x};ext=1\r\n", chunk.len()).as_bytes());
+            request.extend_from_slice(chunk);
+            request.extend_from_slice(b"\r\n");
+        }
+        request.extend_from_slice(b"0\r\nTrailer: ignored\r\n\r\n");
+        request
+    };
+    let body = packet_lines(&[Some(&format!(
+        "{id} {} refs/heads/topic\0report-status",
+        repo.object_hash().null()
+    ))]);
+
+    let mut out = Vec::new();
+    serve_connection(
+        &mut request_for_repo(&body).as_slice(),
+        &mut out,
+        &options,
+        &AtomicBool::default(),
+    )?;
+    assert!(
+        out.starts_with(b"HTTP/1.1 403 Forbidden\r\n"),
+        "anonymous users can't push by default"
+    );
+    assert!(repo.try_find_reference("refs/heads/topic")?.is_some());
+
+    let config_path = repo.git_dir().join("config");
+    let mut config = std::fs::read(&config_path)?;
+    config.extend_from_slice(b"[http]\n\treceivepack = true\n");
+    std::fs::write(&config_path, config)?;
+    let mut out = Vec::new();
+    serve_connection(
+        &mut request_for_repo(&body).as_slice(),
+        &mut out,
+        &options,
+        &AtomicBool::default(),
+    )?;
+    let out = gix::bstr::BString::from(out);
+    assert!(out.starts_with(b"HTTP/1.1 200 OK\r\n"), "{out}");
+    assert!(
+        out.ends_with(b"\r\n\r\n000eunpack ok\n0018ok refs/heads/topic\n0000"),
+        "{out}"
+    );
+    assert!(repo.try_find_reference("refs/heads/topic")?.is_none());
+
+    let mut out = Vec::new();
+    serve_connection(
+        &mut &b"GET /does-not-exist/info/refs?service=git-upload-pack HTTP/1.1\r\n\r\n"[..],
+        &mut out,
+        &options,
+        &AtomicBool::default(),
+    )?;
+    assert!(out.starts_with(b"HTTP/1.1 404 Not Found\r\n"));
+    Ok(())
+}
+
+#[cfg(any(
+    feature = "blocking-http-transport-reqwest",
+    feature = "blocking-http-transport-curl"
+))]
+#[test]
+fn clone_from_reference_server_with_each_protocol_version() -> crate::Result {
+    use std::sync::atomic::Ordering;
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}/base", listener.local_addr()?);
+    let options = Options {
+        base_path: crate::remote::repo_path(""),
+        export_all: true,
+    };
+    let should_interrupt = AtomicBool::default();
+    std::thread::scope(|scope| -> crate::Result {
+        let server = scope.spawn(|| {
+            gix::http_backend::serve(&listener, &options, &should_interrupt, &|_peer, err| {
+                panic!("no request fails: {err}")
+            })
+        });
+        let res = (|| -> crate::Result {
+            for version in ["1", "2"] {
+                let tmp = gix_testtools::tempfile::tempdir()?;
+                let (repo, _outcome) = gix::prepare_clone_bare(url.as_str(), tmp.path())?
+                    .with_in_memory_config_overrides(Some(format!("protocol.version={version}")))
+                    .fetch_only(gix::progress::Discard, &AtomicBool::default())?;
+                assert_eq!(
+                    repo.find_reference("refs/remotes/origin/main")?.id(),
+                    crate::remote::repo("base").find_reference("main")?.id(),
+                    "{version}"
+                );
+            }
+            Ok(())
+        })();
+        should_interrupt.store(true, Ordering::SeqCst);
+        server.join().expect("no panic")?;
+        res
+    })
+}
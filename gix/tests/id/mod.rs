@@ -185,4 +185,169 @@ mod ancestors {
         }
         Ok(())
     }
+
+    fn summaries_of_commits_changing(
+        repo: &gix::Repository,
+        paths: &[&str],
+        use_commit_graph: bool,
+        first_parent_only: bool,
+    ) -> crate::Result<Vec<String>> {
+        let mut walk = repo
+            .head_id()?
+            .ancestors()
+            .use_commit_graph(use_commit_graph)
+            .with_paths(paths.iter().copied());
+        if first_parent_only {
+            walk = walk.first_parent_only();
+        }
+        let mut summaries = walk
+            .all()?
+            .map(|info| -> crate::Result<_> { Ok(info?.object()?.message()?.summary().to_string()) })
+            .collect::<Result<Vec<_>, _>>()?;
+        summaries.sort();
+        Ok(summaries)
+    }
+
+    #[test]
+    fn with_paths() -> crate::Result {
+        let repo = crate::named_repo("make_path_limited_repo.sh")?;
+        for use_commit_graph in [false, true] {
+            for (paths, first_parent_only, expected) in [
+                (
+                    &["dir"][..],
+                    false,
+                    &[
+                        "c1",
+                        "c3-dir",
+                        "c4-side-dir",
+                        "c7-dir",
+                        "merge-both",
+                        "merge-same-as-first",
+                    ][..],
+                ),
+                (&["dir"], true, &["c1", "c3-dir", "c7-dir", "merge-both"]),
+                (&["dir/f/"], false, &["c1", "c3-dir", "c7-dir", "merge-both"]),
+                (&["dir/f"], true, &["c1", "c3-dir", "c7-dir"]),
+                (
+                    &["other"],
+                    false,
+                    &["c1", "c2-other", "c5-side-other", "merge-both", "merge-same-as-first"],
+                ),
+                (
+                    &["dir", "other2"],
+                    false,
+                    &[
+                        "c1",
+                        "c3-dir",
+                        "c4-side-dir",
+                        "c6-side2-other",
+                        "c7-dir",
+                        "merge-both",
+                        "merge-same-as-first",
+                    ],
+                ),
+                (&["does-not-exist"], false, &[]),
+            ] {
+                assert_eq!(
+                    summaries_of_commits_changing(&repo, paths, use_commit_graph, first_parent_only)?,
+                    expected,
+                    "{paths:?}, first-parent: {first_parent_only}, commit-graph: {use_commit_graph}; like `git log --full-history`"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn with_paths_trusts_bloom_filters() -> crate::Result {
+        let (repo, _tmp) = crate::repo_rw("make_path_limited_repo.sh")?;
+        let info_dir = repo.objects.store_ref().path().join("info");
+        let graph = repo.commit_graph()?;
+        let commits: Vec<_> = graph
+            .iter_commits()
+            .map(|c| -> crate::Result<_> {
+                Ok(gix::commitgraph::file::write::Commit {
+                    id: c.id().to_owned(),
+                    root_tree_id: c.root_tree_id().to_owned(),
+                    parents: c
+                        .iter_parents()
+                        .map(|pos| pos.map(|pos| graph.id_at(pos).to_owned()))
+                        .collect::<Result<_, _>>()?,
+                    committer_timestamp: c.committer_timestamp(),
+                    bloom_filter: Some(vec![0]),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        drop(graph);
+        let mut file = std::fs::File::create(info_dir.join("commit-graph"))?;
+        gix::commitgraph::File::write_from_commits(
+            commits,
+            None,
+            repo.object_hash(),
+            Some(&Default::default()),
+            &mut file,
+        )?;
+
+        assert_eq!(
+            summaries_of_commits_changing(&repo, &["dir"], true, false)?,
+            &["c1", "merge-both", "merge-same-as-first"],
+            "the filters claim nothing changed compared to the first parent, so only roots and merges can be returned"
+        );
+        assert_eq!(
+            summaries_of_commits_changing(&repo, &["dir"], false, false)?,
+            &[
+                "c1",
+                "c3-dir",
+                "c4-side-dir",
+                "c7-dir",
+                "merge-both",
+                "merge-same-as-first"
+            ],
+            "without commit-graph, trees are compared"
+        );
+
+        let summaries_with_graph = |use_commit_graph: Option<bool>| -> crate::Result<Vec<String>> {
+            let mut summaries = repo
+                .head_id()?
+                .ancestors()
+                .with_commit_graph(Some(repo.commit_graph()?))
+                .use_commit_graph(use_commit_graph)
+                .with_paths(Some("dir"))
+                .all()?
+                .map(|info| -> crate::Result<_> { Ok(info?.object()?.message()?.summary().to_string()) })
+                .collect::<Result<Vec<_>, _>>()?;
+            summaries.sort();
+            Ok(summaries)
+        };
+        assert_eq!(
+            summaries_with_graph(None)?,
+            &["c1", "merge-both", "merge-same-as-first"],
+            "the commit-graph passed by the caller is used for the path filter as well"
+        );
+        assert_eq!(
+            summaries_with_graph(Some(false))?.len(),
+            6,
+            "turning the commit-graph off also ignores the one passed by the caller"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn with_paths_in_shallow_clone() -> crate::Result {
+        for name in ["shallow.git", "shallow"] {
+            let repo = crate::util::named_subrepo_opts("make_shallow_repo.sh", name, crate::restricted())?;
+            for use_commit_graph in [false, true] {
+                assert_eq!(
+                    summaries_of_commits_changing(&repo, &["a"], use_commit_graph, false)?,
+                    &["c3"],
+                    "the shallow commit is treated like a root as its parents aren't available"
+                );
+                assert_eq!(
+                    summaries_of_commits_changing(&repo, &["does-not-exist"], use_commit_graph, false)?,
+                    &[] as &[&str]
+                );
+            }
+        }
+        Ok(())
+    }
 }
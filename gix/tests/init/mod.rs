@@ -126,3 +126,60 @@ mod non_bare {
         Ok(())
     }
 }
+
+#[cfg(feature = "sha256")]
+mod sha256 {
+    use gix_testtools::tempfile;
+
+    use crate::util::restricted;
+
+    #[test]
+    fn objects_references_and_index_roundtrip() -> crate::Result {
+        let tmp = tempfile::tempdir()?;
+        let repo = gix::ThreadSafeRepository::init_opts(
+            tmp.path(),
+            gix::create::Kind::WithWorktree,
+            gix::create::Options {
+                object_hash: gix::hash::Kind::Sha256,
+                ..Default::default()
+            },
+            restricted(),
+        )?
+        .to_thread_local();
+        assert_eq!(repo.object_hash(), gix::hash::Kind::Sha256);
+        let config = std::fs::read_to_string(repo.git_dir().join("config"))?;
+        assert!(config.contains("repositoryformatversion = 1"), "{config}");
+        assert!(config.contains("objectformat = sha256"), "{config}");
+
+        let empty_tree_id = repo.write_object(gix::objs::Tree::empty())?;
+        assert_eq!(empty_tree_id, gix::hash::ObjectId::empty_tree(gix::hash::Kind::Sha256));
+        let blob_id = repo.write_blob(b"hello")?;
+        assert_eq!(blob_id.kind(), gix::hash::Kind::Sha256);
+        let mut editor = repo.edit_tree(empty_tree_id)?;
+        editor.upsert("dir/file", gix::object::tree::EntryKind::Blob, blob_id)?;
+        let tree_id = editor.write()?;
+        let commit_id = repo.commit("HEAD", "initial", tree_id, gix::commit::NO_PARENT_IDS)?;
+
+        let repo = gix::open_opts(repo.git_dir(), restricted())?;
+        assert_eq!(repo.object_hash(), gix::hash::Kind::Sha256, "the format is persisted");
+        let head = repo.head_commit()?;
+        assert_eq!(head.id, commit_id);
+        let entry = head
+            .tree()?
+            .peel_to_entry_by_path("dir/file")?
+            .expect("entry is present");
+        assert_eq!(entry.object_id(), blob_id);
+        assert_eq!(entry.object()?.data, b"hello");
+
+        #[cfg(feature = "index")]
+        {
+            let mut index = repo.index_from_tree(&tree_id)?;
+            index.write(Default::default())?;
+            let index = repo.open_index()?;
+            assert_eq!(index.object_hash(), gix::hash::Kind::Sha256);
+            assert_eq!(index.entries().len(), 1);
+            assert_eq!(index.entries()[0].id, blob_id);
+        }
+        Ok(())
+    }
+}
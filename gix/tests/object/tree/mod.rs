@@ -25,3 +25,48 @@ fn lookup_entry_by_path() -> crate::Result {
     );
     Ok(())
 }
+
+mod editor {
+    use gix::object::tree::EntryKind;
+
+    use crate::util::repo_rw;
+
+    #[test]
+    fn upsert_and_remove_nested_entries_of_an_existing_tree() -> crate::Result {
+        let (repo, _tmp) = repo_rw("make_basic_repo.sh")?;
+        let head_tree = repo.head_commit()?.tree()?;
+        let blob = repo.write_blob("content")?.detach();
+
+        let mut editor = head_tree.edit()?;
+        let id = editor
+            .upsert("a/b/file", EntryKind::Blob, blob)?
+            .upsert("a/exe", EntryKind::BlobExecutable, blob)?
+            .write()?;
+        let tree = id.object()?.into_tree();
+        let mut buf = Vec::new();
+        let entry = tree.lookup_entry_by_path("a/b/file", &mut buf)?.expect("present");
+        assert_eq!(entry.object_id(), blob);
+        assert_eq!(entry.mode().kind(), EntryKind::Blob);
+        assert_eq!(
+            tree.lookup_entry_by_path("this", &mut buf)?
+                .expect("present")
+                .object_id(),
+            head_tree.find_entry("this").expect("present").object_id(),
+            "unchanged entries are kept"
+        );
+
+        let id = editor.remove("a/b")?.remove("this")?.write()?;
+        let tree = id.object()?.into_tree();
+        assert!(tree.lookup_entry_by_path("a/exe", &mut buf)?.is_some());
+        assert!(tree.lookup_entry_by_path("a/b", &mut buf)?.is_none());
+        assert!(tree.lookup_entry_by_path("this", &mut buf)?.is_none());
+
+        assert!(
+            editor.upsert("a//b", EntryKind::Blob, blob).is_err(),
+            "empty path components are invalid"
+        );
+        let id = repo.edit_tree(id)?.remove("a")?.write()?;
+        assert_eq!(id, repo.empty_tree().id(), "nothing is left");
+        Ok(())
+    }
+}
@@ -112,6 +112,145 @@ mod blocking_and_async_io {
         Ok(())
     }
 
+    #[test]
+    #[cfg(all(
+        feature = "blocking-network-server",
+        any(
+            feature = "blocking-http-transport-reqwest",
+            feature = "blocking-http-transport-curl"
+        )
+    ))]
+    fn fetch_with_packfile_uris_downloads_offloaded_packs() -> crate::Result {
+        use std::{
+            io::{BufRead, Write},
+            sync::atomic::Ordering,
+        };
+
+        use gix::bstr::ByteSlice;
+
+        let server = gix_testtools::scripted_fixture_writable("make_basic_repo.sh")?;
+        let server_repo = gix::open_opts(server.path(), crate::restricted())?;
+        let blob = server_repo
+            .head_commit()?
+            .tree()?
+            .find_entry("this")
+            .expect("present")
+            .object_id();
+        let packs = gix_testtools::tempfile::tempdir()?;
+        let mut pack_objects = std::process::Command::new(gix::path::env::exe_invocation())
+            .args(["pack-objects", "-q"])
+            .arg(packs.path().join("pack"))
+            .current_dir(server_repo.git_dir())
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()?;
+        writeln!(pack_objects.stdin.take().expect("piped"), "{blob}")?;
+        let output = pack_objects.wait_with_output()?;
+        assert!(output.status.success());
+        let pack_hash = gix::ObjectId::from_hex(output.stdout.trim_end())?;
+        let pack = std::fs::read(packs.path().join(format!("pack-{pack_hash}.pack")))?;
+        let server_config = server_repo.git_dir().join("config");
+        let config = std::fs::read(&server_config)?;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let pack_url = format!("http://{}/pack-{pack_hash}.pack", listener.local_addr()?);
+        let clone_with_announced_hash = |announced: gix::ObjectId| -> crate::Result<_> {
+            let mut server_config = std::fs::File::create(&server_config)?;
+            server_config.write_all(&config)?;
+            writeln!(
+                server_config,
+                "[uploadpack]\n\tblobPackfileUri = {blob} {announced} {pack_url}"
+            )?;
+
+            let daemon = std::net::TcpListener::bind("127.0.0.1:0")?;
+            let url = format!("git://{}/.git", daemon.local_addr()?);
+            let tmp = gix_testtools::tempfile::tempdir()?;
+            let mut prepare = gix::prepare_clone_bare(url, tmp.path())?
+                .with_in_memory_config_overrides(["protocol.version=2", "fetch.uriProtocols=ftp, http"]);
+            let options = gix::daemon::Options {
+                base_path: Some(server.path().into()),
+                export_all: true,
+                ..Default::default()
+            };
+            let stop_daemon = AtomicBool::default();
+            let served = AtomicBool::default();
+            let res = std::thread::scope(|scope| {
+                let daemon = scope.spawn(|| {
+                    gix::daemon::serve(&daemon, &options, &stop_daemon, &|_peer, err| {
+                        panic!("the daemon doesn't fail: {err}")
+                    })
+                });
+                scope.spawn(|| -> std::io::Result<()> {
+                    let (stream, _) = listener.accept()?;
+                    served.store(true, Ordering::SeqCst);
+                    let mut request = std::io::BufReader::new(&stream);
+                    let mut line = String::new();
+                    while request.read_line(&mut line)? > 2 {
+                        line.clear();
+                    }
+                    let mut out = &stream;
+                    write!(out, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", pack.len())?;
+                    out.write_all(&pack)
+                });
+                let res = prepare.fetch_only(gix::progress::Discard, &AtomicBool::default());
+                if !served.load(Ordering::SeqCst) {
+                    // Unblock the pack server if the pack was never requested.
+                    std::net::TcpStream::connect(listener.local_addr()?)?;
+                }
+                stop_daemon.store(true, Ordering::SeqCst);
+                daemon.join().expect("no panic")?;
+                Ok::<_, std::io::Error>(res)
+            });
+            Ok((res?, tmp))
+        };
+
+        let ((repo, outcome), _tmp) = match clone_with_announced_hash(pack_hash)? {
+            (Ok(res), tmp) => (res, tmp),
+            (Err(err), _) => return Err(err.into()),
+        };
+        let fetch::Status::Change {
+            write_pack_bundle,
+            packfile_uri_bundles,
+            ..
+        } = outcome.status
+        else {
+            unreachable!("a clone receives objects")
+        };
+        assert_eq!(packfile_uri_bundles.len(), 1);
+        assert_eq!(packfile_uri_bundles[0].index.data_hash, pack_hash);
+        assert_eq!(packfile_uri_bundles[0].index.num_objects, 1);
+        assert!(
+            packfile_uri_bundles[0].keep_path.is_none(),
+            "keep files are removed after the refs were updated"
+        );
+        assert_eq!(
+            write_pack_bundle.index.num_objects, 5,
+            "the offloaded blob isn't part of the pack sent by the server"
+        );
+        assert!(repo.find_object(blob).is_ok(), "the blob is available after the clone");
+
+        let announced = gix::ObjectId::from_hex(b"0000000000000000000000000000000000000001")?;
+        let (res, tmp) = clone_with_announced_hash(announced)?;
+        match res {
+            Err(gix::clone::fetch::Error::Fetch(fetch::Error::PackfileUriChecksumMismatch {
+                uri,
+                expected,
+                actual,
+            })) => {
+                assert_eq!(uri, pack_url);
+                assert_eq!(expected, announced);
+                assert_eq!(actual, pack_hash);
+            }
+            res => panic!("the pack doesn't match the announced hash: {res:?}"),
+        }
+        let pack_dir = tmp.path().join("objects").join("pack");
+        assert!(
+            !pack_dir.join(format!("pack-{pack_hash}.pack")).exists(),
+            "packs with mismatching hashes are removed"
+        );
+        Ok(())
+    }
+
     #[test]
     #[cfg(feature = "blocking-network-client")]
     fn fetch_with_alternates_adds_tips_from_alternates() -> crate::Result<()> {
@@ -160,6 +299,87 @@ mod blocking_and_async_io {
         Ok(())
     }
 
+    #[test]
+    #[cfg(feature = "blocking-network-client")]
+    fn object_info_and_fetch_objects_by_id() -> crate::Result {
+        use std::io::Write;
+
+        let server = gix_testtools::scripted_fixture_writable("make_basic_repo.sh")?;
+        let server_repo = gix::open_opts(server.path(), crate::restricted())?;
+        let tree = server_repo.head_commit()?.tree()?;
+        let blob = tree.find_entry("this").expect("present").object_id();
+        let missing = hex_to_id("0000000000000000000000000000000000000001");
+        let url = server.path().to_owned();
+
+        let tmp = TempDir::new()?;
+        let mut repo = gix::init_bare(tmp.path())?;
+        repo.config_snapshot_mut().set_raw_value(&Protocol::VERSION, "1")?;
+        let err = repo
+            .remote_at(url.clone())?
+            .connect(Fetch)?
+            .object_info([blob], progress::Discard)
+            .unwrap_err();
+        assert!(
+            matches!(err, gix::remote::object_info::Error::Unsupported),
+            "the command is only available in protocol V2"
+        );
+        repo.config_snapshot_mut().set_raw_value(&Protocol::VERSION, "2")?;
+
+        writeln!(
+            std::fs::OpenOptions::new()
+                .append(true)
+                .open(server_repo.git_dir().join("config"))?,
+            "[transfer]\n\tadvertiseObjectInfo = true\n[uploadpack]\n\tallowAnySHA1InWant = true"
+        )?;
+        let infos = repo
+            .remote_at(url.clone())?
+            .connect(Fetch)?
+            .object_info([blob, missing], progress::Discard)?;
+        assert_eq!(infos.len(), 2);
+        assert_eq!(
+            (infos[0].id, infos[0].size),
+            (blob, Some(server_repo.find_object(blob)?.data.len() as u64))
+        );
+        assert_eq!(
+            (infos[1].id, infos[1].size),
+            (missing, None),
+            "the server doesn't have it"
+        );
+
+        let err = repo
+            .remote_at(url.clone())?
+            .connect(Fetch)?
+            .prepare_fetch_objects(progress::Discard, None)
+            .err()
+            .expect("ids are required");
+        assert!(matches!(err, gix::remote::fetch::prepare::Error::MissingObjectIds));
+
+        let out = repo
+            .remote_at(url)?
+            .connect(Fetch)?
+            .prepare_fetch_objects(progress::Discard, [blob, tree.id])?
+            .receive(progress::Discard, &AtomicBool::default())?;
+        let Status::Change {
+            write_pack_bundle,
+            update_refs,
+            negotiate,
+            ..
+        } = out.status
+        else {
+            unreachable!("the objects are fetched")
+        };
+        assert_eq!(
+            write_pack_bundle.index.num_objects, 2,
+            "just the objects, as there is no negotiation"
+        );
+        assert_eq!(negotiate.rounds[0].haves_sent, 0);
+        assert!(update_refs.edits.is_empty(), "no ref points to the objects");
+        assert!(write_pack_bundle.keep_path.is_none());
+        assert!(repo.find_object(blob).is_ok() && repo.find_object(tree.id).is_ok());
+        assert!(repo.head_commit().is_err(), "commits aren't received");
+        Ok(())
+    }
+
     #[maybe_async::test(
         feature = "blocking-network-client",
         async(feature = "async-network-client-async-std", async_std::test)
@@ -280,6 +500,48 @@ mod blocking_and_async_io {
         Ok(())
     }
 
+    #[maybe_async::test(
+        feature = "blocking-network-client",
+        async(feature = "async-network-client-async-std", async_std::test)
+    )]
+    async fn fetch_shallow_backfills_tags_pointing_to_commits_we_have() -> crate::Result {
+        let (repo, tmp) = try_repo_rw_args("two-origins", ["--depth=2"], Mode::CloneWithShallowSupport)?;
+        let head_id = repo.head_id()?.detach();
+        let status = std::process::Command::new(gix::path::env::exe_invocation())
+            .args(["-c", "user.name=gitoxide", "-c", "user.email=gitoxide@localhost"])
+            .args(["tag", "-a", "-m", "annotated tag on an existing commit", "new-tag"])
+            .arg(head_id.to_string())
+            .current_dir(tmp.path().join("base"))
+            .status()?;
+        assert!(status.success());
+
+        let daemon = spawn_git_daemon_if_async(tmp.path().join("base"))?;
+        let remote = into_daemon_remote_if_async(
+            repo.head()?
+                .into_remote(Fetch)
+                .expect("present")?
+                .with_fetch_tags(fetch::Tags::Included),
+            daemon.as_ref(),
+            None,
+        );
+        remote
+            .connect(Fetch)
+            .await?
+            .prepare_fetch(gix::progress::Discard, Default::default())
+            .await?
+            .receive(gix::progress::Discard, &AtomicBool::default())
+            .await?;
+
+        let mut tag_ref = repo.find_reference("refs/tags/new-tag")?;
+        assert_ne!(
+            tag_ref.id(),
+            head_id,
+            "the tag itself is annotated and was received separately"
+        );
+        assert_eq!(tag_ref.peel_to_id_in_place()?, head_id);
+        Ok(())
+    }
+
     #[maybe_async::test(
         feature = "blocking-network-client",
         async(feature = "async-network-client-async-std", async_std::test)
@@ -453,8 +715,9 @@ mod blocking_and_async_io {
                 .await?;
 
             match res.status {
-                gix::remote::fetch::Status::Change { write_pack_bundle, update_refs, negotiate } => {
+                gix::remote::fetch::Status::Change { write_pack_bundle, packfile_uri_bundles, update_refs, negotiate } => {
                     assert_eq!(negotiate.rounds.len(), 1);
+                    assert!(packfile_uri_bundles.is_empty(), "packfile URIs aren't requested by default");
                     assert_eq!(write_pack_bundle.index.data_hash, hex_to_id(expected_data_hash), );
                     assert_eq!(write_pack_bundle.index.num_objects, 3 + num_objects_offset, "{fetch_tags:?}");
                     assert!(write_pack_bundle.data_path.as_deref().map_or(false, std::path::Path::is_file));
@@ -534,6 +797,7 @@ mod blocking_and_async_io {
                         write_pack_bundle,
                         update_refs,
                         negotiate,
+                        ..
                     } => {
                         assert_eq!(negotiate.rounds.len(), 1);
                         assert_eq!(write_pack_bundle.pack_version, gix::odb::pack::data::Version::V2);
@@ -595,6 +859,7 @@ mod blocking_and_async_io {
                         );
                         update_refs
                     }
+                    fetch::Status::DumbTransfer { .. } => unreachable!("the remote isn't a dumb server"),
                 };
 
                 assert_eq!(
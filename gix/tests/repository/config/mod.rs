@@ -33,17 +33,27 @@ mod ssh_options {
         );
         Ok(())
     }
+
+    #[test]
+    fn native_client_is_opt_in() -> crate::Result {
+        let mut repo = repo("ssh-all-options");
+        assert!(!repo.ssh_connect_options()?.native, "ssh programs are used by default");
+        repo.config_snapshot_mut()
+            .set_value(&gix::config::tree::gitoxide::Ssh::NATIVE, "true")?;
+        assert!(repo.ssh_connect_options()?.native);
+        Ok(())
+    }
 }
 
 #[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
 mod transport_options;
 
-#[cfg(feature = "blocking-network-client")]
+#[cfg(any(feature = "blocking-network-client", feature = "async-http-transport-reqwest"))]
 pub fn repo(name: &str) -> gix::Repository {
     repo_opts(name, |opts| opts.strict_config(true))
 }
 
-#[cfg(feature = "blocking-network-client")]
+#[cfg(any(feature = "blocking-network-client", feature = "async-http-transport-reqwest"))]
 pub fn repo_opts(name: &str, modify: impl FnOnce(gix::open::Options) -> gix::open::Options) -> gix::Repository {
     let dir = gix_testtools::scripted_fixture_read_only("make_config_repos.sh").unwrap();
     gix::open_opts(dir.join(name), modify(gix::open::Options::isolated())).unwrap()
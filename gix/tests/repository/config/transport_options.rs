@@ -1,6 +1,7 @@
 #[cfg(any(
     feature = "blocking-http-transport-reqwest",
-    feature = "blocking-http-transport-curl"
+    feature = "blocking-http-transport-curl",
+    feature = "async-http-transport-reqwest"
 ))]
 mod http {
     use gix_transport::client::http::options::{
@@ -78,7 +79,10 @@ mod http {
         assert_eq!(no_proxy, None);
         assert!(!verbose, "verbose is disabled by default");
         assert_eq!(ssl_ca_info.as_deref(), Some(std::path::Path::new("./CA.pem")));
-        #[cfg(feature = "blocking-http-transport-reqwest")]
+        #[cfg(any(
+            feature = "blocking-http-transport-reqwest",
+            feature = "async-http-transport-reqwest"
+        ))]
         {
             assert!(
                 backend.is_none(),
@@ -9,12 +9,18 @@ mod object;
 mod open;
 #[cfg(feature = "attributes")]
 mod pathspec;
+#[cfg(feature = "blocking-network-server")]
+mod receive_pack;
 mod reference;
 mod remote;
 mod shallow;
+#[cfg(feature = "worktree-mutation")]
+mod sparse_checkout;
 mod state;
 #[cfg(feature = "attributes")]
 mod submodule;
+#[cfg(all(feature = "blocking-network-client", feature = "blocking-network-server"))]
+mod upload_pack;
 mod worktree;
 
 #[cfg(feature = "dirwalk")]
@@ -397,6 +397,31 @@ mod commit {
     }
 }
 
+#[cfg(feature = "index")]
+mod write_tree_from_index {
+    use crate::util::repo_rw;
+
+    #[test]
+    fn matches_head_tree_and_refreshes_the_tree_extension() -> crate::Result {
+        let (repo, _tmp) = repo_rw("make_basic_repo.sh")?;
+        let mut index = repo.open_index()?;
+        let expected = repo.head_commit()?.tree_id()?;
+        assert_eq!(repo.write_tree_from_index(&mut index)?, expected);
+        let tree = index.tree().expect("present after writing");
+        assert_eq!(tree.id, expected);
+        assert_eq!(tree.num_entries, Some(index.entries().len() as u32));
+
+        index.remove_entries(|_, path, _| path == "this");
+        assert_eq!(
+            index.tree().expect("present").num_entries,
+            None,
+            "changes invalidate the extension"
+        );
+        assert_eq!(repo.write_tree_from_index(&mut index)?, repo.empty_tree().id());
+        Ok(())
+    }
+}
+
 fn empty_bare_repo() -> crate::Result<(tempfile::TempDir, gix::Repository)> {
     let tmp = tempfile::tempdir()?;
     let repo = gix::ThreadSafeRepository::init_opts(
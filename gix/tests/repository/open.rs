@@ -389,3 +389,49 @@ mod worktree {
         Ok(())
     }
 }
+
+mod ref_storage {
+    use gix::refs::{store::RefStorage, transaction::PreviousValue};
+
+    #[test]
+    fn reftable_is_used_if_configured_with_repository_format_version_1() -> crate::Result {
+        let dir = gix_testtools::tempfile::tempdir()?;
+        let repo = gix::init(dir.path())?;
+        assert_eq!(repo.refs.ref_storage(), RefStorage::Files);
+
+        let config_path = repo.git_dir().join("config");
+        let mut config = std::fs::read_to_string(&config_path)?;
+        config = config.replace("repositoryformatversion = 0", "repositoryformatversion = 1");
+        config.push_str("[extensions]\n\trefStorage = reftable\n[user]\n\tname = name\n\temail = name@example.com\n");
+        std::fs::write(&config_path, config)?;
+
+        let repo = gix::open_opts(dir.path(), gix::open::Options::isolated())?;
+        assert_eq!(repo.refs.ref_storage(), RefStorage::Reftable);
+        let id = gix::ObjectId::empty_tree(repo.object_hash());
+        repo.reference("refs/heads/main", id, PreviousValue::MustNotExist, "created")?;
+        assert!(
+            !repo.git_dir().join("refs/heads/main").exists(),
+            "the reference lives in the reftable stack"
+        );
+        assert_eq!(repo.find_reference("main")?.id(), id);
+        assert_eq!(
+            repo.find_reference("main")?.log_iter().all()?.expect("present").count(),
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ref_storage_is_ignored_with_repository_format_version_0() -> crate::Result {
+        let dir = gix_testtools::tempfile::tempdir()?;
+        let repo = gix::init_bare(dir.path())?;
+        let config_path = repo.git_dir().join("config");
+        let mut config = std::fs::read_to_string(&config_path)?;
+        config.push_str("[extensions]\n\trefStorage = reftable\n");
+        std::fs::write(&config_path, config)?;
+
+        let repo = gix::open_opts(dir.path(), gix::open::Options::isolated())?;
+        assert_eq!(repo.refs.ref_storage(), RefStorage::Files);
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::atomic::AtomicBool;
+
+use gix::protocol::transport::Protocol;
+
+/// Encode `command` as packet line, followed by a flush packet.
+fn command_request(command: &str) -> Vec<u8> {
+    format!("{:04x}{command}0000", command.len() + 4).into_bytes()
+}
+
+#[test]
+fn options_from_configuration() -> crate::Result {
+    let mut repo = crate::named_repo("make_basic_repo.sh")?;
+    let options = repo.receive_pack_options()?;
+    assert!(!options.deny_deletes && !options.deny_non_fast_forwards && !options.advertise_push_options);
+    assert!(options.agent.starts_with("git/"));
+    assert_eq!(
+        options.hooks_dir.as_deref(),
+        Some(repo.git_dir().join("hooks").as_path())
+    );
+    assert_eq!(
+        options.committer.as_ref().map(|committer| committer.name.clone()),
+        Some("gitoxide".into()),
+        "the committer is used for reflogs"
+    );
+
+    let mut config = repo.config_snapshot_mut();
+    config.set_raw_value(&gix::config::tree::Receive::DENY_DELETES, "true")?;
+    config.set_raw_value(&gix::config::tree::Receive::ADVERTISE_PUSH_OPTIONS, "yes")?;
+    config.set_raw_value(&gix::config::tree::Core::HOOKS_PATH, "/path/to/hooks")?;
+    drop(config);
+    let options = repo.receive_pack_options()?;
+    assert!(options.deny_deletes && options.advertise_push_options && !options.deny_non_fast_forwards);
+    assert_eq!(
+        options.hooks_dir.as_deref(),
+        Some(std::path::Path::new("/path/to/hooks"))
+    );
+    Ok(())
+}
+
+#[test]
+fn deletions_respect_configuration() -> crate::Result {
+    let (mut repo, _tmp) = crate::repo_rw("make_basic_repo.sh")?;
+    let id = repo.head_id()?.detach();
+    repo.reference(
+        "refs/heads/topic",
+        id,
+        gix::refs::transaction::PreviousValue::MustNotExist,
+        "",
+    )?;
+    let request = command_request(&format!(
+        "{id} {} refs/heads/topic\0report-status",
+        repo.object_hash().null()
+    ));
+
+    for deny_deletes in [true, false] {
+        repo.config_snapshot_mut().set_raw_value(
+            &gix::config::tree::Receive::DENY_DELETES,
+            deny_deletes.to_string().as_str(),
+        )?;
+        let options = gix::protocol::receive_pack::Options {
+            stateless_rpc: true,
+            ..repo.receive_pack_options()?
+        };
+        let mut out = Vec::new();
+        let outcome = repo.receive_pack(
+            Protocol::V0,
+            &mut request.as_slice(),
+            &mut out,
+            &AtomicBool::default(),
+            &options,
+        )?;
+        assert_eq!(outcome.updates.len(), 1);
+        assert_eq!(
+            outcome.updates[0].rejection.as_deref(),
+            deny_deletes.then_some("deletion prohibited")
+        );
+        assert_eq!(
+            repo.try_find_reference("refs/heads/topic")?.is_some(),
+            deny_deletes,
+            "the reference is only deleted if this is allowed"
+        );
+    }
+    Ok(())
+}
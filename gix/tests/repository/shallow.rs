@@ -43,6 +43,38 @@ fn yes() -> crate::Result {
     Ok(())
 }
 
+#[test]
+#[parallel]
+fn prune_removes_commits_that_do_not_exist() -> crate::Result {
+    let tmp = gix_testtools::scripted_fixture_writable("make_shallow_repo.sh")?;
+    // Open a new instance after each change to the shallow file to not rely on its modification time.
+    let open = || gix::open_opts(tmp.path().join("shallow"), crate::restricted());
+    let repo = open()?;
+    assert_eq!(repo.prune_shallow_commits()?, 0, "all shallow commits exist");
+
+    let existing = hex_to_id("30887839de28edf7ab66c860e5c58b4d445f6b12");
+    let missing = hex_to_id("0000000000000000000000000000000000000001");
+    std::fs::write(repo.shallow_file(), format!("{missing}\n{existing}\n"))?;
+    let repo = open()?;
+    assert_eq!(repo.prune_shallow_commits()?, 1);
+    assert_eq!(repo.shallow_commits()?.expect("present").as_slice(), [existing]);
+
+    std::fs::write(repo.shallow_file(), format!("{missing}\n"))?;
+    let repo = open()?;
+    assert_eq!(repo.prune_shallow_commits()?, 1);
+    assert!(
+        !repo.shallow_file().exists(),
+        "the shallow file is removed if no commit remains"
+    );
+    assert!(!repo.is_shallow());
+    assert_eq!(
+        repo.prune_shallow_commits()?,
+        0,
+        "non-shallow repositories have nothing to prune"
+    );
+    Ok(())
+}
+
 mod traverse {
     use serial_test::parallel;
 
@@ -0,0 +1,189 @@
+use std::{path::Path, sync::atomic::AtomicBool};
+
+use gix::{bstr::ByteSlice, sparse_checkout::Patterns};
+
+use crate::util::repo_rw;
+
+mod patterns {
+    use gix::{bstr::ByteSlice, sparse_checkout::Patterns};
+
+    #[test]
+    fn cone_includes_root_files_parent_files_and_recursive_directories() {
+        let patterns = Patterns::from_directories(["a/b/", "/c", "c/d"]);
+        for (path, expected) in [
+            ("root", true),
+            ("a/file", true),
+            ("a/b/file", true),
+            ("a/b/c/d/file", true),
+            ("a/other/file", false),
+            ("c/file", true),
+            ("c/d/file", true),
+            ("d/file", false),
+            ("ab/file", false),
+        ] {
+            assert_eq!(patterns.is_included(path.into()), expected, "{path}");
+        }
+        assert!(patterns.is_cone());
+    }
+
+    #[test]
+    fn cone_roundtrips_through_its_file_format() -> crate::Result {
+        let patterns = Patterns::from_directories(["a/b", "c"]);
+        let mut buf = Vec::new();
+        patterns.write_to(&mut buf)?;
+        assert_eq!(buf.as_bstr(), "/*\n!/*/\n/a/\n!/a/*/\n/a/b/\n/c/\n");
+        assert_eq!(Patterns::from_bytes(&buf, true), patterns);
+        Ok(())
+    }
+
+    #[test]
+    fn cone_falls_back_to_non_cone_if_patterns_are_not_in_cone_format() {
+        let patterns = Patterns::from_bytes(b"/*\n!/*/\n*.md\n", true);
+        assert!(!patterns.is_cone());
+        assert!(patterns.is_included("dir/readme.md".into()));
+
+        let patterns = Patterns::from_bytes(b"/a/\n", true);
+        assert!(!patterns.is_cone(), "the root patterns are mandatory");
+    }
+
+    #[test]
+    fn non_cone_lets_the_last_matching_pattern_of_a_path_or_its_directories_decide() {
+        let patterns = Patterns::from_bytes(b"# comment\n/*\n!/a/\n/a/b/\n!*.tmp\n", false);
+        for (path, expected) in [
+            ("root", true),
+            ("root.tmp", false),
+            ("a/file", false),
+            ("a/b/file", true),
+            ("a/b/c/file", true),
+            ("a/b/file.tmp", false),
+            ("c/file", true),
+        ] {
+            assert_eq!(patterns.is_included(path.into()), expected, "{path}");
+        }
+        assert!(
+            !Patterns::from_bytes(b"", false).is_included("root".into()),
+            "nothing is included without patterns"
+        );
+    }
+
+    #[test]
+    fn non_cone_roundtrips_through_its_file_format() -> crate::Result {
+        let patterns = Patterns::from_bytes(b"/*\n!/a/\n/a/b/\n", false);
+        let mut buf = Vec::new();
+        patterns.write_to(&mut buf)?;
+        assert_eq!(buf.as_bstr(), "/*\n!/a/\n/a/b/\n");
+        Ok(())
+    }
+}
+
+#[test]
+fn set_add_reapply_and_disable() -> crate::Result {
+    let (mut repo, _tmp) = repo_rw("make_sparse_checkout_repo.sh")?;
+    let workdir = repo.work_dir().expect("non-bare").to_owned();
+    let should_interrupt = AtomicBool::default();
+    assert_eq!(repo.sparse_checkout_patterns()?, None, "not enabled by default");
+    assert!(matches!(
+        repo.sparse_checkout_reapply(&should_interrupt),
+        Err(gix::sparse_checkout::update::Error::NotEnabled)
+    ));
+
+    let outcome = repo.sparse_checkout_set(Patterns::from_directories(Some("c")), &should_interrupt)?;
+    assert_eq!(outcome.files_removed, 3, "a/file, a/b/file and d/file");
+    assert!(outcome.kept_modified.is_empty());
+    assert_eq!(files_on_disk(&workdir), ["c/file", "root"]);
+    assert!(!workdir.join("a").exists(), "empty directories are removed");
+    assert_eq!(skipped_entries(&repo)?, ["a/b/file", "a/file", "d/file"]);
+    assert_eq!(std::fs::read(repo.sparse_checkout_file())?.as_bstr(), "/*\n!/*/\n/c/\n");
+    let reopened = gix::open_opts(repo.path(), crate::restricted())?;
+    assert_eq!(
+        reopened.sparse_checkout_patterns()?,
+        Some(Patterns::from_directories(Some("c"))),
+        "the configuration was written to disk as well"
+    );
+
+    let outcome = repo.sparse_checkout_add(Some("a/b"), &should_interrupt)?;
+    assert_eq!(outcome.files_removed, 0);
+    assert_eq!(
+        files_on_disk(&workdir),
+        ["a/b/file", "a/file", "c/file", "root"],
+        "files directly in leading directories are included in cone mode"
+    );
+    assert_eq!(skipped_entries(&repo)?, ["d/file"]);
+    assert_eq!(std::fs::read(workdir.join("a/b/file"))?.as_bstr(), "a/b/file\n");
+
+    std::fs::write(workdir.join("c/file"), "modified and grown")?;
+    let outcome = repo.sparse_checkout_set(Patterns::from_directories(Some("d")), &should_interrupt)?;
+    assert_eq!(outcome.files_removed, 2, "a/file and a/b/file");
+    assert_eq!(outcome.kept_modified, ["c/file"], "modified files are kept");
+    assert_eq!(files_on_disk(&workdir), ["c/file", "d/file", "root"]);
+    assert_eq!(skipped_entries(&repo)?, ["a/b/file", "a/file"]);
+
+    std::fs::write(repo.sparse_checkout_file(), "/*\n!/*/\n/a/\n")?;
+    let outcome = repo.sparse_checkout_reapply(&should_interrupt)?;
+    assert_eq!(outcome.files_removed, 1, "d/file");
+    assert_eq!(outcome.kept_modified, ["c/file"]);
+    assert_eq!(files_on_disk(&workdir), ["a/b/file", "a/file", "c/file", "root"]);
+
+    let outcome = repo.sparse_checkout_disable(&should_interrupt)?;
+    assert_eq!(outcome.files_removed, 0);
+    assert_eq!(
+        files_on_disk(&workdir),
+        ["a/b/file", "a/file", "c/file", "d/file", "root"]
+    );
+    assert!(skipped_entries(&repo)?.is_empty());
+    assert_eq!(repo.sparse_checkout_patterns()?, None);
+    assert!(repo.sparse_checkout_file().is_file(), "the patterns are kept");
+    Ok(())
+}
+
+#[test]
+fn sparse_indices_are_rejected() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_sparse_index_repo.sh")?;
+    let should_interrupt = AtomicBool::default();
+    assert!(repo.open_index()?.is_sparse(), "git collapsed the excluded directories");
+    std::fs::write(repo.sparse_checkout_file(), "/*\n!/*/\n/a/\n")?;
+
+    let err = repo.sparse_checkout_reapply(&should_interrupt).unwrap_err();
+    assert!(matches!(err, gix::sparse_checkout::update::Error::SparseIndex { .. }));
+    assert!(
+        err.to_string()
+            .contains("expand it with `git sparse-checkout set --no-sparse-index`"),
+        "the error explains how to get a full index"
+    );
+    assert!(
+        !repo.work_dir().expect("non-bare").join("a").exists(),
+        "nothing was checked out"
+    );
+    assert!(repo.open_index()?.is_sparse(), "the index wasn't changed");
+    Ok(())
+}
+
+fn files_on_disk(workdir: &Path) -> Vec<String> {
+    fn collect(dir: &Path, workdir: &Path, out: &mut Vec<String>) {
+        for entry in std::fs::read_dir(dir).expect("readable") {
+            let path = entry.expect("valid entry").path();
+            if path.is_dir() {
+                if path.file_name() != Some(".git".as_ref()) {
+                    collect(&path, workdir, out);
+                }
+            } else {
+                let rela_path = path.strip_prefix(workdir).expect("within workdir");
+                out.push(gix::path::to_unix_separators_on_windows(gix::path::into_bstr(rela_path)).to_string());
+            }
+        }
+    }
+    let mut files = Vec::new();
+    collect(workdir, workdir, &mut files);
+    files.sort();
+    files
+}
+
+fn skipped_entries(repo: &gix::Repository) -> crate::Result<Vec<String>> {
+    let index = repo.open_index()?;
+    Ok(index
+        .entries()
+        .iter()
+        .filter(|entry| entry.flags.contains(gix::index::entry::Flags::SKIP_WORKTREE))
+        .map(|entry| entry.path(&index).to_str_lossy().into_owned())
+        .collect())
+}
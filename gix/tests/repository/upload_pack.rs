@@ -0,0 +1,354 @@
+use std::sync::atomic::AtomicBool;
+
+use gix::{
+    protocol::transport::{client::git, Protocol},
+    remote::{fetch, fetch::Status, Direction::Fetch},
+};
+
+/// Fetch all branches of the `base` remote repository into a new bare repository through an in-memory pipe,
+/// with the client using protocol `version`.
+fn fetch_from_upload_pack(
+    version: Protocol,
+    shallow: fetch::Shallow,
+) -> crate::Result<(gix::Repository, gix::Repository, gix_testtools::tempfile::TempDir)> {
+    let server_path = gix_testtools::scripted_fixture_read_only("make_remote_repos.sh")?.join("base");
+    let (client_write, mut server_read) = gix_features::io::pipe::unidirectional(1);
+    let (mut server_write, client_read) = gix_features::io::pipe::unidirectional(1);
+    // Clients don't tell the server if they want V1, just like `git` when it's spawned without `GIT_PROTOCOL`.
+    let server_version = match version {
+        Protocol::V1 => Protocol::V0,
+        version => version,
+    };
+    let server = std::thread::spawn({
+        let server_path = server_path.clone();
+        move || -> Result<(), gix::repository::upload_pack::Error> {
+            let repo = gix::open_opts(server_path, gix::open::Options::isolated()).expect("valid repository");
+            let options = repo.upload_pack_options().expect("valid configuration");
+            repo.upload_pack(
+                server_version,
+                &mut server_read,
+                &mut server_write,
+                &AtomicBool::default(),
+                &options,
+            )
+        }
+    });
+
+    let tmp = gix_testtools::tempfile::tempdir()?;
+    let repo = gix::init_bare(tmp.path())?;
+    {
+        let remote = repo
+            .remote_at("file:///served/over/pipes")?
+            .with_refspecs(Some("+refs/heads/*:refs/remotes/origin/*"), Fetch)?;
+        let transport = git::Connection::new(
+            client_read,
+            client_write,
+            version,
+            "/",
+            None::<(String, Option<u16>)>,
+            git::ConnectMode::Process,
+            false,
+        );
+        let outcome = remote
+            .to_connection_with_transport(transport)
+            .prepare_fetch(gix::progress::Discard, Default::default())?
+            .with_shallow(shallow)
+            .receive(gix::progress::Discard, &AtomicBool::default())?;
+        assert!(
+            matches!(outcome.status, Status::Change { .. }),
+            "a pack was received and refs were updated"
+        );
+    }
+    server.join().expect("no panic")?;
+    Ok((gix::open_opts(server_path, gix::open::Options::isolated())?, repo, tmp))
+}
+
+#[test]
+fn fetch_all_branches_with_each_protocol_version() -> crate::Result {
+    for version in [Protocol::V1, Protocol::V2] {
+        let (server, client, _tmp) = fetch_from_upload_pack(version, fetch::Shallow::NoChange)?;
+        for branch in server.references()?.local_branches()? {
+            let branch = branch?;
+            let remote_branch =
+                client.find_reference(format!("refs/remotes/origin/{}", branch.name().shorten()).as_str())?;
+            assert_eq!(remote_branch.id(), branch.id(), "{version:?}");
+            assert_eq!(
+                remote_branch.id().ancestors().all()?.count(),
+                branch.id().ancestors().all()?.count(),
+                "{version:?}: all history was received"
+            );
+        }
+        assert!(!client.is_shallow());
+    }
+    Ok(())
+}
+
+#[test]
+fn shallow_fetch() -> crate::Result {
+    // V1 clients pass all advertised features with the first want, including `deepen-relative`, which is why
+    // they would receive the full history just like they would from `git upload-pack`.
+    let (server, client, _tmp) = fetch_from_upload_pack(
+        Protocol::V2,
+        fetch::Shallow::DepthAtRemote(1.try_into().expect("non-zero")),
+    )?;
+    assert!(client.is_shallow());
+    let main = client.find_reference("refs/remotes/origin/main")?;
+    assert_eq!(main.id(), server.find_reference("main")?.id());
+    assert_eq!(
+        main.id().ancestors().all()?.count(),
+        1,
+        "only the tip of the branch was received"
+    );
+    Ok(())
+}
+
+#[test]
+fn options_from_configuration() -> crate::Result {
+    let mut repo = crate::named_repo("make_basic_repo.sh")?;
+    let options = repo.upload_pack_options()?;
+    assert!(!options.allow_filter && !options.allow_any_object_in_want && !options.allow_ref_in_want);
+    assert!(!options.advertise_object_info);
+    assert!(options.agent.starts_with("git/"));
+
+    let mut config = repo.config_snapshot_mut();
+    config.set_raw_value(&gix::config::tree::UploadPack::ALLOW_FILTER, "true")?;
+    config.set_raw_value(&gix::config::tree::UploadPack::ALLOW_REF_IN_WANT, "yes")?;
+    config.set_raw_value(&gix::config::tree::Transfer::ADVERTISE_OBJECT_INFO, "true")?;
+    drop(config);
+    let options = repo.upload_pack_options()?;
+    assert!(options.allow_filter && options.allow_ref_in_want && !options.allow_any_object_in_want);
+    assert!(options.advertise_object_info);
+    Ok(())
+}
+
+mod negotiation {
+    use std::{
+        io::{Read, Write},
+        sync::{atomic::AtomicBool, Arc, Mutex},
+    };
+
+    use gix::{
+        bstr::ByteSlice,
+        protocol::transport::{client::git, Protocol},
+        remote::{fetch, fetch::outcome::negotiate::Round, fetch::Status, Direction::Fetch},
+    };
+
+    /// Remove the capabilities in `remove` from the first packetline, which is the V0 reference advertisement,
+    /// to make the client believe the server doesn't support them.
+    struct RemoveCapabilities<R> {
+        inner: R,
+        remove: &'static [&'static str],
+        first_line: Option<std::io::Cursor<Vec<u8>>>,
+    }
+
+    impl<R: Read> Read for RemoveCapabilities<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.first_line.is_none() {
+                let mut len = [0; 4];
+                self.inner.read_exact(&mut len)?;
+                let len = usize::from_str_radix(std::str::from_utf8(&len).expect("hex"), 16).expect("valid length");
+                let mut line = vec![0; len - 4];
+                self.inner.read_exact(&mut line)?;
+                let (refs, capabilities) = line.split_at(line.find_byte(0).expect("capabilities") + 1);
+                let capabilities: Vec<_> = capabilities
+                    .trim_end()
+                    .split_str(" ")
+                    .filter(|capability| !self.remove.iter().any(|name| *capability == name.as_bytes()))
+                    .collect();
+                let line = [refs, &capabilities.join(&b' '), b"\n"].concat();
+                let mut out = format!("{:04x}", line.len() + 4).into_bytes();
+                out.extend_from_slice(&line);
+                self.first_line = Some(std::io::Cursor::new(out));
+            }
+            let first_line = self.first_line.as_mut().expect("set");
+            if first_line.position() < first_line.get_ref().len() as u64 {
+                return first_line.read(buf);
+            }
+            self.inner.read(buf)
+        }
+    }
+
+    /// Keep a copy of everything written to `inner`.
+    struct Record<W> {
+        inner: W,
+        written: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl<W: Write> Write for Record<W> {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let n = self.inner.write(buf)?;
+            self.written.lock().expect("not poisoned").extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    /// Fetch the new commit of the server in `make_negotiation_repos.sh` over a V1 connection that persists across requests,
+    /// after removing the capabilities in `remove` from the advertisement of the server.
+    /// Return the negotiation rounds and everything the client sent.
+    fn fetch_without(remove: &'static [&'static str]) -> crate::Result<(Vec<Round>, Vec<u8>)> {
+        let dir = gix_testtools::scripted_fixture_writable("make_negotiation_repos.sh")?;
+        let server_path = dir.path().join("server");
+        // Like `git upload-pack`, the server acknowledges haves while reading them, which needs buffers as large as those of
+        // OS pipes to not block while the client is still sending.
+        let (client_write, mut server_read) = gix_features::io::pipe::unidirectional(1024);
+        let (mut server_write, client_read) = gix_features::io::pipe::unidirectional(1024);
+        let server = std::thread::spawn({
+            let server_path = server_path.clone();
+            move || -> Result<(), gix::repository::upload_pack::Error> {
+                let repo = gix::open_opts(server_path, gix::open::Options::isolated()).expect("valid repository");
+                let options = repo.upload_pack_options().expect("valid configuration");
+                repo.upload_pack(
+                    Protocol::V0,
+                    &mut server_read,
+                    &mut server_write,
+                    &AtomicBool::default(),
+                    &options,
+                )
+            }
+        });
+
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let repo = gix::open_opts(dir.path().join("client"), gix::open::Options::isolated())?;
+        let transport = git::Connection::new(
+            RemoveCapabilities {
+                inner: client_read,
+                remove,
+                first_line: None,
+            },
+            Record {
+                inner: client_write,
+                written: written.clone(),
+            },
+            Protocol::V1,
+            "/",
+            None::<(String, Option<u16>)>,
+            git::ConnectMode::Process,
+            false,
+        );
+        let outcome = repo
+            .remote_at("file:///served/over/pipes")?
+            .with_refspecs(Some(REFSPEC), Fetch)?
+            .to_connection_with_transport(transport)
+            .prepare_fetch(gix::progress::Discard, Default::default())?
+            .receive(gix::progress::Discard, &AtomicBool::default())?;
+        server.join().expect("no panic")?;
+
+        let rounds = received_rounds(outcome, dir.path())?;
+        let written = written.lock().expect("not poisoned").clone();
+        Ok((rounds, written))
+    }
+
+    const REFSPEC: &str = "+refs/heads/*:refs/remotes/origin/*";
+
+    /// Assert that the new commit of the server in `dir` was received according to `outcome`, and return the rounds of negotiation.
+    fn received_rounds(outcome: fetch::Outcome, dir: &std::path::Path) -> crate::Result<Vec<Round>> {
+        let Status::Change { negotiate, .. } = outcome.status else {
+            panic!("expected a pack to be received, got {:?}", outcome.status)
+        };
+        let client = gix::open_opts(dir.join("client"), gix::open::Options::isolated())?;
+        let server = gix::open_opts(dir.join("server"), gix::open::Options::isolated())?;
+        assert_eq!(
+            client.find_reference("refs/remotes/origin/main")?.id(),
+            server.find_reference("main")?.id(),
+            "the new commit was received"
+        );
+        Ok(negotiate.rounds)
+    }
+
+    #[test]
+    fn multi_ack_detailed_stops_once_the_server_is_ready() -> crate::Result {
+        let (rounds, written) = fetch_without(&[])?;
+        assert!(
+            !written.contains_str("no-done"),
+            "`no-done` is advertised, but only used with stateless connections"
+        );
+        assert_eq!(rounds.len(), 3, "{rounds:?}");
+        assert_eq!(
+            rounds[2].haves_sent, 0,
+            "the server said it's ready in the second round, so `done` follows without more haves"
+        );
+        assert_eq!(rounds[1].haves_sent, 32, "most of the unrelated commits are never sent");
+        Ok(())
+    }
+
+    #[test]
+    fn multi_ack_keeps_sending_haves_until_the_server_acknowledged_them() -> crate::Result {
+        let (rounds, written) = fetch_without(&["multi_ack_detailed", "no-done"])?;
+        assert!(written.contains_str(" multi_ack "), "the basic mode is used");
+        assert_eq!(rounds.len(), 4, "{rounds:?}");
+        assert!(rounds[2].previous_response_had_at_least_one_in_common);
+        assert_eq!(
+            rounds[2].haves_sent, 64,
+            "the server can't say it's ready, so a full round of haves follows the first acknowledgement"
+        );
+        assert_eq!(
+            rounds[3].haves_sent, 0,
+            "the server acknowledged the unrelated commits with `continue`, leaving nothing to send"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn single_ack_stops_after_the_first_acknowledgement() -> crate::Result {
+        let (rounds, written) = fetch_without(&["multi_ack", "multi_ack_detailed", "no-done"])?;
+        assert!(!written.contains_str("multi_ack"), "neither multi_ack mode is used");
+        assert_eq!(rounds.len(), 3, "{rounds:?}");
+        assert_eq!(
+            rounds[2].haves_sent, 0,
+            "the server doesn't acknowledge anything after the first common commit, so `done` is sent right away"
+        );
+        assert!(rounds[2].previous_response_had_at_least_one_in_common);
+        Ok(())
+    }
+
+    #[cfg(any(
+        feature = "blocking-http-transport-reqwest",
+        feature = "blocking-http-transport-curl"
+    ))]
+    #[test]
+    fn multi_ack_detailed_with_no_done_over_stateless_connections_receives_the_pack_once_the_server_is_ready(
+    ) -> crate::Result {
+        use std::sync::atomic::Ordering;
+
+        let dir = gix_testtools::scripted_fixture_writable("make_negotiation_repos.sh")?;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}/server", listener.local_addr()?);
+        let options = gix::http_backend::Options {
+            base_path: dir.path().into(),
+            export_all: true,
+        };
+        let mut repo = gix::open_opts(dir.path().join("client"), gix::open::Options::isolated())?;
+        repo.config_snapshot_mut()
+            .set_raw_value(&gix::config::tree::Protocol::VERSION, "1")?;
+        let should_interrupt = AtomicBool::default();
+        let outcome = std::thread::scope(|scope| -> crate::Result<fetch::Outcome> {
+            let server = scope.spawn(|| {
+                gix::http_backend::serve(&listener, &options, &should_interrupt, &|_peer, err| {
+                    panic!("no request fails: {err}")
+                })
+            });
+            let res = (|| -> crate::Result<fetch::Outcome> {
+                Ok(repo
+                    .remote_at(url.as_str())?
+                    .with_refspecs(Some(REFSPEC), Fetch)?
+                    .connect(Fetch)?
+                    .prepare_fetch(gix::progress::Discard, Default::default())?
+                    .receive(gix::progress::Discard, &AtomicBool::default())?)
+            })();
+            should_interrupt.store(true, Ordering::SeqCst);
+            server.join().expect("no panic")?;
+            res
+        })?;
+        let rounds = received_rounds(outcome, dir.path())?;
+        assert_eq!(
+            rounds.len(),
+            2,
+            "with `no-done`, the pack follows `ready` in the same response: {rounds:?}"
+        );
+        Ok(())
+    }
+}
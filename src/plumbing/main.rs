@@ -424,6 +424,7 @@ pub fn main() -> Result<()> {
             ref_name,
             remote,
             shallow,
+            filter,
             directory,
         }) => {
             let opts = core::repository::clone::Options {
@@ -433,6 +434,7 @@ pub fn main() -> Result<()> {
                 no_tags,
                 ref_name,
                 shallow: shallow.into(),
+                filter,
             };
             prepare_and_run(
                 "clone",
@@ -444,6 +446,115 @@ pub fn main() -> Result<()> {
                 move |progress, out, err| core::repository::clone(remote, directory, config, progress, out, err, opts),
             )
         }
+        #[cfg(feature = "gitoxide-core-blocking-server")]
+        Subcommands::UploadPack(crate::plumbing::options::upload_pack::Platform {
+            stateless_rpc,
+            advertise_refs,
+            directory,
+        }) => prepare_and_run(
+            "upload-pack",
+            trace,
+            false,
+            false,
+            false,
+            None,
+            move |_progress, out, _err| {
+                let opts = core::repository::upload_pack::Options {
+                    protocol: core::repository::upload_pack::protocol_from_env(
+                        std::env::var_os("GIT_PROTOCOL").as_deref(),
+                    ),
+                    stateless_rpc,
+                    advertise_refs,
+                };
+                core::repository::upload_pack(
+                    gix::open(directory)?,
+                    std::io::stdin().lock(),
+                    out,
+                    &gix::interrupt::IS_INTERRUPTED,
+                    opts,
+                )
+            },
+        ),
+        #[cfg(feature = "gitoxide-core-blocking-server")]
+        Subcommands::ReceivePack(crate::plumbing::options::receive_pack::Platform {
+            stateless_rpc,
+            advertise_refs,
+            directory,
+        }) => prepare_and_run(
+            "receive-pack",
+            trace,
+            false,
+            false,
+            false,
+            None,
+            move |_progress, out, _err| {
+                let opts = core::repository::receive_pack::Options {
+                    protocol: core::repository::upload_pack::protocol_from_env(
+                        std::env::var_os("GIT_PROTOCOL").as_deref(),
+                    ),
+                    stateless_rpc,
+                    advertise_refs,
+                };
+                core::repository::receive_pack(
+                    gix::open(directory)?,
+                    std::io::stdin().lock(),
+                    out,
+                    &gix::interrupt::IS_INTERRUPTED,
+                    opts,
+                )
+            },
+        ),
+        #[cfg(feature = "gitoxide-core-blocking-server")]
+        Subcommands::Daemon(crate::plumbing::options::daemon::Platform {
+            listen,
+            port,
+            base_path,
+            export_all,
+            enable_receive_pack,
+            directories,
+        }) => prepare_and_run(
+            "daemon",
+            trace,
+            false,
+            false,
+            false,
+            None,
+            move |_progress, _out, err| {
+                core::daemon::daemon(
+                    (listen.as_str(), port),
+                    core::daemon::Options {
+                        base_path,
+                        export_all,
+                        allowed_directories: directories,
+                        enable_receive_pack,
+                    },
+                    err,
+                    &gix::interrupt::IS_INTERRUPTED,
+                )
+            },
+        ),
+        #[cfg(feature = "gitoxide-core-blocking-server")]
+        Subcommands::HttpBackend(crate::plumbing::options::http_backend::Platform {
+            listen,
+            port,
+            export_all,
+            base_path,
+        }) => prepare_and_run(
+            "http-backend",
+            trace,
+            false,
+            false,
+            false,
+            None,
+            move |_progress, _out, err| {
+                core::daemon::http_backend(
+                    (listen.as_str(), port),
+                    gix::http_backend::Options { base_path, export_all },
+                    err,
+                    &gix::interrupt::IS_INTERRUPTED,
+                )
+            },
+        ),
         #[cfg(feature = "gitoxide-core-blocking-client")]
         Subcommands::Fetch(crate::plumbing::options::fetch::Platform {
             dry_run,
@@ -113,6 +113,18 @@ pub enum Subcommands {
     Fetch(fetch::Platform),
     #[cfg(feature = "gitoxide-core-blocking-client")]
     Clone(clone::Platform),
+    /// Serve fetches and clones from the repository at the given directory, just like `git upload-pack`.
+    #[cfg(feature = "gitoxide-core-blocking-server")]
+    UploadPack(upload_pack::Platform),
+    /// Serve pushes into the repository at the given directory, just like `git receive-pack`.
+    #[cfg(feature = "gitoxide-core-blocking-server")]
+    ReceivePack(receive_pack::Platform),
+    /// Serve repositories to clients connecting via the `git://` protocol, just like `git daemon`.
+    #[cfg(feature = "gitoxide-core-blocking-server")]
+    Daemon(daemon::Platform),
+    /// Serve repositories to clients connecting via the smart HTTP protocol, similar to `git http-backend` behind a web server.
+    #[cfg(feature = "gitoxide-core-blocking-server")]
+    HttpBackend(http_backend::Platform),
     /// Interact with the mailmap.
     #[clap(subcommand)]
     Mailmap(mailmap::Subcommands),
@@ -353,6 +365,102 @@ pub mod config {
     }
 }
 
+#[cfg(feature = "gitoxide-core-blocking-server")]
+pub mod upload_pack {
+    use std::path::PathBuf;
+
+    #[derive(Debug, clap::Parser)]
+    pub struct Platform {
+        /// Serve a single request without sending an advertisement first, as needed for stateless transports like HTTP.
+        #[clap(long)]
+        pub stateless_rpc: bool,
+
+        /// Only send the ref advertisement or capabilities and exit, as needed for stateless transports like HTTP.
+        #[clap(long, alias = "http-backend-info-refs")]
+        pub advertise_refs: bool,
+
+        /// The repository to serve, which is expected to be passed by `git` or `ssh`.
+        ///
+        /// The protocol version is read from the `GIT_PROTOCOL` environment variable.
+        pub directory: PathBuf,
+    }
+}
+
+#[cfg(feature = "gitoxide-core-blocking-server")]
+pub mod receive_pack {
+    use std::path::PathBuf;
+
+    #[derive(Debug, clap::Parser)]
+    pub struct Platform {
+        /// Serve a single request without sending an advertisement first, as needed for stateless transports like HTTP.
+        #[clap(long)]
+        pub stateless_rpc: bool,
+
+        /// Only send the ref advertisement and exit, as needed for stateless transports like HTTP.
+        #[clap(long, alias = "http-backend-info-refs")]
+        pub advertise_refs: bool,
+
+        /// The repository to push into, which is expected to be passed by `git` or `ssh`.
+        ///
+        /// The protocol version is read from the `GIT_PROTOCOL` environment variable.
+        pub directory: PathBuf,
+    }
+}
+
+#[cfg(feature = "gitoxide-core-blocking-server")]
+pub mod daemon {
+    use std::path::PathBuf;
+
+    #[derive(Debug, clap::Parser)]
+    pub struct Platform {
+        /// The address to listen on.
+        #[clap(long, default_value = "0.0.0.0")]
+        pub listen: String,
+
+        /// The port to listen on.
+        #[clap(long, default_value_t = 9418)]
+        pub port: u16,
+
+        /// Resolve the paths of all requested repositories relative to this directory.
+        #[clap(long)]
+        pub base_path: Option<PathBuf>,
+
+        /// Serve all repositories, not only those with a `git-daemon-export-ok` file.
+        #[clap(long)]
+        pub export_all: bool,
+
+        /// Allow clients to push into the served repositories.
+        #[clap(long)]
+        pub enable_receive_pack: bool,
+
+        /// If set, only serve repositories in these directories or their subdirectories.
+        pub directories: Vec<PathBuf>,
+    }
+}
+
+#[cfg(feature = "gitoxide-core-blocking-server")]
+pub mod http_backend {
+    use std::path::PathBuf;
+
+    #[derive(Debug, clap::Parser)]
+    pub struct Platform {
+        /// The address to listen on.
+        #[clap(long, default_value = "0.0.0.0")]
+        pub listen: String,
+
+        /// The port to listen on.
+        #[clap(long, default_value_t = 8080)]
+        pub port: u16,
+
+        /// Serve all repositories, not only those with a `git-daemon-export-ok` file.
+        #[clap(long)]
+        pub export_all: bool,
+
+        /// The directory containing all repositories to serve, which the paths of request URLs are relative to.
+        pub base_path: PathBuf,
+    }
+}
+
 #[cfg(feature = "gitoxide-core-blocking-client")]
 pub mod fetch {
     use std::num::NonZeroU32;
@@ -459,6 +567,12 @@ pub mod clone {
         #[clap(flatten)]
         pub shallow: ShallowOptions,
 
+        /// Create a partial clone by omitting objects as described by the filter, like `blob:none`, `blob:limit=1m` or `tree:0`.
+        ///
+        /// Omitted objects are fetched from the remote when they are needed.
+        #[clap(long, value_parser = crate::shared::AsFilter, value_name = "FILTER")]
+        pub filter: Option<gix::remote::fetch::Filter>,
+
         /// The url of the remote to connect to, like `https://github.com/byron/gitoxide`.
         pub remote: OsString,
 
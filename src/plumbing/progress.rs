@@ -406,14 +406,6 @@ static GIT_CONFIG: &[Record] = &[
         config: "sparse.expectFilesOutsideOfPatterns",
         usage: NotPlanned("TODO")
     },
-    Record {
-        config: "remote.<name>.promisor",
-        usage: Planned("Required for big monorepos, and typically used in conjunction with sparse indices")
-    },
-    Record {
-        config: "remote.<name>.partialCloneFilter",
-        usage: Planned("Required for big monorepos, and typically used in conjunction with sparse indices")
-    },
     Record {
         config: "merge.renameLimit",
         usage: Planned("The same as 'diff.renameLimit'")
@@ -396,6 +396,21 @@ mod clap {
         }
     }
 
+    #[derive(Clone)]
+    #[cfg(feature = "gitoxide-core-blocking-client")]
+    pub struct AsFilter;
+
+    #[cfg(feature = "gitoxide-core-blocking-client")]
+    impl TypedValueParser for AsFilter {
+        type Value = gix::remote::fetch::Filter;
+
+        fn parse_ref(&self, cmd: &Command, arg: Option<&Arg>, value: &OsStr) -> Result<Self::Value, Error> {
+            AsBString
+                .try_map(|spec| gix::remote::fetch::Filter::from_bytes(spec.as_ref()))
+                .parse_ref(cmd, arg, value)
+        }
+    }
+
     #[derive(Clone)]
     pub struct AsPartialRefName;
 
@@ -409,6 +424,8 @@ mod clap {
         }
     }
 }
+#[cfg(feature = "gitoxide-core-blocking-client")]
+pub use self::clap::AsFilter;
 pub use self::clap::{
     AsBString, AsHashKind, AsOutputFormat, AsPartialRefName, AsPathSpec, AsTime, CheckPathSpec, ParseRenameFraction,
 };